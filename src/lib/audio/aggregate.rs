@@ -0,0 +1,316 @@
+//! Aggregation of multiple output devices into a single contiguous range of output channels.
+//!
+//! The channels of the primary output device come first, followed by those of each additional
+//! device in the order in which they are named within the config. A single buffer spanning all
+//! channels is rendered on the primary device's stream. The channels belonging to each additional
+//! device are then queued for that device's own stream.
+//!
+//! Each device runs on its own clock, so the rate at which an additional device consumes its queue
+//! drifts slowly from the rate at which it is filled. Each additional stream resamples its queue
+//! by a ratio that is nudged towards keeping the queue at `target_latency_frames`. If the queue
+//! runs dry (e.g. while the primary stream starts) silence is played, and if it grows beyond
+//! `max_latency_frames` the oldest frames are dropped.
+//!
+//! The queues are fixed size rings shared without locking, so that neither stream's callback
+//! waits on the other or allocates.
+
+use audio::frames_per_buffer;
+use nannou_audio;
+use nannou_audio::Buffer;
+use std::sync::atomic::{self, AtomicU32, AtomicUsize};
+use std::sync::Arc;
+
+/// The greatest deviation of the resampling ratio from `1.0`, i.e. 0.2%.
+///
+/// Far beyond the drift of any real clock while remaining inaudible.
+const MAX_RATIO_DEVIATION: f64 = 0.002;
+
+/// The amount by which the ratio moves towards its target per buffer, smoothing out jitter in
+/// the timing of each stream's callback.
+const RATIO_SMOOTHING: f64 = 0.01;
+
+/// The stream of an additional output device.
+pub type Stream = nannou_audio::Stream<Model>;
+
+// The interleaved samples queued for an additional device.
+type Queue = Arc<Ring>;
+
+// A fixed capacity queue of samples with a single producer, the primary stream, and a single
+// consumer, the stream of an additional device.
+struct Ring {
+    // The bits of each `f32` sample.
+    samples: Box<[AtomicU32]>,
+    // The total number of samples pushed and popped, wrapping on overflow.
+    pushed: AtomicUsize,
+    popped: AtomicUsize,
+}
+
+/// The channels of all additional devices, written to on the primary output stream.
+pub struct Aggregate {
+    // The buffer spanning the channels of all devices.
+    buffer: Buffer,
+    // The number of channels belonging to the primary device.
+    primary_channels: usize,
+    // The channels and queue of each additional device.
+    devices: Vec<Device>,
+}
+
+/// The range of the aggregate channels belonging to an additional device.
+pub struct Device {
+    start: usize,
+    channels: usize,
+    queue: Queue,
+}
+
+/// The state stored on the stream of each additional device.
+pub struct Model {
+    queue: Queue,
+    channels: usize,
+    // The ratio of queued frames consumed per output frame.
+    ratio: f64,
+    // The position between the `current` and `next` frames.
+    phase: f64,
+    current: Vec<f32>,
+    next: Vec<f32>,
+}
+
+impl Aggregate {
+    /// Aggregate the channels of the primary device with those of no other devices to begin.
+    pub fn new(primary_channels: usize) -> Self {
+        let buffer = new_buffer(primary_channels, frames_per_buffer());
+        let devices = vec![];
+        Aggregate { buffer, primary_channels, devices }
+    }
+
+    /// The total number of channels across all devices.
+    pub fn channels(&self) -> usize {
+        self.buffer.channels()
+    }
+
+    /// Create an additional device with the given number of channels, along with the model for
+    /// its stream.
+    ///
+    /// The device's channels are only appended once it is passed to `add_device`, allowing it to
+    /// be discarded if its stream fails to build.
+    pub fn new_device(&self, channels: usize) -> (Device, Model) {
+        // Leave room for the primary stream to push a couple of buffers beyond the latency limit
+        // before the device's stream next trims the queue.
+        let capacity = (max_latency_frames() + frames_per_buffer() * 2) * channels;
+        let queue: Queue = Arc::new(Ring::new(capacity));
+        let device = Device { start: 0, channels, queue: queue.clone() };
+        (device, Model::new(queue, channels))
+    }
+
+    /// Append the channels of the given additional device.
+    pub fn add_device(&mut self, mut device: Device) {
+        device.start = self.channels();
+        self.buffer = new_buffer(device.start + device.channels, frames_per_buffer());
+        self.devices.push(device);
+    }
+
+    /// Render all channels via the given function, writing those of the primary device to its
+    /// buffer and queueing the rest for their devices.
+    pub fn render<F>(&mut self, primary: &mut Buffer, render: F)
+    where
+        F: FnOnce(&mut Buffer),
+    {
+        // The primary stream should always request the same number of frames. If it does not,
+        // render its own channels alone rather than allocate, leaving the other devices' queues
+        // to run dry.
+        if self.buffer.len_frames() != primary.len_frames() {
+            render(primary);
+            return;
+        }
+
+        render(&mut self.buffer);
+
+        let channels = self.channels();
+        for (frame, primary_frame) in self.buffer.frames().zip(primary.frames_mut()) {
+            let len = primary_frame.len().min(self.primary_channels);
+            primary_frame[..len].copy_from_slice(&frame[..len]);
+        }
+
+        // Frames that do not fit are dropped, as the device's stream has stalled.
+        for device in &self.devices {
+            let end = device.start + device.channels;
+            for frame in self.buffer.chunks(channels) {
+                if !device.queue.push_frame(&frame[device.start..end]) {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl Model {
+    fn new(queue: Queue, channels: usize) -> Self {
+        Model {
+            queue,
+            channels,
+            ratio: 1.0,
+            phase: 0.0,
+            current: vec![0.0; channels],
+            next: vec![0.0; channels],
+        }
+    }
+
+    // Write the queued frames to the given interleaved samples.
+    fn fill(&mut self, samples: &mut [f32], channels: usize) {
+        let Model {
+            ref queue,
+            channels: device_channels,
+            ref mut ratio,
+            ref mut phase,
+            ref mut current,
+            ref mut next,
+        } = *self;
+        // Drop the oldest frames if the queue has grown beyond the latency limit.
+        let max_samples = max_latency_frames() * device_channels;
+        let queued = queue.len();
+        if queued > max_samples {
+            queue.skip(queued - target_latency_frames() * device_channels);
+        }
+        *ratio = next_ratio(*ratio, queue.len() / device_channels.max(1));
+        for frame in samples.chunks_mut(channels) {
+            // Step forward through the queue by the ratio.
+            *phase += *ratio;
+            while *phase >= 1.0 {
+                *phase -= 1.0;
+                // If the queue has run dry, step towards silence.
+                let is_dry = queue.len() < device_channels;
+                for (c, n) in current.iter_mut().zip(next.iter_mut()) {
+                    *c = *n;
+                    *n = if is_dry { 0.0 } else { queue.pop().unwrap_or(0.0) };
+                }
+            }
+
+            // Linearly interpolate between the surrounding frames.
+            let t = *phase as f32;
+            let interpolated = current.iter().zip(next.iter()).map(|(&c, &n)| c + (n - c) * t);
+            for (sample, value) in frame.iter_mut().zip(interpolated) {
+                *sample = value;
+            }
+        }
+    }
+}
+
+impl Ring {
+    fn new(capacity: usize) -> Self {
+        let samples = (0..capacity.max(1)).map(|_| AtomicU32::new(0)).collect::<Vec<_>>();
+        Ring {
+            samples: samples.into_boxed_slice(),
+            pushed: AtomicUsize::new(0),
+            popped: AtomicUsize::new(0),
+        }
+    }
+
+    // The number of samples currently queued.
+    fn len(&self) -> usize {
+        let pushed = self.pushed.load(atomic::Ordering::Acquire);
+        let popped = self.popped.load(atomic::Ordering::Acquire);
+        pushed.wrapping_sub(popped)
+    }
+
+    // Push all samples of the given frame, or none if there is not enough room.
+    //
+    // Must only be called by the producer.
+    fn push_frame(&self, frame: &[f32]) -> bool {
+        let capacity = self.samples.len();
+        if capacity - self.len() < frame.len() {
+            return false;
+        }
+        let pushed = self.pushed.load(atomic::Ordering::Relaxed);
+        for (i, &sample) in frame.iter().enumerate() {
+            let slot = &self.samples[pushed.wrapping_add(i) % capacity];
+            slot.store(sample.to_bits(), atomic::Ordering::Relaxed);
+        }
+        self.pushed.store(pushed.wrapping_add(frame.len()), atomic::Ordering::Release);
+        true
+    }
+
+    // Pop the oldest sample, if any.
+    //
+    // Must only be called by the consumer.
+    fn pop(&self) -> Option<f32> {
+        if self.len() == 0 {
+            return None;
+        }
+        let popped = self.popped.load(atomic::Ordering::Relaxed);
+        let slot = &self.samples[popped % self.samples.len()];
+        let sample = f32::from_bits(slot.load(atomic::Ordering::Relaxed));
+        self.popped.store(popped.wrapping_add(1), atomic::Ordering::Release);
+        Some(sample)
+    }
+
+    // Drop up to the given number of the oldest samples.
+    //
+    // Must only be called by the consumer.
+    fn skip(&self, samples: usize) {
+        let samples = samples.min(self.len());
+        let popped = self.popped.load(atomic::Ordering::Relaxed);
+        self.popped.store(popped.wrapping_add(samples), atomic::Ordering::Release);
+    }
+}
+
+/// The number of frames that each additional device aims to keep queued.
+pub fn target_latency_frames() -> usize {
+    frames_per_buffer() * 2
+}
+
+/// The number of queued frames beyond which the oldest are dropped.
+pub fn max_latency_frames() -> usize {
+    frames_per_buffer() * 8
+}
+
+/// The render function for the stream of each additional device.
+pub fn render(model: &mut Model, buffer: &mut Buffer) {
+    let channels = buffer.channels();
+    model.fill(&mut buffer[..], channels);
+}
+
+// Nudge the resampling ratio towards keeping the given number of frames queued.
+fn next_ratio(ratio: f64, queued_frames: usize) -> f64 {
+    let error = queued_frames as f64 - target_latency_frames() as f64;
+    // The deviation of the resampling ratio per frame of error in the queue's latency.
+    let ratio_per_frame_error = MAX_RATIO_DEVIATION / frames_per_buffer() as f64;
+    let target = 1.0 + (error * ratio_per_frame_error)
+        .max(-MAX_RATIO_DEVIATION)
+        .min(MAX_RATIO_DEVIATION);
+    ratio + (target - ratio) * RATIO_SMOOTHING
+}
+
+fn new_buffer(channels: usize, frames: usize) -> Buffer {
+    let samples = vec![0.0; channels * frames].into_boxed_slice();
+    Buffer::new(samples, channels, super::sample_rate() as u32)
+}
+
+#[test]
+fn test_ratio() {
+    // A queue that is fuller than the target is consumed faster and vice versa.
+    assert!(next_ratio(1.0, target_latency_frames() * 2) > 1.0);
+    assert_eq!(next_ratio(1.0, target_latency_frames()), 1.0);
+    let ratio = (0..10_000).fold(1.0, |ratio, _| next_ratio(ratio, 0));
+    assert!(ratio < 1.0);
+    assert!(1.0 - ratio <= MAX_RATIO_DEVIATION + std::f64::EPSILON);
+}
+
+
+#[test]
+fn test_ring() {
+    // Whole frames are pushed only while they fit and are popped in order.
+    let ring = Ring::new(5);
+    assert!(ring.push_frame(&[0.0, 1.0]));
+    assert!(ring.push_frame(&[2.0, 3.0]));
+    assert!(!ring.push_frame(&[4.0, 5.0]));
+    assert_eq!(ring.len(), 4);
+    assert_eq!(ring.pop(), Some(0.0));
+    ring.skip(2);
+    assert_eq!(ring.pop(), Some(3.0));
+    assert_eq!(ring.pop(), None);
+
+    // Samples wrap around the end of the ring.
+    assert!(ring.push_frame(&[6.0, 7.0]));
+    assert!(ring.push_frame(&[8.0, 9.0]));
+    let popped: Vec<_> = (0..4).filter_map(|_| ring.pop()).collect();
+    assert_eq!(popped, vec![6.0, 7.0, 8.0, 9.0]);
+}
@@ -0,0 +1,378 @@
+//! An ambisonics bus offering an alternative to DBAP for rendering sounds to the speakers.
+//!
+//! While enabled, each channel of each sound is encoded into higher-order ambisonics (ACN channel
+//! ordering with SN3D normalisation) according to its direction from the centre of the speaker
+//! layout. The bus is then decoded to all full-range speakers via a max-rE weighted projection
+//! decoder. As the encoded signal does not depend on the speaker layout, the same project may be
+//! reproduced within venues with very different speaker counts without re-tuning DBAP. The
+//! encoded B-format signal may also be recorded to disk for decoding elsewhere.
+//!
+//! The floorplan's x axis is treated as the front, its y axis as the left and height as up.
+//! Sounds are blurred towards the omnidirectional component as they approach the centre of the
+//! layout. Installation assignments and the proximity limit only apply to DBAP.
+
+use audio::{capture, frames_per_buffer, sound, MAX_CHANNELS};
+use fxhash::FxHashMap;
+use nannou::geom::Point3;
+use nannou_audio::Buffer;
+
+/// The lowest ambisonics order that may be selected.
+pub const MIN_ORDER: u8 = 1;
+/// The highest ambisonics order that may be selected.
+pub const MAX_ORDER: u8 = 3;
+/// The number of components within a signal of the highest order.
+pub const MAX_COMPONENTS: usize = (MAX_ORDER as usize + 1) * (MAX_ORDER as usize + 1);
+
+/// The gain of each ambisonics component in ACN order.
+pub type Coefficients = [f32; MAX_COMPONENTS];
+
+/// The method used to render sounds to the speakers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Mode {
+    /// Distance-based amplitude panning between the nearest speakers.
+    Dbap,
+    /// Encode all sounds onto the ambisonics bus and decode it to the speakers.
+    Ambisonics,
+}
+
+/// The user-facing parameters of the ambisonics bus.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Params {
+    /// The method used to render sounds to the speakers.
+    #[serde(default = "default::mode")]
+    pub mode: Mode,
+    /// The order of the encoded signal, between `MIN_ORDER` and `MAX_ORDER`.
+    #[serde(default = "default::order")]
+    pub order: u8,
+}
+
+/// The state of the ambisonics bus on the audio output thread.
+pub struct Bus {
+    params: Params,
+    // The output channel and position of each full-range speaker for the current buffer.
+    speakers: Vec<(usize, Point3<f64>)>,
+    // The output channel and decoding coefficients of each full-range speaker.
+    decoder: Vec<(usize, Coefficients)>,
+    // The centre of the speaker layout from which directions are measured.
+    centre: Point3<f64>,
+    // The mean distance of the speakers from the centre.
+    radius: f64,
+    // The encoded signal for the current buffer, one set of components per frame.
+    bformat: Vec<Coefficients>,
+    // The encoding coefficients of each sound channel as of the end of the previous buffer.
+    coefficients: FxHashMap<(sound::Id, usize), Coefficients>,
+    // The buffers of the B-format recording in progress and its number of components, if any.
+    recording: Option<(capture::Buffers, usize)>,
+}
+
+impl Mode {
+    /// All modes in the order in which they are displayed.
+    pub const ALL: [Mode; 2] = [Mode::Dbap, Mode::Ambisonics];
+
+    /// The name of the mode as displayed within the GUI.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Mode::Dbap => "DBAP",
+            Mode::Ambisonics => "Ambisonics",
+        }
+    }
+}
+
+impl Params {
+    /// The parameters with the order clamped to the supported range.
+    pub fn clamped(mut self) -> Self {
+        self.order = self.order.max(MIN_ORDER).min(MAX_ORDER);
+        self
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            mode: default::mode(),
+            order: default::order(),
+        }
+    }
+}
+
+impl Bus {
+    /// Create a new ambisonics bus with the given parameters.
+    pub fn new(params: Params) -> Self {
+        Bus {
+            params: params.clamped(),
+            speakers: Vec::with_capacity(MAX_CHANNELS),
+            decoder: Vec::with_capacity(MAX_CHANNELS),
+            centre: Point3 { x: 0.0, y: 0.0, z: 0.0 },
+            radius: 0.0,
+            bformat: Vec::with_capacity(frames_per_buffer() * 2),
+            coefficients: Default::default(),
+            recording: None,
+        }
+    }
+
+    /// The current parameters of the bus.
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Update the parameters of the bus.
+    pub fn set_params(&mut self, params: Params) {
+        self.params = params.clamped();
+    }
+
+    /// Whether or not sounds should be rendered via the bus rather than DBAP.
+    pub fn is_active(&self) -> bool {
+        self.params.mode == Mode::Ambisonics
+    }
+
+    /// Begin writing the encoded signal to the given buffers each buffer.
+    ///
+    /// The buffers are created via `capture::spawn_wav_writer` by the caller so that no file IO
+    /// occurs on the audio thread.
+    pub fn start_recording(&mut self, buffers: capture::Buffers, components: usize) {
+        self.recording = Some((buffers, components.min(MAX_COMPONENTS)));
+    }
+
+    /// Stop writing the encoded signal.
+    pub fn stop_recording(&mut self) {
+        self.recording = None;
+    }
+
+    /// Forget the encoding coefficients of the sound with the given `Id`.
+    pub fn remove_sound(&mut self, id: &sound::Id) {
+        self.coefficients.retain(|&(sound_id, _), _| sound_id != *id);
+    }
+
+    /// Forget the encoding coefficients of all sounds.
+    pub fn clear_sounds(&mut self) {
+        self.coefficients.clear();
+    }
+
+    /// Clear the bus and the speaker layout ready for the next buffer.
+    pub fn begin_buffer(&mut self, len_frames: usize) {
+        self.speakers.clear();
+        self.bformat.clear();
+        self.bformat.resize(len_frames, [0.0; MAX_COMPONENTS]);
+    }
+
+    /// Add a full-range speaker to which the bus is decoded.
+    pub fn add_speaker(&mut self, channel: usize, point: Point3<f64>) {
+        self.speakers.push((channel, point));
+    }
+
+    /// Update the centre of the layout and the decoder following the addition of all speakers.
+    pub fn update_decoder(&mut self) {
+        self.decoder.clear();
+        if self.speakers.is_empty() {
+            return;
+        }
+
+        let n = self.speakers.len() as f64;
+        let (sum_x, sum_y, sum_z) = self.speakers
+            .iter()
+            .fold((0.0, 0.0, 0.0), |(x, y, z), &(_, p)| (x + p.x, y + p.y, z + p.z));
+        self.centre = Point3 { x: sum_x / n, y: sum_y / n, z: sum_z / n };
+        let centre = self.centre;
+        self.radius = self.speakers
+            .iter()
+            .map(|&(_, p)| distance(centre, p))
+            .sum::<f64>() / n;
+
+        // Normalise so that the power of a sound is roughly preserved on a uniform layout.
+        let order = self.params.order as usize;
+        let weights = max_re_weights(order);
+        let power: f32 = (0..order + 1)
+            .map(|degree| (2 * degree + 1) as f32 * weights[degree] * weights[degree])
+            .sum();
+        let norm = 1.0 / (n as f32 * power).sqrt();
+        for &(channel, point) in self.speakers.iter() {
+            let mut row = encode_point(centre, self.radius, point, order);
+            for (component, coefficient) in row.iter_mut().enumerate() {
+                let degree = degree(component);
+                *coefficient *= (2 * degree + 1) as f32 * weights[degree] * norm;
+            }
+            self.decoder.push((channel, row));
+        }
+    }
+
+    /// Encode a single channel of a sound positioned at `point` onto the bus.
+    ///
+    /// The channel is read from the sound's interleaved `samples`. The coefficients are
+    /// interpolated from those of the previous buffer to avoid discontinuities as sounds move.
+    pub fn encode_channel(
+        &mut self,
+        sound_id: sound::Id,
+        channel: usize,
+        point: Point3<f64>,
+        samples: &[f32],
+        n_channels: usize,
+    ) {
+        let order = self.params.order as usize;
+        let components = num_components(order);
+        let current = encode_point(self.centre, self.radius, point, order);
+        let previous = self.coefficients
+            .insert((sound_id, channel), current)
+            .unwrap_or(current);
+        let len_frames = self.bformat.len() as f32;
+        let frames = self.bformat.iter_mut().zip(samples.chunks(n_channels));
+        for (frame_i, (frame, samples)) in frames.enumerate() {
+            let lerp_amt = frame_i as f32 / len_frames;
+            let sample = samples[channel];
+            for i in 0..components {
+                let coefficient = previous[i] + (current[i] - previous[i]) * lerp_amt;
+                frame[i] += sample * coefficient;
+            }
+        }
+    }
+
+    /// Decode the bus onto the speaker channels of the output buffer.
+    ///
+    /// The encoded signal is also written to the B-format recording in progress, if any.
+    pub fn decode(&mut self, buffer: &mut Buffer) {
+        let components = num_components(self.params.order as usize);
+        for (frame, bformat) in buffer.frames_mut().zip(self.bformat.iter()) {
+            for &(channel, ref row) in self.decoder.iter() {
+                let sample = (0..components).fold(0.0, |sum, i| sum + row[i] * bformat[i]);
+                frame[channel] += sample;
+            }
+        }
+
+        if let Some((ref buffers, components)) = self.recording {
+            let mut samples = buffers.pop_empty(self.bformat.len() * components);
+            for frame in self.bformat.iter() {
+                samples.extend(frame[..components].iter().cloned());
+            }
+            buffers.filled.push(samples);
+        }
+    }
+}
+
+/// The number of components within an ambisonics signal of the given order.
+pub fn num_components(order: usize) -> usize {
+    (order + 1) * (order + 1)
+}
+
+/// The SN3D spherical harmonic coefficients in ACN order for the given unit direction.
+///
+/// Components above the given order are zeroed.
+pub fn encode(x: f64, y: f64, z: f64, order: usize) -> Coefficients {
+    let mut c = [0.0; MAX_COMPONENTS];
+    let (sqrt_3, sqrt_15) = (3.0f64.sqrt(), 15.0f64.sqrt());
+    let (sqrt_3_8, sqrt_5_8) = ((3.0f64 / 8.0).sqrt(), (5.0f64 / 8.0).sqrt());
+    let values = [
+        1.0,
+        y,
+        z,
+        x,
+        sqrt_3 * x * y,
+        sqrt_3 * y * z,
+        (3.0 * z * z - 1.0) / 2.0,
+        sqrt_3 * x * z,
+        sqrt_3 / 2.0 * (x * x - y * y),
+        sqrt_5_8 * y * (3.0 * x * x - y * y),
+        sqrt_15 * x * y * z,
+        sqrt_3_8 * y * (5.0 * z * z - 1.0),
+        z * (5.0 * z * z - 3.0) / 2.0,
+        sqrt_3_8 * x * (5.0 * z * z - 1.0),
+        sqrt_15 / 2.0 * z * (x * x - y * y),
+        sqrt_5_8 * x * (x * x - 3.0 * y * y),
+    ];
+    let components = num_components(order.min(MAX_ORDER as usize));
+    for (c, &v) in c.iter_mut().zip(values.iter()).take(components) {
+        *c = v as f32;
+    }
+    c
+}
+
+// Encode the direction of `point` from `centre`.
+//
+// The directional components are scaled down as the point approaches the centre so that a sound
+// at the centre of the layout is reproduced by all speakers equally.
+fn encode_point(
+    centre: Point3<f64>,
+    radius: f64,
+    point: Point3<f64>,
+    order: usize,
+) -> Coefficients {
+    let (x, y, z) = (point.x - centre.x, point.y - centre.y, point.z - centre.z);
+    let distance = distance(centre, point);
+    if distance <= 0.0 {
+        return encode(1.0, 0.0, 0.0, 0);
+    }
+    let spread = match radius > 0.0 {
+        true => (distance / radius).min(1.0) as f32,
+        false => 1.0,
+    };
+    let mut coefficients = encode(x / distance, y / distance, z / distance, order);
+    for coefficient in coefficients.iter_mut().skip(1) {
+        *coefficient *= spread;
+    }
+    coefficients
+}
+
+fn distance(a: Point3<f64>, b: Point3<f64>) -> f64 {
+    let (x, y, z) = (b.x - a.x, b.y - a.y, b.z - a.z);
+    (x * x + y * y + z * z).sqrt()
+}
+
+// The degree of the component at the given ACN index.
+fn degree(component: usize) -> usize {
+    (component as f64).sqrt() as usize
+}
+
+// The max-rE weight of each degree up to the given order, improving localisation of the decoder.
+fn max_re_weights(order: usize) -> [f32; MAX_ORDER as usize + 1] {
+    let mut weights = [0.0; MAX_ORDER as usize + 1];
+    let x = (137.9 / (order as f64 + 1.51)).to_radians().cos();
+    for (degree, weight) in weights.iter_mut().enumerate().take(order + 1) {
+        *weight = legendre(degree, x) as f32;
+    }
+    weights
+}
+
+// The legendre polynomial of the given degree evaluated at `x`.
+fn legendre(degree: usize, x: f64) -> f64 {
+    let (mut prev, mut current) = (1.0, x);
+    if degree == 0 {
+        return prev;
+    }
+    for n in 1..degree {
+        let next = ((2 * n + 1) as f64 * x * current - n as f64 * prev) / (n + 1) as f64;
+        prev = current;
+        current = next;
+    }
+    current
+}
+
+pub mod default {
+    use super::Mode;
+
+    pub fn mode() -> Mode {
+        Mode::Dbap
+    }
+
+    pub fn order() -> u8 {
+        3
+    }
+}
+
+#[test]
+fn test_encode_addition_theorem() {
+    // With SN3D normalisation the components of each degree sum to the legendre polynomial of the
+    // cosine of the angle between the two directions.
+    let normalise = |(x, y, z): (f64, f64, f64)| {
+        let mag = (x * x + y * y + z * z).sqrt();
+        (x / mag, y / mag, z / mag)
+    };
+    let a = normalise((0.3, -0.8, 0.5));
+    let b = normalise((-0.6, 0.2, 0.9));
+    let cos = a.0 * b.0 + a.1 * b.1 + a.2 * b.2;
+    let ya = encode(a.0, a.1, a.2, MAX_ORDER as usize);
+    let yb = encode(b.0, b.1, b.2, MAX_ORDER as usize);
+    for degree in 0..MAX_ORDER as usize + 1 {
+        let sum: f32 = (degree * degree..num_components(degree)).map(|i| ya[i] * yb[i]).sum();
+        assert!((sum as f64 - legendre(degree, cos)).abs() < 1e-5);
+    }
+    // Components above the given order are zeroed.
+    assert!(encode(a.0, a.1, a.2, 1)[4..].iter().all(|&c| c == 0.0));
+}
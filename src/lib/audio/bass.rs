@@ -0,0 +1,39 @@
+//! Bass management for routing low frequencies to subwoofers.
+//!
+//! When at least one speaker is marked as a subwoofer, each sound is summed to mono and sent to
+//! the subwoofers while the full-range speakers continue to receive the DBAP mix. All other
+//! output channels are then high-passed and the subwoofer channels low-passed at the crossover
+//! frequency. This keeps low end that would otherwise be spread across small satellites.
+
+/// The lowest crossover frequency that may be set.
+pub const MIN_CROSSOVER_HZ: f32 = 40.0;
+/// The highest crossover frequency that may be set.
+pub const MAX_CROSSOVER_HZ: f32 = 250.0;
+
+/// The user-facing parameters of bass management.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Params {
+    /// The frequency at which the signal is split between subwoofers and full-range speakers.
+    #[serde(default = "default::crossover_hz")]
+    pub crossover_hz: f32,
+    /// Whether the low end of each sound is panned among the subwoofers via DBAP.
+    ///
+    /// When `false`, the low end of each sound is sent equally to all subwoofers.
+    #[serde(default)]
+    pub sub_dbap: bool,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            crossover_hz: default::crossover_hz(),
+            sub_dbap: false,
+        }
+    }
+}
+
+pub mod default {
+    pub fn crossover_hz() -> f32 {
+        80.0
+    }
+}
@@ -0,0 +1,319 @@
+//! A binaural bus for auditioning the spatial scene on headphones.
+//!
+//! Each channel of each sound is rendered relative to a virtual listener on the floorplan via a
+//! spherical head model of the HRTF (Brown & Duda, 1998): an interaural time difference and a
+//! head-shadow filter per ear along with inverse distance attenuation. The stereo result is
+//! written to a chosen pair of output channels, replacing whatever the speakers would otherwise
+//! play there. As the model has no pinna cues, sounds in front of and behind the listener are not
+//! distinguished.
+
+use audio::{frames_per_buffer, sample_rate, sound};
+use fxhash::FxHashMap;
+use metres::Metres;
+use nannou::geom::{Point2, Point3};
+use nannou_audio::Buffer;
+use std::f64::consts::PI;
+
+/// The radius of the modelled head.
+pub const HEAD_RADIUS: f64 = 0.0875;
+/// The speed of sound in metres per second.
+const SPEED_OF_SOUND: f64 = 343.0;
+/// The distance within which sounds are no longer attenuated.
+const MIN_DISTANCE: f64 = 1.0;
+/// The length of the delay line for each sound channel, enough for the greatest interaural delay
+/// (roughly 63 frames) at the highest of the supported `SAMPLE_RATES`.
+const DELAY_LINE_LEN: usize = 128;
+/// The head-shadow filter gain at high frequencies for a sound directly opposite an ear.
+const MIN_SHADOW_ALPHA: f64 = 0.1;
+/// The angle from an ear at which the head shadow is greatest.
+const MAX_SHADOW_RADIANS: f64 = PI * 5.0 / 6.0;
+
+/// The user-facing parameters of the binaural bus.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Params {
+    /// Whether or not the binaural bus is rendered.
+    #[serde(default)]
+    pub enabled: bool,
+    /// The position of the virtual listener on the floorplan.
+    #[serde(default = "default::listener")]
+    pub listener: Point2<Metres>,
+    /// The direction faced by the listener, anti-clockwise from the floorplan's x axis.
+    #[serde(default)]
+    pub facing_radians: f64,
+    /// The output channel for the left ear. The right ear uses the following channel.
+    #[serde(default)]
+    pub output_channel: usize,
+    /// The linear gain applied to the bus.
+    #[serde(default = "default::gain")]
+    pub gain: f32,
+}
+
+/// The state of the binaural bus on the audio output thread.
+pub struct Bus {
+    params: Params,
+    // The left and right samples for the current buffer.
+    frames: Vec<[f32; 2]>,
+    // The state of the ears for each channel of each sound.
+    sound_channels: FxHashMap<(sound::Id, usize), SoundChannel>,
+}
+
+// The state of both ears for a single channel of a sound.
+#[derive(Copy, Clone)]
+struct SoundChannel {
+    // The most recent input samples, shared by both ears.
+    delay_line: [f32; DELAY_LINE_LEN],
+    // The index at which the next input sample is written.
+    write_index: usize,
+    ears: [Ear; 2],
+}
+
+// The HRTF of a single ear for a single sound channel.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Ear {
+    // The interaural delay in frames.
+    delay_frames: f32,
+    // The distance attenuation.
+    gain: f32,
+    // The head-shadow filter coefficients and state.
+    b0: f32,
+    b1: f32,
+    a1: f32,
+    x1: f32,
+    y1: f32,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            enabled: false,
+            listener: default::listener(),
+            facing_radians: 0.0,
+            output_channel: 0,
+            gain: default::gain(),
+        }
+    }
+}
+
+impl Bus {
+    /// Create a new binaural bus with the given parameters.
+    pub fn new(params: Params) -> Self {
+        Bus {
+            params,
+            frames: Vec::with_capacity(frames_per_buffer() * 2),
+            sound_channels: Default::default(),
+        }
+    }
+
+    /// The current parameters of the bus.
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Update the parameters of the bus.
+    pub fn set_params(&mut self, params: Params) {
+        self.params = params;
+    }
+
+    /// Whether or not the bus should be rendered.
+    pub fn is_enabled(&self) -> bool {
+        self.params.enabled
+    }
+
+    /// Forget the state of the sound with the given `Id`.
+    pub fn remove_sound(&mut self, id: &sound::Id) {
+        self.sound_channels.retain(|&(sound_id, _), _| sound_id != *id);
+    }
+
+    /// Forget the state of all sounds.
+    pub fn clear_sounds(&mut self) {
+        self.sound_channels.clear();
+    }
+
+    /// Silence the bus ready for the next buffer.
+    pub fn begin_buffer(&mut self, len_frames: usize) {
+        self.frames.clear();
+        self.frames.resize(len_frames, [0.0; 2]);
+    }
+
+    /// Render a single channel of a sound positioned at `point` onto the bus.
+    ///
+    /// The channel is read from the sound's interleaved `samples`. The delay and gain of each ear
+    /// are interpolated from those of the previous buffer to avoid discontinuities.
+    pub fn render_channel(
+        &mut self,
+        sound_id: sound::Id,
+        channel: usize,
+        point: Point3<f64>,
+        samples: &[f32],
+        n_channels: usize,
+    ) {
+        let targets = ears(&self.params, point);
+        let state = self.sound_channels
+            .entry((sound_id, channel))
+            .or_insert_with(|| SoundChannel::new(targets));
+        let previous = state.ears;
+        for (ear, target) in state.ears.iter_mut().zip(targets.iter()) {
+            ear.b0 = target.b0;
+            ear.b1 = target.b1;
+            ear.a1 = target.a1;
+        }
+
+        let len_frames = self.frames.len() as f32;
+        let frames = self.frames.iter_mut().zip(samples.chunks(n_channels));
+        for (frame_i, (frame, samples)) in frames.enumerate() {
+            let lerp_amt = frame_i as f32 / len_frames;
+            state.delay_line[state.write_index] = samples[channel];
+            for (i, ear) in state.ears.iter_mut().enumerate() {
+                let (from, to) = (&previous[i], &targets[i]);
+                let delay = from.delay_frames + (to.delay_frames - from.delay_frames) * lerp_amt;
+                let gain = from.gain + (to.gain - from.gain) * lerp_amt;
+                let x = read_delayed(&state.delay_line, state.write_index, delay);
+                let y = ear.b0 * x + ear.b1 * ear.x1 - ear.a1 * ear.y1;
+                ear.x1 = x;
+                ear.y1 = y;
+                frame[i] += y * gain;
+            }
+            state.write_index = (state.write_index + 1) % DELAY_LINE_LEN;
+        }
+        for (ear, target) in state.ears.iter_mut().zip(targets.iter()) {
+            ear.delay_frames = target.delay_frames;
+            ear.gain = target.gain;
+        }
+    }
+
+    /// Write the bus to its pair of output channels, replacing their contents.
+    ///
+    /// The bus is only written if both channels exist within the buffer.
+    pub fn write(&self, buffer: &mut Buffer, master_volume: f32) {
+        let left = self.params.output_channel;
+        let right = left + 1;
+        if right >= buffer.channels() {
+            return;
+        }
+        let gain = self.params.gain * master_volume;
+        for (frame, stereo) in buffer.frames_mut().zip(self.frames.iter()) {
+            frame[left] = stereo[0] * gain;
+            frame[right] = stereo[1] * gain;
+        }
+    }
+}
+
+impl SoundChannel {
+    fn new(ears: [Ear; 2]) -> Self {
+        SoundChannel {
+            delay_line: [0.0; DELAY_LINE_LEN],
+            write_index: 0,
+            ears,
+        }
+    }
+}
+
+// The HRTF of the left and right ears for a sound at the given point.
+fn ears(params: &Params, point: Point3<f64>) -> [Ear; 2] {
+    let (x, y, z) = (
+        point.x - params.listener.x.0,
+        point.y - params.listener.y.0,
+        point.z,
+    );
+    let distance = (x * x + y * y + z * z).sqrt();
+    let gain = (1.0 / distance.max(MIN_DISTANCE)) as f32;
+    let left = params.facing_radians + PI / 2.0;
+    let right = params.facing_radians - PI / 2.0;
+    let mut ears = [Ear::new(), Ear::new()];
+    for (ear, &ear_radians) in ears.iter_mut().zip([left, right].iter()) {
+        // A sound at the centre of the head reaches both ears equally.
+        let cos = match distance > 0.0 {
+            true => (x * ear_radians.cos() + y * ear_radians.sin()) / distance,
+            false => 0.0,
+        };
+        let radians = cos.max(-1.0).min(1.0).acos();
+        ear.delay_frames = interaural_delay_frames(radians, sample_rate());
+        ear.gain = gain;
+        let (b0, b1, a1) = head_shadow_coefficients(radians);
+        ear.b0 = b0;
+        ear.b1 = b1;
+        ear.a1 = a1;
+    }
+    ears
+}
+
+impl Ear {
+    fn new() -> Self {
+        Ear { delay_frames: 0.0, gain: 0.0, b0: 1.0, b1: 0.0, a1: 0.0, x1: 0.0, y1: 0.0 }
+    }
+}
+
+// The delay in frames for a sound at the given angle from an ear.
+//
+// Sounds on the near side of the head travel a straight line while those on the far side wrap
+// around the head.
+fn interaural_delay_frames(radians: f64, sample_hz: f64) -> f32 {
+    let secs = match radians < PI / 2.0 {
+        true => HEAD_RADIUS / SPEED_OF_SOUND * (1.0 - radians.cos()),
+        false => HEAD_RADIUS / SPEED_OF_SOUND * (1.0 + radians - PI / 2.0),
+    };
+    (secs * sample_hz) as f32
+}
+
+// The one-pole, one-zero head-shadow filter for a sound at the given angle from an ear.
+//
+// Returns the `(b0, b1, a1)` coefficients of the bilinear transform. The filter has unity gain at
+// DC and a high frequency gain of `alpha`, boosting sounds facing the ear and shadowing those
+// opposite.
+fn head_shadow_coefficients(radians: f64) -> (f32, f32, f32) {
+    let alpha = (1.0 + MIN_SHADOW_ALPHA / 2.0)
+        + (1.0 - MIN_SHADOW_ALPHA / 2.0) * (radians / MAX_SHADOW_RADIANS * PI).cos();
+    let w0 = SPEED_OF_SOUND / HEAD_RADIUS;
+    let t = sample_rate() / w0;
+    let b0 = (1.0 + alpha * t) / (1.0 + t);
+    let b1 = (1.0 - alpha * t) / (1.0 + t);
+    let a1 = (1.0 - t) / (1.0 + t);
+    (b0 as f32, b1 as f32, a1 as f32)
+}
+
+// Read the sample `delay` frames before the most recently written sample via linear
+// interpolation.
+fn read_delayed(delay_line: &[f32; DELAY_LINE_LEN], write_index: usize, delay: f32) -> f32 {
+    let delay = delay.max(0.0).min((DELAY_LINE_LEN - 2) as f32);
+    let whole = delay as usize;
+    let fract = delay - whole as f32;
+    let a = delay_line[(write_index + DELAY_LINE_LEN - whole) % DELAY_LINE_LEN];
+    let b = delay_line[(write_index + DELAY_LINE_LEN - whole - 1) % DELAY_LINE_LEN];
+    a + (b - a) * fract
+}
+
+pub mod default {
+    use metres::Metres;
+    use nannou::geom::Point2;
+
+    pub fn listener() -> Point2<Metres> {
+        Point2 { x: Metres(0.0), y: Metres(0.0) }
+    }
+
+    pub fn gain() -> f32 {
+        1.0
+    }
+}
+
+#[test]
+fn test_head_shadow() {
+    // The filter has unity gain at DC for all angles.
+    for &radians in &[0.0, PI / 2.0, PI] {
+        let (b0, b1, a1) = head_shadow_coefficients(radians);
+        assert!(((b0 + b1) / (1.0 + a1) - 1.0).abs() < 1e-5);
+    }
+    // High frequencies are boosted facing the ear and shadowed opposite it.
+    let nyquist_gain = |radians| {
+        let (b0, b1, a1) = head_shadow_coefficients(radians);
+        (b0 - b1) / (1.0 - a1)
+    };
+    assert!(nyquist_gain(0.0) > 1.0);
+    assert!(nyquist_gain(PI) < 1.0);
+    // Sounds opposite an ear arrive later than those facing it, within the length of the delay
+    // line at every supported sample rate.
+    for &hz in super::SAMPLE_RATES {
+        let hz = hz as f64;
+        assert!(interaural_delay_frames(PI, hz) > interaural_delay_frames(0.0, hz));
+        assert!((interaural_delay_frames(PI, hz) as usize) < DELAY_LINE_LEN - 2);
+    }
+}
@@ -0,0 +1,259 @@
+//! A master "blackout" that fades the whole exhibition to silence, holds and then restores.
+//!
+//! Intended for fire alarm tests and daily announcements. The blackout is applied to the final
+//! output after the limiter, so the master volume and all other settings are left untouched and
+//! the exhibition returns exactly as it was once restored.
+
+use audio::sample_rate;
+use nannou_audio::Buffer;
+use std::fmt;
+use std::str::FromStr;
+
+/// The maximum duration of the fade out and fade in.
+pub const MAX_FADE_SECS: f64 = 60.0;
+
+/// The maximum duration for which silence is held.
+pub const MAX_HOLD_SECS: f64 = 60.0 * 60.0;
+
+/// The user-facing parameters of the blackout.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Params {
+    /// The duration over which the exhibition is faded to silence.
+    #[serde(default = "default::fade_out_secs")]
+    pub fade_out_secs: f64,
+    /// The duration for which silence is held before restoring.
+    #[serde(default = "default::hold_secs")]
+    pub hold_secs: f64,
+    /// The duration over which the exhibition is restored.
+    #[serde(default = "default::fade_in_secs")]
+    pub fade_in_secs: f64,
+    /// The times of day at the venue at which a blackout begins automatically.
+    #[serde(default)]
+    pub schedule: Vec<TimeOfDay>,
+}
+
+/// A time of day with minute precision.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+}
+
+/// The blackout gain applied on the audio output thread.
+#[derive(Clone, Debug)]
+pub struct Envelope {
+    stage: Stage,
+    gain: f32,
+}
+
+// The current stage of a blackout.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Stage {
+    Idle,
+    FadeOut { step: f32, hold_frames: u64, fade_in_step: f32 },
+    Hold { frames_remaining: u64, fade_in_step: f32 },
+    FadeIn { step: f32 },
+}
+
+impl Params {
+    /// The total duration of a blackout from the beginning of the fade out to full restoration.
+    pub fn total_secs(&self) -> f64 {
+        self.fade_out_secs + self.hold_secs + self.fade_in_secs
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            fade_out_secs: default::fade_out_secs(),
+            hold_secs: default::hold_secs(),
+            fade_in_secs: default::fade_in_secs(),
+            schedule: Vec::new(),
+        }
+    }
+}
+
+impl TimeOfDay {
+    /// The number of seconds since midnight.
+    pub fn secs_of_day(&self) -> u32 {
+        self.hour as u32 * 60 * 60 + self.minute as u32 * 60
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid time of day `{}`, expected `HH:MM`", s);
+        let mut parts = s.trim().splitn(2, ':');
+        let hour: u8 = parts.next().and_then(|h| h.parse().ok()).ok_or_else(err)?;
+        let minute: u8 = parts.next().and_then(|m| m.parse().ok()).ok_or_else(err)?;
+        if hour > 23 || minute > 59 {
+            return Err(err());
+        }
+        Ok(TimeOfDay { hour, minute })
+    }
+}
+
+impl Envelope {
+    /// An idle envelope, passing the output through untouched.
+    pub fn new() -> Self {
+        Envelope { stage: Stage::Idle, gain: 1.0 }
+    }
+
+    /// Whether or not a blackout is currently underway.
+    pub fn is_active(&self) -> bool {
+        self.stage != Stage::Idle
+    }
+
+    /// Begin a blackout with the given params.
+    ///
+    /// If a blackout is already underway, the fade out continues from the current gain.
+    pub fn start(&mut self, params: &Params) {
+        self.stage = Stage::FadeOut {
+            step: step_per_frame(params.fade_out_secs),
+            hold_frames: (params.hold_secs.max(0.0) * sample_rate()) as u64,
+            fade_in_step: step_per_frame(params.fade_in_secs),
+        };
+    }
+
+    /// Restore the output over the given duration, skipping any remaining hold.
+    pub fn restore(&mut self, fade_in_secs: f64) {
+        if self.is_active() {
+            self.stage = Stage::FadeIn { step: step_per_frame(fade_in_secs) };
+        }
+    }
+
+    /// Apply the blackout gain to the given buffer, stepping the envelope forward.
+    pub fn process(&mut self, buffer: &mut Buffer) {
+        if !self.is_active() {
+            return;
+        }
+        for frame in buffer.frames_mut() {
+            self.step();
+            for sample in frame.iter_mut() {
+                *sample *= self.gain;
+            }
+        }
+    }
+
+    // Step the envelope forward by a single frame.
+    fn step(&mut self) {
+        match self.stage {
+            Stage::Idle => (),
+            Stage::FadeOut { step, hold_frames, fade_in_step } => {
+                self.gain -= step;
+                if self.gain <= 0.0 {
+                    self.gain = 0.0;
+                    self.stage = Stage::Hold { frames_remaining: hold_frames, fade_in_step };
+                }
+            },
+            Stage::Hold { frames_remaining, fade_in_step } => {
+                self.stage = match frames_remaining {
+                    0 => Stage::FadeIn { step: fade_in_step },
+                    n => Stage::Hold { frames_remaining: n - 1, fade_in_step },
+                };
+            },
+            Stage::FadeIn { step } => {
+                self.gain += step;
+                if self.gain >= 1.0 {
+                    self.gain = 1.0;
+                    self.stage = Stage::Idle;
+                }
+            },
+        }
+    }
+}
+
+/// Parse a schedule of comma or whitespace separated `HH:MM` times.
+pub fn parse_schedule(s: &str) -> Result<Vec<TimeOfDay>, String> {
+    let mut schedule = s
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<TimeOfDay>, _>>()?;
+    schedule.sort();
+    schedule.dedup();
+    Ok(schedule)
+}
+
+/// Format a schedule as comma separated `HH:MM` times.
+pub fn schedule_string(schedule: &[TimeOfDay]) -> String {
+    schedule.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// Whether any of the scheduled times falls after `previous_secs` and up to and including
+/// `now_secs`, both in seconds since midnight.
+///
+/// Handles the interval wrapping around midnight.
+pub fn is_due(schedule: &[TimeOfDay], previous_secs: u32, now_secs: u32) -> bool {
+    schedule.iter().map(TimeOfDay::secs_of_day).any(|t| {
+        if previous_secs <= now_secs {
+            previous_secs < t && t <= now_secs
+        } else {
+            previous_secs < t || t <= now_secs
+        }
+    })
+}
+
+// The change in gain per frame for a fade of the given duration.
+fn step_per_frame(secs: f64) -> f32 {
+    let frames = secs * sample_rate();
+    if frames < 1.0 {
+        1.0
+    } else {
+        (1.0 / frames) as f32
+    }
+}
+
+pub mod default {
+    pub fn fade_out_secs() -> f64 {
+        5.0
+    }
+
+    pub fn hold_secs() -> f64 {
+        60.0
+    }
+
+    pub fn fade_in_secs() -> f64 {
+        10.0
+    }
+}
+
+#[test]
+fn test_envelope() {
+    let params = Params {
+        fade_out_secs: 0.0,
+        hold_secs: 0.0,
+        fade_in_secs: 0.0,
+        schedule: vec![],
+    };
+    let mut envelope = Envelope::new();
+    envelope.start(&params);
+    envelope.step();
+    assert_eq!(envelope.gain, 0.0);
+    envelope.step();
+    assert_eq!(envelope.stage, Stage::FadeIn { step: 1.0 });
+    envelope.step();
+    assert_eq!(envelope.gain, 1.0);
+    assert!(!envelope.is_active());
+}
+
+#[test]
+fn test_schedule() {
+    let schedule = parse_schedule("17:30, 09:00 09:00").unwrap();
+    assert_eq!(schedule_string(&schedule), "09:00, 17:30");
+    assert!(parse_schedule("24:00").is_err());
+    assert!(parse_schedule("9").is_err());
+    let nine = 9 * 60 * 60;
+    assert!(is_due(&schedule, nine - 1, nine));
+    assert!(!is_due(&schedule, nine, nine + 1));
+    // The interval may wrap around midnight.
+    let schedule = parse_schedule("00:00").unwrap();
+    assert!(is_due(&schedule, 24 * 60 * 60 - 1, 1));
+}
@@ -0,0 +1,366 @@
+//! Automated time-alignment and level-matching of speakers via a measurement microphone.
+//!
+//! A sine sweep is played through each speaker in turn while a single channel of the default input
+//! device is recorded. The arrival time and level of each sweep at the microphone are found by
+//! cross-correlating the recording with the sweep, from which the trim and delay that align every
+//! speaker at the microphone's position are derived.
+//!
+//! The input and output streams run at the same rate, so while the absolute latency between them
+//! is unknown it is the same for every sweep and cancels out when comparing speakers.
+
+use audio::{capture, fft, input, output, sample_rate, speaker, test_signal};
+use nannou_audio::Buffer;
+use rustfft::num_complex::Complex;
+use rustfft::num_traits::Zero;
+use std::cmp;
+use std::sync::mpsc;
+use std::thread;
+use time_calc::Ms;
+
+/// The silence before the first sweep, allowing the recording to begin before any sweep arrives.
+///
+/// The first half is also used to measure the background noise of the room.
+pub const LEAD_SECS: f64 = 0.5;
+
+/// The silence after each sweep, allowing its reverberation to decay and covering the longest
+/// expected arrival time of the sweep, including any delay already applied to its speaker.
+pub const GAP_SECS: f64 = 1.5;
+
+/// The level by which a sweep must exceed the background noise for its speaker to be calibrated.
+pub const MIN_SNR_DB: f32 = 10.0;
+
+/// The timing of the sequence of sweeps in frames.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Timing {
+    /// The silence before the first sweep.
+    pub lead_frames: usize,
+    /// The duration of each sweep.
+    pub sweep_frames: usize,
+    /// The duration of each sweep along with the silence that follows it.
+    pub interval_frames: usize,
+}
+
+/// The sequence of sweeps played through each speaker in turn on the audio output thread.
+pub struct Sweeps {
+    sweep: Vec<f32>,
+    gain: f32,
+    channels: Vec<usize>,
+    timing: Timing,
+    frame: usize,
+}
+
+/// The input stream end of a calibration, stored within the default input stream's model.
+#[derive(Clone)]
+pub struct Tx {
+    /// The input channel of the measurement microphone.
+    pub channel: usize,
+    /// The buffers via which the microphone's samples are passed to the GUI.
+    pub buffers: capture::Buffers,
+}
+
+/// A handle to a calibration in progress, collecting the recording of the microphone.
+pub struct Calibration {
+    sweep: Vec<f32>,
+    timing: Timing,
+    n_speakers: usize,
+    buffers: capture::Buffers,
+    recording: Vec<f32>,
+}
+
+/// The arrival time and level of a single speaker's sweep at the microphone.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Measurement {
+    /// The arrival of the sweep relative to the start of its window of the recording.
+    pub arrival_frames: usize,
+    /// The level of the sweep at the microphone relative to that played.
+    pub level_db: f32,
+}
+
+/// The trim and delay that align a speaker with all others at the microphone.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Alignment {
+    pub trim_db: f32,
+    pub delay: Ms,
+}
+
+impl Timing {
+    /// The timing for sweeps of the given length at the current sample rate.
+    pub fn new(sweep_frames: usize) -> Self {
+        let lead_frames = (LEAD_SECS * sample_rate()) as usize;
+        let interval_frames = sweep_frames + (GAP_SECS * sample_rate()) as usize;
+        Timing { lead_frames, sweep_frames, interval_frames }
+    }
+
+    /// The number of frames that must be recorded to capture the sweeps of `n_speakers`.
+    pub fn total_frames(&self, n_speakers: usize) -> usize {
+        self.lead_frames + n_speakers * self.interval_frames
+    }
+
+    // The window of the recording in which the sweep of the speaker at `index` arrives.
+    //
+    // Each window spans the sweep's interval and the lead, so that sweeps arriving up to a lead
+    // earlier than expected are still found in full.
+    fn window(&self, index: usize) -> (usize, usize) {
+        let start = index * self.interval_frames;
+        (start, start + self.interval_frames + self.lead_frames)
+    }
+}
+
+impl Sweeps {
+    /// Play the given sweep at `level_db` through each of the given output channels in turn.
+    pub fn new(sweep: Vec<f32>, level_db: f32, channels: Vec<usize>, timing: Timing) -> Self {
+        let gain = 10.0f32.powf(level_db / 20.0);
+        Sweeps { sweep, gain, channels, timing, frame: 0 }
+    }
+
+    /// Add the sweep of the current speaker, if any, to the given buffer.
+    pub fn process(&mut self, buffer: &mut Buffer) {
+        let n_channels = buffer.channels();
+        if n_channels == 0 {
+            return;
+        }
+        let Timing { lead_frames, interval_frames, .. } = self.timing;
+        for frame in buffer[..].chunks_mut(n_channels) {
+            if self.frame >= lead_frames {
+                let elapsed = self.frame - lead_frames;
+                let index = elapsed / interval_frames;
+                let position = elapsed % interval_frames;
+                if let (Some(&channel), Some(&sample)) =
+                    (self.channels.get(index), self.sweep.get(position))
+                {
+                    if channel < frame.len() {
+                        frame[channel] += sample * self.gain;
+                    }
+                }
+            }
+            self.frame += 1;
+        }
+    }
+}
+
+impl Calibration {
+    /// Begin playing a sweep through each of the given output channels in turn while recording
+    /// `input_channel` of the default input device.
+    pub fn start(
+        output_channels: Vec<usize>,
+        level_db: f32,
+        input_channel: usize,
+        audio_input: &input::Streams,
+        audio_output: &output::Stream,
+    ) -> Result<Self, String> {
+        if output_channels.is_empty() {
+            return Err("there are no speakers to calibrate".to_string());
+        }
+        let sweep = test_signal::sweep();
+        let timing = Timing::new(sweep.len());
+        let n_speakers = output_channels.len();
+        let recording = Vec::with_capacity(timing.total_frames(n_speakers));
+
+        // Begin recording before the sweeps so that the first is not missed.
+        let buffers = capture::Buffers::new(1);
+        let tx = Tx { channel: input_channel, buffers: buffers.clone() };
+        audio_input.send_default(move |audio| audio.calibration = Some(tx))?;
+
+        let sweeps = Sweeps::new(sweep.clone(), level_db, output_channels, timing);
+        audio_output
+            .send(move |audio| audio.calibration = Some(sweeps))
+            .map_err(|err| format!("{:?}", err))?;
+
+        Ok(Calibration { sweep, timing, n_speakers, buffers, recording })
+    }
+
+    /// Collect the samples recorded since the last update, returning the progress from `0.0` to
+    /// `1.0`.
+    pub fn update(&mut self) -> f32 {
+        let total_frames = self.timing.total_frames(self.n_speakers);
+        while let Some(samples) = self.buffers.filled.try_pop() {
+            let remaining = total_frames.saturating_sub(self.recording.len());
+            let take = cmp::min(remaining, samples.len());
+            self.recording.extend(samples[..take].iter().cloned());
+            self.buffers.empty.push(samples);
+        }
+        self.recording.len() as f32 / total_frames as f32
+    }
+
+    /// Whether the sweeps of all speakers have been recorded.
+    pub fn is_complete(&self) -> bool {
+        self.recording.len() >= self.timing.total_frames(self.n_speakers)
+    }
+
+    /// Stop playing the sweeps and recording the microphone.
+    pub fn stop(&self, audio_input: &input::Streams, audio_output: &output::Stream) {
+        if let Err(err) = audio_input.send_default(|audio| audio.calibration = None) {
+            eprintln!("failed to stop calibration recording: {}", err);
+        }
+        audio_output
+            .send(|audio| audio.calibration = None)
+            .expect("failed to stop calibration sweeps on audio output thread");
+    }
+
+    /// Measure the sweep of each speaker within the recording on a dedicated thread.
+    pub fn analyse(self) -> mpsc::Receiver<Vec<Option<Measurement>>> {
+        let (tx, rx) = mpsc::channel();
+        let Calibration { sweep, timing, n_speakers, recording, .. } = self;
+        thread::Builder::new()
+            .name("calibration".into())
+            .spawn(move || {
+                let measurements = measure(&recording, &sweep, &timing, n_speakers);
+                tx.send(measurements).ok();
+            })
+            .expect("failed to spawn calibration thread");
+        rx
+    }
+}
+
+/// Find the arrival time and level of the sweep of each of `n_speakers` within the recording.
+///
+/// Returns `None` for speakers whose sweep could not be distinguished from the background noise.
+pub fn measure(
+    recording: &[f32],
+    sweep: &[f32],
+    timing: &Timing,
+    n_speakers: usize,
+) -> Vec<Option<Measurement>> {
+    let (_, window_end) = timing.window(0);
+    let fft_len = (window_end + sweep.len()).next_power_of_two();
+    let fft = fft::Planner::new(false).plan_fft(fft_len);
+    let ifft = fft::Planner::new(true).plan_fft(fft_len);
+    let mut input = vec![Complex::<f32>::zero(); fft_len];
+    let mut spectrum = vec![Complex::<f32>::zero(); fft_len];
+    let mut correlation = vec![Complex::<f32>::zero(); fft_len];
+
+    // The spectrum of the sweep, against which each window is correlated.
+    let mut sweep_spectrum = vec![Complex::<f32>::zero(); fft_len];
+    for (c, &s) in input.iter_mut().zip(sweep) {
+        c.re = s;
+    }
+    fft.process(&mut input, &mut sweep_spectrum);
+    let sweep_energy = energy(sweep);
+
+    // The background noise recorded before the first sweep.
+    let noise = &recording[..cmp::min(timing.lead_frames / 2, recording.len())];
+    let noise_power = energy(noise) / cmp::max(noise.len(), 1) as f32;
+    let min_power = noise_power * 10.0f32.powf(MIN_SNR_DB / 10.0);
+
+    (0..n_speakers)
+        .map(|index| {
+            let (start, end) = timing.window(index);
+            let (start, end) = (cmp::min(start, recording.len()), cmp::min(end, recording.len()));
+            let window = &recording[start..end];
+            if window.len() < sweep.len() {
+                return None;
+            }
+
+            // Correlate the window with the sweep via the product of their spectra.
+            for c in input.iter_mut() {
+                *c = Complex::zero();
+            }
+            for (c, &s) in input.iter_mut().zip(window) {
+                c.re = s;
+            }
+            fft.process(&mut input, &mut spectrum);
+            for (c, s) in spectrum.iter_mut().zip(&sweep_spectrum) {
+                *c = *c * s.conj();
+            }
+            ifft.process(&mut spectrum, &mut correlation);
+
+            // The lag at which the sweep best matches, allowing for speakers of either polarity.
+            let max_lag = window.len() - sweep.len();
+            let mut arrival_frames = 0;
+            for lag in 0..max_lag + 1 {
+                if correlation[lag].re.abs() > correlation[arrival_frames].re.abs() {
+                    arrival_frames = lag;
+                }
+            }
+
+            let arrived = &window[arrival_frames..arrival_frames + sweep.len()];
+            let arrived_energy = energy(arrived);
+            if arrived_energy <= 0.0 || arrived_energy / sweep.len() as f32 <= min_power {
+                return None;
+            }
+            let level_db = 10.0 * (arrived_energy / sweep_energy).log10();
+            Some(Measurement { arrival_frames, level_db })
+        })
+        .collect()
+}
+
+/// The trim and delay of each speaker that align all measured speakers at the microphone.
+///
+/// `current` holds the trim and delay of each speaker during the measurement, as these are
+/// included in the measured arrival and level. Every speaker is matched to the level of the
+/// quietest so that no trim is raised beyond what was heard, and the latest speaker to arrive
+/// receives no delay. Speakers that were not measured are `None`.
+pub fn align(
+    measurements: &[Option<Measurement>],
+    current: &[(f32, Ms)],
+) -> Vec<Option<Alignment>> {
+    let measured = || measurements.iter().filter_map(|m| *m);
+    let latest = match measured().map(|m| m.arrival_frames).max() {
+        None => return vec![None; measurements.len()],
+        Some(latest) => latest,
+    };
+    let quietest = measured().map(|m| m.level_db).fold(::std::f32::INFINITY, f32::min);
+    let ms_per_frame = 1_000.0 / sample_rate();
+
+    let delays: Vec<_> = measurements
+        .iter()
+        .zip(current)
+        .map(|(m, &(_, delay))| {
+            m.map(|m| delay.ms() + (latest - m.arrival_frames) as f64 * ms_per_frame)
+        })
+        .collect();
+    let shortest = delays.iter().filter_map(|d| *d).fold(::std::f64::INFINITY, f64::min);
+
+    measurements
+        .iter()
+        .zip(current)
+        .zip(delays)
+        .map(|((m, &(trim_db, _)), delay_ms)| {
+            let (m, delay_ms) = (m.as_ref()?, delay_ms?);
+            let trim_db = trim_db - (m.level_db - quietest);
+            let trim_db = trim_db.max(speaker::MIN_TRIM_DB).min(speaker::MAX_TRIM_DB);
+            let delay = Ms((delay_ms - shortest).min(speaker::MAX_DELAY.ms()));
+            Some(Alignment { trim_db, delay })
+        })
+        .collect()
+}
+
+fn energy(samples: &[f32]) -> f32 {
+    samples.iter().map(|s| s * s).sum()
+}
+
+#[test]
+fn test_measure_and_align() {
+    // A short burst of noise stands in for the sweep.
+    let mut rng = 0x9E37_79B9u32;
+    let sweep: Vec<f32> = (0..64)
+        .map(|_| {
+            rng ^= rng << 13;
+            rng ^= rng >> 17;
+            rng ^= rng << 5;
+            (rng as f64 / ::std::u32::MAX as f64 * 2.0 - 1.0) as f32
+        })
+        .collect();
+    let timing = Timing { lead_frames: 32, sweep_frames: 64, interval_frames: 128 };
+
+    // The second speaker arrives 10 frames later than the first at half the amplitude.
+    let mut recording = vec![0.0; timing.total_frames(2)];
+    let arrivals = [(40, 1.0), (timing.interval_frames + 50, 0.5)];
+    for &(start, gain) in &arrivals {
+        for (r, s) in recording[start..].iter_mut().zip(&sweep) {
+            *r += s * gain;
+        }
+    }
+
+    let measurements = measure(&recording, &sweep, &timing, 2);
+    let (a, b) = (measurements[0].unwrap(), measurements[1].unwrap());
+    assert_eq!(a.arrival_frames, 40);
+    assert_eq!(b.arrival_frames, 50);
+    assert!((a.level_db - b.level_db - 6.02).abs() < 0.01);
+
+    let current = [(0.0, Ms(0.0)), (0.0, Ms(0.0))];
+    let alignments = align(&measurements, &current);
+    let (a, b) = (alignments[0].unwrap(), alignments[1].unwrap());
+    assert!((a.trim_db + 6.02).abs() < 0.01 && b.trim_db.abs() < 0.01);
+    assert!((a.delay.ms() - 10_000.0 / sample_rate()).abs() < 1e-6 && b.delay.ms() == 0.0);
+}
@@ -0,0 +1,217 @@
+//! Recording of realtime sources to WAV files.
+//!
+//! While a capture is in progress, the input stream copies the source's channels into buffers
+//! which are written to disk on a dedicated thread so that no file IO occurs on the audio thread.
+//! This allows for sampling site-specific sounds during an install and then using them as WAV
+//! sources.
+
+use audio::{self, input, source};
+use crossbeam::sync::SegQueue;
+use hound;
+use slug::slugify;
+use std::fs;
+use std::ops;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// The number of buffers allocated up front for passing samples to the writer thread.
+const NUM_BUFFERS: usize = 32;
+/// The interval at which the writer thread checks for newly captured samples.
+const WRITE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Buffers of interleaved samples passed from an audio thread to a WAV writer thread.
+#[derive(Clone)]
+pub struct Buffers {
+    /// Buffers of interleaved samples ready to be written.
+    pub filled: Arc<SegQueue<Vec<f32>>>,
+    /// Written buffers ready for re-use.
+    pub empty: Arc<SegQueue<Vec<f32>>>,
+}
+
+/// A handle to a thread writing the samples it receives to a WAV file.
+#[derive(Debug)]
+pub struct WavWriter {
+    path: PathBuf,
+    is_writing: Arc<AtomicBool>,
+    thread: thread::JoinHandle<Result<(), hound::Error>>,
+}
+
+/// The input stream end of a capture, stored within the input stream's model.
+#[derive(Clone)]
+pub struct Tx {
+    /// The range of input channels being captured.
+    pub channels: ops::Range<usize>,
+    /// The buffers shared with the writer thread.
+    pub buffers: Buffers,
+}
+
+/// A handle to a capture in progress.
+#[derive(Debug)]
+pub struct Capture {
+    source_id: source::Id,
+    writer: WavWriter,
+}
+
+impl Buffers {
+    /// Allocate buffers for passing samples with the given number of channels between threads.
+    ///
+    /// The buffers are allocated up front to avoid allocating on the audio thread.
+    pub fn new(n_channels: usize) -> Self {
+        let filled = Arc::new(SegQueue::new());
+        let empty = Arc::new(SegQueue::new());
+        for _ in 0..NUM_BUFFERS {
+            empty.push(Vec::with_capacity(audio::frames_per_buffer() * n_channels));
+        }
+        Buffers { filled, empty }
+    }
+
+    /// Retrieve an empty buffer from the writer thread.
+    ///
+    /// A new buffer with the given capacity is only allocated if the writer thread falls behind.
+    pub fn pop_empty(&self, capacity: usize) -> Vec<f32> {
+        match self.empty.try_pop() {
+            None => Vec::with_capacity(capacity),
+            Some(mut samples) => {
+                samples.clear();
+                samples
+            },
+        }
+    }
+}
+
+impl WavWriter {
+    /// The path to which the samples are being written.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Stop writing and wait for the WAV file to be finalised, returning its path.
+    pub fn finish(self) -> Result<PathBuf, String> {
+        let WavWriter { path, is_writing, thread } = self;
+        is_writing.store(false, Ordering::Relaxed);
+        match thread.join() {
+            Ok(Ok(())) => Ok(path),
+            Ok(Err(err)) => Err(format!("{}", err)),
+            Err(_) => Err("the writer thread panicked".to_string()),
+        }
+    }
+}
+
+impl Capture {
+    /// The realtime source being captured.
+    pub fn source_id(&self) -> source::Id {
+        self.source_id
+    }
+
+    /// The path to which the capture is being written.
+    pub fn path(&self) -> &Path {
+        self.writer.path()
+    }
+
+    /// Stop capturing and wait for the WAV file to be finalised, returning its path.
+    pub fn stop(self, audio_input: &input::Streams) -> Result<PathBuf, String> {
+        let Capture { source_id, writer } = self;
+        audio_input.send(move |audio| {
+            audio.captures.remove(&source_id);
+        })?;
+        writer.finish()
+    }
+}
+
+/// Spawn a thread that writes the samples it receives to a WAV file at `path`.
+///
+/// Samples are written as 32-bit float at the audio server's sample rate. Once all `Buffers`
+/// have been dropped (e.g. when an audio thread drops its end) the file is finalised
+/// automatically.
+pub fn spawn_wav_writer(
+    path: PathBuf,
+    n_channels: usize,
+    thread_name: &str,
+) -> Result<(WavWriter, Buffers), String> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory).map_err(|err| format!("{}", err))?;
+    }
+    let spec = hound::WavSpec {
+        channels: n_channels as u16,
+        sample_rate: audio::sample_rate() as u32,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(&path, spec).map_err(|err| format!("{}", err))?;
+
+    let buffers = Buffers::new(n_channels);
+    let filled = buffers.filled.clone();
+    let empty = buffers.empty.clone();
+
+    let is_writing = Arc::new(AtomicBool::new(true));
+    let is_writing_2 = is_writing.clone();
+    let thread = thread::Builder::new()
+        .name(thread_name.into())
+        .spawn(move || {
+            loop {
+                // Once no other thread holds the buffers, no more samples will arrive.
+                let is_writing = is_writing_2.load(Ordering::Relaxed)
+                    && Arc::strong_count(&filled) > 1;
+                while let Some(samples) = filled.try_pop() {
+                    for &sample in &samples {
+                        writer.write_sample(sample)?;
+                    }
+                    empty.push(samples);
+                }
+                if !is_writing {
+                    break;
+                }
+                thread::sleep(WRITE_INTERVAL);
+            }
+            writer.finalize()
+        })
+        .map_err(|err| format!("{}", err))?;
+
+    Ok((WavWriter { path, is_writing, thread }, buffers))
+}
+
+/// Begin capturing the channels of the given realtime source to a WAV file at `path`.
+///
+/// If the input stream drops the capture (e.g. when switching projects) the file is finalised
+/// automatically.
+pub fn start(
+    source_id: source::Id,
+    realtime: &source::Realtime,
+    path: PathBuf,
+    audio_input: &input::Streams,
+) -> Result<Capture, String> {
+    let n_channels = realtime.channels.len();
+    if n_channels == 0 {
+        return Err("the source has no channels to capture".to_string());
+    }
+    let (writer, buffers) = spawn_wav_writer(path, n_channels, "realtime_capture")?;
+    let tx = Tx {
+        channels: realtime.channels.clone(),
+        buffers,
+    };
+    audio_input.send(move |audio| {
+        audio.captures.insert(source_id, tx);
+    })?;
+    Ok(Capture { source_id, writer })
+}
+
+/// The first unused path for a capture of the source with the given name within `directory`.
+pub fn next_capture_path(directory: &Path, source_name: &str) -> PathBuf {
+    let stem = format!("{}-capture", slugify(source_name));
+    next_wav_path(directory, &stem)
+}
+
+/// The first unused path of the form `<stem>-<n>.wav` within `directory`.
+pub fn next_wav_path(directory: &Path, stem: &str) -> PathBuf {
+    let mut i = 1;
+    loop {
+        let path = directory.join(format!("{}-{}.wav", stem, i));
+        if !path.exists() {
+            return path;
+        }
+        i += 1;
+    }
+}
@@ -1,6 +1,6 @@
 //! An implementation of Distance-Based Amplitude Panning as published by Trond Lossius, 2009.
 
-use nannou::geom::Point2;
+use nannou::geom::Point3;
 
 #[derive(Copy, Clone, Debug)]
 pub struct Speaker {
@@ -25,10 +25,11 @@ pub struct SpeakerGains<'a> {
 /// speaker only."
 ///
 /// A non-zero blur will ensure that the distance is greater than `0.0` and that we never divide by 0.0.
-pub fn blurred_distance_2(source: Point2<f64>, speaker: Point2<f64>, blur: f64) -> f64 {
+pub fn blurred_distance_2(source: Point3<f64>, speaker: Point3<f64>, blur: f64) -> f64 {
     let x = speaker.x - source.x;
     let y = speaker.y - source.y;
-    (x * x + y * y + blur * blur).max(::std::f64::EPSILON)
+    let z = speaker.z - source.z;
+    (x * x + y * y + z * z + blur * blur).max(::std::f64::EPSILON)
 }
 
 impl<'a> SpeakerGains<'a> {
@@ -124,3 +125,15 @@ fn speaker_gains() {
         assert_eq!(g, gain);
     }
 }
+
+#[test]
+fn blurred_distance_height() {
+    use nannou::prelude::*;
+
+    // A sound near the floor should be closer to the floor speaker than the ceiling speaker.
+    let src = pt3(0.0, 0.0, 0.5);
+    let floor = blurred_distance_2(pt3(1.0, 0.0, 0.0), src, 0.0);
+    let ceiling = blurred_distance_2(pt3(1.0, 0.0, 4.0), src, 0.0);
+    assert!(floor < ceiling);
+    assert_eq!(floor, 1.25);
+}
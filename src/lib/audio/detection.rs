@@ -6,7 +6,7 @@
 //! - RMS and Peak per Speaker channel.
 //! - FFT and avg RMS and Peak per installation.
 
-use audio::{MAX_CHANNELS, FRAMES_PER_BUFFER};
+use audio::{frames_per_buffer, MAX_CHANNELS};
 use audio::{fft, sound, speaker};
 use audio::detector::{EnvDetector, Fft, FftDetector, FFT_WINDOW_LEN};
 use crossbeam::sync::SegQueue;
@@ -349,14 +349,14 @@ fn run(
     const SOUND_BUFFERS_TO_PREPARE: usize = EST_NUM_SOUNDS * BUFFERS_PER_SOUND;
     model.num_active_sound_buffers = SOUND_BUFFERS_TO_PREPARE;
     for _ in 0..SOUND_BUFFERS_TO_PREPARE {
-        let buffer = Vec::with_capacity(FRAMES_PER_BUFFER * 2);
+        let buffer = Vec::with_capacity(frames_per_buffer() * 2);
         model.sound_buffer_tx.push(buffer);
     }
 
     // Pre-prepare some output buffers.
     const OUTPUT_BUFFERS_TO_PREPARE: usize = 3;
     for _ in 0..OUTPUT_BUFFERS_TO_PREPARE {
-        let buffer = Vec::with_capacity(FRAMES_PER_BUFFER * MAX_CHANNELS);
+        let buffer = Vec::with_capacity(frames_per_buffer() * MAX_CHANNELS);
         let info = Default::default();
         model.output_buffer_tx.push((buffer, info));
     }
@@ -383,7 +383,7 @@ fn run(
                 let min_num_sound_buffers = BUFFERS_PER_SOUND * model.sounds.len();
                 if model.num_active_sound_buffers < min_num_sound_buffers {
                     for _ in model.num_active_sound_buffers..min_num_sound_buffers {
-                        let samples_len = channels * FRAMES_PER_BUFFER;
+                        let samples_len = channels * frames_per_buffer();
                         let buffer = Vec::with_capacity(samples_len);
                         model.sound_buffer_tx.push(buffer);
                         model.num_active_sound_buffers += 1;
@@ -446,7 +446,8 @@ fn run(
                     .entry(installation_id)
                     .or_insert_with(|| {
                         let speaker_analyses = Vec::with_capacity(MAX_CHANNELS);
-                        let summed_samples_of_all_channels = Vec::with_capacity(FRAMES_PER_BUFFER);
+                        let summed_samples_of_all_channels =
+                            Vec::with_capacity(frames_per_buffer());
                         let fft_detector = FftDetector::new();
                         Installation {
                             speaker_analyses,
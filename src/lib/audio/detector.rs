@@ -30,19 +30,21 @@ pub type FftPlanner = super::fft::Planner;
 // RMS is monitored for visualisation, so we want a window size roughly the duration of one frame.
 //
 // A new visual frame is displayed roughly 60 times per second compared to 44_100 audio frames.
-const WINDOW_SIZE: usize = audio::SAMPLE_RATE as usize / 60;
+fn window_size() -> usize {
+    audio::sample_rate() as usize / 60
+}
 
 // The number of frames used to smooth the attack/release of the RMS detection.
 const RMS_ATTACK_FRAMES: f32 = 0.0;
 const RMS_RELEASE_FRAMES: f32 = 0.0;
-const PEAK_ATTACK_FRAMES: f32 = WINDOW_SIZE as f32 / 8.0;
-const PEAK_RELEASE_FRAMES: f32 = WINDOW_SIZE as f32 / 8.0;
 
 /// The length of the window used for performing the FFT.
 pub const FFT_WINDOW_LEN: usize = 512;
 
 /// The step between each frequency bin is equal to `samplerate / 2 * windowlength`.
-pub const FFT_BIN_STEP_HZ: f64 = audio::SAMPLE_RATE / (2.0 * FFT_WINDOW_LEN as f64);
+pub fn fft_bin_step_hz() -> f64 {
+    audio::sample_rate() / (2.0 * FFT_WINDOW_LEN as f64)
+}
 
 /// An envelope detector for a single channel.
 ///
@@ -62,10 +64,13 @@ pub struct FftDetector {
 impl EnvDetector {
     /// Construct a new `EnvDetector` with a zeroed RMS window.
     pub fn new() -> Self {
-        let slice = vec![[0.0]; WINDOW_SIZE].into_boxed_slice();
+        let window_size = window_size();
+        let slice = vec![[0.0]; window_size].into_boxed_slice();
         let ring_buffer = ring_buffer::Fixed::from(slice);
         let rms = RmsDetector::rms(ring_buffer, RMS_ATTACK_FRAMES, RMS_RELEASE_FRAMES);
-        let peak = PeakDetector::peak(PEAK_ATTACK_FRAMES, PEAK_RELEASE_FRAMES);
+        let peak_attack_frames = window_size as f32 / 8.0;
+        let peak_release_frames = window_size as f32 / 8.0;
+        let peak = PeakDetector::peak(peak_attack_frames, peak_release_frames);
         let current_rms = 0.0;
         let current_peak = 0.0;
         EnvDetector {
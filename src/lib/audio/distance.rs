@@ -0,0 +1,147 @@
+//! A distance model so that far away sounds actually sound far away.
+//!
+//! DBAP only distributes a sound between the speakers. A sound wandering far outside of the
+//! speaker array is panned to the nearest speakers but otherwise sounds just as close as any
+//! other. Sources that opt in to the distance model are additionally attenuated and low-passed
+//! (modelling the absorption of high frequencies by the air) according to their distance from
+//! the nearest speaker, or from the binaural listener if it is nearer.
+
+use audio::sample_rate;
+use metres::Metres;
+use std::f64::EPSILON;
+use std::f64::consts::PI;
+
+/// The cutoff of the air-absorption filter for sounds within the reference distance.
+pub const MAX_CUTOFF_HZ: f64 = 20_000.0;
+
+/// The master parameters of the distance model.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Params {
+    /// The distance within which sounds are neither attenuated nor filtered.
+    #[serde(default = "default::reference_distance")]
+    pub reference_distance: Metres,
+    /// The attenuation in decibels per doubling of distance beyond the reference distance.
+    #[serde(default = "default::rolloff_db")]
+    pub rolloff_db: f64,
+    /// The rate at which the air-absorption cutoff falls per metre beyond the reference distance.
+    ///
+    /// The cutoff is `MAX_CUTOFF_HZ / (1 + air_absorption * excess_metres)`. `0.0` disables the
+    /// filter.
+    #[serde(default = "default::air_absorption")]
+    pub air_absorption: f64,
+}
+
+/// The distance model state for a single sound.
+///
+/// Allocated along with the sound so that no allocation is required on the audio thread.
+#[derive(Clone, Debug)]
+pub struct Filter {
+    // The previous output of the low-pass filter for each channel.
+    y1: Vec<f32>,
+    // The gain and filter coefficient of the previous buffer, or `None` before the first.
+    previous: Option<(f32, f32)>,
+}
+
+impl Params {
+    /// The linear gain for a sound at the given distance from its nearest reference.
+    pub fn gain(&self, distance: Metres) -> f64 {
+        let reference = self.reference_distance.0.max(EPSILON);
+        if distance.0 <= reference {
+            return 1.0;
+        }
+        let db = -self.rolloff_db * (distance.0 / reference).log2();
+        10f64.powf(db / 20.0)
+    }
+
+    /// The cutoff of the air-absorption low-pass for a sound at the given distance.
+    pub fn cutoff_hz(&self, distance: Metres) -> f64 {
+        let excess = (distance.0 - self.reference_distance.0).max(0.0);
+        MAX_CUTOFF_HZ / (1.0 + self.air_absorption.max(0.0) * excess)
+    }
+}
+
+impl Filter {
+    /// Create the distance model state for a sound with the given number of channels.
+    pub fn new(channels: usize) -> Self {
+        Filter {
+            y1: vec![0.0; channels],
+            previous: None,
+        }
+    }
+
+    /// Attenuate and filter the interleaved samples of a sound at the given distance.
+    ///
+    /// The gain and cutoff are interpolated from those of the previous buffer to avoid zipper
+    /// noise as the sound moves.
+    pub fn process(&mut self, params: &Params, distance: Metres, samples: &mut [f32]) {
+        let n_channels = self.y1.len();
+        if n_channels == 0 {
+            return;
+        }
+        let gain = params.gain(distance) as f32;
+        let coeff = one_pole_coefficient(params.cutoff_hz(distance));
+        let (prev_gain, prev_coeff) = self.previous.unwrap_or((gain, coeff));
+        self.previous = Some((gain, coeff));
+
+        let len_frames = (samples.len() / n_channels) as f32;
+        for (frame_i, frame) in samples.chunks_mut(n_channels).enumerate() {
+            let lerp_amt = frame_i as f32 / len_frames;
+            let g = prev_gain + (gain - prev_gain) * lerp_amt;
+            let a = prev_coeff + (coeff - prev_coeff) * lerp_amt;
+            for (sample, y1) in frame.iter_mut().zip(self.y1.iter_mut()) {
+                *y1 = *sample * (1.0 - a) + *y1 * a;
+                *sample = *y1 * g;
+            }
+        }
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            reference_distance: default::reference_distance(),
+            rolloff_db: default::rolloff_db(),
+            air_absorption: default::air_absorption(),
+        }
+    }
+}
+
+// The feedback coefficient of a one-pole low-pass with the given cutoff.
+//
+// Returns `0.0` (no filtering) at or above the maximum cutoff.
+fn one_pole_coefficient(cutoff_hz: f64) -> f32 {
+    if cutoff_hz >= MAX_CUTOFF_HZ {
+        return 0.0;
+    }
+    (-2.0 * PI * cutoff_hz / sample_rate()).exp() as f32
+}
+
+pub mod default {
+    use metres::Metres;
+
+    pub fn reference_distance() -> Metres {
+        Metres(2.0)
+    }
+
+    pub fn rolloff_db() -> f64 {
+        6.0
+    }
+
+    pub fn air_absorption() -> f64 {
+        0.05
+    }
+}
+
+#[test]
+fn test_distance_model() {
+    let params = Params { reference_distance: Metres(2.0), rolloff_db: 6.0, air_absorption: 0.1 };
+    // Sounds within the reference distance are untouched.
+    assert_eq!(params.gain(Metres(1.0)), 1.0);
+    assert_eq!(params.cutoff_hz(Metres(1.0)), MAX_CUTOFF_HZ);
+    assert_eq!(one_pole_coefficient(params.cutoff_hz(Metres(1.0))), 0.0);
+    // Each doubling of distance attenuates by the rolloff.
+    let db = 20.0 * params.gain(Metres(8.0)).log10();
+    assert!((db + 12.0).abs() < 1e-9);
+    // The cutoff falls with distance.
+    assert!(params.cutoff_hz(Metres(50.0)) < params.cutoff_hz(Metres(10.0)));
+}
@@ -0,0 +1,123 @@
+//! Reduction of the final output to the word length of the output device, with optional TPDF
+//! dither.
+//!
+//! The device converts the `f32` output to its native format. On 16-bit converters this truncates
+//! quiet tails into audible, signal-correlated distortion. Quantising to the device's word length
+//! here with triangular (TPDF) dither leaves a constant, benign noise floor instead.
+
+/// The word length to which the output is quantised before reaching the device.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum WordLength {
+    /// Pass the output to the device untouched.
+    Float,
+    Bits24,
+    Bits16,
+}
+
+/// Quantises and optionally dithers the final output.
+#[derive(Clone, Debug)]
+pub struct Dither {
+    word_length: WordLength,
+    enabled: bool,
+    rng: u32,
+}
+
+impl WordLength {
+    /// All word lengths in the order in which they are displayed.
+    pub const ALL: [WordLength; 3] = [WordLength::Float, WordLength::Bits24, WordLength::Bits16];
+
+    /// The name displayed within the GUI.
+    pub fn label(&self) -> &'static str {
+        match *self {
+            WordLength::Float => "32-bit Float",
+            WordLength::Bits24 => "24-bit",
+            WordLength::Bits16 => "16-bit",
+        }
+    }
+
+    /// The number of bits of an integer word length.
+    pub fn bits(&self) -> Option<u32> {
+        match *self {
+            WordLength::Float => None,
+            WordLength::Bits24 => Some(24),
+            WordLength::Bits16 => Some(16),
+        }
+    }
+}
+
+impl Default for WordLength {
+    fn default() -> Self {
+        WordLength::Float
+    }
+}
+
+impl Dither {
+    /// Quantise to the given word length, dithering first if `enabled`.
+    pub fn new(word_length: WordLength, enabled: bool) -> Self {
+        Dither { word_length, enabled, rng: 0x2545_F491 }
+    }
+
+    /// Update the word length and whether or not dither is applied.
+    pub fn set(&mut self, word_length: WordLength, enabled: bool) {
+        self.word_length = word_length;
+        self.enabled = enabled;
+    }
+
+    /// Quantise the given samples in place.
+    pub fn process(&mut self, samples: &mut [f32]) {
+        let bits = match self.word_length.bits() {
+            None => return,
+            Some(bits) => bits,
+        };
+        let scale = (1u32 << (bits - 1)) as f32;
+        let max = (scale - 1.0) / scale;
+        for sample in samples {
+            // The sum of two uniform values of +/-0.5 LSB spans +/-1 LSB with a triangular PDF.
+            let dither = match self.enabled {
+                true => (self.uniform() + self.uniform()) * 0.5,
+                false => 0.0,
+            };
+            let quantised = (*sample * scale + dither).round() / scale;
+            *sample = quantised.max(-1.0).min(max);
+        }
+    }
+
+    // A uniformly distributed value within -1.0..1.0.
+    fn uniform(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        (self.rng as f64 / ::std::u32::MAX as f64 * 2.0 - 1.0) as f32
+    }
+}
+
+impl Default for Dither {
+    fn default() -> Self {
+        Dither::new(Default::default(), true)
+    }
+}
+
+#[test]
+fn test_dither() {
+    let lsb = 1.0 / 32_768.0;
+
+    // Without dither, a tail quieter than half an LSB is truncated to silence.
+    let mut dither = Dither::new(WordLength::Bits16, false);
+    let mut samples = vec![lsb * 0.25; 1_000];
+    dither.process(&mut samples);
+    assert!(samples.iter().all(|&s| s == 0.0));
+
+    // With dither it survives on average and every sample lands on a 16-bit step.
+    let mut dither = Dither::new(WordLength::Bits16, true);
+    let mut samples = vec![lsb * 0.25; 100_000];
+    dither.process(&mut samples);
+    assert!(samples.iter().all(|&s| (s / lsb).fract() == 0.0));
+    let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+    assert!((mean / lsb - 0.25).abs() < 0.02);
+
+    // Floating point output passes through untouched.
+    let mut dither = Dither::new(WordLength::Float, true);
+    let mut samples = vec![lsb * 0.25; 10];
+    dither.process(&mut samples);
+    assert!(samples.iter().all(|&s| s == lsb * 0.25));
+}
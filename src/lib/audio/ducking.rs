@@ -0,0 +1,187 @@
+//! Ducking the soundscape beneath higher priority sounds.
+//!
+//! Announcements and interactive sounds must remain intelligible over the soundscape. While a
+//! sound of at least an installation's trigger priority plays within it, every sound of a lower
+//! priority within the installation is attenuated by the installation's depth. The attenuation is
+//! applied over the attack duration and removed over the release duration once the last
+//! triggering sound ends.
+
+use audio::sound::Installations;
+use audio::source::Priority;
+use fxhash::{FxHashMap, FxHashSet};
+use installation;
+
+/// The greatest depth that may be selected via the GUI.
+pub const MAX_DEPTH_DB: f32 = 24.0;
+
+/// The longest attack that may be selected via the GUI.
+pub const MAX_ATTACK_SECS: f64 = 5.0;
+
+/// The longest release that may be selected via the GUI.
+pub const MAX_RELEASE_SECS: f64 = 10.0;
+
+/// The ducking parameters of each installation.
+pub type InstallationParams = FxHashMap<installation::Id, Params>;
+
+/// How an installation ducks its sounds beneath those of a higher priority.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Params {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Sounds of this priority or higher duck the sounds of a lower priority.
+    #[serde(default = "default::trigger")]
+    pub trigger: Priority,
+    /// The attenuation applied to ducked sounds in decibels.
+    #[serde(default = "default::depth_db")]
+    pub depth_db: f32,
+    /// The duration over which sounds are attenuated once a triggering sound begins.
+    #[serde(default = "default::attack_secs")]
+    pub attack_secs: f64,
+    /// The duration over which sounds are restored once the last triggering sound ends.
+    #[serde(default = "default::release_secs")]
+    pub release_secs: f64,
+}
+
+/// The installations within which a triggering sound is playing, gathered at the start of each
+/// buffer.
+///
+/// Retained by the audio output thread so that no allocation is required once warmed up.
+#[derive(Debug, Default)]
+pub struct Triggered {
+    installations: FxHashSet<installation::Id>,
+}
+
+impl Params {
+    /// The gain applied to ducked sounds.
+    pub fn gain(&self) -> f32 {
+        10f32.powf(-self.depth_db.max(0.0) / 20.0)
+    }
+
+    /// Step the given gain towards the ducked gain, or unity if not `ducked`, for `secs`.
+    pub fn step(&self, gain: f32, ducked: bool, secs: f32) -> f32 {
+        let (target, duration) = match ducked {
+            true => (self.gain(), self.attack_secs),
+            false => (1.0, self.release_secs),
+        };
+        if duration <= 0.0 {
+            return target;
+        }
+        let max_change = (1.0 - self.gain()) * secs / duration as f32;
+        if gain < target {
+            (gain + max_change).min(target)
+        } else {
+            (gain - max_change).max(target)
+        }
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            enabled: false,
+            trigger: default::trigger(),
+            depth_db: default::depth_db(),
+            attack_secs: default::attack_secs(),
+            release_secs: default::release_secs(),
+        }
+    }
+}
+
+impl Triggered {
+    /// Forget all triggering sounds, e.g. at the start of a buffer.
+    pub fn clear(&mut self) {
+        self.installations.clear();
+    }
+
+    /// Note a sound of the given priority playing within the given installations.
+    pub fn add(
+        &mut self,
+        priority: Priority,
+        installations: &Installations,
+        params: &InstallationParams,
+    ) {
+        for (id, params) in params {
+            if params.enabled && priority >= params.trigger && contains(installations, id) {
+                self.installations.insert(*id);
+            }
+        }
+    }
+
+    /// The deepest ducking among the given installations that applies to sounds of the given
+    /// priority, along with whether or not any of those installations are currently ducking.
+    ///
+    /// Returns `None` if none of the installations duck sounds of the given priority.
+    pub fn ducking(
+        &self,
+        priority: Priority,
+        installations: &Installations,
+        params: &InstallationParams,
+    ) -> Option<(Params, bool)> {
+        let mut deepest: Option<Params> = None;
+        let mut ducked = false;
+        for (id, params) in params {
+            if !params.enabled || priority >= params.trigger || !contains(installations, id) {
+                continue;
+            }
+            ducked |= self.installations.contains(id);
+            if deepest.map(|d| params.depth_db > d.depth_db).unwrap_or(true) {
+                deepest = Some(*params);
+            }
+        }
+        deepest.map(|params| (params, ducked))
+    }
+}
+
+// Whether or not a sound within the given installations plays within the installation.
+fn contains(installations: &Installations, id: &installation::Id) -> bool {
+    match *installations {
+        Installations::All => true,
+        Installations::Set(ref set) => set.contains(id),
+    }
+}
+
+pub mod default {
+    use audio::source::Priority;
+
+    pub fn trigger() -> Priority {
+        Priority::Interactive
+    }
+
+    pub fn depth_db() -> f32 {
+        9.0
+    }
+
+    pub fn attack_secs() -> f64 {
+        0.3
+    }
+
+    pub fn release_secs() -> f64 {
+        1.5
+    }
+}
+
+#[test]
+fn test_ducking() {
+    let a = installation::Id(0);
+    let b = installation::Id(1);
+    let set = |ids: &[installation::Id]| Installations::Set(ids.iter().cloned().collect());
+    let mut params = InstallationParams::default();
+    params.insert(a, Params { enabled: true, ..Default::default() });
+    params.insert(b, Params::default());
+
+    let mut triggered = Triggered::default();
+    triggered.add(Priority::Feature, &set(&[a]), &params);
+    assert_eq!(triggered.ducking(Priority::Background, &set(&[a]), &params).unwrap().1, false);
+    triggered.add(Priority::Announcement, &set(&[a, b]), &params);
+    let (ducking, ducked) = triggered.ducking(Priority::Background, &set(&[a]), &params).unwrap();
+    assert!(ducked);
+    // Sounds of the trigger priority or higher are never ducked, nor are sounds elsewhere.
+    assert!(triggered.ducking(Priority::Interactive, &set(&[a]), &params).is_none());
+    assert!(triggered.ducking(Priority::Background, &set(&[b]), &params).is_none());
+
+    // The gain reaches the ducked gain over the attack and unity over the release.
+    let gain = ducking.step(1.0, true, 0.15);
+    assert!(gain < 1.0 && gain > ducking.gain());
+    assert_eq!(ducking.step(gain, true, 0.2), ducking.gain());
+    assert_eq!(ducking.step(ducking.gain(), false, 2.0), 1.0);
+}
@@ -0,0 +1,525 @@
+//! Insert effects applied to each sound's signal before it is spatialised.
+//!
+//! Each `Source` stores the `Effects` parameters that are used for all sounds spawned from it. On
+//! the output thread, each `Sound` owns a `Chain` holding the filter state for each of its
+//! channels. All filters are biquads designed via the RBJ audio EQ cookbook formulae.
+//!
+//! The `ParametricEq` is applied to each speaker's output channel rather than to sounds, allowing
+//! for room correction that differs per speaker position. Similarly, a `Crossover` is applied to
+//! each output channel when bass management is active.
+
+use audio::sample_rate;
+use nannou::rand::Rng;
+
+/// The lowest cutoff frequency that may be specified for a filter.
+pub const MIN_HZ: f32 = 20.0;
+/// The highest cutoff frequency that may be specified for a filter.
+pub const MAX_HZ: f32 = 20_000.0;
+/// The maximum boost or cut applied by each EQ band.
+pub const MAX_EQ_DB: f32 = 12.0;
+/// The maximum cutoff randomisation that may be applied to soundscape sounds.
+pub const MAX_CUTOFF_OCTAVES: f32 = 4.0;
+
+/// The corner frequency of the low EQ band's shelf.
+pub const EQ_LOW_HZ: f32 = 250.0;
+/// The centre frequency of the mid EQ band.
+pub const EQ_MID_HZ: f32 = 1_000.0;
+/// The corner frequency of the high EQ band's shelf.
+pub const EQ_HIGH_HZ: f32 = 4_000.0;
+/// The number of bands within a `ParametricEq`.
+pub const NUM_PARAMETRIC_BANDS: usize = 4;
+/// The lowest Q that may be specified for a parametric EQ band.
+pub const MIN_Q: f32 = 0.1;
+/// The highest Q that may be specified for a parametric EQ band.
+pub const MAX_Q: f32 = 10.0;
+
+// The Q used for the high and low pass filters, producing a butterworth response.
+const FILTER_Q: f64 = ::std::f64::consts::FRAC_1_SQRT_2;
+// The Q used for the mid EQ band.
+const EQ_MID_Q: f64 = 0.7;
+// High pass, low pass, low shelf, mid peak and high shelf.
+const NUM_STAGES: usize = 5;
+
+/// The insert effect parameters applied to all sounds spawned from a source.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Effects {
+    #[serde(default = "default::high_pass")]
+    pub high_pass: Filter,
+    #[serde(default = "default::low_pass")]
+    pub low_pass: Filter,
+    #[serde(default)]
+    pub eq: Eq,
+}
+
+/// A high or low pass filter.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Filter {
+    #[serde(default)]
+    pub enabled: bool,
+    /// The cutoff frequency in hz.
+    pub hz: f32,
+}
+
+/// A three band EQ with a low shelf, a mid peak and a high shelf.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Eq {
+    #[serde(default)]
+    pub low_db: f32,
+    #[serde(default)]
+    pub mid_db: f32,
+    #[serde(default)]
+    pub high_db: f32,
+}
+
+/// The amount by which the effects of each soundscape sound randomly deviate from the source's.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Randomisation {
+    /// The maximum deviation of the filter cutoffs in octaves.
+    #[serde(default)]
+    pub cutoff_octaves: f32,
+    /// The maximum deviation of each EQ band in decibels.
+    #[serde(default)]
+    pub eq_db: f32,
+}
+
+/// A single band of a `ParametricEq`.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Band {
+    /// The centre frequency of a peak or the corner frequency of a shelf in hz.
+    pub hz: f32,
+    /// The bandwidth of a peak or the slope of a shelf.
+    #[serde(default = "default::band_q")]
+    pub q: f32,
+    /// The boost or cut applied by the band.
+    #[serde(default)]
+    pub db: f32,
+}
+
+/// A parametric EQ with a low shelf, a high shelf and peaking bands in between.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ParametricEq {
+    /// The first band is a low shelf, the last a high shelf and all others are peaks.
+    #[serde(default = "default::parametric_bands")]
+    pub bands: [Band; NUM_PARAMETRIC_BANDS],
+}
+
+/// The runtime state of a parametric EQ applied to a single channel on the output thread.
+#[derive(Clone, Debug)]
+pub struct ChannelEq {
+    eq: ParametricEq,
+    stages: [Option<Coefficients>; NUM_PARAMETRIC_BANDS],
+    states: [State; NUM_PARAMETRIC_BANDS],
+}
+
+/// The side of a crossover passed by a `Crossover` filter.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Split {
+    /// Pass frequencies below the crossover, e.g. for a subwoofer.
+    Low,
+    /// Pass frequencies above the crossover, e.g. for a full-range speaker.
+    High,
+}
+
+/// One side of a fourth order Linkwitz-Riley crossover applied to a single channel.
+///
+/// The low and high sides sum to a flat magnitude response.
+#[derive(Clone, Debug)]
+pub struct Crossover {
+    split: Option<Split>,
+    hz: f32,
+    coeffs: Option<Coefficients>,
+    states: [State; 2],
+}
+
+/// The runtime state of a sound's effects, owned by the sound on the output thread.
+#[derive(Clone, Debug)]
+pub struct Chain {
+    effects: Effects,
+    stages: [Option<Coefficients>; NUM_STAGES],
+    // The filter state of each stage for each channel.
+    channels: Vec<[State; NUM_STAGES]>,
+}
+
+// The normalised coefficients of a biquad filter.
+#[derive(Copy, Clone, Debug)]
+struct Coefficients {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+// The state of a transposed direct form II biquad.
+#[derive(Copy, Clone, Debug, Default)]
+struct State {
+    z1: f32,
+    z2: f32,
+}
+
+impl Effects {
+    /// Produce a copy of the effects with each parameter randomly offset within the given
+    /// randomisation ranges.
+    pub fn randomised<R>(&self, mut rng: R, randomisation: &Randomisation) -> Self
+    where
+        R: Rng,
+    {
+        let octaves = randomisation.cutoff_octaves.abs();
+        let db = randomisation.eq_db.abs();
+        let mut offset = |max: f32| match max > 0.0 {
+            true => rng.gen_range(-max, max),
+            false => 0.0,
+        };
+        let mut effects = *self;
+        effects.high_pass.hz = clamp_hz(self.high_pass.hz * 2f32.powf(offset(octaves)));
+        effects.low_pass.hz = clamp_hz(self.low_pass.hz * 2f32.powf(offset(octaves)));
+        effects.eq.low_db = clamp_db(self.eq.low_db + offset(db));
+        effects.eq.mid_db = clamp_db(self.eq.mid_db + offset(db));
+        effects.eq.high_db = clamp_db(self.eq.high_db + offset(db));
+        effects
+    }
+
+    // The coefficients of each active stage, `None` for stages that are bypassed.
+    fn stages(&self) -> [Option<Coefficients>; NUM_STAGES] {
+        let filter = |filter: &Filter, f: fn(f64, f64) -> Coefficients| match filter.enabled {
+            true => Some(f(clamp_hz(filter.hz) as f64, FILTER_Q)),
+            false => None,
+        };
+        let band = |db: f32, f: &dyn Fn(f64) -> Coefficients| match db == 0.0 {
+            true => None,
+            false => Some(f(clamp_db(db) as f64)),
+        };
+        [
+            filter(&self.high_pass, Coefficients::high_pass),
+            filter(&self.low_pass, Coefficients::low_pass),
+            band(self.eq.low_db, &|db| Coefficients::low_shelf(EQ_LOW_HZ as f64, FILTER_Q, db)),
+            band(self.eq.mid_db, &|db| Coefficients::peak(EQ_MID_HZ as f64, EQ_MID_Q, db)),
+            band(self.eq.high_db, &|db| Coefficients::high_shelf(EQ_HIGH_HZ as f64, FILTER_Q, db)),
+        ]
+    }
+}
+
+impl ParametricEq {
+    /// The combined response of all bands at the given frequency in decibels.
+    pub fn response_db(&self, hz: f32) -> f32 {
+        let w = omega(hz as f64);
+        self.stages()
+            .iter()
+            .filter_map(|stage| stage.as_ref())
+            .map(|coeffs| coeffs.response_db(w))
+            .sum()
+    }
+
+    // The coefficients of each band, `None` for bands with no boost or cut.
+    fn stages(&self) -> [Option<Coefficients>; NUM_PARAMETRIC_BANDS] {
+        let mut stages = [None; NUM_PARAMETRIC_BANDS];
+        let last = NUM_PARAMETRIC_BANDS - 1;
+        for (i, (stage, band)) in stages.iter_mut().zip(self.bands.iter()).enumerate() {
+            if band.db == 0.0 {
+                continue;
+            }
+            let hz = clamp_hz(band.hz) as f64;
+            let q = band.q.max(MIN_Q).min(MAX_Q) as f64;
+            let db = clamp_db(band.db) as f64;
+            *stage = Some(match i {
+                0 => Coefficients::low_shelf(hz, q, db),
+                i if i == last => Coefficients::high_shelf(hz, q, db),
+                _ => Coefficients::peak(hz, q, db),
+            });
+        }
+        stages
+    }
+}
+
+impl ChannelEq {
+    /// Create the runtime state for the given EQ.
+    pub fn new(eq: ParametricEq) -> Self {
+        let stages = eq.stages();
+        let states = [State::default(); NUM_PARAMETRIC_BANDS];
+        ChannelEq { eq, stages, states }
+    }
+
+    /// Update the EQ parameters, retaining the filter state where possible.
+    ///
+    /// This does not allocate and may be called on the output thread.
+    pub fn set_eq(&mut self, eq: ParametricEq) {
+        if self.eq == eq {
+            return;
+        }
+        let stages = eq.stages();
+        for ((old, new), state) in self.stages.iter().zip(stages.iter()).zip(&mut self.states) {
+            if old.is_none() && new.is_some() {
+                *state = State::default();
+            }
+        }
+        self.eq = eq;
+        self.stages = stages;
+    }
+
+    /// Process a single sample.
+    pub fn process(&mut self, mut sample: f32) -> f32 {
+        for (stage, state) in self.stages.iter().zip(self.states.iter_mut()) {
+            if let Some(ref coeffs) = *stage {
+                sample = state.process(coeffs, sample);
+            }
+        }
+        sample
+    }
+}
+
+impl Crossover {
+    /// A crossover that passes the signal untouched until a split is set.
+    pub fn new() -> Self {
+        Crossover {
+            split: None,
+            hz: 0.0,
+            coeffs: None,
+            states: [State::default(); 2],
+        }
+    }
+
+    /// Set the side of the crossover to pass and the crossover frequency.
+    ///
+    /// A split of `None` bypasses the filter. This does not allocate and may be called on the
+    /// output thread.
+    pub fn set(&mut self, split: Option<Split>, hz: f32) {
+        if self.split == split && self.hz == hz {
+            return;
+        }
+        if self.split != split {
+            self.states = [State::default(); 2];
+        }
+        let hz_f64 = clamp_hz(hz) as f64;
+        self.coeffs = split.map(|split| match split {
+            Split::Low => Coefficients::low_pass(hz_f64, FILTER_Q),
+            Split::High => Coefficients::high_pass(hz_f64, FILTER_Q),
+        });
+        self.split = split;
+        self.hz = hz;
+    }
+
+    /// Process a single sample.
+    pub fn process(&mut self, sample: f32) -> f32 {
+        match self.coeffs {
+            None => sample,
+            Some(ref coeffs) => {
+                let sample = self.states[0].process(coeffs, sample);
+                self.states[1].process(coeffs, sample)
+            },
+        }
+    }
+}
+
+impl Chain {
+    /// Create the effects chain for a sound with the given number of channels.
+    pub fn new(effects: Effects, channels: usize) -> Self {
+        let stages = effects.stages();
+        let channels = vec![[State::default(); NUM_STAGES]; channels];
+        Chain { effects, stages, channels }
+    }
+
+    /// Update the effect parameters, retaining the filter state where possible.
+    pub fn set_effects(&mut self, effects: Effects) {
+        if self.effects == effects {
+            return;
+        }
+        let stages = effects.stages();
+        // Reset the state of any stages that were previously bypassed.
+        for (i, (old, new)) in self.stages.iter().zip(stages.iter()).enumerate() {
+            if old.is_none() && new.is_some() {
+                for channel in self.channels.iter_mut() {
+                    channel[i] = State::default();
+                }
+            }
+        }
+        self.effects = effects;
+        self.stages = stages;
+    }
+
+    /// Process the given interleaved samples in place.
+    pub fn process(&mut self, samples: &mut [f32]) {
+        let Chain { ref stages, ref mut channels, .. } = *self;
+        if channels.is_empty() || stages.iter().all(Option::is_none) {
+            return;
+        }
+        for frame in samples.chunks_mut(channels.len()) {
+            for (sample, states) in frame.iter_mut().zip(channels.iter_mut()) {
+                for (stage, state) in stages.iter().zip(states.iter_mut()) {
+                    if let Some(ref coeffs) = *stage {
+                        *sample = state.process(coeffs, *sample);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Coefficients {
+    fn new(b0: f64, b1: f64, b2: f64, a0: f64, a1: f64, a2: f64) -> Self {
+        Coefficients {
+            b0: (b0 / a0) as f32,
+            b1: (b1 / a0) as f32,
+            b2: (b2 / a0) as f32,
+            a1: (a1 / a0) as f32,
+            a2: (a2 / a0) as f32,
+        }
+    }
+
+    fn high_pass(hz: f64, q: f64) -> Self {
+        let (sin, cos) = omega(hz).sin_cos();
+        let alpha = sin / (2.0 * q);
+        let b = (1.0 + cos) / 2.0;
+        Self::new(b, -(1.0 + cos), b, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    fn low_pass(hz: f64, q: f64) -> Self {
+        let (sin, cos) = omega(hz).sin_cos();
+        let alpha = sin / (2.0 * q);
+        let b = (1.0 - cos) / 2.0;
+        Self::new(b, 1.0 - cos, b, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    fn peak(hz: f64, q: f64, db: f64) -> Self {
+        let (sin, cos) = omega(hz).sin_cos();
+        let alpha = sin / (2.0 * q);
+        let a = 10f64.powf(db / 40.0);
+        let (b0, b2) = (1.0 + alpha * a, 1.0 - alpha * a);
+        let (a0, a2) = (1.0 + alpha / a, 1.0 - alpha / a);
+        Self::new(b0, -2.0 * cos, b2, a0, -2.0 * cos, a2)
+    }
+
+    // A `q` of `FRAC_1_SQRT_2` produces a shelf slope of `1`.
+    fn low_shelf(hz: f64, q: f64, db: f64) -> Self {
+        let (sin, cos) = omega(hz).sin_cos();
+        let a = 10f64.powf(db / 40.0);
+        let beta = a.sqrt() * sin / q;
+        let b0 = a * ((a + 1.0) - (a - 1.0) * cos + beta);
+        let b1 = 2.0 * a * ((a - 1.0) - (a + 1.0) * cos);
+        let b2 = a * ((a + 1.0) - (a - 1.0) * cos - beta);
+        let a0 = (a + 1.0) + (a - 1.0) * cos + beta;
+        let a1 = -2.0 * ((a - 1.0) + (a + 1.0) * cos);
+        let a2 = (a + 1.0) + (a - 1.0) * cos - beta;
+        Self::new(b0, b1, b2, a0, a1, a2)
+    }
+
+    fn high_shelf(hz: f64, q: f64, db: f64) -> Self {
+        let (sin, cos) = omega(hz).sin_cos();
+        let a = 10f64.powf(db / 40.0);
+        let beta = a.sqrt() * sin / q;
+        let b0 = a * ((a + 1.0) + (a - 1.0) * cos + beta);
+        let b1 = -2.0 * a * ((a - 1.0) + (a + 1.0) * cos);
+        let b2 = a * ((a + 1.0) + (a - 1.0) * cos - beta);
+        let a0 = (a + 1.0) - (a - 1.0) * cos + beta;
+        let a1 = 2.0 * ((a - 1.0) - (a + 1.0) * cos);
+        let a2 = (a + 1.0) - (a - 1.0) * cos - beta;
+        Self::new(b0, b1, b2, a0, a1, a2)
+    }
+
+    // The magnitude response at the given angular frequency in decibels.
+    fn response_db(&self, w: f64) -> f32 {
+        let (sin1, cos1) = w.sin_cos();
+        let (sin2, cos2) = (2.0 * w).sin_cos();
+        let (b0, b1, b2) = (self.b0 as f64, self.b1 as f64, self.b2 as f64);
+        let (a1, a2) = (self.a1 as f64, self.a2 as f64);
+        let num = (b0 + b1 * cos1 + b2 * cos2).powi(2) + (b1 * sin1 + b2 * sin2).powi(2);
+        let den = (1.0 + a1 * cos1 + a2 * cos2).powi(2) + (a1 * sin1 + a2 * sin2).powi(2);
+        (10.0 * (num / den).log10()) as f32
+    }
+}
+
+impl State {
+    fn process(&mut self, c: &Coefficients, x: f32) -> f32 {
+        let y = c.b0 * x + self.z1;
+        self.z1 = c.b1 * x - c.a1 * y + self.z2;
+        self.z2 = c.b2 * x - c.a2 * y;
+        y
+    }
+}
+
+impl Default for Effects {
+    fn default() -> Self {
+        Effects {
+            high_pass: default::high_pass(),
+            low_pass: default::low_pass(),
+            eq: Default::default(),
+        }
+    }
+}
+
+impl Default for ParametricEq {
+    fn default() -> Self {
+        ParametricEq { bands: default::parametric_bands() }
+    }
+}
+
+// The angular frequency of the given frequency in hz.
+fn omega(hz: f64) -> f64 {
+    2.0 * ::std::f64::consts::PI * hz / sample_rate()
+}
+
+// Keep cutoffs within the audible range and below nyquist.
+fn clamp_hz(hz: f32) -> f32 {
+    let max = MAX_HZ.min(sample_rate() as f32 * 0.45);
+    hz.max(MIN_HZ).min(max)
+}
+
+fn clamp_db(db: f32) -> f32 {
+    db.max(-MAX_EQ_DB).min(MAX_EQ_DB)
+}
+
+pub mod default {
+    use super::{Band, Filter, NUM_PARAMETRIC_BANDS};
+
+    pub const HIGH_PASS_HZ: f32 = 80.0;
+    pub const LOW_PASS_HZ: f32 = 12_000.0;
+    pub const BAND_Q: f32 = ::std::f32::consts::FRAC_1_SQRT_2;
+    pub const PARAMETRIC_HZ: [f32; NUM_PARAMETRIC_BANDS] = [100.0, 500.0, 2_000.0, 8_000.0];
+
+    pub fn high_pass() -> Filter {
+        Filter { enabled: false, hz: HIGH_PASS_HZ }
+    }
+
+    pub fn low_pass() -> Filter {
+        Filter { enabled: false, hz: LOW_PASS_HZ }
+    }
+
+    pub fn band_q() -> f32 {
+        BAND_Q
+    }
+
+    pub fn parametric_bands() -> [Band; NUM_PARAMETRIC_BANDS] {
+        let mut bands = [Band { hz: 0.0, q: BAND_Q, db: 0.0 }; NUM_PARAMETRIC_BANDS];
+        for (band, &hz) in bands.iter_mut().zip(PARAMETRIC_HZ.iter()) {
+            band.hz = hz;
+        }
+        bands
+    }
+}
+
+#[test]
+fn test_low_pass_attenuates_nyquist() {
+    let mut effects = Effects::default();
+    effects.low_pass.enabled = true;
+    effects.low_pass.hz = 1_000.0;
+    let mut chain = Chain::new(effects, 1);
+    let mut samples: Vec<f32> = (0..4096).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+    chain.process(&mut samples);
+    let peak = samples[2048..].iter().fold(0.0f32, |max, s| max.max(s.abs()));
+    assert!(peak < 0.01);
+
+    // DC should pass through a low pass filter untouched.
+    let mut chain = Chain::new(effects, 1);
+    let mut samples = vec![1.0; 4096];
+    chain.process(&mut samples);
+    assert!((samples[4095] - 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_parametric_eq_response() {
+    let mut eq = ParametricEq::default();
+    assert_eq!(eq.response_db(1_000.0), 0.0);
+    eq.bands[1].hz = 1_000.0;
+    eq.bands[1].db = 6.0;
+    assert!((eq.response_db(1_000.0) - 6.0).abs() < 0.01);
+    assert!(eq.response_db(20.0).abs() < 0.1);
+}
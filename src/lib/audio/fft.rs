@@ -135,7 +135,7 @@ pub fn lmh(freq_amps_2: &[f32]) -> (f32, f32, f32) {
 
 /// Find the maximum frequency bound of a linear fourier transform bin in hz.
 pub fn linear_bin_max_hz(bin_i: usize) -> f32 {
-    (bin_i + 1) as f32 * super::detector::FFT_BIN_STEP_HZ as f32
+    (bin_i + 1) as f32 * super::detector::fft_bin_step_hz() as f32
 }
 
 /// Find the maximum frequency bound of a logarithmic fourier transform bin in hz.
@@ -161,14 +161,14 @@ pub fn mel_bins(in_freq_amps_2: &[f32], out_freq_amps_2: &mut [f32]) {
         .iter()
         .enumerate()
         .map(|(i, &amp_2)| {
-            let freq_max = (i + 1) as f32 * super::detector::FFT_BIN_STEP_HZ as f32;
+            let freq_max = (i + 1) as f32 * super::detector::fft_bin_step_hz() as f32;
             (freq_max, amp_2)
         })
         .peekable();
 
     // Fill the output bins with the peek of each input bin within range.
     'out_bins: for (out_i, out_bin) in out_freq_amps_2.iter_mut().enumerate() {
-        let out_freq_max = mel_bin_max_hz(out_i, n_out_bins, super::SAMPLE_RATE as f32);
+        let out_freq_max = mel_bin_max_hz(out_i, n_out_bins, super::sample_rate() as f32);
         while let Some(&(in_freq_max, amp_2)) = in_bins.peek() {
             if in_freq_max < out_freq_max {
                 *out_bin = out_bin.max(amp_2);
@@ -0,0 +1,75 @@
+//! A guard against corrupt samples reaching the speakers.
+//!
+//! A single NaN or infinite sample, or a runaway gain such as one derived from an invalid DBAP
+//! input position, is enough to drive every speaker to full scale. Each sound's samples and the
+//! final output are checked on every buffer and any faulty buffer is replaced with silence.
+
+use std::fmt;
+
+/// The greatest magnitude considered a valid sample, roughly +24 dBFS.
+///
+/// Sounds may legitimately exceed full scale before the master volume and limiter, but nothing
+/// played back at a sane volume should come anywhere near this.
+pub const MAX_SAMPLE: f32 = 16.0;
+
+/// The kind of corruption detected within a buffer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// The buffer contained a NaN or infinite sample.
+    NonFinite,
+    /// The buffer contained a sample beyond `MAX_SAMPLE`.
+    Runaway,
+}
+
+/// Check the given samples for corruption.
+///
+/// Returns the first fault found, preferring `NonFinite` as it is the more severe.
+pub fn check(samples: &[f32]) -> Option<Fault> {
+    let mut fault = None;
+    for &s in samples {
+        if !s.is_finite() {
+            return Some(Fault::NonFinite);
+        }
+        if s.abs() > MAX_SAMPLE {
+            fault = Some(Fault::Runaway);
+        }
+    }
+    fault
+}
+
+/// Check the given samples and silence them entirely if any are corrupt.
+pub fn silence_faults(samples: &mut [f32]) -> Option<Fault> {
+    let fault = check(samples);
+    if fault.is_some() {
+        samples.iter_mut().for_each(|s| *s = 0.0);
+    }
+    fault
+}
+
+/// Check a single gain, e.g. one produced by DBAP, before it is applied.
+pub fn check_gain(gain: f32) -> Option<Fault> {
+    check(&[gain])
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            Fault::NonFinite => "NaN or infinite samples",
+            Fault::Runaway => "runaway gain",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[test]
+fn test_silence_faults() {
+    let mut samples = [0.5, -1.0, 2.0];
+    assert_eq!(silence_faults(&mut samples), None);
+    assert_eq!(samples, [0.5, -1.0, 2.0]);
+    let mut samples = [0.5, 100.0, ::std::f32::NAN];
+    assert_eq!(silence_faults(&mut samples), Some(Fault::NonFinite));
+    assert_eq!(samples, [0.0; 3]);
+    let mut samples = [0.5, -100.0];
+    assert_eq!(silence_faults(&mut samples), Some(Fault::Runaway));
+    assert_eq!(samples, [0.0; 2]);
+}
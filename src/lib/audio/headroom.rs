@@ -0,0 +1,159 @@
+//! Headroom-aware auto-gain across simultaneous sounds.
+//!
+//! The soundscape balances sources against one another, but every sound that overlaps another
+//! within an installation adds to its level. Dense passages become much louder than sparse ones.
+//! While enabled, each sound is attenuated according to the number of sounds playing within the
+//! busiest of its installations so that the overall level stays roughly constant.
+
+use audio::sound::Installations;
+use fxhash::FxHashMap;
+use installation;
+
+/// The rate at which the gain of each sound moves towards its target, in linear gain per second.
+///
+/// Smooths the steps in gain that occur as sounds begin and end.
+pub const MAX_GAIN_SLEW_PER_SEC: f32 = 2.0;
+
+/// The greatest reduction that may be selected via the GUI.
+pub const MAX_REDUCTION_DB: f32 = 24.0;
+
+/// The function relating the number of simultaneous sounds to the gain of each.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Law {
+    /// `1 / sqrt(n)`, keeping the summed power of uncorrelated sounds constant.
+    EqualPower,
+    /// `1 / n`, keeping the summed amplitude constant.
+    EqualAmplitude,
+}
+
+/// The master parameters of the headroom auto-gain.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Params {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub law: Law,
+    /// The greatest reduction applied to any sound in decibels.
+    #[serde(default = "default::max_reduction_db")]
+    pub max_reduction_db: f32,
+}
+
+/// The number of sounds playing within each installation, gathered at the start of each buffer.
+///
+/// Retained by the audio output thread so that no allocation is required once warmed up.
+#[derive(Debug, Default)]
+pub struct Counts {
+    installations: FxHashMap<installation::Id, usize>,
+    // Sounds that play within all installations.
+    all: usize,
+}
+
+impl Law {
+    /// All laws in the order in which they are displayed.
+    pub const ALL: [Law; 2] = [Law::EqualPower, Law::EqualAmplitude];
+
+    /// The name displayed within the GUI.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Law::EqualPower => "Equal Power",
+            Law::EqualAmplitude => "Equal Amplitude",
+        }
+    }
+}
+
+impl Default for Law {
+    fn default() -> Self {
+        Law::EqualPower
+    }
+}
+
+impl Params {
+    /// The gain applied to each of `count` sounds playing within the same installation.
+    pub fn gain(&self, count: usize) -> f32 {
+        if !self.enabled || count <= 1 {
+            return 1.0;
+        }
+        let n = count as f32;
+        let gain = match self.law {
+            Law::EqualPower => 1.0 / n.sqrt(),
+            Law::EqualAmplitude => 1.0 / n,
+        };
+        gain.max(10f32.powf(-self.max_reduction_db.max(0.0) / 20.0))
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            enabled: false,
+            law: Default::default(),
+            max_reduction_db: default::max_reduction_db(),
+        }
+    }
+}
+
+impl Counts {
+    /// Forget all counts, e.g. at the start of a buffer.
+    pub fn clear(&mut self) {
+        self.installations.values_mut().for_each(|count| *count = 0);
+        self.all = 0;
+    }
+
+    /// Count a sound playing within the given installations.
+    pub fn add(&mut self, installations: &Installations) {
+        match *installations {
+            Installations::All => self.all += 1,
+            Installations::Set(ref set) => {
+                for &id in set {
+                    *self.installations.entry(id).or_insert(0) += 1;
+                }
+            },
+        }
+    }
+
+    /// The number of sounds playing within the busiest of the given installations.
+    pub fn busiest(&self, installations: &Installations) -> usize {
+        let busiest = match *installations {
+            Installations::All => self.installations.values().cloned().max(),
+            Installations::Set(ref set) => {
+                set.iter().filter_map(|id| self.installations.get(id)).cloned().max()
+            },
+        };
+        busiest.unwrap_or(0) + self.all
+    }
+
+    /// Forget the given installation, e.g. once it is removed.
+    pub fn remove(&mut self, id: &installation::Id) {
+        self.installations.remove(id);
+    }
+}
+
+pub mod default {
+    pub fn max_reduction_db() -> f32 {
+        12.0
+    }
+}
+
+#[test]
+fn test_headroom_gain() {
+    let mut params = Params { enabled: true, ..Default::default() };
+    assert_eq!(params.gain(1), 1.0);
+    assert!((params.gain(4) - 0.5).abs() < 1e-6);
+    params.law = Law::EqualAmplitude;
+    assert!((params.gain(4) - 0.25).abs() < 1e-6);
+    // -12 dB is the floor by default.
+    assert!((params.gain(1_000) - 10f32.powf(-12.0 / 20.0)).abs() < 1e-6);
+    params.enabled = false;
+    assert_eq!(params.gain(4), 1.0);
+
+    let a = installation::Id(0);
+    let b = installation::Id(1);
+    let set = |ids: &[installation::Id]| Installations::Set(ids.iter().cloned().collect());
+    let mut counts = Counts::default();
+    counts.add(&set(&[a]));
+    counts.add(&set(&[a, b]));
+    counts.add(&Installations::All);
+    assert_eq!(counts.busiest(&set(&[b])), 2);
+    assert_eq!(counts.busiest(&set(&[a, b])), 3);
+    assert_eq!(counts.busiest(&Installations::All), 3);
+}
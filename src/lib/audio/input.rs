@@ -1,23 +1,55 @@
 //! The capture function implementation for the audio server's input stream.
 //!
 //! The input stream has a number of `Source`s that read from one or more of the stream's channels.
+//!
+//! Realtime sources may capture from input devices other than the default. A stream is opened for
+//! each of these devices on demand and all streams are managed via the `Streams` handle.
 
-use audio::source;
+use audio::{self, calibration, capture, source};
 use fxhash::FxHashMap;
 use nannou_audio::Buffer;
 use std::cmp;
-use std::sync::Arc;
+use std::convert;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{self, AtomicBool};
 
 /// Simplified type alias for the nannou audio input stream used by the audio server.
 pub type Stream = nannou_audio::Stream<Model>;
 
+/// A handle to the input streams of all devices from which realtime sources capture.
+///
+/// All streams may be closed and rebuilt in place, e.g. for a new sample rate, without
+/// invalidating any of the handles.
+///
+/// The stream of the default input device is always open, while streams for other devices are
+/// opened when a realtime source first names them. Updates are broadcast to all open streams,
+/// however each source is only stored within the model of its own device's stream so that only
+/// that stream captures samples for it.
+#[derive(Clone)]
+pub struct Streams {
+    // The stream of the default device, or `None` while the streams are being rebuilt.
+    default: Arc<Mutex<Option<DeviceStream>>>,
+    devices: Arc<Mutex<Vec<DeviceStream>>>,
+}
+
+// An open input stream along with the name and channel count of its device.
+#[derive(Clone)]
+struct DeviceStream {
+    name: String,
+    channels: usize,
+    stream: Stream,
+}
+
 /// The state stored on each device's input audio stream.
 pub struct Model {
     // All sources that currently exist.
     pub sources: FxHashMap<source::Id, source::Realtime>,
     // The currently active sounds using the realtime source with the given source ID.
     pub active_sounds: FxHashMap<source::Id, Vec<ActiveSound>>,
+    // The realtime sources currently being recorded to disk.
+    pub captures: FxHashMap<source::Id, capture::Tx>,
+    // The measurement microphone recorded during speaker calibration, if any.
+    pub calibration: Option<calibration::Tx>,
 }
 
 /// The duration of an active sound's playback.
@@ -29,9 +61,13 @@ pub enum Duration {
 
 /// A sound with a realtime input source that is currently being played.
 ///
+/// The sound is cloned to each open stream, though only the stream owning its source captures for
+/// it.
+///
 /// Every `input::ActiveSound` has an associated `output::ActiveSound`. Once the
 /// `input::ActiveSound` has no more frames, the `output::ActiveSound`'s signal iterator will yield
 /// `None`.
+#[derive(Clone)]
 pub struct ActiveSound {
     /// The number of frames left to play of this source before it should end.
     pub duration: Duration,
@@ -54,9 +90,13 @@ impl Model {
     pub fn new() -> Self {
         let sources = Default::default();
         let active_sounds = Default::default();
+        let captures = Default::default();
+        let calibration = None;
         Model {
             sources,
             active_sounds,
+            captures,
+            calibration,
         }
     }
 
@@ -66,6 +106,166 @@ impl Model {
     pub fn clear_project_specific_data(&mut self) {
         self.sources.clear();
         self.active_sounds.clear();
+        self.captures.clear();
+        self.calibration = None;
+    }
+}
+
+impl Streams {
+    /// Wrap the stream of the default input device.
+    pub fn new(default: Stream, default_device_name: String, default_channels: usize) -> Self {
+        let streams = Streams { default: Default::default(), devices: Default::default() };
+        streams.replace_default(default, default_device_name, default_channels);
+        streams
+    }
+
+    /// Send the given update to the models of all open input streams.
+    ///
+    /// Updates sent while the streams are being rebuilt are discarded, as the new models are
+    /// populated from scratch.
+    pub fn send<F>(&self, update: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut Model) + Clone + Send + 'static,
+    {
+        let devices = self.devices.lock().expect("failed to lock input device streams");
+        for device in devices.iter() {
+            device
+                .stream
+                .send(update.clone())
+                .map_err(|err| format!("{:?}", err))?;
+        }
+        self.send_default(update)
+    }
+
+    /// Send the given update to the model of the default input device's stream only.
+    pub fn send_default<F>(&self, update: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut Model) + Send + 'static,
+    {
+        let default = self.default.lock().expect("failed to lock default input stream");
+        match *default {
+            Some(ref default) => default.stream.send(update).map_err(|err| format!("{:?}", err)),
+            None => Ok(()),
+        }
+    }
+
+    /// The number of channels available on the stream for the given device, if it is open.
+    ///
+    /// An empty `device` name refers to the default input device.
+    pub fn channels(&self, device: &str) -> Option<usize> {
+        if device.is_empty() {
+            let default = self.default.lock().expect("failed to lock default input stream");
+            return default.as_ref().map(|d| d.channels);
+        }
+        let devices = self.devices.lock().expect("failed to lock input device streams");
+        devices.iter().find(|d| d.name.contains(device)).map(|d| d.channels)
+    }
+
+    /// Insert the given realtime source into the model of its device's stream, opening the stream
+    /// if necessary, and remove it from all other streams.
+    ///
+    /// If the source's device cannot be opened, the source will not capture until it is inserted
+    /// again.
+    pub fn insert_source(&self, id: source::Id, realtime: source::Realtime) -> Result<(), String> {
+        let owner = match self.open(&realtime.device) {
+            Ok(name) => Some(name),
+            Err(err) => {
+                eprintln!("failed to open input device \"{}\": {}", realtime.device, err);
+                None
+            },
+        };
+        let devices = self.devices.lock().expect("failed to lock input device streams");
+        let default = self.default.lock().expect("failed to lock default input stream");
+        for device in devices.iter().chain(default.as_ref()) {
+            let is_owner = owner.as_ref() == Some(&device.name);
+            let realtime = realtime.clone();
+            device
+                .stream
+                .send(move |audio| {
+                    if is_owner {
+                        audio.sources.insert(id, realtime);
+                    } else {
+                        audio.sources.remove(&id);
+                    }
+                })
+                .map_err(|err| format!("{:?}", err))?;
+        }
+        Ok(())
+    }
+
+    /// Start playing the stream of the default input device.
+    ///
+    /// The streams of other devices are played as they are opened.
+    pub fn play(&self) -> Result<(), String> {
+        let default = self.default.lock().expect("failed to lock default input stream");
+        match *default {
+            Some(ref default) => default.stream.play().map_err(|err| format!("{}", err)),
+            None => Ok(()),
+        }
+    }
+
+    /// Close the streams of all devices so that they may be rebuilt.
+    ///
+    /// Streams of devices other than the default are reopened on demand once their sources are
+    /// inserted again.
+    pub fn close(&self) {
+        let mut devices = self.devices.lock().expect("failed to lock input device streams");
+        let mut default = self.default.lock().expect("failed to lock default input stream");
+        devices.clear();
+        *default = None;
+    }
+
+    /// Replace the stream of the default input device, e.g. once rebuilt after `close`.
+    pub fn replace_default(&self, stream: Stream, device_name: String, channels: usize) {
+        let mut default = self.default.lock().expect("failed to lock default input stream");
+        *default = Some(DeviceStream { name: device_name, channels, stream });
+    }
+
+    // Open the stream for the first device whose name contains the given name if it is not
+    // already open, returning the full name of the device.
+    fn open(&self, device: &str) -> Result<String, String> {
+        let default_name = self
+            .default
+            .lock()
+            .expect("failed to lock default input stream")
+            .as_ref()
+            .map(|d| d.name.clone())
+            .ok_or_else(|| "the input streams are being rebuilt".to_string())?;
+        if device.is_empty() {
+            return Ok(default_name);
+        }
+        let mut devices = self.devices.lock().expect("failed to lock input device streams");
+        if let Some(d) = devices.iter().find(|d| d.name.contains(device)) {
+            return Ok(d.name.clone());
+        }
+
+        let host = audio::host();
+        let input_device = host
+            .input_devices()
+            .ok()
+            .into_iter()
+            .flat_map(convert::identity)
+            .find(|d| d.name().map(|n| n.contains(device)).unwrap_or(false))
+            .ok_or_else(|| "no matching input device found".to_string())?;
+        let name = input_device.name().map_err(|err| format!("{}", err))?;
+        if name == default_name {
+            return Ok(name);
+        }
+
+        let channels = cmp::min(input_device.max_supported_input_channels(), audio::MAX_CHANNELS);
+        let stream = host
+            .new_input_stream(Model::new())
+            .capture(capture)
+            .sample_rate(audio::sample_rate() as u32)
+            .frames_per_buffer(audio::frames_per_buffer())
+            .channels(channels)
+            .device(input_device)
+            .build()
+            .map_err(|err| format!("{:?}", err))?;
+        stream.play().map_err(|err| format!("{}", err))?;
+        println!("Opened Input Device: {:?}", name);
+        devices.push(DeviceStream { name: name.clone(), channels, stream });
+        Ok(name)
     }
 }
 
@@ -74,6 +274,8 @@ pub fn capture(model: &mut Model, buffer: &Buffer) {
     let Model {
         ref sources,
         ref mut active_sounds,
+        ref captures,
+        ref calibration,
     } = *model;
 
     // Remove any sounds that have been closed.
@@ -133,6 +335,36 @@ pub fn capture(model: &mut Model, buffer: &Buffer) {
         }
     }
 
+    // Copy the channels of any sources being captured for writing to disk.
+    for (source_id, capture) in captures.iter() {
+        // Only the stream of the source's device captures it.
+        if !sources.contains_key(source_id) {
+            continue;
+        }
+
+        // Retrieve an empty buffer from the writer thread.
+        let capacity = buffer.len_frames() * capture.channels.len();
+        let mut samples = capture.buffers.pop_empty(capacity);
+
+        // Channels beyond those of the device are written as silence.
+        for frame in buffer.frames() {
+            for ch in capture.channels.clone() {
+                samples.push(frame.get(ch).cloned().unwrap_or(0.0));
+            }
+        }
+
+        capture.buffers.filled.push(samples);
+    }
+
+    // Copy the channel of the measurement microphone during speaker calibration.
+    if let Some(ref calibration) = *calibration {
+        let mut samples = calibration.buffers.pop_empty(buffer.len_frames());
+        for frame in buffer.frames() {
+            samples.push(frame.get(calibration.channel).cloned().unwrap_or(0.0));
+        }
+        calibration.buffers.filled.push(samples);
+    }
+
     // Subtract from the remaining frames from each active sound.
     //
     // Remove sounds that have no more remaining samples to capture.
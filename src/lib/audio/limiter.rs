@@ -0,0 +1,119 @@
+//! A brickwall limiter applied to the summed output.
+//!
+//! The limiter reacts instantly to any frame whose peak across all channels exceeds the
+//! threshold and then releases smoothly. Gain is linked across channels so that the spatial
+//! image does not shift while the limiter is engaged. This ensures the amplifiers are never
+//! clipped, even when many sounds coincide within an unattended installation.
+
+use audio::sample_rate;
+use nannou_audio::Buffer;
+use time_calc::Ms;
+
+/// The lowest threshold that may be set in dBFS.
+pub const MIN_THRESHOLD_DB: f32 = -24.0;
+
+/// The user-facing parameters of the limiter.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Params {
+    /// Whether or not the limiter is applied.
+    #[serde(default = "default::enabled")]
+    pub enabled: bool,
+    /// The level in dBFS above which no output sample may rise.
+    #[serde(default = "default::threshold_db")]
+    pub threshold_db: f32,
+    /// The time taken for the gain to recover after the level falls below the threshold.
+    #[serde(default = "default::release")]
+    pub release: Ms,
+}
+
+/// The state of the limiter on the audio output thread.
+pub struct Limiter {
+    params: Params,
+    // The current linear gain applied to the output.
+    gain: f32,
+    // The per-frame coefficient used to recover towards unity gain.
+    release_coeff: f32,
+}
+
+impl Limiter {
+    /// Create a new limiter with the given parameters.
+    pub fn new(params: Params) -> Self {
+        let release_coeff = release_coeff(params.release);
+        Limiter { params, gain: 1.0, release_coeff }
+    }
+
+    /// Update the parameters of the limiter.
+    pub fn set_params(&mut self, params: Params) {
+        self.release_coeff = release_coeff(params.release);
+        self.params = params;
+    }
+
+    /// Limit the given buffer in place.
+    ///
+    /// Returns the greatest gain reduction applied within the buffer in decibels.
+    pub fn process(&mut self, buffer: &mut Buffer) -> f32 {
+        if !self.params.enabled {
+            self.gain = 1.0;
+            return 0.0;
+        }
+        let threshold = db_to_amp(self.params.threshold_db);
+        let mut min_gain: f32 = 1.0;
+        for frame in buffer.frames_mut() {
+            let peak = frame.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+            let target = if peak > threshold { threshold / peak } else { 1.0 };
+            self.gain = if target < self.gain {
+                target
+            } else {
+                target + (self.gain - target) * self.release_coeff
+            };
+            for sample in frame.iter_mut() {
+                *sample *= self.gain;
+            }
+            min_gain = min_gain.min(self.gain);
+        }
+        -amp_to_db(min_gain)
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            enabled: default::enabled(),
+            threshold_db: default::threshold_db(),
+            release: default::release(),
+        }
+    }
+}
+
+// The per-frame coefficient for an exponential release over the given duration.
+fn release_coeff(release: Ms) -> f32 {
+    let frames = release.ms() * sample_rate() / 1_000.0;
+    if frames <= 0.0 {
+        return 0.0;
+    }
+    (-1.0 / frames).exp() as f32
+}
+
+fn db_to_amp(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+fn amp_to_db(amp: f32) -> f32 {
+    20.0 * amp.max(::std::f32::EPSILON).log10()
+}
+
+pub mod default {
+    use time_calc::Ms;
+
+    pub fn enabled() -> bool {
+        true
+    }
+
+    pub fn threshold_db() -> f32 {
+        -0.3
+    }
+
+    pub fn release() -> Ms {
+        Ms(150.0)
+    }
+}
@@ -0,0 +1,67 @@
+//! Measurement of the DSP load of the audio output thread.
+//!
+//! The load is the time spent rendering a buffer relative to the duration of the buffer, where a
+//! load of `1.0` leaves no time to spare. The audio host does not report under-runs directly, so
+//! they are inferred instead. An under-run is counted whenever rendering a buffer takes longer
+//! than the buffer lasts, or when the device requests a buffer well after the previous one should
+//! have run out.
+
+use audio;
+use std::time::Instant;
+use utils;
+
+/// The gap between the start of consecutive callbacks, relative to the duration of a buffer,
+/// beyond which the device is assumed to have run dry.
+const XRUN_GAP: f64 = 1.5;
+
+/// The timing of a single callback.
+#[derive(Copy, Clone, Debug)]
+pub struct Measurement {
+    /// The time spent rendering relative to the duration of the buffer.
+    pub load: f32,
+    /// The time since the previous callback began relative to the duration of the buffer.
+    pub gap: Option<f32>,
+    /// Whether or not an under-run occurred.
+    pub xrun: bool,
+}
+
+/// Tracks the timing of the output stream's callbacks.
+#[derive(Debug, Default)]
+pub struct Monitor {
+    // The moment at which the previous callback began.
+    last_start: Option<Instant>,
+}
+
+impl Monitor {
+    /// Measure a callback that began at `start` and rendered the given number of frames.
+    pub fn measure(&mut self, start: Instant, frames: usize) -> Measurement {
+        let buffer_secs = frames as f64 / audio::sample_rate();
+        let elapsed_secs = utils::duration_to_secs(&start.elapsed());
+        let gap_secs = self
+            .last_start
+            .replace(start)
+            .map(|last| utils::duration_to_secs(&start.duration_since(last)));
+        let relative = |secs: f64| match buffer_secs > 0.0 {
+            true => secs / buffer_secs,
+            false => 0.0,
+        };
+        let load = relative(elapsed_secs) as f32;
+        let gap = gap_secs.map(|gap| relative(gap) as f32);
+        let xrun = is_xrun(buffer_secs, elapsed_secs, gap_secs);
+        Measurement { load, gap, xrun }
+    }
+}
+
+// Whether or not the device likely ran dry during or before the callback.
+fn is_xrun(buffer_secs: f64, elapsed_secs: f64, gap_secs: Option<f64>) -> bool {
+    let late = gap_secs.map(|gap| gap > buffer_secs * XRUN_GAP).unwrap_or(false);
+    elapsed_secs > buffer_secs || late
+}
+
+#[test]
+fn test_is_xrun() {
+    assert!(!is_xrun(0.02, 0.005, None));
+    assert!(!is_xrun(0.02, 0.005, Some(0.021)));
+    assert!(is_xrun(0.02, 0.025, Some(0.02)));
+    assert!(is_xrun(0.02, 0.005, Some(0.05)));
+}
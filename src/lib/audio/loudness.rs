@@ -0,0 +1,173 @@
+//! Loudness and true-peak analysis of the final output, following ITU-R BS.1770.
+//!
+//! On the audio output thread each channel is K-weighted and the mean square of every channel
+//! is summed, while the true peak is estimated by oversampling each channel four times. The
+//! gating and integration over time that produce momentary, short-term and integrated loudness
+//! are left to the GUI, see `gui::loudness`.
+
+use audio::{sample_rate, MAX_CHANNELS};
+use nannou_audio::Buffer;
+use std::f64::consts::PI;
+
+/// The factor by which each channel is oversampled when estimating the true peak.
+pub const OVERSAMPLING: usize = 4;
+
+// The number of taps of each phase of the oversampling filter.
+const TAPS_PER_PHASE: usize = 12;
+
+/// The loudness in LUFS of the given sum of K-weighted channel mean squares.
+pub fn lufs(power: f64) -> f32 {
+    (-0.691 + 10.0 * power.max(1e-20).log10()) as f32
+}
+
+/// The K-weighting and true-peak filters of every output channel.
+pub struct Analyser {
+    shelf: Biquad,
+    high_pass: Biquad,
+    // The phases of the oversampling interpolation filter.
+    phases: [[f32; TAPS_PER_PHASE]; OVERSAMPLING],
+    channels: Vec<Channel>,
+}
+
+/// The analysis of a single buffer of the output.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Analysis {
+    /// The sum of the mean square of each K-weighted channel over the buffer.
+    pub power: f32,
+    /// The greatest amplitude across all channels including between samples.
+    pub true_peak: f32,
+}
+
+// The coefficients of a biquad filter.
+#[derive(Copy, Clone, Debug)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+// The filter state of a single channel.
+#[derive(Clone, Default)]
+struct Channel {
+    shelf: [f64; 2],
+    high_pass: [f64; 2],
+    history: [f32; TAPS_PER_PHASE],
+}
+
+impl Biquad {
+    // Process a single sample in transposed direct form II.
+    fn process(&self, state: &mut [f64; 2], x: f64) -> f64 {
+        let y = self.b[0] * x + state[0];
+        state[0] = self.b[1] * x - self.a[0] * y + state[1];
+        state[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+impl Analyser {
+    /// Design the filters for the current sample rate.
+    ///
+    /// The state of `MAX_CHANNELS` channels is allocated up front to avoid allocating on the
+    /// audio thread.
+    pub fn new() -> Self {
+        let fs = sample_rate();
+
+        // The high shelf modelling the acoustic effect of the head.
+        let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (PI * f0 / fs).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad {
+            b: [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        };
+
+        // The high-pass modelling the ear's insensitivity to low frequencies.
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (PI * f0 / fs).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad {
+            b: [1.0, -2.0, 1.0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        };
+
+        // A Hann windowed sinc interpolating between samples, split into phases of unity gain.
+        let len = TAPS_PER_PHASE * OVERSAMPLING;
+        let centre = (len - 1) as f64 / 2.0;
+        let mut phases = [[0.0; TAPS_PER_PHASE]; OVERSAMPLING];
+        for (p, phase) in phases.iter_mut().enumerate() {
+            for (j, tap) in phase.iter_mut().enumerate() {
+                let n = j * OVERSAMPLING + p;
+                let t = (n as f64 - centre) / OVERSAMPLING as f64;
+                let sinc = if t == 0.0 { 1.0 } else { (PI * t).sin() / (PI * t) };
+                let window = 0.5 - 0.5 * (2.0 * PI * (n as f64 + 0.5) / len as f64).cos();
+                *tap = (sinc * window) as f32;
+            }
+            let sum: f32 = phase.iter().sum();
+            phase.iter_mut().for_each(|tap| *tap /= sum);
+        }
+
+        let channels = Vec::with_capacity(MAX_CHANNELS);
+        Analyser { shelf, high_pass, phases, channels }
+    }
+
+    /// Analyse the given buffer of the final output.
+    pub fn process(&mut self, buffer: &Buffer) -> Analysis {
+        self.analyse(&buffer[..], buffer.channels())
+    }
+
+    // Analyse the given interleaved samples.
+    fn analyse(&mut self, samples: &[f32], n_channels: usize) -> Analysis {
+        if n_channels == 0 || samples.len() < n_channels {
+            return Analysis::default();
+        }
+        let n_frames = samples.len() / n_channels;
+        if self.channels.len() != n_channels {
+            self.channels.resize(n_channels, Channel::default());
+        }
+
+        let mut sum_of_squares = 0.0;
+        let mut true_peak = 0.0f32;
+        for frame in samples.chunks(n_channels) {
+            for (channel, &sample) in self.channels.iter_mut().zip(frame) {
+                let shelved = self.shelf.process(&mut channel.shelf, sample as f64);
+                let weighted = self.high_pass.process(&mut channel.high_pass, shelved);
+                sum_of_squares += weighted * weighted;
+
+                // Interpolate between the previous samples at each phase.
+                let history = &mut channel.history;
+                history.rotate_right(1);
+                history[0] = sample;
+                true_peak = true_peak.max(sample.abs());
+                for phase in &self.phases {
+                    let taps = phase.iter().zip(&history[..]);
+                    let interpolated: f32 = taps.map(|(h, x)| h * x).sum();
+                    true_peak = true_peak.max(interpolated.abs());
+                }
+            }
+        }
+
+        let power = (sum_of_squares / n_frames as f64) as f32;
+        Analysis { power, true_peak }
+    }
+}
+
+#[test]
+fn test_analyser() {
+    // A full scale 1 kHz sine through one channel reads roughly -3 LUFS and 0 dBTP.
+    let mut analyser = Analyser::new();
+    let frames = sample_rate() as usize;
+    let samples: Vec<f32> = (0..frames)
+        .flat_map(|i| {
+            let phase = 2.0 * PI * 1_000.0 * i as f64 / sample_rate();
+            vec![phase.sin() as f32, 0.0]
+        })
+        .collect();
+    let analysis = analyser.analyse(&samples, 2);
+    assert!((lufs(analysis.power as f64) + 3.01).abs() < 0.2);
+    assert!(analysis.true_peak > 0.99 && analysis.true_peak < 1.02);
+}
@@ -1,21 +1,42 @@
 use metres::Metres;
 use nannou_audio::{Device, Host};
+use std::sync::atomic::{self, AtomicUsize};
 use time_calc::Ms;
 
-pub use self::detector::{EnvDetector, Fft, FftDetector, FFT_BIN_STEP_HZ, FFT_WINDOW_LEN};
+pub use self::detector::{fft_bin_step_hz, EnvDetector, Fft, FftDetector, FFT_WINDOW_LEN};
 pub use self::sound::Sound;
 pub use self::source::Source;
 pub use self::speaker::Speaker;
 
+pub mod aggregate;
+pub mod ambisonics;
+pub mod bass;
+pub mod calibration;
+pub mod binaural;
+pub mod blackout;
+pub mod capture;
 pub mod dbap;
 pub mod detection;
 pub mod detector;
+pub mod dither;
+pub mod distance;
+pub mod ducking;
+pub mod effect;
 pub mod fft;
+pub mod guard;
+pub mod headroom;
 pub mod input;
+pub mod limiter;
+pub mod load;
+pub mod loudness;
 pub mod output;
+pub mod reverb;
 pub mod sound;
 pub mod source;
 pub mod speaker;
+pub mod telemetry;
+pub mod test_signal;
+pub mod walk_test;
 
 /// The maximum number of audio channels.
 #[cfg(not(feature = "test_with_stereo"))]
@@ -29,11 +50,47 @@ pub const MAX_CHANNELS: usize = 2;
 /// The number is arbitrary - feel free to increase/decrease this as necessary.
 pub const MAX_SOUNDS: usize = 1024;
 
-/// The desired sample rate of the output stream.
-pub const SAMPLE_RATE: f64 = 48_000.0;
+/// The default sample rate of the audio streams.
+pub const DEFAULT_SAMPLE_RATE: u32 = 48_000;
 
-/// The desired number of frames requested at a time.
-pub const FRAMES_PER_BUFFER: usize = 1024;
+/// The default number of frames requested at a time.
+pub const DEFAULT_FRAMES_PER_BUFFER: usize = 1024;
+
+/// The sample rates that may be selected within the GUI.
+pub const SAMPLE_RATES: &'static [u32] = &[44_100, 48_000, 88_200, 96_000];
+
+/// The buffer sizes that may be selected within the GUI.
+pub const FRAMES_PER_BUFFER_OPTIONS: &'static [usize] = &[64, 128, 256, 512, 1024, 2048, 4096];
+
+// The configuration with which the audio streams were built, set once at startup.
+static SAMPLE_RATE: AtomicUsize = AtomicUsize::new(DEFAULT_SAMPLE_RATE as usize);
+static FRAMES_PER_BUFFER: AtomicUsize = AtomicUsize::new(DEFAULT_FRAMES_PER_BUFFER);
+
+/// The sample rate and buffer size with which the audio streams are built.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct StreamConfig {
+    #[serde(default = "default::sample_rate")]
+    pub sample_rate: u32,
+    #[serde(default = "default::frames_per_buffer")]
+    pub frames_per_buffer: usize,
+    /// The word length to which the output is quantised, e.g. for 16-bit converters.
+    #[serde(default)]
+    pub output_word_length: dither::WordLength,
+    /// Whether TPDF dither is applied when quantising the output.
+    #[serde(default = "default::dither")]
+    pub dither: bool,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        StreamConfig {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            frames_per_buffer: DEFAULT_FRAMES_PER_BUFFER,
+            output_word_length: Default::default(),
+            dither: default::dither(),
+        }
+    }
+}
 
 /// The initial, default master volume.
 pub const DEFAULT_MASTER_VOLUME: f32 = 0.5;
@@ -48,11 +105,47 @@ pub const DEFAULT_DBAP_ROLLOFF_DB: f64 = 4.0;
 /// The "blurring" amount applied to the distance function used for calculating DBAP.
 pub const DISTANCE_BLUR: f64 = 0.01;
 
+/// The greatest height above the floor at which a speaker or source may be placed.
+pub const MAX_HEIGHT: Metres = Metres(20.0);
+
 /// The initial, default proximity limit.
 pub const DEFAULT_PROXIMITY_LIMIT: Metres = Metres(7.0);
 /// Proximity limit squared for efficientcy efficiency.
 pub const DEFAULT_PROXIMITY_LIMIT_2: Metres = Metres(DEFAULT_PROXIMITY_LIMIT.0 * DEFAULT_PROXIMITY_LIMIT.0);
 
+/// The sample rate of the audio streams.
+pub fn sample_rate() -> f64 {
+    SAMPLE_RATE.load(atomic::Ordering::Relaxed) as f64
+}
+
+/// The number of frames requested at a time by the audio streams.
+pub fn frames_per_buffer() -> usize {
+    FRAMES_PER_BUFFER.load(atomic::Ordering::Relaxed)
+}
+
+/// Set the sample rate and buffer size with which the audio streams are built.
+///
+/// Must be called before building the streams or constructing anything that depends on the sample
+/// rate. Invalid values are replaced with the defaults.
+pub fn set_stream_config(config: StreamConfig) {
+    let sample_rate = match config.sample_rate {
+        0 => {
+            eprintln!("invalid sample rate 0, using {}", DEFAULT_SAMPLE_RATE);
+            DEFAULT_SAMPLE_RATE
+        },
+        hz => hz,
+    };
+    let frames_per_buffer = match config.frames_per_buffer {
+        0 => {
+            eprintln!("invalid frames per buffer 0, using {}", DEFAULT_FRAMES_PER_BUFFER);
+            DEFAULT_FRAMES_PER_BUFFER
+        },
+        frames => frames,
+    };
+    SAMPLE_RATE.store(sample_rate as usize, atomic::Ordering::Relaxed);
+    FRAMES_PER_BUFFER.store(frames_per_buffer, atomic::Ordering::Relaxed);
+}
+
 /// Retrieve the desired audio host for the system.
 ///
 /// In general, this uses the default host, but uses the ASIO host if the "asio" feature is enabled
@@ -106,3 +199,31 @@ pub fn find_output_device(host: &Host, target_name: &str) -> Option<Device> {
             .or_else(|| host.default_output_device())
     }
 }
+
+/// Find the first output device whose name contains the given `target_name`.
+///
+/// Unlike `find_output_device`, this does not fall back to the default device.
+pub fn find_named_output_device(host: &Host, target_name: &str) -> Option<Device> {
+    if target_name.is_empty() {
+        return None;
+    }
+    host.output_devices()
+        .ok()
+        .into_iter()
+        .flat_map(std::convert::identity)
+        .find(|d| d.name().map(|n| n.contains(&target_name)).unwrap_or(false))
+}
+
+mod default {
+    pub fn sample_rate() -> u32 {
+        super::DEFAULT_SAMPLE_RATE
+    }
+
+    pub fn frames_per_buffer() -> usize {
+        super::DEFAULT_FRAMES_PER_BUFFER
+    }
+
+    pub fn dither() -> bool {
+        true
+    }
+}
@@ -3,30 +3,57 @@
 //! The render function is passed to `nannou::App`'s build output stream method and describes how
 //! audio should be rendered to the output.
 
-use audio::{DISTANCE_BLUR, FRAMES_PER_BUFFER, MAX_CHANNELS, MAX_SOUNDS};
+use audio::{frames_per_buffer, sample_rate, DISTANCE_BLUR, MAX_CHANNELS, MAX_SOUNDS};
 use audio::{Sound, Speaker};
-use audio::{dbap, detection, source, sound, speaker};
+use audio::{aggregate, ambisonics, bass, binaural, blackout, capture, dbap, detection, dither};
+use audio::{calibration, distance, effect, guard, limiter, load, loudness, reverb, test_signal};
+use audio::ducking;
+use audio::headroom;
+use audio::{telemetry, walk_test};
+use audio::{source, sound, speaker};
 use fxhash::{FxHashMap, FxHashSet};
 use gui;
 use installation;
 use metres::Metres;
 use nannou_audio::Buffer;
-use nannou::geom::Point2;
+use nannou::geom::{Point2, Point3};
 use nannou::math::MetricSpace;
 use osc;
 use soundscape;
 use std;
 use std::ops::{self, Deref, DerefMut};
-use std::sync::{atomic, mpsc, Arc};
+use std::sync::{atomic, mpsc, Arc, Mutex};
 use std::sync::atomic::AtomicUsize;
-use time_calc::Samples;
+use std::time::{Duration, Instant};
+use time_calc::{Ms, Samples};
 use utils;
 
-/// Simplified type alias for the nannou audio output stream used by the audio server.
-pub type Stream = nannou_audio::Stream<Model>;
+/// A handle to the audio output stream, shared by every thread that updates its model.
+///
+/// The stream may be closed and rebuilt in place, e.g. for a new sample rate, without invalidating
+/// any of the handles.
+#[derive(Clone)]
+pub struct Stream {
+    // The running stream, or `None` while it is being rebuilt.
+    stream: Arc<Mutex<Option<nannou_audio::Stream<Model>>>>,
+}
 
 type Channel = usize;
 
+/// The longest duration to wait for the model to be cleared when closing the output stream.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The duration over which sounds are faded out upon removal and faded in and out when muted,
+/// unmuted, soloed or unsoloed, avoiding an audible click on every speaker they feed.
+pub const SOUND_FADE: Ms = Ms(20.0);
+
+/// The shortest duration over which a speaker's DBAP gain may ramp fully between `0.0` and `1.0`
+/// for a single sound channel.
+///
+/// Limits the clicks produced by sounds jumping between speakers, e.g. when moved abruptly or
+/// when a speaker enters or leaves the proximity limit.
+const DBAP_GAIN_RAMP: Ms = Ms(15.0);
+
 // The most recently recorded DBAP speaker gain for each speaker per active sound.
 //
 // TODO: Should possibly move these into their associated `ActiveSound`s - will be easier to track
@@ -37,6 +64,14 @@ type DbapSpeakerGains = FxHashMap<sound::Id, FxHashMap<Channel, FxHashMap<speake
 pub struct ActiveSound {
     sound: Sound,
     total_duration_frames: Option<Samples>,
+    // The gain used to fade the sound in and out when muted, unmuted, soloed or unsoloed.
+    mute_gain: f32,
+    // Whether a fault has been reported for this sound, so that it is only reported once.
+    fault_reported: bool,
+    // The headroom auto-gain applied at the end of the previous buffer, if any.
+    headroom_gain: Option<f32>,
+    // The gain by which the sound is ducked beneath higher priority sounds.
+    duck_gain: f32,
 }
 
 /// A speaker that is currently active on the audio thread.
@@ -44,6 +79,15 @@ pub struct ActiveSpeaker {
     speaker: Speaker,
 }
 
+/// The output level of an installation on the audio thread, faded towards its target.
+struct InstallationVolume {
+    params: installation::Volume,
+    /// The gain derived from the installation's latest sensor reading.
+    sensor_gain: f32,
+    /// The gain applied at the end of the previous buffer.
+    gain: f32,
+}
+
 /// Information relevant to a single `Sound` for the duration of a `render` pass.
 struct SoundOrdered {
     /// The unique identifier associated with this `Sound`.
@@ -78,6 +122,87 @@ struct DbapSpeakerInfo {
     current_gain: f32,
     /// The output buffer channel associated with this speaker.
     output_channel: usize,
+    /// Whether the speaker is within the proximity limit or is only fading out.
+    in_proximity: bool,
+}
+
+impl Stream {
+    /// Wrap the given output stream.
+    pub fn new(stream: nannou_audio::Stream<Model>) -> Self {
+        let stream = Arc::new(Mutex::new(Some(stream)));
+        Stream { stream }
+    }
+
+    /// Send the given update to the stream's model.
+    ///
+    /// Updates sent while the stream is being rebuilt are discarded, as the new model is populated
+    /// from scratch.
+    pub fn send<F>(&self, update: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut Model) + Send + 'static,
+    {
+        let stream = self.stream.lock().expect("failed to lock audio output stream");
+        match *stream {
+            Some(ref stream) => stream.send(update).map_err(|err| format!("{:?}", err)),
+            None => Ok(()),
+        }
+    }
+
+    /// Start playing the stream.
+    pub fn play(&self) -> Result<(), String> {
+        let stream = self.stream.lock().expect("failed to lock audio output stream");
+        match *stream {
+            Some(ref stream) => stream.play().map_err(|err| format!("{}", err)),
+            None => Ok(()),
+        }
+    }
+
+    /// Close the stream so that it may be rebuilt.
+    ///
+    /// The model is first cleared so that all threads are notified of the end of its sounds.
+    pub fn close(&self) {
+        let stream = self.stream.lock().expect("failed to lock audio output stream").take();
+        let stream = match stream {
+            Some(stream) => stream,
+            None => return,
+        };
+        let (cleared_tx, cleared_rx) = mpsc::channel();
+        let clear = stream.send(move |audio| {
+            audio.clear_project_specific_data();
+            cleared_tx.send(()).ok();
+        });
+        if clear.is_err() || cleared_rx.recv_timeout(CLOSE_TIMEOUT).is_err() {
+            eprintln!("failed to clear the audio output model before closing its stream");
+        }
+    }
+
+    /// Replace the stream, e.g. once rebuilt after `close`.
+    pub fn replace(&self, stream: nannou_audio::Stream<Model>) {
+        *self.stream.lock().expect("failed to lock audio output stream") = Some(stream);
+    }
+}
+
+impl InstallationVolume {
+    /// Begin at the target gain so that loading a project does not fade in.
+    fn new(params: installation::Volume) -> Self {
+        let gain = params.target_gain();
+        InstallationVolume { params, sensor_gain: 1.0, gain }
+    }
+
+    /// Step the gain towards its target over the given number of frames.
+    ///
+    /// Returns the gain at the beginning and end of the frames.
+    fn advance(&mut self, frames: usize) -> (f32, f32) {
+        let start = self.gain;
+        let fade_frames = self.params.fade_secs * sample_rate();
+        let max_change = match fade_frames < 1.0 {
+            true => std::f32::MAX,
+            false => (frames as f64 / fade_frames) as f32,
+        };
+        let target = self.params.target_gain() * self.sensor_gain;
+        self.gain = slew(self.gain, target, max_change);
+        (start, self.gain)
+    }
 }
 
 impl ActiveSound {
@@ -87,6 +212,10 @@ impl ActiveSound {
         ActiveSound {
             sound,
             total_duration_frames,
+            mute_gain: 1.0,
+            fault_reported: false,
+            headroom_gain: None,
+            duck_gain: 1.0,
         }
     }
 
@@ -103,6 +232,11 @@ impl ActiveSound {
         };
         normalised_progress
     }
+
+    /// The remaining duration of playback, or `None` if the sound plays indefinitely.
+    pub fn remaining_duration(&self) -> Option<Ms> {
+        self.signal.remaining_frames().map(|frames| frames.to_ms(sample_rate()))
+    }
 }
 
 impl From<Sound> for ActiveSound {
@@ -163,6 +297,66 @@ pub struct Model {
     sounds: FxHashMap<sound::Id, ActiveSound>,
     /// a map from speaker ids to the speakers themselves.
     speakers: FxHashMap<speaker::Id, ActiveSpeaker>,
+    /// The global reverb bus, returned to all speakers.
+    pub reverb: reverb::Reverb,
+    /// The brickwall limiter applied to the summed output after the master volume.
+    pub limiter: limiter::Limiter,
+    /// Measures the loudness and true peak of the final output for the master meter.
+    loudness: loudness::Analyser,
+    /// Fades the whole output to silence and back during a blackout, applied after the limiter.
+    pub blackout: blackout::Envelope,
+    /// Quantises the final output to the word length of the output device.
+    pub dither: dither::Dither,
+    /// Replaces the exhibition with an announcement from each speaker in turn while `Some`.
+    pub walk_test: Option<walk_test::WalkTest>,
+    /// Replaces the exhibition with a test signal through one or more speakers while `Some`.
+    pub test_signal: Option<test_signal::TestSignal>,
+    /// Replaces the exhibition with a reference signal through every speaker while `Some`.
+    pub reference: Option<test_signal::Reference>,
+    /// Replaces the exhibition with a sweep through each speaker in turn during calibration.
+    pub calibration: Option<calibration::Sweeps>,
+    /// The buffers of the recording of the final output in progress and its number of channels.
+    pub recording: Option<(capture::Buffers, usize)>,
+    /// The channels of any additional output devices, rendered along with those of the primary.
+    pub aggregate: Option<aggregate::Aggregate>,
+    /// The timing of the stream's callbacks, used to measure DSP load and under-runs.
+    load: load::Monitor,
+    /// Whether the final output was found to be corrupt during the previous buffer.
+    output_faulted: bool,
+    /// The routing of low frequencies to any speakers marked as subwoofers.
+    pub bass: bass::Params,
+    /// The ambisonics bus used in place of DBAP while enabled.
+    pub ambisonics: ambisonics::Bus,
+    /// The binaural bus for monitoring on headphones, written to a pair of output channels.
+    pub binaural: binaural::Bus,
+    /// The attenuation and air absorption applied to sounds whose source uses the distance model.
+    pub distance: distance::Params,
+    /// Reduces the gain of each sound with the number of sounds playing within its installations.
+    pub headroom: headroom::Params,
+    /// The number of sounds playing within each installation during the current buffer.
+    headroom_counts: headroom::Counts,
+    /// How each installation ducks its sounds beneath those of a higher priority.
+    installation_ducking: ducking::InstallationParams,
+    /// The installations within which a sound that ducks others is playing during the current
+    /// buffer.
+    ducking_triggered: ducking::Triggered,
+    /// The reverb bus for each installation, returned to the installation's speakers.
+    installation_reverbs: FxHashMap<installation::Id, reverb::Reverb>,
+    /// The output level of each installation, applied to the installation's speakers.
+    installation_volumes: FxHashMap<installation::Id, InstallationVolume>,
+    /// A buffer for collecting the gain at the beginning and end of the buffer per installation.
+    installation_gains: FxHashMap<installation::Id, (f32, f32)>,
+    /// A delay line for time-aligning each output channel, indexed by channel.
+    channel_delays: Vec<speaker::Delay>,
+    /// The state of the parametric EQ for each output channel, indexed by channel.
+    channel_eqs: Vec<effect::ChannelEq>,
+    /// The state of the bass management crossover for each output channel, indexed by channel.
+    channel_crossovers: Vec<effect::Crossover>,
+    /// A buffer for collecting the output channel and gain of each subwoofer reached by a sound.
+    subwoofer_gains: Vec<(usize, f32)>,
+    /// The gain used to fade each output channel in and out when its speaker is muted, unmuted,
+    /// soloed or unsoloed, indexed by channel.
+    channel_monitor_gains: Vec<f32>,
 
     /// Used for collecting all `sound::Id`s within the sound map into an ordered list.
     ///
@@ -241,6 +435,63 @@ impl Model {
         // A map from speaker IDs to the speakers themselves.
         let speakers = Default::default();
 
+        // The reverb buses, bypassed until a project specifies their levels.
+        let reverb = reverb::Reverb::new(Default::default());
+        let installation_reverbs = Default::default();
+        let installation_volumes = Default::default();
+        let installation_gains = Default::default();
+
+        // The limiter protecting the output from clipping.
+        let limiter = limiter::Limiter::new(Default::default());
+        let loudness = loudness::Analyser::new();
+
+        // No blackout is underway to begin.
+        let blackout = blackout::Envelope::new();
+
+        // The output is passed to the device as floating point until configured otherwise.
+        let dither = Default::default();
+
+        // No walk test is underway to begin.
+        let walk_test = None;
+        let test_signal = None;
+        let reference = None;
+        let calibration = None;
+
+        // The output is not recorded until requested via the GUI.
+        let recording = None;
+
+        // Only the primary output device is rendered to unless others are aggregated.
+        let aggregate = None;
+        let load = Default::default();
+
+        // Pre-allocate a delay line for every output channel so that speaker delays may be
+        // changed without allocating.
+        let channel_delays = (0..MAX_CHANNELS).map(|_| speaker::Delay::new()).collect();
+        let channel_eqs = (0..MAX_CHANNELS)
+            .map(|_| effect::ChannelEq::new(Default::default()))
+            .collect();
+        let channel_crossovers = (0..MAX_CHANNELS).map(|_| effect::Crossover::new()).collect();
+        let subwoofer_gains = Vec::with_capacity(MAX_CHANNELS);
+        let channel_monitor_gains = vec![1.0; MAX_CHANNELS];
+
+        // Bass management is inactive until a speaker is marked as a subwoofer.
+        let bass = Default::default();
+
+        // Sounds are rendered via DBAP until a project enables the ambisonics bus.
+        let ambisonics = ambisonics::Bus::new(Default::default());
+
+        // The binaural bus is disabled until a project enables it.
+        let binaural = binaural::Bus::new(Default::default());
+
+        // The distance model, applied only to sounds whose source opts in.
+        let distance = Default::default();
+
+        // The headroom auto-gain is disabled until a project enables it.
+        let headroom = Default::default();
+        let headroom_counts = Default::default();
+        let installation_ducking = Default::default();
+        let ducking_triggered = Default::default();
+
         // Pre-allocate the `sounds_ordered` buffer.
         //
         // This just uses the first sound `Id` for every buffer for now (this will be overwritten
@@ -248,7 +499,7 @@ impl Model {
         let sounds_ordered = (0..MAX_SOUNDS)
             .map(|_| SoundOrdered {
                 id: sound::Id::INITIAL,
-                unmixed_samples: vec![0.0; FRAMES_PER_BUFFER * 2],
+                unmixed_samples: vec![0.0; frames_per_buffer() * 2],
                 channels: 0,
             })
             .collect();
@@ -300,6 +551,35 @@ impl Model {
             sound_channels,
             dbap_speaker_infos,
             speakers,
+            reverb,
+            installation_reverbs,
+            installation_volumes,
+            installation_gains,
+            limiter,
+            loudness,
+            blackout,
+            dither,
+            walk_test,
+            test_signal,
+            reference,
+            calibration,
+            recording,
+            aggregate,
+            load,
+            output_faulted: false,
+            bass,
+            ambisonics,
+            binaural,
+            distance,
+            headroom,
+            headroom_counts,
+            installation_ducking,
+            ducking_triggered,
+            channel_delays,
+            channel_eqs,
+            channel_crossovers,
+            subwoofer_gains,
+            channel_monitor_gains,
             exhausted_sounds,
             channels,
             dbap_speaker_gains,
@@ -330,6 +610,11 @@ impl Model {
     /// Returns `false` if there was no installation for the given `Id`.
     pub fn remove_installation(&mut self, id: &installation::Id) {
         self.channels.detection.remove_installation(*id);
+        self.installation_reverbs.remove(id);
+        self.installation_volumes.remove(id);
+        self.installation_gains.remove(id);
+        self.headroom_counts.remove(id);
+        self.installation_ducking.remove(id);
 
         // Remove the installation from any speakers.
         for speaker in self.speakers.values_mut() {
@@ -344,6 +629,53 @@ impl Model {
         }
     }
 
+    /// Insert the reverb bus for the installation with the given `Id`.
+    ///
+    /// The bus is constructed by the caller so that no allocation occurs on the audio thread.
+    pub fn insert_installation_reverb(&mut self, id: installation::Id, reverb: reverb::Reverb) {
+        self.installation_reverbs.insert(id, reverb);
+    }
+
+    /// Update the parameters of the reverb bus for the installation with the given `Id`.
+    pub fn update_installation_reverb(&mut self, id: &installation::Id, params: reverb::Params) {
+        if let Some(reverb) = self.installation_reverbs.get_mut(id) {
+            reverb.set_params(params);
+        }
+    }
+
+    /// Update the output level of the installation with the given `Id`.
+    ///
+    /// The installation's speakers fade from their current gain over the level's fade duration.
+    pub fn update_installation_volume(
+        &mut self,
+        id: installation::Id,
+        params: installation::Volume,
+    ) {
+        match self.installation_volumes.get_mut(&id) {
+            Some(volume) => {
+                volume.params = params;
+                return;
+            },
+            None => (),
+        }
+        self.installation_volumes.insert(id, InstallationVolume::new(params));
+    }
+
+    /// Update how the installation with the given `Id` ducks its sounds beneath those of a higher
+    /// priority.
+    pub fn update_installation_ducking(&mut self, id: installation::Id, params: ducking::Params) {
+        self.installation_ducking.insert(id, params);
+    }
+
+    /// Update the gain derived from the latest sensor reading of the installation.
+    ///
+    /// The installation's speakers fade to the new level over its volume fade duration.
+    pub fn update_installation_sensor_gain(&mut self, id: installation::Id, gain: f32) {
+        if let Some(volume) = self.installation_volumes.get_mut(&id) {
+            volume.sensor_gain = gain;
+        }
+    }
+
     /// Inserts the speaker and sends an `Add` message to the GUI.
     pub fn insert_speaker(&mut self, id: speaker::Id, speaker: Speaker) -> Option<Speaker> {
         let old_speaker = self.speakers
@@ -378,6 +710,49 @@ impl Model {
             .unwrap_or(false)
     }
 
+    /// Update the trim and delay of the speaker with the given `speaker::Id`.
+    pub fn update_speaker_alignment(&mut self, id: &speaker::Id, trim_db: f32, delay: Ms) {
+        if let Some(active) = self.speakers.get_mut(id) {
+            active.speaker.trim_db = trim_db;
+            active.speaker.delay = delay;
+        }
+    }
+
+    /// Update the parametric EQ of the speaker with the given `speaker::Id`.
+    pub fn update_speaker_eq(&mut self, id: &speaker::Id, eq: effect::ParametricEq) {
+        if let Some(active) = self.speakers.get_mut(id) {
+            active.speaker.eq = eq;
+        }
+    }
+
+    /// Specify whether or not the speaker with the given `speaker::Id` is a subwoofer.
+    pub fn update_speaker_subwoofer(&mut self, id: &speaker::Id, subwoofer: bool) {
+        if let Some(active) = self.speakers.get_mut(id) {
+            active.speaker.subwoofer = subwoofer;
+        }
+    }
+
+    /// Update the directivity and aim of the speaker with the given `speaker::Id`.
+    pub fn update_speaker_directivity(
+        &mut self,
+        id: &speaker::Id,
+        directivity: speaker::Directivity,
+        aim_radians: f64,
+    ) {
+        if let Some(active) = self.speakers.get_mut(id) {
+            active.speaker.directivity = directivity;
+            active.speaker.aim_radians = aim_radians;
+        }
+    }
+
+    /// Update whether the speaker with the given `speaker::Id` is muted and soloed.
+    pub fn update_speaker_monitoring(&mut self, id: &speaker::Id, muted: bool, soloed: bool) {
+        if let Some(active) = self.speakers.get_mut(id) {
+            active.speaker.muted = muted;
+            active.speaker.soloed = soloed;
+        }
+    }
+
     /// Removes the installation from the speaker with the given `speaker::Id`.
     pub fn remove_speaker_installation(&mut self, id: speaker::Id, inst: &installation::Id) -> bool {
         self.speakers
@@ -392,6 +767,7 @@ impl Model {
         let channels = sound.channels;
         let source_id = sound.source_id();
         let normalised_progress = sound.normalised_progress();
+        let remaining = sound.remaining_duration();
 
         // Notify the GUI monitor that a sound has started.
         let sound_msg = gui::ActiveSoundMessage::Start {
@@ -399,6 +775,7 @@ impl Model {
             position,
             channels,
             normalised_progress,
+            remaining,
         };
         let msg = gui::AudioMonitorMessage::ActiveSound(id, sound_msg);
         self.channels.gui_audio_monitor_msg_tx.push(msg);
@@ -453,16 +830,38 @@ impl Model {
         count
     }
 
+    /// Fades out and then removes the sound, sending an `End` active sound message to the GUI.
+    ///
+    /// Sounds that are playing are faded out over the `SOUND_FADE` and removed once exhausted
+    /// to avoid clicks. Paused sounds are removed immediately.
+    ///
+    /// Returns `false` if the sound did not exist
+    pub fn remove_sound(&mut self, id: sound::Id) -> bool {
+        let is_playing = match self.sounds.get(&id) {
+            None => return false,
+            Some(sound) => sound.shared.is_playing(),
+        };
+        if !is_playing {
+            return self.remove_sound_immediately(id);
+        }
+        if let Some(sound) = self.sounds.get_mut(&id) {
+            sound.signal.fade_out(Samples(SOUND_FADE.samples(sample_rate())));
+        }
+        true
+    }
+
     /// Removes the sound and sends an `End` active sound message to the GUI.
     ///
     /// Also removes the sound from DBAP tracking.
     ///
     /// Returns `false` if the sound did not exist
-    pub fn remove_sound(&mut self, id: sound::Id) -> bool {
+    pub fn remove_sound_immediately(&mut self, id: sound::Id) -> bool {
         let removed = self.sounds.remove(&id);
         if let Some(sound) = removed {
             // Remove the sound from DBAP gain tracking.
             self.dbap_speaker_gains.remove(&id);
+            self.ambisonics.remove_sound(&id);
+            self.binaural.remove_sound(&id);
             // Notify threads.
             self.channels.notify_sound_end(id, sound);
             true
@@ -484,7 +883,16 @@ impl Model {
         self.channels.detection.clear_project_specific_data();
         self.frame_count.store(0, atomic::Ordering::Relaxed);
         self.soloed.clear();
+        self.walk_test = None;
+        self.test_signal = None;
+        self.calibration = None;
         self.speakers.clear();
+        self.installation_reverbs.clear();
+        self.installation_volumes.clear();
+        self.installation_gains.clear();
+        self.headroom_counts = Default::default();
+        self.ambisonics.clear_sounds();
+        self.binaural.clear_sounds();
 
         let Model { ref mut sounds, ref channels, .. } = *self;
         for (sound_id, sound) in sounds.drain() {
@@ -523,8 +931,53 @@ fn lerp(a: f32, b: f32, lerp: f32) -> f32 {
     a + (b - a) * lerp
 }
 
+/// Step from `current` towards `target`, changing by no more than `max_change`.
+fn slew(current: f32, target: f32, max_change: f32) -> f32 {
+    current + (target - current).max(-max_change).min(max_change)
+}
+
+/// The greatest change in DBAP gain permitted over a buffer of the given number of frames.
+fn max_dbap_gain_change(frames: usize, sample_hz: f64) -> f32 {
+    frames as f32 / DBAP_GAIN_RAMP.samples(sample_hz).max(1) as f32
+}
+
 /// The function given to nannou to use for rendering.
 pub fn render(model: &mut Model, buffer: &mut Buffer) {
+    let start = Instant::now();
+
+    // Render the channels of all aggregated devices at once, or only those of the primary.
+    match model.aggregate.take() {
+        None => render_buffer(model, buffer),
+        Some(mut aggregate) => {
+            aggregate.render(buffer, |aggregate_buffer| render_buffer(model, aggregate_buffer));
+            model.aggregate = Some(aggregate);
+        },
+    }
+
+    // Send the time spent rendering relative to the duration of the buffer to the GUI, along with
+    // the state of the engine for post-mortem should an under-run occur.
+    let frames = buffer.len_frames();
+    let measurement = model.load.measure(start, frames);
+    let starved_sounds = model
+        .sounds
+        .values()
+        .filter(|active| active.sound.signal.kind.is_starved())
+        .count();
+    let telemetry = telemetry::Sample {
+        frame: model.frame_count.load(atomic::Ordering::Relaxed).saturating_sub(frames),
+        frames,
+        load: measurement.load,
+        gap: measurement.gap,
+        xrun: measurement.xrun,
+        active_sounds: model.sounds.len(),
+        starved_sounds,
+    };
+    let msg = gui::AudioMonitorMessage::Dsp(telemetry);
+    model.channels.gui_audio_monitor_msg_tx.push(msg);
+}
+
+// Render all output channels to the given buffer.
+fn render_buffer(model: &mut Model, buffer: &mut Buffer) {
     let Model {
         master_volume,
         cpu_saving_enabled,
@@ -537,6 +990,35 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
         ref mut dbap_speaker_infos,
         ref mut exhausted_sounds,
         ref mut speakers,
+        ref mut reverb,
+        ref mut installation_reverbs,
+        ref mut installation_volumes,
+        ref mut installation_gains,
+        ref mut limiter,
+        ref mut loudness,
+        ref mut blackout,
+        ref mut dither,
+        ref mut walk_test,
+        ref mut test_signal,
+        ref mut reference,
+        ref mut calibration,
+        ref mut recording,
+        aggregate: _,
+        load: _,
+        ref mut output_faulted,
+        bass,
+        ref mut ambisonics,
+        ref mut binaural,
+        distance,
+        headroom,
+        ref mut headroom_counts,
+        ref installation_ducking,
+        ref mut ducking_triggered,
+        ref mut channel_delays,
+        ref mut channel_eqs,
+        ref mut channel_crossovers,
+        ref mut subwoofer_gains,
+        ref mut channel_monitor_gains,
         ref mut dbap_speaker_gains,
         ref mut dbap_speakers,
         ref channels,
@@ -563,6 +1045,51 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
         })
         .collect();
 
+    // Bass management is only active while at least one speaker is marked as a subwoofer.
+    let num_subwoofers = channels_to_speakers
+        .values()
+        .filter(|id| speakers[*id].subwoofer)
+        .count();
+
+    // Prepare the reverb buses for this buffer along with the channels to which they return.
+    reverb.begin_buffer(buffer.len_frames());
+    for (&channel, _) in channels_to_speakers.iter() {
+        reverb.add_output_channel(channel);
+    }
+    for (installation, installation_reverb) in installation_reverbs.iter_mut() {
+        installation_reverb.begin_buffer(buffer.len_frames());
+        for (&channel, speaker_id) in channels_to_speakers.iter() {
+            if speakers[speaker_id].installations.contains(installation) {
+                installation_reverb.add_output_channel(channel);
+            }
+        }
+    }
+
+    // Prepare the ambisonics bus and its decoder for the current layout of full-range speakers.
+    let ambisonics_active = ambisonics.is_active();
+    if ambisonics_active {
+        ambisonics.begin_buffer(buffer.len_frames());
+        for (&channel, speaker_id) in channels_to_speakers.iter() {
+            let speaker = &speakers[speaker_id];
+            if speaker.subwoofer {
+                continue;
+            }
+            let point = Point3 {
+                x: speaker.point.x.0,
+                y: speaker.point.y.0,
+                z: speaker.height.0,
+            };
+            ambisonics.add_speaker(channel, point);
+        }
+        ambisonics.update_decoder();
+    }
+
+    // Silence the binaural bus.
+    let binaural_enabled = binaural.is_enabled();
+    if binaural_enabled {
+        binaural.begin_buffer(buffer.len_frames());
+    }
+
     // Retrieve the total number of sounds so we know how long we should slice
     // `sounds_ordered`.
     let num_sounds = sounds.len();
@@ -582,6 +1109,27 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
     sound_channels.clear();
     dbap_speaker_infos.clear();
 
+    // Count the sounds playing within each installation for the headroom auto-gain.
+    headroom_counts.clear();
+    if headroom.enabled {
+        for active in sounds.values().filter(|active| active.sound.shared.is_playing()) {
+            headroom_counts.add(&active.sound.installations);
+        }
+    }
+
+    // Gather the installations within which a sound is playing that ducks those of lower priority.
+    ducking_triggered.clear();
+    if !installation_ducking.is_empty() {
+        for active in sounds.values().filter(|active| active.sound.shared.is_playing()) {
+            if let Some(priority) = active.sound.priority {
+                ducking_triggered.add(priority, &active.sound.installations, installation_ducking);
+            }
+        }
+    }
+
+    let frames_len = buffer.len_frames() as f32;
+    let max_headroom_change = headroom::MAX_GAIN_SLEW_PER_SEC * frames_len / sample_rate() as f32;
+
     // For each sound, request `buffer.len()` number of frames and push them to the sound's
     // `unmixed_sounds` buffer.
     for (sound_i, ordered_sound) in sounds_ordered.iter_mut().enumerate() {
@@ -596,17 +1144,23 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
         let position = sound.position;
         let n_channels = sound.channels;
         let normalised_progress = sound.normalised_progress();
+        let remaining = sound.remaining_duration();
         let update = gui::ActiveSoundMessage::Update {
             source_id,
             position,
             channels: n_channels,
             normalised_progress,
+            remaining,
         };
         let msg = gui::AudioMonitorMessage::ActiveSound(sound_id, update);
         channels.gui_audio_monitor_msg_tx.push(msg);
 
         let ActiveSound {
             ref mut sound,
+            ref mut mute_gain,
+            ref mut fault_reported,
+            ref mut headroom_gain,
+            ref mut duck_gain,
             ..
         } = *sound;
 
@@ -620,13 +1174,16 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
 
         // Don't play the sound if:
         //
-        // - There are no speakers.
+        // - There are no speakers and the binaural bus is disabled.
         // - The source is muted.
         // - Some other source(s) is/are soloed.
-        let play_condition = speakers.is_empty()
+        //
+        // Muted and unsoloed sounds are first faded out via the `mute_gain`.
+        let play_condition = (speakers.is_empty() && !binaural_enabled)
             || sound.muted
             || (!soloed.is_empty() && !soloed.contains(&sound.source_id()));
-        if play_condition {
+        let target_mute_gain = if play_condition { 0.0 } else { 1.0 };
+        if play_condition && *mute_gain == 0.0 {
             // Pull samples from the signal but do not render them.
             let samples_yielded = sound.signal.samples().take(num_samples).count();
             if samples_yielded < num_samples {
@@ -635,15 +1192,72 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
             continue;
         }
 
+        // The headroom auto-gain, stepped towards its target across the buffer.
+        let target = headroom.gain(headroom_counts.busiest(&sound.installations));
+        let start_headroom_gain = headroom_gain.unwrap_or(target);
+        let end_headroom_gain = slew(start_headroom_gain, target, max_headroom_change);
+        *headroom_gain = Some(end_headroom_gain);
+
+        // The ducking beneath higher priority sounds, stepped towards its target across the buffer.
+        let start_duck_gain = *duck_gain;
+        if let Some(priority) = sound.priority {
+            let installations = &sound.installations;
+            let ducking = ducking_triggered.ducking(priority, installations, installation_ducking);
+            *duck_gain = match ducking {
+                Some((params, ducked)) => {
+                    params.step(*duck_gain, ducked, frames_len / sample_rate() as f32)
+                },
+                None => 1.0,
+            };
+        }
+        let end_duck_gain = *duck_gain;
+
         // Collect the samples from the `Sound`'s `Signal`.
         {
             let mut samples_written = 0;
             for sample in sound.signal.samples().take(num_samples) {
-                let sample = sample * sound.volume;
+                let frame = (samples_written / sound.channels) as f32;
+                let lerp_amt = frame / frames_len;
+                let headroom_gain = lerp(start_headroom_gain, end_headroom_gain, lerp_amt);
+                let sample = sample * sound.volume * sound.intensity_gain * sound.day_gain;
+                let duck_gain = lerp(start_duck_gain, end_duck_gain, lerp_amt);
+                let sample = sample * sound.modulation_gain * headroom_gain * duck_gain;
                 ordered_sound.unmixed_samples.push(sample);
                 samples_written += 1;
             }
 
+            // Apply the sound's insert effects.
+            sound.effects.process(&mut ordered_sound.unmixed_samples);
+
+            // Attenuate and filter the sound with its distance from the nearest reference.
+            if sound.distance_model {
+                let point = Point3 {
+                    x: sound.position.point.x.0,
+                    y: sound.position.point.y.0,
+                    z: sound.position.height.0,
+                };
+                let to_speakers = &channels_to_speakers;
+                let d = nearest_reference_distance(point, to_speakers, speakers, binaural);
+                let samples = &mut ordered_sound.unmixed_samples;
+                sound.distance_filter.process(&distance, d, samples);
+            }
+
+            // Fade the sound in or out if it was just muted, unmuted, soloed or unsoloed.
+            if *mute_gain != target_mute_gain {
+                let step = 1.0 / SOUND_FADE.samples(sample_rate()).max(1) as f32;
+                for frame in ordered_sound.unmixed_samples.chunks_mut(n_channels) {
+                    *mute_gain = slew(*mute_gain, target_mute_gain, step);
+                    for sample in frame {
+                        *sample *= *mute_gain;
+                    }
+                }
+            }
+
+            // Silence the sound entirely if its samples are corrupt.
+            if let Some(fault) = guard::silence_faults(&mut ordered_sound.unmixed_samples) {
+                report_sound_fault(channels, sound_id, fault, fault_reported);
+            }
+
             // If CPU saving is not enabled, send the samples to the detector for analysis.
             if !cpu_saving_enabled {
                 let mut detection_buffer = channels.detection.pop_sound_buffer();
@@ -659,11 +1273,124 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
             }
         }
 
+        // Render each channel of the sound relative to the binaural listener.
+        if binaural_enabled {
+            for (sound_channel, channel_point) in sound.channel_points().enumerate() {
+                let point = Point3 {
+                    x: channel_point.x.0,
+                    y: channel_point.y.0,
+                    z: sound.position.height.0,
+                };
+                let samples = &ordered_sound.unmixed_samples;
+                binaural.render_channel(sound_id, sound_channel, point, samples, n_channels);
+            }
+        }
+
+        // Send the sound to the global reverb and the reverb of each of its installations.
+        if sound.reverb_send > 0.0 {
+            let samples = &ordered_sound.unmixed_samples;
+            if reverb.params().is_active() {
+                reverb.send(samples, sound.channels, sound.reverb_send);
+            }
+            for (installation, installation_reverb) in installation_reverbs.iter_mut() {
+                let is_assigned = match sound.installations {
+                    sound::Installations::All => true,
+                    sound::Installations::Set(ref set) => set.contains(installation),
+                };
+                if is_assigned && installation_reverb.params().is_active() {
+                    installation_reverb.send(samples, sound.channels, sound.reverb_send);
+                }
+            }
+        }
+
+        // Send a mono sum of the sound to the subwoofers.
+        //
+        // The result is low-passed along with the rest of each subwoofer's channel below.
+        if num_subwoofers > 0 && n_channels > 0 {
+            let sound_point_f = Point3 {
+                x: sound.position.point.x.0,
+                y: sound.position.point.y.0,
+                z: sound.position.height.0,
+            };
+            dbap_speakers.clear();
+            subwoofer_gains.clear();
+            for (&channel, speaker_id) in channels_to_speakers.iter() {
+                let active = &speakers[speaker_id];
+                if !active.speaker.subwoofer {
+                    continue;
+                }
+                let weight = speaker::dbap_weight(
+                    &sound.installations,
+                    &active.speaker.installations,
+                    &sound.speaker_filter,
+                    speaker_id,
+                );
+                if weight == 0.0 {
+                    continue;
+                }
+                let speaker_point_f = Point3 {
+                    x: active.speaker.point.x.0,
+                    y: active.speaker.point.y.0,
+                    z: active.speaker.height.0,
+                };
+                let distance = dbap::blurred_distance_2(
+                    sound_point_f,
+                    speaker_point_f,
+                    DISTANCE_BLUR,
+                );
+                dbap_speakers.push(dbap::Speaker { distance, weight });
+                subwoofer_gains.push((channel, 0.0));
+            }
+
+            // Either pan among the subwoofers or split the sound equally between them.
+            if bass.sub_dbap {
+                let rolloff_db = sound.dbap_rolloff_db.unwrap_or(dbap_rolloff_db);
+                let gains = dbap::SpeakerGains::new(&dbap_speakers, rolloff_db);
+                for (subwoofer, gain) in subwoofer_gains.iter_mut().zip(gains) {
+                    subwoofer.1 = match guard::check_gain(gain as f32) {
+                        None => gain as f32,
+                        Some(fault) => {
+                            report_sound_fault(channels, sound_id, fault, fault_reported);
+                            0.0
+                        },
+                    };
+                }
+            } else {
+                let gain = 1.0 / (subwoofer_gains.len() as f32).sqrt();
+                for subwoofer in subwoofer_gains.iter_mut() {
+                    subwoofer.1 = gain;
+                }
+            }
+
+            let mono_gain = 1.0 / n_channels as f32;
+            let samples = ordered_sound.unmixed_samples.chunks(n_channels);
+            for (frame, samples) in buffer.frames_mut().zip(samples) {
+                let mono = samples.iter().sum::<f32>() * mono_gain;
+                for &(channel, gain) in subwoofer_gains.iter() {
+                    frame[channel] += mono * gain;
+                }
+            }
+        }
+
         // Mix the audio from the signal onto each of the output channels.
         if speakers.is_empty() {
             continue;
         }
 
+        // Encode each channel of the sound onto the ambisonics bus in place of DBAP.
+        if ambisonics_active {
+            for (sound_channel, channel_point) in sound.channel_points().enumerate() {
+                let point = Point3 {
+                    x: channel_point.x.0,
+                    y: channel_point.y.0,
+                    z: sound.position.height.0,
+                };
+                let samples = &ordered_sound.unmixed_samples;
+                ambisonics.encode_channel(sound_id, sound_channel, point, samples, n_channels);
+            }
+            continue;
+        }
+
         // Get the currently stored DBAP speaker gains for this sound.
         let dbap_speaker_gains = dbap_speaker_gains
             .entry(sound_id)
@@ -689,16 +1416,24 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
                     None => continue,
                 };
                 let active = &speakers[speaker_id];
+
+                // Subwoofers only receive the mono sum above.
+                if active.speaker.subwoofer {
+                    continue;
+                }
+
                 let speaker_point = &active.speaker.point;
 
                 // Get the current gain by performing DBAP calc.
-                let channel_point_f = Point2 {
+                let channel_point_f = Point3 {
                     x: channel_point.x.0,
                     y: channel_point.y.0,
+                    z: sound.position.height.0,
                 };
-                let speaker_point_f = Point2 {
+                let speaker_point_f = Point3 {
                     x: speaker_point.x.0,
                     y: speaker_point.y.0,
+                    z: active.speaker.height.0,
                 };
 
                 // Get the squared distance between the channel and speaker.
@@ -708,16 +1443,33 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
                     DISTANCE_BLUR,
                 );
 
-                // If this speaker is not within proximity, skip it.
+                // Get the previous gain for this channel.
+                let previous_gain = dbap_speaker_gains
+                    .get(speaker_id)
+                    .map(|&g| g)
+                    .unwrap_or(0.0);
+
+                // If this speaker is not within proximity, fade it out or skip it if silent.
                 if proximity_limit_2 < Metres(distance_2) {
+                    if previous_gain > 0.0 {
+                        dbap_speaker_infos.push(DbapSpeakerInfo {
+                            previous_gain,
+                            current_gain: 0.0,
+                            output_channel: channel,
+                            in_proximity: false,
+                        });
+                    }
                     continue;
                 }
 
-                // Weight the speaker based on whether or not it is assigned.
+                // Weight the speaker based on whether or not it is assigned and whether it is
+                // aimed towards the sound.
                 let weight = speaker::dbap_weight(
                     &sound.installations,
                     &active.speaker.installations,
-                );
+                    &sound.speaker_filter,
+                    speaker_id,
+                ) * active.speaker.directivity_gain(channel_point);
 
                 // TODO: Possibly skip speakers with a weight of 0 (as below)?
                 // Uncertain how this will affect DBAP, but may drastically improve CPU.
@@ -725,12 +1477,6 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
                 //     continue;
                 // }
 
-                // Get the previous gain for this channel.
-                let previous_gain = dbap_speaker_gains
-                    .get(speaker_id)
-                    .map(|&g| g)
-                    .unwrap_or(0.0);
-
                 // Temporarily set the `current_gain` for this `SpeakerInfo` to `0.0`.
                 //
                 // The correct value will be set in the `SpeakerGains` that follow this loop.
@@ -742,6 +1488,7 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
                     previous_gain,
                     current_gain,
                     output_channel,
+                    in_proximity: true,
                 };
 
                 // Create the `dbap::Speaker` so that we may determine the current gain. This
@@ -756,14 +1503,32 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
             let speaker_infos_range = speaker_infos_start..speaker_infos_end;
 
             // If no speakers were found, skip this channel.
-            if dbap_speakers.is_empty() {
+            if speaker_infos_range.start == speaker_infos_range.end {
                 continue;
             }
 
-            // Update the speaker gains.
-            let current_gains = dbap::SpeakerGains::new(&dbap_speakers, dbap_rolloff_db);
-            for (info_i, current_gain) in speaker_infos_range.clone().zip(current_gains) {
-                dbap_speaker_infos[info_i].current_gain = current_gain as _;
+            // Update the speaker gains, using the source's own rolloff if it has one.
+            if !dbap_speakers.is_empty() {
+                let rolloff_db = sound.dbap_rolloff_db.unwrap_or(dbap_rolloff_db);
+                let current_gains = dbap::SpeakerGains::new(&dbap_speakers, rolloff_db);
+                let infos = dbap_speaker_infos[speaker_infos_range.clone()]
+                    .iter_mut()
+                    .filter(|info| info.in_proximity);
+                for (info, current_gain) in infos.zip(current_gains) {
+                    info.current_gain = match guard::check_gain(current_gain as f32) {
+                        None => current_gain as f32,
+                        Some(fault) => {
+                            report_sound_fault(channels, sound_id, fault, fault_reported);
+                            0.0
+                        },
+                    };
+                }
+            }
+
+            // Limit the rate at which the gains may change to avoid clicks.
+            let max_change = max_dbap_gain_change(buffer.len_frames(), sample_rate());
+            for info in &mut dbap_speaker_infos[speaker_infos_range.clone()] {
+                info.current_gain = slew(info.previous_gain, info.current_gain, max_change);
             }
 
             // Create the `SoundChannel` ready for mixing.
@@ -789,7 +1554,6 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
     // Sum the samples for all sound channels onto the output buffer at once.
     //
     // Iterate over each frame and track its index for gain interpolation.
-    let frames_len = buffer.len_frames() as f32;
     for (frame_i, frame) in buffer.frames_mut().enumerate() {
         let lerp_amt = frame_i as f32 / frames_len;
 
@@ -815,6 +1579,7 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
                     previous_gain,
                     current_gain,
                     output_channel,
+                    ..
                 } = *speaker_info;
 
                 let speaker_gain = lerp(previous_gain, current_gain, lerp_amt);
@@ -823,6 +1588,21 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
         }
     }
 
+    // Decode the ambisonics bus to the speakers.
+    if ambisonics_active {
+        ambisonics.decode(buffer);
+    }
+
+    // Return the reverb buses to their speakers.
+    if reverb.params().is_active() {
+        reverb.render(buffer);
+    }
+    for installation_reverb in installation_reverbs.values_mut() {
+        if installation_reverb.params().is_active() {
+            installation_reverb.render(buffer);
+        }
+    }
+
     // Send output buffer to detection thread for analysis.
     let (mut detection_buffer, mut output_info) = channels.detection.pop_output_buffer();
     detection_buffer.extend(buffer.iter().cloned());
@@ -843,6 +1623,8 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
     for sound_id in exhausted_sounds.drain(..) {
         // Remove the sound from DBAP gain tracking.
         dbap_speaker_gains.remove(&sound_id);
+        ambisonics.remove_sound(&sound_id);
+        binaural.remove_sound(&sound_id);
         // Send this with the `End` message to avoid de-allocating on audio thread.
         let sound = sounds.remove(&sound_id).unwrap();
         // Notify the other threads.
@@ -854,14 +1636,162 @@ pub fn render(model: &mut Model, buffer: &mut Buffer) {
         *sample *= master_volume;
     }
 
-    // Find the peak amplitude and send it via the monitor channel.
-    let peak = buffer.iter().fold(0.0, |peak, &s| s.max(peak));
-    channels.gui_audio_monitor_msg_tx.push(gui::AudioMonitorMessage::Master { peak });
+    // Replace the exhibition with the announcements or a test signal while any is underway.
+    //
+    // All pass through the speaker processing below so that the trim, delay and EQ of each
+    // speaker are verified along with the patch. They follow the master volume so that their
+    // level in dBFS is exact.
+    let testing = walk_test.is_some()
+        || test_signal.is_some()
+        || reference.is_some()
+        || calibration.is_some();
+    if testing {
+        buffer.iter_mut().for_each(|s| *s = 0.0);
+    }
+    if let Some(ref mut walk_test) = *walk_test {
+        walk_test.process(buffer);
+    }
+    if let Some(ref mut test_signal) = *test_signal {
+        test_signal.process(buffer);
+    }
+    if let Some(ref mut reference) = *reference {
+        reference.process(buffer, speakers.values().map(|s| s.channel));
+    }
+    if let Some(ref mut calibration) = *calibration {
+        calibration.process(buffer);
+    }
+
+    // Fade the level of each installation towards its target for this buffer.
+    //
+    // Tests are heard at full level so that muted installations may still be verified.
+    installation_gains.clear();
+    for (&id, volume) in installation_volumes.iter_mut() {
+        let gains = volume.advance(buffer.len_frames());
+        if !testing {
+            installation_gains.insert(id, gains);
+        }
+    }
+
+    // Apply the crossover, EQ, trim and delay compensation of each speaker to its output channel.
+    //
+    // When bass management is active, subwoofer channels are low-passed and all other speakers
+    // are high-passed at the crossover frequency.
+    let any_speaker_soloed = speakers.values().any(|s| s.soloed);
+    let monitor_step = 1.0 / SOUND_FADE.samples(sample_rate()).max(1) as f32;
+    for speaker in speakers.values() {
+        let channel = speaker.channel;
+        if channel >= buffer.channels() {
+            continue;
+        }
+        let gain = speaker.trim_gain();
+        let target_monitor_gain = if speaker.is_audible(any_speaker_soloed) { 1.0 } else { 0.0 };
+        let monitor_gain = &mut channel_monitor_gains[channel];
+
+        // A speaker assigned to more than one installation takes the loudest of their levels.
+        let (start_gain, end_gain) = speaker
+            .installations
+            .iter()
+            .filter_map(|id| installation_gains.get(id))
+            .fold(None, |max: Option<(f32, f32)>, &(s, e)| match max {
+                None => Some((s, e)),
+                Some((max_s, max_e)) => Some((max_s.max(s), max_e.max(e))),
+            })
+            .unwrap_or((1.0, 1.0));
+        let delay_frames = speaker.delay_frames();
+        let delay = &mut channel_delays[channel];
+        let eq = &mut channel_eqs[channel];
+        eq.set_eq(speaker.eq);
+        let split = match (num_subwoofers > 0, speaker.subwoofer) {
+            (false, _) => None,
+            (true, true) => Some(effect::Split::Low),
+            (true, false) => Some(effect::Split::High),
+        };
+        let crossover = &mut channel_crossovers[channel];
+        crossover.set(split, bass.crossover_hz);
+        for (frame_i, frame) in buffer.frames_mut().enumerate() {
+            let installation_gain = lerp(start_gain, end_gain, frame_i as f32 / frames_len);
+            *monitor_gain = slew(*monitor_gain, target_monitor_gain, monitor_step);
+            let sample = crossover.process(frame[channel]);
+            let sample = eq.process(sample) * gain * installation_gain * *monitor_gain;
+            frame[channel] = delay.process(sample, delay_frames);
+        }
+    }
+
+    // Write the binaural bus to its headphone pair, bypassing the speaker processing above.
+    if binaural_enabled {
+        binaural.write(buffer, master_volume);
+    }
+
+    // Silence the whole output if it is corrupt, e.g. by a bus or speaker filter.
+    //
+    // Faults are reported as they begin rather than for every buffer in which they persist.
+    let output_fault = guard::silence_faults(buffer);
+    if let Some(fault) = output_fault {
+        if !*output_faulted {
+            let msg = gui::AudioMonitorMessage::Fault { sound: None, fault };
+            channels.gui_audio_monitor_msg_tx.push(msg);
+        }
+    }
+    *output_faulted = output_fault.is_some();
+
+    // Limit the output so that the amplifiers are never clipped.
+    let gain_reduction_db = limiter.process(buffer);
+
+    // Fade to silence and back if a blackout is underway.
+    blackout.process(buffer);
+
+    // Reduce the output to the device's word length, dithering so that quiet tails survive.
+    dither.process(buffer);
+
+    // Write the output exactly as it is heard to the recording in progress, if any.
+    //
+    // The recording is finalised if the number of output channels changes.
+    let channels_changed = match *recording {
+        Some((_, n_channels)) => n_channels != buffer.channels(),
+        None => false,
+    };
+    if channels_changed {
+        *recording = None;
+    }
+    if let Some((ref buffers, _)) = *recording {
+        let mut samples = buffers.pop_empty(buffer.len());
+        samples.extend(buffer.iter().cloned());
+        buffers.filled.push(samples);
+    }
+
+    // Find the peak and RMS amplitude and send them via the monitor channel.
+    let peak = buffer.iter().fold(0.0, |peak, &s| s.abs().max(peak));
+    let sum_of_squares = buffer.iter().fold(0.0, |sum, &s| sum + s * s);
+    let rms = (sum_of_squares / buffer.len().max(1) as f32).sqrt();
+    let frames = buffer.len_frames();
+    let loudness = loudness.process(buffer);
+    let master_msg = gui::AudioMonitorMessage::Master {
+        peak,
+        rms,
+        gain_reduction_db,
+        frames,
+        loudness,
+    };
+    channels.gui_audio_monitor_msg_tx.push(master_msg);
 
     // Step the frame count.
     frame_count.fetch_add(buffer.len_frames(), atomic::Ordering::Relaxed);
 }
 
+// Notify the GUI of the first fault detected within the given sound.
+fn report_sound_fault(
+    channels: &Channels,
+    sound: sound::Id,
+    fault: guard::Fault,
+    reported: &mut bool,
+) {
+    if !*reported {
+        let msg = gui::AudioMonitorMessage::Fault { sound: Some(sound), fault };
+        channels.gui_audio_monitor_msg_tx.push(msg);
+        *reported = true;
+    }
+}
+
 pub fn channel_point(
     sound_point: Point2<Metres>,
     channel_index: usize,
@@ -898,3 +1828,50 @@ pub fn speaker_is_in_proximity(point: &Point2<Metres>, speaker: &Point2<Metres>,
     let distance_2 = Metres(point_f.distance2(speaker_f));
     distance_2 < proximity_limit_2
 }
+
+// The distance from the given point to the nearest full-range speaker, or to the binaural
+// listener if it is enabled and nearer.
+//
+// Returns `0.0` if there are no references so that the sound is left untouched.
+fn nearest_reference_distance(
+    point: Point3<f64>,
+    channels_to_speakers: &FxHashMap<usize, speaker::Id>,
+    speakers: &FxHashMap<speaker::Id, ActiveSpeaker>,
+    binaural: &binaural::Bus,
+) -> Metres {
+    let speaker_points = channels_to_speakers
+        .values()
+        .map(|id| &speakers[id].speaker)
+        .filter(|speaker| !speaker.subwoofer)
+        .map(|speaker| Point3 { x: speaker.point.x.0, y: speaker.point.y.0, z: speaker.height.0 });
+    let listener = match binaural.is_enabled() {
+        true => {
+            let listener = binaural.params().listener;
+            Some(Point3 { x: listener.x.0, y: listener.y.0, z: point.z })
+        },
+        false => None,
+    };
+    let distance_2 = speaker_points
+        .chain(listener)
+        .map(|reference| point.distance2(reference))
+        .fold(None, |min: Option<f64>, d| Some(min.map_or(d, |min| min.min(d))));
+    Metres(distance_2.map(f64::sqrt).unwrap_or(0.0))
+}
+
+#[test]
+fn test_dbap_gain_slew() {
+    // A sound jumping onto a speaker ramps up over `DBAP_GAIN_RAMP` rather than at once.
+    let sample_hz = 48_000.0;
+    let frames = 64;
+    let max_change = max_dbap_gain_change(frames, sample_hz);
+    let gain = slew(0.0, 1.0, max_change);
+    assert!(gain > 0.0 && gain < 0.1);
+
+    let ramp_frames = DBAP_GAIN_RAMP.samples(sample_hz) as usize;
+    let buffers = (ramp_frames + frames - 1) / frames;
+    let gain = (1..buffers).fold(gain, |gain, _| slew(gain, 1.0, max_change));
+    assert!((gain - 1.0).abs() < 1e-6);
+
+    // Jumping away is limited in the same way.
+    assert_eq!(slew(1.0, 0.0, max_change), 1.0 - max_change);
+}
@@ -0,0 +1,183 @@
+//! A send-based reverb bus running on the output thread.
+//!
+//! Each sound sends a mono mix of its channels to the global bus and to the bus of each
+//! installation in which it may be played. Each bus is a feedback delay network whose
+//! decorrelated output taps are returned to the speakers that the bus covers. This allows for
+//! adding a diffuse tail within the space rather than baking reverb into every asset.
+
+use audio::sample_rate;
+use nannou_audio::Buffer;
+
+/// The maximum time in seconds that may be taken for a reverb tail to decay by 60dB.
+pub const MAX_DECAY_SECS: f32 = 10.0;
+
+// The number of delay lines within the feedback delay network.
+const NUM_LINES: usize = 8;
+// Mutually prime delay line lengths between roughly 30ms and 60ms for a dense, even tail.
+const LINE_FRAMES: [usize; NUM_LINES] = [1433, 1601, 1867, 2053, 2251, 2399, 2617, 2797];
+// Scales the output taps so that the tail sits at a similar loudness to the dry signal.
+const OUTPUT_GAIN: f32 = 0.5;
+
+/// The user-facing parameters of a reverb bus.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Params {
+    /// The level at which the reverb is returned to the speakers, between 0.0 and 1.0.
+    ///
+    /// A level of 0.0 bypasses the bus entirely.
+    #[serde(default)]
+    pub level: f32,
+    /// The time taken for the tail to decay by 60dB in seconds.
+    #[serde(default = "default::decay_secs")]
+    pub decay_secs: f32,
+    /// The amount of high frequency absorption within the tail, between 0.0 and 1.0.
+    #[serde(default = "default::damping")]
+    pub damping: f32,
+}
+
+/// A single reverb bus along with the buffers used to collect its input and output channels.
+///
+/// All buffers are allocated on construction so that the bus may be sent to the output thread.
+pub struct Reverb {
+    params: Params,
+    lines: Vec<Line>,
+    // The mono input summed from all sends for the current buffer.
+    input: Vec<f32>,
+    // The output channels to which the bus is returned for the current buffer.
+    output_channels: Vec<usize>,
+}
+
+// A single delay line within the feedback delay network.
+struct Line {
+    buffer: Vec<f32>,
+    index: usize,
+    // The feedback gain producing the desired decay time for this line's length.
+    gain: f32,
+    // The state of the one-pole lowpass used for damping.
+    lowpass: f32,
+}
+
+impl Params {
+    /// Whether or not the bus should be processed.
+    pub fn is_active(&self) -> bool {
+        self.level > 0.0
+    }
+}
+
+impl Reverb {
+    /// Create a new reverb bus with the given parameters.
+    pub fn new(params: Params) -> Self {
+        let lines = LINE_FRAMES
+            .iter()
+            .map(|&frames| Line {
+                buffer: vec![0.0; frames],
+                index: 0,
+                gain: 0.0,
+                lowpass: 0.0,
+            })
+            .collect();
+        let input = Vec::with_capacity(super::frames_per_buffer());
+        let output_channels = Vec::with_capacity(super::MAX_CHANNELS);
+        let mut reverb = Reverb { params, lines, input, output_channels };
+        reverb.update_line_gains();
+        reverb
+    }
+
+    /// The current parameters of the bus.
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Update the parameters of the bus.
+    ///
+    /// If the bus was previously bypassed, its tail is cleared so that no stale audio is heard.
+    pub fn set_params(&mut self, params: Params) {
+        if !self.params.is_active() && params.is_active() {
+            for line in &mut self.lines {
+                line.buffer.iter_mut().for_each(|s| *s = 0.0);
+                line.lowpass = 0.0;
+            }
+        }
+        self.params = params;
+        self.update_line_gains();
+    }
+
+    /// Prepare the bus for a new output buffer with the given number of frames.
+    pub fn begin_buffer(&mut self, frames: usize) {
+        self.input.clear();
+        self.input.resize(frames, 0.0);
+        self.output_channels.clear();
+    }
+
+    /// Return the bus to the given output channel for the current buffer.
+    pub fn add_output_channel(&mut self, channel: usize) {
+        self.output_channels.push(channel);
+    }
+
+    /// Sum the given interleaved samples into the bus input as mono, scaled by `gain`.
+    pub fn send(&mut self, samples: &[f32], channels: usize, gain: f32) {
+        if channels == 0 {
+            return;
+        }
+        let gain = gain / channels as f32;
+        for (input, frame) in self.input.iter_mut().zip(samples.chunks(channels)) {
+            *input += frame.iter().sum::<f32>() * gain;
+        }
+    }
+
+    /// Process the bus input and sum the result onto each of its output channels.
+    pub fn render(&mut self, buffer: &mut Buffer) {
+        let Reverb { ref params, ref mut lines, ref input, ref output_channels } = *self;
+        let gain = params.level * OUTPUT_GAIN;
+        let damping = params.damping.max(0.0).min(1.0);
+        for (frame, &x) in buffer.frames_mut().zip(input.iter()) {
+            // Read the output of each line.
+            let mut outs = [0.0; NUM_LINES];
+            for (out, line) in outs.iter_mut().zip(lines.iter()) {
+                *out = line.buffer[line.index];
+            }
+
+            // Mix the outputs via a householder matrix and feed them back with the input.
+            let householder = outs.iter().sum::<f32>() * 2.0 / NUM_LINES as f32;
+            for (i, line) in lines.iter_mut().enumerate() {
+                let feedback = (outs[i] - householder) * line.gain;
+                line.lowpass = feedback + (line.lowpass - feedback) * damping;
+                let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+                line.buffer[line.index] = line.lowpass + x * sign;
+                line.index = (line.index + 1) % line.buffer.len();
+            }
+
+            // Return a different tap to each neighbouring channel for decorrelation.
+            for &channel in output_channels.iter() {
+                frame[channel] += outs[channel % NUM_LINES] * gain;
+            }
+        }
+    }
+
+    fn update_line_gains(&mut self) {
+        let decay_frames = self.params.decay_secs.max(0.01) * sample_rate() as f32;
+        for line in &mut self.lines {
+            // The gain that attenuates by 60dB over the decay time.
+            line.gain = 0.001f32.powf(line.buffer.len() as f32 / decay_frames);
+        }
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            level: 0.0,
+            decay_secs: default::decay_secs(),
+            damping: default::damping(),
+        }
+    }
+}
+
+pub mod default {
+    pub fn decay_secs() -> f32 {
+        2.5
+    }
+
+    pub fn damping() -> f32 {
+        0.3
+    }
+}
@@ -1,6 +1,7 @@
-use audio::{input, output, source, Source, SAMPLE_RATE};
+use audio::{distance, effect, input, output, sample_rate, source, speaker, Source};
 use crossbeam::sync::SegQueue;
 use fxhash::FxHashSet;
+use hound;
 use installation;
 use metres::Metres;
 use nannou::geom::Point2;
@@ -12,7 +13,7 @@ use time_calc::{Ms, Samples};
 /// `Sound`s can be thought of as a stack of three primary components:
 ///
 /// 1. **Source**: for generating audio data (via oscillator, wave, audio input, etc).
-/// 2. **Pre-spatial effects processing**: E.g. fades.
+/// 2. **Pre-spatial effects processing**: E.g. fades, filters and EQ.
 /// 3. **Spatial Output**: maps the sound from a position in space to the output channels.
 pub struct Sound {
     // State shared with the handles.
@@ -21,6 +22,16 @@ pub struct Sound {
     pub channels: usize,
     // An amplitude multiplier specified by the user for mixing the sound.
     pub volume: f32,
+    // An amplitude multiplier applied by the soundscape's intensity macro.
+    pub intensity_gain: f32,
+    // An amplitude multiplier applied by the source's time of day gain curve.
+    pub day_gain: f32,
+    // An amplitude multiplier applied by the noise modulation of the source's groups.
+    pub modulation_gain: f32,
+    // The priority of the soundscape source from which the sound was spawned, if any.
+    //
+    // Used to duck sounds beneath those of a higher priority within the same installations.
+    pub priority: Option<source::Priority>,
     // Whether or not the sound's source has been muted.
     pub muted: bool,
     // Includes the source and pre-spatial effects.
@@ -34,6 +45,18 @@ pub struct Sound {
     // TODO: This could potentially just be an actual type? `sound::Signal` that matched on
     // the source kind, stored its own stack of effects, etc?
     pub signal: source::Signal,
+    // Insert effects applied to the signal before it is spatialised.
+    pub effects: effect::Chain,
+    // The level at which the sound is sent to the reverb buses.
+    pub reverb_send: f32,
+    // Whether the sound is attenuated and filtered with distance from the nearest speaker.
+    pub distance_model: bool,
+    // The state of the distance model's filter for each channel.
+    pub distance_filter: distance::Filter,
+    // Overrides the master DBAP rolloff for this sound if `Some`.
+    pub dbap_rolloff_db: Option<f64>,
+    // Restricts the speakers from which the sound may be heard.
+    pub speaker_filter: speaker::Filter,
     // The location and orientation of the sound within the space.
     pub position: Position,
     // A constant radians offset for the channels, provided by the sound's `Source`.
@@ -55,6 +78,9 @@ pub struct Position {
     /// The orientation of the sound.
     #[serde(default)]
     pub radians: f32,
+    /// The height of the sound above the floor.
+    #[serde(default)]
+    pub height: Metres,
 }
 
 /// A handle to a currently playing sound.
@@ -140,6 +166,9 @@ impl Handle {
 /// Creates a sound from the given `Source` and send it to the output stream.
 ///
 /// If the sound is a realtime source, send the source end to the input stream.
+///
+/// Returns an error if the source's WAV could not be opened, e.g. if it was moved or removed since
+/// the project was loaded.
 pub fn spawn_from_source(
     id: Id,
     source_id: source::Id,
@@ -151,21 +180,28 @@ pub fn spawn_from_source(
     max_duration_frames: Option<Samples>,
     frame_count: u64,
     wav_reader: &source::wav::reader::Handle,
-    input_stream: &input::Stream,
+    input_stream: &input::Streams,
     output_stream: &output::Stream,
     latency: Ms,
-) -> Handle
+) -> Result<Handle, hound::Error>
 {
     let installations = source.role.clone().into();
+    let position = Position { height: source.height, ..position };
     match source.kind {
         source::Kind::Wav(ref wav) => {
+            let wav = wav.next_variation();
             spawn_from_wav(
                 id,
                 source_id,
-                wav,
+                &wav,
                 source.spread,
                 source.volume,
                 source.muted,
+                source.effects,
+                source.reverb_send,
+                source.distance_model,
+                source.dbap_rolloff_db,
+                source.speaker_filter.clone(),
                 position,
                 source.channel_radians,
                 installations,
@@ -180,13 +216,18 @@ pub fn spawn_from_source(
         },
 
         source::Kind::Realtime(ref realtime) => {
-            spawn_from_realtime(
+            let handle = spawn_from_realtime(
                 id,
                 source_id,
                 realtime,
                 source.spread,
                 source.volume,
                 source.muted,
+                source.effects,
+                source.reverb_send,
+                source.distance_model,
+                source.dbap_rolloff_db,
+                source.speaker_filter.clone(),
                 position,
                 source.channel_radians,
                 installations,
@@ -197,12 +238,15 @@ pub fn spawn_from_source(
                 input_stream,
                 output_stream,
                 latency,
-            )
+            );
+            Ok(handle)
         },
     }
 }
 
 /// Creates a sound from the given `source::Wav` and send it to the output audio stream.
+///
+/// Returns an error if the WAV could not be opened.
 pub fn spawn_from_wav(
     id: Id,
     source_id: source::Id,
@@ -210,6 +254,11 @@ pub fn spawn_from_wav(
     spread: Metres,
     volume: f32,
     muted: bool,
+    effects: effect::Effects,
+    reverb_send: f32,
+    distance_model: bool,
+    dbap_rolloff_db: Option<f64>,
+    speaker_filter: speaker::Filter,
     initial_position: Position,
     channel_radians: f32,
     installations: Installations,
@@ -220,14 +269,22 @@ pub fn spawn_from_wav(
     frame_count: u64,
     wav_reader: &source::wav::reader::Handle,
     audio_output: &output::Stream,
-) -> Handle
+) -> Result<Handle, hound::Error>
 {
     // The wave samples iterator.
-    let samples = wav_reader.play(id, &wav.path, frame_count, wav.should_loop || continuous_preview)
-        .unwrap_or_else(|err| {
-            panic!("failed to send new wav \"{}\"to wav_reader thread: {:?}: {}",
-                   wav.path.display(), err, err);
-        });
+    let looped = wav.should_loop || continuous_preview;
+    let loop_region = wav.loop_region();
+    let loop_crossfade_frames = wav.loop_crossfade_frames();
+    let channel_map = wav.valid_channel_map().map(|map| map.to_vec());
+    let samples = wav_reader.play(
+        id,
+        &wav.path,
+        frame_count,
+        looped,
+        loop_region,
+        loop_crossfade_frames,
+        channel_map,
+    )?;
 
     // The source signal.
     let playback = wav.playback.clone();
@@ -251,10 +308,20 @@ pub fn spawn_from_wav(
     // The sound.
     let sound = Sound {
         shared: shared.clone(),
-        channels: wav.channels,
+        channels: wav.output_channels(),
         volume,
+        intensity_gain: 1.0,
+        day_gain: 1.0,
+        modulation_gain: 1.0,
+        priority: None,
         muted,
         signal,
+        effects: effect::Chain::new(effects, wav.output_channels()),
+        reverb_send,
+        distance_model,
+        distance_filter: distance::Filter::new(wav.output_channels()),
+        dbap_rolloff_db,
+        speaker_filter,
         position: initial_position,
         channel_radians,
         spread,
@@ -276,7 +343,7 @@ pub fn spawn_from_wav(
         })
         .expect("failed to send new sound to audio output thread");
 
-    handle
+    Ok(handle)
 }
 
 /// Creates a sound from the given `source::Realtime` and send it to the output audio stream.
@@ -289,6 +356,11 @@ pub fn spawn_from_realtime(
     spread: Metres,
     volume: f32,
     muted: bool,
+    effects: effect::Effects,
+    reverb_send: f32,
+    distance_model: bool,
+    dbap_rolloff_db: Option<f64>,
+    speaker_filter: speaker::Filter,
     initial_position: Position,
     channel_radians: f32,
     installations: Installations,
@@ -296,7 +368,7 @@ pub fn spawn_from_realtime(
     release_duration_frames: Samples,
     continuous_preview: bool,
     max_duration_frames: Option<Samples>,
-    audio_input: &input::Stream,
+    audio_input: &input::Streams,
     audio_output: &output::Stream,
     latency: Ms,
 ) -> Handle {
@@ -304,13 +376,13 @@ pub fn spawn_from_realtime(
     let duration = if continuous_preview {
         input::Duration::Infinite
     } else {
-        let frames = realtime.duration.samples(SAMPLE_RATE as _);
+        let frames = realtime.duration.samples(sample_rate() as _);
         input::Duration::Frames(frames as _)
     };
 
     // Add some latency in case input and output streams aren't synced.
     let n_channels = realtime.channels.len();
-    let delay_frames = latency.samples(SAMPLE_RATE as _);
+    let delay_frames = latency.samples(sample_rate() as _);
     let delay_samples = delay_frames as usize * n_channels;
 
     // The queue used to send sample buffers from audio input stream to audio output stream signal.
@@ -327,7 +399,7 @@ pub fn spawn_from_realtime(
     signal_buffer_tx.push(vec![0.0; delay_samples]);
 
     // Insert a buffer into the input buffer tx ready for use.
-    input_buffer_tx.push(Vec::with_capacity(super::FRAMES_PER_BUFFER * n_channels));
+    input_buffer_tx.push(Vec::with_capacity(super::frames_per_buffer() * n_channels));
 
     // The signal from which the sound will draw samples.
     let remaining_samples = match duration {
@@ -344,7 +416,7 @@ pub fn spawn_from_realtime(
         sample_index: 0,
         buffer_rx: signal_buffer_rx,
         buffer_tx: input_buffer_tx,
-        current_buffer: Vec::with_capacity(super::FRAMES_PER_BUFFER * n_channels),
+        current_buffer: Vec::with_capacity(super::frames_per_buffer() * n_channels),
         remaining_samples,
         is_closed: is_closed.clone(),
     };
@@ -386,8 +458,18 @@ pub fn spawn_from_realtime(
         shared: shared.clone(),
         channels: n_channels,
         volume,
+        intensity_gain: 1.0,
+        day_gain: 1.0,
+        modulation_gain: 1.0,
+        priority: None,
         muted,
         signal,
+        effects: effect::Chain::new(effects, n_channels),
+        reverb_send,
+        distance_model,
+        distance_filter: distance::Filter::new(n_channels),
+        dbap_rolloff_db,
+        speaker_filter,
         position: initial_position,
         channel_radians,
         spread,
@@ -0,0 +1,127 @@
+//! A 24-hour gain curve allowing a source's sounds to be prominent at specific hours of the day and
+//! to recede otherwise.
+//!
+//! The curve is described by a list of points, each pairing a time of day at the venue (see the
+//! project's time zone) with a gain. The gain is interpolated linearly between neighbouring points,
+//! wrapping around midnight. A curve with no points leaves the gain of the source's sounds
+//! untouched.
+
+use audio::blackout::TimeOfDay;
+
+const SECS_PER_DAY: u32 = 24 * 60 * 60;
+
+/// The greatest gain that may be assigned to a point.
+pub const MAX_GAIN: f32 = 4.0;
+
+/// A 24-hour gain curve for the sounds of a soundscape source.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct DayGain {
+    /// The points of the curve, sorted by time of day.
+    #[serde(default)]
+    pub points: Vec<Point>,
+    /// Whether the gain of active sounds continuously follows the curve.
+    ///
+    /// Otherwise the curve is only evaluated when each sound is spawned. Useful for long sounds
+    /// that may otherwise play on well beyond their hour.
+    #[serde(default)]
+    pub continuous: bool,
+}
+
+/// The gain of the curve at a single time of day.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Point {
+    pub time: TimeOfDay,
+    pub gain: f32,
+}
+
+impl DayGain {
+    /// Whether or not the curve affects the gain of the source's sounds at all.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The gain at the given number of seconds since midnight.
+    pub fn gain_at(&self, secs_of_day: u32) -> f32 {
+        interpolate(&self.points, secs_of_day, |p| (p.time, p.gain)).unwrap_or(1.0)
+    }
+}
+
+/// Linearly interpolate between the values of the given points, sorted by time of day, at the
+/// given number of seconds since midnight, wrapping around midnight.
+///
+/// `time_value` produces the time and value of each point. Returns `None` if there are no points.
+pub fn interpolate<T, F>(points: &[T], secs_of_day: u32, time_value: F) -> Option<f32>
+where
+    F: Fn(&T) -> (TimeOfDay, f32),
+{
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (time_value(first), time_value(last)),
+        _ => return None,
+    };
+    let secs = secs_of_day % SECS_PER_DAY;
+
+    // Find the points either side of the given time, wrapping around midnight.
+    let next_index = points.iter().position(|p| time_value(p).0.secs_of_day() > secs);
+    let (prev, next) = match next_index {
+        Some(0) | None => (last, first),
+        Some(i) => (time_value(&points[i - 1]), time_value(&points[i])),
+    };
+
+    // The duration from the previous point to the time and to the next point.
+    let (prev_secs, next_secs) = (prev.0.secs_of_day(), next.0.secs_of_day());
+    let since_prev = (secs + SECS_PER_DAY - prev_secs) % SECS_PER_DAY;
+    let span = match (next_secs + SECS_PER_DAY - prev_secs) % SECS_PER_DAY {
+        0 => return Some(prev.1),
+        span => span,
+    };
+    let t = since_prev as f32 / span as f32;
+    Some(prev.1 + (next.1 - prev.1) * t)
+}
+
+/// Parse a curve from comma or whitespace separated `HH:MM=gain` points.
+///
+/// The resulting points are sorted by time of day.
+pub fn parse_points(s: &str) -> Result<Vec<Point>, String> {
+    let mut points = s
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let err = || format!("invalid point `{}`, expected `HH:MM=gain`", p);
+            let mut parts = p.splitn(2, '=');
+            let time: TimeOfDay = parts.next().ok_or_else(err)?.parse()?;
+            let gain: f32 = parts.next().and_then(|g| g.parse().ok()).ok_or_else(err)?;
+            if !(gain >= 0.0 && gain <= MAX_GAIN) {
+                return Err(format!("gain `{}` must be between 0 and {}", gain, MAX_GAIN));
+            }
+            Ok(Point { time, gain })
+        })
+        .collect::<Result<Vec<Point>, _>>()?;
+    points.sort_by_key(|p| p.time);
+    points.dedup_by_key(|p| p.time);
+    Ok(points)
+}
+
+/// Format a curve as comma separated `HH:MM=gain` points.
+pub fn points_string(points: &[Point]) -> String {
+    points
+        .iter()
+        .map(|p| format!("{}={}", p.time, p.gain))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[test]
+fn test_day_gain() {
+    let points = parse_points("18:00=0.0, 06:00=1.0").unwrap();
+    assert_eq!(points_string(&points), "06:00=1, 18:00=0");
+    assert!(parse_points("06:00").is_err());
+    assert!(parse_points("06:00=-1").is_err());
+    let curve = DayGain { points, continuous: false };
+    let hour = 60 * 60;
+    assert_eq!(curve.gain_at(6 * hour), 1.0);
+    assert_eq!(curve.gain_at(12 * hour), 0.5);
+    assert_eq!(curve.gain_at(18 * hour), 0.0);
+    // The curve wraps around midnight.
+    assert_eq!(curve.gain_at(0), 0.5);
+    assert_eq!(DayGain::default().gain_at(0), 1.0);
+}
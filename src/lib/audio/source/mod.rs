@@ -1,3 +1,4 @@
+use audio::effect::{self, Effects};
 use fxhash::FxHashSet;
 use installation;
 use metres::Metres;
@@ -8,10 +9,12 @@ use std::ops;
 use time_calc::{Ms, Samples};
 use utils::{self, Range};
 
+pub use self::day_gain::DayGain;
 pub use self::movement::Movement;
 pub use self::realtime::Realtime;
 pub use self::wav::Wav;
 
+pub mod day_gain;
 pub mod realtime;
 pub mod wav;
 
@@ -21,6 +24,22 @@ pub const MAX_ATTACK_DURATION: Ms = Ms(utils::MIN_MS);
 
 pub const MAX_RELEASE_DURATION: Ms = Ms(utils::MIN_MS);
 
+pub const MAX_FOLLOWERS: usize = 16;
+
+pub const MAX_FOLLOW_DELAY: Ms = Ms(10_000.0);
+
+pub const MAX_WEIGHT: f32 = 10.0;
+
+pub const MAX_NO_REPEAT_SPAWNS: usize = 32;
+
+pub const MAX_NO_REPEAT_DURATION: Ms = Ms(utils::HR_MS / 2.0);
+
+pub const MAX_COOLDOWN: Ms = Ms(utils::HR_MS / 2.0);
+
+pub const MAX_FOLLOW_UP_DELAY: Ms = Ms(utils::MIN_MS);
+
+pub const MAX_FOLLOW_UP_RADIUS: Metres = Metres(20.0);
+
 /// Items related to audio sources.
 ///
 /// Audio sources come in two kinds:
@@ -47,12 +66,40 @@ pub struct Source {
     /// If the source only has one channel, `radians` is ignored.
     #[serde(default = "default::channel_radians")]
     pub channel_radians: f32,
+    /// The height above the floor at which sounds are positioned.
+    #[serde(default)]
+    pub height: Metres,
     /// An amplitude modulator specified by the user via the GUI.
     #[serde(default = "default::volume")]
     pub volume: f32,
     /// Whether or not the source has been muted.
     #[serde(default)]
     pub muted: bool,
+    /// Whether or not the source is currently in rotation.
+    ///
+    /// Disabled sources are ignored by the soundscape but retain all of their parameters.
+    #[serde(default = "default::enabled")]
+    pub enabled: bool,
+    /// Insert effects applied to each sound's signal before it is spatialised.
+    #[serde(default)]
+    pub effects: Effects,
+    /// The level at which sounds are sent to the reverb buses, between 0.0 and 1.0.
+    #[serde(default)]
+    pub reverb_send: f32,
+    /// Whether sounds are attenuated and low-passed with distance from the nearest speaker.
+    ///
+    /// The distance model itself is configured via the master parameters.
+    #[serde(default)]
+    pub distance_model: bool,
+    /// Overrides the master DBAP rolloff for this source's sounds if `Some`.
+    ///
+    /// Higher values focus point-like sources onto the nearest speakers while lower values keep
+    /// beds wide.
+    #[serde(default)]
+    pub dbap_rolloff_db: Option<f64>,
+    /// Restricts the speakers from which this source's sounds may be heard.
+    #[serde(default)]
+    pub speaker_filter: audio::speaker::Filter,
 }
 
 /// A **Signal** yielding interleaved samples.
@@ -150,10 +197,108 @@ pub struct Soundscape {
     pub release_duration: Range<Ms>,
     #[serde(default = "default::movement")]
     pub movement: Movement,
+    /// The amount by which each sound's effects randomly deviate from the source's.
+    #[serde(default)]
+    pub effect_randomisation: effect::Randomisation,
+    /// Whether and how the source spawns a sound that follows each tracked visitor.
+    #[serde(default)]
+    pub follow: Follow,
+    /// The class of the source's sounds when competing for an installation's simultaneous sounds.
+    #[serde(default)]
+    pub priority: Priority,
+    /// A 24-hour gain curve applied to the source's sounds.
+    #[serde(default)]
+    pub day_gain: DayGain,
+    /// The relative likelihood of the source being picked among equally suitable sources.
+    #[serde(default = "default::weight")]
+    pub weight: f32,
+    /// Prevents the source from being picked again too soon after it was last picked.
+    #[serde(default)]
+    pub no_repeat: NoRepeat,
+    /// The duration after the source's last sound ends before the source may be picked again.
+    ///
+    /// Unlike `no_repeat`, this is measured from the end of playback rather than the spawn. While
+    /// greater than zero, the source is never picked while one of its sounds is still playing.
+    #[serde(default = "default::cooldown")]
+    pub cooldown: Ms,
+    /// Sounds spawned from other sources near where each of the source's sounds ends.
+    #[serde(default)]
+    pub follow_ups: Vec<FollowUp>,
+}
+
+/// A rule spawning a sound from another source at or near the position at which each sound of a
+/// source ends, allowing for call-and-response behaviours.
+///
+/// Follow-up sounds bypass the soundscape selection in the same manner as sounds that follow
+/// visitors, though they only spawn while the follow-up source is audible and within the
+/// simultaneous sound limits of the source, its groups and the closest installation.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct FollowUp {
+    /// The source from which the follow-up sound is spawned.
+    pub source: Id,
+    /// The likelihood of spawning the follow-up each time one of the source's sounds ends.
+    #[serde(default = "default::follow_up_probability")]
+    pub probability: f32,
+    /// The range from which the delay between the end of the sound and the follow-up is picked.
+    #[serde(default = "default::follow_up_delay")]
+    pub delay: Range<Ms>,
+    /// The distance from the ended sound's last position within which the follow-up is spawned.
+    #[serde(default = "default::follow_up_radius")]
+    pub radius: Metres,
+}
+
+/// Prevents a source from being picked again within a number of spawns or a duration of its last.
+///
+/// Spawns are counted across the whole soundscape, so that the audience does not hear the same
+/// source again until a number of other sounds have been introduced.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct NoRepeat {
+    /// The number of following spawns from which the source is excluded.
+    #[serde(default)]
+    pub spawns: usize,
+    /// The duration from which the source is excluded.
+    #[serde(default = "default::no_repeat_duration")]
+    pub duration: Ms,
+}
+
+/// The priority class of a soundscape source.
+///
+/// Each installation may reserve a number of its simultaneous sounds for each class above
+/// `Background`. Sounds of a lower class may not occupy the unused reservations of higher classes,
+/// so that higher classes always have headroom even when the background is at its limit.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum Priority {
+    Background,
+    Feature,
+    Interactive,
+    Announcement,
+}
+
+/// Describes a source that spawns one sound per tracked visitor, each of which follows the
+/// visitor's position.
+///
+/// Sources that follow visitors are excluded from the regular soundscape selection.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Follow {
+    /// Whether or not the source follows visitors.
+    #[serde(default)]
+    pub enabled: bool,
+    /// The maximum number of visitors that may be followed at once.
+    #[serde(default = "default::max_followers")]
+    pub max_followers: usize,
+    /// The duration for which a visitor must be tracked before a sound is spawned to follow them.
+    #[serde(default = "default::follow_spawn_delay")]
+    pub spawn_delay: Ms,
+    /// The duration after a visitor is lost before their sound is released.
+    ///
+    /// If the visitor is found again within this duration, the same sound continues to follow.
+    #[serde(default = "default::follow_release_delay")]
+    pub release_delay: Ms,
 }
 
 /// Items related to the movement of a source's associated sounds within a soundscape.
 pub mod movement {
+    use metres::Metres;
     use nannou::geom::{Point2, Vector2};
     use nannou::prelude::PI_F64;
     use utils::Range;
@@ -173,6 +318,12 @@ pub mod movement {
     /// The maximum allowed radians offset for rotating a Ngon.
     pub const MAX_RADIANS_OFFSET: f64 = 2.0 * PI_F64;
 
+    /// The maximum speed in metres per second of a segment of a drawn path.
+    pub const MAX_PATH_SPEED: f64 = 5.0;
+
+    /// The number of straight segments with which each segment of a smooth path is approximated.
+    pub const PATH_SUBDIVISIONS: usize = 12;
+
     /// The amount of skew applied to the perception of the max speed constraints.
     pub const MAX_SPEED_SKEW: f32 = 0.25;
 
@@ -194,6 +345,8 @@ pub mod movement {
         /// The position normalised to the constraints of the installation.
         Fixed(Point2<f64>),
         Generative(Generative),
+        /// A path drawn over the floorplan.
+        Path(Path),
     }
 
     /// Movement kinds that are guided by some generative algorithm.
@@ -242,14 +395,94 @@ pub mod movement {
         pub speed: Range<f64>,
     }
 
+    /// A path drawn over the floorplan along which each sound travels from the first point.
+    ///
+    /// Unlike the other movement kinds the points are absolute locations within the exhibition,
+    /// allowing for choreographed trajectories tied to the architecture.
+    #[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+    pub struct Path {
+        #[serde(default)]
+        pub points: Vec<PathPoint>,
+        /// Whether sounds return to the first point after reaching the last.
+        #[serde(default)]
+        pub looped: bool,
+        /// Whether the path curves smoothly through its points rather than following straight
+        /// segments between them.
+        #[serde(default)]
+        pub smooth: bool,
+    }
+
+    /// A single point of a drawn path.
+    #[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+    pub struct PathPoint {
+        /// The location within the exhibition.
+        pub point: Point2<Metres>,
+        /// The speed in metres per second along the segment beginning at this point.
+        pub speed: f64,
+    }
+
+    impl Path {
+        /// The path as straight segments, each beginning at a point with the speed at which the
+        /// segment is travelled.
+        ///
+        /// Smooth paths are subdivided along a Catmull-Rom spline through their points. Looped
+        /// paths end with a segment back to the first point.
+        pub fn polyline(&self) -> Vec<PathPoint> {
+            let points = &self.points;
+            let n = points.len();
+            if n < 2 {
+                return points.clone();
+            }
+            let n_segments = if self.looped { n } else { n - 1 };
+            let subdivisions = if self.smooth { PATH_SUBDIVISIONS } else { 1 };
+            // The point at the given index, wrapping for looped paths and clamping otherwise.
+            let at = |i: isize| -> [f64; 2] {
+                let i = if self.looped {
+                    ((i % n as isize + n as isize) % n as isize) as usize
+                } else {
+                    i.max(0).min(n as isize - 1) as usize
+                };
+                [points[i].point.x.0, points[i].point.y.0]
+            };
+            let mut polyline = Vec::with_capacity(n_segments * subdivisions + 1);
+            for i in 0..n_segments {
+                let i = i as isize;
+                let (p0, p1, p2, p3) = (at(i - 1), at(i), at(i + 1), at(i + 2));
+                let speed = points[i as usize].speed;
+                for s in 0..subdivisions {
+                    let t = s as f64 / subdivisions as f64;
+                    let [x, y] = catmull_rom(p0, p1, p2, p3, t);
+                    let point = Point2 { x: Metres(x), y: Metres(y) };
+                    polyline.push(PathPoint { point, speed });
+                }
+            }
+            let last = if self.looped { points[0] } else { points[n - 1] };
+            polyline.push(last);
+            polyline
+        }
+    }
+
+    // The point at `t` along the Catmull-Rom spline segment between `p1` and `p2`.
+    fn catmull_rom(p0: [f64; 2], p1: [f64; 2], p2: [f64; 2], p3: [f64; 2], t: f64) -> [f64; 2] {
+        let (t2, t3) = (t * t, t * t * t);
+        let f = |i: usize| {
+            0.5 * (2.0 * p1[i]
+                + (p2[i] - p0[i]) * t
+                + (2.0 * p0[i] - 5.0 * p1[i] + 4.0 * p2[i] - p3[i]) * t2
+                + (3.0 * p1[i] - p0[i] - 3.0 * p2[i] + p3[i]) * t3)
+        };
+        [f(0), f(1)]
+    }
+
     impl Movement {
-        pub const VARIANT_COUNT: usize = 2;
+        pub const VARIANT_COUNT: usize = 3;
 
         /// Produce the index of the Movement value variant.
         pub fn to_index(&self) -> usize {
             match *self {
                 Movement::Fixed(_) => 0,
                 Movement::Generative(_) => 1,
+                Movement::Path(_) => 2,
             }
         }
 
@@ -258,6 +491,7 @@ pub mod movement {
             match i {
                 0 => "FIXED",
                 1 => "GENERATIVE",
+                2 => "PATH",
                 _ => "",
             }
         }
@@ -267,6 +501,7 @@ pub mod movement {
             match i {
                 0 => Some(Movement::Fixed(super::default::FIXED)),
                 1 => Some(Movement::Generative(super::default::GENERATIVE)),
+                2 => Some(Movement::Path(Default::default())),
                 _ => None,
             }
         }
@@ -310,6 +545,37 @@ pub enum Kind {
 }
 
 impl Kind {
+    /// Whether or not the source refers to a WAV file that could not be found.
+    pub fn is_missing(&self) -> bool {
+        match *self {
+            Kind::Wav(ref wav) => wav.missing,
+            Kind::Realtime(_) => false,
+        }
+    }
+
+    /// The longest duration over which a sound spawned from the source may play.
+    ///
+    /// Realtime sources play for at most their `duration` and non-looping WAVs for at most their
+    /// length, while looping WAVs may play for up to `MAX_PLAYBACK_DURATION`.
+    pub fn max_playback_duration(&self) -> Ms {
+        match *self {
+            Kind::Realtime(ref realtime) => realtime.duration,
+            Kind::Wav(ref wav) => match wav.should_loop {
+                true => MAX_PLAYBACK_DURATION,
+                false => wav.duration.to_ms(super::sample_rate()),
+            },
+        }
+    }
+
+    /// Limit the given range of playback durations to the longest that the source may play for.
+    pub fn clamp_playback_duration(&self, range: Range<Ms>) -> Range<Ms> {
+        let max = self.max_playback_duration().ms();
+        Range {
+            min: Ms(range.min.ms().min(max)),
+            max: Ms(range.max.ms().min(max)),
+        }
+    }
+
     /// The value used to skew the playback duration to a suitable linear range for a slider.
     ///
     /// This is dependent upon whether or not the source is potentially infinite.
@@ -318,7 +584,7 @@ impl Kind {
             Kind::Realtime(_) => skew::PLAYBACK_DURATION_MAX,
             Kind::Wav(ref wav) => match wav.should_loop {
                 true => skew::PLAYBACK_DURATION_MAX,
-                false => playback_duration_skew(wav.duration.to_ms(super::SAMPLE_RATE)),
+                false => playback_duration_skew(wav.duration.to_ms(super::sample_rate())),
             },
         }
     }
@@ -358,7 +624,7 @@ where
 impl Source {
     pub fn channel_count(&self) -> usize {
         match self.kind {
-            Kind::Wav(ref wav) => wav.channels,
+            Kind::Wav(ref wav) => wav.output_channels(),
             Kind::Realtime(ref rt) => rt.channels.len(),
         }
     }
@@ -405,6 +671,14 @@ impl SignalKind {
         }
     }
 
+    /// Whether or not the signal has no samples ready for the next buffer.
+    pub fn is_starved(&self) -> bool {
+        match *self {
+            SignalKind::Wav { ref samples, .. } => samples.is_starved(),
+            SignalKind::Realtime { ref samples } => samples.is_starved(),
+        }
+    }
+
     /// Borrow the inner iterator yielding samples.
     pub fn samples(&mut self) -> &mut dyn Iterator<Item = f32> {
         match *self {
@@ -429,6 +703,25 @@ impl Signal {
         self
     }
 
+    /// Begin fading out the signal over the given number of frames, after which the signal ends.
+    ///
+    /// If the signal already ends within the given number of frames, this does nothing.
+    pub fn fade_out(&mut self, frames: Samples) {
+        if let Some(remaining) = self.remaining_frames() {
+            if remaining <= frames {
+                return;
+            }
+        }
+        self.release = Release::from_duration_frames(frames);
+        self.duration = Some(match self.duration.take() {
+            None => Duration::from_frames(frames),
+            Some(duration) => Duration {
+                duration_frames: duration.current_frame + frames,
+                current_frame: duration.current_frame,
+            },
+        });
+    }
+
     /// The minimum number of frames between `self.remaining_frames` and
     /// `self.kind.remaining_frames()` if any.
     ///
@@ -495,6 +788,45 @@ fn frames_until_release_begins(signal_remaining_frames: Samples, release: &Relea
     }
 }
 
+impl Priority {
+    pub const VARIANT_COUNT: usize = 4;
+
+    /// All priority classes from lowest to highest.
+    pub const ALL: [Priority; Priority::VARIANT_COUNT] = [
+        Priority::Background,
+        Priority::Feature,
+        Priority::Interactive,
+        Priority::Announcement,
+    ];
+
+    /// Produce the index of the priority class, ordered from lowest to highest.
+    pub fn to_index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Produce a label for the class at the given index.
+    pub fn label_from_index(i: usize) -> &'static str {
+        match i {
+            0 => "BACKGROUND",
+            1 => "FEATURE",
+            2 => "INTERACTIVE",
+            3 => "ANNOUNCEMENT",
+            _ => "",
+        }
+    }
+
+    /// Produce the priority class at the given index.
+    pub fn from_index(i: usize) -> Option<Self> {
+        Priority::ALL.get(i).cloned()
+    }
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority::Background
+    }
+}
+
 impl Role {
     /// Returns `Some(Soundscape)` if the `Role` variant is `Soundscape`.
     ///
@@ -616,6 +948,7 @@ pub mod default {
     // Rotate the channel radians 90deg so that stereo channels are to the side by default.
     pub const CHANNEL_RADIANS: f32 = ::std::f32::consts::PI * 0.5;
     pub const VOLUME: f32 = 0.6;
+    pub const ENABLED: bool = true;
     pub const OCCURRENCE_RATE: Range<Ms> = Range { min: Ms(500.0), max: Ms(HR_MS as _) };
     pub const SIMULTANEOUS_SOUNDS: Range<usize> = Range { min: 0, max: 1 };
     // Assume that the user wants to play back the sound endlessly at first.
@@ -652,6 +985,7 @@ pub mod default {
         max: ::std::f64::consts::PI * 0.5,
     };
     pub const SPEED: Range<f64> = Range { min: 1.0, max: 5.0 };
+    pub const PATH_SPEED: f64 = 1.0;
     pub const NGON: movement::Ngon = movement::Ngon {
         vertices: VERTICES,
         nth: NTH,
@@ -661,6 +995,12 @@ pub mod default {
     };
     pub const GENERATIVE: movement::Generative = movement::Generative::Agent(AGENT);
     pub const MOVEMENT: Movement = Movement::Fixed(FIXED);
+    pub const MAX_FOLLOWERS: usize = 4;
+    pub const FOLLOW_SPAWN_DELAY: Ms = Ms(1_000.0);
+    pub const FOLLOW_RELEASE_DELAY: Ms = Ms(3_000.0);
+    pub const FOLLOW_UP_PROBABILITY: f32 = 0.5;
+    pub const FOLLOW_UP_DELAY: Range<Ms> = Range { min: Ms(1_000.0), max: Ms(3_000.0) };
+    pub const FOLLOW_UP_RADIUS: Metres = Metres(1.0);
 
     pub fn spread() -> Metres {
         SPREAD
@@ -674,6 +1014,10 @@ pub mod default {
         VOLUME
     }
 
+    pub fn enabled() -> bool {
+        ENABLED
+    }
+
     pub fn occurrence_rate() -> Range<Ms> {
         OCCURRENCE_RATE
     }
@@ -717,6 +1061,42 @@ pub mod default {
     pub fn directional() -> bool {
         DIRECTIONAL
     }
+
+    pub fn max_followers() -> usize {
+        MAX_FOLLOWERS
+    }
+
+    pub fn follow_spawn_delay() -> Ms {
+        FOLLOW_SPAWN_DELAY
+    }
+
+    pub fn follow_release_delay() -> Ms {
+        FOLLOW_RELEASE_DELAY
+    }
+
+    pub fn weight() -> f32 {
+        1.0
+    }
+
+    pub fn no_repeat_duration() -> Ms {
+        Ms(0.0)
+    }
+
+    pub fn cooldown() -> Ms {
+        Ms(0.0)
+    }
+
+    pub fn follow_up_probability() -> f32 {
+        FOLLOW_UP_PROBABILITY
+    }
+
+    pub fn follow_up_delay() -> Range<Ms> {
+        FOLLOW_UP_DELAY
+    }
+
+    pub fn follow_up_radius() -> Metres {
+        FOLLOW_UP_RADIUS
+    }
 }
 
 impl Default for Soundscape {
@@ -729,6 +1109,14 @@ impl Default for Soundscape {
         let attack_duration = default::ATTACK_DURATION;
         let release_duration = default::RELEASE_DURATION;
         let movement = default::MOVEMENT;
+        let effect_randomisation = Default::default();
+        let follow = Default::default();
+        let priority = Default::default();
+        let day_gain = Default::default();
+        let weight = default::weight();
+        let no_repeat = Default::default();
+        let cooldown = default::cooldown();
+        let follow_ups = Vec::new();
         Soundscape {
             installations,
             groups,
@@ -738,6 +1126,53 @@ impl Default for Soundscape {
             attack_duration,
             release_duration,
             movement,
+            effect_randomisation,
+            follow,
+            priority,
+            day_gain,
+            weight,
+            no_repeat,
+            cooldown,
+            follow_ups,
+        }
+    }
+}
+
+impl NoRepeat {
+    /// Whether the source is excluded given the number of spawns and the time since its last.
+    pub fn excludes(&self, spawns_since: usize, since: Ms) -> bool {
+        spawns_since < self.spawns || since < self.duration
+    }
+}
+
+impl Default for NoRepeat {
+    fn default() -> Self {
+        NoRepeat {
+            spawns: 0,
+            duration: default::no_repeat_duration(),
+        }
+    }
+}
+
+impl FollowUp {
+    /// A follow-up spawning from the given source with the default probability, delay and radius.
+    pub fn new(source: Id) -> Self {
+        FollowUp {
+            source,
+            probability: default::FOLLOW_UP_PROBABILITY,
+            delay: default::FOLLOW_UP_DELAY,
+            radius: default::FOLLOW_UP_RADIUS,
+        }
+    }
+}
+
+impl Default for Follow {
+    fn default() -> Self {
+        Follow {
+            enabled: false,
+            max_followers: default::MAX_FOLLOWERS,
+            spawn_delay: default::FOLLOW_SPAWN_DELAY,
+            release_delay: default::FOLLOW_RELEASE_DELAY,
         }
     }
 }
@@ -16,6 +16,12 @@ pub struct Realtime {
     pub duration: Ms,
     // The range of channels occuppied by the source.
     pub channels: ops::Range<usize>,
+    /// The name of the input device from which the source captures.
+    ///
+    /// The first device whose name contains this string is used. An empty string refers to the
+    /// default input device.
+    #[serde(default)]
+    pub device: String,
 }
 
 /// The signal end of a `Realtime` audio source.
@@ -48,6 +54,12 @@ impl Signal {
     pub fn remaining_frames(&self) -> Option<Samples> {
         self.remaining_samples.map(|s| Samples((s / self.channels) as _))
     }
+
+    /// Whether or not the current buffer is consumed and no further buffers have arrived from the
+    /// input stream.
+    pub fn is_starved(&self) -> bool {
+        self.sample_index >= self.current_buffer.len() && self.buffer_rx.is_empty()
+    }
 }
 
 impl Iterator for Signal {
@@ -0,0 +1,266 @@
+//! Offline loudness analysis of WAV files.
+//!
+//! Integrated loudness is measured in LUFS following ITU-R BS.1770: the signal is K-weighted,
+//! divided into overlapping 400ms blocks and gated before averaging. All channels are weighted
+//! equally.
+//!
+//! Analysis can take a while for long files, so it is performed on a dedicated `Analyser` thread.
+
+use audio::source;
+use fxhash::FxHashSet;
+use hound::{self, SampleFormat};
+use nannou_audio::sample::Sample;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+/// The loudness to which sources are normalised (EBU R128).
+pub const TARGET_LUFS: f32 = -23.0;
+
+/// The loudness below which blocks are ignored entirely.
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Blocks more than this far below the ungated loudness are ignored.
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// The duration of each gating block.
+const BLOCK_SECS: f64 = 0.4;
+/// The number of steps within each block, producing a 75% overlap.
+const STEPS_PER_BLOCK: usize = 4;
+
+/// The result of analysing a WAV file.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+pub struct Loudness {
+    /// The gated integrated loudness in LUFS.
+    ///
+    /// `None` if the file is silent or shorter than a single gating block.
+    pub integrated_lufs: Option<f32>,
+    /// The absolute peak sample amplitude.
+    pub peak: f32,
+}
+
+/// A handle to the loudness analysis thread.
+pub struct Analyser {
+    tx: mpsc::Sender<(source::Id, PathBuf)>,
+    rx: mpsc::Receiver<Analysed>,
+    pending: FxHashSet<source::Id>,
+}
+
+/// The result of an analysis requested via the `Analyser`.
+pub struct Analysed {
+    pub source_id: source::Id,
+    pub path: PathBuf,
+    pub result: Result<Loudness, String>,
+}
+
+// A second order IIR filter in direct form I.
+#[derive(Clone, Copy, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Loudness {
+    /// The volume that brings the source to the target loudness without clipping its peak.
+    ///
+    /// The volume is clamped to the `0.0..1.0` range of the source volume.
+    pub fn normalised_volume(&self, target_lufs: f32) -> Option<f32> {
+        let lufs = self.integrated_lufs?;
+        let gain = 10f32.powf((target_lufs - lufs) / 20.0);
+        let max_gain = if self.peak > 0.0 { 1.0 / self.peak } else { 1.0 };
+        Some(gain.min(max_gain).max(0.0).min(1.0))
+    }
+}
+
+impl Analyser {
+    /// Request analysis of the WAV at the given path for the given source.
+    ///
+    /// Does nothing if analysis is already pending for the source.
+    pub fn request(&mut self, source_id: source::Id, path: PathBuf) {
+        if !self.pending.insert(source_id) {
+            return;
+        }
+        if self.tx.send((source_id, path)).is_err() {
+            eprintln!("failed to send WAV to the loudness analysis thread");
+        }
+    }
+
+    /// Whether or not analysis is currently pending for the given source.
+    pub fn is_pending(&self, source_id: &source::Id) -> bool {
+        self.pending.contains(source_id)
+    }
+
+    /// Collect all analyses completed since the last call.
+    pub fn completed(&mut self) -> Vec<Analysed> {
+        let completed: Vec<_> = self.rx.try_iter().collect();
+        for analysed in &completed {
+            self.pending.remove(&analysed.source_id);
+        }
+        completed
+    }
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 3]) -> Self {
+        let b = [b[0] / a[0], b[1] / a[0], b[2] / a[0]];
+        let a = [a[1] / a[0], a[2] / a[0]];
+        Biquad { b, a, ..Default::default() }
+    }
+
+    // The high shelf of the K-weighting filter, modelling the acoustic effect of the head.
+    fn k_shelf(sample_hz: f64) -> Self {
+        let (gain_db, q, hz) = (4.0, 1.0 / 2f64.sqrt(), 1_500.0);
+        let a = 10f64.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * hz / sample_hz;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos = w0.cos();
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+        let b = [
+            a * ((a + 1.0) + (a - 1.0) * cos + sqrt_a_alpha),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+            a * ((a + 1.0) + (a - 1.0) * cos - sqrt_a_alpha),
+        ];
+        let a = [
+            (a + 1.0) - (a - 1.0) * cos + sqrt_a_alpha,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos),
+            (a + 1.0) - (a - 1.0) * cos - sqrt_a_alpha,
+        ];
+        Biquad::new(b, a)
+    }
+
+    // The high pass of the K-weighting filter.
+    fn k_high_pass(sample_hz: f64) -> Self {
+        let (q, hz) = (0.5, 38.0);
+        let w0 = 2.0 * PI * hz / sample_hz;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos = w0.cos();
+        let b = [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0];
+        let a = [1.0 + alpha, -2.0 * cos, 1.0 - alpha];
+        Biquad::new(b, a)
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0] - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+/// Spawn the loudness analysis thread.
+pub fn spawn() -> Analyser {
+    let (tx, request_rx) = mpsc::channel::<(source::Id, PathBuf)>();
+    let (result_tx, rx) = mpsc::channel();
+    thread::Builder::new()
+        .name("wav_loudness_analyser".into())
+        .spawn(move || {
+            for (source_id, path) in request_rx {
+                let result = analyse(&path).map_err(|err| format!("{}", err));
+                let analysed = Analysed { source_id, path, result };
+                if result_tx.send(analysed).is_err() {
+                    break;
+                }
+            }
+        })
+        .expect("failed to spawn wav_loudness_analyser thread");
+    let pending = Default::default();
+    Analyser { tx, rx, pending }
+}
+
+/// Measure the integrated loudness and peak of the WAV file at the given path.
+pub fn analyse(path: &Path) -> Result<Loudness, hound::Error> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+    let sample_hz = spec.sample_rate as f64;
+    macro_rules! analyse_samples {
+        ($T:ty) => {{
+            let samples = reader.samples::<$T>().map(|r| r.map(Sample::to_sample::<f32>));
+            analyse_samples(samples, channels, sample_hz)
+        }};
+    }
+    match (spec.sample_format, spec.bits_per_sample) {
+        (SampleFormat::Float, 32) => analyse_samples!(f32),
+        (SampleFormat::Int, 8) => analyse_samples!(i8),
+        (SampleFormat::Int, 16) => analyse_samples!(i16),
+        (SampleFormat::Int, 32) => analyse_samples!(i32),
+        _ => Err(hound::Error::Unsupported),
+    }
+}
+
+/// Measure the integrated loudness and peak of the given interleaved samples.
+pub fn analyse_samples<I>(
+    samples: I,
+    channels: usize,
+    sample_hz: f64,
+) -> Result<Loudness, hound::Error>
+where
+    I: Iterator<Item = Result<f32, hound::Error>>,
+{
+    let step_frames = (sample_hz * BLOCK_SECS / STEPS_PER_BLOCK as f64).round() as usize;
+    let mut filters: Vec<_> = (0..channels)
+        .map(|_| (Biquad::k_shelf(sample_hz), Biquad::k_high_pass(sample_hz)))
+        .collect();
+
+    // The sum of the squared, K-weighted samples of all channels for each step.
+    let mut step_energies = vec![];
+    let mut step_energy = 0.0;
+    let mut step_samples = 0;
+    let mut peak = 0f32;
+    for (i, sample) in samples.enumerate() {
+        let sample = sample?;
+        peak = peak.max(sample.abs());
+        let (ref mut shelf, ref mut high_pass) = filters[i % channels];
+        let weighted = high_pass.process(shelf.process(sample as f64));
+        step_energy += weighted * weighted;
+        step_samples += 1;
+        if step_samples == step_frames * channels {
+            step_energies.push(step_energy);
+            step_energy = 0.0;
+            step_samples = 0;
+        }
+    }
+
+    // The mean square of each overlapping block, summed over channels.
+    let block_frames = (step_frames * STEPS_PER_BLOCK) as f64;
+    let blocks: Vec<f64> = step_energies
+        .windows(STEPS_PER_BLOCK)
+        .map(|steps| steps.iter().sum::<f64>() / block_frames)
+        .collect();
+
+    fn lufs(mean_square: f64) -> f64 {
+        -0.691 + 10.0 * mean_square.log10()
+    }
+
+    fn gated_mean<'a, I>(blocks: I) -> Option<f64>
+    where
+        I: Iterator<Item = &'a f64>,
+    {
+        let (sum, count) = blocks.fold((0.0, 0), |(sum, count), &z| (sum + z, count + 1));
+        if count > 0 { Some(sum / count as f64) } else { None }
+    }
+
+    let above_absolute = |z: &&f64| lufs(**z) > ABSOLUTE_GATE_LUFS;
+    let integrated_lufs = gated_mean(blocks.iter().filter(above_absolute))
+        .map(|mean| lufs(mean) + RELATIVE_GATE_LU)
+        .and_then(|relative_gate| {
+            let above_relative = |z: &&f64| lufs(**z) > relative_gate;
+            gated_mean(blocks.iter().filter(above_absolute).filter(above_relative))
+        })
+        .map(|mean| lufs(mean) as f32);
+
+    Ok(Loudness { integrated_lufs, peak })
+}
+
+#[test]
+fn full_scale_sine_loudness() {
+    // A 0dBFS 1kHz sine on a single channel should measure roughly -3.01 LUFS.
+    let sample_hz = 48_000.0;
+    let samples = (0..sample_hz as usize * 2)
+        .map(|i| Ok((2.0 * PI * 1_000.0 * i as f64 / sample_hz).sin() as f32));
+    let loudness = analyse_samples(samples, 1, sample_hz).unwrap();
+    let lufs = loudness.integrated_lufs.unwrap();
+    assert!((lufs - -3.01).abs() < 0.1, "unexpected loudness {}", lufs);
+}
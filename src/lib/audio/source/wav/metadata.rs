@@ -0,0 +1,153 @@
+//! Reading of loop points embedded within the `smpl` and `cue ` chunks of a RIFF WAVE file.
+//!
+//! `hound` skips over all chunks other than `fmt ` and `data`, so we walk the RIFF chunks
+//! ourselves to find them.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::ops;
+use std::path::Path;
+
+/// The size of the `smpl` chunk header preceding the list of sample loops.
+const SMPL_HEADER_BYTES: u32 = 36;
+/// The size of a single sample loop within the `smpl` chunk.
+const SMPL_LOOP_BYTES: u32 = 24;
+/// The size of a single cue point within the `cue ` chunk.
+const CUE_POINT_BYTES: u32 = 24;
+
+/// Read the loop region embedded within the WAV file at the given path.
+///
+/// See `read_loop_region` for details.
+pub fn read_loop_region_from_path<P>(path: P) -> io::Result<Option<ops::Range<u64>>>
+where
+    P: AsRef<Path>,
+{
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+    read_loop_region(&mut reader)
+}
+
+/// Read the loop region in frames embedded within the given RIFF WAVE data.
+///
+/// The first loop within the `smpl` chunk is preferred. If there is no `smpl` chunk, the region
+/// between the first two cue points of the `cue ` chunk is used. A single cue point is returned
+/// as a region with an empty end, i.e. `start..start`, which the caller should treat as
+/// extending to the end of the file.
+///
+/// Returns `None` if the data contains neither chunk.
+pub fn read_loop_region<R>(reader: &mut R) -> io::Result<Option<ops::Range<u64>>>
+where
+    R: Read + Seek,
+{
+    let mut tag = [0u8; 4];
+    reader.read_exact(&mut tag)?;
+    if &tag != b"RIFF" {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "no RIFF tag found"));
+    }
+    let _riff_len = read_u32(reader)?;
+    reader.read_exact(&mut tag)?;
+    if &tag != b"WAVE" {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "no WAVE tag found"));
+    }
+
+    let mut cue_region = None;
+    loop {
+        match reader.read_exact(&mut tag) {
+            Ok(()) => (),
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+        let len = read_u32(reader)?;
+        // Chunks are padded to an even number of bytes.
+        let padded_len = len as i64 + (len % 2) as i64;
+        match &tag {
+            b"smpl" if len >= SMPL_HEADER_BYTES + SMPL_LOOP_BYTES => {
+                reader.seek(SeekFrom::Current(28))?;
+                let num_loops = read_u32(reader)?;
+                let _sampler_data = read_u32(reader)?;
+                if num_loops > 0 {
+                    let _cue_point_id = read_u32(reader)?;
+                    let _loop_type = read_u32(reader)?;
+                    let start = read_u32(reader)? as u64;
+                    // The end of a `smpl` loop is inclusive.
+                    let end = read_u32(reader)? as u64 + 1;
+                    if start < end {
+                        return Ok(Some(start..end));
+                    }
+                }
+                let read = (SMPL_HEADER_BYTES + if num_loops > 0 { 16 } else { 0 }) as i64;
+                reader.seek(SeekFrom::Current(padded_len - read))?;
+            },
+            b"cue " if len >= 4 => {
+                let num_points = read_u32(reader)?;
+                let num_points = num_points.min((len - 4) / CUE_POINT_BYTES);
+                let mut offsets = Vec::with_capacity(num_points as usize);
+                for _ in 0..num_points {
+                    // Skip the ID, position, data chunk ID, chunk start and block start.
+                    reader.seek(SeekFrom::Current(20))?;
+                    offsets.push(read_u32(reader)? as u64);
+                }
+                offsets.sort();
+                cue_region = match offsets.len() {
+                    0 => None,
+                    1 => Some(offsets[0]..offsets[0]),
+                    _ => Some(offsets[0]..offsets[1]),
+                };
+                let read = 4 + (num_points * CUE_POINT_BYTES) as i64;
+                reader.seek(SeekFrom::Current(padded_len - read))?;
+            },
+            _ => {
+                reader.seek(SeekFrom::Current(padded_len))?;
+            },
+        }
+    }
+
+    Ok(cue_region)
+}
+
+// Read a little-endian `u32`.
+fn read_u32<R>(reader: &mut R) -> io::Result<u32>
+where
+    R: Read,
+{
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    let n = bytes[0] as u32
+        | (bytes[1] as u32) << 8
+        | (bytes[2] as u32) << 16
+        | (bytes[3] as u32) << 24;
+    Ok(n)
+}
+
+#[test]
+fn smpl_loop_region() {
+    use std::io::Cursor;
+
+    fn u32_bytes(n: u32) -> [u8; 4] {
+        [n as u8, (n >> 8) as u8, (n >> 16) as u8, (n >> 24) as u8]
+    }
+
+    let mut smpl = vec![0u8; 28];
+    smpl.extend_from_slice(&u32_bytes(1));
+    smpl.extend_from_slice(&u32_bytes(0));
+    smpl.extend_from_slice(&u32_bytes(0));
+    smpl.extend_from_slice(&u32_bytes(0));
+    smpl.extend_from_slice(&u32_bytes(100));
+    smpl.extend_from_slice(&u32_bytes(199));
+    smpl.extend_from_slice(&u32_bytes(0));
+    smpl.extend_from_slice(&u32_bytes(0));
+
+    let mut wav = Vec::new();
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&u32_bytes(0));
+    wav.extend_from_slice(b"WAVE");
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&u32_bytes(3));
+    wav.extend_from_slice(&[0, 0, 0, 0]);
+    wav.extend_from_slice(b"smpl");
+    wav.extend_from_slice(&u32_bytes(smpl.len() as u32));
+    wav.extend_from_slice(&smpl);
+
+    let region = read_loop_region(&mut Cursor::new(wav)).unwrap();
+    assert_eq!(region, Some(100..200));
+}
@@ -1,10 +1,15 @@
 use audio;
 use hound;
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::ops;
+use std::path::{Path, PathBuf};
 use time_calc::{Ms, SampleHz, Samples};
 
+pub mod loudness;
+pub mod metadata;
 pub mod reader;
 pub mod samples;
+pub mod variations;
 
 /// The WAV file audio source type.
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -17,6 +22,55 @@ pub struct Wav {
     pub should_loop: bool,
     #[serde(default = "default_playback")]
     pub playback: Playback,
+    /// The duration over which the end of the WAV is crossfaded into its beginning when looping.
+    #[serde(default = "default_loop_crossfade")]
+    pub loop_crossfade: Ms,
+    /// The frame at which the looped region begins.
+    #[serde(default = "default_loop_start")]
+    pub loop_start: Samples,
+    /// The frame at which the looped region ends.
+    ///
+    /// `None` indicates that the loop region extends to the end of the file.
+    #[serde(default = "default_loop_end")]
+    pub loop_end: Option<Samples>,
+    /// The loop points embedded within the file's `smpl` or `cue ` chunks, if any.
+    ///
+    /// These are used as the default loop region when the WAV is first added.
+    #[serde(default)]
+    pub embedded_loop: Option<EmbeddedLoop>,
+    /// For each channel of the source, the index of the file channel from which it is read.
+    ///
+    /// This allows for reordering, dropping or duplicating the file's channels before they are
+    /// laid out around the sound's position. `None` indicates the file's channel order is used.
+    #[serde(default)]
+    pub channel_map: Option<Vec<usize>>,
+    /// The result of the offline loudness analysis, if it has completed.
+    #[serde(default)]
+    pub loudness: Option<loudness::Loudness>,
+    /// Whether or not the source volume should be set to reach the target loudness.
+    #[serde(default)]
+    pub normalise: bool,
+    /// Alternative files with the same number of channels, one of which may be played in place
+    /// of `path` each time a sound is spawned.
+    #[serde(default)]
+    pub variations: variations::Variations,
+    /// Whether or not the file could not be found when the project was loaded.
+    ///
+    /// Missing WAVs are kept so that their settings survive until they are relinked, but they are
+    /// never played.
+    #[serde(skip)]
+    pub missing: bool,
+}
+
+/// Loop points read from the metadata of a WAV file.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+pub struct EmbeddedLoop {
+    /// The frame at which the embedded loop begins.
+    pub start: Samples,
+    /// The frame at which the embedded loop ends.
+    ///
+    /// `None` indicates that the loop extends to the end of the file.
+    pub end: Option<Samples>,
 }
 
 /// The playback mode of the WAV file.
@@ -45,18 +99,67 @@ fn default_should_loop() -> bool {
     false
 }
 
+/// By default, looped WAVs jump straight from their last sample back to their first.
+fn default_loop_crossfade() -> Ms {
+    Ms(0.0)
+}
+
+/// By default, loops begin at the start of the file.
+fn default_loop_start() -> Samples {
+    Samples(0)
+}
+
+/// By default, loops extend to the end of the file.
+fn default_loop_end() -> Option<Samples> {
+    None
+}
+
+/// Read the loop points embedded within the WAV file at the given path, clamped to the file's
+/// duration.
+fn read_embedded_loop(path: &Path, duration: Samples) -> Option<EmbeddedLoop> {
+    let region = match metadata::read_loop_region_from_path(path) {
+        Ok(region) => region,
+        Err(err) => {
+            eprintln!("failed to read loop metadata from {}: {}", path.display(), err);
+            None
+        },
+    };
+    region.and_then(|region| {
+        let total = duration.samples() as u64;
+        if region.start >= total {
+            return None;
+        }
+        let start = Samples(region.start as _);
+        let end = match region.end > region.start && region.end < total {
+            true => Some(Samples(region.end as _)),
+            false => None,
+        };
+        Some(EmbeddedLoop { start, end })
+    })
+}
+
 impl Wav {
     /// Attempts to load the WAV header and read the number of channels.
+    ///
+    /// Returns `hound::Error::Unsupported` if the WAV's sample rate differs from that of the
+    /// audio stream.
     pub fn from_path(path: PathBuf) -> Result<Self, hound::Error> {
         let reader = hound::WavReader::open(&path)?;
         let spec = reader.spec();
         let channels = spec.channels as usize;
         let sample_hz = spec.sample_rate as _;
-        assert_eq!(sample_hz, audio::SAMPLE_RATE,
-                   "WAV files must have a sample rate of {}", audio::SAMPLE_RATE);
+        if sample_hz != audio::sample_rate() {
+            return Err(hound::Error::Unsupported);
+        }
         let duration = Samples(reader.duration() as _);
         let playback = default_playback();
         let should_loop = default_should_loop();
+        let loop_crossfade = default_loop_crossfade();
+        let embedded_loop = read_embedded_loop(&path, duration);
+        let (loop_start, loop_end) = match embedded_loop {
+            Some(ref l) => (l.start, l.end),
+            None => (default_loop_start(), default_loop_end()),
+        };
         Ok(Wav {
             path,
             channels,
@@ -64,11 +167,88 @@ impl Wav {
             sample_hz,
             playback,
             should_loop,
+            loop_crossfade,
+            loop_start,
+            loop_end,
+            embedded_loop,
+            channel_map: None,
+            loudness: None,
+            normalise: false,
+            variations: Default::default(),
+            missing: false,
         })
     }
 
+    /// Reload the WAV header from the given path while preserving all user-specified settings.
+    ///
+    /// This is useful for updating the metadata of a WAV whose file has changed on disk.
+    pub fn reload_from_path(&self, path: PathBuf) -> Result<Self, hound::Error> {
+        let mut wav = Self::from_path(path)?;
+        wav.should_loop = self.should_loop;
+        wav.playback = self.playback;
+        wav.loop_crossfade = self.loop_crossfade;
+        wav.loop_start = self.loop_start;
+        wav.loop_end = self.loop_end;
+        wav.channel_map = self.channel_map.clone();
+        wav.normalise = self.normalise;
+        wav.variations = self.variations.clone();
+        Ok(wav)
+    }
+
+    /// Pick the file to be played by the next sound spawned from this source.
+    ///
+    /// If a variation is picked, the returned `Wav` refers to the variation's file with the loop
+    /// region clamped to its duration. Otherwise `self` is returned.
+    pub fn next_variation(&self) -> Cow<Self> {
+        if self.variations.files.is_empty() {
+            return Cow::Borrowed(self);
+        }
+        let variation = match self.variations.next_index(self.missing) {
+            0 => return Cow::Borrowed(self),
+            i => &self.variations.files[i - 1],
+        };
+        let mut wav = self.clone();
+        wav.path = variation.path.clone();
+        wav.duration = variation.duration;
+        wav.missing = variation.missing;
+        wav.embedded_loop = None;
+        wav.loudness = None;
+        if wav.loop_start >= wav.duration {
+            wav.loop_start = default_loop_start();
+        }
+        if wav.loop_end.map(|end| end > wav.duration || end <= wav.loop_start).unwrap_or(false) {
+            wav.loop_end = default_loop_end();
+        }
+        Cow::Owned(wav)
+    }
+
     /// The duration of the `Wav` in milliseconds.
     pub fn duration_ms(&self) -> Ms {
         self.duration.to_ms(self.sample_hz)
     }
+
+    /// The channel map if it is valid for the file's number of channels.
+    pub fn valid_channel_map(&self) -> Option<&[usize]> {
+        self.channel_map
+            .as_ref()
+            .filter(|map| !map.is_empty() && map.iter().all(|&ch| ch < self.channels))
+            .map(|map| &map[..])
+    }
+
+    /// The number of channels produced by the source after the channel map is applied.
+    pub fn output_channels(&self) -> usize {
+        self.valid_channel_map().map(|map| map.len()).unwrap_or(self.channels)
+    }
+
+    /// The range of frames that are repeated when the WAV is looped.
+    pub fn loop_region(&self) -> ops::Range<u64> {
+        let start = self.loop_start.samples() as u64;
+        let end = self.loop_end.unwrap_or(self.duration).samples() as u64;
+        start..end
+    }
+
+    /// The loop crossfade duration in frames.
+    pub fn loop_crossfade_frames(&self) -> u64 {
+        self.loop_crossfade.samples(self.sample_hz) as _
+    }
 }
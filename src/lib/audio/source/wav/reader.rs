@@ -7,6 +7,7 @@ use fxhash::FxHashMap;
 use hound::{self, SampleFormat};
 use num_cpus;
 use std::cell::RefCell;
+use std::cmp;
 use std::collections::VecDeque;
 use std::io::BufReader;
 use std::fs::File;
@@ -82,6 +83,12 @@ pub struct Sound {
     /// Whether or not the wav reader should loop back to the beginning of the file when it reaches
     /// the end.
     looped: bool,
+    /// The range of samples within the file that are repeated when looping.
+    loop_region: ops::Range<usize>,
+    /// Samples read from the head of the loop region that are mixed into its tail when looping.
+    ///
+    /// This is empty if the sound is not looped or has no loop crossfade.
+    loop_head: Vec<f32>,
 }
 
 /// The state of the sound as tracked by the `Model`.
@@ -117,7 +124,7 @@ pub enum Message {
     /// Received when one of the child threads has finished processing a `Play` command.
     PlayComplete(sound::Id, Sound),
     /// When received, the reader thread will re-use the given buffer to read in the next
-    /// `FRAMES_PER_BUFFER` * `channels` worth of samples.
+    /// `frames_per_buffer()` * `channels` worth of samples.
     NextBuffer(sound::Id, Vec<f32>),
     /// Received when one of the child threads has finished processing a `NextBuffer` command.
     NextBufferComplete(sound::Id, Sound),
@@ -156,6 +163,11 @@ pub struct Play {
     pub start_frame: u64,
     /// Whether or not the WAV should be looped.
     pub looped: bool,
+    /// The range of frames that should be repeated when looped.
+    pub loop_region: ops::Range<u64>,
+    /// The number of frames over which the tail of the loop region is crossfaded into its head
+    /// when looped.
+    pub loop_crossfade_frames: u64,
 }
 
 /// A handle to a WAV that receives the buffered samples for use on the audio thread.
@@ -167,6 +179,12 @@ pub struct SamplesStream {
     wav_len_samples: usize,
     // Whether or not the WAV is looped.
     wav_looped: bool,
+    // For each output channel, the index of the file channel from which it is read.
+    channel_map: Option<Vec<usize>>,
+    // The most recently read frame of file channels when a `channel_map` is in use.
+    mapped_frame: Vec<f32>,
+    // The index of the next output channel within the current mapped frame.
+    mapped_frame_index: usize,
 }
 
 impl Handle {
@@ -180,6 +198,9 @@ impl Handle {
         wav_path: &Path,
         start_frame: u64,
         looped: bool,
+        loop_region: ops::Range<u64>,
+        loop_crossfade_frames: u64,
+        channel_map: Option<Vec<usize>>,
     ) -> Result<SamplesStream, hound::Error>
     {
         let reader = WavReader::open(wav_path)?;
@@ -188,8 +209,16 @@ impl Handle {
         let buffer_tx = buffer_queue.clone();
         let buffer_rx = buffer_queue;
         let spec = reader.spec();
-        let play = Play { reader, buffer_tx, start_frame, looped };
-        let samples_stream = SamplesStream::new(buffer_rx, spec, wav_len_samples, looped);
+        let play = Play {
+            reader,
+            buffer_tx,
+            start_frame,
+            looped,
+            loop_region,
+            loop_crossfade_frames,
+        };
+        let samples_stream =
+            SamplesStream::new(buffer_rx, spec, wav_len_samples, looped, channel_map);
         let msg = Message::Play(sound_id, play);
         self.tx.push(msg);
         Ok(samples_stream)
@@ -232,7 +261,16 @@ impl SamplesStream {
         wav_spec: hound::WavSpec,
         wav_len_samples: usize,
         wav_looped: bool,
+        channel_map: Option<Vec<usize>>,
     ) -> Self {
+        // Ignore maps that are empty or refer to channels that do not exist within the file.
+        let file_channels = wav_spec.channels as usize;
+        let channel_map = channel_map
+            .filter(|map| !map.is_empty() && map.iter().all(|&ch| ch < file_channels));
+        let mapped_frame = match channel_map {
+            Some(_) => vec![0.0; file_channels],
+            None => vec![],
+        };
         SamplesStream {
             buffer_rx,
             buffer: RefCell::new(None),
@@ -240,12 +278,21 @@ impl SamplesStream {
             wav_spec,
             wav_len_samples,
             wav_looped,
+            channel_map,
+            mapped_frame,
+            mapped_frame_index: 0,
         }
     }
 
-    /// The number of channels in the source audio.
+    /// The number of channels yielded by the stream.
+    ///
+    /// This is the length of the channel map if there is one, or the number of channels in the
+    /// source audio otherwise.
     pub fn channels(&self) -> usize {
-        self.wav_spec.channels as _
+        match self.channel_map {
+            Some(ref map) => map.len(),
+            None => self.wav_spec.channels as _,
+        }
     }
 
     /// The number of frames remaining in the stream.
@@ -269,8 +316,38 @@ impl SamplesStream {
         }
     }
 
-    /// The next sample in the stream.
+    /// Whether or not the current buffer is consumed and the reader thread has yet to enqueue the
+    /// next.
+    pub fn is_starved(&self) -> bool {
+        let consumed = match *self.buffer.borrow() {
+            Some(ref buffer) => self.buffer_index >= buffer.len(),
+            None => true,
+        };
+        consumed && self.buffer_rx.is_empty()
+    }
+
+    /// The next sample in the stream with the channel map applied.
     pub fn next_sample(&mut self) -> Option<f32> {
+        if self.channel_map.is_none() {
+            return self.next_file_sample();
+        }
+
+        // Read a whole frame of file channels at the beginning of each output frame.
+        if self.mapped_frame_index == 0 {
+            for i in 0..self.mapped_frame.len() {
+                let sample = self.next_file_sample()?;
+                self.mapped_frame[i] = sample;
+            }
+        }
+
+        let map = self.channel_map.as_ref().unwrap();
+        let sample = self.mapped_frame[map[self.mapped_frame_index]];
+        self.mapped_frame_index = (self.mapped_frame_index + 1) % map.len();
+        Some(sample)
+    }
+
+    // The next sample in the stream as it is laid out within the file.
+    fn next_file_sample(&mut self) -> Option<f32> {
         let SamplesStream {
             ref buffer,
             ref buffer_rx,
@@ -328,7 +405,51 @@ impl Model {
 
 /// Process the given `Play` command and return the resulting `Sound`.
 fn play_sound(play: Play) -> Sound {
-    let Play { mut reader, buffer_tx, start_frame, looped } = play;
+    let Play {
+        mut reader,
+        buffer_tx,
+        start_frame,
+        looped,
+        loop_region,
+        loop_crossfade_frames,
+    } = play;
+
+    // Clamp the loop region to the file, falling back to the whole file if the region is empty.
+    let duration_frames = reader.duration() as u64;
+    let loop_end = cmp::min(loop_region.end, duration_frames);
+    let loop_start = match loop_region.start < loop_end {
+        true => loop_region.start,
+        false => 0,
+    };
+    let loop_end = match loop_start < loop_end {
+        true => loop_end,
+        false => duration_frames,
+    };
+    let channels = reader.spec().channels as usize;
+    let loop_region = loop_start as usize * channels..loop_end as usize * channels;
+
+    // If looping with a crossfade, read the head of the loop region so that it may be mixed into
+    // the tail. The crossfade may be at most half the duration of the loop region.
+    let loop_head = match looped && loop_crossfade_frames > 0 {
+        false => vec![],
+        true => {
+            let spec = reader.spec();
+            let frames = cmp::min(loop_crossfade_frames, (loop_end - loop_start) / 2);
+            reader.seek(loop_start as u32)
+                .expect("failed to seek to loop start in wav source");
+            let num_samples = frames as usize * spec.channels as usize;
+            let mut loop_head = Vec::with_capacity(num_samples);
+            for _ in 0..num_samples {
+                match read_next_sample(&mut reader, &spec)
+                    .expect("failed to read loop crossfade samples from wav source")
+                {
+                    Some(sample) => loop_head.push(sample),
+                    None => break,
+                }
+            }
+            loop_head
+        },
+    };
 
     // Seek to the given `start_frame` within the file.
     //
@@ -337,7 +458,6 @@ fn play_sound(play: Play) -> Sound {
     //
     // If `frame` is larger than the number of samples in the file the remaining duration will be
     // wrapped around to the beginning.
-    let duration_frames = reader.duration() as u64;
     let frames = start_frame % duration_frames;
     reader.seek(frames as u32)
         .expect("failed to seek to start frame in wav source");
@@ -348,7 +468,7 @@ fn play_sound(play: Play) -> Sound {
         .map(|_| {
             let mut samples = vec![];
             let start_sample = wav_len_samples - super::samples::remaining(&mut reader);
-            fill_buffer(&mut reader, &mut samples, looped)
+            fill_buffer(&mut reader, &mut samples, looped, &loop_region, &loop_head)
                 .expect("failed to fill buffer");
             let end_sample = wav_len_samples - super::samples::remaining(&mut reader);
             let samples_range = start_sample..end_sample;
@@ -361,6 +481,8 @@ fn play_sound(play: Play) -> Sound {
         buffer_tx,
         prepared_buffers,
         looped,
+        loop_region,
+        loop_head,
     }
 }
 
@@ -381,6 +503,8 @@ fn next_buffer(
         ref mut prepared_buffers,
         ref buffer_tx,
         looped,
+        ref loop_region,
+        ref loop_head,
     } = *sound;
 
     // The total number of samples in the WAV, tracked for `BufferInfo`.
@@ -397,7 +521,7 @@ fn next_buffer(
 
     // Fill the given buffer using the reader and enqueue it.
     let start = wav_len_samples - super::samples::remaining(reader);
-    fill_buffer(reader, &mut samples, looped)?;
+    fill_buffer(reader, &mut samples, looped, loop_region, loop_head)?;
     let end = wav_len_samples - super::samples::remaining(reader);
     let samples_range = start..end;
     let prepared_buffer = PreparedBuffer { samples, samples_range };
@@ -406,20 +530,25 @@ fn next_buffer(
     Ok(())
 }
 
-/// Fill the given `samples` buffer with `FRAMES_PER_BUFFER * channels` samples read from the
+/// Fill the given `samples` buffer with `frames_per_buffer() * channels` samples read from the
 /// `reader`.
+///
+/// When `looped`, the `loop_region` is repeated and the given `loop_head` samples are crossfaded
+/// into its tail.
 fn fill_buffer(
     reader: &mut WavReader,
     samples: &mut Vec<f32>,
     looped: bool,
+    loop_region: &ops::Range<usize>,
+    loop_head: &[f32],
 ) -> Result<(), hound::Error> {
     let spec = reader.spec();
     let channels = spec.channels as usize;
-    let num_samples = audio::FRAMES_PER_BUFFER * channels;
+    let num_samples = audio::frames_per_buffer() * channels;
     samples.clear();
     if looped {
         for _ in 0..num_samples {
-            let sample = read_next_sample_cycled(reader, &spec)?;
+            let sample = read_next_sample_cycled(reader, &spec, loop_region, loop_head)?;
             samples.push(sample);
         }
     } else {
@@ -434,17 +563,42 @@ fn fill_buffer(
 }
 
 /// The same as `read_next_sample` but rather than returning `None` after the last sample in the
-/// `WAV` is read it seeks the reader back to the beginning of the file and
+/// `loop_region` is read it seeks the reader back to the beginning of the region.
+///
+/// If `loop_head` is non-empty, the tail of the region is crossfaded with the `loop_head` samples
+/// and the reader seeks to the end of the head rather than the beginning of the region so that
+/// the loop remains gapless.
 fn read_next_sample_cycled(
     reader: &mut WavReader,
     spec: &hound::WavSpec,
+    loop_region: &ops::Range<usize>,
+    loop_head: &[f32],
 ) -> Result<f32, hound::Error>
 {
+    let channels = spec.channels as usize;
+    let wav_len_samples = reader.len() as usize;
+    let crossfade_start = loop_region.end - loop_head.len();
+    let loop_restart_frame = ((loop_region.start + loop_head.len()) / channels) as u32;
     loop {
+        let position = wav_len_samples - super::samples::remaining(reader);
+        if position >= loop_region.end {
+            reader.seek(loop_restart_frame)?;
+            continue;
+        }
         match read_next_sample(reader, spec)? {
-            Some(sample) => return Ok(sample),
+            Some(sample) => {
+                if position < crossfade_start {
+                    return Ok(sample);
+                }
+                let head_index = position - crossfade_start;
+                let frame = (head_index / channels) as f32;
+                let crossfade_frames = (loop_head.len() / channels) as f32;
+                let lerp = frame / crossfade_frames;
+                let head_sample = loop_head[head_index];
+                return Ok(sample * (1.0 - lerp) + head_sample * lerp);
+            },
             None => {
-                reader.seek(0)?;
+                reader.seek(loop_restart_frame)?;
             },
         }
     }
@@ -0,0 +1,185 @@
+//! Variation sets allowing a single WAV source to be backed by multiple files.
+//!
+//! Each time a sound is spawned from the source, one file is picked from the set consisting of the
+//! source's primary file followed by each of its variations. This avoids the need for many
+//! near-identical sources, each with their own soundscape constraints.
+
+use audio;
+use hound;
+use nannou::rand::{self, Rng};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use time_calc::Samples;
+
+/// The set of alternative files played in place of a WAV source's primary file.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Variations {
+    /// The alternative files in the order in which they are played when using `RoundRobin`.
+    #[serde(default)]
+    pub files: Vec<Variation>,
+    /// The likelihood of the primary file being picked relative to the variation weights.
+    #[serde(default = "default_weight")]
+    pub primary_weight: f32,
+    /// How the file is picked for each new sound.
+    #[serde(default = "default_selection")]
+    pub selection: Selection,
+    // One greater than the index of the last file picked, or `0` if none have been picked.
+    //
+    // This is shared between all copies of the source so that the no-immediate-repeat rule holds
+    // across both the soundscape and source previews.
+    #[serde(skip)]
+    last: Arc<AtomicUsize>,
+}
+
+/// An alternative file within a WAV source's variation set.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Variation {
+    pub path: PathBuf,
+    pub duration: Samples,
+    /// The likelihood of the variation being picked when using `WeightedRandom` selection.
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    /// Whether or not the file could not be found when the project was loaded.
+    #[serde(skip)]
+    pub missing: bool,
+}
+
+/// How a file is picked from the variation set for each new sound.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum Selection {
+    /// Play each file in order, returning to the primary file after the last variation.
+    RoundRobin,
+    /// Pick a file at random according to its weight, never picking the same file twice in a row.
+    WeightedRandom,
+}
+
+/// The number of variants within the `Selection` enum.
+pub const NUM_SELECTIONS: usize = 2;
+
+impl Selection {
+    /// All selection modes in the order in which they are displayed.
+    pub const ALL: [Selection; NUM_SELECTIONS] = [
+        Selection::RoundRobin,
+        Selection::WeightedRandom,
+    ];
+
+    /// The name of the selection mode for display.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Selection::RoundRobin => "Round Robin",
+            Selection::WeightedRandom => "Weighted Random",
+        }
+    }
+}
+
+impl Variation {
+    /// Attempts to load the WAV header of the variation at the given path.
+    pub fn from_path(path: PathBuf) -> Result<Self, hound::Error> {
+        let reader = hound::WavReader::open(&path)?;
+        if reader.spec().sample_rate as f64 != audio::sample_rate() {
+            return Err(hound::Error::Unsupported);
+        }
+        let duration = Samples(reader.duration() as _);
+        let weight = default_weight();
+        Ok(Variation { path, duration, weight, missing: false })
+    }
+}
+
+impl Variations {
+    /// Whether or not the given path is one of the variation files.
+    pub fn contains_path(&self, path: &Path) -> bool {
+        self.files.iter().any(|v| v.path == path)
+    }
+
+    /// Pick the index of the file to play for the next sound.
+    ///
+    /// Index `0` refers to the primary file and `i + 1` refers to `files[i]`. Missing files are
+    /// never picked and the last picked file is only repeated if it is the only candidate.
+    pub fn next_index(&self, primary_missing: bool) -> usize {
+        let last = match self.last.load(Ordering::Relaxed) {
+            0 => None,
+            n => Some(n - 1),
+        };
+        let n_files = self.files.len() + 1;
+        let is_candidate = |i: usize| match i {
+            0 => !primary_missing,
+            i => !self.files[i - 1].missing,
+        };
+        let n_candidates = (0..n_files).filter(|&i| is_candidate(i)).count();
+        let is_allowed = |i: usize| is_candidate(i) && (n_candidates < 2 || Some(i) != last);
+
+        let picked = match self.selection {
+            Selection::RoundRobin => {
+                let start = last.map(|i| i + 1).unwrap_or(0);
+                (0..n_files).map(|i| (start + i) % n_files).find(|&i| is_allowed(i))
+            },
+            Selection::WeightedRandom => {
+                let weight = |i: usize| match i {
+                    0 => self.primary_weight,
+                    i => self.files[i - 1].weight,
+                };
+                let allowed: Vec<_> = (0..n_files).filter(|&i| is_allowed(i)).collect();
+                let total: f32 = allowed.iter().map(|&i| weight(i).max(0.0)).sum();
+                let mut rng = rand::thread_rng();
+                if allowed.is_empty() {
+                    None
+                } else if total <= 0.0 {
+                    Some(allowed[rng.gen_range(0, allowed.len())])
+                } else {
+                    // Walk the cumulative weights until the random target is reached.
+                    let mut target = rng.gen::<f32>() * total;
+                    let found = allowed.iter().cloned().find(|&i| {
+                        target -= weight(i).max(0.0);
+                        target < 0.0
+                    });
+                    found.or(allowed.last().cloned())
+                }
+            },
+        };
+
+        let picked = picked.unwrap_or(0);
+        self.last.store(picked + 1, Ordering::Relaxed);
+        picked
+    }
+}
+
+impl Default for Variations {
+    fn default() -> Self {
+        Variations {
+            files: vec![],
+            primary_weight: default_weight(),
+            selection: default_selection(),
+            last: Default::default(),
+        }
+    }
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+fn default_selection() -> Selection {
+    Selection::RoundRobin
+}
+
+#[test]
+fn test_round_robin_no_repeat() {
+    let variation = |name: &str| Variation {
+        path: PathBuf::from(name),
+        duration: Samples(0),
+        weight: default_weight(),
+        missing: false,
+    };
+    let mut variations = Variations::default();
+    variations.files = vec![variation("b.wav"), variation("c.wav")];
+    let picks: Vec<_> = (0..4).map(|_| variations.next_index(false)).collect();
+    assert_eq!(picks, vec![0, 1, 2, 0]);
+    variations.selection = Selection::WeightedRandom;
+    let mut last = variations.next_index(false);
+    for _ in 0..32 {
+        let next = variations.next_index(false);
+        assert!(next != last);
+        last = next;
+    }
+}
@@ -3,6 +3,14 @@ use fxhash::FxHashSet;
 use installation;
 use metres::Metres;
 use nannou::geom::Point2;
+use time_calc::Ms;
+
+/// The lowest trim that may be applied to a speaker in decibels.
+pub const MIN_TRIM_DB: f32 = -24.0;
+/// The highest trim that may be applied to a speaker in decibels.
+pub const MAX_TRIM_DB: f32 = 12.0;
+/// The longest delay that may be applied to a speaker for time-alignment.
+pub const MAX_DELAY: Ms = Ms(500.0);
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Deserialize, Serialize)]
 pub struct Id(pub u64);
@@ -14,19 +22,190 @@ pub struct Id(pub u64);
 pub struct Speaker {
     // The location of the speaker within the space.
     pub point: Point2<Metres>,
+    // The height of the speaker above the floor.
+    #[serde(default)]
+    pub height: Metres,
     // The channel on which the output is rendered.
     pub channel: usize,
     // Installations assigned to this speaker.
     #[serde(default)]
     pub installations: FxHashSet<installation::Id>,
+    // The gain offset applied to the speaker's output channel in decibels.
+    #[serde(default)]
+    pub trim_db: f32,
+    // The delay applied to the speaker's output channel for time-alignment.
+    #[serde(default = "default_delay")]
+    pub delay: Ms,
+    // The parametric EQ applied to the speaker's output channel, e.g. for room correction.
+    #[serde(default)]
+    pub eq: audio::effect::ParametricEq,
+    // Whether the speaker is a subwoofer, receiving only the low end of all sounds.
+    #[serde(default)]
+    pub subwoofer: bool,
+    // Whether the speaker's output channel is silenced, e.g. while verifying the wiring of a rig.
+    #[serde(default)]
+    pub muted: bool,
+    // Whether the speaker is soloed. While any speaker is soloed, only soloed speakers are heard.
+    #[serde(default)]
+    pub soloed: bool,
+    // The directional response of the speaker across the floorplan.
+    #[serde(default)]
+    pub directivity: Directivity,
+    // The direction in which the speaker is aimed in radians, anti-clockwise from the x axis.
+    #[serde(default)]
+    pub aim_radians: f64,
+}
+
+/// The directional response of a speaker across the floorplan.
+///
+/// Directional speakers contribute less to sounds positioned behind or beside them, e.g. column
+/// speakers aimed along a corridor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Directivity {
+    /// Equally loud in all directions.
+    Omni,
+    Subcardioid,
+    Cardioid,
+    /// The narrowest front lobe along with a small rear lobe.
+    Supercardioid,
+}
+
+/// Restricts the speakers from which a source may be heard, regardless of its position.
+///
+/// E.g. used to ensure narration never comes from speakers hidden inside sculptures.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Filter {
+    /// If non-empty, the source is only heard from these speakers.
+    #[serde(default)]
+    pub include: FxHashSet<Id>,
+    /// The source is never heard from these speakers.
+    #[serde(default)]
+    pub exclude: FxHashSet<Id>,
+}
+
+/// A delay line used to time-align the output channel of a single speaker.
+///
+/// The buffer is allocated up front for the `MAX_DELAY` so that the delay may be changed on the
+/// audio thread without allocating.
+pub struct Delay {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl Speaker {
+    /// The linear gain of the speaker's trim.
+    pub fn trim_gain(&self) -> f32 {
+        10.0f32.powf(self.trim_db / 20.0)
+    }
+
+    /// The speaker's delay in frames, clamped to the `MAX_DELAY`.
+    pub fn delay_frames(&self) -> usize {
+        let ms = self.delay.ms().max(0.0).min(MAX_DELAY.ms());
+        (ms * audio::sample_rate() / 1_000.0).round() as usize
+    }
+
+    /// Whether or not the speaker is heard given the solo state of all speakers.
+    pub fn is_audible(&self, any_soloed: bool) -> bool {
+        !self.muted && (!any_soloed || self.soloed)
+    }
+
+    /// The gain of the speaker towards the given point given its directivity and aim.
+    pub fn directivity_gain(&self, point: Point2<Metres>) -> f64 {
+        if self.directivity == Directivity::Omni {
+            return 1.0;
+        }
+        let (dx, dy) = ((point.x - self.point.x).0, (point.y - self.point.y).0);
+        if dx == 0.0 && dy == 0.0 {
+            return 1.0;
+        }
+        self.directivity.gain(dy.atan2(dx) - self.aim_radians)
+    }
 }
 
-/// Calculate a speaker's DBAP weight taking into consideration its assigned installations.
+impl Directivity {
+    /// All patterns in the order in which they are displayed.
+    pub const ALL: [Directivity; 4] = [
+        Directivity::Omni,
+        Directivity::Subcardioid,
+        Directivity::Cardioid,
+        Directivity::Supercardioid,
+    ];
+
+    /// The name displayed within the GUI.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Directivity::Omni => "Omni",
+            Directivity::Subcardioid => "Subcardioid",
+            Directivity::Cardioid => "Cardioid",
+            Directivity::Supercardioid => "Supercardioid",
+        }
+    }
+
+    /// The gain towards a point at the given angle in radians from the direction of aim.
+    ///
+    /// Follows the first-order polar pattern `|a + (1 - a) * cos(angle)|`.
+    pub fn gain(&self, angle: f64) -> f64 {
+        let a = match *self {
+            Directivity::Omni => return 1.0,
+            Directivity::Subcardioid => 0.7,
+            Directivity::Cardioid => 0.5,
+            Directivity::Supercardioid => 0.37,
+        };
+        (a + (1.0 - a) * angle.cos()).abs()
+    }
+}
+
+impl Default for Directivity {
+    fn default() -> Self {
+        Directivity::Omni
+    }
+}
+
+impl Filter {
+    /// Whether or not the source may be heard from the speaker with the given ID.
+    pub fn allows(&self, id: &Id) -> bool {
+        (self.include.is_empty() || self.include.contains(id)) && !self.exclude.contains(id)
+    }
+
+    /// Whether or not the filter allows all speakers.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+}
+
+impl Delay {
+    /// Allocate a delay line long enough for the `MAX_DELAY`.
+    pub fn new() -> Self {
+        let frames = (MAX_DELAY.ms() * audio::sample_rate() / 1_000.0).ceil() as usize + 1;
+        Delay { buffer: vec![0.0; frames], index: 0 }
+    }
+
+    /// Write the given sample to the line and return the sample from `delay_frames` ago.
+    pub fn process(&mut self, sample: f32, delay_frames: usize) -> f32 {
+        let len = self.buffer.len();
+        self.buffer[self.index] = sample;
+        let read = (self.index + len - delay_frames.min(len - 1)) % len;
+        self.index = (self.index + 1) % len;
+        self.buffer[read]
+    }
+}
+
+fn default_delay() -> Ms {
+    Ms(0.0)
+}
+
+/// Calculate a speaker's DBAP weight taking into consideration its assigned installations and
+/// the sound's speaker filter.
 pub fn dbap_weight(
     sound_installations: &audio::sound::Installations,
     speaker_installations: &FxHashSet<installation::Id>,
+    speaker_filter: &Filter,
+    speaker: &Id,
 ) -> f64
 {
+    if !speaker_filter.allows(speaker) {
+        return 0.0;
+    }
     match *sound_installations {
         audio::sound::Installations::All => 1.0,
         audio::sound::Installations::Set(ref set) => {
@@ -37,3 +216,34 @@ pub fn dbap_weight(
         },
     }
 }
+
+#[test]
+fn test_delay() {
+    let mut delay = Delay::new();
+    let output: Vec<_> = (1..6).map(|i| delay.process(i as f32, 2)).collect();
+    assert_eq!(output, vec![0.0, 0.0, 1.0, 2.0, 3.0]);
+}
+
+#[test]
+fn test_filter() {
+    let mut filter = Filter::default();
+    assert!(filter.allows(&Id(0)));
+    filter.include.insert(Id(0));
+    filter.include.insert(Id(1));
+    filter.exclude.insert(Id(1));
+    assert!(filter.allows(&Id(0)));
+    assert!(!filter.allows(&Id(1)));
+    assert!(!filter.allows(&Id(2)));
+    let all = audio::sound::Installations::All;
+    assert_eq!(dbap_weight(&all, &Default::default(), &filter, &Id(1)), 0.0);
+}
+
+#[test]
+fn test_directivity() {
+    use std::f64::consts::PI;
+    assert_eq!(Directivity::Omni.gain(PI), 1.0);
+    assert!((Directivity::Cardioid.gain(0.0) - 1.0).abs() < 1e-9);
+    assert!((Directivity::Cardioid.gain(PI / 2.0) - 0.5).abs() < 1e-9);
+    assert!(Directivity::Cardioid.gain(PI).abs() < 1e-9);
+    assert!(Directivity::Supercardioid.gain(PI) > 0.0);
+}
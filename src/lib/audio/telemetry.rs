@@ -0,0 +1,25 @@
+//! A snapshot of the audio output thread taken at the end of every callback.
+//!
+//! Snapshots are sent to the GUI thread where the most recent few seconds are retained and written
+//! to a file whenever an under-run occurs (see `gui::telemetry`). Glitches are rarely reproducible
+//! on demand, so the state of the engine leading up to one is the best evidence available.
+
+/// The state of the audio output thread following a single callback.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Sample {
+    /// The number of frames rendered by the output stream prior to this callback.
+    pub frame: usize,
+    /// The number of frames rendered during this callback.
+    pub frames: usize,
+    /// The time spent rendering relative to the duration of the buffer.
+    pub load: f32,
+    /// The time since the previous callback began relative to the duration of the buffer.
+    pub gap: Option<f32>,
+    /// Whether or not an under-run was detected.
+    pub xrun: bool,
+    /// The number of sounds playing.
+    pub active_sounds: usize,
+    /// The number of sounds with no samples ready for the next buffer, i.e. those whose WAV
+    /// reader or input stream has fallen behind.
+    pub starved_sounds: usize,
+}
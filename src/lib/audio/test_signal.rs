@@ -0,0 +1,320 @@
+//! Test signals for commissioning, played directly through a single speaker, through each speaker
+//! in turn or through every speaker at once, bypassing the soundscape.
+//!
+//! - **1 kHz tone** is the reference oscillator used to align levels throughout the signal path.
+//! - **Pink noise** has equal energy per octave and is the usual reference for level matching.
+//! - **Sine sweeps** rise exponentially from 20 Hz to 20 kHz, revealing rattles and resonances.
+//! - **Speech noise** is pink noise shaped to roughly follow the long-term average spectrum of
+//!   speech, useful for judging intelligibility.
+
+use audio::sample_rate;
+use nannou_audio::Buffer;
+use std::f64::consts::PI;
+
+/// The default level of test signals in dBFS.
+pub const DEFAULT_LEVEL_DB: f32 = -20.0;
+
+/// The quietest level of test signals in dBFS.
+pub const MIN_LEVEL_DB: f32 = -60.0;
+
+/// The loudest level of test signals in dBFS.
+pub const MAX_LEVEL_DB: f32 = -6.0;
+
+/// The duration for which the signal plays through each speaker when cycling through them.
+///
+/// Matches the duration of a single sweep so that every speaker receives a complete sweep.
+pub const CYCLE_INTERVAL_SECS: f64 = SWEEP_SECS;
+
+const SWEEP_START_HZ: f64 = 20.0;
+const SWEEP_END_HZ: f64 = 20_000.0;
+const SWEEP_SECS: f64 = 4.0;
+
+const TONE_HZ: f64 = 1_000.0;
+
+// Speech noise is high-passed below the fundamental of most voices and rolls off above 1 kHz.
+const SPEECH_HIGH_PASS_HZ: f64 = 100.0;
+const SPEECH_LOW_PASS_HZ: f64 = 1_000.0;
+
+// The duration of the fades at the edges of each sweep and each step of a cycle.
+const FADE_SECS: f64 = 0.005;
+
+// Scales the sum of the pink noise filters to roughly unity.
+const PINK_GAIN: f32 = 0.11;
+
+/// The kinds of test signal that may be played.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Tone,
+    PinkNoise,
+    SineSweep,
+    SpeechNoise,
+}
+
+/// A test signal playing on the audio output thread.
+///
+/// Plays through each of the given output channels in turn, or continuously if there is only one.
+#[derive(Clone, Debug)]
+pub struct TestSignal {
+    kind: Kind,
+    gain: f32,
+    channels: Vec<usize>,
+    interval_frames: usize,
+    channel_index: usize,
+    frame: usize,
+    generator: Generator,
+}
+
+/// A reference signal playing through every speaker at once on the audio output thread.
+///
+/// Used to verify the full output chain and to calibrate the SPL of the system with a meter.
+#[derive(Clone, Debug)]
+pub struct Reference {
+    kind: Kind,
+    gain: f32,
+    generator: Generator,
+}
+
+// The state of the noise and sweep generators.
+#[derive(Clone, Debug)]
+struct Generator {
+    rng: u32,
+    pink: [f32; 7],
+    low_pass: f32,
+    high_pass: f32,
+    sweep_frame: usize,
+    tone_phase: f64,
+}
+
+impl Kind {
+    /// All kinds of test signal in the order in which they are displayed.
+    pub const ALL: [Kind; 4] = [Kind::Tone, Kind::PinkNoise, Kind::SineSweep, Kind::SpeechNoise];
+
+    /// The kinds of signal suitable for calibrating the whole system via the master reference.
+    pub const REFERENCE: [Kind; 2] = [Kind::Tone, Kind::PinkNoise];
+
+    /// A human-friendly name for the signal.
+    pub fn label(&self) -> &'static str {
+        match *self {
+            Kind::Tone => "1 kHz Tone",
+            Kind::PinkNoise => "Pink Noise",
+            Kind::SineSweep => "Sine Sweep",
+            Kind::SpeechNoise => "Speech Noise",
+        }
+    }
+}
+
+impl TestSignal {
+    /// Play the given kind of signal through each of the given output channels in turn.
+    pub fn new(kind: Kind, level_db: f32, channels: Vec<usize>) -> Self {
+        let gain = db_to_amp(level_db);
+        let interval_frames = (CYCLE_INTERVAL_SECS * sample_rate()) as usize;
+        let generator = Generator::new();
+        TestSignal { kind, gain, channels, interval_frames, channel_index: 0, frame: 0, generator }
+    }
+
+    /// Switch to the given kind of signal.
+    pub fn set_kind(&mut self, kind: Kind) {
+        self.kind = kind;
+    }
+
+    /// Set the level of the signal in dBFS.
+    pub fn set_level_db(&mut self, level_db: f32) {
+        self.gain = db_to_amp(level_db);
+    }
+
+    /// Add the signal to the given buffer.
+    pub fn process(&mut self, buffer: &mut Buffer) {
+        let channels = buffer.channels();
+        self.mix(&mut buffer[..], channels);
+    }
+
+    // Add the signal to the given interleaved samples.
+    fn mix(&mut self, samples: &mut [f32], channels: usize) {
+        if self.channels.is_empty() || channels == 0 {
+            return;
+        }
+        let cycling = self.channels.len() > 1;
+        let fade_frames = (FADE_SECS * sample_rate()) as usize;
+        for frame in samples.chunks_mut(channels) {
+            let fade = match cycling {
+                true => edge_fade(self.frame, self.interval_frames, fade_frames),
+                false => 1.0,
+            };
+            let sample = self.generator.next(self.kind) * self.gain * fade;
+            let channel = self.channels[self.channel_index];
+            if channel < frame.len() {
+                frame[channel] += sample;
+            }
+            self.frame += 1;
+            if cycling && self.frame >= self.interval_frames {
+                self.frame = 0;
+                self.generator.sweep_frame = 0;
+                self.channel_index = (self.channel_index + 1) % self.channels.len();
+            }
+        }
+    }
+}
+
+impl Reference {
+    /// Play the given kind of signal at the given level in dBFS.
+    ///
+    /// The level of the tone is its peak, roughly 3 dB above its RMS level.
+    pub fn new(kind: Kind, level_db: f32) -> Self {
+        let gain = db_to_amp(level_db);
+        let generator = Generator::new();
+        Reference { kind, gain, generator }
+    }
+
+    /// Switch to the given kind of signal.
+    pub fn set_kind(&mut self, kind: Kind) {
+        self.kind = kind;
+    }
+
+    /// Set the level of the signal in dBFS.
+    pub fn set_level_db(&mut self, level_db: f32) {
+        self.gain = db_to_amp(level_db);
+    }
+
+    /// Add the signal to each of the given output channels of the buffer.
+    pub fn process<I>(&mut self, buffer: &mut Buffer, channels: I)
+    where
+        I: Clone + Iterator<Item = usize>,
+    {
+        let n_channels = buffer.channels();
+        if n_channels == 0 {
+            return;
+        }
+        for frame in buffer[..].chunks_mut(n_channels) {
+            let sample = self.generator.next(self.kind) * self.gain;
+            for channel in channels.clone() {
+                if channel < frame.len() {
+                    frame[channel] += sample;
+                }
+            }
+        }
+    }
+}
+
+impl Generator {
+    fn new() -> Self {
+        Generator {
+            rng: 0x9E37_79B9,
+            pink: [0.0; 7],
+            low_pass: 0.0,
+            high_pass: 0.0,
+            sweep_frame: 0,
+            tone_phase: 0.0,
+        }
+    }
+
+    // Produce the next sample of the given kind of signal.
+    fn next(&mut self, kind: Kind) -> f32 {
+        match kind {
+            Kind::Tone => self.tone(),
+            Kind::PinkNoise => self.pink(),
+            Kind::SineSweep => self.sweep(),
+            Kind::SpeechNoise => self.speech(),
+        }
+    }
+
+    // White noise in the range -1.0..1.0 via xorshift, avoiding allocation on the audio thread.
+    fn white(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        (self.rng as f64 / ::std::u32::MAX as f64 * 2.0 - 1.0) as f32
+    }
+
+    // Paul Kellet's refined pink noise filter.
+    fn pink(&mut self) -> f32 {
+        let white = self.white();
+        let b = &mut self.pink;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.1538520;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+        pink * PINK_GAIN
+    }
+
+    // A full scale sine at the reference frequency.
+    fn tone(&mut self) -> f32 {
+        let sample = (2.0 * PI * self.tone_phase).sin() as f32;
+        self.tone_phase = (self.tone_phase + TONE_HZ / sample_rate()) % 1.0;
+        sample
+    }
+
+    // Pink noise band-limited by one-pole filters.
+    fn speech(&mut self) -> f32 {
+        let pink = self.pink();
+        let lp = one_pole_coefficient(SPEECH_LOW_PASS_HZ);
+        let hp = one_pole_coefficient(SPEECH_HIGH_PASS_HZ);
+        self.low_pass += lp * (pink - self.low_pass);
+        self.high_pass += hp * (self.low_pass - self.high_pass);
+        self.low_pass - self.high_pass
+    }
+
+    // An exponential sine sweep that restarts once complete.
+    fn sweep(&mut self) -> f32 {
+        let sweep_frames = (SWEEP_SECS * sample_rate()) as usize;
+        let fade_frames = (FADE_SECS * sample_rate()) as usize;
+        let frame = self.sweep_frame % sweep_frames.max(1);
+        self.sweep_frame = frame + 1;
+        let sample = sweep_sample(frame as f64 / sample_rate());
+        sample * edge_fade(frame, sweep_frames, fade_frames)
+    }
+}
+
+// The sample of the sweep at the given time, integrating the exponentially rising frequency.
+fn sweep_sample(secs: f64) -> f32 {
+    let ratio = SWEEP_END_HZ / SWEEP_START_HZ;
+    let k = SWEEP_SECS / ratio.ln();
+    let phase = 2.0 * PI * SWEEP_START_HZ * k * ((secs / k).exp() - 1.0);
+    phase.sin() as f32
+}
+
+fn db_to_amp(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+/// The samples of a single complete sine sweep at full scale, as played by `Kind::SineSweep`.
+pub fn sweep() -> Vec<f32> {
+    let frames = (SWEEP_SECS * sample_rate()) as usize;
+    let mut generator = Generator::new();
+    (0..frames).map(|_| generator.sweep()).collect()
+}
+
+// The coefficient of a one-pole low-pass filter with the given cutoff.
+fn one_pole_coefficient(hz: f64) -> f32 {
+    (1.0 - (-2.0 * PI * hz / sample_rate()).exp()) as f32
+}
+
+// A raised-cosine fade in and out over the first and last `fade_frames` of `len` frames.
+fn edge_fade(frame: usize, len: usize, fade_frames: usize) -> f32 {
+    let edge = frame.min(len.saturating_sub(frame + 1));
+    if fade_frames == 0 || edge >= fade_frames {
+        return 1.0;
+    }
+    (0.5 - 0.5 * (PI * edge as f64 / fade_frames as f64).cos()) as f32
+}
+
+#[test]
+fn test_cycle() {
+    let mut signal = TestSignal::new(Kind::PinkNoise, 0.0, vec![0, 2]);
+    signal.interval_frames = 4;
+    let mut buffer = [0.0; 24];
+    signal.mix(&mut buffer, 3);
+    let frames: Vec<_> = buffer.chunks(3).collect();
+    assert!(frames[..4].iter().all(|f| f[1] == 0.0 && f[2] == 0.0));
+    assert!(frames[4..].iter().all(|f| f[0] == 0.0 && f[1] == 0.0));
+    assert!(frames[1][0] != 0.0 && frames[5][2] != 0.0);
+}
+
+#[test]
+fn test_sweep_sample() {
+    assert_eq!(sweep_sample(0.0), 0.0);
+    assert!((0..1000).all(|i| sweep_sample(i as f64 * 0.004).abs() <= 1.0));
+}
@@ -0,0 +1,247 @@
+//! A "walk test" that plays an identifying announcement from each speaker of an installation in
+//! turn, so that a single person can verify the patch by ear while walking the space.
+//!
+//! Each speaker's announcement is a pre-rendered WAV within the "assets/walk_test/" directory
+//! named after either the speaker (e.g. "Sculpture Left.wav") or its one-based output channel
+//! (e.g. "12.wav"). If neither exists, a sequence of pips is generated that counts out the
+//! one-based channel number digit by digit, with a single long tone for each zero.
+
+use audio::sample_rate;
+use audio::speaker;
+use hound::{self, SampleFormat};
+use nannou_audio::Buffer;
+use nannou_audio::sample::Sample;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+
+/// The name of the directory within the assets directory containing pre-rendered announcements.
+pub const DIRECTORY_STEM: &'static str = "walk_test";
+
+/// The default duration between the beginning of each speaker's announcement.
+pub const DEFAULT_INTERVAL_SECS: f64 = 3.0;
+
+/// The shortest interval between announcements.
+pub const MIN_INTERVAL_SECS: f64 = 1.0;
+
+/// The longest interval between announcements.
+pub const MAX_INTERVAL_SECS: f64 = 10.0;
+
+// The gain of generated announcements, roughly -12 dBFS.
+const PIP_GAIN: f32 = 0.25;
+const PIP_HZ: f64 = 1_000.0;
+const PIP_SECS: f64 = 0.08;
+const PIP_GAP_SECS: f64 = 0.12;
+const ZERO_SECS: f64 = 0.4;
+const DIGIT_GAP_SECS: f64 = 0.4;
+
+/// The announcement for a single speaker.
+#[derive(Clone, Debug)]
+pub struct Step {
+    /// The speaker being announced.
+    pub speaker: speaker::Id,
+    /// The output channel of the speaker.
+    pub channel: usize,
+    // The mono announcement samples.
+    samples: Vec<f32>,
+}
+
+/// A walk test underway on the audio output thread.
+///
+/// Cycles through the steps until removed.
+#[derive(Clone, Debug)]
+pub struct WalkTest {
+    steps: Vec<Step>,
+    interval_frames: usize,
+    step_index: usize,
+    frame: usize,
+}
+
+impl Step {
+    /// Create the step for the given speaker with the given mono announcement.
+    pub fn new(speaker: speaker::Id, channel: usize, samples: Vec<f32>) -> Self {
+        Step { speaker, channel, samples }
+    }
+
+    /// The duration of the step in seconds given the interval between announcements.
+    ///
+    /// Announcements that are longer than the interval are played in full.
+    pub fn duration_secs(&self, interval_secs: f64) -> f64 {
+        (self.samples.len() as f64 / sample_rate()).max(interval_secs)
+    }
+}
+
+impl WalkTest {
+    /// Begin a walk test through the given steps.
+    pub fn new(steps: Vec<Step>, interval_secs: f64) -> Self {
+        let interval_frames = (interval_secs.max(0.0) * sample_rate()) as usize;
+        WalkTest { steps, interval_frames, step_index: 0, frame: 0 }
+    }
+
+    /// The step currently being announced, if any.
+    pub fn current_step(&self) -> Option<&Step> {
+        self.steps.get(self.step_index)
+    }
+
+    /// Add the announcements to the given buffer, stepping forward through the speakers.
+    pub fn process(&mut self, buffer: &mut Buffer) {
+        let channels = buffer.channels();
+        self.mix(&mut buffer[..], channels);
+    }
+
+    // Add the announcements to the given interleaved samples.
+    fn mix(&mut self, samples: &mut [f32], channels: usize) {
+        if self.steps.is_empty() || channels == 0 {
+            return;
+        }
+        for frame in samples.chunks_mut(channels) {
+            let step = &self.steps[self.step_index];
+            if let Some(&sample) = step.samples.get(self.frame) {
+                if step.channel < frame.len() {
+                    frame[step.channel] += sample;
+                }
+            }
+            self.frame += 1;
+            if self.frame >= self.interval_frames.max(step.samples.len()) {
+                self.frame = 0;
+                self.step_index = (self.step_index + 1) % self.steps.len();
+            }
+        }
+    }
+}
+
+/// The path of the directory containing pre-rendered announcements.
+pub fn directory<P>(assets: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    assets.as_ref().join(DIRECTORY_STEM)
+}
+
+/// Load the pre-rendered announcement for the given speaker, falling back to generated pips.
+pub fn announcement<P>(assets: P, speaker_name: &str, channel: usize) -> Vec<f32>
+where
+    P: AsRef<Path>,
+{
+    let dir = directory(assets);
+    let names = [speaker_name.to_string(), format!("{}", channel + 1)];
+    for name in names.iter() {
+        let path = dir.join(name).with_extension("wav");
+        if !path.exists() {
+            continue;
+        }
+        match load_mono(&path) {
+            Ok(samples) => return samples,
+            Err(err) => eprintln!("failed to load announcement \"{}\": {}", path.display(), err),
+        }
+    }
+    pips(channel + 1)
+}
+
+/// Load the WAV at the given path, summing all channels to mono.
+///
+/// The WAV must be at the output `sample_rate()`.
+pub fn load_mono(path: &Path) -> Result<Vec<f32>, hound::Error> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+    if spec.sample_rate as f64 != sample_rate() {
+        return Err(hound::Error::Unsupported);
+    }
+    let channels = spec.channels.max(1) as usize;
+    macro_rules! read_samples {
+        ($T:ty) => {{
+            reader
+                .samples::<$T>()
+                .map(|r| r.map(Sample::to_sample::<f32>))
+                .collect::<Result<Vec<f32>, _>>()?
+        }};
+    }
+    let interleaved = match (spec.sample_format, spec.bits_per_sample) {
+        (SampleFormat::Float, 32) => read_samples!(f32),
+        (SampleFormat::Int, 8) => read_samples!(i8),
+        (SampleFormat::Int, 16) => read_samples!(i16),
+        (SampleFormat::Int, 32) => read_samples!(i32),
+        _ => return Err(hound::Error::Unsupported),
+    };
+    let gain = 1.0 / channels as f32;
+    let mono = interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() * gain)
+        .collect();
+    Ok(mono)
+}
+
+/// Generate pips counting out the given number digit by digit.
+///
+/// Each digit is counted with as many short pips, with a single long tone for zero.
+pub fn pips(number: usize) -> Vec<f32> {
+    let mut samples = vec![];
+    let digits = number.to_string();
+    for (i, digit) in digits.chars().filter_map(|c| c.to_digit(10)).enumerate() {
+        if i > 0 {
+            push_silence(&mut samples, DIGIT_GAP_SECS);
+        }
+        if digit == 0 {
+            push_tone(&mut samples, ZERO_SECS);
+            continue;
+        }
+        for pip in 0..digit {
+            if pip > 0 {
+                push_silence(&mut samples, PIP_GAP_SECS);
+            }
+            push_tone(&mut samples, PIP_SECS);
+        }
+    }
+    samples
+}
+
+fn secs_to_frames(secs: f64) -> usize {
+    (secs * sample_rate()) as usize
+}
+
+fn push_silence(samples: &mut Vec<f32>, secs: f64) {
+    let frames = secs_to_frames(secs);
+    samples.extend((0..frames).map(|_| 0.0));
+}
+
+// Push a sine tone with a short raised-cosine fade in and out to avoid clicks.
+fn push_tone(samples: &mut Vec<f32>, secs: f64) {
+    let frames = secs_to_frames(secs);
+    let fade_frames = secs_to_frames(0.005).min(frames / 2).max(1);
+    samples.extend((0..frames).map(|i| {
+        let edge = i.min(frames - 1 - i);
+        let fade = if edge < fade_frames {
+            0.5 - 0.5 * (PI * edge as f64 / fade_frames as f64).cos()
+        } else {
+            1.0
+        };
+        let phase = 2.0 * PI * PIP_HZ * i as f64 / sample_rate();
+        (phase.sin() * fade) as f32 * PIP_GAIN
+    }));
+}
+
+#[test]
+fn test_pips() {
+    let pip = secs_to_frames(PIP_SECS);
+    let gap = secs_to_frames(PIP_GAP_SECS);
+    assert_eq!(pips(3).len(), pip * 3 + gap * 2);
+    let expected = pip + secs_to_frames(DIGIT_GAP_SECS) + secs_to_frames(ZERO_SECS);
+    assert_eq!(pips(10).len(), expected);
+    assert!(pips(7).iter().all(|s| s.abs() <= PIP_GAIN));
+}
+
+#[test]
+fn test_steps() {
+    let steps = vec![
+        Step::new(speaker::Id(0), 0, vec![1.0; 4]),
+        Step::new(speaker::Id(1), 1, vec![1.0; 2]),
+    ];
+    let mut walk_test = WalkTest::new(steps, 0.0);
+    walk_test.interval_frames = 3;
+    assert_eq!(walk_test.steps[0].duration_secs(1.0), 1.0);
+    let mut buffer = [0.0; 16];
+    walk_test.mix(&mut buffer, 2);
+    // The first step plays in full as it is longer than the interval.
+    let expected = [1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0];
+    assert_eq!(&buffer[..14], &expected[..]);
+    assert_eq!(walk_test.current_step().map(|s| s.speaker), Some(speaker::Id(0)));
+}
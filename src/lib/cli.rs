@@ -0,0 +1,198 @@
+//! Overrides for the top-level config via command line flags and environment variables.
+//!
+//! Allows provisioned deployments to select the assets directory, project, OSC input port and
+//! audio devices without templating the "assets/config.json" file. Flags take precedence over
+//! environment variables which in turn take precedence over the config file. Overridden values are
+//! never written back to the config file.
+
+use config::Config;
+use std::env;
+use std::path::PathBuf;
+
+/// The usage message printed for `--help` or upon an invalid argument.
+pub const USAGE: &'static str = "\
+USAGE:
+    audio_server [OPTIONS]
+
+OPTIONS:
+    --assets <PATH>            The assets directory.        [env: AUDIO_SERVER_ASSETS]
+    --project <SLUG>           The project to open.         [env: AUDIO_SERVER_PROJECT]
+    --osc-input-port <PORT>    The OSC input port.          [env: AUDIO_SERVER_OSC_INPUT_PORT]
+    --input-device <NAME>      The audio input device.      [env: AUDIO_SERVER_INPUT_DEVICE]
+    --output-device <NAME>     The audio output device.     [env: AUDIO_SERVER_OUTPUT_DEVICE]
+    --headless                 Run without showing the GUI. [env: AUDIO_SERVER_HEADLESS]
+    --help                     Print this message.
+";
+
+const ENV_ASSETS: &'static str = "AUDIO_SERVER_ASSETS";
+const ENV_PROJECT: &'static str = "AUDIO_SERVER_PROJECT";
+const ENV_OSC_INPUT_PORT: &'static str = "AUDIO_SERVER_OSC_INPUT_PORT";
+const ENV_INPUT_DEVICE: &'static str = "AUDIO_SERVER_INPUT_DEVICE";
+const ENV_OUTPUT_DEVICE: &'static str = "AUDIO_SERVER_OUTPUT_DEVICE";
+const ENV_HEADLESS: &'static str = "AUDIO_SERVER_HEADLESS";
+
+/// Config values specified via the command line or environment.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Overrides {
+    /// The assets directory to use in place of the one found next to the executable.
+    pub assets: Option<PathBuf>,
+    /// The slug of the project to open.
+    pub project_slug: Option<String>,
+    /// The port on which OSC input is received.
+    pub osc_input_port: Option<u16>,
+    /// The name of the target audio input device.
+    pub input_device_name: Option<String>,
+    /// The name of the target audio output device.
+    pub output_device_name: Option<String>,
+    /// Whether the server runs without showing the GUI.
+    ///
+    /// The window is hidden and never drawn and CPU saving mode is enabled.
+    pub headless: bool,
+}
+
+/// The result of parsing the command line.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Parsed {
+    /// Run the server with the given overrides.
+    Run(Overrides),
+    /// Print the usage message and exit.
+    Help,
+}
+
+impl Overrides {
+    /// Read the overrides from the process' environment variables and command line arguments.
+    pub fn from_env_and_args() -> Result<Parsed, String> {
+        Self::parse(|key| env::var(key).ok(), env::args().skip(1))
+    }
+
+    /// Parse the overrides from the given environment variable lookup and arguments, excluding
+    /// the program name.
+    pub fn parse<F, I>(var: F, args: I) -> Result<Parsed, String>
+    where
+        F: Fn(&str) -> Option<String>,
+        I: IntoIterator<Item = String>,
+    {
+        let mut overrides = Overrides::default();
+        let non_empty = |key| var(key).and_then(|v| if v.is_empty() { None } else { Some(v) });
+
+        // Environment variables.
+        overrides.assets = non_empty(ENV_ASSETS).map(PathBuf::from);
+        overrides.project_slug = non_empty(ENV_PROJECT);
+        if let Some(port) = non_empty(ENV_OSC_INPUT_PORT) {
+            overrides.osc_input_port = Some(parse_port(ENV_OSC_INPUT_PORT, &port)?);
+        }
+        overrides.input_device_name = non_empty(ENV_INPUT_DEVICE);
+        overrides.output_device_name = non_empty(ENV_OUTPUT_DEVICE);
+        if let Some(headless) = non_empty(ENV_HEADLESS) {
+            overrides.headless = parse_bool(ENV_HEADLESS, &headless)?;
+        }
+
+        // Command line flags, accepting either `--flag value` or `--flag=value`.
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.find('=') {
+                Some(ix) => (arg[..ix].to_string(), Some(arg[ix + 1..].to_string())),
+                None => (arg.clone(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("missing value for `{}`", flag))
+            };
+            match &flag[..] {
+                "--help" | "-h" => return Ok(Parsed::Help),
+                "--assets" => overrides.assets = Some(PathBuf::from(value()?)),
+                "--project" => overrides.project_slug = Some(value()?),
+                "--osc-input-port" => {
+                    overrides.osc_input_port = Some(parse_port(&flag, &value()?)?);
+                },
+                "--input-device" => overrides.input_device_name = Some(value()?),
+                "--output-device" => overrides.output_device_name = Some(value()?),
+                "--headless" => overrides.headless = true,
+                _ => return Err(format!("unrecognised argument `{}`", arg)),
+            }
+        }
+
+        Ok(Parsed::Run(overrides))
+    }
+
+    /// Apply the overrides to the given config.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(ref slug) = self.project_slug {
+            config.selected_project_slug = slug.clone();
+        }
+        if let Some(port) = self.osc_input_port {
+            config.project_default.osc_input_port = port;
+        }
+        if let Some(ref name) = self.input_device_name {
+            config.target_input_device_name = name.clone();
+        }
+        if let Some(ref name) = self.output_device_name {
+            config.target_output_device_name = name.clone();
+        }
+        if self.headless {
+            config.cpu_saving_mode = true;
+        }
+    }
+
+    /// Reset all overridden fields of `config` to their values within `saved`.
+    ///
+    /// Used before saving the config so that overrides are not persisted.
+    pub fn restore(&self, config: &mut Config, saved: &Config) {
+        if self.project_slug.is_some() {
+            config.selected_project_slug = saved.selected_project_slug.clone();
+        }
+        if self.osc_input_port.is_some() {
+            config.project_default.osc_input_port = saved.project_default.osc_input_port;
+        }
+        if self.input_device_name.is_some() {
+            config.target_input_device_name = saved.target_input_device_name.clone();
+        }
+        if self.output_device_name.is_some() {
+            config.target_output_device_name = saved.target_output_device_name.clone();
+        }
+        if self.headless {
+            config.cpu_saving_mode = saved.cpu_saving_mode;
+        }
+    }
+}
+
+fn parse_port(name: &str, value: &str) -> Result<u16, String> {
+    value.parse().map_err(|_| format!("invalid port `{}` for `{}`", value, name))
+}
+
+fn parse_bool(name: &str, value: &str) -> Result<bool, String> {
+    match &value.to_lowercase()[..] {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("invalid value `{}` for `{}`", value, name)),
+    }
+}
+
+#[test]
+fn test_parse() {
+    let args = |s: &str| s.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+    let env = |key: &str| match key {
+        ENV_PROJECT => Some("from-env".to_string()),
+        ENV_OSC_INPUT_PORT => Some("9000".to_string()),
+        ENV_HEADLESS => Some("1".to_string()),
+        _ => None,
+    };
+
+    // Flags take precedence over the environment.
+    let parsed = Overrides::parse(&env, args("--project from-args --output-device=Dante"));
+    let expected = Overrides {
+        project_slug: Some("from-args".into()),
+        osc_input_port: Some(9000),
+        output_device_name: Some("Dante".into()),
+        headless: true,
+        ..Default::default()
+    };
+    assert_eq!(parsed, Ok(Parsed::Run(expected)));
+
+    assert_eq!(Overrides::parse(|_| None, args("--help")), Ok(Parsed::Help));
+    assert!(Overrides::parse(|_| None, args("--osc-input-port nope")).is_err());
+    assert!(Overrides::parse(|_| None, args("--project")).is_err());
+    assert!(Overrides::parse(|_| None, args("--unknown")).is_err());
+}
@@ -1,3 +1,5 @@
+use audio;
+use gui;
 use project;
 use std::ops::Deref;
 
@@ -13,6 +15,18 @@ pub struct Config {
     /// Whether or not CPU saving mode is enabled upon opening the server.
     #[serde(default = "default::cpu_saving_mode")]
     pub cpu_saving_mode: bool,
+    /// Whether or not touch interaction mode is enabled upon opening the server.
+    ///
+    /// Touch mode enlarges hit targets on the floorplan and enables pinch-to-zoom, two-finger
+    /// panning and long-press context menus.
+    #[serde(default)]
+    pub touch_mode: bool,
+    /// The scale, ballistics and peak hold of the master meter.
+    #[serde(default)]
+    pub master_meter: gui::meter::Settings,
+    /// The labels, size and hover cards of the active sounds displayed over the floorplan.
+    #[serde(default)]
+    pub sound_widgets: gui::custom_widget::sound::Settings,
     /// Specify the name of the device that the audio server should use as the input audio device.
     /// The first device that contains the given string will be selected.
     ///
@@ -26,6 +40,16 @@ pub struct Config {
     /// selected.
     #[serde(default)]
     pub target_output_device_name: String,
+    /// The names of additional output devices whose channels follow those of the target output
+    /// device, presenting the channels of all devices as one contiguous range.
+    ///
+    /// The first device that contains each string is selected. Devices that cannot be found are
+    /// skipped.
+    #[serde(default)]
+    pub aggregate_output_device_names: Vec<String>,
+    /// The sample rate and buffer size with which the audio streams are built.
+    #[serde(default)]
+    pub stream: audio::StreamConfig,
 }
 
 impl Default for Config {
@@ -33,14 +57,24 @@ impl Default for Config {
         let project_default = Default::default();
         let selected_project_slug = default::project_slug();
         let cpu_saving_mode = Default::default();
+        let touch_mode = Default::default();
+        let master_meter = Default::default();
+        let sound_widgets = Default::default();
         let target_input_device_name = Default::default();
         let target_output_device_name = Default::default();
+        let aggregate_output_device_names = Default::default();
+        let stream = Default::default();
         Config {
             project_default,
             selected_project_slug,
             cpu_saving_mode,
+            touch_mode,
+            master_meter,
+            sound_widgets,
             target_input_device_name,
             target_output_device_name,
+            aggregate_output_device_names,
+            stream,
         }
     }
 }
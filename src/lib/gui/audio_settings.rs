@@ -0,0 +1,181 @@
+//! The "Audio Settings" panel for selecting the sample rate and buffer size of the audio streams.
+//!
+//! Everything from the speaker delay lines to the WAV readers is sized for the stream
+//! configuration with which the streams were built. Rather than resizing all of these in place,
+//! applying a selection closes the streams and rebuilds them with fresh models, to which the
+//! project is then synced.
+//!
+//! The output word length and dither only affect the final output and so apply immediately.
+
+use audio;
+use audio::dither::WordLength;
+use gui::{collapsible_area, info_text, Channels, Gui, ITEM_HEIGHT, SMALL_FONT_SIZE};
+use nannou::ui::prelude::*;
+
+/// The stream configuration selected within the panel.
+pub struct AudioSettings {
+    /// The configuration with which the running audio streams were built.
+    pub running: audio::StreamConfig,
+    /// The configuration selected within the panel, yet to be applied.
+    pub selected: audio::StreamConfig,
+    /// Whether the streams should be rebuilt with the selected configuration.
+    pub apply: bool,
+}
+
+impl AudioSettings {
+    /// Begin with the given configuration selected and running.
+    pub fn new(running: audio::StreamConfig) -> Self {
+        let selected = running;
+        let apply = false;
+        AudioSettings { running, selected, apply }
+    }
+}
+
+pub fn set(last_area_id: widget::Id, gui: &mut Gui) -> widget::Id {
+    let is_open = gui.state.is_open.audio_settings;
+    const PAD: Scalar = 10.0;
+    const TEXT_H: Scalar = 40.0;
+    const CANVAS_H: Scalar = PAD + TEXT_H + PAD + (ITEM_HEIGHT + PAD) * 5.0;
+    let (area, event) = collapsible_area(is_open, "Audio Settings", gui.ids.side_menu)
+        .align_middle_x_of(gui.ids.side_menu)
+        .down_from(last_area_id, 0.0)
+        .set(gui.ids.audio_settings, gui);
+    if let Some(event) = event {
+        gui.state.is_open.audio_settings = event.is_open();
+    }
+
+    let area = match area {
+        Some(area) => area,
+        None => return gui.ids.audio_settings,
+    };
+
+    let canvas = widget::Canvas::new().pad(PAD).h(CANVAS_H);
+    area.set(canvas, gui);
+
+    let running = gui.state.audio_settings.running;
+    let selected = gui.state.audio_settings.selected;
+    let text = format!(
+        "Running at {} with {}.\nApplying changes briefly interrupts all audio.",
+        sample_rate_label(running.sample_rate),
+        frames_per_buffer_label(running.frames_per_buffer, running.sample_rate),
+    );
+    info_text(&text)
+        .top_left_of(area.id)
+        .kid_area_w_of(area.id)
+        .set(gui.ids.audio_settings_text, gui);
+
+    // The sample rate of all input and output streams.
+    let labels: Vec<_> = audio::SAMPLE_RATES.iter().map(|&hz| sample_rate_label(hz)).collect();
+    let index = audio::SAMPLE_RATES.iter().position(|&hz| hz == selected.sample_rate);
+    for index in widget::DropDownList::new(&labels, index)
+        .label("Sample Rate")
+        .label_font_size(SMALL_FONT_SIZE)
+        .down_from(gui.ids.audio_settings_text, PAD)
+        .align_left_of(gui.ids.audio_settings_text)
+        .kid_area_w_of(area.id)
+        .h(ITEM_HEIGHT)
+        .set(gui.ids.audio_settings_sample_rate, gui)
+    {
+        gui.state.audio_settings.selected.sample_rate = audio::SAMPLE_RATES[index];
+    }
+
+    // The number of frames requested at a time.
+    let options = audio::FRAMES_PER_BUFFER_OPTIONS;
+    let labels: Vec<_> = options
+        .iter()
+        .map(|&frames| frames_per_buffer_label(frames, selected.sample_rate))
+        .collect();
+    let index = options.iter().position(|&frames| frames == selected.frames_per_buffer);
+    for index in widget::DropDownList::new(&labels, index)
+        .label("Buffer Size")
+        .label_font_size(SMALL_FONT_SIZE)
+        .down(PAD)
+        .kid_area_w_of(area.id)
+        .h(ITEM_HEIGHT)
+        .set(gui.ids.audio_settings_frames_per_buffer, gui)
+    {
+        gui.state.audio_settings.selected.frames_per_buffer = options[index];
+    }
+
+    // The word length of the output device and whether to dither when reducing to it.
+    let labels: Vec<_> = WordLength::ALL.iter().map(|w| w.label()).collect();
+    let index = WordLength::ALL.iter().position(|&w| w == selected.output_word_length);
+    let half_w = (gui.kid_area_of(area.id).unwrap().w() - PAD) / 2.0;
+    for index in widget::DropDownList::new(&labels, index)
+        .label("Output Word Length")
+        .label_font_size(SMALL_FONT_SIZE)
+        .down(PAD)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .set(gui.ids.audio_settings_word_length, gui)
+    {
+        let word_length = WordLength::ALL[index];
+        set_dither(&gui.channels, &mut gui.state.audio_settings, word_length, selected.dither);
+    }
+
+    let is_float = selected.output_word_length == WordLength::Float;
+    let label = match selected.dither {
+        true => "TPDF Dither On",
+        false => "TPDF Dither Off",
+    };
+    for dither in widget::Toggle::new(selected.dither)
+        .label(label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .right(PAD)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .enabled(!is_float)
+        .set(gui.ids.audio_settings_dither, gui)
+    {
+        let word_length = selected.output_word_length;
+        set_dither(&gui.channels, &mut gui.state.audio_settings, word_length, dither);
+    }
+
+    // Rebuild the streams with the selection.
+    let label = match selected == running {
+        true => "NO CHANGES TO APPLY",
+        false => "APPLY",
+    };
+    for _click in widget::Button::new()
+        .label(label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .down_from(gui.ids.audio_settings_word_length, PAD)
+        .kid_area_w_of(area.id)
+        .h(ITEM_HEIGHT)
+        .set(gui.ids.audio_settings_apply, gui)
+    {
+        if selected != running {
+            gui.state.audio_settings.apply = true;
+        }
+    }
+
+    area.id
+}
+
+/// Apply the given output word length and dither to the running output stream.
+///
+/// Unlike the rest of the stream configuration, the streams need not be rebuilt.
+pub fn set_dither(
+    channels: &Channels,
+    audio_settings: &mut AudioSettings,
+    word_length: WordLength,
+    dither: bool,
+) {
+    for config in &mut [&mut audio_settings.selected, &mut audio_settings.running] {
+        config.output_word_length = word_length;
+        config.dither = dither;
+    }
+    channels
+        .audio_output
+        .send(move |audio| audio.dither.set(word_length, dither))
+        .expect("failed to send output dither to audio output thread");
+}
+
+fn sample_rate_label(hz: u32) -> String {
+    format!("{} kHz", hz as f64 / 1_000.0)
+}
+
+fn frames_per_buffer_label(frames: usize, sample_rate: u32) -> String {
+    let ms = frames as f64 * 1_000.0 / sample_rate as f64;
+    format!("{} frames ({:.1} ms)", frames, ms)
+}
@@ -0,0 +1,96 @@
+//! A thread dedicated to watching the "assets/audio/" directory for changes to WAV files and
+//! propagating the paths of changed files to the GUI.
+//!
+//! This allows for iterating upon a sound without having to remove and re-add its source.
+
+use nannou;
+use notify::{self, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// The duration over which file system events are debounced before being delivered.
+///
+/// Audio editors tend to write files in multiple stages, so we wait for the file to settle.
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(500);
+
+pub type Receiver = mpsc::Receiver<PathBuf>;
+pub type Spawned = (AudioWatcher, Receiver);
+
+/// A handle to the audio directory watching thread.
+pub struct AudioWatcher {
+    watcher: notify::RecommendedWatcher,
+    thread: thread::JoinHandle<()>,
+}
+
+impl AudioWatcher {
+    /// Stops watching and waits for the thread to finish.
+    pub fn join(self) -> thread::Result<()> {
+        let AudioWatcher { watcher, thread } = self;
+        // Dropping the watcher closes the event channel, in turn ending the thread.
+        drop(watcher);
+        thread.join()
+    }
+}
+
+/// Begin watching the given audio directory and spawn the intermediary thread.
+///
+/// Returns a receiver yielding the path of each WAV file that is created, modified or removed.
+pub fn spawn<P>(audio_path: P, app_proxy: nannou::app::Proxy) -> notify::Result<Spawned>
+where
+    P: AsRef<Path>,
+{
+    let (event_tx, event_rx) = mpsc::channel();
+    let mut watcher = notify::watcher(event_tx, DEBOUNCE_INTERVAL)?;
+    watcher.watch(audio_path, notify::RecursiveMode::Recursive)?;
+
+    let (path_tx, path_rx) = mpsc::channel();
+    let thread = thread::Builder::new()
+        .name("gui_audio_watcher".into())
+        .spawn(move || {
+            for event in event_rx {
+                let path = match event {
+                    notify::DebouncedEvent::Create(path)
+                    | notify::DebouncedEvent::Write(path)
+                    | notify::DebouncedEvent::Remove(path) => path,
+                    // Sources referring to the old path are then found to be missing.
+                    notify::DebouncedEvent::Rename(old_path, path) => {
+                        if is_wav(&old_path) && path_tx.send(old_path).is_err() {
+                            break;
+                        }
+                        path
+                    },
+                    notify::DebouncedEvent::Error(err, path) => {
+                        eprintln!("error while watching audio directory {:?}: {}", path, err);
+                        continue;
+                    },
+                    _ => continue,
+                };
+                if !is_wav(&path) {
+                    continue;
+                }
+                if path_tx.send(path).is_err() {
+                    break;
+                }
+                // Proxy is currently buggy on linux so we only enable this for macos.
+                if cfg!(target_os = "macos") {
+                    if app_proxy.wakeup().is_err() {
+                        eprintln!("audio_watcher proxy could not wakeup app");
+                        break;
+                    }
+                }
+            }
+        })?;
+
+    let audio_watcher = AudioWatcher { watcher, thread };
+    Ok((audio_watcher, path_rx))
+}
+
+/// Whether or not the given path has a ".wav" extension.
+fn is_wav(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("wav"))
+        .unwrap_or(false)
+}
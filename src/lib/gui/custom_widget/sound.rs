@@ -1,5 +1,6 @@
 //! A visual representation of a `Sound` for displaying over the floorplan.
 
+use gui::SMALL_FONT_SIZE;
 use metres::Metres;
 use nannou::ui::Color;
 use nannou::ui::prelude::*;
@@ -22,6 +23,10 @@ pub struct Sound<'a> {
     channel_radians: f64,
     // The normalised playback progress through the sound.
     progress: Option<f64>,
+    // Text displayed beneath the sound.
+    label: Option<&'a str>,
+    // Text displayed on a card beside the sound while hovered.
+    details: Option<&'a str>,
 }
 
 #[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
@@ -30,6 +35,32 @@ pub struct Style {
     pub color: Option<Color>,
 }
 
+/// The text displayed beneath each active sound on the floorplan.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum Label {
+    None,
+    SourceName,
+    Remaining,
+    SourceNameAndRemaining,
+}
+
+/// The number of variants within the `Label` enum.
+pub const NUM_LABELS: usize = 4;
+
+/// User configuration of the active sounds displayed over the floorplan.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Settings {
+    /// The text displayed beneath each sound.
+    #[serde(default = "default::label")]
+    pub label: Label,
+    /// A multiplier applied to the size of each sound.
+    #[serde(default = "default::scale")]
+    pub scale: f64,
+    /// Whether hovering over a sound displays a card with its details.
+    #[serde(default = "default::hover_card")]
+    pub hover_card: bool,
+}
+
 widget_ids! {
     struct Ids {
         circle,
@@ -38,6 +69,9 @@ widget_ids! {
         channel_lines[],
         channel_circles[],
         channel_labels[],
+        label,
+        card,
+        card_text,
     }
 }
 
@@ -45,6 +79,36 @@ pub struct State {
     ids: Ids,
 }
 
+impl Label {
+    /// All variants in the order in which they are displayed.
+    pub const ALL: [Label; NUM_LABELS] = [
+        Label::None,
+        Label::SourceName,
+        Label::Remaining,
+        Label::SourceNameAndRemaining,
+    ];
+
+    /// The name of the label for display.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Label::None => "No Label",
+            Label::SourceName => "Source Name",
+            Label::Remaining => "Remaining Time",
+            Label::SourceNameAndRemaining => "Name & Remaining",
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            label: default::label(),
+            scale: default::scale(),
+            hover_card: default::hover_card(),
+        }
+    }
+}
+
 pub fn dimension_metres(amplitude: f32) -> Metres {
     let min = Sound::MIN_DIMENSION.0;
     let max = Sound::MAX_DIMENSION.0;
@@ -65,6 +129,8 @@ impl<'a> Sound<'a> {
             radians,
             channel_radians,
             progress: None,
+            label: None,
+            details: None,
         }
     }
 
@@ -73,6 +139,18 @@ impl<'a> Sound<'a> {
         self.progress = Some(progress);
         self
     }
+
+    /// Text to display beneath the sound.
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Text to display on a card beside the sound while it is hovered.
+    pub fn details(mut self, details: &'a str) -> Self {
+        self.details = Some(details);
+        self
+    }
 }
 
 impl<'a> Widget for Sound<'a> {
@@ -107,6 +185,8 @@ impl<'a> Widget for Sound<'a> {
             radians,
             channel_radians,
             progress,
+            label,
+            details,
             ..
         } = self;
 
@@ -241,6 +321,43 @@ impl<'a> Widget for Sound<'a> {
             .graphics_for(id)
             .parent(id)
             .set(state.ids.triangle, ui);
+
+        // The label beneath the sound.
+        if let Some(label) = label {
+            widget::Text::new(label)
+                .font_size(SMALL_FONT_SIZE)
+                .x_y(x, y - radius - SMALL_FONT_SIZE as Scalar)
+                .color(color)
+                .graphics_for(id)
+                .parent(id)
+                .set(state.ids.label, ui);
+        }
+
+        // A card displaying the details of the sound while hovered.
+        if let Some(details) = details {
+            if ui.widget_input(id).mouse().is_some() {
+                const CARD_PAD: Scalar = 6.0;
+                const CARD_W: Scalar = 200.0;
+                let line_h = SMALL_FONT_SIZE as Scalar + 4.0;
+                let card_h = details.lines().count() as Scalar * line_h + CARD_PAD * 2.0;
+                let card_x = x + radius + CARD_PAD + CARD_W / 2.0;
+                widget::Rectangle::fill_with([CARD_W, card_h], color::DARK_CHARCOAL.alpha(0.9))
+                    .x_y(card_x, y)
+                    .floating(true)
+                    .graphics_for(id)
+                    .parent(id)
+                    .set(state.ids.card, ui);
+                widget::Text::new(details)
+                    .font_size(SMALL_FONT_SIZE)
+                    .line_spacing(4.0)
+                    .w(CARD_W - CARD_PAD * 2.0)
+                    .top_left_with_margin_on(state.ids.card, CARD_PAD)
+                    .color(color::WHITE)
+                    .graphics_for(id)
+                    .parent(state.ids.card)
+                    .set(state.ids.card_text, ui);
+            }
+        }
     }
 }
 
@@ -250,3 +367,19 @@ impl<'a> Colorable for Sound<'a> {
         self
     }
 }
+
+mod default {
+    use super::Label;
+
+    pub fn label() -> Label {
+        Label::None
+    }
+
+    pub fn scale() -> f64 {
+        1.0
+    }
+
+    pub fn hover_card() -> bool {
+        true
+    }
+}
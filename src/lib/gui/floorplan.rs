@@ -0,0 +1,483 @@
+//! Loading and rendering of the floorplan image.
+//!
+//! The floorplan is loaded from the first of "floorplan.svg", "floorplan.pdf" and "floorplan.png"
+//! found within the images directory. Vector floorplans are measured in SVG user units, each
+//! treated as a single pixel when applying the project's `floorplan_pixels_per_metre`.
+//!
+//! A vector floorplan is rasterised once at its natural size for display while zooming and
+//! panning. Whenever the view changes, the visible region is re-rendered at the resolution of the
+//! floorplan canvas on a dedicated thread and displayed in place of the natural size raster once
+//! ready, so that the floorplan remains sharp at any zoom level.
+//!
+//! Only the first page of a PDF is used. PDFs are converted to SVG via `pdftocairo`, part of
+//! poppler, which must be installed and on the `PATH`. The converted SVG is cached beside the PDF
+//! and regenerated whenever the PDF is newer.
+//!
+//! If no floorplan can be loaded, a blank placeholder is displayed in its place.
+
+use nannou::image::{self, RgbaImage};
+use nannou::prelude::*;
+use nannou::ui;
+use resvg;
+use std::cell::Cell;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc;
+use std::thread;
+use metres::Metres;
+use super::Image;
+use tiny_skia;
+use usvg;
+
+/// The stem of the floorplan file within the images directory.
+const FILE_STEM: &'static str = "floorplan";
+
+/// The greatest camera zoom for vector floorplans, i.e. 16 times their original resolution.
+pub const MAX_VECTOR_ZOOM: f64 = 16.0;
+
+/// The name of the SVG cached from the conversion of a PDF floorplan.
+const PDF_CACHE_FILE_NAME: &'static str = "floorplan.pdf.svg";
+
+/// The size in pixels of the blank floorplan displayed when none can be loaded.
+const PLACEHOLDER_SIZE: [u32; 2] = [1024, 768];
+
+/// The orientation and position of the floorplan image relative to the exhibition's coordinates.
+///
+/// Venue drawings often arrive rotated or mirrored relative to the desired orientation on screen.
+/// The image is first mirrored, then rotated and finally offset. Speakers and other positions
+/// within the exhibition are unaffected; only the image beneath them moves.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Transform {
+    /// The number of quarter turns anticlockwise, from 0 to 3.
+    #[serde(default)]
+    pub quarter_turns: u8,
+    /// Whether the image is mirrored left to right.
+    #[serde(default)]
+    pub mirror: bool,
+    /// The offset of the image along the x axis.
+    #[serde(default)]
+    pub offset_x: Metres,
+    /// The offset of the image along the y axis.
+    #[serde(default)]
+    pub offset_y: Metres,
+}
+
+/// The floorplan image and the state required to transform and re-render it.
+pub struct Floorplan {
+    /// The transformed floorplan at its natural resolution.
+    pub image: Image,
+    // The untransformed floorplan at its natural resolution.
+    natural: RgbaImage,
+    // The map from natural to transformed floorplan pixels.
+    affine: Affine,
+    // Present for vector floorplans.
+    vector: Option<Vector>,
+}
+
+// Re-renders the visible region of a vector floorplan at the resolution of the view.
+struct Vector {
+    // The view most recently requested from the rendering thread.
+    requested: Cell<Option<View>>,
+    // The rendered view and the ID of its image, if any.
+    rendered: Option<(View, ui::image::Id)>,
+    request_tx: mpsc::Sender<View>,
+    rendered_rx: mpsc::Receiver<(View, RgbaImage)>,
+}
+
+// A region of the floorplan rendered at a specific resolution.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct View {
+    // The visible region in transformed floorplan pixels, measured from the bottom left.
+    rect: ui::Rect,
+    // The resolution at which the region is displayed.
+    size: [u32; 2],
+    // The map from natural to transformed floorplan pixels.
+    affine: Affine,
+}
+
+// An affine map between floorplan pixel coordinates, i.e. `p' = m * p + t`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Affine {
+    m: [[f64; 2]; 2],
+    t: [f64; 2],
+}
+
+impl Transform {
+    /// Rotate the image by a further quarter turn anticlockwise.
+    pub fn rotate(&mut self) {
+        self.quarter_turns = (self.quarter_turns + 1) % 4;
+    }
+
+    // The map from natural to transformed floorplan pixels along with the transformed size.
+    fn affine(&self, [w, h]: [f64; 2], pixels_per_metre: f64) -> (Affine, [f64; 2]) {
+        let mirror = match self.mirror {
+            true => Affine { m: [[-1.0, 0.0], [0.0, 1.0]], t: [w, 0.0] },
+            false => Affine::IDENTITY,
+        };
+        let (rotate, [w, h]) = match self.quarter_turns % 4 {
+            0 => (Affine::IDENTITY, [w, h]),
+            1 => (Affine { m: [[0.0, -1.0], [1.0, 0.0]], t: [h, 0.0] }, [h, w]),
+            2 => (Affine { m: [[-1.0, 0.0], [0.0, -1.0]], t: [w, h] }, [w, h]),
+            _ => (Affine { m: [[0.0, 1.0], [-1.0, 0.0]], t: [0.0, w] }, [h, w]),
+        };
+        let offset = [self.offset_x.0 * pixels_per_metre, self.offset_y.0 * pixels_per_metre];
+        let translate = Affine { m: Affine::IDENTITY.m, t: offset };
+        let affine = mirror.then(&rotate).then(&translate);
+        let size = [(w + offset[0]).max(1.0), (h + offset[1]).max(1.0)];
+        (affine, size)
+    }
+}
+
+impl Floorplan {
+    /// The image of the given view of a vector floorplan if it has been rendered.
+    ///
+    /// Otherwise, requests that the view be rendered and returns `None`. Always returns `None` for
+    /// raster floorplans.
+    pub fn view(&self, rect: ui::Rect, [w, h]: [f64; 2]) -> Option<ui::image::Id> {
+        let vector = match self.vector {
+            None => return None,
+            Some(ref vector) => vector,
+        };
+        if w < 1.0 || h < 1.0 {
+            return None;
+        }
+        let size = [w.round() as u32, h.round() as u32];
+        let view = View { rect, size, affine: self.affine };
+        if let Some((rendered, id)) = vector.rendered {
+            if rendered == view {
+                return Some(id);
+            }
+        }
+        if vector.requested.get() != Some(view) {
+            vector.requested.set(Some(view));
+            vector.request_tx.send(view).ok();
+        }
+        None
+    }
+
+    /// Render the given region of the transformed floorplan at the given resolution, e.g. for
+    /// export.
+    ///
+    /// `rect` is measured in transformed floorplan pixels from the bottom left. Vector floorplans
+    /// are re-rendered from the images directory so that the result is sharp at any resolution.
+    pub fn render_view(
+        &self,
+        images_directory: &Path,
+        rect: ui::Rect,
+        size: [u32; 2],
+    ) -> Result<RgbaImage, String> {
+        let view = View { rect, size, affine: self.affine };
+        if self.vector.is_some() {
+            let path = svg_path(images_directory)
+                .map_err(|err| err.to_string())?
+                .ok_or_else(|| "no vector floorplan found".to_string())?;
+            let data = fs::read(&path).map_err(|err| err.to_string())?;
+            let tree = parse(&data)?;
+            let height = tree.svg_node().size.height();
+            return render(&tree, height, view).ok_or_else(|| "failed to render".to_string());
+        }
+        let inverse = self.affine.inverse();
+        let [w, h] = size;
+        let image = RgbaImage::from_fn(w, h, |col, row| {
+            let x = rect.left() + (col as f64 + 0.5) * rect.w() / w as f64;
+            let y = rect.top() - (row as f64 + 0.5) * rect.h() / h as f64;
+            sample(&self.natural, inverse.apply([x, y]))
+        });
+        Ok(image)
+    }
+
+    /// Whether the floorplan was loaded from a vector image.
+    pub fn is_vector(&self) -> bool {
+        self.vector.is_some()
+    }
+
+    /// Apply the given transform if it has changed and upload the latest view rendered since the
+    /// last update, if any.
+    pub fn update(
+        &mut self,
+        app: &App,
+        window_id: WindowId,
+        ui: &mut ui::Ui,
+        transform: Transform,
+        pixels_per_metre: f64,
+    ) {
+        let natural_size = [self.natural.width() as f64, self.natural.height() as f64];
+        let (affine, size) = transform.affine(natural_size, pixels_per_metre);
+        let size = [size[0].round(), size[1].round()];
+        if (affine, size) != (self.affine, [self.image.width, self.image.height]) {
+            let image = transform_image(&self.natural, &affine, size);
+            if let Some(texture) = texture(app, window_id, &image) {
+                let [width, height] = texture.size();
+                ui.image_map.replace(self.image.id, texture.into_ui_image());
+                self.image.width = width as f64;
+                self.image.height = height as f64;
+                self.affine = affine;
+            }
+        }
+
+        let vector = match self.vector {
+            None => return,
+            Some(ref mut vector) => vector,
+        };
+        let (view, image) = match vector.rendered_rx.try_iter().last() {
+            None => return,
+            Some(rendered) => rendered,
+        };
+        let texture = match texture(app, window_id, &image) {
+            None => return,
+            Some(texture) => texture,
+        };
+        let id = match vector.rendered {
+            Some((_, id)) => {
+                ui.image_map.replace(id, texture.into_ui_image());
+                id
+            },
+            None => ui.image_map.insert(texture.into_ui_image()),
+        };
+        vector.rendered = Some((view, id));
+    }
+}
+
+impl Affine {
+    const IDENTITY: Affine = Affine { m: [[1.0, 0.0], [0.0, 1.0]], t: [0.0, 0.0] };
+
+    fn apply(&self, [x, y]: [f64; 2]) -> [f64; 2] {
+        let Affine { m, t } = *self;
+        [m[0][0] * x + m[0][1] * y + t[0], m[1][0] * x + m[1][1] * y + t[1]]
+    }
+
+    // The map applying `self` followed by `other`.
+    fn then(&self, other: &Affine) -> Affine {
+        let (a, b) = (self.m, other.m);
+        let m = [
+            [b[0][0] * a[0][0] + b[0][1] * a[1][0], b[0][0] * a[0][1] + b[0][1] * a[1][1]],
+            [b[1][0] * a[0][0] + b[1][1] * a[1][0], b[1][0] * a[0][1] + b[1][1] * a[1][1]],
+        ];
+        let t = other.apply(self.t);
+        Affine { m, t }
+    }
+
+    fn inverse(&self) -> Affine {
+        let Affine { m, t } = *self;
+        let det = m[0][0] * m[1][1] - m[0][1] * m[1][0];
+        let m = [[m[1][1] / det, -m[0][1] / det], [-m[1][0] / det, m[0][0] / det]];
+        let inverse = Affine { m, t: [0.0, 0.0] };
+        let [tx, ty] = inverse.apply(t);
+        Affine { m, t: [-tx, -ty] }
+    }
+}
+
+/// Load the floorplan from the given images directory.
+///
+/// Falls back to "floorplan.png" if a vector floorplan fails to load, and to a blank placeholder
+/// if that fails too.
+pub fn load(
+    images_directory: &Path,
+    app: &App,
+    window_id: WindowId,
+    ui: &mut ui::Ui,
+) -> Floorplan {
+    let (natural, vector) = match load_vector(images_directory, app) {
+        Ok(Some((natural, vector))) => (natural, Some(vector)),
+        result => {
+            if let Err(err) = result {
+                eprintln!("failed to load vector floorplan, falling back to PNG: {}", err);
+            }
+            let path = images_directory.join(FILE_STEM).with_extension("png");
+            let natural = match image::open(&path) {
+                Ok(image) => image.into_rgba(),
+                Err(err) => {
+                    eprintln!("failed to load floorplan \"{}\": {}", path.display(), err);
+                    placeholder()
+                },
+            };
+            (natural, None)
+        },
+    };
+    let texture = texture(app, window_id, &natural).expect("window closed unexpectedly");
+    let image = insert_image(ui, texture);
+    let affine = Affine::IDENTITY;
+    Floorplan { image, natural, affine, vector }
+}
+
+// Load the SVG or PDF floorplan if there is one, rendering it at its natural size.
+fn load_vector(
+    images_directory: &Path,
+    app: &App,
+) -> Result<Option<(RgbaImage, Vector)>, String> {
+    let path = match svg_path(images_directory).map_err(|err| err.to_string())? {
+        None => return Ok(None),
+        Some(path) => path,
+    };
+    let data = fs::read(&path).map_err(|err| format!("\"{}\": {}", path.display(), err))?;
+
+    // Render the floorplan at its natural size.
+    let tree = parse(&data)?;
+    let size = tree.svg_node().size;
+    let rect = ui::Rect::from_corners([0.0, 0.0], [size.width(), size.height()]);
+    let natural_size = [size.width().ceil() as u32, size.height().ceil() as u32];
+    let view = View { rect, size: natural_size, affine: Affine::IDENTITY };
+    let natural = render(&tree, size.height(), view).ok_or("failed to render floorplan")?;
+
+    // The tree may not be shared between threads, so the rendering thread parses its own.
+    let (request_tx, request_rx) = mpsc::channel::<View>();
+    let (rendered_tx, rendered_rx) = mpsc::channel();
+    let app_proxy = app.create_proxy();
+    let height = size.height();
+    thread::Builder::new()
+        .name("gui_floorplan".into())
+        .spawn(move || {
+            let tree = match parse(&data) {
+                Ok(tree) => tree,
+                Err(err) => {
+                    eprintln!("failed to parse the floorplan for re-rendering: {}", err);
+                    return;
+                },
+            };
+            while let Ok(view) = request_rx.recv() {
+                // Skip to the most recently requested view.
+                let view = request_rx.try_iter().last().unwrap_or(view);
+                let image = match render(&tree, height, view) {
+                    None => continue,
+                    Some(image) => image,
+                };
+                if rendered_tx.send((view, image)).is_err() {
+                    break;
+                }
+                // Proxy is currently buggy on linux so we only enable this for macos.
+                if cfg!(target_os = "macos") {
+                    if app_proxy.wakeup().is_err() {
+                        break;
+                    }
+                }
+            }
+        })
+        .map_err(|err| err.to_string())?;
+
+    let vector = Vector {
+        requested: Cell::new(None),
+        rendered: None,
+        request_tx,
+        rendered_rx,
+    };
+    Ok(Some((natural, vector)))
+}
+
+// The path to the SVG floorplan, converting the PDF floorplan if necessary.
+fn svg_path(images_directory: &Path) -> io::Result<Option<PathBuf>> {
+    let svg = images_directory.join(FILE_STEM).with_extension("svg");
+    if svg.exists() {
+        return Ok(Some(svg));
+    }
+    let pdf = images_directory.join(FILE_STEM).with_extension("pdf");
+    if !pdf.exists() {
+        return Ok(None);
+    }
+    let cache = images_directory.join(PDF_CACHE_FILE_NAME);
+    let is_stale = match (fs::metadata(&pdf)?.modified(), fs::metadata(&cache)) {
+        (Ok(pdf_modified), Ok(cache)) => cache.modified().map(|m| m < pdf_modified).unwrap_or(true),
+        _ => true,
+    };
+    if is_stale {
+        let status = Command::new("pdftocairo")
+            .args(&["-svg", "-f", "1", "-l", "1"])
+            .arg(&pdf)
+            .arg(&cache)
+            .status()?;
+        if !status.success() {
+            let msg = format!("pdftocairo failed to convert \"{}\": {}", pdf.display(), status);
+            return Err(io::Error::new(io::ErrorKind::Other, msg));
+        }
+    }
+    Ok(Some(cache))
+}
+
+// A blank white floorplan.
+fn placeholder() -> RgbaImage {
+    let [w, h] = PLACEHOLDER_SIZE;
+    RgbaImage::from_pixel(w, h, image::Rgba([255, 255, 255, 255]))
+}
+
+fn parse(data: &[u8]) -> Result<usvg::Tree, String> {
+    let opt = usvg::Options::default();
+    usvg::Tree::from_data(data, &opt.to_ref()).map_err(|err| err.to_string())
+}
+
+// Render the given view of the floorplan on a white background.
+fn render(tree: &usvg::Tree, height: f64, view: View) -> Option<RgbaImage> {
+    let [w, h] = view.size;
+    let mut pixmap = tiny_skia::Pixmap::new(w, h)?;
+    pixmap.fill(tiny_skia::Color::WHITE);
+    // SVG is measured from the top left while floorplan pixels are measured from the bottom left.
+    let from_svg = Affine { m: [[1.0, 0.0], [0.0, -1.0]], t: [0.0, height] };
+    let scale_x = w as f64 / view.rect.w();
+    let scale_y = h as f64 / view.rect.h();
+    let to_view = Affine {
+        m: [[scale_x, 0.0], [0.0, -scale_y]],
+        t: [-view.rect.left() * scale_x, view.rect.top() * scale_y],
+    };
+    let Affine { m, t } = from_svg.then(&view.affine).then(&to_view);
+    let transform = tiny_skia::Transform::from_row(
+        m[0][0] as f32,
+        m[1][0] as f32,
+        m[0][1] as f32,
+        m[1][1] as f32,
+        t[0] as f32,
+        t[1] as f32,
+    );
+    resvg::render(tree, usvg::FitTo::Original, transform, pixmap.as_mut())?;
+    // The background is opaque, so the premultiplied pixels are already straight RGBA.
+    RgbaImage::from_raw(w, h, pixmap.take())
+}
+
+// Transform the natural floorplan image, filling any uncovered area with white.
+fn transform_image(natural: &RgbaImage, affine: &Affine, [w, h]: [f64; 2]) -> RgbaImage {
+    let inverse = affine.inverse();
+    let (w, h) = (w.round() as u32, h.round() as u32);
+    RgbaImage::from_fn(w, h, |col, row| {
+        // Image rows are measured from the top while floorplan pixels from the bottom.
+        sample(natural, inverse.apply([col as f64 + 0.5, (h - row) as f64 - 0.5]))
+    })
+}
+
+// The pixel of the natural floorplan at the given point, or white if it lies outside the image.
+fn sample(natural: &RgbaImage, [x, y]: [f64; 2]) -> image::Rgba<u8> {
+    let (natural_w, natural_h) = natural.dimensions();
+    if x < 0.0 || y < 0.0 || x >= natural_w as f64 || y >= natural_h as f64 {
+        return image::Rgba([255, 255, 255, 255]);
+    }
+    *natural.get_pixel(x as u32, natural_h - 1 - y as u32)
+}
+
+fn texture(app: &App, window_id: WindowId, image: &RgbaImage) -> Option<wgpu::Texture> {
+    let window = app.window(window_id)?;
+    let device = window.swap_chain_device();
+    // The wgpu device queue used to load the image data.
+    let mut queue = window.swap_chain_queue().lock().unwrap();
+    // Describe how we will use the texture so that the GPU may handle it efficiently.
+    let usage = wgpu::TextureUsage::SAMPLED;
+    Some(wgpu::Texture::load_from_image_buffer(device, &mut *queue, usage, image))
+}
+
+fn insert_image(ui: &mut ui::Ui, texture: wgpu::Texture) -> Image {
+    let [width, height] = texture.size();
+    let [width, height] = [width as f64, height as f64];
+    let id = ui.image_map.insert(texture.into_ui_image());
+    Image { id, width, height }
+}
+
+#[test]
+fn test_transform_affine() {
+    let transform = Transform { quarter_turns: 1, mirror: true, ..Default::default() };
+    let (affine, size) = transform.affine([4.0, 2.0], 1.0);
+    assert_eq!(size, [2.0, 4.0]);
+    // Mirrored to the bottom right, then rotated to the top right.
+    assert_eq!(affine.apply([0.0, 0.0]), [2.0, 4.0]);
+    assert_eq!(affine.inverse().apply([2.0, 4.0]), [0.0, 0.0]);
+    let transform = Transform { offset_x: Metres(-1.0), ..Default::default() };
+    let (affine, size) = transform.affine([4.0, 2.0], 2.0);
+    assert_eq!(size, [2.0, 2.0]);
+    assert_eq!(affine.apply([3.0, 1.0]), [1.0, 1.0]);
+}
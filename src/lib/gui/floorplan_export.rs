@@ -0,0 +1,333 @@
+//! Exporting the current view of the floorplan along with the speakers and active sounds.
+//!
+//! A single view may be exported as a PNG for documentation and presentations. A time-lapse may
+//! also be recorded, writing a frame of the view to a directory of PNGs every
+//! `TIMELAPSE_FRAME_INTERVAL` for up to `TIMELAPSE_DURATION`. Once the time-lapse ends, the frames
+//! are encoded to an MP4 beside the directory via `ffmpeg`, which must be installed and on the
+//! `PATH`. The frames are kept either way.
+//!
+//! The floorplan itself is rendered once when the export begins. The speakers, sounds and title
+//! are described as SVG and rendered over it for every frame.
+
+use metres::Metres;
+use nannou::image::RgbaImage;
+use nannou::prelude::*;
+use nannou::ui;
+use project;
+use resvg;
+use slug::slugify;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tiny_skia;
+use usvg;
+
+/// The interval at which frames of a time-lapse are written.
+pub const TIMELAPSE_FRAME_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The duration after which a time-lapse ends automatically.
+pub const TIMELAPSE_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// The frame rate of the encoded time-lapse video.
+pub const TIMELAPSE_FPS: u32 = 30;
+
+/// The resolution of exported PNGs relative to the floorplan as displayed.
+pub const PNG_SCALE: f64 = 2.0;
+
+// The font used for the title and speaker channels, relative to the fonts directory.
+const FONT_PATH: &'static str = "NotoSans/NotoSans-Regular.ttf";
+
+/// The region of the floorplan to export and the resolution at which it is rendered.
+#[derive(Copy, Clone, Debug)]
+pub struct View {
+    /// The region in transformed floorplan pixels, measured from the bottom left.
+    pub rect: ui::Rect,
+    /// The resolution of the exported image.
+    pub size: [u32; 2],
+    /// The project's `floorplan_pixels_per_metre`.
+    pub pixels_per_metre: f64,
+}
+
+/// Everything drawn over the floorplan.
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    /// Displayed in the top left corner, typically the project name.
+    pub title: String,
+    pub speakers: Vec<Speaker>,
+    /// The location of each active sound.
+    pub sounds: Vec<Point2<Metres>>,
+    /// The radius with which speakers and sounds are drawn.
+    pub radius: Metres,
+}
+
+/// A speaker drawn over the floorplan.
+#[derive(Copy, Clone, Debug)]
+pub struct Speaker {
+    pub point: Point2<Metres>,
+    /// The zero-based output channel, displayed one-based to match the GUI.
+    pub channel: usize,
+    /// The direction in which a directional speaker is aimed.
+    pub aim_radians: Option<f64>,
+}
+
+/// A time-lapse recording in progress.
+pub struct Timelapse {
+    directory: PathBuf,
+    background: RgbaImage,
+    view: View,
+    fonts_directory: PathBuf,
+    started: Instant,
+    last_frame: Option<Instant>,
+    frames: usize,
+    // The thread writing the most recent frame, joined before encoding.
+    frame_writer: Option<thread::JoinHandle<()>>,
+}
+
+impl View {
+    // The given point in metres as SVG user units, measured from the top left of the image.
+    fn to_svg(&self, p: Point2<Metres>) -> [f64; 2] {
+        let [w, h] = self.size;
+        let x = (p.x.0 * self.pixels_per_metre - self.rect.left()) * w as f64 / self.rect.w();
+        let y = (self.rect.top() - p.y.0 * self.pixels_per_metre) * h as f64 / self.rect.h();
+        [x, y]
+    }
+
+    // The given distance in metres as SVG user units.
+    fn scalar_to_svg(&self, Metres(m): Metres) -> f64 {
+        m * self.pixels_per_metre * self.size[0] as f64 / self.rect.w()
+    }
+}
+
+impl Timelapse {
+    /// Begin a time-lapse of the given view, writing frames to a new directory within `exports`.
+    ///
+    /// `background` is the floorplan rendered at the view and is reused for every frame.
+    pub fn start(
+        exports: &Path,
+        fonts_directory: &Path,
+        project_name: &str,
+        background: RgbaImage,
+        view: View,
+    ) -> Result<Self, String> {
+        let directory = exports.join(file_stem(project_name, "timelapse"));
+        fs::create_dir_all(&directory).map_err(|err| format!("{}", err))?;
+        Ok(Timelapse {
+            directory,
+            background,
+            view,
+            fonts_directory: fonts_directory.to_path_buf(),
+            started: Instant::now(),
+            last_frame: None,
+            frames: 0,
+            frame_writer: None,
+        })
+    }
+
+    /// The time since the time-lapse began.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Whether the time-lapse has reached the `TIMELAPSE_DURATION`.
+    pub fn is_complete(&self) -> bool {
+        self.elapsed() >= TIMELAPSE_DURATION
+    }
+
+    /// Write a frame of the scene if the `TIMELAPSE_FRAME_INTERVAL` has passed since the last.
+    ///
+    /// The frame is rendered and written on a dedicated thread.
+    pub fn update(&mut self, scene: &Scene) {
+        let is_due = self.last_frame
+            .map(|i| i.elapsed() >= TIMELAPSE_FRAME_INTERVAL)
+            .unwrap_or(true);
+        if !is_due {
+            return;
+        }
+        self.last_frame = Some(Instant::now());
+        let path = self.directory.join(format!("frame-{:05}.png", self.frames));
+        self.frames += 1;
+        let mut image = self.background.clone();
+        let view = self.view;
+        let scene = scene.clone();
+        let fonts_directory = self.fonts_directory.clone();
+        let frame_writer = thread::spawn(move || {
+            let result = draw_scene(&mut image, &fonts_directory, view, &scene)
+                .and_then(|()| image.save(&path).map_err(|err| format!("{}", err)));
+            if let Err(err) = result {
+                eprintln!("failed to write time-lapse frame \"{}\": {}", path.display(), err);
+            }
+        });
+        self.frame_writer = Some(frame_writer);
+    }
+
+    /// End the time-lapse, encoding the frames written so far to an MP4 beside their directory.
+    ///
+    /// Encoding happens on a dedicated thread once the last frame has been written. Returns the
+    /// path of the video.
+    pub fn finish(self) -> PathBuf {
+        let video = self.directory.with_extension("mp4");
+        let frames = self.directory.join("frame-%05d.png");
+        let path = video.clone();
+        let frame_writer = self.frame_writer;
+        thread::spawn(move || {
+            if let Some(frame_writer) = frame_writer {
+                if frame_writer.join().is_err() {
+                    eprintln!("the time-lapse frame writer thread panicked");
+                }
+            }
+            let status = Command::new("ffmpeg")
+                .args(&["-y", "-loglevel", "error", "-framerate"])
+                .arg(format!("{}", TIMELAPSE_FPS))
+                .arg("-i")
+                .arg(&frames)
+                // H.264 requires even dimensions.
+                .args(&["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-pix_fmt", "yuv420p"])
+                .arg(&path)
+                .status();
+            match status {
+                Ok(ref status) if status.success() => (),
+                Ok(status) => eprintln!("ffmpeg failed to encode the time-lapse: {}", status),
+                Err(err) => eprintln!("failed to run ffmpeg to encode the time-lapse: {}", err),
+            }
+        });
+        video
+    }
+}
+
+/// Export the given scene over the floorplan `background` as a PNG within `exports`.
+///
+/// Returns the path of the PNG.
+pub fn export_png(
+    exports: &Path,
+    fonts_directory: &Path,
+    project_name: &str,
+    mut background: RgbaImage,
+    view: View,
+    scene: &Scene,
+) -> Result<PathBuf, String> {
+    draw_scene(&mut background, fonts_directory, view, scene)?;
+    fs::create_dir_all(exports).map_err(|err| format!("{}", err))?;
+    let path = exports.join(file_stem(project_name, "floorplan")).with_extension("png");
+    background.save(&path).map_err(|err| format!("{}", err))?;
+    Ok(path)
+}
+
+// A unique file stem for an export of the given kind, e.g. "my-project-floorplan-<timestamp>".
+fn file_stem(project_name: &str, kind: &str) -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let timestamp = project::history::format_timestamp(secs);
+    format!("{}-{}-{}", slugify(project_name), kind, timestamp)
+}
+
+// Render the scene and blend it over the floorplan image.
+fn draw_scene(
+    image: &mut RgbaImage,
+    fonts_directory: &Path,
+    view: View,
+    scene: &Scene,
+) -> Result<(), String> {
+    let [w, h] = view.size;
+    let mut opt = usvg::Options::default();
+    opt.font_family = "Noto Sans".into();
+    opt.fontdb
+        .load_font_file(fonts_directory.join(FONT_PATH))
+        .map_err(|err| format!("{}", err))?;
+    let svg = scene_svg(view, scene);
+    let tree = usvg::Tree::from_str(&svg, &opt.to_ref()).map_err(|err| err.to_string())?;
+    let mut pixmap = tiny_skia::Pixmap::new(w, h).ok_or("invalid export size")?;
+    let transform = tiny_skia::Transform::default();
+    resvg::render(&tree, usvg::FitTo::Original, transform, pixmap.as_mut())
+        .ok_or("failed to render the scene")?;
+    // The scene is premultiplied and mostly transparent, so blend it over the floorplan.
+    for (pixel, src) in image.pixels_mut().zip(pixmap.data().chunks(4)) {
+        let inv_alpha = 255 - src[3] as u32;
+        for i in 0..3 {
+            pixel.0[i] = (src[i] as u32 + (pixel.0[i] as u32 * inv_alpha + 127) / 255) as u8;
+        }
+        pixel.0[3] = 255;
+    }
+    Ok(())
+}
+
+// Describe the scene as SVG measured in pixels of the exported image.
+fn scene_svg(view: View, scene: &Scene) -> String {
+    let [w, h] = view.size;
+    let radius = view.scalar_to_svg(scene.radius).max(4.0);
+    let font_size = (radius * 1.2).max(10.0);
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#,
+        w, h
+    );
+    for &sound in &scene.sounds {
+        let [x, y] = view.to_svg(sound);
+        let _ = writeln!(
+            svg,
+            concat!(
+                r#"<circle cx="{:.1}" cy="{:.1}" r="{:.1}" "#,
+                r#"fill="rgb(48,140,220)" fill-opacity="0.6"/>"#,
+            ),
+            x, y, radius * 0.75
+        );
+    }
+    for speaker in &scene.speakers {
+        let [x, y] = view.to_svg(speaker.point);
+        if let Some(aim) = speaker.aim_radians {
+            // SVG's y axis points down.
+            let end = [x + aim.cos() * radius * 2.0, y - aim.sin() * radius * 2.0];
+            let _ = writeln!(
+                svg,
+                concat!(
+                    r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" "#,
+                    r#"stroke="black" stroke-width="2"/>"#,
+                ),
+                x, y, end[0], end[1]
+            );
+        }
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{:.1}" cy="{:.1}" r="{:.1}" fill="rgb(36,36,36)"/>"#,
+            x, y, radius
+        );
+        let _ = writeln!(
+            svg,
+            concat!(
+                r#"<text x="{:.1}" y="{:.1}" font-size="{:.1}" "#,
+                r#"fill="white" text-anchor="middle">{}</text>"#,
+            ),
+            x, y + font_size * 0.35, font_size, speaker.channel + 1
+        );
+    }
+    let _ = writeln!(
+        svg,
+        r#"<text x="20" y="40" font-size="24" fill="black">{}</text>"#,
+        escape(&scene.title)
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Escape the characters with special meaning within SVG text.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[test]
+fn test_view_to_svg() {
+    let view = View {
+        rect: ui::Rect::from_corners([100.0, 100.0], [300.0, 200.0]),
+        size: [400, 200],
+        pixels_per_metre: 100.0,
+    };
+    // The bottom left and top right of the view.
+    assert_eq!(view.to_svg(pt2(Metres(1.0), Metres(1.0))), [0.0, 200.0]);
+    assert_eq!(view.to_svg(pt2(Metres(3.0), Metres(2.0))), [400.0, 0.0]);
+    assert_eq!(view.scalar_to_svg(Metres(0.5)), 100.0);
+}
@@ -0,0 +1,167 @@
+//! A thread dedicated to polling game controllers and forwarding their input to the GUI.
+//!
+//! The left stick nudges the selected speaker or source preview, the d-pad nudges it in fine
+//! steps, the right stick pans the camera and the triggers zoom. This allows for adjusting the
+//! layout while standing away from the desk during calibration walks.
+
+use gilrs::{self, Axis, Button, EventType, Gilrs};
+use nannou;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The interval at which the controller state is sampled.
+const POLL_INTERVAL: Duration = Duration::from_millis(16);
+/// Stick and trigger values below this magnitude are ignored.
+const DEAD_ZONE: f32 = 0.15;
+
+/// The distance in metres that a selected speaker or source is nudged by a single d-pad press.
+pub const STEP_METRES: f64 = 0.01;
+/// The speed in metres per second at which a fully deflected left stick nudges the selection.
+pub const NUDGE_METRES_PER_SEC: f64 = 0.5;
+/// The speed in GUI scalar units per second at which a fully deflected right stick pans.
+pub const PAN_SCALAR_PER_SEC: f64 = 600.0;
+/// The rate at which the camera zoom changes per second when a trigger is fully pressed.
+pub const ZOOM_PER_SEC: f64 = 1.0;
+
+pub type Receiver = mpsc::Receiver<Message>;
+pub type Spawned = (Gamepad, Receiver);
+
+/// Input forwarded from the game controller to the GUI.
+#[derive(Copy, Clone, Debug)]
+pub enum Message {
+    /// The analog input sampled over the given duration.
+    Analog(Analog),
+    /// A fine nudge of the selection in the given direction, e.g. from the d-pad.
+    Step([i8; 2]),
+}
+
+/// The state of the analog sticks and triggers of all connected controllers.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Analog {
+    /// The left stick, in the range `-1.0..1.0` for each axis.
+    pub nudge: [f32; 2],
+    /// The right stick, in the range `-1.0..1.0` for each axis.
+    pub pan: [f32; 2],
+    /// The right trigger minus the left trigger.
+    pub zoom: f32,
+    /// The duration over which the input was held in seconds.
+    pub secs: f32,
+}
+
+/// A handle to the game controller polling thread.
+pub struct Gamepad {
+    is_running: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl Analog {
+    fn is_active(&self) -> bool {
+        self.nudge != [0.0; 2] || self.pan != [0.0; 2] || self.zoom != 0.0
+    }
+}
+
+impl Gamepad {
+    /// Stops polling and waits for the thread to finish.
+    pub fn join(self) -> thread::Result<()> {
+        let Gamepad { is_running, thread } = self;
+        is_running.store(false, Ordering::Relaxed);
+        thread.join()
+    }
+}
+
+/// Spawn the game controller polling thread.
+///
+/// If game controllers are not supported on this platform the thread ends immediately and the
+/// returned receiver never yields any messages.
+pub fn spawn(app_proxy: nannou::app::Proxy) -> ::std::io::Result<Spawned> {
+    let is_running = Arc::new(AtomicBool::new(true));
+    let is_running_2 = is_running.clone();
+    let (tx, rx) = mpsc::channel();
+    let thread = thread::Builder::new()
+        .name("gui_gamepad".into())
+        .spawn(move || {
+            let mut gilrs = match Gilrs::new() {
+                Ok(gilrs) => gilrs,
+                Err(gilrs::Error::NotImplemented(_)) => {
+                    eprintln!("game controllers are not supported on this platform");
+                    return;
+                },
+                Err(err) => {
+                    eprintln!("failed to initialise game controller support: {}", err);
+                    return;
+                },
+            };
+
+            let mut last = Instant::now();
+            while is_running_2.load(Ordering::Relaxed) {
+                let mut messages = vec![];
+
+                // Forward d-pad presses as fine steps.
+                while let Some(event) = gilrs.next_event() {
+                    if let EventType::ButtonPressed(button, _) = event.event {
+                        let step = match button {
+                            Button::DPadUp => [0, 1],
+                            Button::DPadDown => [0, -1],
+                            Button::DPadLeft => [-1, 0],
+                            Button::DPadRight => [1, 0],
+                            _ => continue,
+                        };
+                        messages.push(Message::Step(step));
+                    }
+                }
+
+                // Sample the sticks and triggers of all connected controllers.
+                let now = Instant::now();
+                let elapsed = now.duration_since(last);
+                last = now;
+                let mut analog = Analog::default();
+                for (_, gamepad) in gilrs.gamepads() {
+                    let trigger = |button| {
+                        gamepad.button_data(button).map(|data| data.value()).unwrap_or(0.0)
+                    };
+                    analog.nudge[0] += dead_zone(gamepad.value(Axis::LeftStickX));
+                    analog.nudge[1] += dead_zone(gamepad.value(Axis::LeftStickY));
+                    analog.pan[0] += dead_zone(gamepad.value(Axis::RightStickX));
+                    analog.pan[1] += dead_zone(gamepad.value(Axis::RightStickY));
+                    analog.zoom += dead_zone(trigger(Button::RightTrigger2))
+                        - dead_zone(trigger(Button::LeftTrigger2));
+                }
+                if analog.is_active() {
+                    analog.secs = elapsed.as_secs() as f32
+                        + elapsed.subsec_nanos() as f32 * 1e-9;
+                    messages.push(Message::Analog(analog));
+                }
+
+                if !messages.is_empty() {
+                    for msg in messages {
+                        if tx.send(msg).is_err() {
+                            return;
+                        }
+                    }
+                    // Proxy is currently buggy on linux so we only enable this for macos.
+                    if cfg!(target_os = "macos") {
+                        if app_proxy.wakeup().is_err() {
+                            eprintln!("gamepad proxy could not wakeup app");
+                            return;
+                        }
+                    }
+                }
+
+                thread::sleep(POLL_INTERVAL);
+            }
+        })?;
+
+    let gamepad = Gamepad { is_running, thread };
+    Ok((gamepad, rx))
+}
+
+// Zero the given axis value if it is within the dead zone.
+fn dead_zone(value: f32) -> f32 {
+    if value.abs() < DEAD_ZONE {
+        0.0
+    } else {
+        value
+    }
+}
@@ -1,24 +1,56 @@
+use audio;
+use audio::ducking;
+use audio::source::Priority;
+use audio::walk_test;
+use fxhash::FxHashMap;
 use gui::{self, collapsible_area, Channels, Gui, ProjectState, State};
 use gui::{ITEM_HEIGHT, SMALL_FONT_SIZE};
 use installation;
+use metres::Metres;
 use nannou::ui;
 use nannou_osc::Connected;
 use nannou::ui::prelude::*;
 use osc;
 use project::{self, Project};
+use soundscape;
+use soundscape::activity::{self, Activity};
 use std::{io, net};
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Instant;
+use utils;
+
+/// The greatest margin by which an installation's area may be expanded via the GUI.
+pub const MAX_AREA_MARGIN: Metres = Metres(5.0);
 
 /// Runtime state relevant to the installation editor GUI.
-#[derive(Default)]
 pub struct InstallationEditor {
     pub selected: Option<Selected>,
+    /// The walk test that is currently underway, if any.
+    pub walk_test: Option<WalkTest>,
+    /// The interval between the announcements of each speaker during a walk test.
+    pub walk_test_interval_secs: f64,
+    /// The latest reading received from the sensor of each installation.
+    pub sensor_readings: FxHashMap<installation::Id, f32>,
+    /// The latest visitor activity reading received for each installation.
+    pub activity_readings: FxHashMap<installation::Id, f32>,
+}
+
+/// A walk test announcing each speaker of an installation in turn.
+pub struct WalkTest {
+    installation: installation::Id,
+    started: Instant,
+    // The name and duration in seconds of each speaker's announcement, in order.
+    steps: Vec<(String, f64)>,
 }
 
 /// The currently selected installation.
 pub struct Selected {
     id: installation::Id,
     name: String,
+    osc_input_prefix: String,
+    // The schedule text being edited, applied once enter is pressed.
+    schedule: String,
     selected_computer: Option<SelectedComputer>,
 }
 
@@ -29,6 +61,232 @@ pub struct SelectedComputer {
     osc_addr: String,
 }
 
+impl Default for InstallationEditor {
+    fn default() -> Self {
+        InstallationEditor {
+            selected: None,
+            walk_test: None,
+            walk_test_interval_secs: walk_test::DEFAULT_INTERVAL_SECS,
+            sensor_readings: Default::default(),
+            activity_readings: Default::default(),
+        }
+    }
+}
+
+impl WalkTest {
+    /// The name of the speaker that is currently being announced.
+    ///
+    /// Estimated from the time since the walk test started.
+    pub fn current_speaker(&self) -> Option<&str> {
+        let total_secs: f64 = self.steps.iter().map(|&(_, secs)| secs).sum();
+        if total_secs <= 0.0 {
+            return None;
+        }
+        let elapsed = self.started.elapsed();
+        let elapsed_secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9;
+        let mut secs = elapsed_secs % total_secs;
+        for &(ref name, duration_secs) in &self.steps {
+            if secs < duration_secs {
+                return Some(name);
+            }
+            secs -= duration_secs;
+        }
+        None
+    }
+}
+
+/// Begin a walk test through the speakers assigned to the given installation, ordered by channel.
+///
+/// Returns `None` if no speakers are assigned to the installation.
+pub fn start_walk_test(
+    id: installation::Id,
+    speakers: &project::Speakers,
+    assets: &Path,
+    interval_secs: f64,
+    channels: &Channels,
+) -> Option<WalkTest> {
+    let mut speaker_ids: Vec<_> = speakers
+        .iter()
+        .filter(|&(_, s)| s.audio.installations.contains(&id))
+        .map(|(&id, _)| id)
+        .collect();
+    if speaker_ids.is_empty() {
+        eprintln!("no speakers are assigned to the installation for the walk test");
+        return None;
+    }
+    speaker_ids.sort_by_key(|id| speakers[id].audio.channel);
+    let steps: Vec<_> = speaker_ids
+        .iter()
+        .map(|id| {
+            let speaker = &speakers[id];
+            let channel = speaker.audio.channel;
+            let samples = walk_test::announcement(assets, &speaker.name, channel);
+            walk_test::Step::new(*id, channel, samples)
+        })
+        .collect();
+    let gui_steps = steps
+        .iter()
+        .map(|step| {
+            let name = speakers[&step.speaker].name.clone();
+            (name, step.duration_secs(interval_secs))
+        })
+        .collect();
+    let audio_walk_test = walk_test::WalkTest::new(steps, interval_secs);
+    channels
+        .audio_output
+        .send(move |audio| audio.walk_test = Some(audio_walk_test))
+        .expect("failed to send walk test to audio output thread");
+    Some(WalkTest { installation: id, started: Instant::now(), steps: gui_steps })
+}
+
+/// Stop the walk test that is underway, if any.
+pub fn stop_walk_test(channels: &Channels, walk_test: &mut Option<WalkTest>) {
+    if walk_test.take().is_some() {
+        channels
+            .audio_output
+            .send(move |audio| audio.walk_test = None)
+            .expect("failed to stop walk test on audio output thread");
+    }
+}
+
+/// Update the local copy of the installation's volume and send it to the audio output thread.
+///
+/// The installation's speakers fade to the new level over its fade duration.
+pub fn set_volume<F>(
+    id: installation::Id,
+    installations: &mut project::Installations,
+    channels: &Channels,
+    update: F,
+)
+where
+    F: FnOnce(&mut installation::Volume),
+{
+    let params = match installations.get_mut(&id) {
+        None => return,
+        Some(installation) => {
+            update(&mut installation.volume);
+            installation.volume
+        },
+    };
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.update_installation_volume(id, params);
+        })
+        .expect("failed to send installation volume to audio output thread");
+}
+
+/// Update the local copy of the installation's ducking params and send them to the audio output
+/// thread.
+pub fn set_ducking<F>(
+    id: installation::Id,
+    installations: &mut project::Installations,
+    channels: &Channels,
+    update: F,
+)
+where
+    F: FnOnce(&mut ducking::Params),
+{
+    let params = match installations.get_mut(&id) {
+        None => return,
+        Some(installation) => {
+            update(&mut installation.ducking);
+            installation.ducking
+        },
+    };
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.update_installation_ducking(id, params);
+        })
+        .expect("failed to send installation ducking to audio output thread");
+}
+
+/// Update the local copy of the installation's sensor gain params and send the resulting gain
+/// for its latest sensor reading to the audio output thread.
+pub fn set_sensor_gain<F>(
+    id: installation::Id,
+    installations: &mut project::Installations,
+    sensor_readings: &FxHashMap<installation::Id, f32>,
+    channels: &Channels,
+    update: F,
+)
+where
+    F: FnOnce(&mut installation::SensorGain),
+{
+    let gain = match installations.get_mut(&id) {
+        None => return,
+        Some(installation) => {
+            update(&mut installation.sensor_gain);
+            let reading = sensor_readings.get(&id);
+            reading.map(|&r| installation.sensor_gain.gain_at(r)).unwrap_or(1.0)
+        },
+    };
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.update_installation_sensor_gain(id, gain);
+        })
+        .expect("failed to send installation sensor gain to audio output thread");
+}
+
+/// Record a reading from the installation's sensor and ride its level accordingly.
+pub fn receive_sensor_reading(
+    id: installation::Id,
+    reading: f32,
+    installations: &mut project::Installations,
+    sensor_readings: &mut FxHashMap<installation::Id, f32>,
+    channels: &Channels,
+) {
+    sensor_readings.insert(id, reading);
+    set_sensor_gain(id, installations, sensor_readings, channels, |_| ());
+}
+
+/// Update the local copy of the installation's activity mapping and send it to the soundscape
+/// thread.
+pub fn set_activity<F>(
+    id: installation::Id,
+    installations: &mut project::Installations,
+    channels: &Channels,
+    update: F,
+)
+where
+    F: FnOnce(&mut Activity),
+{
+    let params = match installations.get_mut(&id) {
+        None => return,
+        Some(installation) => {
+            update(&mut installation.soundscape.activity);
+            installation.soundscape.activity
+        },
+    };
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            soundscape.update_installation(&id, |installation| installation.activity = params);
+        })
+        .expect("failed to send installation activity to soundscape thread");
+}
+
+/// Record a reading of the visitor activity within the installation and pass it on to the
+/// soundscape thread.
+///
+/// Returns the intensity driven by the reading, if any.
+pub fn receive_activity_reading(
+    id: installation::Id,
+    reading: f32,
+    installations: &project::Installations,
+    activity_readings: &mut FxHashMap<installation::Id, f32>,
+    channels: &Channels,
+) -> Option<f32> {
+    activity_readings.insert(id, reading);
+    channels
+        .soundscape
+        .send(move |soundscape| soundscape.set_activity_reading(id, reading))
+        .expect("failed to send installation activity reading to soundscape thread");
+    installations.get(&id).and_then(|inst| inst.soundscape.activity.intensity_at(reading))
+}
+
 pub fn set(
     last_area_id: widget::Id,
     gui: &mut Gui,
@@ -39,6 +297,7 @@ pub fn set(
         ref mut ui,
         ref ids,
         channels,
+        assets,
         state: &mut State {
             ref mut is_open,
             ..
@@ -48,6 +307,7 @@ pub fn set(
     let Project {
         state: project::State {
             ref mut installations,
+            ref speakers,
             ..
         },
         ..
@@ -56,6 +316,10 @@ pub fn set(
         installation_editor:
             InstallationEditor {
                 ref mut selected,
+                ref mut walk_test,
+                ref mut walk_test_interval_secs,
+                ref sensor_readings,
+                ref activity_readings,
             },
         ..
     } = *project_state;
@@ -74,14 +338,32 @@ pub fn set(
     // These options include:
     //
     // - Music Data OSC Output (Text and TextBox)
-    let osc_canvas_h = PAD + ITEM_HEIGHT * 3.0 + PAD;
+    // - Capture of sent OSC to disk (Toggle)
+    let osc_canvas_h = PAD + ITEM_HEIGHT * 5.0 + PAD * 3.0;
     let computer_canvas_h = ITEM_HEIGHT + PAD + ITEM_HEIGHT + PAD + COMPUTER_LIST_HEIGHT;
-    let soundscape_canvas_h = PAD + PAD * 3.0 + PAD + SLIDER_H + PAD;
+    let soundscape_canvas_h = PAD + PAD * 3.0 + PAD + SLIDER_H + PAD
+        + PAD * 3.0 + PAD + SLIDER_H + PAD
+        + PAD * 3.0 + PAD + ITEM_HEIGHT + PAD
+        + SLIDER_H + PAD;
+    let osc_input_canvas_h = PAD + PAD * 3.0 + PAD + ITEM_HEIGHT + PAD;
+    let reverb_canvas_h = PAD + PAD * 3.0 + (PAD + SLIDER_H) * 3.0 + PAD;
+    let volume_canvas_h = PAD + PAD * 3.0 + PAD + SLIDER_H + PAD;
+    let ducking_canvas_h = PAD + PAD * 3.0 + PAD + ITEM_HEIGHT + PAD + SLIDER_H + PAD;
+    let sensor_canvas_h = PAD + PAD * 3.0 + PAD + SLIDER_H + PAD + SLIDER_H + PAD;
+    let activity_canvas_h = PAD + PAD * 3.0 + PAD + ITEM_HEIGHT + (PAD + SLIDER_H) * 2.0 + PAD;
+    let walk_test_canvas_h = PAD + PAD * 3.0 + PAD + ITEM_HEIGHT + PAD;
     let selected_canvas_h = PAD
         + NAME_H + PAD
         + computer_canvas_h + PAD
         + osc_canvas_h + PAD
-        + soundscape_canvas_h + PAD;
+        + soundscape_canvas_h + PAD
+        + osc_input_canvas_h + PAD
+        + reverb_canvas_h + PAD
+        + volume_canvas_h + PAD
+        + ducking_canvas_h + PAD
+        + sensor_canvas_h + PAD
+        + activity_canvas_h + PAD
+        + walk_test_canvas_h + PAD;
 
     // The total height of the installation editor as a sum of the previous heights plus necessary
     // padding.
@@ -118,9 +400,14 @@ pub fn set(
         let id = project::next_installation_id(installations);
         let clone = installation.soundscape.clone();
         let name = installation.name.clone();
+        let osc_input_prefix = installation.osc_input_prefix.clone();
+        let schedule = String::new();
+        let reverb = audio::reverb::Reverb::new(installation.reverb);
+        let volume = installation.volume;
+        let ducking = installation.ducking;
         installations.insert(id, installation);
         let selected_computer = None;
-        *selected = Some(Selected { id, name, selected_computer });
+        *selected = Some(Selected { id, name, osc_input_prefix, schedule, selected_computer });
 
         // Update the soundscape thread.
         channels
@@ -136,6 +423,9 @@ pub fn set(
             .send(move |audio| {
                 let computers = 0;
                 audio.insert_installation(id, computers);
+                audio.insert_installation_reverb(id, reverb);
+                audio.update_installation_volume(id, volume);
+                audio.update_installation_ducking(id, ducking);
             })
             .expect("failed to send new installation to audio output thread");
     }
@@ -236,6 +526,9 @@ pub fn set(
                 let id = installations_vec[index];
                 let installation = &installations[&id];
                 let name = installation.name.clone();
+                let osc_input_prefix = installation.osc_input_prefix.clone();
+                let points = &installation.soundscape.schedule.points;
+                let schedule = soundscape::schedule::points_string(points);
                 let selected_computer = match installation.computers.len() {
                     0 => None,
                     _ => {
@@ -254,6 +547,8 @@ pub fn set(
                 *selected = Some(Selected {
                     id,
                     name,
+                    osc_input_prefix,
+                    schedule,
                     selected_computer,
                 });
             }
@@ -276,6 +571,11 @@ pub fn set(
             *selected = None;
         }
 
+        // Stop any walk test through the removed installation's speakers.
+        if walk_test.as_ref().map(|w| w.installation) == Some(id) {
+            stop_walk_test(channels, walk_test);
+        }
+
         // Remove the local copy from the map.
         installations.remove(&id);
 
@@ -325,6 +625,8 @@ pub fn set(
     let Selected {
         id,
         ref mut name,
+        ref mut osc_input_prefix,
+        ref mut schedule,
         ref mut selected_computer,
     } = *selected;
 
@@ -438,6 +740,739 @@ pub fn set(
             .expect("failed to send installation update to soundscape thread");
     }
 
+    ///////////////////////
+    // RESERVED PRIORITY //
+    ///////////////////////
+
+    widget::Text::new("Reserved For Priority Classes")
+        .font_size(SMALL_FONT_SIZE)
+        .align_left()
+        .down(PAD * 2.0)
+        .set(ids.installation_editor_soundscape_reserved_text, ui);
+
+    // Update the local copy of the reservations and send them to the soundscape.
+    fn set_reserved(
+        id: installation::Id,
+        installations: &mut project::Installations,
+        channels: &Channels,
+        priority: Priority,
+        num: usize,
+    ) {
+        let reserved = {
+            let installation = installations.get_mut(&id).unwrap();
+            if let Some(reserved) = installation.soundscape.reserved.get_mut(priority) {
+                *reserved = num;
+            }
+            installation.soundscape.reserved
+        };
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_installation(&id, |installation| {
+                    installation.reserved = reserved;
+                });
+            })
+            .expect("failed to send installation reservations to soundscape thread");
+    }
+
+    let reserved_w = (selected_canvas_kid_area.w() - PAD * 4.0) / 3.0;
+    let reserved_sliders = [
+        (Priority::Feature, "Feature", ids.installation_editor_soundscape_reserved_feature),
+        (
+            Priority::Interactive,
+            "Interactive",
+            ids.installation_editor_soundscape_reserved_interactive,
+        ),
+        (
+            Priority::Announcement,
+            "Announce",
+            ids.installation_editor_soundscape_reserved_announcement,
+        ),
+    ];
+    for (i, &(priority, name, slider_id)) in reserved_sliders.iter().enumerate() {
+        let num = installations[&id].soundscape.reserved.get(priority);
+        let max = installations[&id].soundscape.simultaneous_sounds.max.max(1) as f32;
+        let label = format!("{}: {}", name, num);
+        let slider = widget::Slider::new(num as f32, 0.0, max)
+            .w(reserved_w)
+            .h(SLIDER_H)
+            .label_font_size(SMALL_FONT_SIZE)
+            .color(ui::color::LIGHT_CHARCOAL)
+            .label(&label);
+        let slider = match i {
+            0 => slider.align_left().down(PAD),
+            _ => slider.right(PAD),
+        };
+        for value in slider.set(slider_id, ui) {
+            let new_num = value.round() as usize;
+            if new_num != num {
+                set_reserved(id, installations, channels, priority, new_num);
+            }
+        }
+    }
+
+    //////////////
+    // SCHEDULE //
+    //////////////
+
+    widget::Text::new("Schedule (Venue Time, e.g. \"09:00=0.5, 19:00=2\")")
+        .font_size(SMALL_FONT_SIZE)
+        .align_left_of(ids.installation_editor_soundscape_simultaneous_sounds_slider)
+        .down(PAD * 2.0)
+        .set(ids.installation_editor_soundscape_schedule_text, ui);
+
+    // A text box of `HH:MM=density` points scaling the simultaneous sounds.
+    let color = match soundscape::schedule::parse_points(schedule) {
+        Ok(ref points) if *points == installations[&id].soundscape.schedule.points => {
+            color::BLACK
+        },
+        Ok(_) => color::DARK_GREEN,
+        Err(_) => color::DARK_RED,
+    };
+    for event in widget::TextBox::new(schedule)
+        .kid_area_w_of(ids.installation_editor_soundscape_canvas)
+        .h(ITEM_HEIGHT)
+        .color(color)
+        .font_size(SMALL_FONT_SIZE)
+        .align_left()
+        .down(PAD)
+        .set(ids.installation_editor_soundscape_schedule, ui)
+    {
+        use nannou::ui::widget::text_box::Event;
+        match event {
+            Event::Update(s) => *schedule = s,
+            Event::Enter => {
+                let points = match soundscape::schedule::parse_points(schedule) {
+                    Ok(points) => points,
+                    Err(err) => {
+                        eprintln!("failed to parse installation schedule: {}", err);
+                        continue;
+                    },
+                };
+                *schedule = soundscape::schedule::points_string(&points);
+
+                // Update the local copy.
+                let installation = installations.get_mut(&id).unwrap();
+                installation.soundscape.schedule.points = points.clone();
+
+                // Update the soundscape copy.
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_installation(&id, |installation| {
+                            installation.schedule.points = points;
+                        });
+                    })
+                    .expect("failed to send installation schedule to soundscape thread");
+            },
+        }
+    }
+
+    /////////////////
+    // AREA MARGIN //
+    /////////////////
+
+    // Expands the area enclosing the installation's speakers within which sounds spawn and move.
+    let margin = installations[&id].soundscape.area_margin;
+    let label = format!("Area Margin: {:.2} metres", margin.0);
+    for value in widget::Slider::new(margin.0, 0.0, MAX_AREA_MARGIN.0)
+        .kid_area_w_of(ids.installation_editor_soundscape_canvas)
+        .h(SLIDER_H)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .label(&label)
+        .align_left()
+        .down(PAD)
+        .set(ids.installation_editor_soundscape_area_margin, ui)
+    {
+        let margin = Metres(value);
+
+        // Update the local copy.
+        installations.get_mut(&id).unwrap().soundscape.area_margin = margin;
+
+        // Update the soundscape copy.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_installation(&id, |installation| {
+                    installation.area_margin = margin;
+                });
+            })
+            .expect("failed to send installation area margin to soundscape thread");
+    }
+
+    //////////////////////
+    // OSC INPUT PREFIX //
+    //////////////////////
+
+    // The canvas for binding an OSC input address prefix to the installation.
+    widget::Canvas::new()
+        .middle_of(ids.installation_editor_selected_canvas)
+        .down_from(ids.installation_editor_soundscape_canvas, PAD)
+        .color(color::CHARCOAL)
+        .w(selected_canvas_kid_area.w())
+        .h(osc_input_canvas_h)
+        .pad(PAD)
+        .set(ids.installation_editor_osc_input_canvas, ui);
+
+    // OSC input prefix header.
+    widget::Text::new("OSC Input Prefix - e.g. \"/tracker_a\"")
+        .font_size(SMALL_FONT_SIZE)
+        .top_left_of(ids.installation_editor_osc_input_canvas)
+        .set(ids.installation_editor_osc_input_text, ui);
+
+    // A textbox for editing the prefix. Red if another installation is bound to the same prefix.
+    let normalised_prefix = installation::normalise_osc_prefix(osc_input_prefix);
+    let color = if normalised_prefix == installations[&id].osc_input_prefix {
+        color::BLACK
+    } else if !normalised_prefix.is_empty()
+        && installations.values().any(|inst| inst.osc_input_prefix == normalised_prefix)
+    {
+        color::DARK_RED
+    } else {
+        color::DARK_GREEN
+    };
+    for event in widget::TextBox::new(osc_input_prefix)
+        .kid_area_w_of(ids.installation_editor_osc_input_canvas)
+        .h(ITEM_HEIGHT)
+        .color(color)
+        .font_size(SMALL_FONT_SIZE)
+        .align_left()
+        .down(PAD * 2.0)
+        .set(ids.installation_editor_osc_input_prefix, ui)
+    {
+        use nannou::ui::widget::text_box::Event;
+        match event {
+            Event::Update(s) => *osc_input_prefix = s,
+            Event::Enter => {
+                let prefix = installation::normalise_osc_prefix(osc_input_prefix);
+                *osc_input_prefix = prefix.clone();
+                installations.get_mut(&id).unwrap().osc_input_prefix = prefix;
+            },
+        }
+    }
+
+    ////////////
+    // REVERB //
+    ////////////
+
+    // The canvas for editing the installation's reverb bus.
+    widget::Canvas::new()
+        .middle_of(ids.installation_editor_selected_canvas)
+        .down_from(ids.installation_editor_osc_input_canvas, PAD)
+        .color(color::CHARCOAL)
+        .w(selected_canvas_kid_area.w())
+        .h(reverb_canvas_h)
+        .pad(PAD)
+        .set(ids.installation_editor_reverb_canvas, ui);
+
+    widget::Text::new("Reverb - Returned to this installation's speakers")
+        .font_size(SMALL_FONT_SIZE)
+        .top_left_of(ids.installation_editor_reverb_canvas)
+        .set(ids.installation_editor_reverb_text, ui);
+
+    // Update the local copy of the reverb params and send them to the audio output thread.
+    fn set_reverb<F>(
+        id: installation::Id,
+        installations: &mut project::Installations,
+        channels: &Channels,
+        update: F,
+    )
+    where
+        F: FnOnce(&mut audio::reverb::Params),
+    {
+        let params = {
+            let installation = installations.get_mut(&id).unwrap();
+            update(&mut installation.reverb);
+            installation.reverb
+        };
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.update_installation_reverb(&id, params);
+            })
+            .expect("failed to send installation reverb to audio output thread");
+    }
+
+    // Return level.
+    let params = installations[&id].reverb;
+    let label = format!("Level: {:.2}", params.level);
+    for value in widget::Slider::new(params.level, 0.0, 1.0)
+        .kid_area_w_of(ids.installation_editor_reverb_canvas)
+        .h(SLIDER_H)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .align_left()
+        .label(&label)
+        .down(PAD * 2.0)
+        .set(ids.installation_editor_reverb_level, ui)
+    {
+        set_reverb(id, installations, channels, |params| params.level = value);
+    }
+
+    // Decay time.
+    let params = installations[&id].reverb;
+    let label = format!("Decay: {:.2} secs", params.decay_secs);
+    let max_decay = audio::reverb::MAX_DECAY_SECS;
+    for value in widget::Slider::new(params.decay_secs, 0.1, max_decay)
+        .skew(0.5)
+        .kid_area_w_of(ids.installation_editor_reverb_canvas)
+        .h(SLIDER_H)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .align_left()
+        .label(&label)
+        .down(PAD)
+        .set(ids.installation_editor_reverb_decay, ui)
+    {
+        set_reverb(id, installations, channels, |params| params.decay_secs = value);
+    }
+
+    // High frequency damping.
+    let params = installations[&id].reverb;
+    let label = format!("Damping: {:.2}", params.damping);
+    for value in widget::Slider::new(params.damping, 0.0, 1.0)
+        .kid_area_w_of(ids.installation_editor_reverb_canvas)
+        .h(SLIDER_H)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .align_left()
+        .label(&label)
+        .down(PAD)
+        .set(ids.installation_editor_reverb_damping, ui)
+    {
+        set_reverb(id, installations, channels, |params| params.damping = value);
+    }
+
+    ////////////
+    // VOLUME //
+    ////////////
+
+    // The canvas for editing the output level of the installation's speakers.
+    widget::Canvas::new()
+        .middle_of(ids.installation_editor_selected_canvas)
+        .down_from(ids.installation_editor_reverb_canvas, PAD)
+        .color(color::CHARCOAL)
+        .w(selected_canvas_kid_area.w())
+        .h(volume_canvas_h)
+        .pad(PAD)
+        .set(ids.installation_editor_volume_canvas, ui);
+
+    widget::Text::new("Volume - Applied to this installation's speakers")
+        .font_size(SMALL_FONT_SIZE)
+        .top_left_of(ids.installation_editor_volume_canvas)
+        .set(ids.installation_editor_volume_text, ui);
+
+    let volume_kid_area = ui.kid_area_of(ids.installation_editor_volume_canvas).unwrap();
+    let third_w = (volume_kid_area.w() - PAD * 2.0) / 3.0;
+
+    // Mute.
+    let params = installations[&id].volume;
+    for muted in widget::Toggle::new(params.muted)
+        .label("Mute")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::DARK_RED)
+        .w(third_w)
+        .h(SLIDER_H)
+        .align_left()
+        .down(PAD * 2.0)
+        .set(ids.installation_editor_volume_mute, ui)
+    {
+        set_volume(id, installations, channels, |params| params.muted = muted);
+    }
+
+    // Gain.
+    let label = format!("Gain: {:.2}", params.gain);
+    for value in widget::Slider::new(params.gain, 0.0, 2.0)
+        .w(third_w)
+        .h(SLIDER_H)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .label(&label)
+        .right(PAD)
+        .set(ids.installation_editor_volume_gain, ui)
+    {
+        set_volume(id, installations, channels, |params| params.gain = value);
+    }
+
+    // The duration over which changes are faded.
+    let label = format!("Fade: {:.1} secs", params.fade_secs);
+    for value in widget::Slider::new(params.fade_secs, 0.0, installation::MAX_VOLUME_FADE_SECS)
+        .skew(0.5)
+        .w(third_w)
+        .h(SLIDER_H)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .label(&label)
+        .right(PAD)
+        .set(ids.installation_editor_volume_fade, ui)
+    {
+        let secs = (value * 10.0).round() / 10.0;
+        set_volume(id, installations, channels, |params| params.fade_secs = secs);
+    }
+
+    /////////////
+    // DUCKING //
+    /////////////
+
+    // The canvas for ducking the installation's sounds beneath those of a higher priority.
+    widget::Canvas::new()
+        .middle_of(ids.installation_editor_selected_canvas)
+        .down_from(ids.installation_editor_volume_canvas, PAD)
+        .color(color::CHARCOAL)
+        .w(selected_canvas_kid_area.w())
+        .h(ducking_canvas_h)
+        .pad(PAD)
+        .set(ids.installation_editor_ducking_canvas, ui);
+
+    widget::Text::new("Ducking - Beneath higher priority sounds")
+        .font_size(SMALL_FONT_SIZE)
+        .top_left_of(ids.installation_editor_ducking_canvas)
+        .set(ids.installation_editor_ducking_text, ui);
+
+    let ducking_kid_area = ui.kid_area_of(ids.installation_editor_ducking_canvas).unwrap();
+    let half_w = (ducking_kid_area.w() - PAD) / 2.0;
+    let third_w = (ducking_kid_area.w() - PAD * 2.0) / 3.0;
+    let params = installations[&id].ducking;
+
+    // Enable ducking.
+    for enabled in widget::Toggle::new(params.enabled)
+        .label("Enabled")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::BLUE)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .align_left()
+        .down(PAD * 2.0)
+        .set(ids.installation_editor_ducking_enabled, ui)
+    {
+        set_ducking(id, installations, channels, |params| params.enabled = enabled);
+    }
+
+    // The lowest priority that ducks the sounds beneath it. Background sounds duck nothing.
+    let trigger_labels: Vec<_> = (1..Priority::VARIANT_COUNT)
+        .map(|i| format!("Triggered By: {}", Priority::label_from_index(i)))
+        .collect();
+    let selected = params.trigger.to_index().checked_sub(1);
+    for index in widget::DropDownList::new(&trigger_labels, selected)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .label_font_size(SMALL_FONT_SIZE)
+        .scrollbar_on_top()
+        .right(PAD)
+        .set(ids.installation_editor_ducking_trigger, ui)
+    {
+        let trigger = match Priority::from_index(index + 1) {
+            Some(priority) => priority,
+            None => continue,
+        };
+        set_ducking(id, installations, channels, |params| params.trigger = trigger);
+    }
+
+    // The depth, attack and release.
+    let label = format!("Depth: {:.1} dB", params.depth_db);
+    for value in widget::Slider::new(params.depth_db, 0.0, ducking::MAX_DEPTH_DB)
+        .w(third_w)
+        .h(SLIDER_H)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .label(&label)
+        .align_left()
+        .down(PAD)
+        .set(ids.installation_editor_ducking_depth, ui)
+    {
+        let db = (value * 2.0).round() / 2.0;
+        set_ducking(id, installations, channels, |params| params.depth_db = db);
+    }
+
+    let label = format!("Attack: {:.2} secs", params.attack_secs);
+    for value in widget::Slider::new(params.attack_secs, 0.0, ducking::MAX_ATTACK_SECS)
+        .skew(0.5)
+        .w(third_w)
+        .h(SLIDER_H)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .label(&label)
+        .right(PAD)
+        .set(ids.installation_editor_ducking_attack, ui)
+    {
+        let secs = (value * 100.0).round() / 100.0;
+        set_ducking(id, installations, channels, |params| params.attack_secs = secs);
+    }
+
+    let label = format!("Release: {:.1} secs", params.release_secs);
+    for value in widget::Slider::new(params.release_secs, 0.0, ducking::MAX_RELEASE_SECS)
+        .skew(0.5)
+        .w(third_w)
+        .h(SLIDER_H)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .label(&label)
+        .right(PAD)
+        .set(ids.installation_editor_ducking_release, ui)
+    {
+        let secs = (value * 10.0).round() / 10.0;
+        set_ducking(id, installations, channels, |params| params.release_secs = secs);
+    }
+
+    /////////////////
+    // SENSOR GAIN //
+    /////////////////
+
+    // The canvas for riding the installation's level against an external sensor.
+    widget::Canvas::new()
+        .middle_of(ids.installation_editor_selected_canvas)
+        .down_from(ids.installation_editor_ducking_canvas, PAD)
+        .color(color::CHARCOAL)
+        .w(selected_canvas_kid_area.w())
+        .h(sensor_canvas_h)
+        .pad(PAD)
+        .set(ids.installation_editor_sensor_canvas, ui);
+
+    widget::Text::new("Sensor Gain - Ride the level via \"<prefix>/sensor\"")
+        .font_size(SMALL_FONT_SIZE)
+        .top_left_of(ids.installation_editor_sensor_canvas)
+        .set(ids.installation_editor_sensor_text, ui);
+
+    let sensor_kid_area = ui.kid_area_of(ids.installation_editor_sensor_canvas).unwrap();
+    let half_w = (sensor_kid_area.w() - PAD) / 2.0;
+    let params = installations[&id].sensor_gain;
+
+    // Enable the sensor and display its latest reading along with the resulting gain.
+    let label = match sensor_readings.get(&id) {
+        Some(&reading) => format!("Reading: {:.1} -> x{:.2}", reading, params.gain_at(reading)),
+        None => "No Readings".to_string(),
+    };
+    for enabled in widget::Toggle::new(params.enabled)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::BLUE)
+        .w(sensor_kid_area.w())
+        .h(SLIDER_H)
+        .align_left()
+        .down(PAD * 2.0)
+        .set(ids.installation_editor_sensor_enabled, ui)
+    {
+        set_sensor_gain(id, installations, sensor_readings, channels, |p| p.enabled = enabled);
+    }
+
+    // The range of readings mapped to the range of gains.
+    let range_slider = |range: utils::Range<f32>, max: f32| {
+        widget::RangeSlider::new(range.min, range.max, 0.0, max)
+            .w(half_w)
+            .h(SLIDER_H)
+            .label_font_size(SMALL_FONT_SIZE)
+            .color(ui::color::LIGHT_CHARCOAL)
+    };
+    let label = format!("Input: {:.0} to {:.0}", params.input.min, params.input.max);
+    for (edge, value) in range_slider(params.input, installation::MAX_SENSOR_INPUT)
+        .label(&label)
+        .align_left()
+        .down(PAD)
+        .set(ids.installation_editor_sensor_input, ui)
+    {
+        let value = value.round();
+        set_sensor_gain(id, installations, sensor_readings, channels, |p| match edge {
+            widget::range_slider::Edge::Start => p.input.min = value,
+            widget::range_slider::Edge::End => p.input.max = value,
+        });
+    }
+
+    let label = format!("Gain: x{:.2} to x{:.2}", params.gain.min, params.gain.max);
+    for (edge, value) in range_slider(params.gain, installation::MAX_SENSOR_GAIN)
+        .label(&label)
+        .right(PAD)
+        .set(ids.installation_editor_sensor_gain, ui)
+    {
+        set_sensor_gain(id, installations, sensor_readings, channels, |p| match edge {
+            widget::range_slider::Edge::Start => p.gain.min = value,
+            widget::range_slider::Edge::End => p.gain.max = value,
+        });
+    }
+
+    //////////////
+    // ACTIVITY //
+    //////////////
+
+    // The canvas for mapping the visitor activity within the installation onto its soundscape.
+    widget::Canvas::new()
+        .middle_of(ids.installation_editor_selected_canvas)
+        .down_from(ids.installation_editor_sensor_canvas, PAD)
+        .color(color::CHARCOAL)
+        .w(selected_canvas_kid_area.w())
+        .h(activity_canvas_h)
+        .pad(PAD)
+        .set(ids.installation_editor_activity_canvas, ui);
+
+    widget::Text::new("Activity - Respond to visitors via \"<prefix>/activity\"")
+        .font_size(SMALL_FONT_SIZE)
+        .top_left_of(ids.installation_editor_activity_canvas)
+        .set(ids.installation_editor_activity_text, ui);
+
+    let activity_kid_area = ui.kid_area_of(ids.installation_editor_activity_canvas).unwrap();
+    let half_w = (activity_kid_area.w() - PAD) / 2.0;
+    let params = installations[&id].soundscape.activity;
+
+    // Enable the activity mapping and display the latest reading along with its response.
+    let label = match activity_readings.get(&id) {
+        Some(&reading) => format!("Reading: {:.1} -> {:.2}", reading, params.response(reading)),
+        None => "No Readings".to_string(),
+    };
+    for enabled in widget::Toggle::new(params.enabled)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::BLUE)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .align_left()
+        .down(PAD * 2.0)
+        .set(ids.installation_editor_activity_enabled, ui)
+    {
+        set_activity(id, installations, channels, |p| p.enabled = enabled);
+    }
+
+    // Whether or not the response drives the soundscape's intensity macro.
+    for drives_intensity in widget::Toggle::new(params.drives_intensity)
+        .label("Drive Intensity")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::BLUE)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .right(PAD)
+        .set(ids.installation_editor_activity_drives_intensity, ui)
+    {
+        set_activity(id, installations, channels, |p| p.drives_intensity = drives_intensity);
+    }
+
+    // The range of readings and the curve mapping them onto the response.
+    let range_slider = |range: utils::Range<f32>, max: f32| {
+        widget::RangeSlider::new(range.min, range.max, 0.0, max)
+            .w(half_w)
+            .h(SLIDER_H)
+            .label_font_size(SMALL_FONT_SIZE)
+            .color(ui::color::LIGHT_CHARCOAL)
+    };
+    let label = format!("Input: {:.0} to {:.0}", params.input.min, params.input.max);
+    for (edge, value) in range_slider(params.input, activity::MAX_INPUT)
+        .label(&label)
+        .align_left()
+        .down(PAD)
+        .set(ids.installation_editor_activity_input, ui)
+    {
+        let value = value.round();
+        set_activity(id, installations, channels, |p| match edge {
+            widget::range_slider::Edge::Start => p.input.min = value,
+            widget::range_slider::Edge::End => p.input.max = value,
+        });
+    }
+
+    let label = format!("Curve: {:.2}", params.curve);
+    for value in widget::Slider::new(params.curve, activity::MIN_CURVE, activity::MAX_CURVE)
+        .skew(0.5)
+        .w(half_w)
+        .h(SLIDER_H)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .label(&label)
+        .right(PAD)
+        .set(ids.installation_editor_activity_curve, ui)
+    {
+        set_activity(id, installations, channels, |p| p.curve = value);
+    }
+
+    // The density and intensity at the bounds of the input range.
+    let label = format!("Density: x{:.2} to x{:.2}", params.density.min, params.density.max);
+    for (edge, value) in range_slider(params.density, activity::MAX_DENSITY)
+        .label(&label)
+        .align_left()
+        .down(PAD)
+        .set(ids.installation_editor_activity_density, ui)
+    {
+        set_activity(id, installations, channels, |p| match edge {
+            widget::range_slider::Edge::Start => p.density.min = value,
+            widget::range_slider::Edge::End => p.density.max = value,
+        });
+    }
+
+    let label = format!("Intensity: {:.2} to {:.2}", params.intensity.min, params.intensity.max);
+    for (edge, value) in range_slider(params.intensity, 1.0)
+        .label(&label)
+        .right(PAD)
+        .set(ids.installation_editor_activity_intensity, ui)
+    {
+        set_activity(id, installations, channels, |p| match edge {
+            widget::range_slider::Edge::Start => p.intensity.min = value,
+            widget::range_slider::Edge::End => p.intensity.max = value,
+        });
+    }
+
+    ///////////////
+    // WALK TEST //
+    ///////////////
+
+    // The canvas for walking through the installation's speakers.
+    widget::Canvas::new()
+        .middle_of(ids.installation_editor_selected_canvas)
+        .down_from(ids.installation_editor_activity_canvas, PAD)
+        .color(color::CHARCOAL)
+        .w(selected_canvas_kid_area.w())
+        .h(walk_test_canvas_h)
+        .pad(PAD)
+        .set(ids.installation_editor_walk_test_canvas, ui);
+
+    widget::Text::new("Walk Test - Announce each speaker in turn")
+        .font_size(SMALL_FONT_SIZE)
+        .top_left_of(ids.installation_editor_walk_test_canvas)
+        .set(ids.installation_editor_walk_test_text, ui);
+
+    // A button for starting and stopping the walk test. Only one may be underway at a time.
+    let walk_test_kid_area = ui.kid_area_of(ids.installation_editor_walk_test_canvas).unwrap();
+    let half_w = (walk_test_kid_area.w() - PAD) / 2.0;
+    let (label, color) = match *walk_test {
+        Some(ref test) if test.installation == id => {
+            let label = match test.current_speaker() {
+                Some(name) => format!("STOP: {}", name),
+                None => "STOP".to_string(),
+            };
+            (label, color::DARK_RED)
+        },
+        Some(_) => ("STOP OTHER TEST".to_string(), color::DARK_RED),
+        None => ("WALK TEST".to_string(), color::DARK_CHARCOAL),
+    };
+    for _click in widget::Button::new()
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .align_left()
+        .down(PAD * 2.0)
+        .set(ids.installation_editor_walk_test_button, ui)
+    {
+        if walk_test.is_some() {
+            stop_walk_test(channels, walk_test);
+        } else {
+            let interval = *walk_test_interval_secs;
+            *walk_test = start_walk_test(id, speakers, assets, interval, channels);
+        }
+    }
+
+    // The interval between announcements, applied upon starting the walk test.
+    let label = format!("Interval: {:.1} secs", *walk_test_interval_secs);
+    let min = walk_test::MIN_INTERVAL_SECS;
+    let max = walk_test::MAX_INTERVAL_SECS;
+    for value in widget::Slider::new(*walk_test_interval_secs, min, max)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .label(&label)
+        .right(PAD)
+        .set(ids.installation_editor_walk_test_interval, ui)
+    {
+        *walk_test_interval_secs = value;
+    }
+
     ///////////////
     // COMPUTERS //
     ///////////////
@@ -445,7 +1480,7 @@ pub fn set(
     // The canvas for displaying the computer selection / editor.
     widget::Canvas::new()
         .middle_of(ids.installation_editor_selected_canvas)
-        .down_from(ids.installation_editor_soundscape_canvas, PAD)
+        .down_from(ids.installation_editor_walk_test_canvas, PAD)
         .color(color::CHARCOAL)
         .w(selected_canvas_kid_area.w())
         .h(computer_canvas_h)
@@ -496,7 +1531,9 @@ pub fn set(
                 let add = osc::output::OscTarget::Add(id, computer, target, osc_addr.clone());
                 let msg = osc::output::Message::Osc(add);
                 channels.osc_out_msg_tx.push(msg);
-                let addr = installation::computer::Address { socket, osc_addr };
+                let capture = false;
+                let dry_run = false;
+                let addr = installation::computer::Address { socket, osc_addr, capture, dry_run };
                 installation.computers.insert(computer, addr);
             }
         } else if n_computers > n {
@@ -552,7 +1589,10 @@ pub fn set(
                     color::BLACK
                 };
                 let addr = &installations[&id].computers[&computer];
-                let label = format!("{} {}", addr.socket, addr.osc_addr);
+                let label = match addr.dry_run {
+                    true => format!("{} {} (dry run)", addr.socket, addr.osc_addr),
+                    false => format!("{} {}", addr.socket, addr.osc_addr),
+                };
 
                 // Use `Button`s for the selectable items.
                 let button = widget::Button::new()
@@ -655,12 +1695,16 @@ pub fn set(
         let add = osc::output::OscTarget::Add(id, selected.computer, target, osc_addr.clone());
         let msg = osc::output::Message::Osc(add);
         channels.osc_out_msg_tx.push(msg);
-        let addr = installation::computer::Address { socket, osc_addr };
-        installations
+        let computers = &mut installations
             .get_mut(&id)
             .expect("no installation for id")
-            .computers
-            .insert(selected.computer, addr);
+            .computers;
+        let (capture, dry_run) = computers
+            .get(&selected.computer)
+            .map(|a| (a.capture, a.dry_run))
+            .unwrap_or((false, false));
+        let addr = installation::computer::Address { socket, osc_addr, capture, dry_run };
+        computers.insert(selected.computer, addr);
     }
 
     // The textbox for editing the OSC output IP address.
@@ -715,5 +1759,51 @@ pub fn set(
         }
     }
 
+    // Capture all OSC sent to the computer to disk, e.g. as evidence of when cues were sent.
+    let computer = selected_computer.computer;
+    let capture = installations[&id].computers[&computer].capture;
+    for capture in widget::Toggle::new(capture)
+        .label("Capture Sent OSC To Disk")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::BLUE)
+        .align_middle_x_of(ids.installation_editor_osc_canvas)
+        .down(PAD)
+        .parent(ids.installation_editor_osc_canvas)
+        .kid_area_w_of(ids.installation_editor_osc_canvas)
+        .h(ITEM_HEIGHT)
+        .set(ids.installation_editor_osc_capture, ui)
+    {
+        let installation = installations.get_mut(&id).unwrap();
+        let name = match capture {
+            true => Some(installation::computer::capture_name(&installation.name, computer)),
+            false => None,
+        };
+        if let Some(addr) = installation.computers.get_mut(&computer) {
+            addr.capture = capture;
+        }
+        let capture = osc::output::OscTarget::Capture(id, computer, name);
+        channels.osc_out_msg_tx.push(osc::output::Message::Osc(capture));
+    }
+
+    // Format and log OSC for the computer without sending it, e.g. while the rig is in use.
+    let dry_run = installations[&id].computers[&computer].dry_run;
+    for dry_run in widget::Toggle::new(dry_run)
+        .label("Dry Run (Log Without Sending)")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::DARK_YELLOW)
+        .align_middle_x_of(ids.installation_editor_osc_canvas)
+        .down(PAD)
+        .parent(ids.installation_editor_osc_canvas)
+        .kid_area_w_of(ids.installation_editor_osc_canvas)
+        .h(ITEM_HEIGHT)
+        .set(ids.installation_editor_osc_dry_run, ui)
+    {
+        if let Some(addr) = installations.get_mut(&id).unwrap().computers.get_mut(&computer) {
+            addr.dry_run = dry_run;
+        }
+        let dry_run = osc::output::OscTarget::DryRun(id, computer, dry_run);
+        channels.osc_out_msg_tx.push(osc::output::Message::Osc(dry_run));
+    }
+
     area.id
 }
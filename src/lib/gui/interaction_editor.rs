@@ -0,0 +1,715 @@
+use gui::{self, collapsible_area, Channels, Gui, ProjectState, State};
+use gui::{ITEM_HEIGHT, SMALL_FONT_SIZE};
+use installation;
+use interaction::{self, CalibrationPoint, Transform};
+use metres::Metres;
+use nannou::prelude::*;
+use nannou::ui;
+use nannou::ui::prelude::*;
+use project::{self, Project};
+use std::time::Instant;
+use time_calc::Ms;
+
+/// Runtime state relevant to the interaction editor GUI.
+#[derive(Default)]
+pub struct InteractionEditor {
+    pub selected: Option<Selected>,
+    /// All entities currently tracked by the interaction inputs.
+    pub tracks: interaction::Tracks,
+}
+
+/// The currently selected interaction input.
+pub struct Selected {
+    id: interaction::Id,
+    name: String,
+    osc_prefix: String,
+    transform_text: String,
+    /// Whether or not clicks on the floorplan add calibration points.
+    calibrating: bool,
+    /// Pairs of positions reported by the input and the floorplan positions at which they were
+    /// observed.
+    calibration_points: Vec<CalibrationPoint>,
+    /// The floorplan position of the visitor simulated via the input, if simulating.
+    simulated_visitor: Option<Point2<Metres>>,
+}
+
+impl Selected {
+    fn new(id: interaction::Id, input: &interaction::Input) -> Self {
+        Selected {
+            id,
+            name: input.name.clone(),
+            osc_prefix: input.osc_prefix.clone(),
+            transform_text: transform_text(&input.transform),
+            calibrating: false,
+            calibration_points: vec![],
+            simulated_visitor: None,
+        }
+    }
+}
+
+/// Transform and forward a position received under the given OSC address prefix.
+///
+/// The position is forwarded to the soundscape once for each interaction input bound to the
+/// prefix, unless it is rejected as an outlier by the entity's smoothing filter.
+pub fn receive_position(
+    channels: &Channels,
+    project: &Project,
+    interaction_editor: &mut InteractionEditor,
+    prefix: &str,
+    entity: interaction::EntityId,
+    raw: Point2<f64>,
+) {
+    let prefix = installation::normalise_osc_prefix(prefix);
+    let now = Instant::now();
+    for (&id, input) in project.state.interaction_inputs.iter() {
+        if input.osc_prefix.is_empty() || input.osc_prefix != prefix {
+            continue;
+        }
+        let interaction = match interaction_editor.tracks.update(input, id, entity, raw, now) {
+            Some(interaction) => interaction,
+            None => continue,
+        };
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_interaction(interaction);
+            })
+            .expect("failed to send interaction to soundscape thread");
+    }
+}
+
+/// Feed the position of the simulated visitor through the selected input as though it were
+/// received via OSC.
+///
+/// This should be called once per GUI update so that the simulated visitor is not lost.
+pub fn update_simulated_visitor(
+    channels: &Channels,
+    project: &Project,
+    interaction_editor: &mut InteractionEditor,
+) {
+    let InteractionEditor { ref selected, ref mut tracks } = *interaction_editor;
+    let (id, position) = match *selected {
+        Some(Selected { id, simulated_visitor: Some(position), .. }) => (id, position),
+        _ => return,
+    };
+    let input = match project.state.interaction_inputs.get(&id) {
+        Some(input) => input,
+        None => return,
+    };
+    let raw = match input.transform.invert(position) {
+        Some(raw) => raw,
+        None => return,
+    };
+    let entity = interaction::SIMULATED_ENTITY;
+    let interaction = match tracks.update(input, id, entity, raw, Instant::now()) {
+        Some(interaction) => interaction,
+        None => return,
+    };
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            soundscape.update_interaction(interaction);
+        })
+        .expect("failed to send simulated interaction to soundscape thread");
+}
+
+/// The floorplan position of the simulated visitor, if one is being simulated.
+pub fn simulated_visitor(interaction_editor: &InteractionEditor) -> Option<Point2<Metres>> {
+    interaction_editor.selected.as_ref().and_then(|s| s.simulated_visitor)
+}
+
+/// Move the simulated visitor to the given floorplan position, e.g. when dragged.
+pub fn move_simulated_visitor(interaction_editor: &mut InteractionEditor, to: Point2<Metres>) {
+    if let Some(ref mut selected) = interaction_editor.selected {
+        if selected.simulated_visitor.is_some() {
+            selected.simulated_visitor = Some(to);
+        }
+    }
+}
+
+/// Remove all tracked entities that have not been seen within their input's track timeout.
+pub fn remove_lost_tracks(
+    channels: &Channels,
+    project: &Project,
+    interaction_editor: &mut InteractionEditor,
+) {
+    let mut lost = vec![];
+    let inputs = &project.state.interaction_inputs;
+    interaction_editor.tracks.remove_lost(inputs, Instant::now(), &mut lost);
+    if lost.is_empty() {
+        return;
+    }
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            for track in lost {
+                soundscape.remove_interaction(&track);
+            }
+        })
+        .expect("failed to send lost interaction tracks to soundscape thread");
+}
+
+/// Called when the floorplan is clicked at the given position.
+///
+/// If the selected input is being calibrated, a calibration point is added using the raw position
+/// of the input's most recently seen entity and the transform is solved again. Returns `true` if
+/// the click was used.
+pub fn floorplan_clicked(
+    project: &mut Project,
+    interaction_editor: &mut InteractionEditor,
+    position: Point2<Metres>,
+) -> bool {
+    let InteractionEditor { ref mut selected, ref mut tracks } = *interaction_editor;
+    let selected = match *selected {
+        Some(ref mut selected) if selected.calibrating => selected,
+        _ => return false,
+    };
+    let raw = match tracks.latest(&selected.id) {
+        Some(track) => track.interaction.raw,
+        None => return true,
+    };
+    selected.calibration_points.push(CalibrationPoint { raw, floorplan: position });
+    if let Some(transform) = Transform::calibrate(&selected.calibration_points) {
+        if let Some(input) = project.state.interaction_inputs.get_mut(&selected.id) {
+            input.transform = transform;
+        }
+        selected.transform_text = transform_text(&transform);
+        tracks.reset_filters(&selected.id);
+    }
+    true
+}
+
+pub fn set(
+    last_area_id: widget::Id,
+    gui: &mut Gui,
+    project: &mut Project,
+    project_state: &mut gui::ProjectState,
+) -> widget::Id {
+    let Gui {
+        ref mut ui,
+        ref ids,
+        channels,
+        state: &mut State {
+            ref mut is_open,
+            ..
+        },
+        ..
+    } = *gui;
+    let Project {
+        state: project::State {
+            ref mut interaction_inputs,
+            ref camera,
+            ..
+        },
+        ..
+    } = *project;
+    let ProjectState {
+        interaction_editor:
+            InteractionEditor {
+                ref mut selected,
+                ref mut tracks,
+            },
+        ..
+    } = *project_state;
+
+    // The height of the list of inputs.
+    const LIST_HEIGHT: Scalar = ITEM_HEIGHT * 3.0;
+    const ADD_H: Scalar = ITEM_HEIGHT;
+    const NAME_H: Scalar = ITEM_HEIGHT;
+    const PAD: Scalar = 6.0;
+    const TEXT_PAD: Scalar = PAD * 2.0;
+    const BUTTON_H: Scalar = ITEM_HEIGHT;
+    const INFO_H: Scalar = 40.0;
+    const SLIDER_H: Scalar = ITEM_HEIGHT;
+
+    // The height of the canvas displaying options for the selected input.
+    let selected_canvas_h = PAD
+        + NAME_H + PAD
+        + TEXT_PAD + PAD + ITEM_HEIGHT + PAD
+        + TEXT_PAD + PAD + ITEM_HEIGHT + PAD
+        + TEXT_PAD + PAD + SLIDER_H + PAD + SLIDER_H + PAD
+        + BUTTON_H + PAD
+        + BUTTON_H + PAD
+        + INFO_H + PAD;
+
+    // The total height of the interaction editor.
+    let interaction_editor_h = LIST_HEIGHT + ADD_H + selected_canvas_h;
+
+    let is_open_now = is_open.interaction_editor;
+    let (area, event) = collapsible_area(is_open_now, "Interaction Editor", ids.side_menu)
+        .align_middle_x_of(ids.side_menu)
+        .down_from(last_area_id, 0.0)
+        .set(ids.interaction_editor, ui);
+    if let Some(event) = event {
+        is_open.interaction_editor = event.is_open();
+    }
+
+    // If the area is open, continue. If its closed, return the editor id as the last id.
+    let area = match area {
+        Some(area) => area,
+        None => return ids.interaction_editor,
+    };
+
+    // The canvas on which the interaction editor widgets will be placed.
+    let canvas = widget::Canvas::new().pad(0.0).h(interaction_editor_h);
+    area.set(canvas, ui);
+
+    // A button for adding new inputs.
+    for _click in widget::Button::new()
+        .label("+")
+        .kid_area_w_of(area.id)
+        .mid_top_with_margin_on(area.id, LIST_HEIGHT)
+        .h(ADD_H)
+        .set(ids.interaction_editor_add, ui)
+    {
+        let input = interaction::Input::default();
+        let id = project::next_interaction_input_id(interaction_inputs);
+        *selected = Some(Selected::new(id, &input));
+        interaction_inputs.insert(id, input);
+    }
+
+    // If there are no inputs, display some text for adding one.
+    if interaction_inputs.is_empty() {
+        widget::Text::new("Add an interaction input with the \"+\" button below!")
+            .font_size(SMALL_FONT_SIZE)
+            .align_middle_x_of(area.id)
+            .down(PAD + ITEM_HEIGHT)
+            .set(ids.interaction_editor_none, ui);
+        return area.id;
+    }
+
+    // The list of inputs sorted by ID.
+    let mut inputs_vec: Vec<_> = interaction_inputs.keys().cloned().collect();
+    inputs_vec.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // Display the input list.
+    let (mut events, scrollbar) = widget::ListSelect::single(inputs_vec.len())
+        .item_size(ITEM_HEIGHT)
+        .h(LIST_HEIGHT)
+        .align_middle_x_of(area.id)
+        .align_top_of(area.id)
+        .scrollbar_color(color::LIGHT_CHARCOAL)
+        .scrollbar_next_to()
+        .set(ids.interaction_editor_list, ui);
+
+    // Track whether or not an item was removed.
+    let mut maybe_remove_index = None;
+
+    // The index of the selected input.
+    let selected_index = selected.as_ref()
+        .and_then(|s| inputs_vec.iter().position(|&id| id == s.id));
+
+    while let Some(event) = events.next(ui, |i| selected_index == Some(i)) {
+        use nannou::ui::widget::list_select::Event;
+        match event {
+            // Instantiate a button for each input.
+            Event::Item(item) => {
+                let id = inputs_vec[item.i];
+                let label = &interaction_inputs[&id].name;
+                let color = if selected_index == Some(item.i) {
+                    color::BLUE
+                } else {
+                    color::CHARCOAL
+                };
+                let button = widget::Button::new()
+                    .label(&label)
+                    .label_font_size(SMALL_FONT_SIZE)
+                    .label_x(position::Relative::Place(position::Place::Start(Some(
+                        10.0,
+                    ))))
+                    .color(color);
+                item.set(button, ui);
+
+                // If the button or any of its children are capturing the mouse, display
+                // the `remove` button.
+                let show_remove_button = ui.global_input()
+                    .current
+                    .widget_capturing_mouse
+                    .map(|id| {
+                        id == item.widget_id
+                            || ui.widget_graph()
+                                .does_recursive_depth_edge_exist(item.widget_id, id)
+                    })
+                    .unwrap_or(false);
+
+                if !show_remove_button {
+                    continue;
+                }
+
+                if widget::Button::new()
+                    .label("X")
+                    .label_font_size(SMALL_FONT_SIZE)
+                    .color(color::DARK_RED.alpha(0.5))
+                    .w_h(ITEM_HEIGHT, ITEM_HEIGHT)
+                    .align_right_of(item.widget_id)
+                    .align_middle_y_of(item.widget_id)
+                    .parent(item.widget_id)
+                    .set(ids.interaction_editor_remove, ui)
+                    .was_clicked()
+                {
+                    maybe_remove_index = Some(item.i);
+                }
+            }
+
+            // Update the selected input.
+            Event::Selection(index) => {
+                let id = inputs_vec[index];
+                *selected = Some(Selected::new(id, &interaction_inputs[&id]));
+            }
+
+            _ => (),
+        }
+    }
+
+    // Instantiate the scrollbar widget if necessary.
+    if let Some(scrollbar) = scrollbar {
+        scrollbar.set(ui);
+    }
+
+    // Remove an input if necessary.
+    if let Some(i) = maybe_remove_index {
+        let id = inputs_vec.remove(i);
+        if selected.as_ref().map(|s| s.id) == Some(id) {
+            *selected = None;
+        }
+        interaction_inputs.remove(&id);
+        tracks.remove_input(&id);
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.remove_interaction_input(&id);
+            })
+            .expect("failed to send removed interaction input to soundscape thread");
+    }
+
+    let area_rect = ui.rect_of(area.id).unwrap();
+    let start = area_rect.y.start;
+    let end = start + selected_canvas_h;
+    let selected_canvas_y = ui::Range { start, end };
+
+    widget::Canvas::new()
+        .pad(PAD)
+        .w_of(ids.side_menu)
+        .h(selected_canvas_h)
+        .y(selected_canvas_y.middle())
+        .align_middle_x_of(ids.side_menu)
+        .set(ids.interaction_editor_selected_canvas, ui);
+    let selected_canvas_kid_area = ui.kid_area_of(ids.interaction_editor_selected_canvas)
+        .unwrap();
+
+    // If an input is selected, display its options.
+    let selected = match *selected {
+        Some(ref mut selected) => selected,
+        None => return area.id,
+    };
+
+    let Selected {
+        id,
+        ref mut name,
+        ref mut osc_prefix,
+        ref mut transform_text,
+        ref mut calibrating,
+        ref mut calibration_points,
+        ref mut simulated_visitor,
+    } = *selected;
+
+    // A textbox for editing the name of the input.
+    let color = if name == &interaction_inputs[&id].name {
+        color::BLACK
+    } else if name.is_empty() {
+        color::DARK_RED
+    } else {
+        color::DARK_GREEN
+    };
+    for event in widget::TextBox::new(name)
+        .w(selected_canvas_kid_area.w())
+        .h(NAME_H)
+        .color(color)
+        .font_size(SMALL_FONT_SIZE)
+        .mid_top_of(ids.interaction_editor_selected_canvas)
+        .set(ids.interaction_editor_name, ui)
+    {
+        use nannou::ui::widget::text_box::Event;
+        match event {
+            Event::Update(s) => *name = s,
+            Event::Enter => if !name.is_empty() {
+                interaction_inputs.get_mut(&id).unwrap().name = name.clone();
+            },
+        }
+    }
+
+    // The OSC address prefix under which positions are received.
+    widget::Text::new("OSC Prefix - positions at \"<prefix>/position x y\"")
+        .font_size(SMALL_FONT_SIZE)
+        .align_left_of(ids.interaction_editor_name)
+        .down(PAD * 2.0)
+        .set(ids.interaction_editor_osc_prefix_text, ui);
+    let color = match installation::normalise_osc_prefix(osc_prefix) {
+        ref p if *p == interaction_inputs[&id].osc_prefix => color::BLACK,
+        ref p if p.is_empty() => color::DARK_RED,
+        _ => color::DARK_GREEN,
+    };
+    for event in widget::TextBox::new(osc_prefix)
+        .w(selected_canvas_kid_area.w())
+        .h(ITEM_HEIGHT)
+        .color(color)
+        .font_size(SMALL_FONT_SIZE)
+        .align_left_of(ids.interaction_editor_name)
+        .down(PAD)
+        .set(ids.interaction_editor_osc_prefix, ui)
+    {
+        use nannou::ui::widget::text_box::Event;
+        match event {
+            Event::Update(s) => *osc_prefix = s,
+            Event::Enter => {
+                let prefix = installation::normalise_osc_prefix(osc_prefix);
+                *osc_prefix = prefix.clone();
+                interaction_inputs.get_mut(&id).unwrap().osc_prefix = prefix;
+                tracks.remove_input(&id);
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.remove_interaction_input(&id);
+                    })
+                    .expect("failed to send interaction input update to soundscape thread");
+            },
+        }
+    }
+
+    // The transform from the input's coordinate frame onto the floorplan.
+    widget::Text::new("Transform - Scale X, Scale Y, Rotation°, Offset X m, Offset Y m")
+        .font_size(SMALL_FONT_SIZE)
+        .align_left_of(ids.interaction_editor_name)
+        .down(PAD * 2.0)
+        .set(ids.interaction_editor_transform_text, ui);
+    let color = match parse_transform(transform_text) {
+        Ok(ref t) if *t == interaction_inputs[&id].transform => color::BLACK,
+        Ok(_) => color::DARK_GREEN,
+        Err(_) => color::DARK_RED,
+    };
+    for event in widget::TextBox::new(transform_text)
+        .w(selected_canvas_kid_area.w())
+        .h(ITEM_HEIGHT)
+        .color(color)
+        .font_size(SMALL_FONT_SIZE)
+        .align_left_of(ids.interaction_editor_name)
+        .down(PAD)
+        .set(ids.interaction_editor_transform, ui)
+    {
+        use nannou::ui::widget::text_box::Event;
+        match event {
+            Event::Update(s) => *transform_text = s,
+            Event::Enter => match parse_transform(transform_text) {
+                Ok(transform) => {
+                    interaction_inputs.get_mut(&id).unwrap().transform = transform;
+                    *transform_text = self::transform_text(&transform);
+                    tracks.reset_filters(&id);
+                },
+                Err(err) => eprintln!("failed to parse interaction transform: {}", err),
+            },
+        }
+    }
+
+    // Smoothing of tracker jitter and rejection of sudden jumps.
+    widget::Text::new("Smoothing - Jumps beyond the max are rejected")
+        .font_size(SMALL_FONT_SIZE)
+        .align_left_of(ids.interaction_editor_name)
+        .down(PAD * 2.0)
+        .set(ids.interaction_editor_smoothing_text, ui);
+
+    let smoothing = interaction_inputs[&id].smoothing;
+    let label = match smoothing.time_constant.ms() {
+        ms if ms > 0.0 => format!("Smoothing: {:.0} ms", ms),
+        _ => "Smoothing: Off".to_string(),
+    };
+    let half_w = selected_canvas_kid_area.w() / 2.0 - PAD / 2.0;
+    for new_ms in widget::Slider::new(smoothing.time_constant.ms(), 0.0, 2_000.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .skew(0.5)
+        .color(color::LIGHT_CHARCOAL)
+        .w(half_w)
+        .h(SLIDER_H)
+        .align_left_of(ids.interaction_editor_name)
+        .down(PAD)
+        .set(ids.interaction_editor_smoothing_time, ui)
+    {
+        interaction_inputs.get_mut(&id).unwrap().smoothing.time_constant = Ms(new_ms.round());
+    }
+
+    // The duration after which an entity that is no longer reported is considered lost.
+    let track_timeout = interaction_inputs[&id].track_timeout;
+    let label = format!("Lost After: {:.1} secs", track_timeout.ms() / 1_000.0);
+    for new_ms in widget::Slider::new(track_timeout.ms(), 100.0, 30_000.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .skew(0.5)
+        .color(color::LIGHT_CHARCOAL)
+        .w(half_w)
+        .h(SLIDER_H)
+        .right(PAD)
+        .set(ids.interaction_editor_track_timeout, ui)
+    {
+        interaction_inputs.get_mut(&id).unwrap().track_timeout = Ms(new_ms.round());
+    }
+
+    let label = match smoothing.max_jump.0 {
+        m if m > 0.0 => format!("Max Jump: {:.2} m", m),
+        _ => "Max Jump: Off".to_string(),
+    };
+    for new_metres in widget::Slider::new(smoothing.max_jump.0, 0.0, 10.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .w(half_w)
+        .h(SLIDER_H)
+        .align_left_of(ids.interaction_editor_name)
+        .down_from(ids.interaction_editor_smoothing_time, PAD)
+        .set(ids.interaction_editor_smoothing_max_jump, ui)
+    {
+        interaction_inputs.get_mut(&id).unwrap().smoothing.max_jump = Metres(new_metres);
+    }
+
+    let label = format!("Accept After: {} rejections", smoothing.max_rejections);
+    let max_rejections = smoothing.max_rejections as f32;
+    for new_rejections in widget::Slider::new(max_rejections, 1.0, 100.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .w(half_w)
+        .h(SLIDER_H)
+        .right(PAD)
+        .set(ids.interaction_editor_smoothing_max_rejections, ui)
+    {
+        let new_rejections = new_rejections.round() as u32;
+        interaction_inputs.get_mut(&id).unwrap().smoothing.max_rejections = new_rejections;
+    }
+
+    // Buttons for calibrating the transform by clicking corresponding points on the floorplan.
+    let button_w = selected_canvas_kid_area.w() / 4.0 - PAD * 0.75;
+    let label = match *calibrating {
+        true => format!("CALIBRATING ({})", calibration_points.len()),
+        false => "CALIBRATE".to_string(),
+    };
+    for new_calibrating in widget::Toggle::new(*calibrating)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .w(button_w * 2.0 + PAD)
+        .h(BUTTON_H)
+        .align_left_of(ids.interaction_editor_name)
+        .down_from(ids.interaction_editor_smoothing_max_jump, PAD)
+        .set(ids.interaction_editor_calibrate, ui)
+    {
+        *calibrating = new_calibrating;
+    }
+
+    if widget::Button::new()
+        .label("CLEAR")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .w(button_w)
+        .h(BUTTON_H)
+        .right(PAD)
+        .set(ids.interaction_editor_calibrate_clear, ui)
+        .was_clicked()
+    {
+        calibration_points.clear();
+    }
+
+    if widget::Button::new()
+        .label("RESET")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .w(button_w)
+        .h(BUTTON_H)
+        .right(PAD)
+        .set(ids.interaction_editor_transform_reset, ui)
+        .was_clicked()
+    {
+        let transform = Transform::default();
+        interaction_inputs.get_mut(&id).unwrap().transform = transform;
+        *transform_text = self::transform_text(&transform);
+        calibration_points.clear();
+        tracks.reset_filters(&id);
+    }
+
+    // A toggle for simulating a visitor that may be dragged around the floorplan.
+    let label = match *simulated_visitor {
+        Some(_) => "SIMULATING VISITOR - DRAG THE MARKER",
+        None => "SIMULATE VISITOR",
+    };
+    for new_simulating in widget::Toggle::new(simulated_visitor.is_some())
+        .label(label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .w(selected_canvas_kid_area.w())
+        .h(BUTTON_H)
+        .align_left_of(ids.interaction_editor_name)
+        .down_from(ids.interaction_editor_calibrate, PAD)
+        .set(ids.interaction_editor_simulate, ui)
+    {
+        // Place the visitor in the middle of the visible floorplan.
+        *simulated_visitor = match new_simulating {
+            true => Some(camera.position),
+            false => None,
+        };
+    }
+
+    // Display the latest position received along with calibration instructions.
+    let position_line = match tracks.latest(&id) {
+        None => "No entities tracked".to_string(),
+        Some(track) => format!(
+            "{} tracked. Entity {} Raw: ({:.3}, {:.3}) -> ({:.2}m, {:.2}m)",
+            tracks.count(&id),
+            track.interaction.entity.0,
+            track.interaction.raw.x,
+            track.interaction.raw.y,
+            track.interaction.position.x.0,
+            track.interaction.position.y.0,
+        ),
+    };
+    let info = match *calibrating {
+        false => position_line,
+        true => format!("{}\nClick the floorplan where the tracked point currently is.",
+                        position_line),
+    };
+    widget::Text::new(&info)
+        .font_size(SMALL_FONT_SIZE)
+        .align_left_of(ids.interaction_editor_name)
+        .down_from(ids.interaction_editor_simulate, PAD * 2.0)
+        .line_spacing(PAD)
+        .set(ids.interaction_editor_info, ui);
+
+    area.id
+}
+
+// Produce the text representation of the given transform.
+fn transform_text(t: &Transform) -> String {
+    format!(
+        "{} {} {:.2} {:.3} {:.3}",
+        t.scale_x,
+        t.scale_y,
+        t.radians.to_degrees(),
+        t.translation.x.0,
+        t.translation.y.0,
+    )
+}
+
+// Parse a transform of the form "<scale_x> <scale_y> <degrees> <offset_x> <offset_y>".
+fn parse_transform(text: &str) -> Result<Transform, String> {
+    let values = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<f64>().map_err(|_| format!("\"{}\" is not a number", s)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if values.len() != 5 {
+        return Err(format!("expected 5 values but found {}", values.len()));
+    }
+    Ok(Transform {
+        scale_x: values[0],
+        scale_y: values[1],
+        radians: values[2].to_radians(),
+        translation: pt2(Metres(values[3]), Metres(values[4])),
+    })
+}
@@ -0,0 +1,252 @@
+//! Momentary, short-term and integrated loudness of the master bus following EBU R 128, along
+//! with the greatest true peak.
+//!
+//! The audio output thread sends the K-weighted power and true peak of every buffer (see
+//! `audio::loudness`). These are accumulated into 100 ms sub-blocks from which the gated
+//! measurements are derived. Readings may be logged to a CSV file, e.g. to demonstrate to venue
+//! management that agreed loudness limits are respected.
+
+use audio;
+use audio::loudness::{lufs, Analysis};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Blocks quieter than this are excluded from the integrated loudness.
+pub const ABSOLUTE_GATE_LUFS: f32 = -70.0;
+
+/// Blocks this far below the absolutely gated loudness are excluded from the integrated loudness.
+pub const RELATIVE_GATE_LU: f32 = -10.0;
+
+/// The interval at which readings are written to the log.
+pub const LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+const SUB_BLOCK_SECS: f64 = 0.1;
+const MOMENTARY_SUB_BLOCKS: usize = 4;
+const SHORT_TERM_SUB_BLOCKS: usize = 30;
+
+// The resolution and ceiling of the histogram of block loudness used for the integrated loudness.
+//
+// A histogram bounds the memory required while measuring for days at a time.
+const HISTOGRAM_STEP_LU: f32 = 0.1;
+const HISTOGRAM_MAX_LUFS: f32 = 10.0;
+
+/// The loudness of the master bus measured since the last reset.
+pub struct Loudness {
+    // The mean power of the most recent sub-blocks.
+    sub_blocks: VecDeque<f64>,
+    // The energy and length of the sub-block in progress.
+    energy: f64,
+    frames: usize,
+    // The number of blocks and their summed power within each bin of loudness.
+    histogram: Vec<(u64, f64)>,
+    /// The greatest true peak amplitude since the last reset.
+    pub max_true_peak: f32,
+    log: Option<Log>,
+}
+
+// A CSV log of loudness readings.
+struct Log {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    last_write: Instant,
+    // The greatest true peak since the last line was written.
+    max_true_peak: f32,
+}
+
+impl Default for Loudness {
+    fn default() -> Self {
+        let bins = ((HISTOGRAM_MAX_LUFS - ABSOLUTE_GATE_LUFS) / HISTOGRAM_STEP_LU) as usize;
+        Loudness {
+            sub_blocks: VecDeque::with_capacity(SHORT_TERM_SUB_BLOCKS + 1),
+            energy: 0.0,
+            frames: 0,
+            histogram: vec![(0, 0.0); bins],
+            max_true_peak: 0.0,
+            log: None,
+        }
+    }
+}
+
+impl Loudness {
+    /// Accumulate the analysis of a buffer of the given number of frames.
+    pub fn update(&mut self, analysis: Analysis, frames: usize) {
+        self.max_true_peak = self.max_true_peak.max(analysis.true_peak);
+        self.energy += analysis.power as f64 * frames as f64;
+        self.frames += frames;
+
+        // Complete the sub-block and the 400 ms block that it ends.
+        let sub_block_frames = (SUB_BLOCK_SECS * audio::sample_rate()) as usize;
+        if self.frames >= sub_block_frames {
+            let power = self.energy / self.frames as f64;
+            self.energy = 0.0;
+            self.frames = 0;
+            self.sub_blocks.push_back(power);
+            if self.sub_blocks.len() > SHORT_TERM_SUB_BLOCKS {
+                self.sub_blocks.pop_front();
+            }
+            if let Some(block) = self.mean_power(MOMENTARY_SUB_BLOCKS) {
+                self.add_block(block);
+            }
+        }
+
+        self.update_log(analysis.true_peak);
+    }
+
+    /// The loudness over the last 400 ms in LUFS.
+    pub fn momentary(&self) -> Option<f32> {
+        self.mean_power(MOMENTARY_SUB_BLOCKS).map(lufs)
+    }
+
+    /// The loudness over the last 3 seconds in LUFS.
+    pub fn short_term(&self) -> Option<f32> {
+        self.mean_power(SHORT_TERM_SUB_BLOCKS).map(lufs)
+    }
+
+    /// The gated loudness since the last reset in LUFS.
+    pub fn integrated(&self) -> Option<f32> {
+        let (count, sum) = sum_bins(&self.histogram);
+        if count == 0 {
+            return None;
+        }
+        let relative_gate = lufs(sum / count as f64) + RELATIVE_GATE_LU;
+        let start = ((relative_gate - ABSOLUTE_GATE_LUFS) / HISTOGRAM_STEP_LU).max(0.0) as usize;
+        let start = start.min(self.histogram.len());
+        match sum_bins(&self.histogram[start..]) {
+            (0, _) => None,
+            (count, sum) => Some(lufs(sum / count as f64)),
+        }
+    }
+
+    /// Restart all measurements, continuing any log in progress.
+    pub fn reset(&mut self) {
+        self.sub_blocks.clear();
+        self.energy = 0.0;
+        self.frames = 0;
+        self.histogram.iter_mut().for_each(|bin| *bin = (0, 0.0));
+        self.max_true_peak = 0.0;
+    }
+
+    /// The path of the log in progress, if any.
+    pub fn log_path(&self) -> Option<&Path> {
+        self.log.as_ref().map(|log| &log.path[..])
+    }
+
+    /// Begin logging readings to a new CSV file within the given directory.
+    pub fn start_log(&mut self, directory: &Path) -> Result<(), String> {
+        fs::create_dir_all(directory).map_err(|err| format!("{}", err))?;
+        let path = next_log_path(directory);
+        let file = File::create(&path).map_err(|err| format!("{}", err))?;
+        let mut writer = BufWriter::new(file);
+        let header = "unix_secs,momentary_lufs,short_term_lufs,integrated_lufs,max_true_peak_dbtp";
+        writeln!(writer, "{}", header).map_err(|err| format!("{}", err))?;
+        writer.flush().map_err(|err| format!("{}", err))?;
+        let last_write = Instant::now();
+        self.log = Some(Log { path, writer, last_write, max_true_peak: 0.0 });
+        Ok(())
+    }
+
+    /// Stop logging readings, if doing so.
+    pub fn stop_log(&mut self) {
+        if let Some(mut log) = self.log.take() {
+            if let Err(err) = log.writer.flush() {
+                eprintln!("failed to flush loudness log: {}", err);
+            }
+        }
+    }
+
+    // The mean power of the last `n` sub-blocks if there are at least `n`.
+    fn mean_power(&self, n: usize) -> Option<f64> {
+        if self.sub_blocks.len() < n {
+            return None;
+        }
+        let sum: f64 = self.sub_blocks.iter().rev().take(n).sum();
+        Some(sum / n as f64)
+    }
+
+    // Add a 400 ms block to the histogram if it is above the absolute gate.
+    fn add_block(&mut self, power: f64) {
+        let loudness = lufs(power);
+        if loudness <= ABSOLUTE_GATE_LUFS {
+            return;
+        }
+        let index = ((loudness - ABSOLUTE_GATE_LUFS) / HISTOGRAM_STEP_LU) as usize;
+        let bin = &mut self.histogram[index.min(self.histogram.len() - 1)];
+        bin.0 += 1;
+        bin.1 += power;
+    }
+
+    // Write a line to the log if one is in progress and the interval has passed.
+    fn update_log(&mut self, true_peak: f32) {
+        match self.log {
+            None => return,
+            Some(ref mut log) => {
+                log.max_true_peak = log.max_true_peak.max(true_peak);
+                if log.last_write.elapsed() < LOG_INTERVAL {
+                    return;
+                }
+            },
+        }
+        let (momentary, short_term, integrated) =
+            (self.momentary(), self.short_term(), self.integrated());
+        let log = self.log.as_mut().expect("no loudness log");
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let reading = |lufs: Option<f32>| lufs.map(|l| format!("{:.1}", l)).unwrap_or_default();
+        let result = writeln!(
+            log.writer,
+            "{},{},{},{},{:.1}",
+            secs,
+            reading(momentary),
+            reading(short_term),
+            reading(integrated),
+            amp_to_db(log.max_true_peak),
+        ).and_then(|_| log.writer.flush());
+        if let Err(err) = result {
+            eprintln!("failed to write loudness log: {}", err);
+        }
+        log.last_write = Instant::now();
+        log.max_true_peak = 0.0;
+    }
+}
+
+/// Convert the given amplitude to decibels, e.g. a true peak to dBTP.
+pub fn amp_to_db(amp: f32) -> f32 {
+    20.0 * amp.max(1e-6).log10()
+}
+
+// The total count and power of the given bins.
+fn sum_bins(bins: &[(u64, f64)]) -> (u64, f64) {
+    bins.iter().fold((0, 0.0), |(count, sum), &(c, s)| (count + c, sum + s))
+}
+
+// The first unused path of the form `loudness-<n>.csv` within `directory`.
+fn next_log_path(directory: &Path) -> PathBuf {
+    let mut i = 1;
+    loop {
+        let path = directory.join(format!("loudness-{}.csv", i));
+        if !path.exists() {
+            return path;
+        }
+        i += 1;
+    }
+}
+
+#[test]
+fn test_integrated_gating() {
+    let mut loudness = Loudness::default();
+    // Ten blocks at -20 LUFS and ten at -40 LUFS, the latter excluded by the relative gate.
+    let power = |lufs: f64| 10f64.powf((lufs + 0.691) / 10.0);
+    (0..10).for_each(|_| loudness.add_block(power(-20.0)));
+    (0..10).for_each(|_| loudness.add_block(power(-40.0)));
+    // Silence is excluded by the absolute gate.
+    (0..10).for_each(|_| loudness.add_block(0.0));
+    let integrated = loudness.integrated().unwrap();
+    assert!((integrated + 20.0).abs() < 0.01);
+    loudness.reset();
+    assert_eq!(loudness.integrated(), None);
+}
@@ -1,24 +1,101 @@
 //! A "Master" side-bar widget providing control over master volume and input latency.
 
-use gui::{collapsible_area, Gui};
+use audio::{self, ambisonics, bass, binaural, blackout, distance, headroom, limiter, reverb};
+use audio::test_signal;
+use gui::{collapsible_area, custom_widget, loudness, meter, Channels, Gui};
 use gui::{ITEM_HEIGHT, SMALL_FONT_SIZE};
 use project::{self, Project};
+use soundscape;
 use nannou::ui;
 use nannou::ui::prelude::*;
+use std::time::{Duration, Instant};
 use time_calc::Ms;
+use time_zone::TimeZone;
 use metres::Metres;
+use utils;
+
+/// The GUI's view of the load on the audio output thread.
+#[derive(Debug, Default)]
+pub struct DspLoad {
+    /// The greatest load measured since the last GUI update.
+    pub load: f32,
+    /// The greatest load measured since the last reset.
+    pub peak: f32,
+    /// The number of under-runs counted since the last reset.
+    pub xruns: usize,
+}
+
+/// The GUI's view of the master blackout.
+#[derive(Debug, Default)]
+pub struct Blackout {
+    // The moment at which the blackout underway is expected to be fully restored, if any.
+    ends: Option<Instant>,
+    // The time of day at which the schedule was last checked, in seconds since midnight at the
+    // venue.
+    checked_secs: Option<u32>,
+    // The schedule text currently being edited, if any.
+    schedule_text: Option<String>,
+    // The time zone text currently being edited, if any.
+    time_zone_text: Option<String>,
+}
+
+/// The GUI's view of the master reference signal.
+#[derive(Debug)]
+pub struct Reference {
+    /// The kind of signal playing through every speaker, if any.
+    pub kind: Option<test_signal::Kind>,
+    /// The level of the signal in dBFS.
+    pub level_db: f32,
+}
+
+impl Default for Reference {
+    fn default() -> Self {
+        Reference {
+            kind: None,
+            level_db: test_signal::DEFAULT_LEVEL_DB,
+        }
+    }
+}
+
+impl Blackout {
+    /// The number of seconds until the blackout underway is fully restored, if any.
+    pub fn remaining_secs(&self) -> Option<f64> {
+        let now = Instant::now();
+        self.ends
+            .filter(|&ends| ends > now)
+            .map(|ends| utils::duration_to_secs(&(ends - now)))
+    }
+
+    /// Whether one of the given scheduled times has passed since the schedule was last checked.
+    ///
+    /// Always returns `false` upon the first check so that a blackout is not triggered by simply
+    /// starting the server.
+    pub fn check_schedule(
+        &mut self,
+        schedule: &[blackout::TimeOfDay],
+        time_zone: &TimeZone,
+    ) -> bool {
+        let now = time_zone.now_secs_of_day();
+        match self.checked_secs.replace(now) {
+            Some(previous) => blackout::is_due(schedule, previous, now),
+            None => false,
+        }
+    }
+}
 
 pub fn set(last_area_id: widget::Id, gui: &mut Gui, project: &mut Project) -> widget::Id {
     let Gui {
         ref mut ui,
-        ref audio_monitor,
+        ref mut audio_monitor,
         ref ids,
         ref channels,
+        ref assets,
         ref mut state,
         ..
     } = *gui;
     let Project {
         state: project::State {
+            ref name,
             ref mut master,
             ..
         },
@@ -31,7 +108,41 @@ pub fn set(last_area_id: widget::Id, gui: &mut Gui, project: &mut Project) -> wi
     const LATENCY_H: Scalar = ITEM_HEIGHT;
     const DECIBEL_H: Scalar = ITEM_HEIGHT;
     const PROXIMITY_H: Scalar = ITEM_HEIGHT;
-    const MASTER_H: Scalar = PAD + MASTER_VOLUME_H + PAD + LATENCY_H + PAD + DECIBEL_H + PAD + PROXIMITY_H + PAD;
+    const REVERB_H: Scalar = ITEM_HEIGHT;
+    const LIMITER_H: Scalar = ITEM_HEIGHT;
+    const TICK_RATE_H: Scalar = ITEM_HEIGHT;
+    const TEMPO_H: Scalar = ITEM_HEIGHT;
+    const BASS_H: Scalar = ITEM_HEIGHT;
+    const AMBISONICS_H: Scalar = ITEM_HEIGHT;
+    const BINAURAL_H: Scalar = ITEM_HEIGHT;
+    const DISTANCE_H: Scalar = ITEM_HEIGHT;
+    const HEADROOM_H: Scalar = ITEM_HEIGHT;
+    const BLACKOUT_H: Scalar = ITEM_HEIGHT;
+    const BLACKOUT_SCHEDULE_H: Scalar = ITEM_HEIGHT;
+    const TIME_ZONE_H: Scalar = ITEM_HEIGHT;
+    const OUTPUT_RECORD_H: Scalar = ITEM_HEIGHT;
+    const REFERENCE_H: Scalar = ITEM_HEIGHT;
+    // The width of the limiter gain reduction meter beside the master peak meter.
+    const GAIN_REDUCTION_W: Scalar = 80.0;
+    // The gain reduction in decibels at which the gain reduction meter is full.
+    const MAX_GAIN_REDUCTION_DB: f32 = 12.0;
+    const METER_TEXT_H: Scalar = SMALL_FONT_SIZE as Scalar;
+    const METER_SCALE_H: Scalar = ITEM_HEIGHT;
+    const METER_TIMES_H: Scalar = ITEM_HEIGHT;
+    const DSP_LOAD_H: Scalar = ITEM_HEIGHT;
+    const LOUDNESS_H: Scalar = ITEM_HEIGHT;
+    // The width of the button for resetting the DSP load peak and under-run count.
+    const DSP_LOAD_RESET_W: Scalar = 80.0;
+    const SOUND_WIDGETS_H: Scalar = ITEM_HEIGHT;
+    const MASTER_H: Scalar = PAD + MASTER_VOLUME_H + PAD + METER_TEXT_H + PAD + METER_SCALE_H + PAD
+        + METER_TIMES_H + PAD + DSP_LOAD_H + PAD + LOUDNESS_H + PAD + SOUND_WIDGETS_H + PAD
+        + LATENCY_H + PAD
+        + DECIBEL_H + PAD
+        + PROXIMITY_H + PAD
+        + (REVERB_H + PAD) * 3.0 + LIMITER_H + PAD + TICK_RATE_H + PAD + (TEMPO_H + PAD) * 2.0
+        + BASS_H + PAD + AMBISONICS_H + PAD + BINAURAL_H + PAD + DISTANCE_H + PAD + HEADROOM_H + PAD
+        + BLACKOUT_H + PAD + BLACKOUT_SCHEDULE_H + PAD + TIME_ZONE_H + PAD + OUTPUT_RECORD_H + PAD
+        + REFERENCE_H + PAD;
 
     // The collapsible area widget.
     let is_open = state.is_open.master;
@@ -54,7 +165,8 @@ pub fn set(last_area_id: widget::Id, gui: &mut Gui, project: &mut Project) -> wi
     area.set(canvas, ui);
 
     // Display the peak volume as a gradient underlay below the slider.
-    let peak = audio_monitor.master_peak;
+    let settings = audio_monitor.master_meter_settings;
+    let peak = settings.scale.normalise(audio_monitor.master_meter.peak);
     let left_hsla = ui::color::DARK_GREEN.to_hsl();
     let right_hsla = ui::color::DARK_RED.to_hsl();
     let hue_diff = right_hsla.0 - left_hsla.0;
@@ -66,7 +178,8 @@ pub fn set(last_area_id: widget::Id, gui: &mut Gui, project: &mut Project) -> wi
     let left_rgba = left_color.into();
     let peak_rgba = peak_color.into();
     let canvas_kid_rect = ui.rect_of(area.id).unwrap().pad(PAD);
-    let w = (canvas_kid_rect.w() * peak as f64).min(canvas_kid_rect.w());
+    let meter_w = canvas_kid_rect.w() - GAIN_REDUCTION_W - PAD;
+    let w = (meter_w * peak as f64).min(meter_w);
     let rect = ui::Rect::from_xy_dim([0.0, 0.0], [w, MASTER_VOLUME_H])
         .align_top_of(canvas_kid_rect)
         .align_left_of(canvas_kid_rect);
@@ -81,15 +194,28 @@ pub fn set(last_area_id: widget::Id, gui: &mut Gui, project: &mut Project) -> wi
         .with_bounding_rect(rect)
         .set(ids.master_peak_meter, ui);
 
+    // Mark the held peak with a thin line over the meter.
+    if settings.peak_hold.ms() > 0.0 {
+        let held = settings.scale.normalise(audio_monitor.master_meter.held_peak) as Scalar;
+        let x = canvas_kid_rect.left() + (meter_w * held).max(1.0) - 1.0;
+        let y = canvas_kid_rect.top() - MASTER_VOLUME_H / 2.0;
+        widget::Rectangle::fill([2.0, MASTER_VOLUME_H])
+            .xy([x, y])
+            .color(ui::color::LIGHT_RED)
+            .parent(area.id)
+            .graphics_for(area.id)
+            .set(ids.master_peak_hold, ui);
+    }
+
     // The master volume slider.
     let label = format!("Exhibition Volume: {:.2}", master.volume);
     for new_volume in widget::Slider::new(master.volume, 0.0, 1.0)
         .label(&label)
         .label_font_size(SMALL_FONT_SIZE)
-        .kid_area_w_of(area.id)
+        .w(meter_w)
         .h_of(ids.master_peak_meter)
         .align_middle_y_of(ids.master_peak_meter)
-        .align_middle_x_of(area.id)
+        .align_left_of(ids.master_peak_meter)
         .parent(ids.master_peak_meter)
         .border_color(ui::color::TRANSPARENT)
         .color(ui::color::LIGHT_CHARCOAL.alpha(0.6))
@@ -107,6 +233,270 @@ pub fn set(last_area_id: widget::Id, gui: &mut Gui, project: &mut Project) -> wi
             .expect("failed to send updated master volume to audio output thread");
     }
 
+    // The gain reduction currently applied by the limiter, filling from the right.
+    let gain_reduction_db = audio_monitor.master_gain_reduction_db;
+    let gain_reduction_rect = ui::Rect::from_xy_dim([0.0, 0.0], [GAIN_REDUCTION_W, MASTER_VOLUME_H])
+        .align_top_of(canvas_kid_rect)
+        .align_right_of(canvas_kid_rect);
+    widget::Rectangle::fill(gain_reduction_rect.dim())
+        .xy(gain_reduction_rect.xy())
+        .color(ui::color::DARK_CHARCOAL)
+        .parent(area.id)
+        .set(ids.master_gain_reduction_bg, ui);
+    let amount = (gain_reduction_db / MAX_GAIN_REDUCTION_DB).min(1.0) as Scalar;
+    if amount > 0.0 {
+        let w = GAIN_REDUCTION_W * amount;
+        widget::Rectangle::fill([w, MASTER_VOLUME_H])
+            .top_right_of(ids.master_gain_reduction_bg)
+            .color(ui::color::DARK_ORANGE)
+            .graphics_for(ids.master_gain_reduction_bg)
+            .set(ids.master_gain_reduction_meter, ui);
+    }
+    let label = match master.limiter.enabled {
+        true => format!("GR {:.1} dB", gain_reduction_db),
+        false => "GR Off".to_string(),
+    };
+    widget::Text::new(&label)
+        .font_size(SMALL_FONT_SIZE)
+        .middle_of(ids.master_gain_reduction_bg)
+        .graphics_for(ids.master_gain_reduction_bg)
+        .set(ids.master_gain_reduction_text, ui);
+
+    // The peak, held peak and RMS readout.
+    let scale = settings.scale;
+    let readout = {
+        let master_meter = &audio_monitor.master_meter;
+        format!(
+            "Peak: {}   Hold: {}   RMS: {}",
+            scale.label(master_meter.peak),
+            scale.label(master_meter.held_peak),
+            scale.label(master_meter.rms),
+        )
+    };
+    widget::Text::new(&readout)
+        .font_size(SMALL_FONT_SIZE)
+        .down_from(ids.master_volume, PAD)
+        .align_left_of(ids.master_volume)
+        .set(ids.master_meter_text, ui);
+
+    // The meter scale and peak hold time.
+    let half_w = ui.kid_area_of(area.id).unwrap().w() / 2.0 - PAD / 2.0;
+    let scale_names: Vec<_> = meter::Scale::ALL.iter().map(|s| s.name()).collect();
+    let selected = meter::Scale::ALL.iter().position(|&s| s == scale);
+    for idx in widget::DropDownList::new(&scale_names, selected)
+        .label("Meter Scale")
+        .label_font_size(SMALL_FONT_SIZE)
+        .down(PAD)
+        .align_left_of(ids.master_volume)
+        .w(half_w)
+        .h(METER_SCALE_H)
+        .set(ids.master_meter_scale, ui)
+    {
+        audio_monitor.master_meter_settings.scale = meter::Scale::ALL[idx];
+    }
+
+    let label = format!("Peak Hold: {:.0} ms", settings.peak_hold.ms());
+    for new_ms in widget::Slider::new(settings.peak_hold.ms(), 0.0, 5_000.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .right(PAD)
+        .align_top()
+        .w(half_w)
+        .h(METER_SCALE_H)
+        .set(ids.master_meter_peak_hold, ui)
+    {
+        audio_monitor.master_meter_settings.peak_hold = Ms(new_ms);
+    }
+
+    // The integration times of the meter.
+    let third_w = ui.kid_area_of(area.id).unwrap().w() / 3.0 - PAD * 2.0 / 3.0;
+    let label = format!("Attack: {:.0} ms", settings.attack.ms());
+    for new_ms in widget::Slider::new(settings.attack.ms(), 0.0, 1_000.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .skew(3.0)
+        .down_from(ids.master_meter_scale, PAD)
+        .align_left_of(ids.master_volume)
+        .w(third_w)
+        .h(METER_TIMES_H)
+        .set(ids.master_meter_attack, ui)
+    {
+        audio_monitor.master_meter_settings.attack = Ms(new_ms);
+    }
+
+    let label = format!("Release: {:.0} ms", settings.release.ms());
+    for new_ms in widget::Slider::new(settings.release.ms(), 0.0, 3_000.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .skew(3.0)
+        .right(PAD)
+        .align_top()
+        .w(third_w)
+        .h(METER_TIMES_H)
+        .set(ids.master_meter_release, ui)
+    {
+        audio_monitor.master_meter_settings.release = Ms(new_ms);
+    }
+
+    let label = format!("RMS: {:.0} ms", settings.rms_integration.ms());
+    for new_ms in widget::Slider::new(settings.rms_integration.ms(), 0.0, 3_000.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .skew(3.0)
+        .right(PAD)
+        .align_top()
+        .w(third_w)
+        .h(METER_TIMES_H)
+        .set(ids.master_meter_rms_integration, ui)
+    {
+        audio_monitor.master_meter_settings.rms_integration = Ms(new_ms);
+    }
+
+    // The load on the audio output thread, warning of under-runs before they become frequent.
+    let dsp_load_w = canvas_kid_rect.w() - DSP_LOAD_RESET_W - PAD;
+    widget::Rectangle::fill([dsp_load_w, DSP_LOAD_H])
+        .down_from(ids.master_meter_attack, PAD)
+        .align_left_of(ids.master_volume)
+        .color(ui::color::DARK_CHARCOAL)
+        .parent(area.id)
+        .set(ids.master_dsp_load_bg, ui);
+    let load = audio_monitor.dsp_load.load;
+    let amount = load.min(1.0) as Scalar;
+    if amount > 0.0 {
+        let color = if load < 0.5 {
+            ui::color::DARK_GREEN
+        } else if load < 0.8 {
+            ui::color::DARK_ORANGE
+        } else {
+            ui::color::DARK_RED
+        };
+        widget::Rectangle::fill([dsp_load_w * amount, DSP_LOAD_H])
+            .top_left_of(ids.master_dsp_load_bg)
+            .color(color)
+            .graphics_for(ids.master_dsp_load_bg)
+            .set(ids.master_dsp_load_meter, ui);
+    }
+    let label = format!(
+        "DSP Load: {:.0}%   Peak: {:.0}%   Xruns: {}",
+        load * 100.0,
+        audio_monitor.dsp_load.peak * 100.0,
+        audio_monitor.dsp_load.xruns,
+    );
+    widget::Text::new(&label)
+        .font_size(SMALL_FONT_SIZE)
+        .middle_of(ids.master_dsp_load_bg)
+        .graphics_for(ids.master_dsp_load_bg)
+        .set(ids.master_dsp_load_text, ui);
+    for _click in widget::Button::new()
+        .label("RESET")
+        .label_font_size(SMALL_FONT_SIZE)
+        .right_from(ids.master_dsp_load_bg, PAD)
+        .align_middle_y_of(ids.master_dsp_load_bg)
+        .w_h(DSP_LOAD_RESET_W, DSP_LOAD_H)
+        .set(ids.master_dsp_load_reset, ui)
+    {
+        audio_monitor.dsp_load.peak = 0.0;
+        audio_monitor.dsp_load.xruns = 0;
+    }
+
+    // The loudness and true peak of the master bus, optionally logged to the project directory.
+    let loudness_w = canvas_kid_rect.w() - (DSP_LOAD_RESET_W + PAD) * 2.0;
+    widget::Rectangle::fill([loudness_w, LOUDNESS_H])
+        .down_from(ids.master_dsp_load_bg, PAD)
+        .align_left_of(ids.master_volume)
+        .color(ui::color::DARK_CHARCOAL)
+        .parent(area.id)
+        .set(ids.master_loudness_bg, ui);
+    let reading = |lufs: Option<f32>| match lufs {
+        Some(lufs) => format!("{:.1}", lufs),
+        None => "-".to_string(),
+    };
+    let label = format!(
+        "M {}  S {}  I {} LUFS   True Peak: {:.1} dBTP",
+        reading(audio_monitor.loudness.momentary()),
+        reading(audio_monitor.loudness.short_term()),
+        reading(audio_monitor.loudness.integrated()),
+        loudness::amp_to_db(audio_monitor.loudness.max_true_peak),
+    );
+    widget::Text::new(&label)
+        .font_size(SMALL_FONT_SIZE)
+        .middle_of(ids.master_loudness_bg)
+        .graphics_for(ids.master_loudness_bg)
+        .set(ids.master_loudness_text, ui);
+    for _click in widget::Button::new()
+        .label("RESET")
+        .label_font_size(SMALL_FONT_SIZE)
+        .right_from(ids.master_loudness_bg, PAD)
+        .align_middle_y_of(ids.master_loudness_bg)
+        .w_h(DSP_LOAD_RESET_W, LOUDNESS_H)
+        .set(ids.master_loudness_reset, ui)
+    {
+        audio_monitor.loudness.reset();
+    }
+    let is_logging = audio_monitor.loudness.log_path().is_some();
+    for _click in widget::Toggle::new(is_logging)
+        .label(if is_logging { "LOGGING" } else { "LOG" })
+        .label_font_size(SMALL_FONT_SIZE)
+        .right(PAD)
+        .align_middle_y_of(ids.master_loudness_bg)
+        .w_h(DSP_LOAD_RESET_W, LOUDNESS_H)
+        .set(ids.master_loudness_log, ui)
+    {
+        if is_logging {
+            audio_monitor.loudness.stop_log();
+            continue;
+        }
+        let project_directory = project::project_directory_path(assets, name);
+        let directory = project::loudness_directory_path(&project_directory);
+        if let Err(err) = audio_monitor.loudness.start_log(&directory) {
+            eprintln!("failed to begin loudness log: {}", err);
+        }
+    }
+
+    // The appearance of the active sounds over the floorplan.
+    let sound_widgets = audio_monitor.sound_widget_settings;
+    let label_names: Vec<_> = custom_widget::sound::Label::ALL.iter().map(|l| l.name()).collect();
+    let selected = custom_widget::sound::Label::ALL
+        .iter()
+        .position(|&l| l == sound_widgets.label);
+    for idx in widget::DropDownList::new(&label_names, selected)
+        .label("Sound Label")
+        .label_font_size(SMALL_FONT_SIZE)
+        .down_from(ids.master_loudness_bg, PAD)
+        .align_left_of(ids.master_volume)
+        .w(third_w)
+        .h(SOUND_WIDGETS_H)
+        .set(ids.master_sound_widgets_label, ui)
+    {
+        audio_monitor.sound_widget_settings.label = custom_widget::sound::Label::ALL[idx];
+    }
+
+    let label = format!("Sound Size: {:.2}x", sound_widgets.scale);
+    for new_scale in widget::Slider::new(sound_widgets.scale, 0.5, 3.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .right(PAD)
+        .align_top()
+        .w(third_w)
+        .h(SOUND_WIDGETS_H)
+        .set(ids.master_sound_widgets_scale, ui)
+    {
+        audio_monitor.sound_widget_settings.scale = new_scale;
+    }
+
+    for hover_card in widget::Toggle::new(sound_widgets.hover_card)
+        .label("Hover Card")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::DARK_ORANGE)
+        .right(PAD)
+        .align_top()
+        .w(third_w)
+        .h(SOUND_WIDGETS_H)
+        .set(ids.master_sound_widgets_hover_card, ui)
+    {
+        audio_monitor.sound_widget_settings.hover_card = hover_card;
+    }
+
     // The realtime source latency slider.
     let label = format!("Realtime Source Latency: {:.2} ms", master.realtime_source_latency.ms());
     let max_latency_ms = 2_000.0;
@@ -117,7 +507,7 @@ pub fn set(last_area_id: widget::Id, gui: &mut Gui, project: &mut Project) -> wi
         .h(LATENCY_H)
         .kid_area_w_of(area.id)
         .align_middle_x_of(area.id)
-        .down(PAD)
+        .down_from(ids.master_sound_widgets_label, PAD)
         .set(ids.master_realtime_source_latency, ui)
     {
         // Update the local copy.
@@ -181,6 +571,923 @@ pub fn set(last_area_id: widget::Id, gui: &mut Gui, project: &mut Project) -> wi
             .expect("failed to send updated proximity limit to audio output thread");
         }
 
+    // Update the local global reverb params and send them to the audio output thread.
+    fn set_reverb<F>(reverb: &mut reverb::Params, channels: &Channels, update: F)
+    where
+        F: FnOnce(&mut reverb::Params),
+    {
+        update(reverb);
+        let params = *reverb;
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.reverb.set_params(params);
+            })
+            .expect("failed to send updated reverb to audio output thread");
+    }
+
+    // Update the local limiter params and send them to the audio output thread.
+    fn set_limiter<F>(limiter: &mut limiter::Params, channels: &Channels, update: F)
+    where
+        F: FnOnce(&mut limiter::Params),
+    {
+        update(limiter);
+        let params = *limiter;
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.limiter.set_params(params);
+            })
+            .expect("failed to send updated limiter to audio output thread");
+    }
+
+    // The global reverb return level.
+    let label = format!("Reverb Level: {:.2}", master.reverb.level);
+    for new_level in widget::Slider::new(master.reverb.level, 0.0, 1.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .h(REVERB_H)
+        .kid_area_w_of(area.id)
+        .align_middle_x_of(area.id)
+        .down(PAD)
+        .set(ids.master_reverb_level, ui)
+    {
+        set_reverb(&mut master.reverb, channels, |params| params.level = new_level);
+    }
+
+    // The global reverb decay time.
+    let label = format!("Reverb Decay: {:.2} secs", master.reverb.decay_secs);
+    let decay = master.reverb.decay_secs;
+    for new_decay in widget::Slider::new(decay, 0.1, reverb::MAX_DECAY_SECS)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .skew(0.5)
+        .h(REVERB_H)
+        .kid_area_w_of(area.id)
+        .align_middle_x_of(area.id)
+        .down(PAD)
+        .set(ids.master_reverb_decay, ui)
+    {
+        set_reverb(&mut master.reverb, channels, |params| params.decay_secs = new_decay);
+    }
+
+    // The global reverb high frequency damping.
+    let label = format!("Reverb Damping: {:.2}", master.reverb.damping);
+    for new_damping in widget::Slider::new(master.reverb.damping, 0.0, 1.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .h(REVERB_H)
+        .kid_area_w_of(area.id)
+        .align_middle_x_of(area.id)
+        .down(PAD)
+        .set(ids.master_reverb_damping, ui)
+    {
+        set_reverb(&mut master.reverb, channels, |params| params.damping = new_damping);
+    }
+
+    // The master limiter toggle, threshold and release.
+    let toggle_w = ITEM_HEIGHT * 3.0;
+    let slider_w = (ui.kid_area_of(area.id).unwrap().w() - toggle_w) / 2.0 - PAD;
+    for enabled in widget::Toggle::new(master.limiter.enabled)
+        .label("Limiter")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::DARK_ORANGE)
+        .w(toggle_w)
+        .h(LIMITER_H)
+        .align_left_of(ids.master_reverb_damping)
+        .down_from(ids.master_reverb_damping, PAD)
+        .set(ids.master_limiter_enabled, ui)
+    {
+        set_limiter(&mut master.limiter, channels, |params| params.enabled = enabled);
+    }
+
+    let label = format!("Threshold: {:.1} dB", master.limiter.threshold_db);
+    let threshold_db = master.limiter.threshold_db;
+    for new_threshold in widget::Slider::new(threshold_db, limiter::MIN_THRESHOLD_DB, 0.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(slider_w)
+        .h(LIMITER_H)
+        .right(PAD)
+        .set(ids.master_limiter_threshold, ui)
+    {
+        let new_threshold = (new_threshold * 10.0).round() / 10.0;
+        set_limiter(&mut master.limiter, channels, |params| params.threshold_db = new_threshold);
+    }
+
+    let label = format!("Release: {:.0} ms", master.limiter.release.ms());
+    for new_ms in widget::Slider::new(master.limiter.release.ms(), 10.0, 2_000.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .skew(0.5)
+        .w(slider_w)
+        .h(LIMITER_H)
+        .right(PAD)
+        .set(ids.master_limiter_release, ui)
+    {
+        set_limiter(&mut master.limiter, channels, |params| params.release = Ms(new_ms));
+    }
+
+    // The soundscape tick rate along with the measured jitter of the ticker.
+    let half_w = ui.kid_area_of(area.id).unwrap().w() / 2.0 - PAD / 2.0;
+    let label = format!("Soundscape Tick: {:.0} ms", master.soundscape_tick_rate.ms());
+    let (min_ms, max_ms) = (soundscape::MIN_TICK_RATE.ms(), soundscape::MAX_TICK_RATE.ms());
+    for new_ms in widget::Slider::new(master.soundscape_tick_rate.ms(), min_ms, max_ms)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .skew(0.5)
+        .w(half_w)
+        .h(TICK_RATE_H)
+        .align_left_of(ids.master_limiter_enabled)
+        .down_from(ids.master_limiter_enabled, PAD)
+        .set(ids.master_soundscape_tick_rate, ui)
+    {
+        master.soundscape_tick_rate = Ms(new_ms.round());
+        channels.soundscape.set_tick_rate(master.soundscape_tick_rate);
+    }
+
+    let jitter = format!("Tick Jitter: {:.2} ms", channels.soundscape.tick_jitter().ms());
+    widget::Text::new(&jitter)
+        .font_size(SMALL_FONT_SIZE)
+        .right(PAD * 2.0)
+        .align_middle_y_of(ids.master_soundscape_tick_rate)
+        .set(ids.master_soundscape_tick_jitter, ui);
+
+    // Update the local soundscape tempo and send it to the soundscape thread.
+    fn set_tempo<F>(tempo: &mut soundscape::tempo::Params, channels: &Channels, update: F)
+    where
+        F: FnOnce(&mut soundscape::tempo::Params),
+    {
+        update(tempo);
+        let params = *tempo;
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.set_tempo(params);
+            })
+            .expect("failed to send updated tempo to soundscape thread");
+    }
+
+    // Whether spawns are quantised to the tempo, the tempo and whether to wait for beats or bars.
+    use soundscape::tempo::{self, Quantum};
+    let slider_w = (ui.kid_area_of(area.id).unwrap().w() - toggle_w) / 2.0 - PAD;
+    for enabled in widget::Toggle::new(master.soundscape_tempo.enabled)
+        .label("Tempo")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::DARK_ORANGE)
+        .w(toggle_w)
+        .h(TEMPO_H)
+        .align_left_of(ids.master_soundscape_tick_rate)
+        .down_from(ids.master_soundscape_tick_rate, PAD)
+        .set(ids.master_tempo_enabled, ui)
+    {
+        set_tempo(&mut master.soundscape_tempo, channels, |params| params.enabled = enabled);
+    }
+
+    let label = format!("{:.1} BPM", master.soundscape_tempo.bpm);
+    let bpm = master.soundscape_tempo.bpm;
+    for new_bpm in widget::Slider::new(bpm, tempo::MIN_BPM, tempo::MAX_BPM)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(slider_w)
+        .h(TEMPO_H)
+        .right(PAD)
+        .set(ids.master_tempo_bpm, ui)
+    {
+        let new_bpm = (new_bpm * 10.0).round() / 10.0;
+        set_tempo(&mut master.soundscape_tempo, channels, |params| params.bpm = new_bpm);
+    }
+
+    let label = match master.soundscape_tempo.quantum {
+        Quantum::Beat => "Spawn On Each Beat",
+        Quantum::Bar => "Spawn On Each Bar",
+    };
+    for _click in widget::Button::new()
+        .label(label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(slider_w)
+        .h(TEMPO_H)
+        .right(PAD)
+        .set(ids.master_tempo_quantum, ui)
+    {
+        set_tempo(&mut master.soundscape_tempo, channels, |params| {
+            params.quantum = match params.quantum {
+                Quantum::Beat => Quantum::Bar,
+                Quantum::Bar => Quantum::Beat,
+            };
+        });
+    }
+
+    // Whether the tempo is shared via Ableton Link, the beats per bar and the session's state.
+    let label = match tempo::LINK_SUPPORTED {
+        true => "Link",
+        false => "No Link",
+    };
+    for link in widget::Toggle::new(master.soundscape_tempo.link)
+        .label(label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::DARK_ORANGE)
+        .enabled(tempo::LINK_SUPPORTED)
+        .w(toggle_w)
+        .h(TEMPO_H)
+        .align_left_of(ids.master_tempo_enabled)
+        .down_from(ids.master_tempo_enabled, PAD)
+        .set(ids.master_tempo_link, ui)
+    {
+        set_tempo(&mut master.soundscape_tempo, channels, |params| params.link = link);
+    }
+
+    let beats_per_bar = master.soundscape_tempo.beats_per_bar;
+    let label = format!("{} Beats Per Bar", beats_per_bar);
+    let max_beats = tempo::MAX_BEATS_PER_BAR as f64;
+    for new_beats in widget::Slider::new(beats_per_bar as f64, 1.0, max_beats)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(slider_w)
+        .h(TEMPO_H)
+        .right(PAD)
+        .set(ids.master_tempo_beats_per_bar, ui)
+    {
+        let new_beats = new_beats.round() as u32;
+        if new_beats != beats_per_bar {
+            set_tempo(&mut master.soundscape_tempo, channels, |params| {
+                params.beats_per_bar = new_beats;
+            });
+        }
+    }
+
+    let status = match channels.soundscape.link_status() {
+        Some(status) => format!("{} Peers at {:.1} BPM", status.peers, status.bpm),
+        None => "Not Linked".to_string(),
+    };
+    widget::Text::new(&status)
+        .font_size(SMALL_FONT_SIZE)
+        .right(PAD * 2.0)
+        .align_middle_y_of(ids.master_tempo_beats_per_bar)
+        .set(ids.master_tempo_link_status, ui);
+
+    // Update the local bass management params and send them to the audio output thread.
+    fn set_bass<F>(bass: &mut bass::Params, channels: &Channels, update: F)
+    where
+        F: FnOnce(&mut bass::Params),
+    {
+        update(bass);
+        let params = *bass;
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.bass = params;
+            })
+            .expect("failed to send updated bass management to audio output thread");
+    }
+
+    // The subwoofer crossover frequency and whether or not the subwoofers are panned via DBAP.
+    let slider_w = ui.kid_area_of(area.id).unwrap().w() - toggle_w - PAD;
+    let label = format!("Sub Crossover: {:.0} Hz", master.bass.crossover_hz);
+    let (min_hz, max_hz) = (bass::MIN_CROSSOVER_HZ, bass::MAX_CROSSOVER_HZ);
+    for new_hz in widget::Slider::new(master.bass.crossover_hz, min_hz, max_hz)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .skew(0.5)
+        .w(slider_w)
+        .h(BASS_H)
+        .align_left_of(ids.master_tempo_link)
+        .down_from(ids.master_tempo_link, PAD)
+        .set(ids.master_bass_crossover, ui)
+    {
+        set_bass(&mut master.bass, channels, |params| params.crossover_hz = new_hz.round());
+    }
+
+    for sub_dbap in widget::Toggle::new(master.bass.sub_dbap)
+        .label("Sub DBAP")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::DARK_ORANGE)
+        .w(toggle_w)
+        .h(BASS_H)
+        .right(PAD)
+        .set(ids.master_bass_sub_dbap, ui)
+    {
+        set_bass(&mut master.bass, channels, |params| params.sub_dbap = sub_dbap);
+    }
+
+    // Update the local ambisonics params and send them to the audio output thread.
+    fn set_ambisonics<F>(ambisonics: &mut ambisonics::Params, channels: &Channels, update: F)
+    where
+        F: FnOnce(&mut ambisonics::Params),
+    {
+        update(ambisonics);
+        let params = *ambisonics;
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.ambisonics.set_params(params);
+            })
+            .expect("failed to send updated ambisonics params to audio output thread");
+    }
+
+    // Stop the recording of the ambisonics bus in progress, if any.
+    fn stop_recording(recording: &mut Option<audio::capture::WavWriter>, channels: &Channels) {
+        if let Some(writer) = recording.take() {
+            channels
+                .audio_output
+                .send(move |audio| {
+                    audio.ambisonics.stop_recording();
+                })
+                .expect("failed to send stop recording message to audio output thread");
+            if let Err(err) = writer.finish() {
+                eprintln!("failed to finish B-format recording: {}", err);
+            }
+        }
+    }
+
+    // The output mode, the ambisonics order and the B-format recording.
+    let third_w = ui.kid_area_of(area.id).unwrap().w() / 3.0 - PAD * 2.0 / 3.0;
+    let mode_names: Vec<_> = ambisonics::Mode::ALL.iter().map(|m| m.name()).collect();
+    let selected = ambisonics::Mode::ALL.iter().position(|&m| m == master.ambisonics.mode);
+    for idx in widget::DropDownList::new(&mode_names, selected)
+        .label("Output Mode")
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(third_w)
+        .h(AMBISONICS_H)
+        .align_left_of(ids.master_bass_crossover)
+        .down_from(ids.master_bass_crossover, PAD)
+        .set(ids.master_ambisonics_mode, ui)
+    {
+        let mode = ambisonics::Mode::ALL[idx];
+        if mode != ambisonics::Mode::Ambisonics {
+            stop_recording(&mut state.ambisonics_recording, channels);
+        }
+        set_ambisonics(&mut master.ambisonics, channels, |params| params.mode = mode);
+    }
+
+    let label = format!("Ambisonics Order: {}", master.ambisonics.order);
+    let order = master.ambisonics.order as f32;
+    let (min_order, max_order) = (ambisonics::MIN_ORDER as f32, ambisonics::MAX_ORDER as f32);
+    for new_order in widget::Slider::new(order, min_order, max_order)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(third_w)
+        .h(AMBISONICS_H)
+        .right(PAD)
+        .align_top()
+        .set(ids.master_ambisonics_order, ui)
+    {
+        let new_order = new_order.round() as u8;
+        if new_order != master.ambisonics.order {
+            set_ambisonics(&mut master.ambisonics, channels, |params| params.order = new_order);
+        }
+    }
+
+    // Recording is only available while sounds are encoded onto the ambisonics bus.
+    let is_active = master.ambisonics.mode == ambisonics::Mode::Ambisonics;
+    let label = match state.ambisonics_recording {
+        Some(ref writer) => {
+            let file_name = writer.path().file_name().and_then(|name| name.to_str());
+            format!("STOP {}", file_name.unwrap_or("RECORDING"))
+        },
+        None => "RECORD B-FORMAT".to_string(),
+    };
+    let record_color = match state.ambisonics_recording {
+        Some(_) => ui::color::DARK_RED,
+        None => ui::color::DARK_CHARCOAL,
+    };
+    for _click in widget::Button::new()
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(record_color)
+        .enabled(is_active)
+        .w(third_w)
+        .h(AMBISONICS_H)
+        .right(PAD)
+        .align_top()
+        .set(ids.master_ambisonics_record, ui)
+    {
+        if state.ambisonics_recording.is_some() {
+            stop_recording(&mut state.ambisonics_recording, channels);
+            continue;
+        }
+
+        // Write all components of the current order to the project's recordings directory.
+        let components = ambisonics::num_components(master.ambisonics.order as usize);
+        let project_directory = project::project_directory_path(assets, name);
+        let directory = project::recordings_directory_path(&project_directory);
+        let stem = format!("bformat-order-{}", master.ambisonics.order);
+        let path = audio::capture::next_wav_path(&directory, &stem);
+        match audio::capture::spawn_wav_writer(path, components, "bformat_recording") {
+            Err(err) => eprintln!("failed to begin B-format recording: {}", err),
+            Ok((writer, buffers)) => {
+                channels
+                    .audio_output
+                    .send(move |audio| {
+                        audio.ambisonics.start_recording(buffers, components);
+                    })
+                    .expect("failed to send B-format recording to audio output thread");
+                state.ambisonics_recording = Some(writer);
+            },
+        }
+    }
+
+    // The binaural headphone bus, its output pair, the direction faced by the listener and gain.
+    //
+    // The listener is positioned by dragging it on the floorplan.
+    let slider_w = (ui.kid_area_of(area.id).unwrap().w() - toggle_w) / 3.0 - PAD;
+    for enabled in widget::Toggle::new(master.binaural.enabled)
+        .label("Binaural")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::DARK_ORANGE)
+        .w(toggle_w)
+        .h(BINAURAL_H)
+        .align_left_of(ids.master_ambisonics_mode)
+        .down_from(ids.master_ambisonics_mode, PAD)
+        .set(ids.master_binaural_enabled, ui)
+    {
+        set_binaural(&mut master.binaural, channels, |params| params.enabled = enabled);
+    }
+
+    let left = master.binaural.output_channel;
+    let label = format!("Phones: {}-{}", left + 1, left + 2);
+    let max_channel = state.audio_channels.output.max(2) - 2;
+    for new_channel in widget::Slider::new(left as f32, 0.0, max_channel as f32)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(slider_w)
+        .h(BINAURAL_H)
+        .right(PAD)
+        .set(ids.master_binaural_output, ui)
+    {
+        let new_channel = new_channel.round() as usize;
+        if new_channel != left {
+            set_binaural(&mut master.binaural, channels, |p| p.output_channel = new_channel);
+        }
+    }
+
+    let degrees = master.binaural.facing_radians.to_degrees();
+    let label = format!("Facing: {:.0}°", degrees);
+    for new_degrees in widget::Slider::new(degrees, -180.0, 180.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(slider_w)
+        .h(BINAURAL_H)
+        .right(PAD)
+        .set(ids.master_binaural_facing, ui)
+    {
+        let radians = new_degrees.round().to_radians();
+        set_binaural(&mut master.binaural, channels, |params| params.facing_radians = radians);
+    }
+
+    let label = format!("Phones Level: {:.2}", master.binaural.gain);
+    for new_gain in widget::Slider::new(master.binaural.gain, 0.0, 2.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(slider_w)
+        .h(BINAURAL_H)
+        .right(PAD)
+        .set(ids.master_binaural_gain, ui)
+    {
+        set_binaural(&mut master.binaural, channels, |params| params.gain = new_gain);
+    }
+
+    // The distance model applied to those sources that opt in via the source editor.
+    let reference = master.distance.reference_distance.0;
+    let label = format!("Distance Ref: {:.1} m", reference);
+    for new_reference in widget::Slider::new(reference, 0.0, 20.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(third_w)
+        .h(DISTANCE_H)
+        .align_left_of(ids.master_binaural_enabled)
+        .down_from(ids.master_binaural_enabled, PAD)
+        .set(ids.master_distance_reference, ui)
+    {
+        let new_reference = Metres((new_reference * 10.0).round() / 10.0);
+        set_distance(&mut master.distance, channels, |p| p.reference_distance = new_reference);
+    }
+
+    let label = format!("Distance Rolloff: {:.1} dB", master.distance.rolloff_db);
+    for new_rolloff in widget::Slider::new(master.distance.rolloff_db, 0.0, 12.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(third_w)
+        .h(DISTANCE_H)
+        .right(PAD)
+        .align_top()
+        .set(ids.master_distance_rolloff, ui)
+    {
+        let new_rolloff = (new_rolloff * 10.0).round() / 10.0;
+        set_distance(&mut master.distance, channels, |params| params.rolloff_db = new_rolloff);
+    }
+
+    let label = format!("Air Absorption: {:.2}", master.distance.air_absorption);
+    for new_absorption in widget::Slider::new(master.distance.air_absorption, 0.0, 0.5)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(third_w)
+        .h(DISTANCE_H)
+        .right(PAD)
+        .align_top()
+        .set(ids.master_distance_air_absorption, ui)
+    {
+        set_distance(&mut master.distance, channels, |p| p.air_absorption = new_absorption);
+    }
+
+    // The headroom auto-gain, reducing the gain of each sound as more play at once.
+    let half_w = (ui.kid_area_of(area.id).unwrap().w() - toggle_w) / 2.0 - PAD;
+    for enabled in widget::Toggle::new(master.headroom.enabled)
+        .label("Auto-Gain")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::DARK_ORANGE)
+        .w(toggle_w)
+        .h(HEADROOM_H)
+        .align_left_of(ids.master_distance_reference)
+        .down_from(ids.master_distance_reference, PAD)
+        .set(ids.master_headroom_enabled, ui)
+    {
+        set_headroom(&mut master.headroom, channels, |params| params.enabled = enabled);
+    }
+
+    let law_names: Vec<_> = headroom::Law::ALL.iter().map(|law| law.name()).collect();
+    let selected = headroom::Law::ALL.iter().position(|&law| law == master.headroom.law);
+    for idx in widget::DropDownList::new(&law_names, selected)
+        .label("Law")
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(half_w)
+        .h(HEADROOM_H)
+        .right(PAD)
+        .align_top()
+        .set(ids.master_headroom_law, ui)
+    {
+        let law = headroom::Law::ALL[idx];
+        set_headroom(&mut master.headroom, channels, |params| params.law = law);
+    }
+
+    let label = format!("Max Reduction: {:.1} dB", master.headroom.max_reduction_db);
+    let max_reduction_db = master.headroom.max_reduction_db;
+    for new_db in widget::Slider::new(max_reduction_db, 0.0, headroom::MAX_REDUCTION_DB)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(half_w)
+        .h(HEADROOM_H)
+        .right(PAD)
+        .align_top()
+        .set(ids.master_headroom_max_reduction, ui)
+    {
+        let new_db = (new_db * 2.0).round() / 2.0;
+        set_headroom(&mut master.headroom, channels, |params| params.max_reduction_db = new_db);
+    }
+
+    // The blackout, fading the whole exhibition to silence, holding and then restoring.
+    let quarter_w = ui.kid_area_of(area.id).unwrap().w() / 4.0 - PAD * 3.0 / 4.0;
+    let remaining = state.blackout.remaining_secs();
+    let (label, color) = match remaining {
+        Some(secs) => (format!("RESTORE ({:.0}s)", secs.ceil()), ui::color::DARK_RED),
+        None => ("BLACKOUT".to_string(), ui::color::DARK_CHARCOAL),
+    };
+    for _click in widget::Button::new()
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color)
+        .w(quarter_w)
+        .h(BLACKOUT_H)
+        .align_left_of(ids.master_headroom_enabled)
+        .down_from(ids.master_headroom_enabled, PAD)
+        .set(ids.master_blackout_button, ui)
+    {
+        match remaining {
+            Some(_) => {
+                let fade_in_secs = master.blackout.fade_in_secs;
+                restore_blackout(fade_in_secs, channels, &mut state.blackout);
+            },
+            None => start_blackout(&master.blackout, channels, &mut state.blackout),
+        }
+    }
+
+    let fade_slider = |secs: f64, max: f64| {
+        widget::Slider::new(secs, 0.0, max)
+            .skew(0.5)
+            .label_font_size(SMALL_FONT_SIZE)
+            .w(quarter_w)
+            .h(BLACKOUT_H)
+            .right(PAD)
+            .align_top()
+    };
+
+    let label = format!("Fade Out: {:.1} secs", master.blackout.fade_out_secs);
+    for secs in fade_slider(master.blackout.fade_out_secs, blackout::MAX_FADE_SECS)
+        .label(&label)
+        .set(ids.master_blackout_fade_out, ui)
+    {
+        master.blackout.fade_out_secs = (secs * 10.0).round() / 10.0;
+    }
+
+    let label = format!("Hold: {:.0} secs", master.blackout.hold_secs);
+    for secs in fade_slider(master.blackout.hold_secs, blackout::MAX_HOLD_SECS)
+        .label(&label)
+        .set(ids.master_blackout_hold, ui)
+    {
+        master.blackout.hold_secs = secs.round();
+    }
+
+    let label = format!("Fade In: {:.1} secs", master.blackout.fade_in_secs);
+    for secs in fade_slider(master.blackout.fade_in_secs, blackout::MAX_FADE_SECS)
+        .label(&label)
+        .set(ids.master_blackout_fade_in, ui)
+    {
+        master.blackout.fade_in_secs = (secs * 10.0).round() / 10.0;
+    }
+
+    // The daily times at which a blackout begins automatically, e.g. "09:00, 17:30".
+    widget::Text::new("Daily Blackouts")
+        .font_size(SMALL_FONT_SIZE)
+        .color(ui::color::WHITE)
+        .w(quarter_w)
+        .align_left_of(ids.master_blackout_button)
+        .down_from(ids.master_blackout_button, PAD * 2.0)
+        .set(ids.master_blackout_schedule_text, ui);
+
+    let schedule_string = blackout::schedule_string(&master.blackout.schedule);
+    let (text, color) = match state.blackout.schedule_text {
+        None => (schedule_string, ui::color::BLACK),
+        Some(ref text) => match blackout::parse_schedule(text) {
+            Ok(_) => (text.clone(), ui::color::DARK_GREEN),
+            Err(_) => (text.clone(), ui::color::DARK_RED),
+        },
+    };
+    let schedule_w = ui.kid_area_of(area.id).unwrap().w() - quarter_w - PAD;
+    for event in widget::TextBox::new(&text)
+        .font_size(SMALL_FONT_SIZE)
+        .color(color)
+        .w(schedule_w)
+        .h(BLACKOUT_SCHEDULE_H)
+        .align_right_of(ids.master_blackout_fade_in)
+        .down_from(ids.master_blackout_fade_in, PAD)
+        .set(ids.master_blackout_schedule, ui)
+    {
+        use nannou::ui::widget::text_box::Event;
+        match event {
+            Event::Update(s) => state.blackout.schedule_text = Some(s),
+            Event::Enter => match blackout::parse_schedule(&text) {
+                Ok(schedule) => {
+                    master.blackout.schedule = schedule;
+                    state.blackout.schedule_text = None;
+                },
+                Err(err) => eprintln!("failed to parse blackout schedule: {}", err),
+            },
+        }
+    }
+
+    // The time zone of the venue against which the blackout and day gain schedules are evaluated.
+    widget::Text::new("Venue Time Zone")
+        .font_size(SMALL_FONT_SIZE)
+        .color(ui::color::WHITE)
+        .w(quarter_w)
+        .align_left_of(ids.master_blackout_button)
+        .down_from(ids.master_blackout_schedule, PAD * 2.0)
+        .set(ids.master_time_zone_text, ui);
+
+    let (text, color) = match state.blackout.time_zone_text {
+        None => (master.time_zone.to_string(), ui::color::BLACK),
+        Some(ref text) => match text.parse::<TimeZone>() {
+            Ok(_) => (text.clone(), ui::color::DARK_GREEN),
+            Err(_) => (text.clone(), ui::color::DARK_RED),
+        },
+    };
+    for event in widget::TextBox::new(&text)
+        .font_size(SMALL_FONT_SIZE)
+        .color(color)
+        .w(schedule_w)
+        .h(TIME_ZONE_H)
+        .align_right_of(ids.master_blackout_schedule)
+        .down_from(ids.master_blackout_schedule, PAD)
+        .set(ids.master_time_zone, ui)
+    {
+        use nannou::ui::widget::text_box::Event;
+        match event {
+            Event::Update(s) => state.blackout.time_zone_text = Some(s),
+            Event::Enter => match text.parse::<TimeZone>() {
+                Ok(time_zone) => {
+                    master.time_zone = time_zone;
+                    state.blackout.time_zone_text = None;
+                    // Avoid triggering a scheduled blackout due to the jump in the time of day.
+                    state.blackout.checked_secs = None;
+                    channels
+                        .soundscape
+                        .send(move |soundscape| soundscape.time_zone = time_zone)
+                        .expect("failed to send time zone to soundscape thread");
+                },
+                Err(err) => eprintln!("failed to parse time zone: {}", err),
+            },
+        }
+    }
+
+    // Record the final output of all channels exactly as it is heard for archival.
+    let label = match state.output_recording {
+        Some(ref writer) => {
+            let file_name = writer.path().file_name().and_then(|name| name.to_str());
+            format!("STOP {}", file_name.unwrap_or("RECORDING"))
+        },
+        None => "RECORD OUTPUT".to_string(),
+    };
+    let record_color = match state.output_recording {
+        Some(_) => ui::color::DARK_RED,
+        None => ui::color::DARK_CHARCOAL,
+    };
+    for _click in widget::Button::new()
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(record_color)
+        .kid_area_w_of(area.id)
+        .h(OUTPUT_RECORD_H)
+        .align_left_of(ids.master_blackout_button)
+        .down_from(ids.master_time_zone, PAD)
+        .set(ids.master_output_record, ui)
+    {
+        if state.output_recording.is_some() {
+            stop_output_recording(&mut state.output_recording, channels);
+            continue;
+        }
+
+        // Write all output channels to the project's recordings directory.
+        let n_channels = state.audio_channels.output;
+        let project_directory = project::project_directory_path(assets, name);
+        let directory = project::recordings_directory_path(&project_directory);
+        let stem = format!("output-{}ch", n_channels);
+        let path = audio::capture::next_wav_path(&directory, &stem);
+        match audio::capture::spawn_wav_writer(path, n_channels, "output_recording") {
+            Err(err) => eprintln!("failed to begin output recording: {}", err),
+            Ok((writer, buffers)) => {
+                channels
+                    .audio_output
+                    .send(move |audio| {
+                        audio.recording = Some((buffers, n_channels));
+                    })
+                    .expect("failed to send output recording to audio output thread");
+                state.output_recording = Some(writer);
+            },
+        }
+    }
+
+    // A reference tone or pink noise through every speaker for verifying the whole output chain
+    // and calibrating SPL with a meter.
+    let half_w = ui.kid_area_of(area.id).unwrap().w() / 2.0 - PAD / 2.0;
+    let kinds = &test_signal::Kind::REFERENCE;
+    let kind_names: Vec<_> = Some("Reference Off")
+        .into_iter()
+        .chain(kinds.iter().map(|kind| kind.label()))
+        .collect();
+    let selected = state.reference.kind
+        .and_then(|kind| kinds.iter().position(|&k| k == kind))
+        .map(|idx| idx + 1)
+        .or(Some(0));
+    for idx in widget::DropDownList::new(&kind_names, selected)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(half_w)
+        .h(REFERENCE_H)
+        .align_left_of(ids.master_output_record)
+        .down_from(ids.master_output_record, PAD)
+        .set(ids.master_reference_kind, ui)
+    {
+        let kind = match idx {
+            0 => None,
+            idx => Some(kinds[idx - 1]),
+        };
+        set_reference(kind, state.reference.level_db, channels, &mut state.reference);
+    }
+
+    let level_db = state.reference.level_db;
+    let label = format!("Reference Level: {:.1} dBFS", level_db);
+    let min = test_signal::MIN_LEVEL_DB;
+    let max = test_signal::MAX_LEVEL_DB;
+    for new_level_db in widget::Slider::new(level_db, min, max)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(half_w)
+        .h(REFERENCE_H)
+        .right(PAD)
+        .set(ids.master_reference_level, ui)
+    {
+        let kind = state.reference.kind;
+        set_reference(kind, new_level_db, channels, &mut state.reference);
+    }
 
     area.id
 }
+
+/// Play the given kind of reference signal through every speaker, or stop it if `None`.
+pub fn set_reference(
+    kind: Option<test_signal::Kind>,
+    level_db: f32,
+    channels: &Channels,
+    state: &mut Reference,
+) {
+    state.kind = kind;
+    state.level_db = level_db;
+    channels
+        .audio_output
+        .send(move |audio| {
+            let kind = match kind {
+                None => {
+                    audio.reference = None;
+                    return;
+                },
+                Some(kind) => kind,
+            };
+            if let Some(ref mut reference) = audio.reference {
+                reference.set_kind(kind);
+                reference.set_level_db(level_db);
+                return;
+            }
+            audio.reference = Some(test_signal::Reference::new(kind, level_db));
+        })
+        .expect("failed to send reference signal to audio output thread");
+}
+
+/// Stop the recording of the final output in progress, if any, and finalise its WAV file.
+pub fn stop_output_recording(
+    recording: &mut Option<audio::capture::WavWriter>,
+    channels: &Channels,
+) {
+    if let Some(writer) = recording.take() {
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.recording = None;
+            })
+            .expect("failed to send stop recording message to audio output thread");
+        if let Err(err) = writer.finish() {
+            eprintln!("failed to finish output recording: {}", err);
+        }
+    }
+}
+
+/// Begin a blackout with the given params on the audio output thread.
+pub fn start_blackout(params: &blackout::Params, channels: &Channels, state: &mut Blackout) {
+    let params = params.clone();
+    let total = Duration::from_millis((params.total_secs() * 1_000.0) as u64);
+    state.ends = Some(Instant::now() + total);
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.blackout.start(&params);
+        })
+        .expect("failed to send blackout to audio output thread");
+}
+
+/// Restore from the blackout underway, if any, over the given duration.
+pub fn restore_blackout(fade_in_secs: f64, channels: &Channels, state: &mut Blackout) {
+    if state.remaining_secs().is_none() {
+        return;
+    }
+    let fade_in = Duration::from_millis((fade_in_secs * 1_000.0) as u64);
+    state.ends = Some(Instant::now() + fade_in);
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.blackout.restore(fade_in_secs);
+        })
+        .expect("failed to send blackout restore to audio output thread");
+}
+
+/// Update the local distance model params and send them to the audio output thread.
+fn set_distance<F>(distance: &mut distance::Params, channels: &Channels, update: F)
+where
+    F: FnOnce(&mut distance::Params),
+{
+    update(distance);
+    let params = *distance;
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.distance = params;
+        })
+        .expect("failed to send updated distance model to audio output thread");
+}
+
+/// Update the local headroom auto-gain params and send them to the audio output thread.
+fn set_headroom<F>(headroom: &mut headroom::Params, channels: &Channels, update: F)
+where
+    F: FnOnce(&mut headroom::Params),
+{
+    update(headroom);
+    let params = *headroom;
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.headroom = params;
+        })
+        .expect("failed to send updated headroom auto-gain to audio output thread");
+}
+
+/// Update the local binaural params and send them to the audio output thread.
+pub fn set_binaural<F>(binaural: &mut binaural::Params, channels: &Channels, update: F)
+where
+    F: FnOnce(&mut binaural::Params),
+{
+    update(binaural);
+    let params = *binaural;
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.binaural.set_params(params);
+        })
+        .expect("failed to send updated binaural params to audio output thread");
+}
@@ -0,0 +1,196 @@
+//! Ballistics and scales for the master level meter.
+//!
+//! The audio output thread sends the raw peak and RMS of every buffer. The `Meter` integrates
+//! these over the configured attack, release and RMS times so that the meter reads steadily, and
+//! holds the highest recent peak so that short transients remain visible.
+
+use audio;
+use std::time::{Duration, Instant};
+use time_calc::Ms;
+
+/// The lowest level displayed on the dBFS scale.
+pub const DBFS_FLOOR: f32 = -60.0;
+/// The level in dBFS that reads as 0 VU.
+pub const VU_REFERENCE_DBFS: f32 = -18.0;
+/// The lowest level displayed on the VU scale.
+pub const VU_MIN: f32 = -20.0;
+/// The highest level displayed on the VU scale.
+pub const VU_MAX: f32 = 3.0;
+
+/// The scale along which levels are displayed.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum Scale {
+    /// Amplitude mapped linearly from silence to full scale.
+    Linear,
+    /// Decibels relative to full scale, from `DBFS_FLOOR` to 0.
+    Dbfs,
+    /// Volume units relative to `VU_REFERENCE_DBFS`, from `VU_MIN` to `VU_MAX`.
+    Vu,
+}
+
+/// The number of variants within the `Scale` enum.
+pub const NUM_SCALES: usize = 3;
+
+/// User configuration of the master meter.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Settings {
+    /// The scale along which levels are displayed.
+    #[serde(default = "default::scale")]
+    pub scale: Scale,
+    /// The time taken for the meter to rise towards a louder peak.
+    #[serde(default = "default::attack")]
+    pub attack: Ms,
+    /// The time taken for the meter to fall towards a quieter peak.
+    #[serde(default = "default::release")]
+    pub release: Ms,
+    /// The time over which the RMS readout is integrated.
+    #[serde(default = "default::rms_integration")]
+    pub rms_integration: Ms,
+    /// The duration for which the highest recent peak is held. Zero disables peak hold.
+    #[serde(default = "default::peak_hold")]
+    pub peak_hold: Ms,
+}
+
+/// The integrated state of a level meter.
+#[derive(Debug, Default)]
+pub struct Meter {
+    /// The peak amplitude after ballistics are applied.
+    pub peak: f32,
+    /// The RMS amplitude integrated over the configured time.
+    pub rms: f32,
+    /// The highest recent peak amplitude.
+    pub held_peak: f32,
+    held_at: Option<Instant>,
+    mean_square: f32,
+}
+
+impl Scale {
+    /// All scales in the order in which they are displayed.
+    pub const ALL: [Scale; NUM_SCALES] = [Scale::Linear, Scale::Dbfs, Scale::Vu];
+
+    /// The name of the scale for display.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Scale::Linear => "Linear",
+            Scale::Dbfs => "dBFS",
+            Scale::Vu => "VU",
+        }
+    }
+
+    /// The position of the given amplitude along the meter in the range `0.0..=1.0`.
+    pub fn normalise(&self, amp: f32) -> f32 {
+        let normalised = match *self {
+            Scale::Linear => amp,
+            Scale::Dbfs => (amp_to_db(amp) - DBFS_FLOOR) / -DBFS_FLOOR,
+            Scale::Vu => (amp_to_vu(amp) - VU_MIN) / (VU_MAX - VU_MIN),
+        };
+        normalised.max(0.0).min(1.0)
+    }
+
+    /// A label describing the given amplitude in the units of the scale.
+    pub fn label(&self, amp: f32) -> String {
+        match *self {
+            Scale::Linear => format!("{:.2}", amp),
+            Scale::Dbfs if amp_to_db(amp) <= DBFS_FLOOR => "-inf dBFS".to_string(),
+            Scale::Dbfs => format!("{:.1} dBFS", amp_to_db(amp)),
+            Scale::Vu => format!("{:+.1} VU", amp_to_vu(amp).max(VU_MIN)),
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            scale: default::scale(),
+            attack: default::attack(),
+            release: default::release(),
+            rms_integration: default::rms_integration(),
+            peak_hold: default::peak_hold(),
+        }
+    }
+}
+
+impl Meter {
+    /// Integrate the raw peak and RMS of a single buffer of `frames` frames.
+    pub fn update(&mut self, settings: &Settings, peak: f32, rms: f32, frames: usize) {
+        let secs = frames as f64 / audio::sample_rate();
+        let time = if peak > self.peak { settings.attack } else { settings.release };
+        self.peak += (peak - self.peak) * coefficient(time, secs);
+        let rms_coefficient = coefficient(settings.rms_integration, secs);
+        self.mean_square += (rms * rms - self.mean_square) * rms_coefficient;
+        self.rms = self.mean_square.sqrt();
+
+        // Hold the highest peak until it expires.
+        let hold = Duration::from_millis(settings.peak_hold.ms().max(0.0) as u64);
+        let now = Instant::now();
+        let expired = self.held_at.map(|at| now.duration_since(at) >= hold).unwrap_or(true);
+        if expired || self.peak >= self.held_peak {
+            self.held_peak = self.peak;
+            self.held_at = Some(now);
+        }
+    }
+
+    /// Reset the meter to silence.
+    pub fn clear(&mut self) {
+        *self = Default::default();
+    }
+}
+
+/// Convert the given amplitude to decibels relative to full scale.
+pub fn amp_to_db(amp: f32) -> f32 {
+    if amp > 0.0 {
+        20.0 * amp.log10()
+    } else {
+        ::std::f32::NEG_INFINITY
+    }
+}
+
+// Convert the given amplitude to volume units.
+fn amp_to_vu(amp: f32) -> f32 {
+    amp_to_db(amp) - VU_REFERENCE_DBFS
+}
+
+// The amount by which a one-pole smoother moves towards its target over `secs` seconds given
+// the time constant `time`.
+fn coefficient(time: Ms, secs: f64) -> f32 {
+    let time_secs = time.ms() / 1_000.0;
+    if time_secs <= 0.0 {
+        return 1.0;
+    }
+    (1.0 - (-secs / time_secs).exp()) as f32
+}
+
+mod default {
+    use super::Scale;
+    use time_calc::Ms;
+
+    pub fn scale() -> Scale {
+        Scale::Dbfs
+    }
+
+    pub fn attack() -> Ms {
+        Ms(10.0)
+    }
+
+    pub fn release() -> Ms {
+        Ms(300.0)
+    }
+
+    pub fn rms_integration() -> Ms {
+        Ms(300.0)
+    }
+
+    pub fn peak_hold() -> Ms {
+        Ms(1_500.0)
+    }
+}
+
+#[test]
+fn test_scale_normalise() {
+    assert_eq!(Scale::Dbfs.normalise(1.0), 1.0);
+    assert_eq!(Scale::Dbfs.normalise(0.0), 0.0);
+    assert!((Scale::Dbfs.normalise(0.001) - 0.5).abs() < 1e-4);
+    let vu_reference = 10f32.powf(VU_REFERENCE_DBFS / 20.0);
+    let expected = -VU_MIN / (VU_MAX - VU_MIN);
+    assert!((Scale::Vu.normalise(vu_reference) - expected).abs() < 1e-4);
+}
@@ -2,6 +2,8 @@ use audio;
 use camera::Camera;
 use config::Config;
 use fxhash::FxHashMap;
+use installation;
+use interaction;
 use metres::Metres;
 use nannou;
 use nannou::prelude::*;
@@ -11,34 +13,57 @@ use osc;
 use osc::input::Log as OscInputLog;
 use osc::output::Log as OscOutputLog;
 use project::{self, Project};
-use soundscape::Soundscape;
+use project::part::exports_directory;
+use soundscape::{self, Soundscape};
 use slug::slugify;
 use std::collections::VecDeque;
+use std::io;
+use std::mem;
 use std::path::{Path, PathBuf};
 use std::ops::{Deref, DerefMut};
 use std::sync::{mpsc, Arc};
 use std::sync::atomic::AtomicUsize;
+use std::time::{SystemTime, UNIX_EPOCH};
 use time_calc::Ms;
 use utils::{self, HumanReadableTime, SEC_MS, MIN_MS, HR_MS};
 
+use self::audio_settings::AudioSettings;
 use self::installation_editor::InstallationEditor;
+use self::interaction_editor::InteractionEditor;
+use self::osc_composer::OscComposer;
 use self::project_editor::ProjectEditor;
 use self::soundscape_editor::SoundscapeEditor;
+use self::soundscape_simulation::SoundscapeSimulation;
 use self::source_editor::{SourceEditor, SourcePreviewMode};
 use self::speaker_editor::SpeakerEditor;
 
-mod custom_widget;
+pub mod audio_settings;
+pub mod audio_watcher;
+pub mod custom_widget;
+pub mod floorplan;
+pub mod floorplan_export;
 pub mod installation_editor;
+pub mod loudness;
+pub mod interaction_editor;
 pub mod control_log;
+pub mod fault_log;
+pub mod gamepad;
 pub mod master;
+pub mod meter;
 pub mod monitor;
+pub mod osc_composer;
 pub mod osc_in_log;
+pub mod osc_namespace;
 pub mod osc_out_log;
 pub mod project_editor;
+pub mod source_dependencies;
 pub mod source_editor;
 pub mod soundscape_editor;
+pub mod soundscape_simulation;
 pub mod speaker_editor;
+pub mod telemetry;
 mod theme;
+pub mod touch;
 
 type ActiveSoundMap = FxHashMap<audio::sound::Id, ActiveSound>;
 
@@ -48,10 +73,14 @@ type ActiveSoundMap = FxHashMap<audio::sound::Id, ActiveSound>;
 pub struct Model {
     /// The nannou UI state.
     pub ui: Ui,
+    /// The window in which the GUI is displayed.
+    window_id: WindowId,
     /// The currently selected project.
     pub project: Option<(Project, ProjectState)>,
     /// Whether or not the GUI is currently in CPU-saving mode.
     pub cpu_saving_mode: bool,
+    /// Whether or not the GUI is currently in touch interaction mode.
+    pub touch_mode: bool,
     /// All images used within the GUI.
     images: Images,
     /// A unique ID for each widget.
@@ -64,6 +93,8 @@ pub struct Model {
     sound_id_gen: audio::sound::IdGenerator,
     /// The latest received audio state.
     audio_monitor: AudioMonitor,
+    /// Measures the loudness of WAV sources on a separate thread.
+    loudness_analyser: audio::source::wav::loudness::Analyser,
     /// The path to the assets directory path at the time the App started running.
     assets: PathBuf,
 }
@@ -72,6 +103,7 @@ pub struct Model {
 pub struct Gui<'a> {
     ui: UiCell<'a>,
     cpu_saving_mode: bool,
+    touch_mode: &'a mut bool,
     images: &'a Images,
     ids: &'a mut Ids,
     state: &'a mut State,
@@ -86,12 +118,28 @@ pub struct Gui<'a> {
 pub struct ProjectState {
     /// Runtime state related to the installation editor GUI panel.
     installation_editor: InstallationEditor,
+    /// Runtime state related to the interaction editor GUI panel.
+    interaction_editor: InteractionEditor,
     /// Runtime state related to the source editor GUI panel.
     soundscape_editor: SoundscapeEditor,
+    /// Runtime state related to the soundscape simulation GUI panel.
+    soundscape_simulation: SoundscapeSimulation,
     /// Runtime state related to the speaker editor GUI panel.
     speaker_editor: SpeakerEditor,
     /// Runtime state related to the source editor GUI panel.
     source_editor: SourceEditor,
+    /// Runtime state related to the OSC composer GUI panel.
+    osc_composer: OscComposer,
+    /// The context menu opened over a speaker on the floorplan, if any.
+    floorplan_context_menu: Option<FloorplanContextMenu>,
+}
+
+/// A context menu opened over a speaker on the floorplan via a long press or right click.
+struct FloorplanContextMenu {
+    /// The speaker to which the menu applies.
+    speaker: audio::speaker::Id,
+    /// The absolute GUI position at which the menu was opened.
+    xy: ui::Point,
 }
 
 /// State available to the GUI during widget instantiation.
@@ -104,10 +152,26 @@ pub struct State {
     osc_out_log: Log<OscOutputLog>,
     /// A log of the most recently received controls for testing/debugging/monitoring.
     control_log: ControlLog,
+    /// A log of the most recent faults silenced on the audio output thread.
+    fault_log: FaultLog,
     /// State related to the project editor.
     project_editor: ProjectEditor,
     /// Whether or not each of the collapsible areas are open within the sidebar.
     is_open: IsOpen,
+    /// Touches currently in contact with the screen, tracked while in touch mode.
+    touches: touch::Touches,
+    /// The recording of the ambisonics bus in progress, if any.
+    ambisonics_recording: Option<audio::capture::WavWriter>,
+    /// The recording of the final output of all channels in progress, if any.
+    output_recording: Option<audio::capture::WavWriter>,
+    /// The master blackout underway and its schedule.
+    blackout: master::Blackout,
+    /// The master reference signal used for soundcheck and calibration.
+    reference: master::Reference,
+    /// The sample rate and buffer size of the audio streams.
+    audio_settings: AudioSettings,
+    /// The time-lapse of the floorplan view being recorded, if any.
+    floorplan_timelapse: Option<floorplan_export::Timelapse>,
 }
 
 /// The state of each collapsible area in the sidebar.
@@ -115,13 +179,21 @@ struct IsOpen {
     project_editor: bool,
     master: bool,
     installation_editor: bool,
+    interaction_editor: bool,
     soundscape_editor: bool,
+    soundscape_simulation: bool,
     speaker_editor: bool,
     source_editor: bool,
+    source_dependencies: bool,
     side_menu: bool,
     osc_in_log: bool,
     osc_out_log: bool,
+    osc_composer: bool,
+    osc_namespace: bool,
     control_log: bool,
+    fault_log: bool,
+    audio_settings: bool,
+    floorplan_transform: bool,
 }
 
 /// The number of audio input and output channels available on the input and output devices.
@@ -139,9 +211,11 @@ pub struct Channels {
     pub control_rx: mpsc::Receiver<osc::input::Control>,
     pub soundscape: Soundscape,
     pub wav_reader: audio::source::wav::reader::Handle,
-    pub audio_input: audio::input::Stream,
+    pub audio_input: audio::input::Streams,
     pub audio_output: audio::output::Stream,
     pub audio_monitor_msg_rx: monitor::Receiver,
+    pub audio_watcher_rx: audio_watcher::Receiver,
+    pub gamepad_rx: gamepad::Receiver,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -151,9 +225,8 @@ struct Image {
     height: Scalar,
 }
 
-#[derive(Debug)]
 struct Images {
-    floorplan: Image,
+    floorplan: floorplan::Floorplan,
 }
 
 struct Log<T> {
@@ -167,18 +240,39 @@ struct Log<T> {
 
 type ControlLog = Log<osc::input::Control>;
 
+// A fault detected and silenced on the audio output thread.
+struct AudioFault {
+    // The UTC time at which the fault was received.
+    time: String,
+    // The offending sound and the name of its source, or `None` if found in the final output.
+    sound: Option<(audio::sound::Id, Option<String>)>,
+    fault: audio::guard::Fault,
+}
+
+type FaultLog = Log<AudioFault>;
+
 // A structure for monitoring the state of the audio thread for visualisation.
 #[derive(Default)]
 struct AudioMonitor {
-    master_peak: f32,
+    master_meter: meter::Meter,
+    master_meter_settings: meter::Settings,
+    sound_widget_settings: custom_widget::sound::Settings,
+    // The greatest gain reduction applied by the master limiter since the last GUI update.
+    master_gain_reduction_db: f32,
+    dsp_load: master::DspLoad,
+    // Loudness of the master bus, retained across project switches like the DSP load.
+    loudness: loudness::Loudness,
+    // The most recent telemetry of the audio output thread, dumped upon an under-run.
+    telemetry: telemetry::Telemetry,
     pub active_sounds: ActiveSoundMap,
     speakers: FxHashMap<audio::speaker::Id, ChannelLevels>,
 }
 
 impl AudioMonitor {
-    /// Clears all state and resets the last received master peak volume.
+    /// Clears all state and resets the master meter.
     pub fn clear(&mut self) {
-        self.master_peak = 0.0;
+        self.master_meter.clear();
+        self.master_gain_reduction_db = 0.0;
         self.active_sounds.clear();
         self.speakers.clear();
     }
@@ -201,6 +295,8 @@ struct ActiveSound {
     channels: Vec<ChannelLevels>,
     // The normalised progress through the playback of the sound.
     normalised_progress: Option<f64>,
+    // The remaining duration of playback, or `None` if the sound plays indefinitely.
+    remaining: Option<Ms>,
 }
 
 // The detected levels for a single channel.
@@ -212,7 +308,15 @@ struct ChannelLevels {
 
 /// A message sent from the audio thread with some audio levels.
 pub enum AudioMonitorMessage {
-    Master { peak: f32 },
+    Master {
+        peak: f32,
+        rms: f32,
+        gain_reduction_db: f32,
+        frames: usize,
+        loudness: audio::loudness::Analysis,
+    },
+    Dsp(audio::telemetry::Sample),
+    Fault { sound: Option<audio::sound::Id>, fault: audio::guard::Fault },
     ActiveSound(audio::sound::Id, ActiveSoundMessage),
     Speaker(audio::speaker::Id, SpeakerMessage),
 }
@@ -221,12 +325,14 @@ pub enum AudioMonitorMessage {
 pub enum ActiveSoundMessage {
     Start {
         normalised_progress: Option<f64>,
+        remaining: Option<Ms>,
         source_id: audio::source::Id,
         position: audio::sound::Position,
         channels: usize,
     },
     Update {
         normalised_progress: Option<f64>,
+        remaining: Option<Ms>,
         source_id: audio::source::Id,
         position: audio::sound::Position,
         channels: usize,
@@ -256,12 +362,20 @@ impl Default for IsOpen {
             project_editor: false,
             master: false,
             installation_editor: false,
+            interaction_editor: false,
             soundscape_editor: false,
+            soundscape_simulation: false,
             speaker_editor: false,
             source_editor: false,
+            source_dependencies: false,
             osc_in_log: false,
             osc_out_log: false,
+            osc_composer: false,
+            osc_namespace: false,
             control_log: false,
+            fault_log: false,
+            audio_settings: false,
+            floorplan_transform: false,
         }
     }
 }
@@ -298,22 +412,8 @@ impl Model {
             });
 
         // Load and insert the images to be used.
-        let floorplan_path = images_directory(assets).join("floorplan.png");
-        let floorplan_texture = {
-            let window = app.window(window_id).expect("window closed unexpectedly");
-            let device  = window.swap_chain_device();
-            let image = nannou::image::open(floorplan_path).unwrap();
-            let image_rgba = image.into_rgba();
-            // The wgpu device queue used to load the image data.
-            let mut queue = window.swap_chain_queue().lock().unwrap();
-            // Describe how we will use the texture so that the GPU may handle it efficiently.
-            let usage = wgpu::TextureUsage::SAMPLED;
-            wgpu::Texture::load_from_image_buffer(device, &mut *queue, usage, &image_rgba)
-        };
-        let [width, height] = floorplan_texture.size();
-        let [width, height] = [width as f64, height as f64];
-        let id = ui.image_map.insert(floorplan_texture.into_ui_image());
-        let floorplan = Image { id, width, height };
+        let images_directory = images_directory(assets);
+        let floorplan = floorplan::load(&images_directory, app, window_id, &mut ui);
         let images = Images { floorplan };
 
         // Initialise the GUI state.
@@ -330,21 +430,31 @@ impl Model {
         let (project_tuple, state) = if let Some(project) = project {
             project.reset_and_sync_all_threads(&channels);
             let project_state = Default::default();
-            let mut state = State::new(&project.config, audio_channels);
+            let mut state = State::new(&project.config, audio_channels, config.stream);
             state.project_editor.text_box_name = project.name.clone();
             let project_tuple = Some((project, project_state));
             (project_tuple, state)
         } else {
-            let state = State::new(&config.project_default, audio_channels);
+            let state = State::new(&config.project_default, audio_channels, config.stream);
             (None, state)
         };
 
         // Initialise the audio monitor.
-        let audio_monitor = Default::default();
+        let audio_monitor = AudioMonitor {
+            master_meter_settings: config.master_meter,
+            sound_widget_settings: config.sound_widgets,
+            ..Default::default()
+        };
 
         // Whether or not CPU saving mode is enabled.
         let cpu_saving_mode = config.cpu_saving_mode;
 
+        // Whether or not touch interaction mode is enabled.
+        let touch_mode = config.touch_mode;
+
+        // Spawn the thread used for analysing the loudness of WAV sources.
+        let loudness_analyser = audio::source::wav::loudness::spawn();
+
         // Notify audio output thread.
         channels
             .audio_output
@@ -353,7 +463,9 @@ impl Model {
 
         Model {
             ui,
+            window_id,
             cpu_saving_mode,
+            touch_mode,
             images,
             state,
             ids,
@@ -362,6 +474,7 @@ impl Model {
             sound_id_gen,
             assets: assets.into(),
             audio_monitor,
+            loudness_analyser,
         }
     }
 
@@ -369,18 +482,21 @@ impl Model {
     ///
     /// - Collect pending OSC and control messages for the logs.
     /// - Instantiate the Ui's widgets.
-    pub fn update(&mut self, default_project_config: &project::Config) {
+    pub fn update(&mut self, app: &App, default_project_config: &project::Config) {
         let Model {
             ref mut ui,
+            window_id,
             ref mut ids,
             ref mut project,
             ref mut state,
             ref mut audio_monitor,
             ref mut cpu_saving_mode,
-            ref images,
+            ref mut touch_mode,
+            ref mut images,
             ref channels,
             ref sound_id_gen,
             ref assets,
+            ref mut loudness_analyser,
             ..
         } = *self;
 
@@ -451,6 +567,156 @@ impl Model {
                         .expect("failed to send updated source volume to audio output thread");
                 }
 
+                &osc::input::Control::InstallationSourceVolume(ref source_volume) => {
+                    let osc::input::InstallationSourceVolume {
+                        ref prefix,
+                        ref name,
+                        volume,
+                    } = *source_volume;
+
+                    let project = match *project {
+                        None => continue,
+                        Some((ref mut proj, _)) => proj,
+                    };
+
+                    // Find the installations bound to the prefix.
+                    let installations = installations_bound_to_prefix(project, prefix);
+                    if installations.is_empty() {
+                        continue;
+                    }
+
+                    // Update the local copies of matching sources assigned to the installations.
+                    let mut ids = vec![];
+                    for (&id, source) in project.state.sources.iter_mut() {
+                        if let Some(ref name) = *name {
+                            if &source.name != name {
+                                continue;
+                            }
+                        }
+                        let is_assigned = match source.audio.role {
+                            Some(audio::source::Role::Soundscape(ref s)) => {
+                                installations.iter().any(|id| s.installations.contains(id))
+                            },
+                            _ => false,
+                        };
+                        if is_assigned {
+                            source.volume = volume;
+                            ids.push(id);
+                        }
+                    }
+
+                    for id in ids {
+                        // Update the soundscape copy.
+                        channels
+                            .soundscape
+                            .send(move |soundscape| {
+                                soundscape.update_source(&id, |source| source.volume = volume);
+                            })
+                            .expect("failed to send updated source volume to soundscape thread");
+
+                        // Update the audio output copies.
+                        channels
+                            .audio_output
+                            .send(move |audio| {
+                                audio.update_sounds_with_source(&id, move |_, sound| {
+                                    sound.volume = volume;
+                                });
+                            })
+                            .expect("failed to send updated source volume to audio output thread");
+                    }
+                }
+
+                &osc::input::Control::InstallationVolume(ref volume) => {
+                    let osc::input::InstallationVolume { ref prefix, gain, muted } = *volume;
+
+                    let project = match *project {
+                        None => continue,
+                        Some((ref mut proj, _)) => proj,
+                    };
+
+                    let installations = installations_bound_to_prefix(project, prefix);
+                    for id in installations {
+                        let installations = &mut project.state.installations;
+                        installation_editor::set_volume(id, installations, channels, |params| {
+                            if let Some(gain) = gain {
+                                params.gain = gain;
+                            }
+                            if let Some(muted) = muted {
+                                params.muted = muted;
+                            }
+                        });
+                    }
+                }
+
+                &osc::input::Control::InstallationSensor(ref sensor) => {
+                    let osc::input::InstallationSensor { ref prefix, reading } = *sensor;
+
+                    let (project, project_state) = match *project {
+                        None => continue,
+                        Some((ref mut proj, ref mut state)) => (proj, state),
+                    };
+
+                    let installations = installations_bound_to_prefix(project, prefix);
+                    for id in installations {
+                        installation_editor::receive_sensor_reading(
+                            id,
+                            reading,
+                            &mut project.state.installations,
+                            &mut project_state.installation_editor.sensor_readings,
+                            channels,
+                        );
+                    }
+                }
+
+                &osc::input::Control::InstallationActivity(ref activity) => {
+                    let osc::input::InstallationActivity { ref prefix, reading } = *activity;
+
+                    let (project, project_state) = match *project {
+                        None => continue,
+                        Some((ref mut proj, ref mut state)) => (proj, state),
+                    };
+
+                    // Installations sharing a prefix drive the intensity to the highest of theirs.
+                    let mut intensity: Option<f32> = None;
+                    let installations = installations_bound_to_prefix(project, prefix);
+                    for id in installations {
+                        let driven = installation_editor::receive_activity_reading(
+                            id,
+                            reading,
+                            &project.state.installations,
+                            &mut project_state.installation_editor.activity_readings,
+                            channels,
+                        );
+                        intensity = match (intensity, driven) {
+                            (Some(a), Some(b)) => Some(a.max(b)),
+                            (a, b) => a.or(b),
+                        };
+                    }
+
+                    if let Some(intensity) = intensity {
+                        project.master.soundscape_intensity = intensity;
+                        channels
+                            .soundscape
+                            .send(move |soundscape| soundscape.set_intensity(intensity))
+                            .expect("failed to send soundscape intensity to soundscape thread");
+                    }
+                }
+
+                &osc::input::Control::InteractionPosition(ref position) => {
+                    let osc::input::InteractionPosition { ref prefix, entity, x, y } = *position;
+                    if let Some((ref project, ref mut project_state)) = *project {
+                        let entity = interaction::EntityId(entity.unwrap_or(0));
+                        interaction_editor::receive_position(
+                            channels,
+                            project,
+                            &mut project_state.interaction_editor,
+                            prefix,
+                            entity,
+                            pt2(x as f64, y as f64),
+                        );
+                    }
+                }
+
                 &osc::input::Control::PlaySoundscape => {
                     channels
                         .soundscape
@@ -464,22 +730,187 @@ impl Model {
                         .pause()
                         .expect("failed to send `Pause` message to soundscape thread");
                 }
+
+                &osc::input::Control::Blackout(ref blackout) => {
+                    if let Some((ref project, _)) = *project {
+                        let mut params = project.master.blackout.clone();
+                        let secs = |s: Option<f32>, default: f64| {
+                            s.map(|s| s as f64).unwrap_or(default)
+                        };
+                        params.fade_out_secs = secs(blackout.fade_out_secs, params.fade_out_secs);
+                        params.hold_secs = secs(blackout.hold_secs, params.hold_secs);
+                        params.fade_in_secs = secs(blackout.fade_in_secs, params.fade_in_secs);
+                        master::start_blackout(&params, channels, &mut state.blackout);
+                    }
+                }
+
+                &osc::input::Control::Restore(osc::input::Restore { fade_in_secs }) => {
+                    if let Some((ref project, _)) = *project {
+                        let default_secs = project.master.blackout.fade_in_secs;
+                        let secs = fade_in_secs.map(|s| s as f64).unwrap_or(default_secs);
+                        master::restore_blackout(secs, channels, &mut state.blackout);
+                    }
+                }
+
+                &osc::input::Control::Intensity(osc::input::Intensity(intensity)) => {
+                    // Update local copy.
+                    if let Some((ref mut project, _)) = *project {
+                        project.master.soundscape_intensity = intensity;
+                    }
+
+                    // Update the soundscape copy.
+                    channels
+                        .soundscape
+                        .send(move |soundscape| soundscape.set_intensity(intensity))
+                        .expect("failed to send soundscape intensity to soundscape thread");
+                },
+
+                &osc::input::Control::Scene(ref scene) => {
+                    if let Some((ref mut project, _)) = *project {
+                        let scenes = &project.master.soundscape_scenes;
+                        if !scenes.contains(&scene.name) {
+                            eprintln!("no soundscape scene named `{}`", scene.name);
+                        } else {
+                            let secs = scene
+                                .crossfade_secs
+                                .map(|s| s as f64)
+                                .unwrap_or(scenes.crossfade_secs);
+                            let name = scene.name.clone();
+                            soundscape_editor::cue_scene(name, secs, &mut project.master, channels);
+                        }
+                    }
+                },
             }
 
             // Log the message.
             state.control_log.push_msg(control);
         }
 
+        // Begin a blackout at each of the project's scheduled times.
+        if let Some((ref project, _)) = *project {
+            let master = &project.master;
+            if state.blackout.check_schedule(&master.blackout.schedule, &master.time_zone) {
+                master::start_blackout(&project.master.blackout, channels, &mut state.blackout);
+            }
+        }
+
+        // Crossfade towards each scene at its scheduled time.
+        if let Some((ref mut project, ref mut project_state)) = *project {
+            let due = project_state.soundscape_editor.check_scene_schedule(
+                &project.master.soundscape_scenes,
+                &project.master.time_zone,
+            );
+            if let Some(scene) = due {
+                let secs = project.master.soundscape_scenes.crossfade_secs;
+                soundscape_editor::cue_scene(scene, secs, &mut project.master, channels);
+            }
+        }
+
+        // Progress any speaker calibration underway, even while the speaker editor is closed.
+        if let Some((ref mut project, ref mut project_state)) = *project {
+            speaker_editor::update_calibration(
+                &mut project_state.speaker_editor,
+                &mut project.state.speakers,
+                channels,
+            );
+        }
+
+        // Reload any WAV sources whose files have changed on disk.
+        for path in channels.audio_watcher_rx.try_iter() {
+            if let Some((ref mut project, ref mut project_state)) = *project {
+                source_editor::reload_wav_sources(
+                    &path,
+                    channels,
+                    sound_id_gen,
+                    project,
+                    &mut project_state.source_editor,
+                );
+            }
+        }
+
+        // Nudge the selection and move the camera with any connected game controllers.
+        for msg in channels.gamepad_rx.try_iter() {
+            if let Some((ref mut project, ref mut project_state)) = *project {
+                apply_gamepad_message(msg, channels, project, project_state);
+            }
+        }
+
+        // Analyse the loudness of any new WAV sources and apply completed analyses.
+        if let Some((ref mut project, ref mut project_state)) = *project {
+            source_editor::update_loudness(
+                loudness_analyser,
+                channels,
+                project,
+                &mut project_state.source_editor,
+            );
+        }
+
+        // Feed in the simulated visitor, if any, and forget any tracked entities that are no
+        // longer reported by their interaction input.
+        if let Some((ref project, ref mut project_state)) = *project {
+            let interaction_editor = &mut project_state.interaction_editor;
+            interaction_editor::update_simulated_visitor(channels, project, interaction_editor);
+            interaction_editor::remove_lost_tracks(channels, project, interaction_editor);
+        }
+
         // Update the map of active sounds.
+        let mut max_gain_reduction_db: Option<f32> = None;
+        let mut max_dsp_load: Option<f32> = None;
+        let mut queue_depth = 0;
         loop {
             let msg = match channels.audio_monitor_msg_rx.try_pop() {
                 None => break,
                 Some(msg) => msg,
             };
+            queue_depth += 1;
 
             match msg {
-                AudioMonitorMessage::Master { peak } => {
-                    audio_monitor.master_peak = peak;
+                AudioMonitorMessage::Master { peak, rms, gain_reduction_db, frames, loudness } => {
+                    let settings = audio_monitor.master_meter_settings;
+                    audio_monitor.master_meter.update(&settings, peak, rms, frames);
+                    audio_monitor.loudness.update(loudness, frames);
+                    let max = max_gain_reduction_db.unwrap_or(0.0).max(gain_reduction_db);
+                    max_gain_reduction_db = Some(max);
+                },
+                AudioMonitorMessage::Dsp(sample) => {
+                    let load = sample.load;
+                    max_dsp_load = Some(max_dsp_load.unwrap_or(0.0).max(load));
+                    audio_monitor.dsp_load.peak = audio_monitor.dsp_load.peak.max(load);
+                    audio_monitor.telemetry.push(sample, queue_depth - 1);
+                    if sample.xrun {
+                        audio_monitor.dsp_load.xruns += 1;
+                        if audio_monitor.telemetry.is_dump_due() {
+                            let directory = telemetry_directory(assets);
+                            match audio_monitor.telemetry.dump(&directory) {
+                                Ok(path) => eprintln!(
+                                    "audio under-run: wrote telemetry to \"{}\"",
+                                    path.display()
+                                ),
+                                Err(err) => eprintln!("failed to write audio telemetry: {}", err),
+                            }
+                        }
+                    }
+                },
+                AudioMonitorMessage::Fault { sound, fault } => {
+                    let sound = sound.map(|id| {
+                        let name = audio_monitor
+                            .active_sounds
+                            .get(&id)
+                            .and_then(|s| match *project {
+                                Some((ref project, _)) => project.sources.get(&s.source_id),
+                                None => None,
+                            })
+                            .map(|source| source.name.clone());
+                        (id, name)
+                    });
+                    let secs = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map(|d| d.as_secs())
+                        .unwrap_or(0);
+                    let time = project::history::format_timestamp(secs);
+                    let fault = AudioFault { time, sound, fault };
+                    eprintln!("{}", fault.line());
+                    state.fault_log.push_msg(fault);
                 },
                 AudioMonitorMessage::ActiveSound(id, msg) => match msg {
                     ActiveSoundMessage::Start {
@@ -487,12 +918,14 @@ impl Model {
                         position,
                         channels,
                         normalised_progress,
+                        remaining,
                     } => {
                         let active_sound = ActiveSound::new(
                             source_id,
                             position,
                             channels,
                             normalised_progress,
+                            remaining,
                         );
                         audio_monitor.active_sounds.insert(id, active_sound);
                     }
@@ -501,15 +934,18 @@ impl Model {
                         position,
                         channels,
                         normalised_progress,
+                        remaining,
                     } => {
                         let active_sound = audio_monitor
                             .active_sounds
                             .entry(id)
                             .or_insert_with(|| {
-                                ActiveSound::new(source_id, position, channels, normalised_progress)
+                                let progress = normalised_progress;
+                                ActiveSound::new(source_id, position, channels, progress, remaining)
                             });
                         active_sound.position = position;
                         active_sound.normalised_progress = normalised_progress;
+                        active_sound.remaining = remaining;
                     }
                     ActiveSoundMessage::UpdateChannel { index, rms, peak } => {
                         if let Some(active_sound) = audio_monitor.active_sounds.get_mut(&id) {
@@ -548,6 +984,12 @@ impl Model {
                 },
             }
         }
+        if let Some(gain_reduction_db) = max_gain_reduction_db {
+            audio_monitor.master_gain_reduction_db = gain_reduction_db;
+        }
+        if let Some(load) = max_dsp_load {
+            audio_monitor.dsp_load.load = load;
+        }
 
         // Check that all active sounds are still valid in case the GUI switched the project.
         match *project {
@@ -555,10 +997,25 @@ impl Model {
             None => audio_monitor.clear(),
         }
 
+        // Hold the lease on the selected project's directory.
+        let selected_project = project.as_ref().map(|&(ref project, _)| project);
+        project_editor::update_lock(&mut state.project_editor, selected_project, assets);
+
+        // Apply the project's floorplan transform and display the latest rendering of the visible
+        // region of a vector floorplan.
+        let (transform, pixels_per_metre) = match *project {
+            Some((ref project, _)) => {
+                (project.state.floorplan, project.config.floorplan_pixels_per_metre)
+            },
+            None => (Default::default(), default_project_config.floorplan_pixels_per_metre),
+        };
+        images.floorplan.update(app, window_id, ui, transform, pixels_per_metre);
+
         // Set the widgets.
         let ui = ui.set_widgets();
 
-        // Check for `Ctrl+S` or `Cmd+S` for saving, or `Ctrl+Space` for cpu saving mode.
+        // Check for `Ctrl+S` or `Cmd+S` for saving, `Ctrl+Space` for cpu saving mode or `Ctrl+T`
+        // for touch mode.
         for event in ui.global_input().events().ui() {
             if let ui::event::Ui::Press(_, press) = *event {
                 match press.button {
@@ -568,7 +1025,8 @@ impl Model {
                             || press.modifiers.contains(ui::input::keyboard::ModifierKey::GUI);
                         if save_mod {
                             if let Some((ref project, _)) = *project {
-                                project.save(assets).expect("failed to save project on keyboard shortcut");
+                                project_editor::save(project, assets, &mut state.project_editor);
+                                state.project_editor.changes = None;
                             }
                         }
                     }
@@ -586,14 +1044,26 @@ impl Model {
                         }
                     }
 
+                    ui::event::Button::Keyboard(ui::input::Key::T) => {
+                        if press.modifiers.contains(ui::input::keyboard::ModifierKey::CTRL) {
+                            *touch_mode = !*touch_mode;
+                        }
+                    }
+
                     _ => (),
                 }
             }
         }
 
+        // Track touches for recognising pinches and long presses.
+        if *touch_mode {
+            state.touches.update(ui.global_input().events().ui());
+        }
+
         let mut gui = Gui {
             ui,
             cpu_saving_mode: *cpu_saving_mode,
+            touch_mode,
             ids,
             images,
             state,
@@ -620,6 +1090,66 @@ impl Model {
         !self.audio_monitor.active_sounds.is_empty()
     }
 
+    /// The current configuration of the master meter.
+    pub fn master_meter_settings(&self) -> meter::Settings {
+        self.audio_monitor.master_meter_settings
+    }
+
+    /// The current configuration of the active sounds displayed over the floorplan.
+    pub fn sound_widget_settings(&self) -> custom_widget::sound::Settings {
+        self.audio_monitor.sound_widget_settings
+    }
+
+    /// The configuration with which the running audio streams were built.
+    ///
+    /// Any selection within the audio settings that has not yet been applied is excluded.
+    pub fn stream_config(&self) -> audio::StreamConfig {
+        self.state.audio_settings.running
+    }
+
+    /// The stream configuration applied via the audio settings since the last call, if any.
+    pub fn take_stream_config_to_apply(&mut self) -> Option<audio::StreamConfig> {
+        let audio_settings = &mut self.state.audio_settings;
+        match mem::replace(&mut audio_settings.apply, false) {
+            true => Some(audio_settings.selected),
+            false => None,
+        }
+    }
+
+    /// Update the GUI for audio streams rebuilt with the given configuration and sync the
+    /// selected project to their models.
+    ///
+    /// WAV sources are checked against the new sample rate first, so that those at a different
+    /// rate are marked as missing and those relinked at the new rate are restored.
+    pub fn audio_streams_rebuilt(
+        &mut self,
+        running: audio::StreamConfig,
+        input_channels: usize,
+        output_channels: usize,
+    ) {
+        self.state.audio_settings.running = running;
+        self.state.audio_settings.selected = running;
+        let (input, output) = (input_channels, output_channels);
+        self.state.audio_channels = AudioChannels { input, output };
+        if let Some((ref mut project, _)) = self.project {
+            project.sources.prepare(project::audio_directory(&self.assets));
+            project.reset_and_sync_all_threads(&self.channels);
+        }
+    }
+
+    /// Save the selected project, if any, before exiting.
+    ///
+    /// The project is saved as a copy if another machine saved it since it was opened here.
+    pub fn save_project(&mut self) -> io::Result<()> {
+        let Model { ref mut project, ref state, ref assets, .. } = *self;
+        match *project {
+            Some((ref mut project, _)) => {
+                project_editor::save_before_switching(project, assets, &state.project_editor)
+            },
+            None => Ok(()),
+        }
+    }
+
     /// If a project is currently selected, this returns its directory path slug.
     pub fn selected_project_slug(&self) -> Option<String> {
         self.project.as_ref().map(|&(ref project, _)| slugify(&project.name))
@@ -629,19 +1159,39 @@ impl Model {
 impl State {
     /// Initialise the `State` and send any loaded speakers and sources to the audio and composer
     /// threads.
-    fn new(config: &project::Config, audio_channels: AudioChannels) -> Self {
+    fn new(
+        config: &project::Config,
+        audio_channels: AudioChannels,
+        stream: audio::StreamConfig,
+    ) -> Self {
         let osc_in_log = Log::with_limit(config.osc_input_log_limit);
         let osc_out_log = Log::with_limit(config.osc_output_log_limit);
         let control_log = Log::with_limit(config.control_log_limit);
+        let fault_log = Log::with_limit(fault_log::LIMIT);
         let is_open = Default::default();
         let project_editor = ProjectEditor::default();
+        let touches = Default::default();
+        let ambisonics_recording = None;
+        let output_recording = None;
+        let blackout = Default::default();
+        let reference = Default::default();
+        let audio_settings = AudioSettings::new(stream);
+        let floorplan_timelapse = None;
         State {
             osc_in_log,
             osc_out_log,
             control_log,
+            fault_log,
             audio_channels,
             project_editor,
             is_open,
+            touches,
+            ambisonics_recording,
+            output_recording,
+            blackout,
+            reference,
+            audio_settings,
+            floorplan_timelapse,
         }
     }
 }
@@ -656,9 +1206,11 @@ impl Channels {
         control_rx: mpsc::Receiver<osc::input::Control>,
         soundscape: Soundscape,
         wav_reader: audio::source::wav::reader::Handle,
-        audio_input: audio::input::Stream,
+        audio_input: audio::input::Streams,
         audio_output: audio::output::Stream,
         audio_monitor_msg_rx: monitor::Receiver,
+        audio_watcher_rx: audio_watcher::Receiver,
+        gamepad_rx: gamepad::Receiver,
     ) -> Self {
         Channels {
             frame_count,
@@ -671,6 +1223,8 @@ impl Channels {
             audio_input,
             audio_output,
             audio_monitor_msg_rx,
+            audio_watcher_rx,
+            gamepad_rx,
         }
     }
 }
@@ -739,10 +1293,12 @@ impl Log<OscOutputLog> {
             addr,
             ref msg,
             ref error,
+            dry_run,
             ..
         } in &self.deque
         {
-            let addr_string = format!("{}: [{}] \"{}\"\n", index, addr, msg.addr);
+            let dry_run = if dry_run { " (dry run)" } else { "" };
+            let addr_string = format!("{}: [{}] \"{}\"{}\n", index, addr, msg.addr, dry_run);
             s.push_str(&addr_string);
 
             // Arguments.
@@ -797,6 +1353,31 @@ impl ControlLog {
     }
 }
 
+impl FaultLog {
+    // Format the log in a single string of faults.
+    fn format(&self) -> String {
+        let mut s = String::new();
+        let mut index = self.start_index + self.deque.len();
+        for fault in &self.deque {
+            s.push_str(&format!("{}: {}\n", index, fault.line()));
+            index -= 1;
+        }
+        s
+    }
+}
+
+impl AudioFault {
+    // Describe the fault on a single line.
+    fn line(&self) -> String {
+        let origin = match self.sound {
+            None => "final output".to_string(),
+            Some((id, Some(ref name))) => format!("sound {:?} of \"{}\"", id, name),
+            Some((id, None)) => format!("sound {:?}", id),
+        };
+        format!("{} silenced {} in {}", self.time, self.fault, origin)
+    }
+}
+
 impl<T> Deref for Log<T> {
     type Target = VecDeque<T>;
     fn deref(&self) -> &Self::Target {
@@ -810,12 +1391,14 @@ impl ActiveSound {
         pos: audio::sound::Position,
         channels: usize,
         normalised_progress: Option<f64>,
+        remaining: Option<Ms>,
     ) -> Self {
         ActiveSound {
             source_id,
             position: pos,
             channels: (0..channels).map(|_| ChannelLevels::default()).collect(),
             normalised_progress,
+            remaining,
         }
     }
 }
@@ -830,6 +1413,11 @@ fn images_directory(assets: &Path) -> PathBuf {
     assets.join("images")
 }
 
+/// The directory in which telemetry is written upon an audio under-run.
+fn telemetry_directory(assets: &Path) -> PathBuf {
+    assets.join("telemetry")
+}
+
 // A unique ID for each widget in the GUI.
 widget_ids! {
     pub struct Ids {
@@ -851,30 +1439,139 @@ widget_ids! {
         project_editor_none,
         project_editor_list,
         project_editor_copy,
+        project_editor_vary,
         project_editor_save,
+        project_editor_changes,
+        project_editor_changes_canvas,
+        project_editor_changes_text,
+        project_editor_changes_scrollbar,
+        project_editor_history,
+        project_editor_history_text,
+        project_editor_history_list,
+        project_editor_export,
+        project_editor_import_layout,
+        project_editor_import_content,
+        project_editor_import_text,
+        project_editor_import_list,
+        project_editor_lock_text,
+        project_editor_conflict_text,
+        project_editor_conflict_overwrite,
+        project_editor_conflict_save_as,
         project_editor_remove,
         // Master control settings.
         master,
         master_peak_meter,
+        master_peak_hold,
+        master_meter_text,
+        master_meter_scale,
+        master_meter_peak_hold,
+        master_meter_attack,
+        master_meter_release,
+        master_meter_rms_integration,
+        master_sound_widgets_label,
+        master_sound_widgets_scale,
+        master_sound_widgets_hover_card,
         master_volume,
         master_realtime_source_latency,
         master_dbap_rolloff,
         master_proximity_limit,
+        master_reverb_level,
+        master_reverb_decay,
+        master_reverb_damping,
+        master_limiter_enabled,
+        master_limiter_threshold,
+        master_limiter_release,
+        master_soundscape_tick_rate,
+        master_soundscape_tick_jitter,
+        master_tempo_enabled,
+        master_tempo_bpm,
+        master_tempo_quantum,
+        master_tempo_link,
+        master_tempo_beats_per_bar,
+        master_tempo_link_status,
+        master_bass_crossover,
+        master_bass_sub_dbap,
+        master_ambisonics_mode,
+        master_ambisonics_order,
+        master_ambisonics_record,
+        master_binaural_enabled,
+        master_binaural_output,
+        master_binaural_facing,
+        master_binaural_gain,
+        master_distance_reference,
+        master_distance_rolloff,
+        master_distance_air_absorption,
+        master_headroom_enabled,
+        master_headroom_law,
+        master_headroom_max_reduction,
+        master_blackout_button,
+        master_blackout_fade_out,
+        master_blackout_hold,
+        master_blackout_fade_in,
+        master_blackout_schedule_text,
+        master_blackout_schedule,
+        master_time_zone_text,
+        master_time_zone,
+        master_output_record,
+        master_reference_kind,
+        master_reference_level,
+        master_gain_reduction_bg,
+        master_gain_reduction_meter,
+        master_gain_reduction_text,
+        master_dsp_load_bg,
+        master_dsp_load_meter,
+        master_dsp_load_text,
+        master_dsp_load_reset,
+        master_loudness_bg,
+        master_loudness_text,
+        master_loudness_reset,
+        master_loudness_log,
         // OSC input log.
         osc_in_log,
         osc_in_log_text,
         osc_in_log_scrollbar_y,
         osc_in_log_scrollbar_x,
+        // OSC namespace.
+        osc_namespace,
+        osc_namespace_text,
+        osc_namespace_scrollbar_y,
+        osc_namespace_scrollbar_x,
+        // Source dependencies.
+        source_dependencies,
+        source_dependencies_canvas,
+        source_dependencies_text,
+        source_dependencies_scrollbar,
+        source_dependencies_remove_dangling,
         // OSC output log.
         osc_out_log,
         osc_out_log_text,
         osc_out_log_scrollbar_y,
         osc_out_log_scrollbar_x,
+        // Audio settings.
+        audio_settings,
+        audio_settings_text,
+        audio_settings_sample_rate,
+        audio_settings_frames_per_buffer,
+        audio_settings_word_length,
+        audio_settings_dither,
+        audio_settings_apply,
+        // OSC Composer.
+        osc_composer,
+        osc_composer_none,
+        osc_composer_target_list,
+        osc_composer_addr_text_box,
+        osc_composer_args_text_box,
+        osc_composer_send,
         // Control Log.
         control_log,
         control_log_text,
         control_log_scrollbar_y,
         control_log_scrollbar_x,
+        // Audio Fault Log.
+        fault_log,
+        fault_log_text,
+        fault_log_scrollbar_y,
+        fault_log_scrollbar_x,
         // Installation Editor.
         installation_editor,
         installation_editor_none,
@@ -891,9 +1588,76 @@ widget_ids! {
         installation_editor_osc_text,
         installation_editor_osc_ip_text_box,
         installation_editor_osc_address_text_box,
+        installation_editor_osc_capture,
+        installation_editor_osc_dry_run,
         installation_editor_soundscape_canvas,
         installation_editor_soundscape_text,
         installation_editor_soundscape_simultaneous_sounds_slider,
+        installation_editor_soundscape_reserved_text,
+        installation_editor_soundscape_reserved_feature,
+        installation_editor_soundscape_reserved_interactive,
+        installation_editor_soundscape_reserved_announcement,
+        installation_editor_soundscape_schedule_text,
+        installation_editor_soundscape_schedule,
+        installation_editor_soundscape_area_margin,
+        installation_editor_osc_input_canvas,
+        installation_editor_osc_input_text,
+        installation_editor_osc_input_prefix,
+        installation_editor_reverb_canvas,
+        installation_editor_volume_canvas,
+        installation_editor_volume_text,
+        installation_editor_volume_mute,
+        installation_editor_volume_gain,
+        installation_editor_volume_fade,
+        installation_editor_ducking_canvas,
+        installation_editor_ducking_text,
+        installation_editor_ducking_enabled,
+        installation_editor_ducking_trigger,
+        installation_editor_ducking_depth,
+        installation_editor_ducking_attack,
+        installation_editor_ducking_release,
+        installation_editor_sensor_canvas,
+        installation_editor_sensor_text,
+        installation_editor_sensor_enabled,
+        installation_editor_sensor_input,
+        installation_editor_sensor_gain,
+        installation_editor_activity_canvas,
+        installation_editor_activity_text,
+        installation_editor_activity_enabled,
+        installation_editor_activity_drives_intensity,
+        installation_editor_activity_input,
+        installation_editor_activity_curve,
+        installation_editor_activity_density,
+        installation_editor_activity_intensity,
+        installation_editor_walk_test_canvas,
+        installation_editor_walk_test_text,
+        installation_editor_walk_test_button,
+        installation_editor_walk_test_interval,
+        installation_editor_reverb_text,
+        installation_editor_reverb_level,
+        installation_editor_reverb_decay,
+        installation_editor_reverb_damping,
+        interaction_editor,
+        interaction_editor_none,
+        interaction_editor_list,
+        interaction_editor_add,
+        interaction_editor_remove,
+        interaction_editor_selected_canvas,
+        interaction_editor_name,
+        interaction_editor_osc_prefix_text,
+        interaction_editor_osc_prefix,
+        interaction_editor_transform_text,
+        interaction_editor_transform,
+        interaction_editor_smoothing_text,
+        interaction_editor_smoothing_time,
+        interaction_editor_smoothing_max_jump,
+        interaction_editor_smoothing_max_rejections,
+        interaction_editor_track_timeout,
+        interaction_editor_calibrate,
+        interaction_editor_calibrate_clear,
+        interaction_editor_transform_reset,
+        interaction_editor_simulate,
+        interaction_editor_info,
         // Speaker Editor.
         speaker_editor,
         speaker_editor_no_speakers,
@@ -904,8 +1668,30 @@ widget_ids! {
         speaker_editor_selected_none,
         speaker_editor_selected_name,
         speaker_editor_selected_channel,
+        speaker_editor_selected_trim,
+        speaker_editor_selected_delay,
+        speaker_editor_selected_subwoofer,
+        speaker_editor_selected_height,
+        speaker_editor_selected_directivity,
+        speaker_editor_selected_aim,
+        speaker_editor_selected_solo,
+        speaker_editor_selected_mute,
+        speaker_editor_selected_eq_bg,
+        speaker_editor_selected_eq_curve,
+        speaker_editor_selected_eq_bands[],
+        speaker_editor_selected_eq_hz,
+        speaker_editor_selected_eq_db,
+        speaker_editor_selected_eq_q,
         speaker_editor_selected_position,
         speaker_editor_selected_installations_canvas,
+        speaker_editor_test_signal_canvas,
+        speaker_editor_test_signal_text,
+        speaker_editor_test_signal_kind,
+        speaker_editor_test_signal_level,
+        speaker_editor_test_signal_play,
+        speaker_editor_test_signal_cycle,
+        speaker_editor_calibration_input,
+        speaker_editor_calibrate,
         speaker_editor_selected_installations_text,
         speaker_editor_selected_installations_ddl,
         speaker_editor_selected_installations_list,
@@ -913,19 +1699,77 @@ widget_ids! {
         // Audio Sources.
         soundscape_editor,
         soundscape_editor_is_playing,
+        soundscape_editor_intensity,
+        soundscape_editor_scenes_canvas,
+        soundscape_editor_scenes_text,
+        soundscape_editor_scenes_list,
+        soundscape_editor_scenes_add,
+        soundscape_editor_scenes_cue,
+        soundscape_editor_scenes_remove,
+        soundscape_editor_scenes_crossfade,
+        soundscape_editor_scenes_schedule_text,
+        soundscape_editor_scenes_schedule,
         soundscape_editor_group_canvas,
         soundscape_editor_group_text,
         soundscape_editor_group_add,
         soundscape_editor_group_none,
         soundscape_editor_group_list,
         soundscape_editor_group_remove,
+        soundscape_editor_group_drag_handle,
         soundscape_editor_selected_canvas,
         soundscape_editor_selected_text,
         soundscape_editor_selected_name,
+        soundscape_editor_selected_color,
+        soundscape_editor_selected_solo,
+        soundscape_editor_selected_mute,
         soundscape_editor_occurrence_rate_text,
         soundscape_editor_occurrence_rate_slider,
         soundscape_editor_simultaneous_sounds_text,
         soundscape_editor_simultaneous_sounds_slider,
+        soundscape_editor_min_separation_text,
+        soundscape_editor_min_separation,
+        soundscape_editor_intensity_text,
+        soundscape_editor_intensity_rate,
+        soundscape_editor_intensity_simultaneous,
+        soundscape_editor_intensity_gain,
+        soundscape_editor_intensity_curve,
+        soundscape_editor_schedule_text,
+        soundscape_editor_schedule,
+        soundscape_editor_transitions_text,
+        soundscape_editor_transition_from,
+        soundscape_editor_transition_to,
+        soundscape_editor_transition_weight,
+        soundscape_editor_transitions_summary,
+        soundscape_editor_modulation_text,
+        soundscape_editor_modulation_rate_toggle,
+        soundscape_editor_modulation_rate_range,
+        soundscape_editor_modulation_rate_period,
+        soundscape_editor_modulation_spread_toggle,
+        soundscape_editor_modulation_spread_range,
+        soundscape_editor_modulation_spread_period,
+        soundscape_editor_modulation_volume_toggle,
+        soundscape_editor_modulation_volume_range,
+        soundscape_editor_modulation_volume_period,
+        soundscape_editor_scene_level_text,
+        soundscape_editor_scene_level,
+        soundscape_editor_heatmap_text,
+        soundscape_editor_heatmap_paint,
+        soundscape_editor_heatmap_clear,
+        soundscape_editor_heatmap_weight,
+        soundscape_editor_heatmap_radius,
+        // Soundscape simulation.
+        soundscape_simulation,
+        soundscape_simulation_run,
+        soundscape_simulation_text,
+        soundscape_simulation_spawns_text,
+        soundscape_simulation_spawns_bg,
+        soundscape_simulation_spawns_plot,
+        soundscape_simulation_sounds_text,
+        soundscape_simulation_sounds_bg,
+        soundscape_simulation_sounds_plots[],
+        soundscape_simulation_summary_canvas,
+        soundscape_simulation_summary_text,
+        soundscape_simulation_summary_scrollbar,
         // Audio Sources.
         source_editor,
         source_editor_no_sources,
@@ -954,12 +1798,42 @@ widget_ids! {
         source_editor_selected_soundscape_attack_duration_slider,
         source_editor_selected_soundscape_release_duration_text,
         source_editor_selected_soundscape_release_duration_slider,
+        source_editor_selected_soundscape_effect_randomisation_text,
+        source_editor_selected_soundscape_effect_randomisation_cutoff,
+        source_editor_selected_soundscape_effect_randomisation_eq,
+        source_editor_selected_soundscape_follow_text,
+        source_editor_selected_soundscape_follow_toggle,
+        source_editor_selected_soundscape_follow_max,
+        source_editor_selected_soundscape_follow_spawn_delay,
+        source_editor_selected_soundscape_follow_release_delay,
+        source_editor_selected_soundscape_priority_text,
+        source_editor_selected_soundscape_priority,
+        source_editor_selected_soundscape_selection_text,
+        source_editor_selected_soundscape_weight,
+        source_editor_selected_soundscape_no_repeat_spawns,
+        source_editor_selected_soundscape_no_repeat_duration,
+        source_editor_selected_soundscape_cooldown,
+        source_editor_selected_soundscape_follow_ups_text,
+        source_editor_selected_soundscape_follow_ups_source,
+        source_editor_selected_soundscape_follow_ups_probability,
+        source_editor_selected_soundscape_follow_ups_delay,
+        source_editor_selected_soundscape_follow_ups_radius,
+        source_editor_selected_soundscape_day_gain_text,
+        source_editor_selected_soundscape_day_gain,
+        source_editor_selected_soundscape_day_gain_continuous,
         source_editor_selected_soundscape_groups_text,
         source_editor_selected_soundscape_groups_list,
         source_editor_selected_soundscape_movement_text,
         source_editor_selected_soundscape_movement_mode_list,
         source_editor_selected_soundscape_movement_generative_list,
         source_editor_selected_soundscape_movement_fixed_point,
+        source_editor_selected_soundscape_movement_path_draw,
+        source_editor_selected_soundscape_movement_path_undo,
+        source_editor_selected_soundscape_movement_path_clear,
+        source_editor_selected_soundscape_movement_path_loop,
+        source_editor_selected_soundscape_movement_path_smooth,
+        source_editor_selected_soundscape_movement_path_speed,
+        source_editor_selected_soundscape_movement_path_text,
         source_editor_selected_soundscape_movement_agent_max_speed_text,
         source_editor_selected_soundscape_movement_agent_max_speed_slider,
         source_editor_selected_soundscape_movement_agent_max_force_text,
@@ -981,19 +1855,37 @@ widget_ids! {
         source_editor_selected_wav_canvas,
         source_editor_selected_wav_text,
         source_editor_selected_wav_data,
+        source_editor_selected_wav_relink,
         source_editor_selected_wav_loop_toggle,
+        source_editor_selected_wav_loop_region,
+        source_editor_selected_wav_loop_crossfade,
+        source_editor_selected_wav_channel_map_text,
+        source_editor_selected_wav_channel_map,
+        source_editor_selected_wav_normalise,
+        source_editor_selected_wav_variations_text,
+        source_editor_selected_wav_variations_add,
+        source_editor_selected_wav_variations_selection,
+        source_editor_selected_wav_variations_list,
+        source_editor_selected_wav_variations_remove,
         source_editor_selected_wav_playback_text,
         source_editor_selected_wav_playback_list,
         source_editor_selected_realtime_canvas,
         source_editor_selected_realtime_text,
         source_editor_selected_realtime_duration,
+        source_editor_selected_realtime_device_text,
+        source_editor_selected_realtime_device,
         source_editor_selected_realtime_start_channel,
         source_editor_selected_realtime_end_channel,
+        source_editor_selected_realtime_capture,
+        source_editor_selected_realtime_capture_add,
+        source_editor_selected_realtime_capture_discard,
         source_editor_selected_common_canvas,
         source_editor_selected_volume_text,
         source_editor_selected_volume_slider,
         source_editor_selected_solo,
         source_editor_selected_mute,
+        source_editor_selected_enabled,
+        source_editor_selected_height,
         source_editor_selected_channel_layout_text,
         source_editor_selected_channel_layout_spread,
         source_editor_selected_channel_layout_rotation,
@@ -1001,6 +1893,22 @@ widget_ids! {
         source_editor_selected_channel_layout_spread_circle,
         source_editor_selected_channel_layout_channels[],
         source_editor_selected_channel_layout_channel_labels[],
+        source_editor_selected_effects_canvas,
+        source_editor_selected_effects_text,
+        source_editor_selected_effects_high_pass_toggle,
+        source_editor_selected_effects_high_pass_slider,
+        source_editor_selected_effects_low_pass_toggle,
+        source_editor_selected_effects_low_pass_slider,
+        source_editor_selected_effects_eq_text,
+        source_editor_selected_effects_eq_low,
+        source_editor_selected_effects_eq_mid,
+        source_editor_selected_effects_eq_high,
+        source_editor_selected_effects_reverb_send,
+        source_editor_selected_effects_distance_model,
+        source_editor_selected_effects_dbap_rolloff_toggle,
+        source_editor_selected_effects_dbap_rolloff_slider,
+        source_editor_selected_effects_speakers_include,
+        source_editor_selected_effects_speakers_exclude,
         source_editor_preview_canvas,
         source_editor_preview_text,
         source_editor_preview_one_shot,
@@ -1010,10 +1918,30 @@ widget_ids! {
         floorplan_canvas,
         floorplan,
         floorplan_project_name,
+        floorplan_installation_areas[],
+        floorplan_heatmap_cells[],
         floorplan_speakers[],
         floorplan_speaker_labels[],
+        floorplan_speaker_heights[],
+        floorplan_speaker_aims[],
         floorplan_sounds[],
+        floorplan_interactions[],
+        floorplan_source_path,
+        floorplan_source_path_points[],
+        floorplan_simulated_visitor,
+        floorplan_binaural_listener,
+        floorplan_binaural_listener_facing,
         floorplan_channel_to_speaker_lines[],
+        floorplan_touch_mode,
+        floorplan_transform_toggle,
+        floorplan_transform_rotate,
+        floorplan_transform_mirror,
+        floorplan_transform_offset_x,
+        floorplan_transform_offset_y,
+        floorplan_export_png,
+        floorplan_export_timelapse,
+        floorplan_context_menu,
+        floorplan_context_menu_options[],
 
         // Text drawn in the CPU-saving mode.
         cpu_saving_mode,
@@ -1021,6 +1949,68 @@ widget_ids! {
     }
 }
 
+// Apply input from a game controller to the selected speaker or source preview and the camera.
+//
+// The selected speaker takes priority over the source preview when nudging.
+fn apply_gamepad_message(
+    msg: gamepad::Message,
+    channels: &Channels,
+    project: &mut Project,
+    project_state: &mut ProjectState,
+) {
+    let (nudge_m, analog) = match msg {
+        gamepad::Message::Step(step) => {
+            let x = Metres(step[0] as f64 * gamepad::STEP_METRES);
+            let y = Metres(step[1] as f64 * gamepad::STEP_METRES);
+            ((x, y), None)
+        },
+        gamepad::Message::Analog(analog) => {
+            let scale = gamepad::NUDGE_METRES_PER_SEC * analog.secs as f64;
+            let x = Metres(analog.nudge[0] as f64 * scale);
+            let y = Metres(analog.nudge[1] as f64 * scale);
+            ((x, y), Some(analog))
+        },
+    };
+
+    // Nudge the selected speaker, or otherwise the source preview.
+    if nudge_m != (Metres(0.0), Metres(0.0)) {
+        let selected_speaker = project_state.speaker_editor.selected.and_then(|i| {
+            speaker_editor::sorted_speakers_vec(&project.state.speakers).get(i).cloned()
+        });
+        if let Some(speaker_id) = selected_speaker {
+            let speaker = project.state.speakers.get_mut(&speaker_id).unwrap();
+            let p = speaker.audio.point;
+            let new_p = Point2 { x: p.x + nudge_m.0, y: p.y + nudge_m.1 };
+            speaker_editor::set_speaker_point(speaker_id, speaker, new_p, channels);
+        } else if let (Some((_, sound_id)), Some(p)) = (
+            project_state.source_editor.preview.current,
+            project_state.source_editor.preview.point,
+        ) {
+            let new_p = Point2 { x: p.x + nudge_m.0, y: p.y + nudge_m.1 };
+            project_state.source_editor.preview.point = Some(new_p);
+            channels
+                .audio_output
+                .send(move |audio| {
+                    audio.update_sound(&sound_id, move |s| {
+                        s.position.point = new_p;
+                    });
+                })
+                .expect("failed to send sound position to audio output thread");
+        }
+    }
+
+    // Pan and zoom the camera. The zoom is clamped when the floorplan is next instantiated.
+    if let Some(analog) = analog {
+        let secs = analog.secs as f64;
+        let camera = &mut project.state.camera;
+        let pan_x = analog.pan[0] as f64 * gamepad::PAN_SCALAR_PER_SEC * secs;
+        let pan_y = analog.pan[1] as f64 * gamepad::PAN_SCALAR_PER_SEC * secs;
+        camera.position.x += camera.scalar_to_metres(pan_x);
+        camera.position.y += camera.scalar_to_metres(pan_y);
+        camera.zoom *= 1.0 + analog.zoom as f64 * gamepad::ZOOM_PER_SEC * secs;
+    }
+}
+
 // Begin building a `CollapsibleArea` for the sidebar.
 pub fn collapsible_area(
     is_open: bool,
@@ -1061,6 +2051,21 @@ pub fn hz_label(hz: f64) -> String {
     }
 }
 
+// The installations whose OSC input prefix matches the given prefix.
+fn installations_bound_to_prefix(project: &Project, prefix: &str) -> Vec<installation::Id> {
+    let prefix = installation::normalise_osc_prefix(prefix);
+    project
+        .state
+        .installations
+        .iter()
+        .filter(|&(_, ref inst)| {
+            let inst_prefix = &inst.osc_input_prefix;
+            !inst_prefix.is_empty() && installation::normalise_osc_prefix(inst_prefix) == prefix
+        })
+        .map(|(&id, _)| id)
+        .collect()
+}
+
 // A function to simplify the creation of a label for a duration slider.
 pub fn duration_label(ms: &Ms) -> String {
     // Playback duration.
@@ -1089,6 +2094,12 @@ pub fn duration_label(ms: &Ms) -> String {
     }
 }
 
+// A compact `m:ss` label for the remaining duration of an active sound.
+fn remaining_label(ms: &Ms) -> String {
+    let total_secs = (ms.ms() / SEC_MS).ceil().max(0.0) as u64;
+    format!("{}:{:02}", total_secs / 60, total_secs % 60)
+}
+
 pub const TEXT_PAD: Scalar = 20.0;
 pub const ITEM_HEIGHT: Scalar = 30.0;
 pub const SMALL_FONT_SIZE: FontSize = 12;
@@ -1111,24 +2122,45 @@ fn set_side_menu_widgets(
         // Installation Editor - for editing installation-specific data.
         last_area_id = installation_editor::set(last_area_id, gui, project, project_state);
 
+        // Interaction Editor - for mapping tracking system positions onto the floorplan.
+        last_area_id = interaction_editor::set(last_area_id, gui, project, project_state);
+
         // Speaker Editor - for adding, editing and removing speakers.
         last_area_id = speaker_editor::set(last_area_id, gui, project, project_state);
 
         // Soundscape Editor - for playing/pausing and adding, editing and removing groups.
         last_area_id = soundscape_editor::set(last_area_id, gui, project, project_state);
 
+        // Soundscape Simulation - for previewing a day of the soundscape at many times real-time.
+        last_area_id = soundscape_simulation::set(last_area_id, gui, project, project_state);
+
         // For adding, changing and removing audio sources.
         last_area_id = source_editor::set(last_area_id, gui, project, project_state);
 
+        // The installations, groups and speakers referred to by sources.
+        last_area_id = source_dependencies::set(last_area_id, gui, project, project_state);
+
         // The log of received controls.
         last_area_id = control_log::set(last_area_id, gui, project);
 
         // The log of received OSC messages.
         last_area_id = osc_in_log::set(last_area_id, gui, project);
+
+        // For composing and sending arbitrary OSC messages to installation computers.
+        last_area_id = osc_composer::set(last_area_id, gui, project, project_state);
+
+        // Every OSC address to which the server responds and that it emits.
+        last_area_id = osc_namespace::set(last_area_id, gui, project);
     }
 
     // The log of sent OSC messages.
-    osc_out_log::set(last_area_id, gui);
+    last_area_id = osc_out_log::set(last_area_id, gui);
+
+    // The log of corrupt audio silenced on the output thread.
+    last_area_id = fault_log::set(last_area_id, gui);
+
+    // The sample rate and buffer size of the audio streams.
+    audio_settings::set(last_area_id, gui);
 }
 
 // Update all widgets in the GUI with the given state.
@@ -1266,15 +2298,21 @@ fn set_widgets(
     let metres_from_floorplan_pixels = |px| Metres(px / floorplan_pixels_per_metre);
     let metres_to_floorplan_pixels = |Metres(m)| m * floorplan_pixels_per_metre;
 
-    let floorplan_w_metres = metres_from_floorplan_pixels(gui.images.floorplan.width);
-    let floorplan_h_metres = metres_from_floorplan_pixels(gui.images.floorplan.height);
+    let floorplan_w_metres = metres_from_floorplan_pixels(gui.images.floorplan.image.width);
+    let floorplan_h_metres = metres_from_floorplan_pixels(gui.images.floorplan.image.height);
 
     // The amount which the image must be scaled to fill the floorplan_canvas while preserving
     // aspect ratio.
-    let full_scale_w = floorplan_canvas_w / gui.images.floorplan.width;
-    let full_scale_h = floorplan_canvas_h / gui.images.floorplan.height;
-    let floorplan_w = full_scale_w * gui.images.floorplan.width;
-    let floorplan_h = full_scale_h * gui.images.floorplan.height;
+    let full_scale_w = floorplan_canvas_w / gui.images.floorplan.image.width;
+    let full_scale_h = floorplan_canvas_h / gui.images.floorplan.image.height;
+    let floorplan_w = full_scale_w * gui.images.floorplan.image.width;
+    let floorplan_h = full_scale_h * gui.images.floorplan.image.height;
+
+    // Vector floorplans remain sharp when zoomed beyond their original resolution.
+    let max_zoom = match gui.images.floorplan.is_vector() {
+        true => floorplan::MAX_VECTOR_ZOOM,
+        false => 1.0,
+    };
 
     // If the floorplan was scrolled, adjust the camera zoom.
     let total_scroll = gui.widget_input(gui.ids.floorplan)
@@ -1282,16 +2320,57 @@ fn set_widgets(
         .fold(0.0, |acc, scroll| acc + scroll.y);
     project.state.camera.zoom = (project.state.camera.zoom - total_scroll / 200.0)
         .max(full_scale_w.min(full_scale_h))
-        .min(1.0);
+        .min(max_zoom);
 
-    // Move the camera by clicking with the left mouse button and dragging.
-    let total_drag = gui.widget_input(gui.ids.floorplan)
-        .drags()
-        .left()
-        .map(|drag| drag.delta_xy)
-        .fold([0.0, 0.0], |acc, dt| [acc[0] + dt[0], acc[1] + dt[1]]);
-    project.state.camera.position.x -= project.state.camera.scalar_to_metres(total_drag[0]);
-    project.state.camera.position.y -= project.state.camera.scalar_to_metres(total_drag[1]);
+    // Move the camera by clicking with the left mouse button and dragging, or paint the selected
+    // soundscape group's heatmap while painting.
+    //
+    // While multiple touches are down, ignore any drags that the OS emulates from them.
+    let drags: Vec<_> = if gui.state.touches.count_on(gui.ids.floorplan) > 1 {
+        vec![]
+    } else {
+        gui.widget_input(gui.ids.floorplan)
+            .drags()
+            .left()
+            .map(|drag| (drag.delta_xy, drag.to))
+            .collect()
+    };
+    if soundscape_editor::is_painting(&project_state.soundscape_editor) {
+        let points: Vec<_> = drags
+            .iter()
+            .map(|&(_, to)| {
+                let camera = &project.state.camera;
+                let x = camera.position.x + camera.scalar_to_metres(to[0]);
+                let y = camera.position.y + camera.scalar_to_metres(to[1]);
+                pt2(x, y)
+            })
+            .collect();
+        if !points.is_empty() {
+            soundscape_editor::floorplan_painted(
+                &mut project.state.soundscape_groups,
+                &project_state.soundscape_editor,
+                gui.channels,
+                &points,
+            );
+        }
+    } else {
+        let total_drag = drags
+            .iter()
+            .fold([0.0, 0.0], |acc, &(dt, _)| [acc[0] + dt[0], acc[1] + dt[1]]);
+        project.state.camera.position.x -= project.state.camera.scalar_to_metres(total_drag[0]);
+        project.state.camera.position.y -= project.state.camera.scalar_to_metres(total_drag[1]);
+    }
+
+    // In touch mode, pinch to zoom and drag with two fingers to move the camera.
+    if *gui.touch_mode {
+        if let Some(pinch) = gui.state.touches.pinch(gui.ids.floorplan) {
+            project.state.camera.zoom = (project.state.camera.zoom * pinch.scale)
+                .max(full_scale_w.min(full_scale_h))
+                .min(max_zoom);
+            project.state.camera.position.x -= project.state.camera.scalar_to_metres(pinch.pan[0]);
+            project.state.camera.position.y -= project.state.camera.scalar_to_metres(pinch.pan[1]);
+        }
+    }
 
     // The part of the image visible from the camera.
     let visible_w_m = project.state.camera.scalar_to_metres(floorplan_canvas_w);
@@ -1327,19 +2406,47 @@ fn set_widgets(
     let visible_h = metres_to_floorplan_pixels(visible_h_m);
     let visible_rect = ui::Rect::from_xy_dim([visible_x, visible_y], [visible_w, visible_h]);
 
-    // If the left mouse button was clicked on the floorplan, deselect the speaker.
-    if gui.widget_input(gui.ids.floorplan)
-        .clicks()
-        .left()
-        .next()
-        .is_some()
-    {
-        project_state.speaker_editor.selected = None;
+    // If the left mouse button was clicked on the floorplan, either add an interaction
+    // calibration point, add a point to the selected source's path, paint the selected group's
+    // heatmap or deselect the speaker.
+    let floorplan_click = gui.widget_input(gui.ids.floorplan).clicks().left().next();
+    if let Some(click) = floorplan_click {
+        let position = {
+            let camera = &project.state.camera;
+            let x = camera.position.x + camera.scalar_to_metres(click.xy[0]);
+            let y = camera.position.y + camera.scalar_to_metres(click.xy[1]);
+            pt2(x, y)
+        };
+        let calibrated = interaction_editor::floorplan_clicked(
+            project,
+            &mut project_state.interaction_editor,
+            position,
+        );
+        let drawn = !calibrated && source_editor::floorplan_clicked(
+            &mut project.state.sources.map,
+            &project_state.source_editor,
+            gui.channels,
+            position,
+        );
+        let painted = !calibrated && !drawn && soundscape_editor::floorplan_painted(
+            &mut project.state.soundscape_groups,
+            &project_state.soundscape_editor,
+            gui.channels,
+            &[position],
+        );
+        if !calibrated && !drawn && !painted {
+            project_state.speaker_editor.selected = None;
+        }
+        project_state.floorplan_context_menu = None;
     }
 
-    // Display the floorplan.
-    widget::Image::new(gui.images.floorplan.id)
-        .source_rectangle(visible_rect)
+    // Display the floorplan, preferring a rendering of vector floorplans at the current view.
+    let vector_view = gui.images.floorplan.view(visible_rect, [floorplan_w, floorplan_h]);
+    let floorplan_image = match vector_view {
+        Some(id) => widget::Image::new(id),
+        None => widget::Image::new(gui.images.floorplan.image.id).source_rectangle(visible_rect),
+    };
+    floorplan_image
         .w_h(floorplan_w, floorplan_h)
         .middle_of(gui.ids.floorplan_canvas)
         .set(gui.ids.floorplan, gui);
@@ -1350,6 +2457,112 @@ fn set_widgets(
         .color(ui::color::BLACK)
         .set(gui.ids.floorplan_project_name, gui);
 
+    // A button for toggling touch mode, as touch-only machines may have no keyboard.
+    let touch_mode_button_h = match *gui.touch_mode {
+        true => touch::ITEM_HEIGHT,
+        false => ITEM_HEIGHT,
+    };
+    for new_touch_mode in widget::Toggle::new(*gui.touch_mode)
+        .w_h(touch_mode_button_h * 3.0, touch_mode_button_h)
+        .top_right_with_margin_on(gui.ids.floorplan_canvas, 20.0)
+        .label("TOUCH")
+        .label_font_size(SMALL_FONT_SIZE)
+        .label_color(color::WHITE)
+        .color(DARK_A)
+        .set(gui.ids.floorplan_touch_mode, gui)
+    {
+        *gui.touch_mode = new_touch_mode;
+    }
+
+    // A button for revealing the controls for rotating, mirroring and offsetting the floorplan.
+    for is_open in widget::Toggle::new(gui.state.is_open.floorplan_transform)
+        .w_h(touch_mode_button_h * 3.0, touch_mode_button_h)
+        .left_from(gui.ids.floorplan_touch_mode, 10.0)
+        .label("FLOORPLAN")
+        .label_font_size(SMALL_FONT_SIZE)
+        .label_color(color::WHITE)
+        .color(DARK_A)
+        .set(gui.ids.floorplan_transform_toggle, gui)
+    {
+        gui.state.is_open.floorplan_transform = is_open;
+    }
+
+    if gui.state.is_open.floorplan_transform {
+        set_floorplan_transform_widgets(gui, &mut project.state.floorplan, touch_mode_button_h);
+    }
+
+    // Buttons for exporting the current view to a PNG or recording a time-lapse of it.
+    let export_view = |scale: f64| floorplan_export::View {
+        rect: visible_rect,
+        size: [(floorplan_w * scale).round() as u32, (floorplan_h * scale).round() as u32],
+        pixels_per_metre: floorplan_pixels_per_metre,
+    };
+    for _click in widget::Button::new()
+        .w_h(touch_mode_button_h * 3.0, touch_mode_button_h)
+        .left_from(gui.ids.floorplan_transform_toggle, 10.0)
+        .label("PNG")
+        .label_font_size(SMALL_FONT_SIZE)
+        .label_color(color::WHITE)
+        .color(DARK_A)
+        .set(gui.ids.floorplan_export_png, gui)
+    {
+        let view = export_view(floorplan_export::PNG_SCALE);
+        let scene = floorplan_export_scene(project, &gui.audio_monitor);
+        let (exports, fonts) = (exports_directory(gui.assets), fonts_directory(gui.assets));
+        let name = &project.name;
+        let result = gui.images.floorplan
+            .render_view(&images_directory(gui.assets), view.rect, view.size)
+            .and_then(|bg| floorplan_export::export_png(&exports, &fonts, name, bg, view, &scene));
+        if let Err(err) = result {
+            eprintln!("failed to export the floorplan view: {}", err);
+        }
+    }
+
+    let timelapse_label = match gui.state.floorplan_timelapse {
+        Some(ref timelapse) => {
+            let secs = timelapse.elapsed().as_secs();
+            format!("STOP {}:{:02}", secs / 60, secs % 60)
+        },
+        None => "TIME-LAPSE".to_string(),
+    };
+    let is_recording = gui.state.floorplan_timelapse.is_some();
+    for record in widget::Toggle::new(is_recording)
+        .w_h(touch_mode_button_h * 4.0, touch_mode_button_h)
+        .left_from(gui.ids.floorplan_export_png, 10.0)
+        .label(&timelapse_label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .label_color(color::WHITE)
+        .color(DARK_A)
+        .set(gui.ids.floorplan_export_timelapse, gui)
+    {
+        if !record {
+            if let Some(timelapse) = gui.state.floorplan_timelapse.take() {
+                timelapse.finish();
+            }
+            continue;
+        }
+        let view = export_view(1.0);
+        let (exports, fonts) = (exports_directory(gui.assets), fonts_directory(gui.assets));
+        let name = &project.name;
+        let result = gui.images.floorplan
+            .render_view(&images_directory(gui.assets), view.rect, view.size)
+            .and_then(|bg| floorplan_export::Timelapse::start(&exports, &fonts, name, bg, view));
+        match result {
+            Ok(timelapse) => gui.state.floorplan_timelapse = Some(timelapse),
+            Err(err) => eprintln!("failed to begin the floorplan time-lapse: {}", err),
+        }
+    }
+
+    // Write the next frame of the time-lapse if one is due.
+    if gui.state.floorplan_timelapse.is_some() {
+        let scene = floorplan_export_scene(project, &gui.audio_monitor);
+        let timelapse = gui.state.floorplan_timelapse.as_mut().unwrap();
+        timelapse.update(&scene);
+        if timelapse.is_complete() {
+            gui.state.floorplan_timelapse.take().unwrap().finish();
+        }
+    }
+
     // Retrieve the absolute xy position of the floorplan as this will be useful for converting
     // absolute GUI values to metres and vice versa.
     let floorplan_xy = gui.rect_of(gui.ids.floorplan).unwrap().xy();
@@ -1362,6 +2575,13 @@ fn set_widgets(
     let radius_min = project.state.camera.metres_to_scalar(radius_min_m);
     let radius_max = project.state.camera.metres_to_scalar(radius_max_m);
 
+    // In touch mode, ensure widgets on the floorplan remain large enough to be touched.
+    let touch_mode = *gui.touch_mode;
+    let min_target_radius = match touch_mode {
+        true => touch::TARGET_RADIUS,
+        false => 0.0,
+    };
+
     fn x_position_metres_to_floorplan(x: Metres, cam: &Camera) -> Scalar {
         cam.metres_to_scalar(x - cam.position.x)
     }
@@ -1391,6 +2611,81 @@ fn set_widgets(
     //     Point2 { x, y }
     // };
 
+    // Draw the area of each installation beneath the speakers.
+    {
+        let Gui { ref mut ui, ref mut ids, .. } = *gui;
+        let camera = &project.camera;
+        let mut hulls = vec![];
+        for (id, installation) in project.installations.iter() {
+            let points = project
+                .speakers
+                .values()
+                .filter(|s| s.audio.installations.contains(id))
+                .map(|s| s.audio.point);
+            let margin = installation.soundscape.area_margin;
+            let area = soundscape::movement::Area::from_points(points, margin)
+                .filter(|area| area.hull.len() >= 3);
+            if let Some(area) = area {
+                hulls.push(area.hull);
+            }
+        }
+        if ids.floorplan_installation_areas.len() < hulls.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            ids.floorplan_installation_areas.resize(hulls.len(), id_gen);
+        }
+        for (i, hull) in hulls.into_iter().enumerate() {
+            let points = hull.into_iter().map(|p| {
+                let (x, y) = position_metres_to_gui(p, camera);
+                [x, y]
+            });
+            widget::Polygon::abs_fill(points)
+                .color(color::LIGHT_BLUE.alpha(0.15))
+                .parent(ids.floorplan)
+                .graphics_for(ids.floorplan)
+                .set(ids.floorplan_installation_areas[i], ui);
+        }
+    }
+
+    // Draw the painted cells of the selected group's heatmap while it is being painted, darker
+    // where sounds are less likely and brighter where they are more likely.
+    {
+        let Gui { ref mut ui, ref mut ids, .. } = *gui;
+        let groups = &project.soundscape_groups;
+        let heatmap = soundscape_editor::painted_heatmap(groups, &project_state.soundscape_editor);
+        if let Some(heatmap) = heatmap {
+            use soundscape::heatmap::{CELL_SIZE, DEFAULT_WEIGHT, MAX_WEIGHT};
+            let camera = &project.camera;
+            let side = camera.metres_to_scalar(CELL_SIZE);
+            let cells: Vec<_> = heatmap
+                .weights
+                .iter()
+                .enumerate()
+                .filter(|&(_, &weight)| weight != DEFAULT_WEIGHT)
+                .map(|(i, &weight)| (heatmap.cell_centre(i), weight))
+                .collect();
+            if ids.floorplan_heatmap_cells.len() < cells.len() {
+                let id_gen = &mut ui.widget_id_generator();
+                ids.floorplan_heatmap_cells.resize(cells.len(), id_gen);
+            }
+            for (i, (point, weight)) in cells.into_iter().enumerate() {
+                let (x, y) = position_metres_to_gui(point, camera);
+                let color = match weight < DEFAULT_WEIGHT {
+                    true => color::BLACK.alpha((DEFAULT_WEIGHT - weight) * 0.5),
+                    false => {
+                        let t = (weight - DEFAULT_WEIGHT) / (MAX_WEIGHT - DEFAULT_WEIGHT);
+                        color::LIGHT_ORANGE.alpha(t * 0.5)
+                    },
+                };
+                widget::Rectangle::fill([side, side])
+                    .x_y(x, y)
+                    .color(color)
+                    .parent(ids.floorplan)
+                    .graphics_for(ids.floorplan)
+                    .set(ids.floorplan_heatmap_cells[i], ui);
+            }
+        }
+    }
+
     {
         let Gui {
             ref mut ids,
@@ -1420,7 +2715,16 @@ fn set_widgets(
             let id_gen = &mut ui.widget_id_generator();
             ids.floorplan_speaker_labels.resize(num_speakers, id_gen);
         }
+        if ids.floorplan_speaker_heights.len() < num_speakers {
+            let id_gen = &mut ui.widget_id_generator();
+            ids.floorplan_speaker_heights.resize(num_speakers, id_gen);
+        }
+        if ids.floorplan_speaker_aims.len() < num_speakers {
+            let id_gen = &mut ui.widget_id_generator();
+            ids.floorplan_speaker_aims.resize(num_speakers, id_gen);
+        }
 
+        let any_soloed = speakers.values().any(|s| s.soloed);
         let sorted_speakers = speaker_editor::sorted_speakers_vec(speakers);
         for (i, speaker_id) in sorted_speakers.into_iter().enumerate() {
             let speaker = speakers.get_mut(&speaker_id).unwrap();
@@ -1447,25 +2751,7 @@ fn set_widgets(
                 let y = p.y + dragged_y_m;
                 let new_p = Point2 { x, y };
                 if p != new_p {
-                    // Update the local copy.
-                    speaker.audio.point = new_p;
-
-                    // Update the audio copy.
-                    let speaker_clone = speaker.audio.clone();
-                    channels
-                        .audio_output
-                        .send(move |audio| {
-                            audio.insert_speaker(speaker_id, speaker_clone);
-                        })
-                        .expect("failed to send updated speaker to audio output thread");
-
-                    // Update the soundscape copy.
-                    channels
-                        .soundscape
-                        .send(move |soundscape| {
-                            soundscape.update_speaker(&speaker_id, |s| s.point = new_p);
-                        })
-                        .expect("failed to send speaker update to soundscape thread");
+                    speaker_editor::set_speaker_point(speaker_id, speaker, new_p, channels);
                 }
                 new_p
             };
@@ -1483,11 +2769,23 @@ fn set_widgets(
                 project_state.speaker_editor.selected = Some(i);
             }
 
+            // Open the context menu if the speaker was long-pressed or right-clicked.
+            let long_pressed = touch_mode && state.touches.long_pressed(widget_id);
+            let right_clicked = ui.widget_input(widget_id).clicks().right().next().is_some();
+            if long_pressed || right_clicked {
+                let menu = FloorplanContextMenu { speaker: speaker_id, xy: [x, y] };
+                project_state.floorplan_context_menu = Some(menu);
+            }
+
             // Give some tactile colour feedback if the speaker is interacted with.
+            //
+            // Soloed speakers are yellow while silenced and unused channels are darkened.
             let color = if Some(i) == project_state.speaker_editor.selected {
                 color::BLUE
+            } else if speaker.soloed {
+                color::DARK_YELLOW
             } else {
-                if channel < state.audio_channels.output {
+                if channel < state.audio_channels.output && speaker.is_audible(any_soloed) {
                     color::DARK_RED
                 } else {
                     color::DARK_RED.with_luminance(0.15)
@@ -1504,6 +2802,7 @@ fn set_widgets(
 
             // Feed the RMS into the speaker's radius.
             let radius = radius_min + (radius_max - radius_min) * rms.powf(0.5) as f64;
+            let radius = radius.max(min_target_radius);
 
             // Display a circle for the speaker.
             widget::Circle::fill(radius)
@@ -1520,6 +2819,31 @@ fn set_widgets(
                 .font_size(font_size)
                 .graphics_for(widget_id)
                 .set(label_widget_id, ui);
+
+            // Indicate the elevation of any speaker raised above the floor.
+            let height = speaker.audio.height;
+            if height > Metres(0.0) {
+                let label = format!("{:.1}m", height.0);
+                widget::Text::new(&label)
+                    .x_y(x, y - radius - SMALL_FONT_SIZE as Scalar)
+                    .font_size(SMALL_FONT_SIZE)
+                    .color(color::LIGHT_CHARCOAL)
+                    .graphics_for(widget_id)
+                    .parent(ids.floorplan)
+                    .set(ids.floorplan_speaker_heights[i], ui);
+            }
+
+            // Indicate the aim of any directional speaker.
+            if speaker.audio.directivity != audio::speaker::Directivity::Omni {
+                let aim = speaker.audio.aim_radians;
+                let end = [x + aim.cos() * radius * 2.0, y + aim.sin() * radius * 2.0];
+                widget::Line::abs([x, y], end)
+                    .thickness(2.0)
+                    .color(color)
+                    .graphics_for(widget_id)
+                    .parent(ids.floorplan)
+                    .set(ids.floorplan_speaker_aims[i], ui);
+            }
         }
     }
 
@@ -1555,12 +2879,13 @@ fn set_widgets(
             let condition = (current, point, selected);
             let (spread_m, channel_radians, channel_count, position, color) = match condition {
                 (Some((_, id)), Some(point), Some(selected_id)) if id == sound_id => {
-                    let (spread, channel_radians, channel_count) = {
+                    let (spread, channel_radians, channel_count, height) = {
                         let source = &project.sources[&selected_id];
                         let spread = source.audio.spread;
                         let channel_radians = source.audio.channel_radians;
                         let channel_count = source.audio.channel_count();
-                        (spread, channel_radians, channel_count)
+                        let height = source.audio.height;
+                        (spread, channel_radians, channel_count, height)
                     };
 
                     // Determine how far the source preview has been dragged, if at all.
@@ -1596,6 +2921,7 @@ fn set_widgets(
                         audio::sound::Position {
                             point: new_p,
                             radians: 0.0,
+                            height,
                         }
                     };
 
@@ -1609,11 +2935,16 @@ fn set_widgets(
                 }
                 _ => {
                     // Find the source.
-                    let (&id, source) = project
+                    //
+                    // The source may have been removed while its sounds are fading out.
+                    let (&id, source) = match project
                         .sources
                         .iter()
                         .find(|&(&id, _)| id == active_sound.source_id)
-                        .expect("No source found for active sound");
+                    {
+                        Some(entry) => entry,
+                        None => continue,
+                    };
                     let spread = source.audio.spread;
                     let channel_radians = source.audio.channel_radians;
                     let channel_count = source.audio.channel_count();
@@ -1627,6 +2958,15 @@ fn set_widgets(
                         color::DARK_BLUE
                     };
 
+                    // Clicking the sound selects its source within the source editor.
+                    let clicked = ui.widget_input(sound_widget_id).clicks().left().next().is_some();
+                    if clicked && selected != Some(id) {
+                        state.is_open.source_editor = true;
+                        let editor = &mut project_state.source_editor;
+                        source_editor::select_source(editor, channels, id);
+                    }
+                    let selected = project_state.source_editor.selected;
+
                     // If the source editor is open and this sound is selected, highlight it.
                     if state.is_open.source_editor {
                         if let Some(selected_id) = selected {
@@ -1651,7 +2991,9 @@ fn set_widgets(
 
             let spread = project.camera.metres_to_scalar(spread_m);
             let side_m = custom_widget::sound::dimension_metres(0.0);
-            let side = project.state.camera.metres_to_scalar(side_m);
+            let settings = audio_monitor.sound_widget_settings;
+            let side = project.state.camera.metres_to_scalar(side_m) * settings.scale;
+            let side = side.max(min_target_radius * 2.0);
             let channel_amps = &channel_amplitudes[..channel_count];
             let installations = project.state
                 .sources
@@ -1659,6 +3001,11 @@ fn set_widgets(
                 .find(|&(&id, _)| id == active_sound.source_id)
                 .map(|(_, s)| s.audio.role.clone().into())
                 .unwrap_or(audio::sound::Installations::All);
+            let speaker_filter = project.state
+                .sources
+                .get(&active_sound.source_id)
+                .map(|s| s.audio.speaker_filter.clone())
+                .unwrap_or_default();
 
             // Determine the line colour by checking for interactions with the sound.
             let line_color = match ui.widget_input(sound_widget_id).mouse() {
@@ -1686,8 +3033,12 @@ fn set_widgets(
                 fn find_speakers_in_proximity(
                     // The location of the source channel.
                     point: &Point2<Metres>,
+                    // The height of the source above the floor.
+                    height: Metres,
                     // Installations that the current sound is applied to.
                     installations: &audio::sound::Installations,
+                    // The speakers from which the current sound may be heard.
+                    speaker_filter: &audio::speaker::Filter,
                     // All speakers.
                     speakers: &project::Speakers,
                     // The rolloff attenuation.
@@ -1703,19 +3054,24 @@ fn set_widgets(
 
                     let (ids, dbap_speakers): (Vec<audio::speaker::Id>, Vec<audio::dbap::Speaker>) = {
                         // The location of the sound.
-                        let point_f = Point2 {
+                        let point_f = Point3 {
                             x: point.x.0,
                             y: point.y.0,
+                            z: height.0,
                         };
 
                         let mut iter = speakers.iter();
                         iter.next()
                             .map(|(&id, speaker)| {
                                 // The function used to create the dbap speakers.
-                                let dbap_speaker = |speaker: &project::Speaker| -> audio::dbap::Speaker {
-                                    let speaker_f = Point2 {
+                                let dbap_speaker = |
+                                    id: audio::speaker::Id,
+                                    speaker: &project::Speaker,
+                                | -> audio::dbap::Speaker {
+                                    let speaker_f = Point3 {
                                         x: speaker.audio.point.x.0,
                                         y: speaker.audio.point.y.0,
+                                        z: speaker.audio.height.0,
                                     };
                                     let distance = audio::dbap::blurred_distance_2(
                                         point_f,
@@ -1725,14 +3081,16 @@ fn set_widgets(
                                     let weight = audio::speaker::dbap_weight(
                                         installations,
                                         &speaker.audio.installations,
-                                    );
+                                        speaker_filter,
+                                        &id,
+                                    ) * speaker.audio.directivity_gain(*point);
                                     audio::dbap::Speaker { distance, weight }
                                 };
 
-                                let init = (vec![id], vec![dbap_speaker(speaker)]);
+                                let init = (vec![id], vec![dbap_speaker(id, speaker)]);
                                 iter.fold(init, |(mut ids, mut speakers), (&id, s)| {
                                     ids.push(id);
-                                    speakers.push(dbap_speaker(s));
+                                    speakers.push(dbap_speaker(id, s));
                                     (ids, speakers)
                                 })
                             })
@@ -1751,7 +3109,9 @@ fn set_widgets(
 
                 find_speakers_in_proximity(
                     &channel_point_m,
+                    position.height,
                     &installations,
+                    &speaker_filter,
                     speakers,
                     project.master.dbap_rolloff_db,
                     &mut speakers_in_proximity,
@@ -1788,10 +3148,48 @@ fn set_widgets(
                 }
             }
 
+            // The optional label and hover card text for the sound.
+            let name = project.sources
+                .get(&active_sound.source_id)
+                .map(|source| &source.name[..])
+                .unwrap_or("");
+            let remaining = active_sound.remaining.as_ref().map(remaining_label);
+            let label = match settings.label {
+                custom_widget::sound::Label::None => None,
+                custom_widget::sound::Label::SourceName => Some(name.to_string()),
+                custom_widget::sound::Label::Remaining => remaining.clone(),
+                custom_widget::sound::Label::SourceNameAndRemaining => match remaining {
+                    Some(ref remaining) => Some(format!("{} ({})", name, remaining)),
+                    None => Some(name.to_string()),
+                },
+            };
+            let details = match settings.hover_card {
+                false => None,
+                true => {
+                    let mut details = format!(
+                        "{}\n{} channel(s)\nx: {:.2}m  y: {:.2}m  h: {:.2}m",
+                        name,
+                        channel_count,
+                        position.point.x.0,
+                        position.point.y.0,
+                        position.height.0,
+                    );
+                    if let Some(ref remaining) = remaining {
+                        details.push_str(&format!("\nRemaining: {}", remaining));
+                    }
+                    if let Some(progress) = active_sound.normalised_progress {
+                        details.push_str(&format!("\nProgress: {:.0}%", progress * 100.0));
+                    }
+                    Some(details)
+                },
+            };
+
             let (x, y) = position_metres_to_gui(position.point, &project.camera);
             let radians = position.radians as _;
             custom_widget::Sound::new(channel_amps, spread, radians, channel_radians as _)
                 .and_then(active_sound.normalised_progress, |w, p| w.progress(p))
+                .and_then(label.as_ref(), |w, label| w.label(label))
+                .and_then(details.as_ref(), |w, details| w.details(details))
                 .color(color)
                 .x_y(x, y)
                 .w_h(side, side)
@@ -1799,4 +3197,315 @@ fn set_widgets(
                 .set(sound_widget_id, ui);
         }
     }
+
+    // Draw the latest position of each entity tracked by the interaction inputs.
+    {
+        let Gui { ref mut ui, ref mut ids, .. } = *gui;
+        let mut tracks: Vec<_> = project_state.interaction_editor.tracks
+            .iter()
+            .map(|(_, track)| &track.interaction)
+            .collect();
+        tracks.sort_by_key(|interaction| (interaction.input.0, interaction.entity.0));
+        if ids.floorplan_interactions.len() < tracks.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            ids.floorplan_interactions.resize(tracks.len(), id_gen);
+        }
+        const INTERACTION_RADIUS: Scalar = 6.0;
+        for (i, interaction) in tracks.into_iter().enumerate() {
+            let (x, y) = position_metres_to_gui(interaction.position, &project.camera);
+            widget::Circle::fill(INTERACTION_RADIUS.max(min_target_radius * 0.5))
+                .x_y(x, y)
+                .color(color::DARK_ORANGE.alpha(0.8))
+                .parent(ids.floorplan)
+                .graphics_for(ids.floorplan)
+                .set(ids.floorplan_interactions[i], ui);
+        }
+    }
+
+    // Draw the simulated visitor, if any, as a larger marker that may be dragged around.
+    {
+        let Gui { ref mut ui, ref ids, .. } = *gui;
+        let interaction_editor = &mut project_state.interaction_editor;
+        if let Some(point) = interaction_editor::simulated_visitor(interaction_editor) {
+            let (dragged_x, dragged_y) = ui.widget_input(ids.floorplan_simulated_visitor)
+                .drags()
+                .left()
+                .fold((0.0, 0.0), |(x, y), drag| {
+                    (x + drag.delta_xy[0], y + drag.delta_xy[1])
+                });
+            let point = {
+                let x = point.x + project.camera.scalar_to_metres(dragged_x);
+                let y = point.y + project.camera.scalar_to_metres(dragged_y);
+                Point2 { x, y }
+            };
+            interaction_editor::move_simulated_visitor(interaction_editor, point);
+            const SIMULATED_VISITOR_RADIUS: Scalar = 12.0;
+            let (x, y) = position_metres_to_gui(point, &project.camera);
+            widget::Circle::fill(SIMULATED_VISITOR_RADIUS.max(min_target_radius))
+                .x_y(x, y)
+                .color(color::DARK_ORANGE.alpha(0.4))
+                .parent(ids.floorplan)
+                .set(ids.floorplan_simulated_visitor, ui);
+        }
+    }
+
+    // Draw the movement path of the selected source, if any, along with each of its points.
+    {
+        let Gui { ref mut ui, ref mut ids, .. } = *gui;
+        let path = source_editor::selected_path(&project.sources, &project_state.source_editor);
+        if let Some(path) = path {
+            let camera = &project.camera;
+            let polyline: Vec<_> = path
+                .polyline()
+                .iter()
+                .map(|p| {
+                    let (x, y) = position_metres_to_gui(p.point, camera);
+                    [x, y]
+                })
+                .collect();
+            if polyline.len() > 1 {
+                widget::PointPath::abs(polyline)
+                    .thickness(2.0)
+                    .color(color::DARK_PURPLE.alpha(0.8))
+                    .parent(ids.floorplan)
+                    .graphics_for(ids.floorplan)
+                    .set(ids.floorplan_source_path, ui);
+            }
+            if ids.floorplan_source_path_points.len() < path.points.len() {
+                let id_gen = &mut ui.widget_id_generator();
+                ids.floorplan_source_path_points.resize(path.points.len(), id_gen);
+            }
+            const PATH_POINT_RADIUS: Scalar = 4.0;
+            for (i, p) in path.points.iter().enumerate() {
+                let (x, y) = position_metres_to_gui(p.point, camera);
+                widget::Circle::fill(PATH_POINT_RADIUS)
+                    .x_y(x, y)
+                    .color(color::DARK_PURPLE)
+                    .parent(ids.floorplan)
+                    .graphics_for(ids.floorplan)
+                    .set(ids.floorplan_source_path_points[i], ui);
+            }
+        }
+    }
+
+    // Draw the binaural listener, if enabled, as a marker facing its direction that may be
+    // dragged around.
+    if project.master.binaural.enabled {
+        let Gui { ref mut ui, ref ids, ref channels, .. } = *gui;
+        let (dragged_x, dragged_y) = ui.widget_input(ids.floorplan_binaural_listener)
+            .drags()
+            .left()
+            .fold((0.0, 0.0), |(x, y), drag| {
+                (x + drag.delta_xy[0], y + drag.delta_xy[1])
+            });
+        if dragged_x != 0.0 || dragged_y != 0.0 {
+            let dragged_x_m = project.camera.scalar_to_metres(dragged_x);
+            let dragged_y_m = project.camera.scalar_to_metres(dragged_y);
+            master::set_binaural(&mut project.state.master.binaural, channels, |params| {
+                params.listener.x = params.listener.x + dragged_x_m;
+                params.listener.y = params.listener.y + dragged_y_m;
+            });
+        }
+        const LISTENER_RADIUS: Scalar = 10.0;
+        let binaural = project.master.binaural;
+        let (x, y) = position_metres_to_gui(binaural.listener, &project.camera);
+        let radius = LISTENER_RADIUS.max(min_target_radius);
+        widget::Circle::fill(radius)
+            .x_y(x, y)
+            .color(color::LIGHT_BLUE.alpha(0.6))
+            .parent(ids.floorplan)
+            .set(ids.floorplan_binaural_listener, ui);
+        let facing = [
+            x + binaural.facing_radians.cos() * radius * 2.0,
+            y + binaural.facing_radians.sin() * radius * 2.0,
+        ];
+        widget::Line::abs([x, y], facing)
+            .thickness(2.0)
+            .color(color::LIGHT_BLUE)
+            .graphics_for(ids.floorplan_binaural_listener)
+            .parent(ids.floorplan)
+            .set(ids.floorplan_binaural_listener_facing, ui);
+    }
+
+    // Draw the context menu over the floorplan if one is open.
+    set_floorplan_context_menu(gui, project, project_state);
+}
+
+// The speakers and active sounds of the project to draw over an exported floorplan view.
+fn floorplan_export_scene(
+    project: &Project,
+    audio_monitor: &AudioMonitor,
+) -> floorplan_export::Scene {
+    let speakers = project
+        .state
+        .speakers
+        .values()
+        .map(|speaker| {
+            let audio = &speaker.audio;
+            let aim_radians = match audio.directivity {
+                audio::speaker::Directivity::Omni => None,
+                _ => Some(audio.aim_radians),
+            };
+            floorplan_export::Speaker { point: audio.point, channel: audio.channel, aim_radians }
+        })
+        .collect();
+    let sounds = audio_monitor
+        .active_sounds
+        .values()
+        .map(|sound| sound.position.point)
+        .collect();
+    floorplan_export::Scene {
+        title: project.name.clone(),
+        speakers,
+        sounds,
+        radius: project.config.min_speaker_radius_metres,
+    }
+}
+
+// The controls for rotating, mirroring and offsetting the floorplan image beneath the exhibition.
+fn set_floorplan_transform_widgets(
+    gui: &mut Gui,
+    transform: &mut floorplan::Transform,
+    item_h: Scalar,
+) {
+    // The greatest offset of the floorplan image along either axis.
+    const MAX_OFFSET: f64 = 100.0;
+    const PAD: Scalar = 10.0;
+    let item_w = item_h * 6.0 + PAD;
+
+    for _click in widget::Button::new()
+        .w_h(item_w, item_h)
+        .down_from(gui.ids.floorplan_touch_mode, PAD)
+        .align_right_of(gui.ids.floorplan_touch_mode)
+        .label("ROTATE")
+        .label_font_size(SMALL_FONT_SIZE)
+        .label_color(color::WHITE)
+        .color(DARK_A)
+        .set(gui.ids.floorplan_transform_rotate, gui)
+    {
+        transform.rotate();
+    }
+
+    for mirror in widget::Toggle::new(transform.mirror)
+        .w_h(item_w, item_h)
+        .down(PAD)
+        .label("MIRROR")
+        .label_font_size(SMALL_FONT_SIZE)
+        .label_color(color::WHITE)
+        .color(DARK_A)
+        .set(gui.ids.floorplan_transform_mirror, gui)
+    {
+        transform.mirror = mirror;
+    }
+
+    let mut offsets = [
+        (gui.ids.floorplan_transform_offset_x, "X Offset (m)", &mut transform.offset_x),
+        (gui.ids.floorplan_transform_offset_y, "Y Offset (m)", &mut transform.offset_y),
+    ];
+    for &mut (id, label, ref mut offset) in offsets.iter_mut() {
+        for m in widget::NumberDialer::new(offset.0, -MAX_OFFSET, MAX_OFFSET, 2)
+            .w_h(item_w, item_h)
+            .down(PAD)
+            .label(label)
+            .label_font_size(SMALL_FONT_SIZE)
+            .label_color(color::WHITE)
+            .color(DARK_A)
+            .set(id, gui)
+        {
+            **offset = Metres(m);
+        }
+    }
+}
+
+// The context menu opened by long-pressing or right-clicking a speaker on the floorplan.
+fn set_floorplan_context_menu(
+    gui: &mut Gui,
+    project: &mut Project,
+    project_state: &mut ProjectState,
+) {
+    let speaker_id = match project_state.floorplan_context_menu {
+        Some(ref menu) if project.state.speakers.contains_key(&menu.speaker) => menu.speaker,
+        _ => {
+            project_state.floorplan_context_menu = None;
+            return;
+        },
+    };
+    let xy = project_state.floorplan_context_menu.as_ref().unwrap().xy;
+
+    let (solo, mute) = {
+        let speaker = &project.state.speakers[&speaker_id];
+        let solo = if speaker.soloed { "Unsolo" } else { "Solo" };
+        let mute = if speaker.muted { "Unmute" } else { "Mute" };
+        (solo, mute)
+    };
+    let options = ["Select", solo, mute, "Remove", "Close"];
+    const PAD: Scalar = 6.0;
+    const MENU_W: Scalar = 160.0;
+    let item_h = match *gui.touch_mode {
+        true => touch::ITEM_HEIGHT,
+        false => ITEM_HEIGHT,
+    };
+    let menu_h = PAD + (item_h + PAD) * options.len() as Scalar;
+
+    // Open the menu down and to the right of the speaker.
+    let (x, y) = (xy[0] + MENU_W * 0.5, xy[1] - menu_h * 0.5);
+    widget::Canvas::new()
+        .x_y(x, y)
+        .w_h(MENU_W, menu_h)
+        .pad(PAD)
+        .color(DARK_A)
+        .parent(gui.ids.floorplan_canvas)
+        .set(gui.ids.floorplan_context_menu, gui);
+
+    if gui.ids.floorplan_context_menu_options.len() < options.len() {
+        let id_gen = &mut gui.ui.widget_id_generator();
+        gui.ids.floorplan_context_menu_options.resize(options.len(), id_gen);
+    }
+
+    let mut selected_option = None;
+    for (i, &option) in options.iter().enumerate() {
+        let id = gui.ids.floorplan_context_menu_options[i];
+        let button = widget::Button::new()
+            .label(option)
+            .label_font_size(SMALL_FONT_SIZE)
+            .w(MENU_W - PAD * 2.0)
+            .h(item_h);
+        let button = match i {
+            0 => button.mid_top_of(gui.ids.floorplan_context_menu),
+            _ => button.down(PAD),
+        };
+        if button.set(id, gui).was_clicked() {
+            selected_option = Some(i);
+        }
+    }
+
+    match selected_option {
+        Some(0) => {
+            let sorted_speakers = speaker_editor::sorted_speakers_vec(&project.state.speakers);
+            let index = sorted_speakers.iter().position(|&id| id == speaker_id);
+            project_state.speaker_editor.selected = index;
+            gui.state.is_open.side_menu = true;
+            gui.state.is_open.speaker_editor = true;
+        },
+        Some(1) => {
+            let soloed = !project.state.speakers[&speaker_id].soloed;
+            let speakers = &mut project.state.speakers;
+            speaker_editor::set_speaker_soloed(speaker_id, speakers, soloed, false, gui.channels);
+        },
+        Some(2) => {
+            let speaker = project.state.speakers.get_mut(&speaker_id).unwrap();
+            let muted = !speaker.muted;
+            speaker_editor::set_speaker_muted(speaker_id, speaker, muted, gui.channels);
+        },
+        Some(3) => {
+            let sorted_speakers = speaker_editor::sorted_speakers_vec(&project.state.speakers);
+            let index = sorted_speakers.iter().position(|&id| id == speaker_id);
+            if index.is_some() && index == project_state.speaker_editor.selected {
+                project_state.speaker_editor.selected = None;
+            }
+            speaker_editor::remove_speaker(speaker_id, &mut project.state.speakers, gui.channels);
+        },
+        _ => return,
+    }
+    project_state.floorplan_context_menu = None;
 }
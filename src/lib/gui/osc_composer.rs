@@ -0,0 +1,265 @@
+//! An "OSC Composer" side-bar widget for composing and sending arbitrary OSC messages to any of
+//! the configured installation computers.
+//!
+//! This is useful for quickly testing an installation's OSC receivers without having to run a
+//! separate OSC utility on the exhibition machine.
+
+use gui::{collapsible_area, Gui, ProjectState, State};
+use gui::{ITEM_HEIGHT, SMALL_FONT_SIZE};
+use installation;
+use nannou::ui::prelude::*;
+use nannou_osc;
+use osc;
+use project::{self, Project};
+
+/// Runtime state related to the OSC composer GUI panel.
+pub struct OscComposer {
+    /// The installation computer to which the message will be sent.
+    pub target: Option<(installation::Id, installation::computer::Id)>,
+    /// The OSC address of the message.
+    pub addr: String,
+    /// The whitespace separated arguments of the message.
+    pub args: String,
+}
+
+impl Default for OscComposer {
+    fn default() -> Self {
+        OscComposer {
+            target: None,
+            addr: "/".into(),
+            args: String::new(),
+        }
+    }
+}
+
+/// Parse the given whitespace separated arguments into a list of OSC arguments.
+///
+/// Each argument may be explicitly typed with one of the following prefixes:
+///
+/// - `i:` - Int
+/// - `f:` - Float
+/// - `d:` - Double
+/// - `b:` - Bool
+/// - `s:` - String
+///
+/// Untyped arguments are parsed as an `Int`, `Float` or `Bool` if possible and otherwise fall
+/// back to a `String`.
+pub fn parse_args(s: &str) -> Result<Vec<nannou_osc::Type>, String> {
+    fn err(arg: &str) -> String {
+        format!("failed to parse argument \"{}\"", arg)
+    }
+    s.split_whitespace()
+        .map(|arg| {
+            let (prefix, value) = match arg.find(':') {
+                Some(i) if i == 1 => (Some(&arg[..1]), &arg[2..]),
+                _ => (None, arg),
+            };
+            match prefix {
+                Some("i") => value.parse().map(nannou_osc::Type::Int).map_err(|_| err(arg)),
+                Some("f") => value.parse().map(nannou_osc::Type::Float).map_err(|_| err(arg)),
+                Some("d") => value.parse().map(nannou_osc::Type::Double).map_err(|_| err(arg)),
+                Some("b") => value.parse().map(nannou_osc::Type::Bool).map_err(|_| err(arg)),
+                Some("s") => Ok(nannou_osc::Type::String(value.into())),
+                _ => {
+                    if let Ok(i) = arg.parse() {
+                        Ok(nannou_osc::Type::Int(i))
+                    } else if let Ok(f) = arg.parse() {
+                        Ok(nannou_osc::Type::Float(f))
+                    } else if let Ok(b) = arg.parse() {
+                        Ok(nannou_osc::Type::Bool(b))
+                    } else {
+                        Ok(nannou_osc::Type::String(arg.into()))
+                    }
+                },
+            }
+        })
+        .collect()
+}
+
+pub fn set(
+    last_area_id: widget::Id,
+    gui: &mut Gui,
+    project: &Project,
+    project_state: &mut ProjectState,
+) -> widget::Id {
+    let Gui {
+        ref mut ui,
+        ref ids,
+        channels,
+        state: &mut State {
+            ref mut is_open,
+            ..
+        },
+        ..
+    } = *gui;
+    let Project {
+        state: project::State {
+            ref installations,
+            ..
+        },
+        ..
+    } = *project;
+    let ProjectState {
+        ref mut osc_composer,
+        ..
+    } = *project_state;
+
+    const PAD: Scalar = 6.0;
+    const OSC_COMPOSER_H: Scalar = PAD
+        + ITEM_HEIGHT + PAD
+        + ITEM_HEIGHT + PAD
+        + ITEM_HEIGHT + PAD
+        + ITEM_HEIGHT + PAD;
+
+    let (area, event) = collapsible_area(is_open.osc_composer, "OSC Composer", ids.side_menu)
+        .align_middle_x_of(ids.side_menu)
+        .down_from(last_area_id, 0.0)
+        .set(ids.osc_composer, ui);
+    if let Some(event) = event {
+        is_open.osc_composer = event.is_open();
+    }
+
+    let area = match area {
+        Some(area) => area,
+        None => return ids.osc_composer,
+    };
+
+    // The canvas on which the composer widgets will be placed.
+    let canvas = widget::Canvas::new().pad(PAD).h(OSC_COMPOSER_H);
+    area.set(canvas, ui);
+
+    // Collect all installation computers as possible targets sorted by installation name.
+    let mut targets: Vec<_> = installations
+        .iter()
+        .flat_map(|(&inst_id, inst)| {
+            inst.computers.keys().map(move |&comp_id| (inst_id, comp_id))
+        })
+        .collect();
+    targets.sort_by(|a, b| {
+        installations[&a.0].name.cmp(&installations[&b.0].name).then((a.1).0.cmp(&(b.1).0))
+    });
+
+    // If there are no targets, display some text explaining how to add one.
+    if targets.is_empty() {
+        widget::Text::new("Add a computer to an installation to compose OSC messages.")
+            .font_size(SMALL_FONT_SIZE)
+            .kid_area_w_of(area.id)
+            .mid_top_of(area.id)
+            .set(ids.osc_composer_none, ui);
+        return area.id;
+    }
+
+    // Unselect the target if it no longer exists.
+    if osc_composer.target.map(|t| !targets.contains(&t)).unwrap_or(false) {
+        osc_composer.target = None;
+    }
+
+    // A drop down list for selecting the target installation computer.
+    let target_labels: Vec<String> = targets
+        .iter()
+        .map(|&(inst_id, comp_id)| {
+            let inst = &installations[&inst_id];
+            let addr = &inst.computers[&comp_id];
+            format!("{} #{} - {}", inst.name, comp_id.0 + 1, addr.socket)
+        })
+        .collect();
+    let selected_index = osc_composer.target.and_then(|t| targets.iter().position(|&s| s == t));
+    for index in widget::DropDownList::new(&target_labels, selected_index)
+        .mid_top_of(area.id)
+        .kid_area_w_of(area.id)
+        .h(ITEM_HEIGHT)
+        .max_visible_items(5)
+        .scrollbar_on_top()
+        .label("SELECT TARGET")
+        .label_font_size(SMALL_FONT_SIZE)
+        .set(ids.osc_composer_target_list, ui)
+    {
+        osc_composer.target = Some(targets[index]);
+    }
+
+    // A text box for editing the OSC address.
+    let color = match osc_composer.addr.starts_with('/') {
+        true => color::BLACK,
+        false => color::DARK_RED,
+    };
+    for event in widget::TextBox::new(&osc_composer.addr)
+        .align_middle_x_of(area.id)
+        .down(PAD)
+        .kid_area_w_of(area.id)
+        .h(ITEM_HEIGHT)
+        .font_size(SMALL_FONT_SIZE)
+        .color(color)
+        .set(ids.osc_composer_addr_text_box, ui)
+    {
+        if let widget::text_box::Event::Update(s) = event {
+            osc_composer.addr = s;
+        }
+    }
+
+    // A text box for editing the arguments.
+    let args = parse_args(&osc_composer.args);
+    let color = match args {
+        Ok(_) => color::BLACK,
+        Err(_) => color::DARK_RED,
+    };
+    let mut enter_pressed = false;
+    for event in widget::TextBox::new(&osc_composer.args)
+        .align_middle_x_of(area.id)
+        .down(PAD)
+        .kid_area_w_of(area.id)
+        .h(ITEM_HEIGHT)
+        .font_size(SMALL_FONT_SIZE)
+        .color(color)
+        .set(ids.osc_composer_args_text_box, ui)
+    {
+        use nannou::ui::widget::text_box::Event;
+        match event {
+            Event::Update(s) => osc_composer.args = s,
+            Event::Enter => enter_pressed = true,
+        }
+    }
+
+    // A button for sending the message.
+    let label = match args {
+        Ok(ref args) => format!("SEND ({} args)", args.len()),
+        Err(ref err) => err.clone(),
+    };
+    let clicked = widget::Button::new()
+        .align_middle_x_of(area.id)
+        .down(PAD)
+        .kid_area_w_of(area.id)
+        .h(ITEM_HEIGHT)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .set(ids.osc_composer_send, ui)
+        .was_clicked();
+
+    if clicked || enter_pressed {
+        let (installation, computer) = match osc_composer.target {
+            Some(target) => target,
+            None => {
+                eprintln!("no OSC composer target selected");
+                return area.id;
+            },
+        };
+        let args = match args {
+            Ok(args) => args,
+            Err(err) => {
+                eprintln!("could not send composed OSC message: {}", err);
+                return area.id;
+            },
+        };
+        if !osc_composer.addr.starts_with('/') {
+            eprintln!("OSC addresses must begin with a '/'");
+            return area.id;
+        }
+        let msg = nannou_osc::Message {
+            addr: osc_composer.addr.clone(),
+            args: if args.is_empty() { None } else { Some(args) },
+        };
+        let send = osc::output::Message::Send(installation, computer, msg);
+        channels.osc_out_msg_tx.push(send);
+    }
+
+    area.id
+}
@@ -0,0 +1,112 @@
+use gui::{collapsible_area, info_text, Gui};
+use installation;
+use nannou::ui::prelude::*;
+use osc;
+use project::Project;
+
+pub fn set(
+    last_area_id: widget::Id,
+    gui: &mut Gui,
+    project: &Project,
+) -> widget::Id {
+    let is_open = gui.state.is_open.osc_namespace;
+    let canvas_h = 300.0;
+    let (area, event) = collapsible_area(is_open, "OSC Namespace", gui.ids.side_menu)
+        .align_middle_x_of(gui.ids.side_menu)
+        .down_from(last_area_id, 0.0)
+        .set(gui.ids.osc_namespace, gui);
+    if let Some(event) = event {
+        gui.state.is_open.osc_namespace = event.is_open();
+    }
+
+    if let Some(area) = area {
+        // The canvas on which the namespace will be placed.
+        let canvas = widget::Canvas::new()
+            .scroll_kids()
+            .pad(10.0)
+            .h(canvas_h);
+        area.set(canvas, gui);
+
+        // The text widget used to display the namespace.
+        let namespace_string = format(project);
+        info_text(&namespace_string)
+            .top_left_of(area.id)
+            .kid_area_w_of(area.id)
+            .set(gui.ids.osc_namespace_text, gui);
+
+        // Scrollbars.
+        widget::Scrollbar::y_axis(area.id)
+            .color(color::LIGHT_CHARCOAL)
+            .auto_hide(false)
+            .set(gui.ids.osc_namespace_scrollbar_y, gui);
+        widget::Scrollbar::x_axis(area.id)
+            .color(color::LIGHT_CHARCOAL)
+            .auto_hide(true)
+            .set(gui.ids.osc_namespace_scrollbar_x, gui);
+
+        area.id
+    } else {
+        gui.ids.osc_namespace
+    }
+}
+
+/// Format every OSC address to which the server currently responds and every address that it
+/// emits given the project's installations and interaction inputs.
+pub fn format(project: &Project) -> String {
+    let mut s = format!("INPUT - Listening on port {}\n\n", project.config.osc_input_port);
+    for address in osc::input::namespace() {
+        s.push_str(&address.format());
+    }
+
+    // Installations, sorted by name for a stable listing.
+    let mut installations: Vec<_> = project.installations.values().collect();
+    installations.sort_by(|a, b| a.name.cmp(&b.name));
+    for installation in &installations {
+        let prefix = installation::normalise_osc_prefix(&installation.osc_input_prefix);
+        if prefix.is_empty() {
+            continue;
+        }
+        s.push_str(&format!("\nInstallation \"{}\"\n", installation.name));
+        for address in osc::input::installation_namespace(&prefix) {
+            s.push_str(&address.format());
+        }
+    }
+
+    // Interaction inputs.
+    let mut inputs: Vec<_> = project.interaction_inputs.values().collect();
+    inputs.sort_by(|a, b| a.name.cmp(&b.name));
+    for input in inputs {
+        let prefix = installation::normalise_osc_prefix(&input.osc_prefix);
+        if prefix.is_empty() {
+            continue;
+        }
+        s.push_str(&format!("\nInteraction Input \"{}\"\n", input.name));
+        for address in osc::input::interaction_namespace(&prefix) {
+            s.push_str(&address.format());
+        }
+    }
+
+    // Every computer of every installation receives the announcement and the installation's
+    // audio analysis.
+    s.push_str("\nOUTPUT\n");
+    s.push_str("\nAll Computers\n");
+    s.push_str(&osc::output::announce_namespace().format());
+    let mut any_computers = false;
+    for installation in &installations {
+        let mut computers: Vec<_> = installation.computers.iter().collect();
+        computers.sort_by_key(|&(&id, _)| id);
+        for (id, computer) in computers {
+            any_computers = true;
+            let dry_run = if computer.dry_run { " (dry run)" } else { "" };
+            s.push_str(&format!(
+                "\nInstallation \"{}\" - Computer {} at {}{}\n",
+                installation.name, id.0, computer.socket, dry_run,
+            ));
+            s.push_str(&osc::output::namespace(&computer.osc_addr).format());
+        }
+    }
+    if !any_computers {
+        s.push_str("\nNo installation computers to send to.\n");
+    }
+    s
+}
@@ -1,18 +1,35 @@
 //! A "Projects" side-bar widget providing allowing the user to create and remove new projects.
 
-use gui::{collapsible_area, Gui, ProjectState, State, TEXT_PAD, ITEM_HEIGHT, SMALL_FONT_SIZE};
+use gui::{collapsible_area, info_text, Gui, ProjectState, State};
+use gui::{TEXT_PAD, ITEM_HEIGHT, SMALL_FONT_SIZE};
 use project::{self, Project};
 use nannou::ui;
 use nannou::ui::prelude::*;
 use osc;
 use slug::slugify;
 use std::fs;
+use std::io;
 use std::path::Path;
 
 /// State related to the project editor GUI.
 #[derive(Default)]
 pub struct ProjectEditor {
     pub text_box_name: String,
+    /// The changes made since the project was last saved, if they are currently being reviewed.
+    ///
+    /// While reviewing, the "SAVE" button becomes "CONFIRM" so that saves are deliberate.
+    pub changes: Option<Vec<project::diff::Change>>,
+    /// The snapshots within the project's history, newest first, if they are being browsed.
+    pub history: Option<Vec<project::history::Snapshot>>,
+    /// The part being imported and the files from which it may be imported, if being browsed.
+    pub import: Option<(project::part::Part, Vec<project::part::ImportSource>)>,
+    /// The lease on the selected project's directory.
+    pub lock: Option<project::lock::Lock>,
+    /// Whether a save was refused as another machine saved the project since it was opened here.
+    ///
+    /// While `true`, the user is asked whether to overwrite the other machine's changes or to
+    /// save as a copy.
+    pub conflict: bool,
 }
 
 pub fn set(
@@ -51,13 +68,41 @@ pub fn set(
     const PROJECT_LIST_MAX_H: Scalar = ITEM_HEIGHT * 3.0;
     const BUTTON_H: Scalar = ITEM_HEIGHT;
     const NAME_TEXT_BOX_H: Scalar = ITEM_HEIGHT;
-    const CANVAS_H: Scalar = PROJECT_LIST_MAX_H + BUTTON_H + NAME_TEXT_BOX_H;
+    const CHANGES_H: Scalar = ITEM_HEIGHT * 4.0;
+    const HISTORY_H: Scalar = ITEM_HEIGHT * 4.0;
+    const IMPORT_H: Scalar = ITEM_HEIGHT * 4.0;
+    const LOCK_H: Scalar = ITEM_HEIGHT;
+    const CONFLICT_H: Scalar = ITEM_HEIGHT * 2.0;
+    const CANVAS_H: Scalar = PROJECT_LIST_MAX_H + BUTTON_H + NAME_TEXT_BOX_H + BUTTON_H;
 
     // The canvas on which the controls will be placed.
-    let canvas = widget::Canvas::new().pad(0.0).h(CANVAS_H);
+    let changes_h = match project_editor.changes {
+        Some(_) => CHANGES_H,
+        None => 0.0,
+    };
+    let history_h = match project_editor.history {
+        Some(_) => HISTORY_H,
+        None => 0.0,
+    };
+    let import_h = match project_editor.import {
+        Some(_) => IMPORT_H,
+        None => 0.0,
+    };
+    let is_shared = project_editor.lock.as_ref().map(|l| l.other().is_some()).unwrap_or(false);
+    let lock_h = match is_shared {
+        true => LOCK_H,
+        false => 0.0,
+    };
+    let conflict_h = match project_editor.conflict {
+        true => CONFLICT_H,
+        false => 0.0,
+    };
+    let canvas = widget::Canvas::new()
+        .pad(0.0)
+        .h(CANVAS_H + lock_h + conflict_h + changes_h + history_h + import_h);
     area.set(canvas, ui);
 
-    let button_w = ui.kid_area_of(area.id).unwrap().w() / 3.0;
+    let button_w = ui.kid_area_of(area.id).unwrap().w() / 6.0;
     let button = || widget::Button::new()
         .color(super::DARK_A)
         .label_font_size(SMALL_FONT_SIZE)
@@ -72,9 +117,8 @@ pub fn set(
     {
         // If a project was already selected, attempt to save it before creating and loading the
         // new empty project.
-        if let Some((project, _)) = project.take() {
-            project
-                .save(assets)
+        if let Some((mut project, _)) = project.take() {
+            save_before_switching(&mut project, assets, project_editor)
                 .expect("failed to save the project before switching to the new one");
         }
 
@@ -85,6 +129,9 @@ pub fn set(
         audio_monitor.clear();
         let new_project_state = ProjectState::default();
         project_editor.text_box_name = new_project.name.clone();
+        project_editor.changes = None;
+        project_editor.history = None;
+        project_editor.import = None;
         *project = Some((new_project, new_project_state));
     }
 
@@ -97,9 +144,8 @@ pub fn set(
     {
         // If a project was already selected, attempt to save it before creating and loading the
         // new empty project.
-        if let Some((old_project, _)) = project.take() {
-            old_project
-                .save(assets)
+        if let Some((mut old_project, _)) = project.take() {
+            save_before_switching(&mut old_project, assets, project_editor)
                 .expect("failed to save the project before switching to the new one");
 
             // Create a new default project.
@@ -110,21 +156,105 @@ pub fn set(
             audio_monitor.clear();
             let new_project_state = ProjectState::default();
             project_editor.text_box_name = new_project.name.clone();
+            project_editor.changes = None;
+            project_editor.history = None;
+            project_editor.import = None;
             *project = Some((new_project, new_project_state));
         }
     }
 
-    // Show the plus button at the bottom of the editor.
+    // Create a variation inheriting from the selected project, saving only what it overrides.
+    for _click in button()
+        .label("VARY")
+        .align_top_of(ids.project_editor_add)
+        .right(0.0)
+        .set(ids.project_editor_vary, ui)
+    {
+        if let Some((mut base_project, _)) = project.take() {
+            save_before_switching(&mut base_project, assets, project_editor)
+                .expect("failed to save the project before switching to the new one");
+
+            // The variation begins identical to its base.
+            let mut new_project = base_project;
+            new_project.base = Some(slugify(&new_project.name));
+            new_project.name = format!("{} variation", new_project.name);
+            new_project.save(assets).expect("failed to create new project directory");
+            new_project.reset_and_sync_all_threads(channels);
+            audio_monitor.clear();
+            let new_project_state = ProjectState::default();
+            project_editor.text_box_name = new_project.name.clone();
+            project_editor.changes = None;
+            project_editor.history = None;
+            project_editor.import = None;
+            *project = Some((new_project, new_project_state));
+        }
+    }
+
+    // Show or hide the changes made since the project was last saved.
     for _click in button()
-        .label("SAVE")
+        .label("DIFF")
+        .align_top_of(ids.project_editor_add)
+        .right(0.0)
+        .set(ids.project_editor_changes, ui)
+    {
+        match project_editor.changes.take() {
+            Some(_) => (),
+            None => if let Some((ref project, _)) = *project {
+                project_editor.history = None;
+                project_editor.import = None;
+                match project::diff::changes_since_save(project, assets) {
+                    Ok(changes) => project_editor.changes = Some(changes),
+                    Err(err) => eprintln!("failed to compare project with last save: {}", err),
+                }
+            },
+        }
+    }
+
+    // Show the changes for review before saving, then save once they are confirmed.
+    let save_label = match project_editor.changes {
+        Some(_) => "CONFIRM",
+        None => "SAVE",
+    };
+    for _click in button()
+        .label(save_label)
         .align_top_of(ids.project_editor_add)
         .right(0.0)
         .set(ids.project_editor_save, ui)
     {
-        // If a project was already selected, attempt to save it before creating and loading the
-        // new empty project.
         if let Some((ref project, _)) = *project {
-            project.save(assets).expect("failed to save the project");
+            project_editor.history = None;
+            project_editor.import = None;
+            match project_editor.changes.take() {
+                Some(_) => save(project, assets, project_editor),
+                None => match project::diff::changes_since_save(project, assets) {
+                    Ok(changes) => project_editor.changes = Some(changes),
+                    Err(err) => {
+                        eprintln!("failed to compare project with last save: {}", err);
+                        save(project, assets, project_editor);
+                    },
+                },
+            }
+        }
+    }
+
+    // Show or hide the snapshots within the project's history.
+    for _click in button()
+        .label("HISTORY")
+        .align_top_of(ids.project_editor_add)
+        .right(0.0)
+        .set(ids.project_editor_history, ui)
+    {
+        match project_editor.history.take() {
+            Some(_) => (),
+            None => if let Some((ref project, _)) = *project {
+                project_editor.changes = None;
+                project_editor.import = None;
+                let project_directory = project::project_directory_path(assets, &project.name);
+                match project::history::snapshots(&project_directory) {
+                    Ok(snapshots) => project_editor.history = Some(snapshots),
+                    Err(err) => eprintln!("failed to load project history: {}", err),
+                }
+            },
         }
     }
 
@@ -196,8 +326,12 @@ pub fn set(
         match event {
             // Instantiate a button for each project.
             Event::Item(item) => {
-                let label = &project_slugs[item.i];
-                let is_selected = selected_project_slug.as_ref() == Some(label);
+                let slug = &project_slugs[item.i];
+                let is_selected = selected_project_slug.as_ref() == Some(slug);
+                let label = match project::variation::load(&project_directories[item.i]) {
+                    Some(variation) => format!("{}  (variation of {})", slug, variation.base),
+                    None => slug.clone(),
+                };
 
                 // Blue if selected, gray otherwise.
                 let color = if is_selected {
@@ -257,9 +391,8 @@ pub fn set(
 
                 // If a project was already selected, attempt to save it before creating and loading the
                 // new empty project.
-                if let Some((project, _)) = project.take() {
-                    project
-                        .save(assets)
+                if let Some((mut project, _)) = project.take() {
+                    save_before_switching(&mut project, assets, project_editor)
                         .expect("failed to save the project before switching to the new one");
                 }
 
@@ -270,6 +403,9 @@ pub fn set(
                 let loaded_project_state = ProjectState::default();
                 selected_project_slug = Some(slugify(&loaded_project.name));
                 project_editor.text_box_name = loaded_project.name.clone();
+                project_editor.changes = None;
+                project_editor.history = None;
+                project_editor.import = None;
                 *project = Some((loaded_project, loaded_project_state));
             },
 
@@ -309,6 +445,9 @@ pub fn set(
             audio_monitor.clear();
             let loaded_project_state = ProjectState::default();
             project_editor.text_box_name = loaded_project.name.clone();
+            project_editor.changes = None;
+            project_editor.history = None;
+            project_editor.import = None;
             *project = Some((loaded_project, loaded_project_state));
 
         // Otherwise, just clear all the data.
@@ -333,8 +472,8 @@ pub fn set(
     }
 
     // Get the selected project in there is one.
-    let project = match *project {
-        Some((ref mut project, _)) => project,
+    let (project, project_state) = match *project {
+        Some((ref mut project, ref mut project_state)) => (project, project_state),
         None => return area.id,
     };
 
@@ -382,6 +521,9 @@ pub fn set(
                                 err,
                             );
                         } else {
+                            let old_slug = slugify(&project.name);
+                            let new_slug = slugify(&project_editor.text_box_name);
+                            project::variation::rename_base(assets, &old_slug, &new_slug);
                             project.name = project_editor.text_box_name.clone();
                         }
                     }
@@ -390,5 +532,367 @@ pub fn set(
         }
     }
 
+    // Export the layout and content of the project or import either from another project.
+    let third_w = ui.kid_area_of(area.id).unwrap().w() / 3.0;
+    for _click in button()
+        .label("EXPORT")
+        .w(third_w)
+        .down_from(ids.project_editor_name, 0.0)
+        .align_left_of(ids.project_editor_name)
+        .set(ids.project_editor_export, ui)
+    {
+        for &part in project::part::Part::ALL.iter() {
+            if let Err(err) = project::part::export(assets, project, part) {
+                eprintln!("failed to export project {}: {}", part.name(), err);
+            }
+        }
+        if let Err(err) = project::simulation::export(assets, project) {
+            eprintln!("failed to export project speaker layout for simulation: {}", err);
+        }
+    }
+
+    let import_buttons = [
+        (project::part::Part::Layout, "IMPORT LAYOUT", ids.project_editor_import_layout),
+        (project::part::Part::Content, "IMPORT CONTENT", ids.project_editor_import_content),
+    ];
+    for &(part, label, id) in import_buttons.iter() {
+        let is_importing = match project_editor.import {
+            Some((importing, _)) => importing == part,
+            None => false,
+        };
+        let color = match is_importing {
+            true => color::DARK_GREEN,
+            false => super::DARK_A,
+        };
+        for _click in button()
+            .label(label)
+            .color(color)
+            .w(third_w)
+            .right(0.0)
+            .align_top_of(ids.project_editor_export)
+            .set(id, ui)
+        {
+            project_editor.changes = None;
+            project_editor.history = None;
+            project_editor.import = match is_importing {
+                true => None,
+                false => {
+                    let sources = project::part::import_sources(assets, &project.name, part);
+                    Some((part, sources))
+                },
+            };
+        }
+    }
+
+    // Warn if another machine or process has the project open.
+    let mut last_id = ids.project_editor_export;
+    if let Some(lease) = project_editor.lock.as_ref().and_then(|lock| lock.other()) {
+        let text = format!("Also open on {} (pid {}). Saves may conflict.", lease.host, lease.pid);
+        info_text(&text)
+            .color(color::DARK_RED)
+            .down_from(last_id, 0.0)
+            .align_left_of(last_id)
+            .w_of(area.id)
+            .h(LOCK_H)
+            .parent(area.id)
+            .set(ids.project_editor_lock_text, ui);
+        last_id = ids.project_editor_lock_text;
+    }
+
+    // If a save was refused, ask whether to overwrite the other machine's save or save a copy.
+    if project_editor.conflict {
+        info_text("Saved on another machine since opened here.")
+            .color(color::DARK_RED)
+            .down_from(last_id, 0.0)
+            .align_left_of(last_id)
+            .w_of(area.id)
+            .h(ITEM_HEIGHT)
+            .parent(area.id)
+            .set(ids.project_editor_conflict_text, ui);
+
+        let half_w = ui.kid_area_of(area.id).unwrap().w() / 2.0;
+        for _click in button()
+            .label("OVERWRITE THEIRS")
+            .color(color::DARK_RED)
+            .w(half_w)
+            .down_from(ids.project_editor_conflict_text, 0.0)
+            .align_left_of(ids.project_editor_conflict_text)
+            .set(ids.project_editor_conflict_overwrite, ui)
+        {
+            project_editor.conflict = false;
+            save_with_snapshot(project, assets, &mut project_editor.lock);
+        }
+
+        for _click in button()
+            .label("SAVE AS COPY")
+            .w(half_w)
+            .right(0.0)
+            .align_top_of(ids.project_editor_conflict_overwrite)
+            .set(ids.project_editor_conflict_save_as, ui)
+        {
+            project_editor.conflict = false;
+            match save_as_copy(project, assets) {
+                Ok(()) => project_editor.text_box_name = project.name.clone(),
+                Err(err) => eprintln!("failed to save the project as a copy: {}", err),
+            }
+        }
+
+        last_id = ids.project_editor_conflict_overwrite;
+    }
+
+    // The changes made since the last save, if they are being reviewed.
+    if let Some(ref changes) = project_editor.changes {
+        widget::Canvas::new()
+            .scroll_kids_vertically()
+            .color(super::DARK_A)
+            .pad(TEXT_PAD)
+            .down_from(last_id, 0.0)
+            .align_left_of(last_id)
+            .w_of(area.id)
+            .h(CHANGES_H)
+            .parent(area.id)
+            .set(ids.project_editor_changes_canvas, ui);
+
+        let changes_string = match changes.len() {
+            0 => "No changes since the last save.".to_string(),
+            n => {
+                let lines: Vec<_> = changes.iter().map(|change| change.to_string()).collect();
+                format!("{} changes since the last save:\n{}", n, lines.join("\n"))
+            },
+        };
+        info_text(&changes_string)
+            .top_left_of(ids.project_editor_changes_canvas)
+            .kid_area_w_of(ids.project_editor_changes_canvas)
+            .set(ids.project_editor_changes_text, ui);
+
+        widget::Scrollbar::y_axis(ids.project_editor_changes_canvas)
+            .color(color::LIGHT_CHARCOAL)
+            .auto_hide(false)
+            .set(ids.project_editor_changes_scrollbar, ui);
+    }
+
+    // The snapshots within the project's history, if they are being browsed.
+    let mut restore = None;
+    if let Some(ref snapshots) = project_editor.history {
+        if snapshots.is_empty() {
+            info_text("No snapshots yet. One is written upon each save.")
+                .down_from(last_id, TEXT_PAD)
+                .align_left_of(last_id)
+                .w_of(area.id)
+                .parent(area.id)
+                .set(ids.project_editor_history_text, ui);
+        }
+
+        let (mut events, scrollbar) = widget::ListSelect::single(snapshots.len())
+            .item_size(ITEM_HEIGHT)
+            .w_of(area.id)
+            .h(HISTORY_H)
+            .down_from(last_id, 0.0)
+            .align_left_of(last_id)
+            .parent(area.id)
+            .scrollbar_next_to()
+            .scrollbar_color(color::LIGHT_CHARCOAL)
+            .set(ids.project_editor_history_list, ui);
+        while let Some(event) = events.next(ui, |_| false) {
+            use self::ui::widget::list_select::Event;
+            match event {
+                Event::Item(item) => {
+                    let label = format!("RESTORE {} UTC", snapshots[item.i].name());
+                    let button = widget::Button::new()
+                        .label(&label)
+                        .label_font_size(SMALL_FONT_SIZE)
+                        .color(color::CHARCOAL);
+                    item.set(button, ui);
+                },
+                Event::Selection(i) => restore = Some(snapshots[i].clone()),
+                _ => (),
+            }
+        }
+        if let Some(s) = scrollbar {
+            s.set(ui);
+        }
+    }
+
+    // The files from which the part may be imported, if they are being browsed.
+    let mut import = None;
+    if let Some((part, ref sources)) = project_editor.import {
+        if sources.is_empty() {
+            info_text("No other projects or exports to import from.")
+                .down_from(last_id, TEXT_PAD)
+                .align_left_of(last_id)
+                .w_of(area.id)
+                .parent(area.id)
+                .set(ids.project_editor_import_text, ui);
+        }
+
+        let (mut events, scrollbar) = widget::ListSelect::single(sources.len())
+            .item_size(ITEM_HEIGHT)
+            .w_of(area.id)
+            .h(IMPORT_H)
+            .down_from(last_id, 0.0)
+            .align_left_of(last_id)
+            .parent(area.id)
+            .scrollbar_next_to()
+            .scrollbar_color(color::LIGHT_CHARCOAL)
+            .set(ids.project_editor_import_list, ui);
+        while let Some(event) = events.next(ui, |_| false) {
+            use self::ui::widget::list_select::Event;
+            match event {
+                Event::Item(item) => {
+                    let source = &sources[item.i];
+                    let kind = match source.is_project {
+                        true => "PROJECT",
+                        false => "EXPORT",
+                    };
+                    let label = format!(
+                        "IMPORT {} FROM {} {}",
+                        part.name().to_uppercase(),
+                        kind,
+                        source.name(),
+                    );
+                    let button = widget::Button::new()
+                        .label(&label)
+                        .label_font_size(SMALL_FONT_SIZE)
+                        .color(color::CHARCOAL);
+                    item.set(button, ui);
+                },
+                Event::Selection(i) => import = Some((part, sources[i].clone())),
+                _ => (),
+            }
+        }
+        if let Some(s) = scrollbar {
+            s.set(ui);
+        }
+    }
+
+    // Replace the part of the project with the selected file's and sync all threads.
+    if let Some((part, source)) = import {
+        project_editor.import = None;
+        match project::part::import(assets, project, part, &source.path) {
+            Err(err) => eprintln!(
+                "failed to import {} from {}: {}",
+                part.name(),
+                source.path.display(),
+                err,
+            ),
+            Ok(()) => {
+                project.reset_and_sync_all_threads(channels);
+                audio_monitor.clear();
+                *project_state = ProjectState::default();
+            },
+        }
+    }
+
+    // Restore the selected snapshot and reload the project from its directory.
+    //
+    // The project is saved first so that the current state may be found within the history.
+    if let Some(snapshot) = restore {
+        project_editor.history = None;
+        project.save(assets).expect("failed to save the project before restoring a snapshot");
+        let project_directory = project::project_directory_path(assets, &project.name);
+        let retention = project.config.history;
+        match project::history::restore(&project_directory, &snapshot, &retention) {
+            Err(err) => eprintln!("failed to restore snapshot {}: {}", snapshot.name(), err),
+            Ok(()) => {
+                *project = Project::load(assets, &project_directory, default_project_config);
+                if let Some(ref mut lock) = project_editor.lock {
+                    lock.mark_saved();
+                }
+                project.reset_and_sync_all_threads(channels);
+                audio_monitor.clear();
+                *project_state = ProjectState::default();
+                project_editor.text_box_name = project.name.clone();
+            },
+        }
+    }
+
     area.id
 }
+
+/// Save the project and write a snapshot of it to the project's history.
+///
+/// If another machine saved the project since it was opened or last saved here, nothing is
+/// written and the user is instead asked whether to overwrite their save or to save a copy.
+pub fn save(project: &Project, assets: &Path, project_editor: &mut ProjectEditor) {
+    let is_modified_externally = project_editor
+        .lock
+        .as_ref()
+        .map(|lock| lock.is_modified_externally())
+        .unwrap_or(false);
+    if is_modified_externally {
+        eprintln!("refusing to save \"{}\" as it was saved on another machine", project.name);
+        project_editor.conflict = true;
+        return;
+    }
+    save_with_snapshot(project, assets, &mut project_editor.lock);
+}
+
+/// Save the project before switching away from it or exiting.
+///
+/// If another machine saved the project since it was opened or last saved here, the project is
+/// saved as a copy instead so that the changes of neither machine are lost.
+pub fn save_before_switching(
+    project: &mut Project,
+    assets: &Path,
+    project_editor: &ProjectEditor,
+) -> io::Result<()> {
+    let is_modified_externally = project_editor
+        .lock
+        .as_ref()
+        .map(|lock| lock.is_modified_externally())
+        .unwrap_or(false);
+    match is_modified_externally {
+        true => save_as_copy(project, assets),
+        false => project.save(assets),
+    }
+}
+
+/// Acquire the lease on the selected project's directory, releasing that of the previously
+/// selected project, or renew it if the project is unchanged.
+pub fn update_lock(project_editor: &mut ProjectEditor, project: Option<&Project>, assets: &Path) {
+    let directory = project.map(|project| project::project_directory_path(assets, &project.name));
+    let is_current = match (project_editor.lock.as_mut(), directory.as_ref()) {
+        (Some(lock), Some(directory)) => {
+            if lock.directory() == directory.as_path() {
+                lock.update();
+                true
+            } else {
+                false
+            }
+        },
+        (None, None) => true,
+        _ => false,
+    };
+    if !is_current {
+        // Release the previous lease first in case the project was renamed and the lease file
+        // moved along with its directory.
+        project_editor.lock = None;
+        project_editor.conflict = false;
+        project_editor.lock = directory.map(project::lock::Lock::acquire);
+    }
+}
+
+// Save the project and a snapshot of it, recording the save within the lock.
+fn save_with_snapshot(project: &Project, assets: &Path, lock: &mut Option<project::lock::Lock>) {
+    if let Err(err) = project.save_with_snapshot(assets) {
+        eprintln!("failed to save the project with a snapshot: {}", err);
+    }
+    if let Some(ref mut lock) = *lock {
+        lock.mark_saved();
+    }
+}
+
+// Rename the project after this machine and save it to the resulting new directory.
+fn save_as_copy(project: &mut Project, assets: &Path) -> io::Result<()> {
+    let projects_directory = project::projects_directory(assets);
+    let stem = format!("{} {}", project.name, project::lock::host_name());
+    let mut name = stem.clone();
+    let mut i = 2;
+    while projects_directory.join(slugify(&name)).exists() {
+        name = format!("{} {}", stem, i);
+        i += 1;
+    }
+    eprintln!("saving \"{}\" as \"{}\" to avoid overwriting another save", project.name, name);
+    project.name = name;
+    project.save(assets)
+}
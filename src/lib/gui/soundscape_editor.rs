@@ -1,27 +1,163 @@
 //! A `Soundscape` panel displaying:
 //!
 //! - Play/Pause toggle for the soundscape.
-//! - Groups panel for creating/removing soundscape source groups.
+//! - Intensity macro scaling all groups at once.
+//! - Scenes panel for cueing, scheduling and crossfading between named sets of group levels.
+//! - Groups panel for creating/removing/reordering soundscape source groups.
+//! - Time of day schedule scaling the density of the selected group.
+//! - Transitions between the sources of the selected group.
+//! - Noise modulation of the selected group's rate, spread and volume.
+//! - Spawn heatmap of the selected group, painted over the floorplan.
 
-use gui::{collapsible_area, hz_label, Gui, ProjectState, State};
+use audio;
+use gui::{collapsible_area, duration_label, hz_label, Channels, Gui, ProjectState, State};
 use gui::{ITEM_HEIGHT, SMALL_FONT_SIZE};
+use master::Master;
+use metres::Metres;
 use project::{self, Project};
+use nannou::geom::Point2;
 use nannou::ui;
 use nannou::ui::prelude::*;
 use soundscape;
+use soundscape::heatmap::Heatmap;
 use time_calc::Ms;
+use time_zone::TimeZone;
 use utils;
 
 /// GUI state related to the soundscape editor area.
 #[derive(Default)]
 pub struct SoundscapeEditor {
     pub selected: Option<Selected>,
+    /// The group currently being dragged to a new position within the group list.
+    pub dragging: Option<soundscape::group::Id>,
+    /// The scene whose group levels are being edited, if any.
+    pub selected_scene: Option<String>,
+    // The name of the scene being added, applied once enter is pressed.
+    new_scene_name: String,
+    // The scene schedule text currently being edited, if any.
+    scene_schedule_text: Option<String>,
+    // The time of day at which the scene schedule was last checked, in seconds since midnight at
+    // the venue.
+    scene_checked_secs: Option<u32>,
+    // The weight painted onto the selected group's heatmap.
+    heatmap_weight: f32,
+    // The radius of the brush with which the heatmap is painted.
+    heatmap_radius: Metres,
 }
 
 /// The currently selected group.
 pub struct Selected {
     name: String,
     id: soundscape::group::Id,
+    /// The schedule text being edited, applied once enter is pressed.
+    schedule: String,
+    /// The source and target of the transition being edited.
+    transition_from: Option<audio::source::Id>,
+    transition_to: Option<audio::source::Id>,
+    /// Whether or not clicks and drags over the floorplan paint the group's heatmap.
+    painting_heatmap: bool,
+}
+
+impl SoundscapeEditor {
+    /// The scene cued by the schedule since the schedule was last checked, if any.
+    ///
+    /// Always returns `None` upon the first check so that the scene most recently cued is not
+    /// replaced by simply starting the server.
+    pub fn check_scene_schedule(
+        &mut self,
+        scenes: &soundscape::scene::Scenes,
+        time_zone: &TimeZone,
+    ) -> Option<String> {
+        let now = time_zone.now_secs_of_day();
+        let previous = self.scene_checked_secs.replace(now)?;
+        scenes.due(previous, now).map(|scene| scene.to_string())
+    }
+}
+
+impl Selected {
+    fn new(id: soundscape::group::Id, name: String, schedule: String) -> Self {
+        let (transition_from, transition_to) = (None, None);
+        let painting_heatmap = false;
+        Selected { id, name, schedule, transition_from, transition_to, painting_heatmap }
+    }
+}
+
+/// The palette from which each group's colour is selected.
+pub const GROUP_COLORS: &[ui::Color] = &[
+    color::DARK_CHARCOAL,
+    color::DARK_RED,
+    color::DARK_ORANGE,
+    color::DARK_YELLOW,
+    color::DARK_GREEN,
+    color::DARK_PURPLE,
+    color::DARK_BROWN,
+    color::DARK_GREY,
+];
+
+/// The colour associated with the given palette index.
+pub fn group_color(index: usize) -> ui::Color {
+    GROUP_COLORS[index % GROUP_COLORS.len()]
+}
+
+/// Whether or not the floorplan is currently being used to paint the selected group's heatmap.
+pub fn is_painting(editor: &SoundscapeEditor) -> bool {
+    editor.selected.as_ref().map(|s| s.painting_heatmap).unwrap_or(false)
+}
+
+/// The heatmap of the selected group if it is currently being painted.
+pub fn painted_heatmap<'a>(
+    soundscape_groups: &'a project::SoundscapeGroups,
+    editor: &SoundscapeEditor,
+) -> Option<&'a Heatmap> {
+    let selected = editor.selected.as_ref().filter(|s| s.painting_heatmap)?;
+    soundscape_groups.get(&selected.id).map(|group| &group.heatmap)
+}
+
+/// Paint the selected group's heatmap at the given points over the floorplan.
+///
+/// Returns `true` if the heatmap is being painted and the points were consumed.
+pub fn floorplan_painted(
+    soundscape_groups: &mut project::SoundscapeGroups,
+    editor: &SoundscapeEditor,
+    channels: &Channels,
+    points: &[Point2<Metres>],
+) -> bool {
+    let id = match editor.selected {
+        Some(ref selected) if selected.painting_heatmap => selected.id,
+        _ => return false,
+    };
+
+    // Update the local copy.
+    let heatmap = match soundscape_groups.get_mut(&id) {
+        None => return false,
+        Some(group) => {
+            for &point in points {
+                group.heatmap.paint(point, editor.heatmap_radius, editor.heatmap_weight);
+            }
+            group.heatmap.clone()
+        },
+    };
+
+    // Update the soundscape copy.
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            soundscape.update_group(&id, |group| group.heatmap = heatmap);
+        })
+        .expect("failed to send painted group heatmap to soundscape thread");
+    true
+}
+
+/// Cue the given scene, crossfading the level of every group towards the scene over `secs`.
+pub fn cue_scene(scene: String, secs: f64, master: &mut Master, channels: &Channels) {
+    // Update the local copy.
+    master.soundscape_scenes.current = Some(scene.clone());
+
+    // Update the soundscape copy.
+    channels
+        .soundscape
+        .send(move |soundscape| soundscape.crossfade_to_scene(scene, secs))
+        .expect("failed to send scene crossfade to soundscape thread");
 }
 
 /// Sets all widgets in the soundscape area and returns the `Id` of the last area.
@@ -44,7 +180,9 @@ pub fn set(
 
     let Project {
         state: project::State {
+            ref mut master,
             ref mut soundscape_groups,
+            ref sources,
             ..
         },
         ..
@@ -58,17 +196,33 @@ pub fn set(
     // Constants to use as widget heights.
     const PAD: Scalar = 6.0;
     const IS_PLAYING_H: Scalar = ITEM_HEIGHT;
+    const INTENSITY_H: Scalar = ITEM_HEIGHT;
     const PLUS_GROUP_H: Scalar = ITEM_HEIGHT;
     const GROUP_LIST_MAX_H: Scalar = ITEM_HEIGHT * 5.0;
     const TEXT_BOX_H: Scalar = ITEM_HEIGHT;
     const TITLE_H: Scalar = SMALL_FONT_SIZE as Scalar * 1.333;
+    const SCENES_CANVAS_H: Scalar = PAD
+        + TITLE_H + PAD * 2.0 + ITEM_HEIGHT + PAD + ITEM_HEIGHT + PAD
+        + TITLE_H + PAD * 2.0 + TEXT_BOX_H + PAD;
     const GROUP_CANVAS_H: Scalar = PAD + TITLE_H + PAD + PLUS_GROUP_H + GROUP_LIST_MAX_H + PAD;
     const SLIDER_H: Scalar = ITEM_HEIGHT;
+    const TOGGLE_H: Scalar = ITEM_HEIGHT;
+    const COLOR_H: Scalar = ITEM_HEIGHT;
     const SELECTED_CANVAS_H: Scalar = PAD
         + TITLE_H + PAD * 2.0 + TEXT_BOX_H + PAD
+        + COLOR_H + PAD
+        + TOGGLE_H + PAD
         + TITLE_H + PAD * 2.0 + SLIDER_H + PAD
-        + TITLE_H + PAD + SLIDER_H + PAD;
-    let soundscape_editor_canvas_h = PAD + IS_PLAYING_H + PAD + GROUP_CANVAS_H + PAD + SELECTED_CANVAS_H + PAD;
+        + TITLE_H + PAD + SLIDER_H + PAD
+        + TITLE_H + PAD + SLIDER_H + PAD
+        + TITLE_H + PAD + (SLIDER_H + PAD) * 4.0
+        + TITLE_H + PAD * 2.0 + TEXT_BOX_H + PAD
+        + TITLE_H + PAD * 2.0 + ITEM_HEIGHT + PAD + SLIDER_H + PAD + TITLE_H + PAD
+        + TITLE_H + PAD * 2.0 + (TOGGLE_H + PAD) * 3.0
+        + TITLE_H + PAD + SLIDER_H + PAD
+        + TITLE_H + PAD * 2.0 + TOGGLE_H + PAD + SLIDER_H + PAD;
+    let soundscape_editor_canvas_h = PAD + IS_PLAYING_H + PAD + INTENSITY_H + PAD
+        + SCENES_CANVAS_H + PAD + GROUP_CANVAS_H + PAD + SELECTED_CANVAS_H + PAD;
 
     // The collapsible area.
     let (area, event) = collapsible_area(is_open.soundscape_editor, "Soundscape Editor", ids.side_menu)
@@ -119,6 +273,206 @@ pub fn set(
         }
     }
 
+    // The intensity macro, scaling the rates, simultaneous sounds and gains of all groups.
+    let label = format!("Intensity: {:.2}", master.soundscape_intensity);
+    for new_intensity in widget::Slider::new(master.soundscape_intensity, 0.0, 1.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .h(INTENSITY_H)
+        .kid_area_w_of(area.id)
+        .align_middle_x_of(area.id)
+        .down(PAD)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .set(ids.soundscape_editor_intensity, ui)
+    {
+        master.soundscape_intensity = new_intensity;
+        channels
+            .soundscape
+            .send(move |soundscape| soundscape.set_intensity(new_intensity))
+            .expect("failed to send soundscape intensity to soundscape thread");
+    }
+
+    ////////////
+    // SCENES //
+    ////////////
+
+    // A canvas on which scenes are added, cued and scheduled.
+    widget::Canvas::new()
+        .parent(area.id)
+        .kid_area_w_of(area.id)
+        .h(SCENES_CANVAS_H)
+        .align_middle_x_of(area.id)
+        .down(PAD)
+        .pad(PAD)
+        .color(color::CHARCOAL)
+        .set(ids.soundscape_editor_scenes_canvas, ui);
+
+    let label = match master.soundscape_scenes.current {
+        Some(ref scene) => format!("Scenes (Current: {})", scene),
+        None => "Scenes".to_string(),
+    };
+    widget::Text::new(&label)
+        .top_left_of(ids.soundscape_editor_scenes_canvas)
+        .font_size(SMALL_FONT_SIZE)
+        .set(ids.soundscape_editor_scenes_text, ui);
+
+    let scenes_kid_area = ui.kid_area_of(ids.soundscape_editor_scenes_canvas).unwrap();
+    let half_w = scenes_kid_area.w() / 2.0 - PAD / 2.0;
+    let third_w = (scenes_kid_area.w() - PAD * 2.0) / 3.0;
+
+    // Forget the selected scene if it no longer exists.
+    if let Some(scene) = soundscape_editor.selected_scene.take() {
+        if master.soundscape_scenes.contains(&scene) {
+            soundscape_editor.selected_scene = Some(scene);
+        }
+    }
+
+    // A drop down list for selecting the scene to cue and whose group levels are edited.
+    let selected_scene_index = soundscape_editor
+        .selected_scene
+        .as_ref()
+        .and_then(|s| master.soundscape_scenes.names.iter().position(|n| n == s));
+    for index in widget::DropDownList::new(&master.soundscape_scenes.names, selected_scene_index)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .label_font_size(SMALL_FONT_SIZE)
+        .scrollbar_on_top()
+        .align_left()
+        .down(PAD * 2.0)
+        .set(ids.soundscape_editor_scenes_list, ui)
+    {
+        soundscape_editor.selected_scene = Some(master.soundscape_scenes.names[index].clone());
+    }
+
+    // A text box for adding a new scene once enter is pressed.
+    for event in widget::TextBox::new(&soundscape_editor.new_scene_name)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .font_size(SMALL_FONT_SIZE)
+        .color(color::BLACK)
+        .right(PAD)
+        .set(ids.soundscape_editor_scenes_add, ui)
+    {
+        use self::ui::widget::text_box::Event;
+        match event {
+            Event::Update(name) => soundscape_editor.new_scene_name = name,
+            Event::Enter => {
+                let name = soundscape_editor.new_scene_name.trim().to_string();
+                if name.is_empty() || master.soundscape_scenes.contains(&name) {
+                    continue;
+                }
+                master.soundscape_scenes.names.push(name.clone());
+                soundscape_editor.selected_scene = Some(name);
+                soundscape_editor.new_scene_name.clear();
+            },
+        }
+    }
+
+    // A button for cueing the selected scene.
+    let button = |label| widget::Button::new()
+        .label(label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(third_w)
+        .h(ITEM_HEIGHT);
+    for _click in button("Cue")
+        .align_left_of(ids.soundscape_editor_scenes_list)
+        .down_from(ids.soundscape_editor_scenes_list, PAD)
+        .color(color::BLUE)
+        .set(ids.soundscape_editor_scenes_cue, ui)
+    {
+        if let Some(ref scene) = soundscape_editor.selected_scene {
+            let secs = master.soundscape_scenes.crossfade_secs;
+            cue_scene(scene.clone(), secs, master, channels);
+        }
+    }
+
+    // A button for removing the selected scene along with its cues and group levels.
+    for _click in button("Remove")
+        .right(PAD)
+        .color(color::DARK_RED)
+        .set(ids.soundscape_editor_scenes_remove, ui)
+    {
+        let scene = match soundscape_editor.selected_scene.take() {
+            Some(scene) => scene,
+            None => continue,
+        };
+
+        // Update the local copy.
+        let scenes = &mut master.soundscape_scenes;
+        scenes.names.retain(|n| *n != scene);
+        scenes.schedule.retain(|cue| cue.scene != scene);
+        if scenes.current.as_ref() == Some(&scene) {
+            scenes.current = None;
+        }
+        for group in soundscape_groups.values_mut() {
+            group.soundscape.scene_levels.remove(&scene);
+        }
+
+        // Update the soundscape copy.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                for group in soundscape.groups_mut() {
+                    group.scene_levels.remove(&scene);
+                }
+            })
+            .expect("failed to remove scene levels from soundscape thread");
+    }
+
+    // A slider for the duration over which the soundscape crossfades towards a cued scene.
+    let crossfade_secs = master.soundscape_scenes.crossfade_secs;
+    let label = format!("Crossfade: {}", duration_label(&Ms(crossfade_secs * 1_000.0)));
+    let max_secs = soundscape::scene::MAX_CROSSFADE_SECS;
+    for new_secs in widget::Slider::new(crossfade_secs, 0.0, max_secs)
+        .skew(0.5)
+        .w(third_w)
+        .h(SLIDER_H)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .right(PAD)
+        .set(ids.soundscape_editor_scenes_crossfade, ui)
+    {
+        master.soundscape_scenes.crossfade_secs = new_secs.round();
+    }
+
+    widget::Text::new("Schedule (Venue Time, e.g. \"07:30=day, 20:00=night\")")
+        .align_left_of(ids.soundscape_editor_scenes_cue)
+        .down_from(ids.soundscape_editor_scenes_cue, PAD)
+        .font_size(SMALL_FONT_SIZE)
+        .set(ids.soundscape_editor_scenes_schedule_text, ui);
+
+    // A text box of `HH:MM=scene` cues, where every cued scene must exist.
+    let schedule_string = soundscape::scene::schedule_string(&master.soundscape_scenes.schedule);
+    let (text, color) = match soundscape_editor.scene_schedule_text {
+        None => (schedule_string, color::BLACK),
+        Some(ref text) => match parse_scene_schedule(text, &master.soundscape_scenes) {
+            Ok(_) => (text.clone(), color::DARK_GREEN),
+            Err(_) => (text.clone(), color::DARK_RED),
+        },
+    };
+    for event in widget::TextBox::new(&text)
+        .kid_area_w_of(ids.soundscape_editor_scenes_canvas)
+        .h(TEXT_BOX_H)
+        .align_left()
+        .down(PAD * 2.0)
+        .font_size(SMALL_FONT_SIZE)
+        .color(color)
+        .set(ids.soundscape_editor_scenes_schedule, ui)
+    {
+        use self::ui::widget::text_box::Event;
+        match event {
+            Event::Update(s) => soundscape_editor.scene_schedule_text = Some(s),
+            Event::Enter => match parse_scene_schedule(&text, &master.soundscape_scenes) {
+                Ok(schedule) => {
+                    master.soundscape_scenes.schedule = schedule;
+                    soundscape_editor.scene_schedule_text = None;
+                },
+                Err(err) => eprintln!("failed to parse scene schedule: {}", err),
+            },
+        }
+    }
+
     //////////////////
     // GROUP EDITOR //
     //////////////////
@@ -129,7 +483,7 @@ pub fn set(
         .kid_area_w_of(area.id)
         .h(GROUP_CANVAS_H)
         .align_middle_x_of(area.id)
-        .down(PAD)
+        .down_from(ids.soundscape_editor_scenes_canvas, PAD)
         .pad(PAD)
         .color(color::CHARCOAL)
         .set(ids.soundscape_editor_group_canvas, ui);
@@ -152,13 +506,24 @@ pub fn set(
         // Add a new group.
         let name = "<unnamed>".to_string();
         let soundscape = soundscape::Group::default();
+        let id = project::next_soundscape_group_id(soundscape_groups);
         let group = project::SoundscapeGroup {
             name: name.clone(),
-            soundscape,
+            soundscape: soundscape.clone(),
+            order: soundscape_groups.len(),
+            color: id.0 % GROUP_COLORS.len(),
         };
-        let id = project::next_soundscape_group_id(soundscape_groups);
         soundscape_groups.insert(id, group);
-        soundscape_editor.selected = Some(Selected { id, name });
+        let schedule = String::new();
+        soundscape_editor.selected = Some(Selected::new(id, name, schedule));
+
+        // Update the soundscape copy.
+        channels
+            .soundscape
+            .send(move |soundscape_model| {
+                soundscape_model.insert_group(id, soundscape);
+            })
+            .expect("failed to send new soundscape group to soundscape thread");
     }
 
     // If there are no groups, display some text for adding a group.
@@ -173,14 +538,18 @@ pub fn set(
 
     // Otherwise display the list of all groups that currently exist.
     //
-    // First, collect all groups into alphabetical order.
-    let mut groups_vec: Vec<_> = soundscape_groups
-        .iter()
-        .map(|(&id, group)| (id, group.name.clone()))
+    // First, collect all groups into their user-specified order.
+    let mut groups_vec: Vec<_> = project::ordered_soundscape_groups(soundscape_groups)
+        .into_iter()
+        .map(|(id, group)| (id, group.name.clone(), group.color))
         .collect();
-    groups_vec.sort_by(|a, b| a.1.cmp(&b.1));
 
-    // A list of all groups in alphabetical order.
+    // Stop dragging as soon as the left mouse button is released.
+    if !ui.global_input().current.mouse.buttons.left().is_down() {
+        soundscape_editor.dragging = None;
+    }
+
+    // A list of all groups in order.
     let num_groups = groups_vec.len();
     let (mut events, scrollbar) = widget::ListSelect::single(num_groups)
         .down(0.0)
@@ -195,22 +564,27 @@ pub fn set(
     let selected_index = soundscape_editor
         .selected
         .as_ref()
-        .and_then(|s| groups_vec.iter().position(|&(id, _)| id == s.id));
+        .and_then(|s| groups_vec.iter().position(|&(id, _, _)| id == s.id));
 
-    // Track whether or not an item was removed.
+    // Track whether or not an item was removed or moved.
     let mut maybe_remove_index = None;
+    let mut maybe_move = None;
     while let Some(event) = events.next(ui, |i| Some(i) == selected_index) {
         use self::ui::widget::list_select::Event;
         match event {
             // Instantiate the widget for this item.
             Event::Item(item) => {
                 let is_selected = selected_index == Some(item.i);
+                let (group_id, _, group_color_index) = groups_vec[item.i];
+                let is_dragging = soundscape_editor.dragging == Some(group_id);
 
-                // Blue if selected, gray otherwise.
-                let color = if is_selected {
+                // Blue if selected, the group's colour otherwise.
+                let color = if is_dragging {
+                    color::LIGHT_BLUE
+                } else if is_selected {
                     color::BLUE
                 } else {
-                    color::DARK_CHARCOAL
+                    group_color(group_color_index)
                 };
 
                 // Use the name as the label.
@@ -225,8 +599,25 @@ pub fn set(
                     .color(color);
                 item.set(button, ui);
 
+                // If some other group is being dragged over this item, move it here.
+                if let Some(drag_id) = soundscape_editor.dragging {
+                    let is_under_mouse = ui.global_input()
+                        .current
+                        .widget_under_mouse
+                        .map(|id| {
+                            id == item.widget_id
+                                || ui.widget_graph()
+                                    .does_recursive_depth_edge_exist(item.widget_id, id)
+                        })
+                        .unwrap_or(false);
+                    if is_under_mouse && drag_id != group_id {
+                        maybe_move = Some((drag_id, item.i));
+                    }
+                    continue;
+                }
+
                 // If the button or any of its children are capturing the mouse, display
-                // the `remove` button.
+                // the `remove` button and the drag handle.
                 let show_remove_button = ui.global_input()
                     .current
                     .widget_capturing_mouse
@@ -241,6 +632,26 @@ pub fn set(
                     continue;
                 }
 
+                // A handle that may be dragged up or down to reorder the group.
+                widget::Button::new()
+                    .label("=")
+                    .label_font_size(SMALL_FONT_SIZE)
+                    .color(color::LIGHT_CHARCOAL.alpha(0.5))
+                    .w_h(ITEM_HEIGHT, ITEM_HEIGHT)
+                    .align_left_of(item.widget_id)
+                    .align_middle_y_of(item.widget_id)
+                    .parent(item.widget_id)
+                    .set(ids.soundscape_editor_group_drag_handle, ui);
+                let drag_started = ui.widget_input(ids.soundscape_editor_group_drag_handle)
+                    .presses()
+                    .mouse()
+                    .left()
+                    .next()
+                    .is_some();
+                if drag_started {
+                    soundscape_editor.dragging = Some(group_id);
+                }
+
                 if widget::Button::new()
                     .label("X")
                     .label_font_size(SMALL_FONT_SIZE)
@@ -259,8 +670,10 @@ pub fn set(
             // Update the selected source.
             Event::Selection(idx) => {
                 soundscape_editor.selected = {
-                    let (id, ref name) = groups_vec[idx];
-                    Some(Selected { id, name: name.clone() })
+                    let (id, ref name, _) = groups_vec[idx];
+                    let points = &soundscape_groups[&id].schedule.points;
+                    let schedule = soundscape::schedule::points_string(points);
+                    Some(Selected::new(id, name.clone(), schedule))
                 };
             }
 
@@ -273,9 +686,14 @@ pub fn set(
         s.set(ui);
     }
 
+    // Move the dragged group to its new position if necessary.
+    if let Some((id, index)) = maybe_move {
+        project::move_soundscape_group(soundscape_groups, id, index);
+    }
+
     // Remove a group if necessary.
     if let Some(i) = maybe_remove_index {
-        let (id, _) = groups_vec.remove(i);
+        let (id, _, _) = groups_vec.remove(i);
 
         // Unselect the removed group.
         if soundscape_editor.selected.as_ref().map(|s| s.id) == Some(id) {
@@ -301,6 +719,9 @@ pub fn set(
     // Only continue if there is some selected group.
     let SoundscapeEditor {
         ref mut selected,
+        ref selected_scene,
+        ref mut heatmap_weight,
+        ref mut heatmap_radius,
         ..
     } = *soundscape_editor;
 
@@ -351,13 +772,128 @@ pub fn set(
         }
     }
 
+    ///////////
+    // COLOR //
+    ///////////
+
+    // A rightward flowing list of the colours that may be assigned to the group.
+    let selected_kid_area = ui.kid_area_of(ids.soundscape_editor_selected_canvas).unwrap();
+    let n_colors = GROUP_COLORS.len();
+    let color_w = selected_kid_area.w() / n_colors as Scalar;
+    let selected_color = soundscape_groups[&selected.id].color % n_colors;
+    let (mut events, _scrollbar) = widget::ListSelect::single(n_colors)
+        .flow_right()
+        .item_size(color_w)
+        .w(selected_kid_area.w())
+        .h(COLOR_H)
+        .align_left_of(ids.soundscape_editor_selected_name)
+        .down(PAD)
+        .set(ids.soundscape_editor_selected_color, ui);
+    while let Some(event) = events.next(ui, |i| i == selected_color) {
+        use self::ui::widget::list_select::Event;
+        match event {
+            // Instantiate a swatch for each colour, marking the group's current colour.
+            Event::Item(item) => {
+                let label = if item.i == selected_color { "*" } else { "" };
+                let swatch = widget::Button::new()
+                    .label(label)
+                    .label_font_size(SMALL_FONT_SIZE)
+                    .color(GROUP_COLORS[item.i]);
+                item.set(swatch, ui);
+            },
+
+            // Assign the selected colour to the group.
+            Event::Selection(idx) => {
+                if let Some(group) = soundscape_groups.get_mut(&selected.id) {
+                    group.color = idx;
+                }
+            },
+
+            _ => (),
+        }
+    }
+
+    ////////////////
+    // SOLO/MUTE //
+    ////////////////
+
+    let button_w = selected_kid_area.w() / 2.0 - PAD / 2.0;
+    let toggle = |value: bool| widget::Toggle::new(value)
+        .w(button_w)
+        .h(TOGGLE_H)
+        .label_font_size(SMALL_FONT_SIZE);
+
+    // Solo button.
+    let solo = soundscape_groups[&selected.id].soloed;
+    for new_solo in toggle(solo)
+        .label("SOLO")
+        .align_left()
+        .down(PAD)
+        .color(color::DARK_YELLOW)
+        .set(ids.soundscape_editor_selected_solo, ui)
+    {
+        let id = selected.id;
+
+        // If the CTRL key was down, unsolo all other groups.
+        let unsolo_others = ui.global_input()
+            .current
+            .modifiers
+            .contains(ui::input::keyboard::ModifierKey::CTRL);
+        if unsolo_others {
+            for group in soundscape_groups.values_mut() {
+                group.soloed = false;
+            }
+        }
+
+        // Update the local copy.
+        soundscape_groups.get_mut(&id).unwrap().soloed = new_solo;
+
+        // Update the soundscape copy and fade out any sounds that are no longer audible.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                if unsolo_others {
+                    for group in soundscape.groups_mut() {
+                        group.soloed = false;
+                    }
+                }
+                soundscape.update_group(&id, |group| group.soloed = new_solo);
+                soundscape.fade_out_inaudible_sounds();
+            })
+            .expect("failed to send soloed group update to soundscape thread");
+    }
+
+    // Mute button.
+    let mute = soundscape_groups[&selected.id].muted;
+    for new_mute in toggle(mute)
+        .label("MUTE")
+        .align_top()
+        .right(PAD)
+        .color(color::BLUE)
+        .set(ids.soundscape_editor_selected_mute, ui)
+    {
+        let id = selected.id;
+
+        // Update the local copy.
+        soundscape_groups.get_mut(&id).unwrap().muted = new_mute;
+
+        // Update the soundscape copy and fade out any sounds that are no longer audible.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_group(&id, |group| group.muted = new_mute);
+                soundscape.fade_out_inaudible_sounds();
+            })
+            .expect("failed to send muted group update to soundscape thread");
+    }
+
     /////////////////////
     // OCCURRENCE RATE //
     /////////////////////
 
     widget::Text::new("Occurrence Rate")
-        .align_left()
-        .down(PAD)
+        .align_left_of(ids.soundscape_editor_selected_solo)
+        .down_from(ids.soundscape_editor_selected_solo, PAD)
         .font_size(SMALL_FONT_SIZE)
         .set(ids.soundscape_editor_occurrence_rate_text, ui);
 
@@ -470,5 +1006,610 @@ pub fn set(
             .expect("failed to send updated simultaneous sounds constraint to soundscape thread");
     }
 
+    ////////////////////
+    // MIN SEPARATION //
+    ////////////////////
+
+    widget::Text::new("Minimum Separation")
+        .align_left()
+        .down(PAD)
+        .font_size(SMALL_FONT_SIZE)
+        .set(ids.soundscape_editor_min_separation_text, ui);
+
+    // The distance kept between the group's concurrently active sounds.
+    let min_separation = soundscape_groups[&selected.id].min_separation;
+    let label = match min_separation.0 {
+        m if m <= 0.0 => "No minimum distance between sounds".to_string(),
+        m => format!("At least {:.2} metres between sounds", m),
+    };
+    let max = soundscape::group::MAX_MIN_SEPARATION.0;
+    for new_metres in widget::Slider::new(min_separation.0, 0.0, max)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .kid_area_w_of(ids.soundscape_editor_selected_canvas)
+        .h(SLIDER_H)
+        .align_left()
+        .down(PAD * 2.0)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .set(ids.soundscape_editor_min_separation, ui)
+    {
+        let id = selected.id;
+        let min_separation = Metres(new_metres);
+
+        // Update the local copy.
+        soundscape_groups.get_mut(&id).unwrap().min_separation = min_separation;
+
+        // Update the soundscape copy.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_group(&id, |group| group.min_separation = min_separation);
+            })
+            .expect("failed to send updated minimum separation to soundscape thread");
+    }
+
+    ////////////////////////
+    // INTENSITY RESPONSE //
+    ////////////////////////
+
+    widget::Text::new("Intensity Response")
+        .align_left()
+        .down(PAD)
+        .font_size(SMALL_FONT_SIZE)
+        .set(ids.soundscape_editor_intensity_text, ui);
+
+    // The scale applied at zero and at full intensity to each of the group's constraints.
+    let response = soundscape_groups[&selected.id].intensity_response;
+    let scale_label = |name: &str, range: utils::Range<f32>| {
+        format!("{} x{:.2} to x{:.2}", name, range.min, range.max)
+    };
+    const MIN_SCALE: f64 = 0.1;
+    const MAX_SCALE: f64 = 4.0;
+
+    let label = scale_label("Rate", response.rate);
+    let (min, max) = (response.rate.min as f64, response.rate.max as f64);
+    for (edge, value) in range_slider(min, max, MIN_SCALE, MAX_SCALE)
+        .align_left()
+        .label(&label)
+        .down(PAD)
+        .set(ids.soundscape_editor_intensity_rate, ui)
+    {
+        let value = value as f32;
+        set_intensity_response(selected.id, soundscape_groups, channels, move |r| match edge {
+            widget::range_slider::Edge::Start => r.rate.min = value,
+            widget::range_slider::Edge::End => r.rate.max = value,
+        });
+    }
+
+    let label = scale_label("Sounds", response.simultaneous);
+    let (min, max) = (response.simultaneous.min as f64, response.simultaneous.max as f64);
+    for (edge, value) in range_slider(min, max, MIN_SCALE, MAX_SCALE)
+        .align_left()
+        .label(&label)
+        .down(PAD)
+        .set(ids.soundscape_editor_intensity_simultaneous, ui)
+    {
+        let value = value as f32;
+        set_intensity_response(selected.id, soundscape_groups, channels, move |r| match edge {
+            widget::range_slider::Edge::Start => r.simultaneous.min = value,
+            widget::range_slider::Edge::End => r.simultaneous.max = value,
+        });
+    }
+
+    let label = scale_label("Gain", response.gain);
+    let (min, max) = (response.gain.min as f64, response.gain.max as f64);
+    for (edge, value) in range_slider(min, max, MIN_SCALE, MAX_SCALE)
+        .align_left()
+        .label(&label)
+        .down(PAD)
+        .set(ids.soundscape_editor_intensity_gain, ui)
+    {
+        let value = value as f32;
+        set_intensity_response(selected.id, soundscape_groups, channels, move |r| match edge {
+            widget::range_slider::Edge::Start => r.gain.min = value,
+            widget::range_slider::Edge::End => r.gain.max = value,
+        });
+    }
+
+    let label = format!("Curve: {:.2}", response.curve);
+    for new_curve in widget::Slider::new(response.curve, 0.25, 4.0)
+        .skew(0.5)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .kid_area_w_of(ids.soundscape_editor_selected_canvas)
+        .h(SLIDER_H)
+        .align_left()
+        .down(PAD)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .set(ids.soundscape_editor_intensity_curve, ui)
+    {
+        set_intensity_response(selected.id, soundscape_groups, channels, move |r| {
+            r.curve = new_curve;
+        });
+    }
+
+    //////////////
+    // SCHEDULE //
+    //////////////
+
+    widget::Text::new("Schedule (Venue Time, e.g. \"09:00=0.5, 19:00=2\")")
+        .align_left()
+        .down(PAD)
+        .font_size(SMALL_FONT_SIZE)
+        .set(ids.soundscape_editor_schedule_text, ui);
+
+    // A text box of `HH:MM=density` points scaling the group's rate and simultaneous sounds.
+    let color = match soundscape::schedule::parse_points(&selected.schedule) {
+        Ok(ref points) if *points == soundscape_groups[&selected.id].schedule.points => {
+            color::BLACK
+        },
+        Ok(_) => color::DARK_GREEN,
+        Err(_) => color::DARK_RED,
+    };
+    for event in widget::TextBox::new(&selected.schedule)
+        .kid_area_w_of(ids.soundscape_editor_selected_canvas)
+        .h(TEXT_BOX_H)
+        .align_left()
+        .down(PAD * 2.0)
+        .font_size(SMALL_FONT_SIZE)
+        .color(color)
+        .set(ids.soundscape_editor_schedule, ui)
+    {
+        use self::ui::widget::text_box::Event;
+        match event {
+            // When typing, only update the text being edited.
+            Event::Update(new_text) => selected.schedule = new_text,
+            // Only when enter is pressed do we update the schedule.
+            Event::Enter => {
+                let points = match soundscape::schedule::parse_points(&selected.schedule) {
+                    Ok(points) => points,
+                    Err(err) => {
+                        eprintln!("failed to parse group schedule: {}", err);
+                        continue;
+                    },
+                };
+                let id = selected.id;
+                selected.schedule = soundscape::schedule::points_string(&points);
+
+                // Update the local copy.
+                soundscape_groups.get_mut(&id).unwrap().schedule.points = points.clone();
+
+                // Update the soundscape copy.
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_group(&id, |group| group.schedule.points = points);
+                    })
+                    .expect("failed to send updated group schedule to soundscape thread");
+            },
+        }
+    }
+
+    /////////////////
+    // TRANSITIONS //
+    /////////////////
+
+    widget::Text::new("Transitions")
+        .align_left()
+        .down(PAD)
+        .font_size(SMALL_FONT_SIZE)
+        .set(ids.soundscape_editor_transitions_text, ui);
+
+    // The sources within the selected group, sorted by name.
+    let mut group_sources: Vec<(audio::source::Id, &str)> = sources
+        .map
+        .iter()
+        .filter_map(|(&id, source)| match source.audio.role {
+            Some(audio::source::Role::Soundscape(ref s)) if s.groups.contains(&selected.id) => {
+                Some((id, &source.name[..]))
+            },
+            _ => None,
+        })
+        .collect();
+    group_sources.sort_by(|a, b| a.1.cmp(b.1));
+    let source_names: Vec<&str> = group_sources.iter().map(|&(_, name)| name).collect();
+    let index_of = |id: Option<audio::source::Id>| {
+        id.and_then(|id| group_sources.iter().position(|&(s, _)| s == id))
+    };
+
+    // Forget any sources that have since been removed from the group.
+    if index_of(selected.transition_from).is_none() {
+        selected.transition_from = None;
+    }
+    if index_of(selected.transition_to).is_none() {
+        selected.transition_to = None;
+    }
+
+    // Drop down lists for the source and target of the transition being edited.
+    let transition_list = |selected_idx| {
+        widget::DropDownList::new(&source_names, selected_idx)
+            .w(button_w)
+            .h(ITEM_HEIGHT)
+            .label_font_size(SMALL_FONT_SIZE)
+            .scrollbar_on_top()
+    };
+    for idx in transition_list(index_of(selected.transition_from))
+        .label("From")
+        .align_left()
+        .down(PAD * 2.0)
+        .set(ids.soundscape_editor_transition_from, ui)
+    {
+        selected.transition_from = Some(group_sources[idx].0);
+    }
+    for idx in transition_list(index_of(selected.transition_to))
+        .label("To")
+        .right(PAD)
+        .set(ids.soundscape_editor_transition_to, ui)
+    {
+        selected.transition_to = Some(group_sources[idx].0);
+    }
+
+    // The weight of the transition between the selected sources.
+    let edge = match (selected.transition_from, selected.transition_to) {
+        (Some(from), Some(to)) => {
+            let weight = soundscape_groups[&selected.id].transitions.weight(&from, &to);
+            Some((from, to, weight))
+        },
+        _ => None,
+    };
+    let (weight, label) = match edge {
+        Some((_, _, weight)) => (weight, format!("Weight: {:.1}", weight)),
+        None => (0.0, "Select a source and target".to_string()),
+    };
+    let max = soundscape::group::MAX_TRANSITION_WEIGHT;
+    for new_weight in widget::Slider::new(weight, 0.0, max)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .kid_area_w_of(ids.soundscape_editor_selected_canvas)
+        .h(SLIDER_H)
+        .align_left_of(ids.soundscape_editor_transition_from)
+        .down_from(ids.soundscape_editor_transition_from, PAD)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .set(ids.soundscape_editor_transition_weight, ui)
+    {
+        let (from, to) = match edge {
+            Some((from, to, _)) => (from, to),
+            None => continue,
+        };
+        let new_weight = (new_weight * 10.0).round() / 10.0;
+        let id = selected.id;
+
+        // Update the local copy.
+        let transitions = {
+            let group = soundscape_groups.get_mut(&id).unwrap();
+            group.soundscape.transitions.set(from, to, new_weight);
+            group.transitions.clone()
+        };
+
+        // Update the soundscape copy.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_group(&id, |group| group.transitions = transitions);
+            })
+            .expect("failed to send updated group transitions to soundscape thread");
+    }
+
+    // Summarise the transitions from the selected source.
+    let text = match selected.transition_from {
+        None => String::new(),
+        Some(from) => {
+            let targets: Vec<String> = soundscape_groups[&selected.id]
+                .transitions
+                .edges
+                .iter()
+                .filter(|t| t.from == from)
+                .filter_map(|t| {
+                    let &(_, name) = group_sources.iter().find(|&&(s, _)| s == t.to)?;
+                    Some(format!("{} ({:.1})", name, t.weight))
+                })
+                .collect();
+            match targets.is_empty() {
+                true => "Followed By: Any".to_string(),
+                false => format!("Followed By: {}", targets.join(", ")),
+            }
+        },
+    };
+    widget::Text::new(&text)
+        .align_left()
+        .down(PAD)
+        .font_size(SMALL_FONT_SIZE)
+        .set(ids.soundscape_editor_transitions_summary, ui);
+
+    ////////////////
+    // MODULATION //
+    ////////////////
+
+    widget::Text::new("Modulation")
+        .align_left()
+        .down(PAD)
+        .font_size(SMALL_FONT_SIZE)
+        .set(ids.soundscape_editor_modulation_text, ui);
+
+    // A row of widgets for each modulator: on/off, the bounds of its scale and its period.
+    use soundscape::modulation::{self, Modulation, Modulator};
+    type Field = fn(&mut Modulation) -> &mut Modulator;
+    let group_modulation = soundscape_groups[&selected.id].modulation;
+    let rows: [(&str, Modulator, Field, widget::Id, widget::Id, widget::Id); 3] = [
+        (
+            "Rate",
+            group_modulation.rate,
+            |m| &mut m.rate,
+            ids.soundscape_editor_modulation_rate_toggle,
+            ids.soundscape_editor_modulation_rate_range,
+            ids.soundscape_editor_modulation_rate_period,
+        ),
+        (
+            "Spread",
+            group_modulation.spread,
+            |m| &mut m.spread,
+            ids.soundscape_editor_modulation_spread_toggle,
+            ids.soundscape_editor_modulation_spread_range,
+            ids.soundscape_editor_modulation_spread_period,
+        ),
+        (
+            "Volume",
+            group_modulation.volume,
+            |m| &mut m.volume,
+            ids.soundscape_editor_modulation_volume_toggle,
+            ids.soundscape_editor_modulation_volume_range,
+            ids.soundscape_editor_modulation_volume_period,
+        ),
+    ];
+    let modulator_w = (selected_kid_area.w() - PAD * 2.0) / 3.0;
+    for (i, &(name, modulator, field, toggle_id, range_id, period_id)) in rows.iter().enumerate() {
+        let label = format!("{}: {}", name, if modulator.enabled { "ON" } else { "OFF" });
+        for new_enabled in widget::Toggle::new(modulator.enabled)
+            .w(modulator_w)
+            .h(TOGGLE_H)
+            .label(&label)
+            .label_font_size(SMALL_FONT_SIZE)
+            .color(ui::color::LIGHT_CHARCOAL)
+            .align_left()
+            .down(if i == 0 { PAD * 2.0 } else { PAD })
+            .set(toggle_id, ui)
+        {
+            set_modulation(selected.id, soundscape_groups, channels, move |m| {
+                field(m).enabled = new_enabled;
+            });
+        }
+
+        let range = modulator.range;
+        let label = format!("x{:.2} to x{:.2}", range.min, range.max);
+        let (min, max) = (modulation::MIN_SCALE as f64, modulation::MAX_SCALE as f64);
+        for (edge, value) in range_slider(range.min as f64, range.max as f64, min, max)
+            .w(modulator_w)
+            .label(&label)
+            .right(PAD)
+            .set(range_id, ui)
+        {
+            let value = value as f32;
+            set_modulation(selected.id, soundscape_groups, channels, move |m| match edge {
+                widget::range_slider::Edge::Start => field(m).range.min = value,
+                widget::range_slider::Edge::End => field(m).range.max = value,
+            });
+        }
+
+        let label = format!("Every {}", duration_label(&modulator.period));
+        let (min, max) = (modulation::MIN_PERIOD.ms(), modulation::MAX_PERIOD.ms());
+        for new_ms in widget::Slider::new(modulator.period.ms(), min, max)
+            .skew(0.5)
+            .w(modulator_w)
+            .h(SLIDER_H)
+            .label(&label)
+            .label_font_size(SMALL_FONT_SIZE)
+            .color(ui::color::LIGHT_CHARCOAL)
+            .right(PAD)
+            .set(period_id, ui)
+        {
+            // Round to the nearest minute.
+            let period = Ms((new_ms / utils::MIN_MS).round() * utils::MIN_MS);
+            set_modulation(selected.id, soundscape_groups, channels, move |m| {
+                field(m).period = period;
+            });
+        }
+    }
+
+    /////////////////
+    // SCENE LEVEL //
+    /////////////////
+
+    let label = match *selected_scene {
+        Some(ref scene) => format!("Scene Level ({})", scene),
+        None => "Scene Level (Select A Scene Above)".to_string(),
+    };
+    widget::Text::new(&label)
+        .align_left_of(ids.soundscape_editor_modulation_rate_toggle)
+        .down(PAD)
+        .font_size(SMALL_FONT_SIZE)
+        .set(ids.soundscape_editor_scene_level_text, ui);
+
+    // A slider for the level of the selected group within the selected scene.
+    let level = selected_scene
+        .as_ref()
+        .map(|scene| soundscape_groups[&selected.id].scene_levels.get(scene))
+        .unwrap_or(1.0);
+    let label = format!("x{:.2}", level);
+    let max_level = soundscape::scene::MAX_LEVEL as f64;
+    for new_level in widget::Slider::new(level as f64, 0.0, max_level)
+        .kid_area_w_of(ids.soundscape_editor_selected_canvas)
+        .h(SLIDER_H)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .align_left()
+        .down(PAD)
+        .enabled(selected_scene.is_some())
+        .set(ids.soundscape_editor_scene_level, ui)
+    {
+        let scene = match *selected_scene {
+            Some(ref scene) => scene.clone(),
+            None => continue,
+        };
+        let id = selected.id;
+        let new_level = ((new_level * 100.0).round() / 100.0) as f32;
+
+        // Update the local copy.
+        let group = soundscape_groups.get_mut(&id).unwrap();
+        group.soundscape.scene_levels.set(&scene, new_level);
+
+        // Update the soundscape copy.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_group(&id, |group| group.scene_levels.set(&scene, new_level));
+            })
+            .expect("failed to send updated group scene level to soundscape thread");
+    }
+
+    ////////////////////
+    // SPAWN HEATMAP //
+    ////////////////////
+
+    widget::Text::new("Spawn Heatmap (Paint Over The Floorplan)")
+        .align_left_of(ids.soundscape_editor_scene_level)
+        .down(PAD)
+        .font_size(SMALL_FONT_SIZE)
+        .set(ids.soundscape_editor_heatmap_text, ui);
+
+    // Toggle whether clicks and drags over the floorplan paint the heatmap.
+    let label = if selected.painting_heatmap { "PAINTING" } else { "PAINT" };
+    for new_painting in widget::Toggle::new(selected.painting_heatmap)
+        .w(button_w)
+        .h(TOGGLE_H)
+        .label(label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::DARK_ORANGE)
+        .align_left()
+        .down(PAD * 2.0)
+        .set(ids.soundscape_editor_heatmap_paint, ui)
+    {
+        selected.painting_heatmap = new_painting;
+    }
+
+    // Clear all painted cells so that the group spawns uniformly again.
+    let is_empty = soundscape_groups[&selected.id].heatmap.is_empty();
+    for _click in widget::Button::new()
+        .w(button_w)
+        .h(TOGGLE_H)
+        .label("CLEAR")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::DARK_RED)
+        .enabled(!is_empty)
+        .align_top()
+        .right(PAD)
+        .set(ids.soundscape_editor_heatmap_clear, ui)
+    {
+        let id = selected.id;
+
+        // Update the local copy.
+        soundscape_groups.get_mut(&id).unwrap().heatmap.clear();
+
+        // Update the soundscape copy.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_group(&id, |group| group.heatmap.clear());
+            })
+            .expect("failed to send cleared group heatmap to soundscape thread");
+    }
+
+    // The weight and radius of the brush.
+    use soundscape::heatmap::{MAX_BRUSH_RADIUS, MAX_WEIGHT};
+    let label = format!("Weight: x{:.2}", *heatmap_weight);
+    for new_weight in widget::Slider::new(*heatmap_weight as f64, 0.0, MAX_WEIGHT as f64)
+        .w(button_w)
+        .h(SLIDER_H)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .align_left_of(ids.soundscape_editor_heatmap_paint)
+        .down(PAD)
+        .set(ids.soundscape_editor_heatmap_weight, ui)
+    {
+        *heatmap_weight = ((new_weight * 100.0).round() / 100.0) as f32;
+    }
+
+    let label = format!("Radius: {:.2}m", heatmap_radius.0);
+    for new_radius in widget::Slider::new(heatmap_radius.0, 0.0, MAX_BRUSH_RADIUS.0)
+        .w(button_w)
+        .h(SLIDER_H)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(ui::color::LIGHT_CHARCOAL)
+        .align_top()
+        .right(PAD)
+        .set(ids.soundscape_editor_heatmap_radius, ui)
+    {
+        *heatmap_radius = Metres(new_radius);
+    }
+
     area.id
 }
+
+// Parse a scene schedule, ensuring that every cued scene exists.
+fn parse_scene_schedule(
+    s: &str,
+    scenes: &soundscape::scene::Scenes,
+) -> Result<Vec<soundscape::scene::Cue>, String> {
+    let schedule = soundscape::scene::parse_schedule(s)?;
+    match schedule.iter().find(|cue| !scenes.contains(&cue.scene)) {
+        Some(cue) => Err(format!("no scene named `{}`", cue.scene)),
+        None => Ok(schedule),
+    }
+}
+
+// Update the modulation of the given group, both locally and on the soundscape thread.
+//
+// The modulation takes effect from the next soundscape tick.
+fn set_modulation<F>(
+    id: soundscape::group::Id,
+    soundscape_groups: &mut project::SoundscapeGroups,
+    channels: &Channels,
+    update: F,
+)
+where
+    F: Fn(&mut soundscape::modulation::Modulation),
+{
+    // Update the local copy.
+    let modulation = {
+        let group = soundscape_groups.get_mut(&id).unwrap();
+        update(&mut group.soundscape.modulation);
+        group.modulation
+    };
+
+    // Update the soundscape copy.
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            soundscape.update_group(&id, |group| group.modulation = modulation);
+        })
+        .expect("failed to send updated group modulation to soundscape thread");
+}
+
+// Update the intensity response of the given group, both locally and on the soundscape thread.
+fn set_intensity_response<F>(
+    id: soundscape::group::Id,
+    soundscape_groups: &mut project::SoundscapeGroups,
+    channels: &Channels,
+    update: F,
+)
+where
+    F: Fn(&mut soundscape::group::IntensityResponse),
+{
+    // Update the local copy.
+    let response = {
+        let group = soundscape_groups.get_mut(&id).unwrap();
+        update(&mut group.soundscape.intensity_response);
+        group.intensity_response
+    };
+
+    // Update the soundscape copy and the gain of any active sounds.
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            soundscape.update_group(&id, |group| group.intensity_response = response);
+            soundscape.update_intensity_gains();
+        })
+        .expect("failed to send updated intensity response to soundscape thread");
+}
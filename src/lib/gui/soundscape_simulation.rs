@@ -0,0 +1,240 @@
+//! A "Soundscape Simulation" side-bar widget previewing a day of the soundscape.
+//!
+//! The simulation runs on its own thread from a snapshot of the soundscape thread (see
+//! `soundscape::simulation`) so that playback is not interrupted. Once complete, the sounds spawned
+//! throughout the day and the mean number of sounds within each installation are plotted from
+//! midnight to midnight at the venue, followed by the number of sounds spawned from each source.
+
+use audio;
+use gui::{collapsible_area, info_text, Gui, ProjectState};
+use gui::{DARK_A, ITEM_HEIGHT, SMALL_FONT_SIZE};
+use nannou::ui::prelude::*;
+use project::Project;
+use soundscape::simulation::{self, Report};
+use std::sync::mpsc;
+use std::thread;
+
+/// GUI state related to the soundscape simulation.
+#[derive(Default)]
+pub struct SoundscapeSimulation {
+    // Receives the report of the simulation underway, if any.
+    pending: Option<mpsc::Receiver<Report>>,
+    // The report of the most recently completed simulation, if any.
+    report: Option<Report>,
+}
+
+// The colours with which each installation's sounds are plotted.
+const PLOT_COLORS: &[(&str, Color)] = &[
+    ("blue", color::LIGHT_BLUE),
+    ("orange", color::LIGHT_ORANGE),
+    ("green", color::LIGHT_GREEN),
+    ("purple", color::LIGHT_PURPLE),
+    ("yellow", color::LIGHT_YELLOW),
+    ("red", color::LIGHT_RED),
+];
+
+pub fn set(
+    last_area_id: widget::Id,
+    gui: &mut Gui,
+    project: &Project,
+    project_state: &mut ProjectState,
+) -> widget::Id {
+    let state = &mut project_state.soundscape_simulation;
+
+    // Collect the report of the simulation underway once complete.
+    if let Some(result) = state.pending.as_ref().map(|rx| rx.try_recv()) {
+        match result {
+            Err(mpsc::TryRecvError::Empty) => (),
+            Err(mpsc::TryRecvError::Disconnected) => {
+                eprintln!("failed to simulate the soundscape");
+                state.pending = None;
+            },
+            Ok(report) => {
+                state.report = Some(report);
+                state.pending = None;
+            },
+        }
+    }
+
+    let is_open = gui.state.is_open.soundscape_simulation;
+    const PAD: Scalar = 6.0;
+    const BUTTON_H: Scalar = ITEM_HEIGHT;
+    const TITLE_H: Scalar = SMALL_FONT_SIZE as Scalar * 1.333;
+    const PLOT_H: Scalar = 80.0;
+    const TEXT_H: Scalar = 160.0;
+    const CANVAS_H: Scalar = PAD + BUTTON_H + PAD
+        + TITLE_H + PAD + PLOT_H + PAD
+        + TITLE_H + PAD + PLOT_H + PAD
+        + TEXT_H + PAD;
+    let (area, event) = collapsible_area(is_open, "Soundscape Simulation", gui.ids.side_menu)
+        .align_middle_x_of(gui.ids.side_menu)
+        .down_from(last_area_id, 0.0)
+        .set(gui.ids.soundscape_simulation, gui);
+    if let Some(event) = event {
+        gui.state.is_open.soundscape_simulation = event.is_open();
+    }
+
+    let area = match area {
+        Some(area) => area,
+        None => return gui.ids.soundscape_simulation,
+    };
+
+    let canvas = widget::Canvas::new().pad(PAD).h(CANVAS_H);
+    area.set(canvas, gui);
+
+    // Simulate a day from a snapshot of the soundscape.
+    let label = match state.pending {
+        Some(_) => "SIMULATING...",
+        None => "SIMULATE A DAY",
+    };
+    for _click in widget::Button::new()
+        .label(label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::BLUE)
+        .enabled(state.pending.is_none())
+        .mid_top_of(area.id)
+        .kid_area_w_of(area.id)
+        .h(BUTTON_H)
+        .set(gui.ids.soundscape_simulation_run, gui)
+    {
+        let (tx, rx) = mpsc::channel();
+        let scenes = project.master.soundscape_scenes.clone();
+        gui.channels
+            .soundscape
+            .send(move |soundscape| {
+                let simulation = simulation::Simulation::new(soundscape);
+                thread::Builder::new()
+                    .name("soundscape_simulation".into())
+                    .spawn(move || {
+                        tx.send(simulation.run(&scenes)).ok();
+                    })
+                    .expect("failed to spawn soundscape simulation thread");
+            })
+            .expect("failed to send soundscape simulation to soundscape thread");
+        state.pending = Some(rx);
+    }
+
+    let report = match state.report {
+        Some(ref report) => report,
+        None => {
+            info_text("Simulate a day to preview the soundscape at the current settings.")
+                .align_left_of(gui.ids.soundscape_simulation_run)
+                .down(PAD * 2.0)
+                .set(gui.ids.soundscape_simulation_text, gui);
+            return area.id;
+        },
+    };
+
+    // The number of sounds spawned throughout the day.
+    let max_spawns = report.max_spawns();
+    let title = format!(
+        "Spawns Per 15 Minutes, 00:00 to 24:00 (Total {}, Peak {})",
+        report.total_spawns(),
+        max_spawns,
+    );
+    widget::Text::new(&title)
+        .font_size(SMALL_FONT_SIZE)
+        .align_left_of(gui.ids.soundscape_simulation_run)
+        .down(PAD)
+        .set(gui.ids.soundscape_simulation_spawns_text, gui);
+    let area_w = gui.kid_area_of(area.id).unwrap().w();
+    widget::Rectangle::fill([area_w, PLOT_H])
+        .color(DARK_A)
+        .align_left()
+        .down(PAD)
+        .parent(area.id)
+        .set(gui.ids.soundscape_simulation_spawns_bg, gui);
+    let max_x = (simulation::BINS - 1) as f32;
+    let max_y = (max_spawns as f32).max(1.0);
+    widget::PlotPath::new(0.0, max_x, 0.0, max_y, |x: f32| report.spawns[x as usize] as f32)
+        .wh_of(gui.ids.soundscape_simulation_spawns_bg)
+        .middle_of(gui.ids.soundscape_simulation_spawns_bg)
+        .color(color::LIGHT_BLUE)
+        .thickness(2.0)
+        .parent(gui.ids.soundscape_simulation_spawns_bg)
+        .set(gui.ids.soundscape_simulation_spawns_plot, gui);
+
+    // The mean number of sounds within each installation throughout the day, in order of name.
+    let mut installations: Vec<_> = report
+        .installation_sounds
+        .iter()
+        .filter_map(|(id, counts)| Some((&project.installations.get(id)?.name, counts)))
+        .collect();
+    installations.sort_by(|a, b| a.0.cmp(b.0));
+    let max_sounds = installations
+        .iter()
+        .flat_map(|&(_, counts)| counts.iter().cloned())
+        .fold(1.0, f32::max);
+    widget::Text::new("Mean Sounds Per Installation, 00:00 to 24:00")
+        .font_size(SMALL_FONT_SIZE)
+        .align_left_of(gui.ids.soundscape_simulation_spawns_bg)
+        .down_from(gui.ids.soundscape_simulation_spawns_bg, PAD)
+        .set(gui.ids.soundscape_simulation_sounds_text, gui);
+    widget::Rectangle::fill([area_w, PLOT_H])
+        .color(DARK_A)
+        .align_left()
+        .down(PAD)
+        .parent(area.id)
+        .set(gui.ids.soundscape_simulation_sounds_bg, gui);
+    if gui.ids.soundscape_simulation_sounds_plots.len() < installations.len() {
+        let id_gen = &mut gui.ui.widget_id_generator();
+        gui.ids.soundscape_simulation_sounds_plots.resize(installations.len(), id_gen);
+    }
+    for (i, &(_, counts)) in installations.iter().enumerate() {
+        let (_, color) = PLOT_COLORS[i % PLOT_COLORS.len()];
+        widget::PlotPath::new(0.0, max_x, 0.0, max_sounds, |x: f32| counts[x as usize])
+            .wh_of(gui.ids.soundscape_simulation_sounds_bg)
+            .middle_of(gui.ids.soundscape_simulation_sounds_bg)
+            .color(color)
+            .thickness(2.0)
+            .parent(gui.ids.soundscape_simulation_sounds_bg)
+            .set(gui.ids.soundscape_simulation_sounds_plots[i], gui);
+    }
+
+    // A summary of each installation followed by the use of each source.
+    let mut text = String::new();
+    for (i, &(name, counts)) in installations.iter().enumerate() {
+        let (color_name, _) = PLOT_COLORS[i % PLOT_COLORS.len()];
+        let mean = counts.iter().sum::<f32>() / counts.len() as f32;
+        let peak = counts.iter().cloned().fold(0.0, f32::max);
+        let line = format!("{} ({}): mean {:.1}, peak {:.1}\n", name, color_name, mean, peak);
+        text.push_str(&line);
+    }
+    let mut sources: Vec<_> = project
+        .sources
+        .iter()
+        .filter(|&(_, source)| match source.role {
+            Some(audio::source::Role::Soundscape(_)) => true,
+            _ => false,
+        })
+        .map(|(id, source)| {
+            let spawns = report.source_spawns.get(id).cloned().unwrap_or(0);
+            (&source.name, spawns)
+        })
+        .collect();
+    sources.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    text.push_str("\nSounds spawned per source:\n");
+    for (name, spawns) in sources {
+        text.push_str(&format!("    {}: {}\n", name, spawns));
+    }
+    widget::Canvas::new()
+        .scroll_kids_vertically()
+        .color(color::DARK_CHARCOAL)
+        .pad(PAD)
+        .align_left_of(gui.ids.soundscape_simulation_sounds_bg)
+        .down_from(gui.ids.soundscape_simulation_sounds_bg, PAD)
+        .w(area_w)
+        .h(TEXT_H)
+        .parent(area.id)
+        .set(gui.ids.soundscape_simulation_summary_canvas, gui);
+    info_text(&text)
+        .top_left_of(gui.ids.soundscape_simulation_summary_canvas)
+        .kid_area_w_of(gui.ids.soundscape_simulation_summary_canvas)
+        .set(gui.ids.soundscape_simulation_summary_text, gui);
+    widget::Scrollbar::y_axis(gui.ids.soundscape_simulation_summary_canvas)
+        .color(color::LIGHT_CHARCOAL)
+        .auto_hide(false)
+        .set(gui.ids.soundscape_simulation_summary_scrollbar, gui);
+
+    area.id
+}
@@ -0,0 +1,182 @@
+//! A "Source Dependencies" side-bar widget listing everything that a source refers to.
+//!
+//! The references of the source selected within the source editor are listed so that they may be
+//! reviewed before removing or disabling the source. References to installations, groups and
+//! speakers that have since been removed are listed for all sources and may be removed at once.
+
+use audio;
+use gui::{collapsible_area, info_text, Channels, Gui, ProjectState};
+use gui::ITEM_HEIGHT;
+use nannou::ui::prelude::*;
+use project::{self, Project};
+use project::dependencies::{self, Dependency};
+
+pub fn set(
+    last_area_id: widget::Id,
+    gui: &mut Gui,
+    project: &mut Project,
+    project_state: &ProjectState,
+) -> widget::Id {
+    let dangling = dependencies::dangling(&project.state);
+    let title = match dangling.len() {
+        0 => "Source Dependencies".to_string(),
+        n => format!("Source Dependencies ({} DANGLING)", n),
+    };
+    let is_open = gui.state.is_open.source_dependencies;
+    const PAD: Scalar = 6.0;
+    const TEXT_H: Scalar = 200.0;
+    const BUTTON_H: Scalar = ITEM_HEIGHT;
+    const CANVAS_H: Scalar = PAD + TEXT_H + PAD + BUTTON_H + PAD;
+    let (area, event) = collapsible_area(is_open, &title, gui.ids.side_menu)
+        .align_middle_x_of(gui.ids.side_menu)
+        .down_from(last_area_id, 0.0)
+        .set(gui.ids.source_dependencies, gui);
+    if let Some(event) = event {
+        gui.state.is_open.source_dependencies = event.is_open();
+    }
+
+    let area = match area {
+        Some(area) => area,
+        None => return gui.ids.source_dependencies,
+    };
+
+    let canvas = widget::Canvas::new().pad(PAD).h(CANVAS_H);
+    area.set(canvas, gui);
+
+    // The references of the selected source followed by all dangling references.
+    let selected = project_state.source_editor.selected;
+    let text = format(project, selected, &dangling, |id| {
+        gui.audio_monitor.active_sounds.values().filter(|s| s.source_id == id).count()
+    });
+    widget::Canvas::new()
+        .scroll_kids_vertically()
+        .color(color::DARK_CHARCOAL)
+        .pad(PAD)
+        .top_left_of(area.id)
+        .kid_area_w_of(area.id)
+        .h(TEXT_H)
+        .set(gui.ids.source_dependencies_canvas, gui);
+    info_text(&text)
+        .top_left_of(gui.ids.source_dependencies_canvas)
+        .kid_area_w_of(gui.ids.source_dependencies_canvas)
+        .set(gui.ids.source_dependencies_text, gui);
+    widget::Scrollbar::y_axis(gui.ids.source_dependencies_canvas)
+        .color(color::LIGHT_CHARCOAL)
+        .auto_hide(false)
+        .set(gui.ids.source_dependencies_scrollbar, gui);
+
+    // Remove every dangling reference from every source.
+    let color = match dangling.is_empty() {
+        true => color::DARK_CHARCOAL,
+        false => color::DARK_RED,
+    };
+    for _click in widget::Button::new()
+        .label("REMOVE DANGLING REFERENCES")
+        .label_font_size(12)
+        .color(color)
+        .enabled(!dangling.is_empty())
+        .down_from(gui.ids.source_dependencies_canvas, PAD)
+        .kid_area_w_of(area.id)
+        .h(BUTTON_H)
+        .set(gui.ids.source_dependencies_remove_dangling, gui)
+    {
+        remove_dangling(&gui.channels, &mut project.state.sources, &dangling);
+    }
+
+    area.id
+}
+
+/// Describe the references of the selected source, if any, followed by all dangling references.
+///
+/// `playing` produces the number of sounds currently playing from the source with the given ID.
+pub fn format<F>(
+    project: &Project,
+    selected: Option<audio::source::Id>,
+    dangling: &[(audio::source::Id, Dependency)],
+    playing: F,
+) -> String
+where
+    F: Fn(audio::source::Id) -> usize,
+{
+    let mut s = String::new();
+    match selected.and_then(|id| project.sources.get(&id).map(|source| (id, source))) {
+        None => s.push_str("Select a source within the Source Editor to list its references.\n"),
+        Some((id, source)) => {
+            s.push_str(&format!("\"{}\"\n", source.name));
+            let entries = dependencies::of_source(&project.state, &id);
+            for entry in &entries {
+                s.push_str(&format!("    {}\n", entry));
+            }
+            if entries.is_empty() {
+                s.push_str("    No installations, groups or speakers\n");
+            }
+            if !source.enabled {
+                s.push_str("    Disabled\n");
+            }
+            if project.sources.soloed.contains(&id) {
+                s.push_str("    Soloed\n");
+            }
+            if let Some(audio::source::Role::Soundscape(ref soundscape)) = source.role {
+                if soundscape.follow.enabled {
+                    s.push_str("    Follows tracked visitors\n");
+                }
+            }
+            match playing(id) {
+                0 => (),
+                1 => s.push_str("    1 sound playing\n"),
+                n => s.push_str(&format!("    {} sounds playing\n", n)),
+            }
+        },
+    }
+
+    s.push_str("\nDANGLING REFERENCES\n");
+    if dangling.is_empty() {
+        s.push_str("    None\n");
+    }
+    for &(id, dependency) in dangling {
+        let name = project.sources.get(&id).map(|s| &s.name[..]).unwrap_or("");
+        s.push_str(&format!("    \"{}\": {} (REMOVED)\n", name, dependency));
+    }
+    s
+}
+
+/// Remove the given references from the sources locally and on the soundscape and audio output
+/// threads.
+pub fn remove_dangling(
+    channels: &Channels,
+    sources: &mut project::Sources,
+    dangling: &[(audio::source::Id, Dependency)],
+) {
+    for &(id, dependency) in dangling {
+        // Update the local copy.
+        if let Some(source) = sources.get_mut(&id) {
+            dependency.remove_from(&mut source.audio);
+        }
+
+        // Update the soundscape copy.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_source(&id, move |source| {
+                    dependency.remove_from_constraints(&mut source.constraints);
+                    dependency.remove_from_filter(&mut source.speaker_filter);
+                });
+            })
+            .expect("failed to remove dangling reference from source on soundscape thread");
+
+        // Update the sounds playing from the source.
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.update_sounds_with_source(&id, move |_, sound| {
+                    dependency.remove_from_filter(&mut sound.speaker_filter);
+                    if let Dependency::Installation(ref installation) = dependency {
+                        if let audio::sound::Installations::Set(ref mut set) = sound.installations {
+                            set.remove(installation);
+                        }
+                    }
+                });
+            })
+            .expect("failed to remove dangling reference from sounds on audio output thread");
+    }
+}
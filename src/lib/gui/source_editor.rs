@@ -1,7 +1,11 @@
 use audio;
 use audio::source::Role;
-use audio::source::wav::Playback;
-use gui::{collapsible_area, duration_label, hz_label, Gui, ProjectState, State};
+use audio::source::wav::{loudness, Playback};
+use audio::source::wav::variations::{self, Variation};
+use camera::Camera;
+use fxhash::FxHashSet;
+use hound;
+use gui::{collapsible_area, duration_label, hz_label, Channels, Gui, ProjectState, State};
 use gui::{DARK_A, ITEM_HEIGHT, SMALL_FONT_SIZE};
 use metres::Metres;
 use nannou::prelude::*;
@@ -9,7 +13,9 @@ use nannou::ui;
 use nannou::ui::prelude::*;
 use project::{self, Project};
 use soundscape;
-use std::{self, cmp, mem, ops};
+use std::{self, cmp, fs, mem, ops};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
 use std::sync::atomic;
 use time_calc::{Ms, Samples};
 use utils;
@@ -21,6 +27,28 @@ pub struct SourceEditor {
     pub selected: Option<audio::source::Id>,
     /// The source currently being previewed via the source editor GUI.
     pub preview: SourcePreview,
+    /// The channel map text currently being edited for the source with the given Id.
+    pub channel_map_text: Option<(audio::source::Id, String)>,
+    /// The missing source for which the last relink attempt found no similar file.
+    pub relink_failed: Option<audio::source::Id>,
+    /// The input device name currently being edited for the realtime source with the given Id.
+    pub device_text: Option<(audio::source::Id, String)>,
+    /// The realtime source currently being recorded to disk, if any.
+    pub capture: Option<audio::capture::Capture>,
+    /// The WAV most recently captured from the realtime source with the given Id.
+    ///
+    /// This is offered for adding as a new WAV source until it is added or discarded.
+    pub captured: Option<(audio::source::Id, PathBuf)>,
+    /// The time of day gain curve text currently being edited for the source with the given Id.
+    pub day_gain_text: Option<(audio::source::Id, String)>,
+    /// Whether clicks on the floorplan add points to the selected source's movement path.
+    pub drawing_path: bool,
+    /// The source of the follow-up currently being edited for the selected source.
+    pub follow_up_source: Option<audio::source::Id>,
+    /// The WAV files whose loudness analysis failed.
+    ///
+    /// These are not analysed again until the file changes on disk.
+    pub loudness_failed: FxHashSet<PathBuf>,
 }
 
 /// A source currently being previewed.
@@ -51,6 +79,595 @@ const SOUNDSCAPE_COLOR: ui::Color = ui::color::DARK_RED;
 const INTERACTIVE_COLOR: ui::Color = ui::color::DARK_GREEN;
 const SCRIBBLES_COLOR: ui::Color = ui::color::DARK_PURPLE;
 
+/// Format the given WAV channel map as a list of whitespace separated, one-based channel numbers.
+fn channel_map_text(channel_map: &Option<Vec<usize>>) -> String {
+    match *channel_map {
+        None => String::new(),
+        Some(ref map) => {
+            let numbers: Vec<String> = map.iter().map(|ch| (ch + 1).to_string()).collect();
+            numbers.join(" ")
+        },
+    }
+}
+
+/// Parse a list of whitespace separated, one-based channel numbers into a WAV channel map.
+///
+/// An empty list produces `None`, indicating that the file's channel order should be used.
+fn parse_channel_map(text: &str, file_channels: usize) -> Result<Option<Vec<usize>>, String> {
+    let map = text
+        .split_whitespace()
+        .map(|s| match s.parse::<usize>() {
+            Ok(ch) if ch >= 1 && ch <= file_channels => Ok(ch - 1),
+            _ => Err(format!("invalid channel \"{}\"", s)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match map.is_empty() {
+        true => Ok(None),
+        false => Ok(Some(map)),
+    }
+}
+
+/// Select the source with the given Id, stopping the preview of the previously selected source.
+///
+/// Used by both the source list and the floorplan when an active sound is clicked.
+pub fn select_source(
+    source_editor: &mut SourceEditor,
+    channels: &Channels,
+    id: audio::source::Id,
+) {
+    source_editor.selected = Some(id);
+    source_editor.drawing_path = false;
+
+    // If a source was being previewed, stop it.
+    if let Some((_, sound_id)) = source_editor.preview.current {
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.remove_sound(sound_id);
+            })
+            .expect("failed to remove previewed sound from audio output thread");
+        source_editor.preview.current = None;
+    }
+}
+
+/// The movement path of the selected source, if it moves along a path.
+pub fn selected_path<'a>(
+    sources: &'a project::SourcesMap,
+    source_editor: &SourceEditor,
+) -> Option<&'a audio::source::movement::Path> {
+    let id = source_editor.selected?;
+    match sources.get(&id)?.audio.role {
+        Some(Role::Soundscape(ref soundscape)) => match soundscape.movement {
+            audio::source::Movement::Path(ref path) => Some(path),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Called when the floorplan is clicked at the given position.
+///
+/// If a path is being drawn for the selected source, the position is added to the end of the path
+/// with the speed of the previous point. Returns `true` if the click was used.
+pub fn floorplan_clicked(
+    sources: &mut project::SourcesMap,
+    source_editor: &SourceEditor,
+    channels: &Channels,
+    position: Point2<Metres>,
+) -> bool {
+    if !source_editor.drawing_path {
+        return false;
+    }
+    let id = match selected_path(sources, source_editor) {
+        Some(_) => source_editor.selected.unwrap(),
+        None => return false,
+    };
+    set_path(sources, channels, id, |path| {
+        let speed = path
+            .points
+            .last()
+            .map(|p| p.speed)
+            .unwrap_or(audio::source::default::PATH_SPEED);
+        path.points.push(audio::source::movement::PathPoint { point: position, speed });
+    });
+    true
+}
+
+// Update the local copy of the source's movement path and send it to the soundscape.
+//
+// All active sounds of the source begin travelling the updated path from its first point.
+fn set_path<F>(
+    sources: &mut project::SourcesMap,
+    channels: &Channels,
+    id: audio::source::Id,
+    update: F,
+)
+where
+    F: FnOnce(&mut audio::source::movement::Path),
+{
+    let soundscape = match sources.get_mut(&id).and_then(|s| s.audio.role.as_mut()) {
+        Some(role) => match role.soundscape_mut() {
+            Some(soundscape) => soundscape,
+            None => return,
+        },
+        None => return,
+    };
+    match soundscape.movement {
+        audio::source::Movement::Path(ref mut path) => update(path),
+        _ => return,
+    }
+    let movement = soundscape.movement.clone();
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            soundscape.update_source_movement(&id, &movement);
+        })
+        .expect("failed to send source movement path to soundscape thread");
+}
+
+/// Update the preview of the given source in accordance with the newly selected mode.
+///
+/// If the preview is already in the given mode, the preview is stopped. If the source's WAV can no
+/// longer be opened, it is marked missing on all threads.
+fn update_preview_mode(
+    new_mode: SourcePreviewMode,
+    channels: &Channels,
+    sound_id_gen: &audio::sound::IdGenerator,
+    camera: &Camera,
+    source_id: audio::source::Id,
+    source: &mut project::Source,
+    preview: &mut SourcePreview,
+    realtime_source_latency: &Ms,
+) {
+    loop {
+        match preview.current {
+            // If a preview exists, remove it.
+            Some((mode, sound_id)) => {
+                channels
+                    .audio_output
+                    .send(move |audio| {
+                        audio.remove_sound(sound_id);
+                    })
+                    .expect("failed to remove sound from audio output thread");
+
+                preview.current = None;
+                if mode != new_mode {
+                    continue;
+                }
+            }
+
+            // Otherwise set the preview mode to one-shot.
+            None => {
+                // Missing WAVs cannot be previewed until they are relinked.
+                if source.audio.kind.is_missing() {
+                    break;
+                }
+
+                let sound_id = sound_id_gen.generate_next();
+                preview.current = Some((new_mode, sound_id));
+
+                // Set the preview position to the centre of the camera if not yet set.
+                if preview.point.is_none() {
+                    preview.point = Some(camera.position);
+                }
+
+                // Send the selected source to the audio thread for playback.
+                let should_cycle = match new_mode {
+                    SourcePreviewMode::OneShot => false,
+                    SourcePreviewMode::Continuous => true,
+                };
+
+                // No attack or release for previews.
+                let attack_duration = Samples(0);
+                let release_duration = Samples(0);
+                let max_duration = None;
+                let position = audio::sound::Position {
+                    point: preview.point.unwrap(),
+                    radians: 0.0,
+                    height: source.audio.height,
+                };
+
+                // When previewing sounds, remove the role so they play back through all
+                // speakers.
+                let mut audio = source.audio.clone();
+                audio.role = None;
+
+                let spawned = audio::sound::spawn_from_source(
+                    sound_id,
+                    source_id,
+                    &audio,
+                    position,
+                    attack_duration,
+                    release_duration,
+                    should_cycle,
+                    max_duration,
+                    channels.frame_count.load(atomic::Ordering::Relaxed) as _,
+                    &channels.wav_reader,
+                    &channels.audio_input,
+                    &channels.audio_output,
+                    *realtime_source_latency,
+                );
+                if let Err(err) = spawned {
+                    preview.current = None;
+                    mark_wav_missing(channels, source_id, source, err);
+                }
+            }
+        }
+        break;
+    }
+}
+
+/// Stop the current preview (if any) and start it again from the beginning.
+///
+/// This is useful for hearing the changes after a source's audio has been modified.
+fn restart_preview(
+    channels: &Channels,
+    sound_id_gen: &audio::sound::IdGenerator,
+    camera: &Camera,
+    source_id: audio::source::Id,
+    source: &mut project::Source,
+    preview: &mut SourcePreview,
+    realtime_source_latency: &Ms,
+) {
+    if let Some((mode, _)) = preview.current {
+        for _ in 0..2 {
+            update_preview_mode(
+                mode,
+                channels,
+                sound_id_gen,
+                camera,
+                source_id,
+                source,
+                preview,
+                realtime_source_latency,
+            );
+        }
+    }
+}
+
+// Mark the given source's WAV as missing on the GUI and soundscape threads after it failed to open.
+fn mark_wav_missing(
+    channels: &Channels,
+    id: audio::source::Id,
+    source: &mut project::Source,
+    err: hound::Error,
+) {
+    let wav = match source.audio.kind {
+        audio::source::Kind::Wav(ref mut wav) => wav,
+        _ => return,
+    };
+    eprintln!("failed to open WAV \"{}\": {}", wav.path.display(), err);
+    wav.missing = true;
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            soundscape.update_source(&id, |source| {
+                if let audio::source::Kind::Wav(ref mut wav) = source.kind {
+                    wav.missing = true;
+                }
+            });
+        })
+        .expect("failed to send missing WAV source to soundscape thread");
+}
+
+/// Reload the metadata of all WAV sources that refer to the file at the given path.
+///
+/// Sources on the soundscape thread are updated and the preview is restarted if it is playing
+/// one of the reloaded sources.
+pub fn reload_wav_sources(
+    path: &Path,
+    channels: &Channels,
+    sound_id_gen: &audio::sound::IdGenerator,
+    project: &mut Project,
+    source_editor: &mut SourceEditor,
+) {
+    let project::State {
+        ref camera,
+        ref master,
+        ref mut sources,
+        ..
+    } = project.state;
+
+    // The file may now be analysed successfully.
+    source_editor.loudness_failed.retain(|failed| !is_same_path(failed, path));
+
+    for (&id, source) in sources.map.iter_mut() {
+        let new_wav = match source.audio.kind {
+            audio::source::Kind::Wav(ref wav) if is_same_path(&wav.path, path) => {
+                match wav.reload_from_path(path.to_path_buf()) {
+                    Ok(new_wav) => new_wav,
+                    // The file can no longer be played, e.g. its sample rate has changed.
+                    Err(err) => {
+                        eprintln!("failed to reload WAV \"{}\": {}", path.display(), err);
+                        let mut new_wav = wav.clone();
+                        new_wav.missing = true;
+                        new_wav
+                    },
+                }
+            },
+            audio::source::Kind::Wav(ref wav)
+                if wav.variations.files.iter().any(|v| is_same_path(&v.path, path)) =>
+            {
+                let new_variation = match Variation::from_path(path.to_path_buf()) {
+                    Ok(new_variation) => Some(new_variation),
+                    Err(err) => {
+                        eprintln!("failed to reload WAV \"{}\": {}", path.display(), err);
+                        None
+                    },
+                };
+                let mut new_wav = wav.clone();
+                for variation in &mut new_wav.variations.files {
+                    if is_same_path(&variation.path, path) {
+                        match new_variation {
+                            Some(ref new_variation) => {
+                                variation.duration = new_variation.duration;
+                                variation.missing = false;
+                            },
+                            None => variation.missing = true,
+                        }
+                    }
+                }
+                new_wav
+            },
+            _ => continue,
+        };
+
+        // Update the local copy.
+        source.audio.kind = audio::source::Kind::Wav(new_wav.clone());
+
+        // Update the soundscape copy.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_source(&id, move |source| {
+                    source.kind = audio::source::Kind::Wav(new_wav);
+                });
+            })
+            .expect("failed to send reloaded WAV source to soundscape thread");
+
+        // Restart the preview so that the new audio may be heard.
+        if source_editor.selected == Some(id) {
+            restart_preview(
+                channels,
+                sound_id_gen,
+                camera,
+                id,
+                source,
+                &mut source_editor.preview,
+                &master.realtime_source_latency,
+            );
+        }
+    }
+}
+
+/// Request loudness analysis for all WAV sources that have not yet been analysed and store the
+/// results of all completed analyses.
+///
+/// If a source is set to normalise its loudness, its volume is updated accordingly. Files that fail
+/// to be analysed are skipped until they change on disk.
+pub fn update_loudness(
+    analyser: &mut loudness::Analyser,
+    channels: &Channels,
+    project: &mut Project,
+    source_editor: &mut SourceEditor,
+) {
+    for (&id, source) in project.state.sources.map.iter() {
+        if let audio::source::Kind::Wav(ref wav) = source.audio.kind {
+            if wav.loudness.is_none()
+                && !wav.missing
+                && !analyser.is_pending(&id)
+                && !source_editor.loudness_failed.contains(&wav.path)
+            {
+                analyser.request(id, wav.path.clone());
+            }
+        }
+    }
+
+    for analysed in analyser.completed() {
+        let loudness::Analysed { source_id: id, path, result } = analysed;
+        let loudness = match result {
+            Ok(loudness) => loudness,
+            Err(err) => {
+                eprintln!("failed to analyse loudness of \"{}\": {}", path.display(), err);
+                source_editor.loudness_failed.insert(path);
+                continue;
+            },
+        };
+
+        // Update the local copy, ignoring the result if the source's file has since changed.
+        let source = match project.state.sources.map.get_mut(&id) {
+            Some(source) => source,
+            None => continue,
+        };
+        let normalise = match source.audio.kind {
+            audio::source::Kind::Wav(ref mut wav) if wav.path == path => {
+                wav.loudness = Some(loudness);
+                wav.normalise
+            },
+            _ => continue,
+        };
+
+        // Update the soundscape copy.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_source(&id, move |source| {
+                    if let audio::source::Kind::Wav(ref mut wav) = source.kind {
+                        wav.loudness = Some(loudness);
+                    }
+                });
+            })
+            .expect("failed to send source loudness to soundscape thread");
+
+        if normalise {
+            if let Some(volume) = loudness.normalised_volume(loudness::TARGET_LUFS) {
+                set_source_volume(channels, id, source, volume);
+            }
+        }
+    }
+}
+
+// Update the volume of the given source on all threads.
+fn set_source_volume(
+    channels: &Channels,
+    id: audio::source::Id,
+    source: &mut project::Source,
+    volume: f32,
+) {
+    // Update the local copy.
+    source.volume = volume;
+
+    // Update the soundscape copy.
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            soundscape.update_source(&id, |source| source.volume = volume);
+        })
+        .expect("failed to send source volume update to soundscape thread");
+
+    // Update the audio output copies.
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.update_sounds_with_source(&id, move |_, sound| {
+                sound.volume = volume;
+            });
+        })
+        .expect("failed to send source volume update to audio output thread");
+}
+
+// Update the insert effects of the source along with all of its sounds.
+fn set_source_effects(
+    channels: &Channels,
+    id: audio::source::Id,
+    source: &mut project::Source,
+    effects: audio::effect::Effects,
+) {
+    // Update the local copy.
+    source.effects = effects;
+
+    // Update the soundscape copy.
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            soundscape.update_source(&id, |source| source.effects = effects);
+        })
+        .expect("failed to send source effects update to soundscape thread");
+
+    // Update the audio output copies.
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.update_sounds_with_source(&id, move |_, sound| {
+                sound.effects.set_effects(effects);
+            });
+        })
+        .expect("failed to send source effects update to audio output thread");
+}
+
+// Update the DBAP rolloff override of the source and all of its sounds.
+fn set_source_dbap_rolloff(
+    channels: &Channels,
+    id: audio::source::Id,
+    source: &mut project::Source,
+    dbap_rolloff_db: Option<f64>,
+) {
+    // Update the local copy.
+    source.dbap_rolloff_db = dbap_rolloff_db;
+
+    // Update the soundscape copy.
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            soundscape.update_source(&id, |source| source.dbap_rolloff_db = dbap_rolloff_db);
+        })
+        .expect("failed to send source dbap rolloff update to soundscape thread");
+
+    // Update the audio output copies.
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.update_sounds_with_source(&id, move |_, sound| {
+                sound.dbap_rolloff_db = dbap_rolloff_db;
+            });
+        })
+        .expect("failed to send source dbap rolloff update to audio output thread");
+}
+
+// Toggle the given speaker within the include or exclude set of the source's speaker filter.
+fn toggle_source_speaker(
+    channels: &Channels,
+    id: audio::source::Id,
+    source: &mut project::Source,
+    speaker: audio::speaker::Id,
+    exclude: bool,
+) {
+    let insert = {
+        let set = match exclude {
+            true => &source.speaker_filter.exclude,
+            false => &source.speaker_filter.include,
+        };
+        !set.contains(&speaker)
+    };
+    let toggle = move |filter: &mut audio::speaker::Filter| {
+        let set = match exclude {
+            true => &mut filter.exclude,
+            false => &mut filter.include,
+        };
+        if insert {
+            set.insert(speaker);
+        } else {
+            set.remove(&speaker);
+        }
+    };
+
+    // Update the local copy.
+    toggle(&mut source.speaker_filter);
+
+    // Update the soundscape copy.
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            soundscape.update_source(&id, |source| toggle(&mut source.speaker_filter));
+        })
+        .expect("failed to send source speaker filter update to soundscape thread");
+
+    // Update the audio output copies.
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.update_sounds_with_source(&id, move |_, sound| {
+                toggle(&mut sound.speaker_filter);
+            });
+        })
+        .expect("failed to send source speaker filter update to audio output thread");
+}
+
+// Stop the capture in progress, if any, returning the Id of the captured source.
+fn stop_capture(
+    source_editor: &mut SourceEditor,
+    channels: &Channels,
+) -> Option<audio::source::Id> {
+    let capture = source_editor.capture.take()?;
+    let source_id = capture.source_id();
+    match capture.stop(&channels.audio_input) {
+        Ok(path) => source_editor.captured = Some((source_id, path)),
+        Err(err) => eprintln!("failed to finish capturing realtime source: {}", err),
+    }
+    Some(source_id)
+}
+
+// Whether or not the two paths refer to the same file.
+fn is_same_path(a: &Path, b: &Path) -> bool {
+    if a == b {
+        return true;
+    }
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
 pub fn set(
     last_area_id: widget::Id,
     gui: &mut Gui,
@@ -64,6 +681,7 @@ pub fn set(
         ref mut audio_monitor,
         channels,
         sound_id_gen,
+        assets,
         state:
             &mut State {
                 ref mut is_open,
@@ -80,6 +698,7 @@ pub fn set(
             ref soundscape_groups,
             ref installations,
             ref mut sources,
+            ref speakers,
             ..
         },
         ..
@@ -106,23 +725,47 @@ pub fn set(
         + TEXT_PAD + PAD + SLIDER_H + PAD
         + TEXT_PAD + PAD + SLIDER_H + PAD
         + TEXT_PAD + PAD + SLIDER_H + PAD
+        + TEXT_PAD + PAD * 2.0 + SLIDER_H + PAD
         + TEXT_PAD + PAD * 3.5 + SOUNDSCAPE_GROUP_LIST_H + PAD
         + TEXT_PAD + PAD * 2.0 + BUTTON_H + PAD + BUTTON_H + PAD
         + TEXT_PAD + PAD * 2.0 + SLIDER_H + PAD
         + TEXT_PAD + PAD * 2.0 + SLIDER_H + PAD
         + TEXT_PAD + PAD * 2.0 + SLIDER_H + PAD
         + TEXT_PAD + PAD * 2.0 + SLIDER_H * 2.0 + PAD
-        + TEXT_PAD + PAD * 2.0 + SLIDER_H + PAD;
+        + TEXT_PAD + PAD * 2.0 + SLIDER_H + PAD
+        + TEXT_PAD + PAD * 3.0 + SLIDER_H * 2.0 + PAD
+        + TEXT_PAD + PAD * 2.0 + ITEM_HEIGHT + PAD
+        + TEXT_PAD + PAD * 3.0 + SLIDER_H * 2.0 + PAD
+        + TEXT_PAD + PAD * 2.0 + ITEM_HEIGHT + PAD
+        + TEXT_PAD + PAD * 3.0 + SLIDER_H * 2.0 + PAD;
+    const RELINK_H: Scalar = ITEM_HEIGHT;
     const LOOP_TOGGLE_H: Scalar = ITEM_HEIGHT;
+    const LOOP_REGION_H: Scalar = ITEM_HEIGHT;
+    const LOOP_CROSSFADE_H: Scalar = ITEM_HEIGHT;
+    const CHANNEL_MAP_H: Scalar = ITEM_HEIGHT;
+    const NORMALISE_H: Scalar = ITEM_HEIGHT;
     const PLAYBACK_MODE_H: Scalar = ITEM_HEIGHT;
-    const WAV_CANVAS_H: Scalar =
-        100.0 + PAD + LOOP_TOGGLE_H + PAD * 4.0 + PLAYBACK_MODE_H + PAD;
-    const REALTIME_CANVAS_H: Scalar = 94.0;
+    const VARIATION_ADD_H: Scalar = ITEM_HEIGHT;
+    const VARIATION_SELECTION_H: Scalar = ITEM_HEIGHT;
+    const VARIATION_LIST_H: Scalar = ITEM_HEIGHT * 3.0;
+    const WAV_CANVAS_H: Scalar = 140.0 + PAD + RELINK_H + PAD + LOOP_TOGGLE_H + PAD
+        + LOOP_REGION_H + PAD + LOOP_CROSSFADE_H + PAD + TEXT_PAD + CHANNEL_MAP_H + PAD
+        + NORMALISE_H + PAD + TEXT_PAD + PAD + VARIATION_ADD_H + PAD + VARIATION_SELECTION_H
+        + PAD + VARIATION_LIST_H + PAD * 4.0 + PLAYBACK_MODE_H + PAD;
+    const DEVICE_H: Scalar = ITEM_HEIGHT;
+    const CAPTURE_H: Scalar = ITEM_HEIGHT;
+    const REALTIME_CANVAS_H: Scalar = 94.0 + TEXT_PAD + PAD + DEVICE_H + PAD + CAPTURE_H + PAD
+        + CAPTURE_H + PAD;
     const CHANNEL_LAYOUT_H: Scalar = 200.0;
-    const COMMON_CANVAS_H: Scalar = TEXT_PAD + PAD + SLIDER_H + PAD + CHANNEL_LAYOUT_H;
+    const ENABLED_H: Scalar = ITEM_HEIGHT;
+    const COMMON_CANVAS_H: Scalar = TEXT_PAD + PAD + SLIDER_H + PAD + ENABLED_H + PAD
+        + SLIDER_H + PAD + CHANNEL_LAYOUT_H;
+    const EFFECTS_CANVAS_H: Scalar = TEXT_PAD + PAD + SLIDER_H + PAD + SLIDER_H + PAD
+        + TEXT_PAD + PAD + SLIDER_H + PAD + SLIDER_H + PAD + SLIDER_H + PAD + SLIDER_H + PAD;
     let kind_specific_h = WAV_CANVAS_H.max(REALTIME_CANVAS_H);
     let selected_canvas_h = ITEM_HEIGHT * 2.0 + PAD * 7.0 + PREVIEW_CANVAS_H + kind_specific_h
-        + COMMON_CANVAS_H + INSTALLATIONS_CANVAS_H + PAD + SOUNDSCAPE_CANVAS_H;
+        + COMMON_CANVAS_H + PAD + EFFECTS_CANVAS_H + INSTALLATIONS_CANVAS_H + PAD
+        + SOUNDSCAPE_CANVAS_H;
     let source_editor_canvas_h = LIST_HEIGHT + ITEM_HEIGHT + selected_canvas_h;
 
     let (area, event) = collapsible_area(is_open.source_editor, "Source Editor", ids.side_menu)
@@ -192,12 +835,19 @@ pub fn set(
                 Event::Item(item) => {
                     let selected = selected_index == item.i;
                     let id = sources_vec[item.i];
-                    let (label, is_wav) = {
+                    let (label, is_wav, enabled) = {
                         let source = &sources[&id];
                         match source.audio.kind {
-                            audio::source::Kind::Wav(ref wav) => {
-                                (format!("[{}CH WAV] {}", wav.channels, source.name), true)
-                            }
+                            audio::source::Kind::Wav(ref wav) if wav.missing => (
+                                format!("[MISSING WAV] {}", source.name),
+                                true,
+                                source.enabled,
+                            ),
+                            audio::source::Kind::Wav(ref wav) => (
+                                format!("[{}CH WAV] {}", wav.channels, source.name),
+                                true,
+                                source.enabled,
+                            ),
                             audio::source::Kind::Realtime(ref rt) => (
                                 format!(
                                     "[{}-{}CH RT] {}",
@@ -206,6 +856,7 @@ pub fn set(
                                     source.name
                                 ),
                                 false,
+                                source.enabled,
                             ),
                         }
                     };
@@ -217,6 +868,16 @@ pub fn set(
                         color::CHARCOAL
                     };
 
+                    // Grey out the label of disabled sources and highlight missing ones.
+                    let is_missing = sources[&id].audio.kind.is_missing();
+                    let label_color = if is_missing {
+                        color::LIGHT_RED
+                    } else if enabled {
+                        color::WHITE
+                    } else {
+                        color::DARK_GREY
+                    };
+
                     // Use `Button`s for the selectable items.
                     let button = widget::Button::new()
                         .label(&label)
@@ -224,12 +885,16 @@ pub fn set(
                         .label_x(position::Relative::Place(position::Place::Start(Some(
                             10.0,
                         ))))
+                        .label_color(label_color)
                         .color(color);
                     item.set(button, ui);
 
                     // If the button or any of its children are capturing the mouse, display
                     // the `remove` button.
-                    let show_remove_button = !is_wav
+                    //
+                    // WAV sources are only removable once missing, as otherwise they would be
+                    // re-added from the audio directory when the project is next loaded.
+                    let show_remove_button = (!is_wav || is_missing)
                         && ui.global_input()
                             .current
                             .widget_capturing_mouse
@@ -262,18 +927,7 @@ pub fn set(
                 // Update the selected source.
                 Event::Selection(idx) => {
                     let id = sources_vec[idx];
-                    source_editor.selected = Some(id);
-
-                    // If a source was being previewed, stop it.
-                    if let Some((_, sound_id)) = source_editor.preview.current {
-                        channels
-                            .audio_output
-                            .send(move |audio| {
-                                audio.remove_sound(sound_id);
-                            })
-                            .expect("failed to remove previewed sound from audio output thread");
-                        source_editor.preview.current = None;
-                    }
+                    select_source(source_editor, channels, id);
                 }
 
                 _ => (),
@@ -353,9 +1007,11 @@ pub fn set(
         const DEFAULT_DURATION: Ms = Ms(3_000.0);
         let n_channels = DEFAULT_CHANNELS;
         let duration = DEFAULT_DURATION;
+        let device = String::new();
         let realtime = audio::source::Realtime {
             channels: n_channels,
             duration,
+            device,
         };
 
         // Create the Source.
@@ -365,15 +1021,29 @@ pub fn set(
         let role = Default::default();
         let spread = audio::source::default::SPREAD;
         let channel_radians = audio::source::default::CHANNEL_RADIANS;
+        let height = Metres(0.0);
         let volume = audio::source::default::VOLUME;
         let muted = bool::default();
+        let enabled = audio::source::default::ENABLED;
+        let effects = Default::default();
+        let reverb_send = 0.0;
+        let distance_model = false;
+        let dbap_rolloff_db = None;
+        let speaker_filter = Default::default();
         let audio = audio::Source {
             kind,
             role,
             spread,
             channel_radians,
+            height,
             volume,
             muted,
+            enabled,
+            effects,
+            reverb_send,
+            distance_model,
+            dbap_rolloff_db,
+            speaker_filter,
         };
         let source = project::Source { name, audio };
 
@@ -383,9 +1053,7 @@ pub fn set(
         // Send the source to the audio input thread.
         channels
             .audio_input
-            .send(move |audio| {
-                audio.sources.insert(id, realtime);
-            })
+            .insert_source(id, realtime)
             .expect("failed to send new source to audio input thread");
     }
 
@@ -519,6 +1187,12 @@ pub fn set(
 
                     // If the source became a soundscape source, send it to the soundscape thread.
                     (_, Some(Role::Soundscape(_))) => {
+                        // Begin with playback durations that the source may actually play for.
+                        let kind = &source.audio.kind;
+                        if let Some(Role::Soundscape(ref mut soundscape)) = source.audio.role {
+                            soundscape.playback_duration =
+                                kind.clamp_playback_duration(soundscape.playback_duration);
+                        }
                         let soundscape_source = soundscape::Source::from_audio_source(&source.audio)
                             .expect("source did not have soundscape role");
                         channels
@@ -576,84 +1250,6 @@ pub fn set(
     let preview_kid_area = ui.kid_area_of(ids.source_editor_preview_canvas).unwrap();
     let button_w = preview_kid_area.w() / 2.0 - PAD / 2.0;
 
-    fn update_mode(
-        new_mode: SourcePreviewMode,
-        channels: &super::Channels,
-        sound_id_gen: &audio::sound::IdGenerator,
-        camera: &super::Camera,
-        source_id: audio::source::Id,
-        source: &project::Source,
-        preview: &mut SourcePreview,
-        realtime_source_latency: &Ms,
-    ) {
-        loop {
-            match preview.current {
-                // If a preview exists, remove it.
-                Some((mode, sound_id)) => {
-                    channels
-                        .audio_output
-                        .send(move |audio| {
-                            audio.remove_sound(sound_id);
-                        })
-                        .expect("failed to remove sound from audio output thread");
-
-                    preview.current = None;
-                    if mode != new_mode {
-                        continue;
-                    }
-                }
-
-                // Otherwise set the preview mode to one-shot.
-                None => {
-                    let sound_id = sound_id_gen.generate_next();
-                    preview.current = Some((new_mode, sound_id));
-
-                    // Set the preview position to the centre of the camera if not yet set.
-                    if preview.point.is_none() {
-                        preview.point = Some(camera.position);
-                    }
-
-                    // Send the selected source to the audio thread for playback.
-                    let should_cycle = match new_mode {
-                        SourcePreviewMode::OneShot => false,
-                        SourcePreviewMode::Continuous => true,
-                    };
-
-                    // No attack or release for previews.
-                    let attack_duration = Samples(0);
-                    let release_duration = Samples(0);
-                    let max_duration = None;
-                    let position = audio::sound::Position {
-                        point: preview.point.unwrap(),
-                        radians: 0.0,
-                    };
-
-                    // When previewing sounds, remove the role so they play back through all
-                    // speakers.
-                    let mut audio = source.audio.clone();
-                    audio.role = None;
-
-                    let _handle = audio::sound::spawn_from_source(
-                        sound_id,
-                        source_id,
-                        &audio,
-                        position,
-                        attack_duration,
-                        release_duration,
-                        should_cycle,
-                        max_duration,
-                        channels.frame_count.load(atomic::Ordering::Relaxed) as _,
-                        &channels.wav_reader,
-                        &channels.audio_input,
-                        &channels.audio_output,
-                        *realtime_source_latency,
-                    );
-                }
-            }
-            break;
-        }
-    }
-
     if widget::Button::new()
         .bottom_left_of(ids.source_editor_preview_canvas)
         .label("One Shot")
@@ -666,13 +1262,13 @@ pub fn set(
         .set(ids.source_editor_preview_one_shot, ui)
         .was_clicked()
     {
-        update_mode(
+        update_preview_mode(
             SourcePreviewMode::OneShot,
             channels,
             sound_id_gen,
             camera,
             id,
-            &sources[&id],
+            sources.get_mut(&id).unwrap(),
             &mut source_editor.preview,
             &master.realtime_source_latency,
         );
@@ -690,19 +1286,43 @@ pub fn set(
         .set(ids.source_editor_preview_continuous, ui)
         .was_clicked()
     {
-        update_mode(
+        update_preview_mode(
             SourcePreviewMode::Continuous,
             channels,
             sound_id_gen,
             camera,
             id,
-            &sources[&id],
+            sources.get_mut(&id).unwrap(),
             &mut source_editor.preview,
             &master.realtime_source_latency,
         );
     }
 
+    // Other WAV sources whose files may be added as variations of the selected WAV source.
+    let mut variation_candidates: Vec<(String, PathBuf)> = match sources[&id].audio.kind {
+        audio::source::Kind::Wav(ref wav) => sources
+            .iter()
+            .filter(|&(&other_id, _)| other_id != id)
+            .filter_map(|(_, other)| match other.audio.kind {
+                audio::source::Kind::Wav(ref other_wav) if !other_wav.missing
+                    && other_wav.channels == wav.channels
+                    && other_wav.path != wav.path
+                    && !wav.variations.contains_path(&other_wav.path) =>
+                {
+                    Some((other.name.clone(), other_wav.path.clone()))
+                },
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+    variation_candidates.sort_by(|a, b| a.0.cmp(&b.0));
+
     // Kind-specific data.
+    let mut normalised_volume = None;
+    let mut capture_clicked = false;
+    let mut add_captured = false;
+    let mut discard_captured = false;
     let (kind_canvas_id, num_channels) = match sources.get_mut(&id).unwrap().audio.kind {
         audio::source::Kind::Wav(ref mut wav) => {
             // Instantiate a small canvas for displaying wav-specific stuff.
@@ -726,10 +1346,43 @@ pub fn set(
             } else {
                 format!("Duration: {:.4} milliseconds", duration_ms.ms())
             };
-            let file_line = format!("File: {}", wav.path.file_name().unwrap().to_str().unwrap());
+            let file_name = wav.path.file_name().unwrap().to_str().unwrap();
+            let file_line = match wav.missing {
+                true => format!("File: {} (MISSING)", file_name),
+                false => format!("File: {}", file_name),
+            };
+            let embedded_loop_line = match wav.embedded_loop {
+                None => "Embedded Loop: None".to_string(),
+                Some(ref l) => {
+                    let start_ms = l.start.to_ms(wav.sample_hz);
+                    let end_ms = l.end.unwrap_or(wav.duration).to_ms(wav.sample_hz);
+                    format!(
+                        "Embedded Loop: {} to {}",
+                        duration_label(&Ms(start_ms.ms().round())),
+                        duration_label(&Ms(end_ms.ms().round())),
+                    )
+                },
+            };
+            let loudness_line = match wav.loudness {
+                None => "Loudness: Analysing...".to_string(),
+                Some(ref l) => {
+                    let peak_db = 20.0 * l.peak.log10();
+                    match l.integrated_lufs {
+                        Some(lufs) => {
+                            format!("Loudness: {:.1} LUFS, Peak: {:.1} dBFS", lufs, peak_db)
+                        },
+                        None => format!("Loudness: Silent, Peak: {:.1} dBFS", peak_db),
+                    }
+                },
+            };
             let data = format!(
-                "{}\nChannels: {}\nSample Rate: {}\n{}",
-                file_line, wav.channels, wav.sample_hz, duration_line
+                "{}\nChannels: {}\nSample Rate: {}\n{}\n{}\n{}",
+                file_line,
+                wav.channels,
+                wav.sample_hz,
+                duration_line,
+                embedded_loop_line,
+                loudness_line,
             );
             widget::Text::new(&data)
                 .font_size(SMALL_FONT_SIZE)
@@ -738,14 +1391,63 @@ pub fn set(
                 .line_spacing(PAD)
                 .set(ids.source_editor_selected_wav_data, ui);
 
+            // A button for relinking the source to the most similarly named file within the audio
+            // directory if its file is missing.
+            let canvas_kid_area = ui.kid_area_of(ids.source_editor_selected_wav_canvas).unwrap();
+            let (label, relink_color) = if !wav.missing {
+                ("File Found", color::LIGHT_CHARCOAL)
+            } else if source_editor.relink_failed == Some(id) {
+                ("Relink: No Similar File Found", color::DARK_RED)
+            } else {
+                ("Relink Missing File", color::DARK_RED)
+            };
+            let relink_clicked = widget::Button::new()
+                .color(relink_color)
+                .label(label)
+                .label_font_size(SMALL_FONT_SIZE)
+                .down(PAD * 2.0)
+                .h(RELINK_H)
+                .w(canvas_kid_area.w())
+                .align_middle_x_of(ids.source_editor_selected_wav_canvas)
+                .set(ids.source_editor_selected_wav_relink, ui)
+                .was_clicked();
+            if relink_clicked && wav.missing {
+                let audio_path = project::audio_directory(assets);
+                let new_wav = project::find_relink_path(&audio_path, &wav.path)
+                    .and_then(|path| match wav.reload_from_path(path.clone()) {
+                        Ok(new_wav) => Some(new_wav),
+                        Err(err) => {
+                            eprintln!("failed to relink WAV to \"{}\": {}", path.display(), err);
+                            None
+                        },
+                    });
+                match new_wav {
+                    None => source_editor.relink_failed = Some(id),
+                    Some(new_wav) => {
+                        // Update the local copy.
+                        *wav = new_wav.clone();
+                        source_editor.relink_failed = None;
+
+                        // Update the soundscape copy.
+                        channels
+                            .soundscape
+                            .send(move |soundscape| {
+                                soundscape.update_source(&id, move |source| {
+                                    source.kind = audio::source::Kind::Wav(new_wav);
+                                });
+                            })
+                            .expect("failed to send relinked WAV source to soundscape thread");
+                    },
+                }
+            }
+
             // A `Toggle` for whether or not the WAV should loop.
             let label = if wav.should_loop { "Looping: ON" } else { "Looping: OFF" };
-            let canvas_kid_area = ui.kid_area_of(ids.source_editor_selected_wav_canvas).unwrap();
             for new_loop in widget::Toggle::new(wav.should_loop)
                 .color(color::LIGHT_CHARCOAL)
                 .label(label)
                 .label_font_size(SMALL_FONT_SIZE)
-                .down(PAD * 2.0)
+                .down(PAD)
                 .h(LOOP_TOGGLE_H)
                 .w(canvas_kid_area.w())
                 .align_middle_x_of(ids.source_editor_selected_wav_canvas)
@@ -770,28 +1472,434 @@ pub fn set(
                 // with a looping version.
             }
 
-            // The playback mode selection.
-            widget::Text::new("Playback Mode")
-                .font_size(SMALL_FONT_SIZE)
+            // A range slider for the region of the WAV that is repeated when looping.
+            let region = wav.loop_region();
+            let start_ms = Samples(region.start as _).to_ms(wav.sample_hz);
+            let end_ms = Samples(region.end as _).to_ms(wav.sample_hz);
+            let label = format!(
+                "Loop Region: {} to {}",
+                duration_label(&Ms(start_ms.ms().round())),
+                duration_label(&Ms(end_ms.ms().round())),
+            );
+            let total_frames = wav.duration.samples() as f64;
+            for (edge, value) in widget::RangeSlider::new(
+                    region.start as f64,
+                    region.end as f64,
+                    0.0,
+                    total_frames,
+                )
+                .label(&label)
+                .label_font_size(SMALL_FONT_SIZE)
+                .color(color::LIGHT_CHARCOAL)
                 .down(PAD)
-                .align_left_of(ids.source_editor_selected_wav_text)
-                .set(ids.source_editor_selected_wav_playback_text, ui);
-            let item_w = canvas_kid_area.w() * 0.5;
-            let n_items = audio::source::wav::NUM_PLAYBACK_OPTIONS;
-            let (mut events, _scrollbar) = widget::ListSelect::single(n_items)
-                .flow_right()
-                .item_size(item_w)
-                .down(PAD * 2.0)
-                .h(PLAYBACK_MODE_H)
+                .h(LOOP_REGION_H)
                 .w(canvas_kid_area.w())
-                .mid_bottom_of(ids.source_editor_selected_wav_canvas)
-                .set(ids.source_editor_selected_wav_playback_list, ui);
+                .align_middle_x_of(ids.source_editor_selected_wav_canvas)
+                .set(ids.source_editor_selected_wav_loop_region, ui)
+            {
+                let frame = Samples(value as _);
 
-            fn playback_from_index(i: usize) -> Option<Playback> {
-                match i {
-                    0 => Some(Playback::Retrigger),
-                    1 => Some(Playback::Continuous),
-                    _ => None,
+                // Update the local copy.
+                match edge {
+                    widget::range_slider::Edge::Start => wav.loop_start = frame,
+                    widget::range_slider::Edge::End => wav.loop_end = Some(frame),
+                }
+                let (new_start, new_end) = (wav.loop_start, wav.loop_end);
+
+                // Update the soundscape thread copy.
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            if let audio::source::Kind::Wav(ref mut wav) = source.kind {
+                                wav.loop_start = new_start;
+                                wav.loop_end = new_end;
+                            }
+                        });
+                    })
+                    .expect("failed to send source loop region to soundscape thread");
+            }
+
+            // A slider for the duration of the crossfade between the end and start of the loop.
+            //
+            // The crossfade is limited to half the duration of the loop region.
+            let region = wav.loop_region();
+            let region_frames = Samples(region.end.saturating_sub(region.start) as _);
+            let max_crossfade_ms = region_frames.to_ms(wav.sample_hz).ms() / 2.0;
+            let crossfade_ms = wav.loop_crossfade.ms().min(max_crossfade_ms);
+            let label = format!("Loop Crossfade: {}", duration_label(&Ms(crossfade_ms)));
+            for new_ms in widget::Slider::new(crossfade_ms, 0.0, max_crossfade_ms)
+                .skew(0.5)
+                .label(&label)
+                .label_font_size(SMALL_FONT_SIZE)
+                .color(color::LIGHT_CHARCOAL)
+                .down(PAD)
+                .h(LOOP_CROSSFADE_H)
+                .w(canvas_kid_area.w())
+                .align_middle_x_of(ids.source_editor_selected_wav_canvas)
+                .set(ids.source_editor_selected_wav_loop_crossfade, ui)
+            {
+                let new_crossfade = Ms(new_ms.round());
+
+                // Update the local copy.
+                wav.loop_crossfade = new_crossfade;
+
+                // Update the soundscape thread copy.
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            if let audio::source::Kind::Wav(ref mut wav) = source.kind {
+                                wav.loop_crossfade = new_crossfade;
+                            }
+                        });
+                    })
+                    .expect("failed to send source loop crossfade to soundscape thread");
+            }
+
+            // A text box for mapping the file's channels to the source's channels.
+            //
+            // Each number selects the file channel used for the source channel at that position,
+            // allowing channels to be reordered, dropped or duplicated.
+            let is_editing = match source_editor.channel_map_text {
+                Some((edit_id, _)) => edit_id == id,
+                None => false,
+            };
+            if !is_editing {
+                source_editor.channel_map_text = Some((id, channel_map_text(&wav.channel_map)));
+            }
+            let text = match source_editor.channel_map_text {
+                Some((_, ref text)) => text.clone(),
+                None => String::new(),
+            };
+            let color = match parse_channel_map(&text, wav.channels) {
+                Ok(_) => color::BLACK,
+                Err(_) => color::DARK_RED,
+            };
+            widget::Text::new("Channel Map (e.g. \"2 1 4 3\", empty for file order)")
+                .font_size(SMALL_FONT_SIZE)
+                .down(PAD)
+                .align_left_of(ids.source_editor_selected_wav_text)
+                .set(ids.source_editor_selected_wav_channel_map_text, ui);
+            for event in widget::TextBox::new(&text)
+                .font_size(SMALL_FONT_SIZE)
+                .color(color)
+                .down(PAD)
+                .h(CHANNEL_MAP_H)
+                .w(canvas_kid_area.w())
+                .align_middle_x_of(ids.source_editor_selected_wav_canvas)
+                .set(ids.source_editor_selected_wav_channel_map, ui)
+            {
+                use nannou::ui::widget::text_box::Event;
+                match event {
+                    // When typing, only update the text being edited.
+                    Event::Update(new_text) => {
+                        source_editor.channel_map_text = Some((id, new_text));
+                    },
+                    // Only when enter is pressed do we update the channel map.
+                    Event::Enter => {
+                        let new_map = match parse_channel_map(&text, wav.channels) {
+                            Ok(map) => map,
+                            Err(err) => {
+                                eprintln!("failed to parse channel map: {}", err);
+                                continue;
+                            },
+                        };
+
+                        // Update the local copy.
+                        wav.channel_map = new_map.clone();
+                        source_editor.channel_map_text = Some((id, channel_map_text(&new_map)));
+
+                        // Update the soundscape thread copy.
+                        channels
+                            .soundscape
+                            .send(move |soundscape| {
+                                soundscape.update_source(&id, |source| {
+                                    if let audio::source::Kind::Wav(ref mut wav) = source.kind {
+                                        wav.channel_map = new_map;
+                                    }
+                                });
+                            })
+                            .expect("failed to send source channel map to soundscape thread");
+                    },
+                }
+            }
+
+            // A toggle for automatically setting the volume to reach the target loudness.
+            let label = format!("Normalise to {} LUFS", loudness::TARGET_LUFS);
+            for new_normalise in widget::Toggle::new(wav.normalise)
+                .color(color::LIGHT_CHARCOAL)
+                .label(&label)
+                .label_font_size(SMALL_FONT_SIZE)
+                .down(PAD)
+                .h(NORMALISE_H)
+                .w(canvas_kid_area.w())
+                .align_middle_x_of(ids.source_editor_selected_wav_canvas)
+                .set(ids.source_editor_selected_wav_normalise, ui)
+            {
+                // Update the local copy.
+                wav.normalise = new_normalise;
+
+                // Update the soundscape thread copy.
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            if let audio::source::Kind::Wav(ref mut wav) = source.kind {
+                                wav.normalise = new_normalise;
+                            }
+                        });
+                    })
+                    .expect("failed to send source normalise toggle to soundscape thread");
+
+                // Apply the volume once we're done borrowing the WAV.
+                if new_normalise {
+                    normalised_volume = wav.loudness
+                        .and_then(|l| l.normalised_volume(loudness::TARGET_LUFS));
+                }
+            }
+
+            // The set of files from which one is picked each time a sound is spawned.
+            let label = match wav.variations.files.len() {
+                0 => "Variations: None".to_string(),
+                n => format!("Variations: {} + Primary File", n),
+            };
+            widget::Text::new(&label)
+                .font_size(SMALL_FONT_SIZE)
+                .down(PAD)
+                .align_left_of(ids.source_editor_selected_wav_text)
+                .set(ids.source_editor_selected_wav_variations_text, ui);
+
+            // Add the file of another WAV source with the same number of channels.
+            let candidate_names: Vec<_> = variation_candidates
+                .iter()
+                .map(|&(ref name, _)| name.clone())
+                .collect();
+            for index in widget::DropDownList::new(&candidate_names, None)
+                .label("Add Variation From Source")
+                .label_font_size(SMALL_FONT_SIZE)
+                .scrollbar_on_top()
+                .max_visible_items(5)
+                .color(color::LIGHT_CHARCOAL)
+                .down(PAD * 2.0)
+                .h(VARIATION_ADD_H)
+                .w(canvas_kid_area.w())
+                .align_middle_x_of(ids.source_editor_selected_wav_canvas)
+                .set(ids.source_editor_selected_wav_variations_add, ui)
+            {
+                let path = variation_candidates[index].1.clone();
+                let variation = match Variation::from_path(path.clone()) {
+                    Ok(variation) => variation,
+                    Err(err) => {
+                        eprintln!("failed to add variation \"{}\": {}", path.display(), err);
+                        continue;
+                    },
+                };
+
+                // Update the local copy.
+                wav.variations.files.push(variation.clone());
+
+                // Update the soundscape thread copy.
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            if let audio::source::Kind::Wav(ref mut wav) = source.kind {
+                                wav.variations.files.push(variation);
+                            }
+                        });
+                    })
+                    .expect("failed to send new source variation to soundscape thread");
+            }
+
+            // The mode by which a file is picked from the set.
+            let item_w = canvas_kid_area.w() / variations::NUM_SELECTIONS as Scalar;
+            let (mut events, _scrollbar) = widget::ListSelect::single(variations::NUM_SELECTIONS)
+                .flow_right()
+                .item_size(item_w)
+                .down(PAD)
+                .h(VARIATION_SELECTION_H)
+                .w(canvas_kid_area.w())
+                .align_middle_x_of(ids.source_editor_selected_wav_canvas)
+                .set(ids.source_editor_selected_wav_variations_selection, ui);
+            let selected_index = variations::Selection::ALL
+                .iter()
+                .position(|&s| s == wav.variations.selection)
+                .unwrap_or(0);
+            while let Some(event) = events.next(ui, |i| i == selected_index) {
+                use self::ui::widget::list_select::Event;
+                match event {
+                    Event::Item(item) => {
+                        let color = if item.i == selected_index {
+                            color::LIGHT_CHARCOAL
+                        } else {
+                            DARK_A
+                        };
+                        let button = widget::Button::new()
+                            .label(variations::Selection::ALL[item.i].name())
+                            .label_font_size(SMALL_FONT_SIZE)
+                            .color(color);
+                        item.set(button, ui);
+                    },
+                    Event::Selection(new_index) => {
+                        let new_selection = variations::Selection::ALL[new_index];
+
+                        // Update the local copy.
+                        wav.variations.selection = new_selection;
+
+                        // Update the soundscape thread copy.
+                        channels
+                            .soundscape
+                            .send(move |soundscape| {
+                                soundscape.update_source(&id, |source| {
+                                    if let audio::source::Kind::Wav(ref mut wav) = source.kind {
+                                        wav.variations.selection = new_selection;
+                                    }
+                                });
+                            })
+                            .expect("failed to send variation selection to soundscape thread");
+                    },
+                    _ => (),
+                }
+            }
+
+            // A list of the primary file followed by each variation along with their weights.
+            let n_files = wav.variations.files.len() + 1;
+            let (mut items, scrollbar) = widget::List::flow_down(n_files)
+                .item_size(ITEM_HEIGHT)
+                .h(VARIATION_LIST_H)
+                .w(canvas_kid_area.w())
+                .align_middle_x_of(ids.source_editor_selected_wav_canvas)
+                .down(PAD)
+                .scrollbar_next_to()
+                .scrollbar_color(color::LIGHT_CHARCOAL)
+                .set(ids.source_editor_selected_wav_variations_list, ui);
+            let mut maybe_remove_index = None;
+            while let Some(item) = items.next(ui) {
+                let (weight, label) = {
+                    let (path, weight, missing) = match item.i {
+                        0 => (&wav.path, wav.variations.primary_weight, wav.missing),
+                        i => {
+                            let variation = &wav.variations.files[i - 1];
+                            (&variation.path, variation.weight, variation.missing)
+                        },
+                    };
+                    let file_name = path.file_name().and_then(OsStr::to_str).unwrap_or("");
+                    let label = match missing {
+                        true => format!("{} (MISSING) - Weight: {:.2}", file_name, weight),
+                        false => format!("{} - Weight: {:.2}", file_name, weight),
+                    };
+                    (weight, label)
+                };
+                let slider = widget::Slider::new(weight, 0.0, 1.0)
+                    .label(&label)
+                    .label_font_size(SMALL_FONT_SIZE)
+                    .color(color::LIGHT_CHARCOAL);
+                if let Some(new_weight) = item.set(slider, ui) {
+                    let i = item.i;
+
+                    // Update the local copy.
+                    match i {
+                        0 => wav.variations.primary_weight = new_weight,
+                        i => wav.variations.files[i - 1].weight = new_weight,
+                    }
+
+                    // Update the soundscape thread copy.
+                    channels
+                        .soundscape
+                        .send(move |soundscape| {
+                            soundscape.update_source(&id, |source| {
+                                if let audio::source::Kind::Wav(ref mut wav) = source.kind {
+                                    match i {
+                                        0 => wav.variations.primary_weight = new_weight,
+                                        i => wav.variations.files[i - 1].weight = new_weight,
+                                    }
+                                }
+                            });
+                        })
+                        .expect("failed to send variation weight to soundscape thread");
+                }
+
+                // The primary file may not be removed.
+                if item.i == 0 {
+                    continue;
+                }
+
+                // If the slider is capturing the mouse, display the `remove` button.
+                let show_remove_button = ui.global_input()
+                    .current
+                    .widget_capturing_mouse
+                    .map(|id| {
+                        id == item.widget_id
+                            || ui.widget_graph()
+                                .does_recursive_depth_edge_exist(item.widget_id, id)
+                    })
+                    .unwrap_or(false);
+
+                if !show_remove_button {
+                    continue;
+                }
+
+                if widget::Button::new()
+                    .label("X")
+                    .label_font_size(SMALL_FONT_SIZE)
+                    .color(color::DARK_RED.alpha(0.5))
+                    .w_h(ITEM_HEIGHT, ITEM_HEIGHT)
+                    .align_right_of(item.widget_id)
+                    .align_middle_y_of(item.widget_id)
+                    .parent(item.widget_id)
+                    .set(ids.source_editor_selected_wav_variations_remove, ui)
+                    .was_clicked()
+                {
+                    maybe_remove_index = Some(item.i - 1);
+                }
+            }
+
+            // The scrollbar for the list.
+            if let Some(scrollbar) = scrollbar {
+                scrollbar.set(ui);
+            }
+
+            // If some variation was clicked for removal, remove it.
+            if let Some(i) = maybe_remove_index {
+                // Remove the local copy.
+                wav.variations.files.remove(i);
+
+                // Remove the soundscape copy.
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            if let audio::source::Kind::Wav(ref mut wav) = source.kind {
+                                wav.variations.files.remove(i);
+                            }
+                        });
+                    })
+                    .expect("failed to remove variation from source on soundscape thread");
+            }
+
+            // The playback mode selection.
+            widget::Text::new("Playback Mode")
+                .font_size(SMALL_FONT_SIZE)
+                .down(PAD)
+                .align_left_of(ids.source_editor_selected_wav_text)
+                .set(ids.source_editor_selected_wav_playback_text, ui);
+            let item_w = canvas_kid_area.w() * 0.5;
+            let n_items = audio::source::wav::NUM_PLAYBACK_OPTIONS;
+            let (mut events, _scrollbar) = widget::ListSelect::single(n_items)
+                .flow_right()
+                .item_size(item_w)
+                .down(PAD * 2.0)
+                .h(PLAYBACK_MODE_H)
+                .w(canvas_kid_area.w())
+                .mid_bottom_of(ids.source_editor_selected_wav_canvas)
+                .set(ids.source_editor_selected_wav_playback_list, ui);
+
+            fn playback_from_index(i: usize) -> Option<Playback> {
+                match i {
+                    0 => Some(Playback::Retrigger),
+                    1 => Some(Playback::Continuous),
+                    _ => None,
                 }
             }
 
@@ -869,7 +1977,7 @@ pub fn set(
                 }
             }
 
-            (ids.source_editor_selected_wav_canvas, wav.channels)
+            (ids.source_editor_selected_wav_canvas, wav.output_channels())
         }
         audio::source::Kind::Realtime(ref mut realtime) => {
             // Instantiate a small canvas for displaying wav-specific stuff.
@@ -945,9 +2053,76 @@ pub fn set(
                 update_realtime!(|realtime: &mut audio::source::Realtime| realtime.duration = new_duration);
             }
 
-            // Starting channel index (to the left).
-            let start_channel_indices = 0..realtime.channels.end;
-            let start_channel_labels = start_channel_indices
+            // A text box for naming the input device from which the source captures.
+            //
+            // The first device whose name contains the text is used and its stream is opened on
+            // demand. An empty name refers to the default input device.
+            let is_editing = match source_editor.device_text {
+                Some((edit_id, _)) => edit_id == id,
+                None => false,
+            };
+            if !is_editing {
+                source_editor.device_text = Some((id, realtime.device.clone()));
+            }
+            let text = match source_editor.device_text {
+                Some((_, ref text)) => text.clone(),
+                None => String::new(),
+            };
+            widget::Text::new("Input Device (empty for default)")
+                .font_size(SMALL_FONT_SIZE)
+                .down(PAD)
+                .align_left_of(ids.source_editor_selected_realtime_text)
+                .set(ids.source_editor_selected_realtime_device_text, ui);
+            for event in widget::TextBox::new(&text)
+                .font_size(SMALL_FONT_SIZE)
+                .down(PAD)
+                .h(DEVICE_H)
+                .kid_area_w_of(ids.source_editor_selected_realtime_canvas)
+                .align_middle_x_of(ids.source_editor_selected_realtime_canvas)
+                .set(ids.source_editor_selected_realtime_device, ui)
+            {
+                use nannou::ui::widget::text_box::Event;
+                match event {
+                    // When typing, only update the text being edited.
+                    Event::Update(new_text) => {
+                        source_editor.device_text = Some((id, new_text));
+                    },
+                    // Only when enter is pressed do we switch devices.
+                    Event::Enter => {
+                        let new_device = text.trim().to_string();
+
+                        // Update the local and soundscape copies.
+                        realtime.device = new_device.clone();
+                        source_editor.device_text = Some((id, new_device.clone()));
+                        channels
+                            .soundscape
+                            .send(move |soundscape| {
+                                soundscape.update_source(&id, |source| {
+                                    if let audio::source::Kind::Realtime(ref mut rt) = source.kind {
+                                        rt.device = new_device;
+                                    }
+                                });
+                            })
+                            .expect("failed to send realtime source device to soundscape thread");
+
+                        // Move the source to the stream of its new device.
+                        channels
+                            .audio_input
+                            .insert_source(id, realtime.clone())
+                            .expect("failed to send realtime source to audio input thread");
+                    },
+                }
+            }
+
+            // The number of channels available on the source's device.
+            let device_channels = channels
+                .audio_input
+                .channels(&realtime.device)
+                .unwrap_or(audio_channels.input);
+
+            // Starting channel index (to the left).
+            let start_channel_indices = 0..realtime.channels.end;
+            let start_channel_labels = start_channel_indices
                 .clone()
                 .map(|ch| format!("Start Channel: {}", ch + 1))
                 .collect::<Vec<_>>();
@@ -957,7 +2132,7 @@ pub fn set(
                 .w() / 2.0 - PAD / 2.0;
             for new_start in widget::DropDownList::new(&start_channel_labels, selected_start)
                 .down(PAD)
-                .align_left()
+                .align_left_of(ids.source_editor_selected_realtime_device)
                 .label("Start Channel")
                 .label_font_size(SMALL_FONT_SIZE)
                 .scrollbar_on_top()
@@ -971,7 +2146,7 @@ pub fn set(
             }
 
             // End channel index (to the right).
-            let mut end_channel_indices = realtime.channels.start..audio_channels.input;
+            let mut end_channel_indices = realtime.channels.start..device_channels;
             let end_channel_labels = end_channel_indices
                 .clone()
                 .map(|ch| format!("End Channel: {}", ch + 1))
@@ -994,6 +2169,66 @@ pub fn set(
                 update_realtime!(|rt: &mut audio::source::Realtime| rt.channels.end = new_end);
             }
 
+            // Record the source's channels to a WAV within the audio directory.
+            let is_capturing = match source_editor.capture {
+                Some(ref capture) => capture.source_id() == id,
+                None => false,
+            };
+            let (label, capture_color) = match is_capturing {
+                true => ("STOP CAPTURE", color::DARK_RED),
+                false => ("CAPTURE TO WAV", color::DARK_CHARCOAL),
+            };
+            if widget::Button::new()
+                .label(label)
+                .label_font_size(SMALL_FONT_SIZE)
+                .color(capture_color)
+                .down_from(ids.source_editor_selected_realtime_start_channel, PAD)
+                .align_left_of(ids.source_editor_selected_realtime_start_channel)
+                .kid_area_w_of(ids.source_editor_selected_realtime_canvas)
+                .h(CAPTURE_H)
+                .set(ids.source_editor_selected_realtime_capture, ui)
+                .was_clicked()
+            {
+                capture_clicked = true;
+            }
+
+            // Offer to add the last capture of this source as a new WAV source.
+            if let Some((captured_id, ref path)) = source_editor.captured {
+                if captured_id == id {
+                    let file_name = path
+                        .file_name()
+                        .and_then(OsStr::to_str)
+                        .unwrap_or("capture");
+                    let label = format!("ADD {}", file_name);
+                    if widget::Button::new()
+                        .label(&label)
+                        .label_font_size(SMALL_FONT_SIZE)
+                        .color(color::DARK_GREEN)
+                        .down(PAD)
+                        .align_left()
+                        .w(channel_w)
+                        .h(CAPTURE_H)
+                        .set(ids.source_editor_selected_realtime_capture_add, ui)
+                        .was_clicked()
+                    {
+                        add_captured = true;
+                    }
+                    if widget::Button::new()
+                        .label("DISCARD")
+                        .label_font_size(SMALL_FONT_SIZE)
+                        .color(color::DARK_CHARCOAL)
+                        .right(PAD)
+                        .align_top()
+                        .w(channel_w)
+                        .h(CAPTURE_H)
+                        .set(ids.source_editor_selected_realtime_capture_discard, ui)
+                        .was_clicked()
+                    {
+                        discard_captured = true;
+                    }
+                }
+            }
+
             (
                 ids.source_editor_selected_realtime_canvas,
                 realtime.channels.len(),
@@ -1001,6 +2236,52 @@ pub fn set(
         }
     };
 
+    // If loudness normalisation was enabled, apply the normalised volume.
+    if let Some(volume) = normalised_volume {
+        set_source_volume(channels, id, sources.get_mut(&id).unwrap(), volume);
+    }
+
+    // Stop the capture in progress or begin capturing the selected realtime source.
+    if capture_clicked && stop_capture(source_editor, channels) != Some(id) {
+        let source = &sources[&id];
+        if let audio::source::Kind::Realtime(ref realtime) = source.kind {
+            let audio_path = project::audio_directory(assets);
+            let path = audio::capture::next_capture_path(&audio_path, &source.name);
+            match audio::capture::start(id, realtime, path, &channels.audio_input) {
+                Ok(capture) => source_editor.capture = Some(capture),
+                Err(err) => eprintln!("failed to begin capturing realtime source: {}", err),
+            }
+        }
+    }
+
+    // Create a new WAV source from the last capture.
+    if add_captured {
+        if let Some((_, path)) = source_editor.captured.take() {
+            let name = path
+                .file_stem()
+                .and_then(OsStr::to_str)
+                .unwrap_or("Capture")
+                .to_string();
+            match audio::source::Wav::from_path(path.clone()) {
+                Ok(wav) => {
+                    let new_id = sources.next_id();
+                    sources.insert(new_id, project::Source::from_wav(name, wav));
+                    source_editor.selected = Some(new_id);
+                },
+                Err(err) => eprintln!("failed to load captured WAV {}: {}", path.display(), err),
+            }
+        }
+    }
+
+    // Remove the last capture from disk.
+    if discard_captured {
+        if let Some((_, path)) = source_editor.captured.take() {
+            if let Err(err) = fs::remove_file(&path) {
+                eprintln!("failed to remove captured WAV {}: {}", path.display(), err);
+            }
+        }
+    }
+
     // Channel layout widgets.
     widget::Canvas::new()
         .down_from(kind_canvas_id, PAD)
@@ -1029,26 +2310,25 @@ pub fn set(
         .color(color::DARK_GREEN)
         .set(ids.source_editor_selected_volume_slider, ui)
     {
-        // Update the local copy.
-        sources.get_mut(&id).unwrap().volume = new_volume;
+        let source = sources.get_mut(&id).unwrap();
+        set_source_volume(channels, id, source, new_volume);
 
-        // Update the soundscape copy.
-        channels
-            .soundscape
-            .send(move |soundscape| {
-                soundscape.update_source(&id, |source| source.volume = new_volume);
-            })
-            .expect("failed to send source volume update to soundscape thread");
-
-        // Update the audio output copies.
-        channels
-            .audio_output
-            .send(move |audio| {
-                audio.update_sounds_with_source(&id, move |_, sound| {
-                    sound.volume = new_volume;
-                });
-            })
-            .expect("failed to send source volume update to audio output thread");
+        // Adjusting the volume by hand disables loudness normalisation.
+        if let audio::source::Kind::Wav(ref mut wav) = source.audio.kind {
+            if wav.normalise {
+                wav.normalise = false;
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            if let audio::source::Kind::Wav(ref mut wav) = source.kind {
+                                wav.normalise = false;
+                            }
+                        });
+                    })
+                    .expect("failed to send source normalise toggle to soundscape thread");
+            }
+        }
     }
 
     // Buttons for solo and mute behaviour.
@@ -1133,6 +2413,71 @@ pub fn set(
             .expect("failed to send muted sources update to audio output thread");
     }
 
+    // Enabled button.
+    //
+    // Disabled sources are taken out of the soundscape's rotation without losing their parameters.
+    let enabled = sources[&id].enabled;
+    let label = if enabled { "ENABLED" } else { "DISABLED" };
+    for new_enabled in widget::Toggle::new(enabled)
+        .w(channel_layout_kid_area.w())
+        .h(ENABLED_H)
+        .label(label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .align_left_of(ids.source_editor_selected_solo)
+        .down_from(ids.source_editor_selected_solo, PAD)
+        .color(color::DARK_GREEN)
+        .set(ids.source_editor_selected_enabled, ui)
+    {
+        // Update local copy.
+        sources.get_mut(&id).unwrap().enabled = new_enabled;
+
+        // Update soundscape copy and fade out any of the source's sounds if it was disabled.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_source(&id, |source| source.enabled = new_enabled);
+                soundscape.fade_out_inaudible_sounds();
+            })
+            .expect("failed to send source enabled update to soundscape thread");
+    }
+
+    // Slider for the height above the floor at which the source's sounds are positioned.
+    let height = sources[&id].audio.height;
+    let label = format!("Height: {:.2} metres", height.0);
+    for new_height in widget::Slider::new(height.0, 0.0, audio::MAX_HEIGHT.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .skew(0.5)
+        .w(channel_layout_kid_area.w())
+        .h(SLIDER_H)
+        .align_left_of(ids.source_editor_selected_enabled)
+        .down_from(ids.source_editor_selected_enabled, PAD)
+        .set(ids.source_editor_selected_height, ui)
+    {
+        let height = Metres((new_height * 100.0).round() / 100.0);
+
+        // Update the local copy.
+        sources.get_mut(&id).unwrap().audio.height = height;
+
+        // Update the soundscape copy.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_source(&id, |source| source.height = height);
+            })
+            .expect("failed to send source height to soundscape thread");
+
+        // Update the audio output copies.
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.update_sounds_with_source(&id, move |_, sound| {
+                    sound.position.height = height;
+                });
+            })
+            .expect("failed to send source height to audio output thread");
+    }
+
     // Display the channel layout.
     widget::Text::new("CHANNEL LAYOUT")
         .font_size(SMALL_FONT_SIZE)
@@ -1298,6 +2643,303 @@ pub fn set(
             .set(label_id, ui);
     }
 
+    /////////////
+    // Effects //
+    /////////////
+
+    widget::Canvas::new()
+        .down_from(ids.source_editor_selected_common_canvas, PAD)
+        .h(EFFECTS_CANVAS_H)
+        .w(selected_canvas_kid_area.w())
+        .pad(PAD)
+        .parent(ids.source_editor_selected_canvas)
+        .color(color::CHARCOAL)
+        .set(ids.source_editor_selected_effects_canvas, ui);
+
+    widget::Text::new("EFFECTS")
+        .font_size(SMALL_FONT_SIZE)
+        .top_left_of(ids.source_editor_selected_effects_canvas)
+        .set(ids.source_editor_selected_effects_text, ui);
+
+    let effects_kid_area = ui.kid_area_of(ids.source_editor_selected_effects_canvas).unwrap();
+    let toggle_w = ITEM_HEIGHT * 2.0;
+    let cutoff_slider_w = effects_kid_area.w() - toggle_w - PAD;
+    let cutoff_slider = |hz: f32, enabled: bool| {
+        let color = match enabled {
+            true => color::DARK_PURPLE,
+            false => color::LIGHT_CHARCOAL,
+        };
+        widget::Slider::new(hz, audio::effect::MIN_HZ, audio::effect::MAX_HZ)
+            .skew(0.2)
+            .label_font_size(SMALL_FONT_SIZE)
+            .w(cutoff_slider_w)
+            .h(SLIDER_H)
+            .color(color)
+    };
+    let filter_toggle = |enabled: bool, label: &'static str| {
+        widget::Toggle::new(enabled)
+            .label(label)
+            .label_font_size(SMALL_FONT_SIZE)
+            .w(toggle_w)
+            .h(SLIDER_H)
+            .color(color::DARK_PURPLE)
+    };
+
+    // High pass filter.
+    let effects = sources[&id].effects;
+    for enabled in filter_toggle(effects.high_pass.enabled, "HP")
+        .align_left()
+        .down(PAD * 1.5)
+        .set(ids.source_editor_selected_effects_high_pass_toggle, ui)
+    {
+        let mut effects = sources[&id].effects;
+        effects.high_pass.enabled = enabled;
+        set_source_effects(channels, id, sources.get_mut(&id).unwrap(), effects);
+    }
+    let label = format!("High Pass: {:.0} Hz", effects.high_pass.hz);
+    for hz in cutoff_slider(effects.high_pass.hz, effects.high_pass.enabled)
+        .label(&label)
+        .right(PAD)
+        .set(ids.source_editor_selected_effects_high_pass_slider, ui)
+    {
+        let mut effects = sources[&id].effects;
+        effects.high_pass.hz = hz.round();
+        set_source_effects(channels, id, sources.get_mut(&id).unwrap(), effects);
+    }
+
+    // Low pass filter.
+    let effects = sources[&id].effects;
+    for enabled in filter_toggle(effects.low_pass.enabled, "LP")
+        .align_left_of(ids.source_editor_selected_effects_high_pass_toggle)
+        .down_from(ids.source_editor_selected_effects_high_pass_toggle, PAD)
+        .set(ids.source_editor_selected_effects_low_pass_toggle, ui)
+    {
+        let mut effects = sources[&id].effects;
+        effects.low_pass.enabled = enabled;
+        set_source_effects(channels, id, sources.get_mut(&id).unwrap(), effects);
+    }
+    let label = format!("Low Pass: {:.0} Hz", effects.low_pass.hz);
+    for hz in cutoff_slider(effects.low_pass.hz, effects.low_pass.enabled)
+        .label(&label)
+        .right(PAD)
+        .set(ids.source_editor_selected_effects_low_pass_slider, ui)
+    {
+        let mut effects = sources[&id].effects;
+        effects.low_pass.hz = hz.round();
+        set_source_effects(channels, id, sources.get_mut(&id).unwrap(), effects);
+    }
+
+    // The three band EQ.
+    widget::Text::new("EQ")
+        .font_size(SMALL_FONT_SIZE)
+        .align_left_of(ids.source_editor_selected_effects_low_pass_toggle)
+        .down_from(ids.source_editor_selected_effects_low_pass_toggle, PAD * 1.5)
+        .set(ids.source_editor_selected_effects_eq_text, ui);
+
+    let eq_slider_w = (effects_kid_area.w() - PAD * 2.0) / 3.0;
+    let eq_slider = |db: f32| {
+        let max = audio::effect::MAX_EQ_DB;
+        widget::Slider::new(db, -max, max)
+            .label_font_size(SMALL_FONT_SIZE)
+            .w(eq_slider_w)
+            .h(SLIDER_H)
+            .color(color::DARK_PURPLE)
+    };
+    // Round to the nearest half decibel so that each band is easy to return to `0`.
+    let round_db = |db: f32| (db * 2.0).round() / 2.0;
+
+    let effects = sources[&id].effects;
+    let label = format!("Low: {:+.1} dB", effects.eq.low_db);
+    for db in eq_slider(effects.eq.low_db)
+        .label(&label)
+        .align_left()
+        .down(PAD * 1.5)
+        .set(ids.source_editor_selected_effects_eq_low, ui)
+    {
+        let mut effects = sources[&id].effects;
+        effects.eq.low_db = round_db(db);
+        set_source_effects(channels, id, sources.get_mut(&id).unwrap(), effects);
+    }
+    let label = format!("Mid: {:+.1} dB", effects.eq.mid_db);
+    for db in eq_slider(effects.eq.mid_db)
+        .label(&label)
+        .right(PAD)
+        .set(ids.source_editor_selected_effects_eq_mid, ui)
+    {
+        let mut effects = sources[&id].effects;
+        effects.eq.mid_db = round_db(db);
+        set_source_effects(channels, id, sources.get_mut(&id).unwrap(), effects);
+    }
+    let label = format!("High: {:+.1} dB", effects.eq.high_db);
+    for db in eq_slider(effects.eq.high_db)
+        .label(&label)
+        .right(PAD)
+        .set(ids.source_editor_selected_effects_eq_high, ui)
+    {
+        let mut effects = sources[&id].effects;
+        effects.eq.high_db = round_db(db);
+        set_source_effects(channels, id, sources.get_mut(&id).unwrap(), effects);
+    }
+
+    // The level at which the source is sent to the global and installation reverb buses.
+    let half_w = (effects_kid_area.w() - PAD) / 2.0;
+    let reverb_send = sources[&id].reverb_send;
+    let label = format!("Reverb Send: {:.2}", reverb_send);
+    for new_send in widget::Slider::new(reverb_send, 0.0, 1.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(half_w)
+        .h(SLIDER_H)
+        .color(color::DARK_PURPLE)
+        .align_left_of(ids.source_editor_selected_effects_eq_low)
+        .down_from(ids.source_editor_selected_effects_eq_low, PAD)
+        .set(ids.source_editor_selected_effects_reverb_send, ui)
+    {
+        // Update the local copy.
+        sources.get_mut(&id).unwrap().reverb_send = new_send;
+
+        // Update the soundscape copy.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_source(&id, |source| source.reverb_send = new_send);
+            })
+            .expect("failed to send source reverb send update to soundscape thread");
+
+        // Update the audio output copies.
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.update_sounds_with_source(&id, move |_, sound| {
+                    sound.reverb_send = new_send;
+                });
+            })
+            .expect("failed to send source reverb send update to audio output thread");
+    }
+
+    // Whether sounds are attenuated and low-passed with distance via the master distance model.
+    let distance_model = sources[&id].distance_model;
+    for new_distance_model in widget::Toggle::new(distance_model)
+        .label("Distance Model")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::DARK_PURPLE)
+        .w(half_w)
+        .h(SLIDER_H)
+        .right(PAD)
+        .set(ids.source_editor_selected_effects_distance_model, ui)
+    {
+        // Update the local copy.
+        sources.get_mut(&id).unwrap().distance_model = new_distance_model;
+
+        // Update the soundscape copy.
+        channels
+            .soundscape
+            .send(move |soundscape| {
+                soundscape.update_source(&id, |source| source.distance_model = new_distance_model);
+            })
+            .expect("failed to send source distance model update to soundscape thread");
+
+        // Update the audio output copies.
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.update_sounds_with_source(&id, move |_, sound| {
+                    sound.distance_model = new_distance_model;
+                });
+            })
+            .expect("failed to send source distance model update to audio output thread");
+    }
+
+    // Optionally override the master DBAP rolloff so that point-like sources may be focused
+    // while beds stay wide.
+    let dbap_rolloff_db = sources[&id].dbap_rolloff_db;
+    for enabled in filter_toggle(dbap_rolloff_db.is_some(), "OWN")
+        .align_left_of(ids.source_editor_selected_effects_reverb_send)
+        .down_from(ids.source_editor_selected_effects_reverb_send, PAD)
+        .set(ids.source_editor_selected_effects_dbap_rolloff_toggle, ui)
+    {
+        let new_rolloff = match enabled {
+            true => Some(master.dbap_rolloff_db),
+            false => None,
+        };
+        set_source_dbap_rolloff(channels, id, sources.get_mut(&id).unwrap(), new_rolloff);
+    }
+    let label = match dbap_rolloff_db {
+        Some(db) => format!("DBAP Rolloff: {:.2} dB", db),
+        None => format!("DBAP Rolloff: {:.2} dB (Master)", master.dbap_rolloff_db),
+    };
+    let color = match dbap_rolloff_db {
+        Some(_) => color::DARK_PURPLE,
+        None => color::LIGHT_CHARCOAL,
+    };
+    let db = dbap_rolloff_db.unwrap_or(master.dbap_rolloff_db);
+    for new_db in widget::Slider::new(db, 1.0, 6.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .w(cutoff_slider_w)
+        .h(SLIDER_H)
+        .color(color)
+        .right(PAD)
+        .set(ids.source_editor_selected_effects_dbap_rolloff_slider, ui)
+    {
+        set_source_dbap_rolloff(channels, id, sources.get_mut(&id).unwrap(), Some(new_db));
+    }
+
+    // Restrict the speakers from which the source may be heard, e.g. so that narration never
+    // comes from speakers hidden inside sculptures.
+    let mut speaker_ids: Vec<_> = speakers.keys().cloned().collect();
+    speaker_ids.sort_by(|a, b| speakers[a].name.cmp(&speakers[b].name));
+    let speaker_filter = sources[&id].speaker_filter.clone();
+    let speaker_labels = |set: &FxHashSet<audio::speaker::Id>| -> Vec<String> {
+        speaker_ids
+            .iter()
+            .map(|s| {
+                let check = if set.contains(s) { "[x]" } else { "[ ]" };
+                format!("{} {}", check, speakers[s].name)
+            })
+            .collect()
+    };
+    let filter_label = |title: &str, set: &FxHashSet<audio::speaker::Id>, empty: &str| {
+        match set.len() {
+            0 => format!("{}: {}", title, empty),
+            1 => format!("{}: 1 Speaker", title),
+            n => format!("{}: {} Speakers", title, n),
+        }
+    };
+    let include_labels = speaker_labels(&speaker_filter.include);
+    let include_label = filter_label("Only From", &speaker_filter.include, "All");
+    for index in widget::DropDownList::new(&include_labels, None)
+        .label(&include_label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .scrollbar_on_top()
+        .max_visible_items(5)
+        .color(color::DARK_PURPLE)
+        .w(half_w)
+        .h(SLIDER_H)
+        .align_left_of(ids.source_editor_selected_effects_reverb_send)
+        .down_from(ids.source_editor_selected_effects_dbap_rolloff_toggle, PAD)
+        .set(ids.source_editor_selected_effects_speakers_include, ui)
+    {
+        let speaker = speaker_ids[index];
+        toggle_source_speaker(channels, id, sources.get_mut(&id).unwrap(), speaker, false);
+    }
+    let exclude_labels = speaker_labels(&speaker_filter.exclude);
+    let exclude_label = filter_label("Never From", &speaker_filter.exclude, "None");
+    for index in widget::DropDownList::new(&exclude_labels, None)
+        .label(&exclude_label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .scrollbar_on_top()
+        .max_visible_items(5)
+        .color(color::DARK_PURPLE)
+        .w(half_w)
+        .h(SLIDER_H)
+        .right(PAD)
+        .set(ids.source_editor_selected_effects_speakers_exclude, ui)
+    {
+        let speaker = speaker_ids[index];
+        toggle_source_speaker(channels, id, sources.get_mut(&id).unwrap(), speaker, true);
+    }
+
     ///////////////////
     // Role-specific //
     ///////////////////
@@ -1315,6 +2957,14 @@ pub fn set(
                 attack_duration,
                 release_duration,
                 movement,
+                effect_randomisation,
+                follow,
+                priority,
+                day_gain,
+                weight,
+                no_repeat,
+                cooldown,
+                follow_ups,
             } = soundscape;
 
             // A canvas on which installation selection widgets are instantiated.
@@ -1323,7 +2973,7 @@ pub fn set(
                 .h(INSTALLATIONS_CANVAS_H)
                 .align_middle_x_of(ids.source_editor_selected_canvas)
                 .parent(ids.source_editor_selected_canvas)
-                .down_from(ids.source_editor_selected_common_canvas, PAD)
+                .down_from(ids.source_editor_selected_effects_canvas, PAD)
                 .pad(PAD)
                 .color(color::CHARCOAL)
                 .set(ids.source_editor_selected_installations_canvas, ui);
@@ -1621,219 +3271,812 @@ pub fn set(
                 // Update the local copy.
                 let new_num = {
                     let soundscape = expect_soundscape_mut(sources, &id);
-                    match edge {
-                        widget::range_slider::Edge::Start => {
-                            soundscape.simultaneous_sounds.min = num;
-                        },
-                        widget::range_slider::Edge::End => {
-                            soundscape.simultaneous_sounds.max = num;
-                        }
-                    }
-                    soundscape.simultaneous_sounds
+                    match edge {
+                        widget::range_slider::Edge::Start => {
+                            soundscape.simultaneous_sounds.min = num;
+                        },
+                        widget::range_slider::Edge::End => {
+                            soundscape.simultaneous_sounds.max = num;
+                        }
+                    }
+                    soundscape.simultaneous_sounds
+                };
+
+                // Update the soundscape copy.
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            source.simultaneous_sounds = new_num;
+                        });
+                    })
+                    .expect("failed to send source simultaenous sounds to soundscape thread");
+            }
+
+            ///////////////////////
+            // Playback Duration //
+            ///////////////////////
+
+            widget::Text::new("Playback Duration")
+                .align_left()
+                .down(PAD * 2.0)
+                .font_size(SMALL_FONT_SIZE)
+                .set(ids.source_editor_selected_soundscape_playback_duration_text, ui);
+
+            // The max duration depends on the kind of source:
+            //
+            // - If it is a non-looping WAV, then the max duration is the length of the WAV.
+            // - If it is a realtime source, then the max is the duration of its realtime data.
+            // - If it is a looping WAV the max is some arbitrary limit.
+            let skew = sources[&id].kind.playback_duration_skew();
+            let max_duration = sources[&id].kind.max_playback_duration();
+            let min_duration = Ms(0.0);
+            let min_duration_ms = min_duration.ms();
+            let max_duration_ms = max_duration.ms();
+            let range = playback_duration;
+            let label = format!("{} to {}", duration_label(&range.min), duration_label(&range.max));
+            let start = range.min.ms() as f64;
+            let end = range.max.ms() as f64;
+            for (edge, value) in range_slider(start, end, min_duration_ms, max_duration_ms)
+                .skew(skew)
+                .align_left()
+                .label(&label)
+                .down(PAD * 2.0)
+                .set(ids.source_editor_selected_soundscape_playback_duration_slider, ui)
+            {
+                let duration = {
+                    let (unit, value) = utils::human_readable_ms(&Ms(value as _));
+                    let (unit, value) = unit.to_finer_unit(value);
+                    unit.to_ms(value.round())
+                };
+
+                // Update the local copy.
+                let new_duration = {
+                    let soundscape = expect_soundscape_mut(sources, &id);
+                    match edge {
+                        widget::range_slider::Edge::Start => {
+                            soundscape.playback_duration.min = duration;
+                        },
+                        widget::range_slider::Edge::End => {
+                            soundscape.playback_duration.max = duration;
+                        }
+                    }
+                    soundscape.playback_duration
+                };
+
+                // Update the soundscape copy.
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            source.playback_duration = new_duration;
+                        });
+                    })
+                    .expect("failed to send new playback duration to source on soundscape thread");
+            }
+
+            /////////////////////
+            // Attack Duration //
+            /////////////////////
+
+            widget::Text::new("Fade-In Duration")
+                .align_left()
+                .down(PAD * 2.0)
+                .font_size(SMALL_FONT_SIZE)
+                .set(ids.source_editor_selected_soundscape_attack_duration_text, ui);
+
+            let min_duration = Ms(0.0);
+            let max_duration = audio::source::MAX_ATTACK_DURATION;
+            let min_duration_ms = min_duration.ms();
+            let max_duration_ms = max_duration.ms();
+            let range = attack_duration;
+            let label = format!("{} to {}", duration_label(&range.min), duration_label(&range.max));
+            let start = range.min.ms() as f64;
+            let end = range.max.ms() as f64;
+            for (edge, value) in range_slider(start, end, min_duration_ms, max_duration_ms)
+                .skew(audio::source::skew::ATTACK)
+                .align_left()
+                .label(&label)
+                .down(PAD * 2.0)
+                .set(ids.source_editor_selected_soundscape_attack_duration_slider, ui)
+            {
+                let duration = {
+                    let (unit, value) = utils::human_readable_ms(&Ms(value as _));
+                    let (unit, value) = unit.to_finer_unit(value);
+                    unit.to_ms(value.round())
+                };
+
+                // Update the local copy.
+                let new_duration = {
+                    let soundscape = expect_soundscape_mut(sources, &id);
+                    match edge {
+                        widget::range_slider::Edge::Start => {
+                            soundscape.attack_duration.min = duration;
+                        },
+                        widget::range_slider::Edge::End => {
+                            soundscape.attack_duration.max = duration;
+                        }
+                    }
+                    soundscape.attack_duration
+                };
+
+                // Update the soundscape copy.
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            source.attack_duration = new_duration;
+                        });
+                    })
+                    .expect("failed to send source attack duration to soundscape thread");
+            }
+
+            //////////////////////
+            // Release Duration //
+            //////////////////////
+
+            widget::Text::new("Fade-Out Duration")
+                .align_left()
+                .down(PAD * 2.0)
+                .font_size(SMALL_FONT_SIZE)
+                .set(ids.source_editor_selected_soundscape_release_duration_text, ui);
+
+            let min_duration = Ms(0.0);
+            let max_duration = audio::source::MAX_RELEASE_DURATION;
+            let min_duration_ms = min_duration.ms();
+            let max_duration_ms = max_duration.ms();
+            let range = release_duration;
+            let label = format!("{} to {}", duration_label(&range.min), duration_label(&range.max));
+            let start = range.min.ms();
+            let end = range.max.ms();
+            for (edge, value) in range_slider(start, end, min_duration_ms, max_duration_ms)
+                .skew(audio::source::skew::RELEASE)
+                .align_left()
+                .label(&label)
+                .down(PAD * 2.0)
+                .set(ids.source_editor_selected_soundscape_release_duration_slider, ui)
+            {
+                let duration = {
+                    let (unit, value) = utils::human_readable_ms(&Ms(value as _));
+                    let (unit, value) = unit.to_finer_unit(value);
+                    unit.to_ms(value.round())
+                };
+
+                // Update the local copy.
+                let new_duration = {
+                    let soundscape = expect_soundscape_mut(sources, &id);
+                    match edge {
+                        widget::range_slider::Edge::Start => {
+                            soundscape.release_duration.min = duration;
+                        },
+                        widget::range_slider::Edge::End => {
+                            soundscape.release_duration.max = duration;
+                        }
+                    }
+                    soundscape.release_duration
+                };
+
+                // Update the soundscape copy.
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            source.release_duration = new_duration;
+                        });
+                    })
+                    .expect("failed to send source release duration to soundscape thread");
+            }
+
+            //////////////////////////
+            // Effect Randomisation //
+            //////////////////////////
+
+            widget::Text::new("Effect Randomisation")
+                .align_left()
+                .down(PAD * 2.0)
+                .font_size(SMALL_FONT_SIZE)
+                .set(ids.source_editor_selected_soundscape_effect_randomisation_text, ui);
+
+            let soundscape_kid_area = ui.kid_area_of(ids.source_editor_selected_soundscape_canvas)
+                .unwrap();
+            let slider_w = soundscape_kid_area.w() / 2.0 - PAD / 2.0;
+            let slider = |value, max| {
+                widget::Slider::new(value, 0.0, max)
+                    .w(slider_w)
+                    .h(SLIDER_H)
+                    .label_font_size(SMALL_FONT_SIZE)
+                    .color(ui::color::LIGHT_CHARCOAL)
+            };
+
+            let octaves = effect_randomisation.cutoff_octaves;
+            let label = format!("Cutoff: ±{:.2} oct", octaves);
+            for new_octaves in slider(octaves, audio::effect::MAX_CUTOFF_OCTAVES)
+                .label(&label)
+                .align_left()
+                .down(PAD * 2.0)
+                .set(ids.source_editor_selected_soundscape_effect_randomisation_cutoff, ui)
+            {
+                let new_octaves = (new_octaves * 20.0).round() / 20.0;
+                let randomisation = {
+                    let soundscape = expect_soundscape_mut(sources, &id);
+                    soundscape.effect_randomisation.cutoff_octaves = new_octaves;
+                    soundscape.effect_randomisation
+                };
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            source.constraints.effect_randomisation = randomisation;
+                        });
+                    })
+                    .expect("failed to send source effect randomisation to soundscape thread");
+            }
+
+            let eq_db = effect_randomisation.eq_db;
+            let label = format!("EQ: ±{:.1} dB", eq_db);
+            for new_eq_db in slider(eq_db, audio::effect::MAX_EQ_DB)
+                .label(&label)
+                .right(PAD)
+                .set(ids.source_editor_selected_soundscape_effect_randomisation_eq, ui)
+            {
+                let new_eq_db = (new_eq_db * 2.0).round() / 2.0;
+                let randomisation = {
+                    let soundscape = expect_soundscape_mut(sources, &id);
+                    soundscape.effect_randomisation.eq_db = new_eq_db;
+                    soundscape.effect_randomisation
+                };
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            source.constraints.effect_randomisation = randomisation;
+                        });
+                    })
+                    .expect("failed to send source effect randomisation to soundscape thread");
+            }
+
+            /////////////////////
+            // Follow Visitors //
+            /////////////////////
+
+            let randomisation_cutoff_id =
+                ids.source_editor_selected_soundscape_effect_randomisation_cutoff;
+            widget::Text::new("Follow Visitors")
+                .align_left_of(randomisation_cutoff_id)
+                .down_from(randomisation_cutoff_id, PAD * 2.0)
+                .font_size(SMALL_FONT_SIZE)
+                .set(ids.source_editor_selected_soundscape_follow_text, ui);
+
+            // Update the local copy of the follow params and send them to the soundscape.
+            fn set_follow<F>(
+                sources: &mut project::SourcesMap,
+                channels: &Channels,
+                id: audio::source::Id,
+                update: F,
+            )
+            where
+                F: FnOnce(&mut audio::source::Follow),
+            {
+                let follow = {
+                    let soundscape = expect_soundscape_mut(sources, &id);
+                    update(&mut soundscape.follow);
+                    soundscape.follow
+                };
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            source.constraints.follow = follow;
+                        });
+                    })
+                    .expect("failed to send source follow params to soundscape thread");
+            }
+
+            let label = if follow.enabled { "ON" } else { "OFF" };
+            for new_enabled in widget::Toggle::new(follow.enabled)
+                .w(slider_w)
+                .h(SLIDER_H)
+                .label(label)
+                .label_font_size(SMALL_FONT_SIZE)
+                .color(ui::color::LIGHT_CHARCOAL)
+                .align_left()
+                .down(PAD * 2.0)
+                .set(ids.source_editor_selected_soundscape_follow_toggle, ui)
+            {
+                set_follow(sources, channels, id, |follow| follow.enabled = new_enabled);
+            }
+
+            let max_followers = follow.max_followers as f32;
+            let label = format!("Max Visitors: {}", follow.max_followers);
+            let max = audio::source::MAX_FOLLOWERS as f32;
+            for new_max in widget::Slider::new(max_followers, 1.0, max)
+                .w(slider_w)
+                .h(SLIDER_H)
+                .label(&label)
+                .label_font_size(SMALL_FONT_SIZE)
+                .color(ui::color::LIGHT_CHARCOAL)
+                .right(PAD)
+                .set(ids.source_editor_selected_soundscape_follow_max, ui)
+            {
+                let new_max = new_max.round() as usize;
+                set_follow(sources, channels, id, |follow| follow.max_followers = new_max);
+            }
+
+            let max_delay_ms = audio::source::MAX_FOLLOW_DELAY.ms() as f32;
+            let delay_slider = |delay: Ms| {
+                widget::Slider::new(delay.ms() as f32, 0.0, max_delay_ms)
+                    .w(slider_w)
+                    .h(SLIDER_H)
+                    .label_font_size(SMALL_FONT_SIZE)
+                    .color(ui::color::LIGHT_CHARCOAL)
+            };
+
+            let label = format!("Spawn After: {}", duration_label(&follow.spawn_delay));
+            let toggle_id = ids.source_editor_selected_soundscape_follow_toggle;
+            for new_ms in delay_slider(follow.spawn_delay)
+                .label(&label)
+                .align_left_of(toggle_id)
+                .down_from(toggle_id, PAD)
+                .set(ids.source_editor_selected_soundscape_follow_spawn_delay, ui)
+            {
+                let delay = Ms((new_ms / 100.0).round() as f64 * 100.0);
+                set_follow(sources, channels, id, |follow| follow.spawn_delay = delay);
+            }
+
+            let label = format!("Release After: {}", duration_label(&follow.release_delay));
+            for new_ms in delay_slider(follow.release_delay)
+                .label(&label)
+                .right(PAD)
+                .set(ids.source_editor_selected_soundscape_follow_release_delay, ui)
+            {
+                let delay = Ms((new_ms / 100.0).round() as f64 * 100.0);
+                set_follow(sources, channels, id, |follow| follow.release_delay = delay);
+            }
+
+            //////////////
+            // Priority //
+            //////////////
+
+            let follow_spawn_delay_id = ids.source_editor_selected_soundscape_follow_spawn_delay;
+            widget::Text::new("Priority")
+                .align_left_of(follow_spawn_delay_id)
+                .down_from(follow_spawn_delay_id, PAD * 2.0)
+                .font_size(SMALL_FONT_SIZE)
+                .set(ids.source_editor_selected_soundscape_priority_text, ui);
+
+            let priority_labels: Vec<_> = (0..audio::source::Priority::VARIANT_COUNT)
+                .map(audio::source::Priority::label_from_index)
+                .collect();
+            for index in widget::DropDownList::new(&priority_labels, Some(priority.to_index()))
+                .kid_area_w_of(ids.source_editor_selected_soundscape_canvas)
+                .h(ITEM_HEIGHT)
+                .label_font_size(SMALL_FONT_SIZE)
+                .scrollbar_on_top()
+                .align_left()
+                .down(PAD * 2.0)
+                .set(ids.source_editor_selected_soundscape_priority, ui)
+            {
+                let new_priority = match audio::source::Priority::from_index(index) {
+                    Some(priority) => priority,
+                    None => continue,
+                };
+                expect_soundscape_mut(sources, &id).priority = new_priority;
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            source.constraints.priority = new_priority;
+                        });
+                    })
+                    .expect("failed to send source priority to soundscape thread");
+            }
+
+            ///////////////
+            // Selection //
+            ///////////////
+
+            let priority_id = ids.source_editor_selected_soundscape_priority;
+            widget::Text::new("Selection")
+                .align_left_of(priority_id)
+                .down_from(priority_id, PAD * 2.0)
+                .font_size(SMALL_FONT_SIZE)
+                .set(ids.source_editor_selected_soundscape_selection_text, ui);
+
+            // The relative likelihood of picking this source among equally suitable sources.
+            let label = format!("Weight: {:.1}", weight);
+            for new_weight in widget::Slider::new(weight, 0.0, audio::source::MAX_WEIGHT)
+                .w(slider_w)
+                .h(SLIDER_H)
+                .label(&label)
+                .label_font_size(SMALL_FONT_SIZE)
+                .color(ui::color::LIGHT_CHARCOAL)
+                .align_left()
+                .down(PAD * 2.0)
+                .set(ids.source_editor_selected_soundscape_weight, ui)
+            {
+                let new_weight = (new_weight * 10.0).round() / 10.0;
+                expect_soundscape_mut(sources, &id).weight = new_weight;
+                channels
+                    .soundscape
+                    .send(move |soundscape| {
+                        soundscape.update_source(&id, |source| {
+                            source.constraints.weight = new_weight;
+                        });
+                    })
+                    .expect("failed to send source weight to soundscape thread");
+            }
+
+            // Update the local copy of the no-repeat window and send it to the soundscape.
+            fn set_no_repeat<F>(
+                sources: &mut project::SourcesMap,
+                channels: &Channels,
+                id: audio::source::Id,
+                update: F,
+            )
+            where
+                F: FnOnce(&mut audio::source::NoRepeat),
+            {
+                let no_repeat = {
+                    let soundscape = expect_soundscape_mut(sources, &id);
+                    update(&mut soundscape.no_repeat);
+                    soundscape.no_repeat
                 };
-
-                // Update the soundscape copy.
                 channels
                     .soundscape
                     .send(move |soundscape| {
                         soundscape.update_source(&id, |source| {
-                            source.simultaneous_sounds = new_num;
+                            source.constraints.no_repeat = no_repeat;
                         });
                     })
-                    .expect("failed to send source simultaenous sounds to soundscape thread");
+                    .expect("failed to send source no-repeat window to soundscape thread");
             }
 
-            ///////////////////////
-            // Playback Duration //
-            ///////////////////////
+            let label = format!("No Repeat Within: {} Sounds", no_repeat.spawns);
+            let max = audio::source::MAX_NO_REPEAT_SPAWNS as f32;
+            for new_spawns in widget::Slider::new(no_repeat.spawns as f32, 0.0, max)
+                .w(slider_w)
+                .h(SLIDER_H)
+                .label(&label)
+                .label_font_size(SMALL_FONT_SIZE)
+                .color(ui::color::LIGHT_CHARCOAL)
+                .right(PAD)
+                .set(ids.source_editor_selected_soundscape_no_repeat_spawns, ui)
+            {
+                let new_spawns = new_spawns.round() as usize;
+                set_no_repeat(sources, channels, id, |no_repeat| no_repeat.spawns = new_spawns);
+            }
 
-            widget::Text::new("Playback Duration")
-                .align_left()
-                .down(PAD * 2.0)
-                .font_size(SMALL_FONT_SIZE)
-                .set(ids.source_editor_selected_soundscape_playback_duration_text, ui);
+            let label = format!("No Repeat For: {}", duration_label(&no_repeat.duration));
+            let max_ms = audio::source::MAX_NO_REPEAT_DURATION.ms() as f32;
+            let weight_id = ids.source_editor_selected_soundscape_weight;
+            for new_ms in widget::Slider::new(no_repeat.duration.ms() as f32, 0.0, max_ms)
+                .w(slider_w)
+                .h(SLIDER_H)
+                .label(&label)
+                .label_font_size(SMALL_FONT_SIZE)
+                .color(ui::color::LIGHT_CHARCOAL)
+                .align_left_of(weight_id)
+                .down_from(weight_id, PAD)
+                .set(ids.source_editor_selected_soundscape_no_repeat_duration, ui)
+            {
+                let duration = Ms((new_ms / 1_000.0).round() as f64 * 1_000.0);
+                set_no_repeat(sources, channels, id, |no_repeat| no_repeat.duration = duration);
+            }
 
-            // The max duration depends on the kind of source:
-            //
-            // - If it is a non-looping WAV, then the max duration is the length of the WAV.
-            // - If it is a looping WAV or a realtime source the max is some arbitrary limit.
-            let skew = sources[&id].kind.playback_duration_skew();
-            let max_duration = match sources[&id].kind {
-                audio::source::Kind::Realtime(ref realtime) => realtime.duration,
-                audio::source::Kind::Wav(ref wav) => match wav.should_loop {
-                    true => audio::source::MAX_PLAYBACK_DURATION,
-                    false => wav.duration.to_ms(audio::SAMPLE_RATE),
-                }
+            // The duration after the source's last sound ends before it may be picked again.
+            let label = match cooldown.ms() {
+                ms if ms <= 0.0 => "Cooldown: Off".to_string(),
+                _ => format!("Cooldown: {}", duration_label(&cooldown)),
             };
-            let min_duration = Ms(0.0);
-            let min_duration_ms = min_duration.ms();
-            let max_duration_ms = max_duration.ms();
-            let range = playback_duration;
-            let label = format!("{} to {}", duration_label(&range.min), duration_label(&range.max));
-            let start = range.min.ms() as f64;
-            let end = range.max.ms() as f64;
-            for (edge, value) in range_slider(start, end, min_duration_ms, max_duration_ms)
-                .skew(skew)
-                .align_left()
+            let max_ms = audio::source::MAX_COOLDOWN.ms() as f32;
+            for new_ms in widget::Slider::new(cooldown.ms() as f32, 0.0, max_ms)
+                .w(slider_w)
+                .h(SLIDER_H)
                 .label(&label)
-                .down(PAD * 2.0)
-                .set(ids.source_editor_selected_soundscape_playback_duration_slider, ui)
+                .label_font_size(SMALL_FONT_SIZE)
+                .color(ui::color::LIGHT_CHARCOAL)
+                .right(PAD)
+                .set(ids.source_editor_selected_soundscape_cooldown, ui)
             {
-                let duration = {
-                    let (unit, value) = utils::human_readable_ms(&Ms(value as _));
-                    let (unit, value) = unit.to_finer_unit(value);
-                    unit.to_ms(value.round())
-                };
+                let new_cooldown = Ms((new_ms / 1_000.0).round() as f64 * 1_000.0);
 
                 // Update the local copy.
-                let new_duration = {
-                    let soundscape = expect_soundscape_mut(sources, &id);
-                    match edge {
-                        widget::range_slider::Edge::Start => {
-                            soundscape.playback_duration.min = duration;
-                        },
-                        widget::range_slider::Edge::End => {
-                            soundscape.playback_duration.max = duration;
-                        }
-                    }
-                    soundscape.playback_duration
-                };
+                expect_soundscape_mut(sources, &id).cooldown = new_cooldown;
 
                 // Update the soundscape copy.
                 channels
                     .soundscape
                     .send(move |soundscape| {
                         soundscape.update_source(&id, |source| {
-                            source.playback_duration = new_duration;
+                            source.constraints.cooldown = new_cooldown;
                         });
                     })
-                    .expect("failed to send new playback duration to source on soundscape thread");
+                    .expect("failed to send source cooldown to soundscape thread");
             }
 
-            /////////////////////
-            // Attack Duration //
-            /////////////////////
+            ////////////////
+            // Follow-Ups //
+            ////////////////
 
-            widget::Text::new("Fade-In Duration")
-                .align_left()
-                .down(PAD * 2.0)
+            let no_repeat_duration_id = ids.source_editor_selected_soundscape_no_repeat_duration;
+            widget::Text::new("Follow-Ups (Spawned Near Where Each Sound Ends)")
+                .align_left_of(no_repeat_duration_id)
+                .down_from(no_repeat_duration_id, PAD * 2.0)
                 .font_size(SMALL_FONT_SIZE)
-                .set(ids.source_editor_selected_soundscape_attack_duration_text, ui);
-
-            let min_duration = Ms(0.0);
-            let max_duration = audio::source::MAX_ATTACK_DURATION;
-            let min_duration_ms = min_duration.ms();
-            let max_duration_ms = max_duration.ms();
-            let range = attack_duration;
-            let label = format!("{} to {}", duration_label(&range.min), duration_label(&range.max));
-            let start = range.min.ms() as f64;
-            let end = range.max.ms() as f64;
-            for (edge, value) in range_slider(start, end, min_duration_ms, max_duration_ms)
-                .skew(audio::source::skew::ATTACK)
-                .align_left()
-                .label(&label)
-                .down(PAD * 2.0)
-                .set(ids.source_editor_selected_soundscape_attack_duration_slider, ui)
+                .set(ids.source_editor_selected_soundscape_follow_ups_text, ui);
+
+            // Update the follow-up spawning from the given source, adding it if necessary, and
+            // send the source's follow-ups to the soundscape.
+            fn set_follow_up<F>(
+                sources: &mut project::SourcesMap,
+                channels: &Channels,
+                id: audio::source::Id,
+                follow_up_source: audio::source::Id,
+                update: F,
+            )
+            where
+                F: FnOnce(&mut Vec<audio::source::FollowUp>, usize),
             {
-                let duration = {
-                    let (unit, value) = utils::human_readable_ms(&Ms(value as _));
-                    let (unit, value) = unit.to_finer_unit(value);
-                    unit.to_ms(value.round())
-                };
-
-                // Update the local copy.
-                let new_duration = {
-                    let soundscape = expect_soundscape_mut(sources, &id);
-                    match edge {
-                        widget::range_slider::Edge::Start => {
-                            soundscape.attack_duration.min = duration;
+                let follow_ups = {
+                    let follow_ups = &mut expect_soundscape_mut(sources, &id).follow_ups;
+                    let index = match follow_ups.iter().position(|f| f.source == follow_up_source) {
+                        Some(index) => index,
+                        None => {
+                            follow_ups.push(audio::source::FollowUp::new(follow_up_source));
+                            follow_ups.len() - 1
                         },
-                        widget::range_slider::Edge::End => {
-                            soundscape.attack_duration.max = duration;
-                        }
-                    }
-                    soundscape.attack_duration
+                    };
+                    update(follow_ups, index);
+                    follow_ups.clone()
                 };
-
-                // Update the soundscape copy.
                 channels
                     .soundscape
                     .send(move |soundscape| {
                         soundscape.update_source(&id, |source| {
-                            source.attack_duration = new_duration;
+                            source.constraints.follow_ups = follow_ups;
                         });
                     })
-                    .expect("failed to send source attack duration to soundscape thread");
+                    .expect("failed to send source follow-ups to soundscape thread");
             }
 
-            //////////////////////
-            // Release Duration //
-            //////////////////////
-
-            widget::Text::new("Fade-Out Duration")
+            // Soundscape sources from which follow-ups may be spawned, marking those in use.
+            let mut follow_up_sources: Vec<_> = sources
+                .iter()
+                .filter(|&(_, source)| match source.role {
+                    Some(Role::Soundscape(_)) => true,
+                    _ => false,
+                })
+                .map(|(&source_id, source)| (source_id, source.name.clone()))
+                .collect();
+            follow_up_sources.sort_by(|a, b| a.1.cmp(&b.1));
+            let labels: Vec<_> = follow_up_sources
+                .iter()
+                .map(|&(source_id, ref name)| {
+                    match follow_ups.iter().find(|f| f.source == source_id) {
+                        Some(f) => format!("{} ({:.0}%)", name, f.probability * 100.0),
+                        None => name.clone(),
+                    }
+                })
+                .collect();
+            let selected = follow_up_sources
+                .iter()
+                .position(|&(source_id, _)| source_editor.follow_up_source == Some(source_id));
+            for index in widget::DropDownList::new(&labels, selected)
+                .w(slider_w)
+                .h(ITEM_HEIGHT)
+                .label("Select Source")
+                .label_font_size(SMALL_FONT_SIZE)
+                .scrollbar_on_top()
+                .max_visible_items(5)
                 .align_left()
                 .down(PAD * 2.0)
-                .font_size(SMALL_FONT_SIZE)
-                .set(ids.source_editor_selected_soundscape_release_duration_text, ui);
+                .set(ids.source_editor_selected_soundscape_follow_ups_source, ui)
+            {
+                source_editor.follow_up_source = Some(follow_up_sources[index].0);
+            }
 
-            let min_duration = Ms(0.0);
-            let max_duration = audio::source::MAX_RELEASE_DURATION;
-            let min_duration_ms = min_duration.ms();
-            let max_duration_ms = max_duration.ms();
-            let range = release_duration;
-            let label = format!("{} to {}", duration_label(&range.min), duration_label(&range.max));
-            let start = range.min.ms();
-            let end = range.max.ms();
-            for (edge, value) in range_slider(start, end, min_duration_ms, max_duration_ms)
-                .skew(audio::source::skew::RELEASE)
-                .align_left()
+            // The follow-up spawning from the selected source, if any.
+            let follow_up_source = selected.map(|index| follow_up_sources[index].0);
+            let follow_up = follow_up_source
+                .and_then(|source_id| follow_ups.iter().find(|f| f.source == source_id))
+                .cloned();
+
+            // A probability of zero removes the follow-up.
+            let probability = follow_up.map(|f| f.probability).unwrap_or(0.0);
+            let label = match follow_up {
+                Some(_) => format!("Probability: {:.0}%", probability * 100.0),
+                None => "Probability: Off".to_string(),
+            };
+            for new_probability in widget::Slider::new(probability, 0.0, 1.0)
+                .w(slider_w)
+                .h(SLIDER_H)
                 .label(&label)
-                .down(PAD * 2.0)
-                .set(ids.source_editor_selected_soundscape_release_duration_slider, ui)
+                .label_font_size(SMALL_FONT_SIZE)
+                .color(ui::color::LIGHT_CHARCOAL)
+                .right(PAD)
+                .set(ids.source_editor_selected_soundscape_follow_ups_probability, ui)
             {
-                let duration = {
-                    let (unit, value) = utils::human_readable_ms(&Ms(value as _));
-                    let (unit, value) = unit.to_finer_unit(value);
-                    unit.to_ms(value.round())
+                let follow_up_source = match follow_up_source {
+                    Some(source_id) => source_id,
+                    None => continue,
                 };
+                let new_probability = (new_probability * 100.0).round() / 100.0;
+                set_follow_up(sources, channels, id, follow_up_source, |follow_ups, index| {
+                    match new_probability > 0.0 {
+                        true => follow_ups[index].probability = new_probability,
+                        false => {
+                            follow_ups.remove(index);
+                        },
+                    }
+                });
+            }
 
-                // Update the local copy.
-                let new_duration = {
-                    let soundscape = expect_soundscape_mut(sources, &id);
+            let delay = follow_up
+                .map(|f| f.delay)
+                .unwrap_or(audio::source::default::FOLLOW_UP_DELAY);
+            let label = format!(
+                "Delay: {} to {}",
+                duration_label(&delay.min),
+                duration_label(&delay.max),
+            );
+            let (start, end) = (delay.min.ms(), delay.max.ms());
+            let max_ms = audio::source::MAX_FOLLOW_UP_DELAY.ms();
+            let follow_ups_source_id = ids.source_editor_selected_soundscape_follow_ups_source;
+            for (edge, value) in widget::RangeSlider::new(start, end, 0.0, max_ms)
+                .w(slider_w)
+                .h(SLIDER_H)
+                .label(&label)
+                .label_font_size(SMALL_FONT_SIZE)
+                .color(ui::color::LIGHT_CHARCOAL)
+                .align_left_of(follow_ups_source_id)
+                .down_from(follow_ups_source_id, PAD)
+                .set(ids.source_editor_selected_soundscape_follow_ups_delay, ui)
+            {
+                let follow_up_source = match (follow_up, follow_up_source) {
+                    (Some(_), Some(source_id)) => source_id,
+                    _ => continue,
+                };
+                let ms = Ms((value / 100.0).round() * 100.0);
+                set_follow_up(sources, channels, id, follow_up_source, |follow_ups, index| {
+                    let delay = &mut follow_ups[index].delay;
                     match edge {
-                        widget::range_slider::Edge::Start => {
-                            soundscape.release_duration.min = duration;
-                        },
-                        widget::range_slider::Edge::End => {
-                            soundscape.release_duration.max = duration;
-                        }
+                        widget::range_slider::Edge::Start => delay.min = ms,
+                        widget::range_slider::Edge::End => delay.max = ms,
                     }
-                    soundscape.release_duration
+                });
+            }
+
+            let radius = follow_up
+                .map(|f| f.radius)
+                .unwrap_or(audio::source::default::FOLLOW_UP_RADIUS);
+            let label = format!("Radius: {:.1}m", radius.0);
+            let max = audio::source::MAX_FOLLOW_UP_RADIUS.0 as f32;
+            for new_radius in widget::Slider::new(radius.0 as f32, 0.0, max)
+                .w(slider_w)
+                .h(SLIDER_H)
+                .label(&label)
+                .label_font_size(SMALL_FONT_SIZE)
+                .color(ui::color::LIGHT_CHARCOAL)
+                .right(PAD)
+                .set(ids.source_editor_selected_soundscape_follow_ups_radius, ui)
+            {
+                let follow_up_source = match (follow_up, follow_up_source) {
+                    (Some(_), Some(source_id)) => source_id,
+                    _ => continue,
                 };
+                let new_radius = Metres((new_radius as f64 * 10.0).round() / 10.0);
+                set_follow_up(sources, channels, id, follow_up_source, |follow_ups, index| {
+                    follow_ups[index].radius = new_radius;
+                });
+            }
 
-                // Update the soundscape copy.
+            //////////////////////
+            // Time Of Day Gain //
+            //////////////////////
+
+            let follow_ups_delay_id = ids.source_editor_selected_soundscape_follow_ups_delay;
+            widget::Text::new("Time Of Day Gain (Venue Time, e.g. \"09:00=1, 21:00=0.2\")")
+                .align_left_of(follow_ups_delay_id)
+                .down_from(follow_ups_delay_id, PAD * 2.0)
+                .font_size(SMALL_FONT_SIZE)
+                .set(ids.source_editor_selected_soundscape_day_gain_text, ui);
+
+            // A text box of `HH:MM=gain` points, interpolated between and around midnight.
+            let is_editing = match source_editor.day_gain_text {
+                Some((edit_id, _)) => edit_id == id,
+                None => false,
+            };
+            if !is_editing {
+                let text = audio::source::day_gain::points_string(&day_gain.points);
+                source_editor.day_gain_text = Some((id, text));
+            }
+            let text = match source_editor.day_gain_text {
+                Some((_, ref text)) => text.clone(),
+                None => String::new(),
+            };
+            let color = match audio::source::day_gain::parse_points(&text) {
+                Ok(ref points) if *points == day_gain.points => color::BLACK,
+                Ok(_) => color::DARK_GREEN,
+                Err(_) => color::DARK_RED,
+            };
+            let kid_w = ui.kid_area_of(ids.source_editor_selected_soundscape_canvas).unwrap().w();
+            let toggle_w = ITEM_HEIGHT * 4.0;
+            for event in widget::TextBox::new(&text)
+                .font_size(SMALL_FONT_SIZE)
+                .color(color)
+                .w(kid_w - toggle_w - PAD)
+                .h(ITEM_HEIGHT)
+                .align_left()
+                .down(PAD * 2.0)
+                .set(ids.source_editor_selected_soundscape_day_gain, ui)
+            {
+                use nannou::ui::widget::text_box::Event;
+                match event {
+                    // When typing, only update the text being edited.
+                    Event::Update(new_text) => {
+                        source_editor.day_gain_text = Some((id, new_text));
+                    },
+                    // Only when enter is pressed do we update the curve.
+                    Event::Enter => {
+                        let points = match audio::source::day_gain::parse_points(&text) {
+                            Ok(points) => points,
+                            Err(err) => {
+                                eprintln!("failed to parse time of day gain: {}", err);
+                                continue;
+                            },
+                        };
+
+                        // Update the local copy.
+                        let text = audio::source::day_gain::points_string(&points);
+                        source_editor.day_gain_text = Some((id, text));
+                        expect_soundscape_mut(sources, &id).day_gain.points = points.clone();
+
+                        // Update the soundscape thread copy.
+                        channels
+                            .soundscape
+                            .send(move |soundscape| {
+                                soundscape.update_source(&id, |source| {
+                                    source.constraints.day_gain.points = points;
+                                });
+                            })
+                            .expect("failed to send source day gain to soundscape thread");
+                    },
+                }
+            }
+
+            // Whether the gain of active sounds follows the curve rather than only at spawn.
+            for new_continuous in widget::Toggle::new(day_gain.continuous)
+                .w(toggle_w)
+                .h(ITEM_HEIGHT)
+                .label("CONTINUOUS")
+                .label_font_size(SMALL_FONT_SIZE)
+                .color(ui::color::LIGHT_CHARCOAL)
+                .right(PAD)
+                .set(ids.source_editor_selected_soundscape_day_gain_continuous, ui)
+            {
+                expect_soundscape_mut(sources, &id).day_gain.continuous = new_continuous;
                 channels
                     .soundscape
                     .send(move |soundscape| {
                         soundscape.update_source(&id, |source| {
-                            source.release_duration = new_duration;
+                            source.constraints.day_gain.continuous = new_continuous;
                         });
                     })
-                    .expect("failed to send source release duration to soundscape thread");
+                    .expect("failed to send source day gain to soundscape thread");
             }
 
             //////////////////////////////////
             // Soundscape Group Assignments //
             //////////////////////////////////
 
+            let day_gain_id = ids.source_editor_selected_soundscape_day_gain;
             widget::Text::new("Soundscape Groups")
-                .align_left()
-                .down(PAD * 2.0)
+                .align_left_of(day_gain_id)
+                .down_from(day_gain_id, PAD * 2.0)
                 .font_size(SMALL_FONT_SIZE)
                 .set(ids.source_editor_selected_soundscape_groups_text, ui);
 
-            let mut groups_vec: Vec<_> = soundscape_groups.iter().collect();
-            groups_vec.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+            let groups_vec = project::ordered_soundscape_groups(soundscape_groups);
             let (mut events, scrollbar) = widget::ListSelect::multiple(groups_vec.len())
                 .item_size(ITEM_HEIGHT)
                 .h(SOUNDSCAPE_GROUP_LIST_H)
@@ -1850,7 +4093,7 @@ pub fn set(
                     // Instantiate a button for each group.
                     Event::Item(item) => {
                         let selected = is_selected(item.i);
-                        let (&group_id, _) = groups_vec[item.i];
+                        let (group_id, _) = groups_vec[item.i];
                         let soundscape = expect_soundscape_mut(sources, &id);
                         let color = if selected { ui::color::BLUE } else { ui::color::BLACK };
                         let button = widget::Button::new()
@@ -1992,6 +4235,119 @@ pub fn set(
 
                     return area.id;
                 },
+                audio::source::Movement::Path(path) => {
+
+                    ////////////////
+                    // DRAWN PATH //
+                    ////////////////
+
+                    let third_w = (canvas_kid_area.w() - PAD * 2.0) / 3.0;
+                    let half_w = (canvas_kid_area.w() - PAD) / 2.0;
+                    let mode_list_id = ids.source_editor_selected_soundscape_movement_mode_list;
+                    let drawing = source_editor.drawing_path;
+                    let label = if drawing { "DRAWING" } else { "DRAW" };
+                    for new_drawing in widget::Toggle::new(drawing)
+                        .w(third_w)
+                        .h(BUTTON_H)
+                        .label(label)
+                        .label_font_size(SMALL_FONT_SIZE)
+                        .color(if drawing { color::BLUE } else { color::DARK_CHARCOAL })
+                        .down_from(mode_list_id, PAD)
+                        .align_left_of(mode_list_id)
+                        .set(ids.source_editor_selected_soundscape_movement_path_draw, ui)
+                    {
+                        source_editor.drawing_path = new_drawing;
+                    }
+
+                    for _click in widget::Button::new()
+                        .w(third_w)
+                        .h(BUTTON_H)
+                        .label("UNDO")
+                        .label_font_size(SMALL_FONT_SIZE)
+                        .color(color::DARK_CHARCOAL)
+                        .right(PAD)
+                        .set(ids.source_editor_selected_soundscape_movement_path_undo, ui)
+                    {
+                        set_path(sources, channels, id, |path| {
+                            path.points.pop();
+                        });
+                    }
+
+                    for _click in widget::Button::new()
+                        .w(third_w)
+                        .h(BUTTON_H)
+                        .label("CLEAR")
+                        .label_font_size(SMALL_FONT_SIZE)
+                        .color(color::DARK_CHARCOAL)
+                        .right(PAD)
+                        .set(ids.source_editor_selected_soundscape_movement_path_clear, ui)
+                    {
+                        set_path(sources, channels, id, |path| path.points.clear());
+                    }
+
+                    let draw_id = ids.source_editor_selected_soundscape_movement_path_draw;
+                    let toggle = |value: bool, label: &'static str| {
+                        widget::Toggle::new(value)
+                            .w(half_w)
+                            .h(BUTTON_H)
+                            .label(label)
+                            .label_font_size(SMALL_FONT_SIZE)
+                            .color(if value { color::BLUE } else { color::DARK_CHARCOAL })
+                    };
+                    for new_looped in toggle(path.looped, "LOOP")
+                        .down_from(draw_id, PAD)
+                        .align_left_of(draw_id)
+                        .set(ids.source_editor_selected_soundscape_movement_path_loop, ui)
+                    {
+                        set_path(sources, channels, id, |path| path.looped = new_looped);
+                    }
+                    for new_smooth in toggle(path.smooth, "SMOOTH")
+                        .right(PAD)
+                        .set(ids.source_editor_selected_soundscape_movement_path_smooth, ui)
+                    {
+                        set_path(sources, channels, id, |path| path.smooth = new_smooth);
+                    }
+
+                    // The speed of the most recently drawn segment, inherited by the next point.
+                    let loop_id = ids.source_editor_selected_soundscape_movement_path_loop;
+                    match path.points.last() {
+                        None => {
+                            widget::Text::new("Enable DRAW and click the floorplan to add points.")
+                                .font_size(SMALL_FONT_SIZE)
+                                .color(color::WHITE)
+                                .down_from(loop_id, PAD * 2.0)
+                                .align_left_of(loop_id)
+                                .set(ids.source_editor_selected_soundscape_movement_path_text, ui);
+                        },
+                        Some(last) => {
+                            let label = format!(
+                                "Point {} Speed: {:.2} m/s",
+                                path.points.len(),
+                                last.speed,
+                            );
+                            let max = audio::source::movement::MAX_PATH_SPEED;
+                            for new_speed in widget::Slider::new(last.speed, 0.0, max)
+                                .w(canvas_kid_area.w())
+                                .h(SLIDER_H)
+                                .label(&label)
+                                .label_font_size(SMALL_FONT_SIZE)
+                                .color(ui::color::LIGHT_CHARCOAL)
+                                .down_from(loop_id, PAD)
+                                .align_left_of(loop_id)
+                                .set(ids.source_editor_selected_soundscape_movement_path_speed, ui)
+                            {
+                                let new_speed = (new_speed * 100.0).round() / 100.0;
+                                set_path(sources, channels, id, |path| {
+                                    if let Some(last) = path.points.last_mut() {
+                                        last.speed = new_speed;
+                                    }
+                                });
+                            }
+                        },
+                    }
+
+                    return area.id;
+                },
                 audio::source::Movement::Generative(generative) => generative,
             };
 
@@ -1,16 +1,261 @@
 use audio;
-use gui::{collapsible_area, Gui, ProjectState};
+use audio::{calibration, test_signal};
+use gui::{collapsible_area, Channels, Gui, ProjectState};
 use gui::{DARK_A, ITEM_HEIGHT, SMALL_FONT_SIZE};
+use metres::Metres;
+use nannou::geom::Point2;
 use nannou::ui;
 use nannou::ui::prelude::*;
 use project::{self, Project};
 use soundscape;
+use std::sync::mpsc;
+use std::time::Instant;
+use time_calc::Ms;
+use utils;
 
 /// Runtime state related to the speaker editor GUI panel.
-#[derive(Default)]
 pub struct SpeakerEditor {
     /// The index of the selected speaker within the project.
     pub selected: Option<usize>,
+    /// The index of the band selected for editing within the selected speaker's EQ.
+    pub selected_eq_band: usize,
+    /// The test signal that is currently playing, if any.
+    pub test_signal: Option<TestSignal>,
+    /// The kind of test signal to play.
+    pub test_signal_kind: test_signal::Kind,
+    /// The level of the test signal in dBFS.
+    pub test_signal_level_db: f32,
+    /// The automated calibration of every speaker underway, if any.
+    pub calibration: Option<Calibration>,
+    /// The input channel of the measurement microphone used for calibration.
+    pub calibration_input: usize,
+    /// A summary of the outcome of the last calibration.
+    pub calibration_status: Option<String>,
+}
+
+/// The stage of an automated calibration of the trim and delay of every speaker.
+pub enum Calibration {
+    /// Playing a sweep through each speaker while recording the measurement microphone.
+    Measuring {
+        calibration: calibration::Calibration,
+        progress: f32,
+        speakers: Vec<audio::speaker::Id>,
+        // The trim and delay of each speaker at the start of the measurement.
+        current: Vec<(f32, Ms)>,
+    },
+    /// Analysing the recording on a dedicated thread.
+    Analysing {
+        results: mpsc::Receiver<Vec<Option<calibration::Measurement>>>,
+        speakers: Vec<audio::speaker::Id>,
+        current: Vec<(f32, Ms)>,
+    },
+}
+
+/// A test signal playing through the selected speaker or through every speaker in turn.
+pub struct TestSignal {
+    started: Instant,
+    // Whether the signal is cycling through all speakers.
+    cycle: bool,
+    // The names of the speakers through which the signal plays, in order.
+    speakers: Vec<String>,
+}
+
+impl Default for SpeakerEditor {
+    fn default() -> Self {
+        SpeakerEditor {
+            selected: None,
+            selected_eq_band: 0,
+            test_signal: None,
+            test_signal_kind: test_signal::Kind::PinkNoise,
+            test_signal_level_db: test_signal::DEFAULT_LEVEL_DB,
+            calibration: None,
+            calibration_input: 0,
+            calibration_status: None,
+        }
+    }
+}
+
+impl TestSignal {
+    /// The name of the speaker through which the signal is currently playing.
+    ///
+    /// Estimated from the time since the signal started.
+    pub fn current_speaker(&self) -> Option<&str> {
+        if self.speakers.is_empty() {
+            return None;
+        }
+        let elapsed_secs = utils::duration_to_secs(&self.started.elapsed());
+        let step = (elapsed_secs / test_signal::CYCLE_INTERVAL_SECS) as usize;
+        Some(&self.speakers[step % self.speakers.len()])
+    }
+}
+
+/// Begin playing a test signal through the given speakers in turn, ordered by channel.
+///
+/// Returns `None` if no speakers are given.
+pub fn start_test_signal(
+    kind: test_signal::Kind,
+    level_db: f32,
+    mut speaker_ids: Vec<audio::speaker::Id>,
+    speakers: &project::Speakers,
+    channels: &Channels,
+) -> Option<TestSignal> {
+    if speaker_ids.is_empty() {
+        return None;
+    }
+    speaker_ids.sort_by_key(|id| speakers[id].audio.channel);
+    let outputs = speaker_ids.iter().map(|id| speakers[id].audio.channel).collect();
+    let names = speaker_ids.iter().map(|id| speakers[id].name.clone()).collect();
+    let audio_test_signal = test_signal::TestSignal::new(kind, level_db, outputs);
+    channels
+        .audio_output
+        .send(move |audio| audio.test_signal = Some(audio_test_signal))
+        .expect("failed to send test signal to audio output thread");
+    let cycle = speaker_ids.len() > 1;
+    Some(TestSignal { started: Instant::now(), cycle, speakers: names })
+}
+
+/// Stop the test signal that is playing, if any.
+pub fn stop_test_signal(channels: &Channels, test_signal: &mut Option<TestSignal>) {
+    if test_signal.take().is_some() {
+        channels
+            .audio_output
+            .send(move |audio| audio.test_signal = None)
+            .expect("failed to stop test signal on audio output thread");
+    }
+}
+
+/// Begin calibrating the trim and delay of every speaker other than subwoofers, whose level and
+/// timing depend upon the crossover.
+pub fn start_calibration(
+    speaker_editor: &mut SpeakerEditor,
+    speakers: &project::Speakers,
+    channels: &Channels,
+) {
+    stop_test_signal(channels, &mut speaker_editor.test_signal);
+    let mut speaker_ids: Vec<_> = speakers
+        .iter()
+        .filter(|&(_, s)| !s.audio.subwoofer)
+        .map(|(&id, _)| id)
+        .collect();
+    speaker_ids.sort_by_key(|id| speakers[id].audio.channel);
+    let outputs = speaker_ids.iter().map(|id| speakers[id].audio.channel).collect();
+    let current = speaker_ids
+        .iter()
+        .map(|id| (speakers[id].audio.trim_db, speakers[id].audio.delay))
+        .collect();
+    let level_db = speaker_editor.test_signal_level_db;
+    let input = speaker_editor.calibration_input;
+    match calibration::Calibration::start(
+        outputs,
+        level_db,
+        input,
+        &channels.audio_input,
+        &channels.audio_output,
+    ) {
+        Err(err) => {
+            eprintln!("failed to begin calibration: {}", err);
+            speaker_editor.calibration_status = Some(format!("Calibration failed: {}", err));
+        },
+        Ok(calibration) => {
+            speaker_editor.calibration_status = None;
+            speaker_editor.calibration = Some(Calibration::Measuring {
+                calibration,
+                progress: 0.0,
+                speakers: speaker_ids,
+                current,
+            });
+        },
+    }
+}
+
+/// Cancel the calibration underway, if any, leaving all speakers untouched.
+pub fn cancel_calibration(speaker_editor: &mut SpeakerEditor, channels: &Channels) {
+    if let Some(Calibration::Measuring { calibration, .. }) = speaker_editor.calibration.take() {
+        calibration.stop(&channels.audio_input, &channels.audio_output);
+    }
+}
+
+/// Progress the calibration underway, if any, applying the results to the speakers once complete.
+///
+/// Called on every GUI update so that calibration continues while the speaker editor is closed.
+pub fn update_calibration(
+    speaker_editor: &mut SpeakerEditor,
+    speakers: &mut project::Speakers,
+    channels: &Channels,
+) {
+    let next = match speaker_editor.calibration.take() {
+        None => return,
+        Some(Calibration::Measuring { mut calibration, speakers: ids, current, .. }) => {
+            let progress = calibration.update();
+            if !calibration.is_complete() {
+                Calibration::Measuring { calibration, progress, speakers: ids, current }
+            } else {
+                calibration.stop(&channels.audio_input, &channels.audio_output);
+                let results = calibration.analyse();
+                Calibration::Analysing { results, speakers: ids, current }
+            }
+        },
+        Some(Calibration::Analysing { results, speakers: ids, current }) => {
+            let measurements = match results.try_recv() {
+                Err(mpsc::TryRecvError::Empty) => {
+                    let analysing = Calibration::Analysing { results, speakers: ids, current };
+                    speaker_editor.calibration = Some(analysing);
+                    return;
+                },
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    eprintln!("failed to analyse calibration recording");
+                    speaker_editor.calibration_status = Some("Calibration failed".to_string());
+                    return;
+                },
+                Ok(measurements) => measurements,
+            };
+
+            // Apply the alignment of each speaker that was heard, reporting those that were not.
+            let alignments = calibration::align(&measurements, &current);
+            let mut aligned = 0;
+            for (id, alignment) in ids.iter().zip(alignments) {
+                let speaker = match speakers.get_mut(id) {
+                    None => continue,
+                    Some(speaker) => speaker,
+                };
+                match alignment {
+                    None => eprintln!("calibration did not detect speaker \"{}\"", speaker.name),
+                    Some(alignment) => {
+                        speaker.audio.trim_db = (alignment.trim_db * 10.0).round() / 10.0;
+                        speaker.audio.delay = alignment.delay;
+                        set_alignment(channels, *id, &speaker.audio);
+                        aligned += 1;
+                    },
+                }
+            }
+            let status = format!("Calibrated {} of {} speakers", aligned, ids.len());
+            speaker_editor.calibration_status = Some(status);
+            return;
+        },
+    };
+    speaker_editor.calibration = Some(next);
+}
+
+// Send the trim and delay of the given speaker to the audio output thread.
+fn set_alignment(channels: &Channels, id: audio::speaker::Id, speaker: &audio::Speaker) {
+    let (trim_db, delay) = (speaker.trim_db, speaker.delay);
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.update_speaker_alignment(&id, trim_db, delay);
+        })
+        .expect("failed to send speaker alignment to audio output thread");
+}
+
+// Send the directivity and aim of the given speaker to the audio output thread.
+fn set_directivity(channels: &Channels, id: audio::speaker::Id, speaker: &audio::Speaker) {
+    let (directivity, aim_radians) = (speaker.directivity, speaker.aim_radians);
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.update_speaker_directivity(&id, directivity, aim_radians);
+        })
+        .expect("failed to send speaker directivity to audio output thread");
 }
 
 /// Convert the given map into a sorted list of speaker Id.
@@ -20,6 +265,110 @@ pub fn sorted_speakers_vec(speakers: &project::Speakers) -> Vec<audio::speaker::
     speakers_vec
 }
 
+/// Move the given speaker to the given point, updating the audio output and soundscape copies.
+pub fn set_speaker_point(
+    speaker_id: audio::speaker::Id,
+    speaker: &mut project::Speaker,
+    point: Point2<Metres>,
+    channels: &Channels,
+) {
+    // Update the local copy.
+    speaker.audio.point = point;
+
+    // Update the audio copy.
+    let speaker_clone = speaker.audio.clone();
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.insert_speaker(speaker_id, speaker_clone);
+        })
+        .expect("failed to send updated speaker to audio output thread");
+
+    // Update the soundscape copy.
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            soundscape.update_speaker(&speaker_id, |s| s.point = point);
+        })
+        .expect("failed to send speaker update to soundscape thread");
+}
+
+// Send the mute and solo state of the given speaker to the audio output thread.
+fn send_speaker_monitoring(
+    speaker_id: audio::speaker::Id,
+    speaker: &audio::Speaker,
+    channels: &Channels,
+) {
+    let (muted, soloed) = (speaker.muted, speaker.soloed);
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.update_speaker_monitoring(&speaker_id, muted, soloed);
+        })
+        .expect("failed to send speaker mute and solo state to audio output thread");
+}
+
+/// Mute or unmute the given speaker, updating the audio output copy.
+pub fn set_speaker_muted(
+    speaker_id: audio::speaker::Id,
+    speaker: &mut project::Speaker,
+    muted: bool,
+    channels: &Channels,
+) {
+    speaker.audio.muted = muted;
+    send_speaker_monitoring(speaker_id, &speaker.audio, channels);
+}
+
+/// Solo or unsolo the given speaker, updating the audio output copy.
+///
+/// If `exclusive` is `true`, all other speakers are unsoloed.
+pub fn set_speaker_soloed(
+    speaker_id: audio::speaker::Id,
+    speakers: &mut project::Speakers,
+    soloed: bool,
+    exclusive: bool,
+    channels: &Channels,
+) {
+    for (&id, speaker) in speakers.iter_mut() {
+        let new_soloed = match id == speaker_id {
+            true => soloed,
+            false if exclusive => false,
+            false => continue,
+        };
+        if speaker.audio.soloed != new_soloed {
+            speaker.audio.soloed = new_soloed;
+            send_speaker_monitoring(id, &speaker.audio, channels);
+        }
+    }
+}
+
+/// Remove the speaker with the given ID from the project, the audio output thread and the
+/// soundscape thread.
+pub fn remove_speaker(
+    speaker_id: audio::speaker::Id,
+    speakers: &mut project::Speakers,
+    channels: &Channels,
+) {
+    // Remove the local copy.
+    speakers.remove(&speaker_id);
+
+    // Remove the speaker from the audio output thread.
+    channels
+        .audio_output
+        .send(move |audio| {
+            audio.remove_speaker(speaker_id);
+        })
+        .expect("failed to remove speaker from audio output thread");
+
+    // Remove the soundscape copy.
+    channels
+        .soundscape
+        .send(move |soundscape| {
+            soundscape.remove_speaker(&speaker_id);
+        })
+        .expect("failed to remove speaker from soundscape thread");
+}
+
 // Instantiate the sidebar speaker editor widgets.
 pub fn set(
     last_area_id: widget::Id,
@@ -30,7 +379,7 @@ pub fn set(
     let Gui {
         ref mut ui,
         ref mut state,
-        ref ids,
+        ref mut ids,
         ref channels,
         ..
     } = *gui;
@@ -58,7 +407,10 @@ pub fn set(
     const INSTALLATION_LIST_H: Scalar = ITEM_HEIGHT * 3.0;
     const INSTALLATIONS_CANVAS_H: Scalar =
         PAD + ITEM_HEIGHT * 2.0 + PAD + INSTALLATION_LIST_H + PAD;
-    const SELECTED_CANVAS_H: Scalar = ITEM_HEIGHT * 2.0 + PAD * 4.0 + INSTALLATIONS_CANVAS_H;
+    const EQ_CURVE_H: Scalar = ITEM_HEIGHT * 2.0;
+    const TEST_SIGNAL_CANVAS_H: Scalar = ITEM_HEIGHT * 4.0 + PAD * 5.0;
+    const SELECTED_CANVAS_H: Scalar = ITEM_HEIGHT * 8.0 + PAD * 11.0 + EQ_CURVE_H
+        + TEST_SIGNAL_CANVAS_H + PAD + INSTALLATIONS_CANVAS_H;
     let speaker_editor_canvas_h = LIST_HEIGHT + ITEM_HEIGHT + SELECTED_CANVAS_H;
 
     let (area, event) = collapsible_area(is_open, "Speaker Editor", ids.side_menu)
@@ -207,25 +559,8 @@ pub fn set(
                 speaker_editor.selected = None;
             }
 
-            // Remove the local copy.
             let speaker_id = speakers_vec.remove(i);
-            speakers.remove(&speaker_id);
-
-            // Remove the speaker from the audio output thread.
-            channels
-                .audio_output
-                .send(move |audio| {
-                    audio.remove_speaker(speaker_id);
-                })
-                .expect("failed to remove speaker from audio output thread");
-
-            // Remove the soundscape copy.
-            channels
-                .soundscape
-                .send(move |soundscape| {
-                    soundscape.remove_speaker(&speaker_id);
-                })
-                .expect("failed to remove speaker from soundscape thread");
+            remove_speaker(speaker_id, speakers, channels);
         }
     }
 
@@ -250,8 +585,17 @@ pub fn set(
             let channel = project::next_available_speaker_channel(speakers);
             let audio = audio::Speaker {
                 point: camera.position,
+                height: Metres(0.0),
                 channel: channel,
                 installations: Default::default(),
+                trim_db: 0.0,
+                delay: Ms(0.0),
+                eq: Default::default(),
+                subwoofer: false,
+                muted: false,
+                soloed: false,
+                directivity: Default::default(),
+                aim_radians: 0.0,
             };
 
             // Update the audio output copy.
@@ -315,7 +659,7 @@ pub fn set(
     for event in widget::TextBox::new(&speakers[&id].name)
         .mid_top_of(ids.speaker_editor_selected_canvas)
         .kid_area_w_of(ids.speaker_editor_selected_canvas)
-        .parent(gui.ids.speaker_editor_selected_canvas)
+        .parent(ids.speaker_editor_selected_canvas)
         .h(ITEM_HEIGHT)
         .color(DARK_A)
         .font_size(SMALL_FONT_SIZE)
@@ -390,6 +734,286 @@ pub fn set(
         }
     }
 
+    // Sliders for the speaker's level trim and delay compensation along with the subwoofer toggle.
+    let selected_kid_area = ui.kid_area_of(ids.speaker_editor_selected_canvas).unwrap();
+    let third_w = (selected_kid_area.w() - PAD * 2.0) / 3.0;
+    let trim_db = speakers[&id].audio.trim_db;
+    let label = format!("Trim: {:+.1} dB", trim_db);
+    let (min_db, max_db) = (audio::speaker::MIN_TRIM_DB, audio::speaker::MAX_TRIM_DB);
+    for new_db in widget::Slider::new(trim_db, min_db, max_db)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .w(third_w)
+        .h(ITEM_HEIGHT)
+        .align_left_of(ids.speaker_editor_selected_channel)
+        .down_from(ids.speaker_editor_selected_channel, PAD)
+        .parent(ids.speaker_editor_selected_canvas)
+        .set(ids.speaker_editor_selected_trim, ui)
+    {
+        // Round to the nearest tenth of a decibel so that `0` is easy to return to.
+        let speaker = speakers.get_mut(&id).unwrap();
+        speaker.audio.trim_db = (new_db * 10.0).round() / 10.0;
+        set_alignment(channels, id, &speaker.audio);
+    }
+
+    let delay_ms = speakers[&id].audio.delay.ms();
+    let label = format!("Delay: {:.2} ms", delay_ms);
+    let max_ms = audio::speaker::MAX_DELAY.ms();
+    for new_ms in widget::Slider::new(delay_ms, 0.0, max_ms)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .skew(0.5)
+        .color(color::LIGHT_CHARCOAL)
+        .w(third_w)
+        .h(ITEM_HEIGHT)
+        .right(PAD)
+        .parent(ids.speaker_editor_selected_canvas)
+        .set(ids.speaker_editor_selected_delay, ui)
+    {
+        let speaker = speakers.get_mut(&id).unwrap();
+        speaker.audio.delay = Ms(new_ms);
+        set_alignment(channels, id, &speaker.audio);
+    }
+
+    // Marking a speaker as a subwoofer routes the low end of all sounds to it.
+    let subwoofer = speakers[&id].audio.subwoofer;
+    for new_subwoofer in widget::Toggle::new(subwoofer)
+        .label("SUB")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .w(third_w)
+        .h(ITEM_HEIGHT)
+        .right(PAD)
+        .parent(ids.speaker_editor_selected_canvas)
+        .set(ids.speaker_editor_selected_subwoofer, ui)
+    {
+        speakers.get_mut(&id).unwrap().audio.subwoofer = new_subwoofer;
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.update_speaker_subwoofer(&id, new_subwoofer);
+            })
+            .expect("failed to send speaker subwoofer state to audio output thread");
+    }
+
+    // Slider for the height of the speaker above the floor, used for DBAP distances.
+    let height = speakers[&id].audio.height;
+    let label = format!("Height: {:.2} metres", height.0);
+    for new_height in widget::Slider::new(height.0, 0.0, audio::MAX_HEIGHT.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .skew(0.5)
+        .color(color::LIGHT_CHARCOAL)
+        .w(selected_kid_area.w())
+        .h(ITEM_HEIGHT)
+        .align_left_of(ids.speaker_editor_selected_trim)
+        .down_from(ids.speaker_editor_selected_trim, PAD)
+        .parent(ids.speaker_editor_selected_canvas)
+        .set(ids.speaker_editor_selected_height, ui)
+    {
+        let speaker = speakers.get_mut(&id).unwrap();
+        speaker.audio.height = Metres((new_height * 100.0).round() / 100.0);
+        let speaker_clone = speaker.audio.clone();
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.insert_speaker(id, speaker_clone);
+            })
+            .expect("failed to send speaker height to audio output thread");
+    }
+
+    // The speaker's directivity and the direction in which it is aimed.
+    let half_w = (selected_kid_area.w() - PAD) / 2.0;
+    let directivity = speakers[&id].audio.directivity;
+    let names: Vec<_> = audio::speaker::Directivity::ALL.iter().map(|d| d.name()).collect();
+    let selected = audio::speaker::Directivity::ALL.iter().position(|&d| d == directivity);
+    for idx in widget::DropDownList::new(&names, selected)
+        .label("Directivity")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .align_left_of(ids.speaker_editor_selected_height)
+        .down_from(ids.speaker_editor_selected_height, PAD)
+        .parent(ids.speaker_editor_selected_canvas)
+        .set(ids.speaker_editor_selected_directivity, ui)
+    {
+        let speaker = speakers.get_mut(&id).unwrap();
+        speaker.audio.directivity = audio::speaker::Directivity::ALL[idx];
+        set_directivity(channels, id, &speaker.audio);
+    }
+
+    let degrees = speakers[&id].audio.aim_radians.to_degrees();
+    let label = format!("Aim: {:.0}°", degrees);
+    for new_degrees in widget::Slider::new(degrees, -180.0, 180.0)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .enabled(directivity != audio::speaker::Directivity::Omni)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .right(PAD)
+        .parent(ids.speaker_editor_selected_canvas)
+        .set(ids.speaker_editor_selected_aim, ui)
+    {
+        let speaker = speakers.get_mut(&id).unwrap();
+        speaker.audio.aim_radians = new_degrees.round().to_radians();
+        set_directivity(channels, id, &speaker.audio);
+    }
+
+    // Solo and mute the speaker's output channel, e.g. while verifying the wiring of a rig.
+    let soloed = speakers[&id].audio.soloed;
+    for new_soloed in widget::Toggle::new(soloed)
+        .label("SOLO")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::DARK_YELLOW)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .align_left_of(ids.speaker_editor_selected_directivity)
+        .down_from(ids.speaker_editor_selected_directivity, PAD)
+        .parent(ids.speaker_editor_selected_canvas)
+        .set(ids.speaker_editor_selected_solo, ui)
+    {
+        // If the CTRL key was down, unsolo all other speakers.
+        let ctrl = ui::input::keyboard::ModifierKey::CTRL;
+        let exclusive = ui.global_input().current.modifiers.contains(ctrl);
+        set_speaker_soloed(id, speakers, new_soloed, exclusive, channels);
+    }
+
+    let muted = speakers[&id].audio.muted;
+    for new_muted in widget::Toggle::new(muted)
+        .label("MUTE")
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::BLUE)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .right(PAD)
+        .parent(ids.speaker_editor_selected_canvas)
+        .set(ids.speaker_editor_selected_mute, ui)
+    {
+        set_speaker_muted(id, speakers.get_mut(&id).unwrap(), new_muted, channels);
+    }
+
+    // The parametric EQ applied to the speaker's output channel.
+    fn set_eq(channels: &Channels, id: audio::speaker::Id, eq: audio::effect::ParametricEq) {
+        channels
+            .audio_output
+            .send(move |audio| {
+                audio.update_speaker_eq(&id, eq);
+            })
+            .expect("failed to send speaker EQ to audio output thread");
+    }
+
+    // Display the combined response of all bands over a log frequency axis.
+    let eq = speakers[&id].audio.eq;
+    let max_db = audio::effect::MAX_EQ_DB;
+    widget::Rectangle::fill([selected_kid_area.w(), EQ_CURVE_H])
+        .color(DARK_A)
+        .align_left_of(ids.speaker_editor_selected_channel)
+        .down_from(ids.speaker_editor_selected_solo, PAD)
+        .parent(ids.speaker_editor_selected_canvas)
+        .set(ids.speaker_editor_selected_eq_bg, ui);
+    let (min_hz, max_hz) = (audio::effect::MIN_HZ, audio::effect::MAX_HZ);
+    let response_db = |x: f32| {
+        let hz = min_hz * (max_hz / min_hz).powf(x);
+        eq.response_db(hz).max(-max_db).min(max_db)
+    };
+    widget::PlotPath::new(0.0, 1.0, -max_db, max_db, response_db)
+        .wh_of(ids.speaker_editor_selected_eq_bg)
+        .middle_of(ids.speaker_editor_selected_eq_bg)
+        .color(color::LIGHT_BLUE)
+        .thickness(2.0)
+        .parent(ids.speaker_editor_selected_eq_bg)
+        .set(ids.speaker_editor_selected_eq_curve, ui);
+
+    // Buttons for selecting the band to edit.
+    const BAND_LABELS: [&str; audio::effect::NUM_PARAMETRIC_BANDS] = ["LOW", "2", "3", "HIGH"];
+    if ids.speaker_editor_selected_eq_bands.len() < BAND_LABELS.len() {
+        let id_gen = &mut ui.widget_id_generator();
+        ids.speaker_editor_selected_eq_bands.resize(BAND_LABELS.len(), id_gen);
+    }
+    let num_bands = BAND_LABELS.len() as Scalar;
+    let band_w = (selected_kid_area.w() - PAD * (num_bands - 1.0)) / num_bands;
+    for (i, label) in BAND_LABELS.iter().enumerate() {
+        let color = match speaker_editor.selected_eq_band == i {
+            true => color::BLUE,
+            false => color::LIGHT_CHARCOAL,
+        };
+        let button = widget::Button::new()
+            .label(label)
+            .label_font_size(SMALL_FONT_SIZE)
+            .color(color)
+            .w(band_w)
+            .h(ITEM_HEIGHT)
+            .parent(ids.speaker_editor_selected_canvas);
+        let button = match i {
+            0 => button
+                .align_left_of(ids.speaker_editor_selected_eq_bg)
+                .down_from(ids.speaker_editor_selected_eq_bg, PAD),
+            _ => button.right(PAD),
+        };
+        if button.set(ids.speaker_editor_selected_eq_bands[i], ui).was_clicked() {
+            speaker_editor.selected_eq_band = i;
+        }
+    }
+
+    // Sliders for the frequency, gain and Q of the selected band.
+    let band_ix = speaker_editor.selected_eq_band;
+    let band = eq.bands[band_ix];
+    let label = format!("{:.0} Hz", band.hz);
+    for new_hz in widget::Slider::new(band.hz, min_hz, max_hz)
+        .skew(0.2)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .w(third_w)
+        .h(ITEM_HEIGHT)
+        .align_left_of(ids.speaker_editor_selected_eq_bg)
+        .down_from(ids.speaker_editor_selected_eq_bands[0], PAD)
+        .parent(ids.speaker_editor_selected_canvas)
+        .set(ids.speaker_editor_selected_eq_hz, ui)
+    {
+        let speaker = speakers.get_mut(&id).unwrap();
+        speaker.audio.eq.bands[band_ix].hz = new_hz.round();
+        set_eq(channels, id, speaker.audio.eq);
+    }
+
+    let label = format!("{:+.1} dB", band.db);
+    for new_db in widget::Slider::new(band.db, -max_db, max_db)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .w(third_w)
+        .h(ITEM_HEIGHT)
+        .right(PAD)
+        .parent(ids.speaker_editor_selected_canvas)
+        .set(ids.speaker_editor_selected_eq_db, ui)
+    {
+        // Round to the nearest tenth of a decibel so that `0` (bypass) is easy to return to.
+        let speaker = speakers.get_mut(&id).unwrap();
+        speaker.audio.eq.bands[band_ix].db = (new_db * 10.0).round() / 10.0;
+        set_eq(channels, id, speaker.audio.eq);
+    }
+
+    let label = format!("Q: {:.2}", band.q);
+    let (min_q, max_q) = (audio::effect::MIN_Q, audio::effect::MAX_Q);
+    for new_q in widget::Slider::new(band.q, min_q, max_q)
+        .skew(0.3)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .w(third_w)
+        .h(ITEM_HEIGHT)
+        .right(PAD)
+        .parent(ids.speaker_editor_selected_canvas)
+        .set(ids.speaker_editor_selected_eq_q, ui)
+    {
+        let speaker = speakers.get_mut(&id).unwrap();
+        speaker.audio.eq.bands[band_ix].q = (new_q * 100.0).round() / 100.0;
+        set_eq(channels, id, speaker.audio.eq);
+    }
+
     // A canvas on which installation selection widgets are instantiated.
     widget::Canvas::new()
         .kid_area_w_of(ids.speaker_editor_selected_canvas)
@@ -508,7 +1132,7 @@ pub fn set(
             .align_right_of(item.widget_id)
             .align_middle_y_of(item.widget_id)
             .parent(item.widget_id)
-            .set(gui.ids.speaker_editor_selected_installations_remove, ui)
+            .set(ids.speaker_editor_selected_installations_remove, ui)
             .was_clicked()
         {
             maybe_remove_index = Some(item.i);
@@ -546,5 +1170,187 @@ pub fn set(
             .expect("failed to remove installation from speaker on soundscape thread");
     }
 
+    // A canvas for playing test signals through the selected speaker or every speaker in turn.
+    widget::Canvas::new()
+        .kid_area_w_of(ids.speaker_editor_selected_canvas)
+        .h(TEST_SIGNAL_CANVAS_H)
+        .up_from(ids.speaker_editor_selected_installations_canvas, PAD)
+        .pad(PAD)
+        .color(color::CHARCOAL)
+        .set(ids.speaker_editor_test_signal_canvas, ui);
+
+    let title = match speaker_editor.calibration_status {
+        Some(ref status) => format!("Test Signal - {}", status),
+        None => "Test Signal - Bypasses the soundscape".to_string(),
+    };
+    widget::Text::new(&title)
+        .top_left_of(ids.speaker_editor_test_signal_canvas)
+        .font_size(SMALL_FONT_SIZE)
+        .set(ids.speaker_editor_test_signal_text, ui);
+
+    let test_signal_kid_area = ui.kid_area_of(ids.speaker_editor_test_signal_canvas).unwrap();
+    let half_w = (test_signal_kid_area.w() - PAD) / 2.0;
+
+    // The kind of signal, which may be changed while playing.
+    let kinds = &test_signal::Kind::ALL;
+    let labels: Vec<_> = kinds.iter().map(|kind| kind.label()).collect();
+    let index = kinds.iter().position(|&kind| kind == speaker_editor.test_signal_kind);
+    for index in widget::DropDownList::new(&labels, index)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .align_left_of(ids.speaker_editor_test_signal_text)
+        .down_from(ids.speaker_editor_test_signal_text, PAD)
+        .parent(ids.speaker_editor_test_signal_canvas)
+        .color(DARK_A)
+        .border_color(color::LIGHT_CHARCOAL)
+        .label_font_size(SMALL_FONT_SIZE)
+        .set(ids.speaker_editor_test_signal_kind, ui)
+    {
+        let kind = kinds[index];
+        speaker_editor.test_signal_kind = kind;
+        channels
+            .audio_output
+            .send(move |audio| {
+                if let Some(ref mut test_signal) = audio.test_signal {
+                    test_signal.set_kind(kind);
+                }
+            })
+            .expect("failed to send test signal kind to audio output thread");
+    }
+
+    // The level of the signal, which may be changed while playing.
+    let level_db = speaker_editor.test_signal_level_db;
+    let label = format!("Level: {:.1} dBFS", level_db);
+    let (min_db, max_db) = (test_signal::MIN_LEVEL_DB, test_signal::MAX_LEVEL_DB);
+    for new_db in widget::Slider::new(level_db, min_db, max_db)
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color::LIGHT_CHARCOAL)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .right(PAD)
+        .parent(ids.speaker_editor_test_signal_canvas)
+        .set(ids.speaker_editor_test_signal_level, ui)
+    {
+        let new_db = (new_db * 2.0).round() / 2.0;
+        speaker_editor.test_signal_level_db = new_db;
+        channels
+            .audio_output
+            .send(move |audio| {
+                if let Some(ref mut test_signal) = audio.test_signal {
+                    test_signal.set_level_db(new_db);
+                }
+            })
+            .expect("failed to send test signal level to audio output thread");
+    }
+
+    // Play through the selected speaker only, or stop if already doing so.
+    let (label, color) = match speaker_editor.test_signal {
+        Some(ref test) if !test.cycle => ("STOP".to_string(), color::DARK_RED),
+        _ => ("PLAY SELECTED".to_string(), color::DARK_CHARCOAL),
+    };
+    for _click in widget::Button::new()
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .align_left_of(ids.speaker_editor_test_signal_kind)
+        .down_from(ids.speaker_editor_test_signal_kind, PAD)
+        .parent(ids.speaker_editor_test_signal_canvas)
+        .set(ids.speaker_editor_test_signal_play, ui)
+    {
+        let was_selected = speaker_editor.test_signal.as_ref().map(|t| !t.cycle);
+        stop_test_signal(channels, &mut speaker_editor.test_signal);
+        if was_selected != Some(true) {
+            let kind = speaker_editor.test_signal_kind;
+            let level_db = speaker_editor.test_signal_level_db;
+            speaker_editor.test_signal =
+                start_test_signal(kind, level_db, vec![id], speakers, channels);
+        }
+    }
+
+    // Cycle through every speaker, or stop if already doing so.
+    let (label, color) = match speaker_editor.test_signal {
+        Some(ref test) if test.cycle => {
+            let label = match test.current_speaker() {
+                Some(name) => format!("STOP: {}", name),
+                None => "STOP".to_string(),
+            };
+            (label, color::DARK_RED)
+        },
+        _ => ("CYCLE ALL".to_string(), color::DARK_CHARCOAL),
+    };
+    for _click in widget::Button::new()
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .right(PAD)
+        .parent(ids.speaker_editor_test_signal_canvas)
+        .set(ids.speaker_editor_test_signal_cycle, ui)
+    {
+        let was_cycling = speaker_editor.test_signal.as_ref().map(|t| t.cycle);
+        stop_test_signal(channels, &mut speaker_editor.test_signal);
+        if was_cycling != Some(true) {
+            let kind = speaker_editor.test_signal_kind;
+            let level_db = speaker_editor.test_signal_level_db;
+            let speaker_ids = speakers.keys().cloned().collect();
+            speaker_editor.test_signal =
+                start_test_signal(kind, level_db, speaker_ids, speakers, channels);
+        }
+    }
+
+    // The input channel of the measurement microphone placed at the listening position.
+    let n_inputs = state.audio_channels.input;
+    let labels: Vec<_> = (0..n_inputs).map(|ch| format!("Mic Input {}", ch + 1)).collect();
+    let index = match speaker_editor.calibration_input < n_inputs {
+        true => Some(speaker_editor.calibration_input),
+        false => None,
+    };
+    for index in widget::DropDownList::new(&labels, index)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .align_left_of(ids.speaker_editor_test_signal_play)
+        .down_from(ids.speaker_editor_test_signal_play, PAD)
+        .parent(ids.speaker_editor_test_signal_canvas)
+        .color(DARK_A)
+        .border_color(color::LIGHT_CHARCOAL)
+        .label_font_size(SMALL_FONT_SIZE)
+        .set(ids.speaker_editor_calibration_input, ui)
+    {
+        speaker_editor.calibration_input = index;
+    }
+
+    // Sweep every speaker in turn and align their trim and delay at the microphone.
+    let (label, color) = match speaker_editor.calibration {
+        None => ("CALIBRATE ALL".to_string(), color::DARK_CHARCOAL),
+        Some(Calibration::Measuring { progress, .. }) => {
+            (format!("CANCEL: {:.0}%", progress * 100.0), color::DARK_RED)
+        },
+        Some(Calibration::Analysing { .. }) => ("ANALYSING...".to_string(), color::DARK_RED),
+    };
+    for _click in widget::Button::new()
+        .label(&label)
+        .label_font_size(SMALL_FONT_SIZE)
+        .color(color)
+        .w(half_w)
+        .h(ITEM_HEIGHT)
+        .right(PAD)
+        .parent(ids.speaker_editor_test_signal_canvas)
+        .set(ids.speaker_editor_calibrate, ui)
+    {
+        let is_measuring = match speaker_editor.calibration {
+            Some(Calibration::Measuring { .. }) => true,
+            _ => false,
+        };
+        if speaker_editor.calibration.is_none() {
+            start_calibration(speaker_editor, speakers, channels);
+        } else if is_measuring {
+            cancel_calibration(speaker_editor, channels);
+        }
+    }
+
     area.id
 }
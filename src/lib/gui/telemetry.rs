@@ -0,0 +1,126 @@
+//! The most recent telemetry of the audio output thread, dumped to a file upon an under-run.
+//!
+//! The audio output thread sends a `telemetry::Sample` at the end of every callback. The last
+//! `WINDOW` of these are retained here along with the depth of the monitor queue at the time each
+//! was received. When a sample reports an under-run, the window is written to a CSV file within
+//! the `telemetry` directory of the assets for post-mortem.
+
+use audio;
+use audio::telemetry::Sample;
+use project;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use utils;
+
+/// The duration of audio leading up to an under-run that is written to the dump.
+pub const WINDOW: Duration = Duration::from_secs(10);
+
+/// The minimum interval between dumps, so that a burst of under-runs produces a single file.
+pub const MIN_DUMP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The most recent telemetry of the audio output thread.
+#[derive(Default)]
+pub struct Telemetry {
+    records: VecDeque<Record>,
+    last_dump: Option<Instant>,
+}
+
+// A sample along with the number of monitor messages that were queued ahead of it.
+#[derive(Copy, Clone, Debug)]
+struct Record {
+    sample: Sample,
+    queue_depth: usize,
+}
+
+impl Telemetry {
+    /// Add the given sample, forgetting those older than the `WINDOW`.
+    ///
+    /// `queue_depth` is the number of monitor messages that were queued ahead of the sample when
+    /// the GUI began receiving them.
+    pub fn push(&mut self, sample: Sample, queue_depth: usize) {
+        // The frame count restarts along with the output stream.
+        let restarted = self.records.back().map(|r| r.sample.frame > sample.frame).unwrap_or(false);
+        if restarted {
+            self.records.clear();
+        }
+        let window_frames = (utils::duration_to_secs(&WINDOW) * audio::sample_rate()) as usize;
+        while let Some(front) = self.records.front().map(|r| r.sample.frame) {
+            if sample.frame - front <= window_frames {
+                break;
+            }
+            self.records.pop_front();
+        }
+        self.records.push_back(Record { sample, queue_depth });
+    }
+
+    /// Whether enough time has passed since the previous dump for another to be written.
+    pub fn is_dump_due(&self) -> bool {
+        self.last_dump.map(|i| i.elapsed() >= MIN_DUMP_INTERVAL).unwrap_or(true)
+    }
+
+    /// Write the retained telemetry to a new CSV file within the given directory.
+    ///
+    /// Times are given in seconds relative to the most recent sample.
+    pub fn dump(&mut self, directory: &Path) -> Result<PathBuf, String> {
+        self.last_dump = Some(Instant::now());
+        fs::create_dir_all(directory).map_err(|err| format!("{}", err))?;
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let name = format!("xrun-{}.csv", project::history::format_timestamp(secs));
+        let path = directory.join(name);
+        let file = File::create(&path).map_err(|err| format!("{}", err))?;
+        let mut writer = BufWriter::new(file);
+        self.write_csv(&mut writer).map_err(|err| format!("{}", err))?;
+        Ok(path)
+    }
+
+    // Write the retained records as CSV.
+    fn write_csv<W: Write>(&self, writer: &mut W) -> ::std::io::Result<()> {
+        let header = "secs,frames,load,gap,xrun,active_sounds,starved_sounds,monitor_queue_depth";
+        writeln!(writer, "{}", header)?;
+        let last_frame = match self.records.back() {
+            Some(record) => record.sample.frame,
+            None => return writer.flush(),
+        };
+        for record in &self.records {
+            let Record { sample, queue_depth } = *record;
+            let frames_ago = last_frame - sample.frame;
+            writeln!(
+                writer,
+                "{:.4},{},{:.3},{},{},{},{},{}",
+                -(frames_ago as f64 / audio::sample_rate()),
+                sample.frames,
+                sample.load,
+                sample.gap.map(|gap| format!("{:.3}", gap)).unwrap_or_default(),
+                sample.xrun as u8,
+                sample.active_sounds,
+                sample.starved_sounds,
+                queue_depth,
+            )?;
+        }
+        writer.flush()
+    }
+}
+
+#[test]
+fn test_window() {
+    let frames = 512;
+    let window_frames = (utils::duration_to_secs(&WINDOW) * audio::sample_rate()) as usize;
+    let mut telemetry = Telemetry::default();
+    for i in 0..window_frames / frames * 2 {
+        let sample = Sample { frame: i * frames, frames, ..Default::default() };
+        telemetry.push(sample, 0);
+    }
+    let records = &telemetry.records;
+    let span = records.back().unwrap().sample.frame - records.front().unwrap().sample.frame;
+    assert!(span <= window_frames && span + frames > window_frames);
+
+    // A restarted stream begins a new window.
+    telemetry.push(Sample { frame: 0, frames, ..Default::default() }, 0);
+    assert_eq!(telemetry.records.len(), 1);
+}
@@ -0,0 +1,149 @@
+//! Tracking of touch input for the GUI's touch interaction mode.
+//!
+//! `conrod` only tracks touches for as long as they are in contact with the screen, so we keep
+//! our own record of where each touch began in order to recognise long presses and pinches.
+
+use fxhash::FxHashMap;
+use nannou::ui;
+use nannou::ui::prelude::*;
+use std::time::{Duration, Instant};
+
+/// The duration for which a touch must be held in place to be considered a long press.
+pub const LONG_PRESS_DURATION: Duration = Duration::from_millis(500);
+/// The distance a touch may drift while still being considered a long press.
+pub const LONG_PRESS_MAX_MOVEMENT: Scalar = 12.0;
+/// The minimum radius of interactive widgets on the floorplan while in touch mode.
+pub const TARGET_RADIUS: Scalar = 24.0;
+/// The height of context menu items while in touch mode.
+pub const ITEM_HEIGHT: Scalar = 48.0;
+
+/// All touches currently in contact with the screen.
+#[derive(Default)]
+pub struct Touches {
+    active: FxHashMap<ui::input::touch::Id, Touch>,
+    /// The widgets that were long-pressed during the last update.
+    long_pressed: Vec<widget::Id>,
+}
+
+// The state of a single touch.
+struct Touch {
+    start_time: Instant,
+    start_xy: ui::Point,
+    prev_xy: ui::Point,
+    xy: ui::Point,
+    widget: Option<widget::Id>,
+    long_pressed: bool,
+}
+
+/// The change in zoom and position produced by two touches on a single widget.
+pub struct Pinch {
+    /// The ratio between the current and previous distance between the two touches.
+    pub scale: Scalar,
+    /// The distance moved by the point between the two touches.
+    pub pan: [Scalar; 2],
+}
+
+impl Touch {
+    fn is_held_in_place(&self) -> bool {
+        let dx = self.xy[0] - self.start_xy[0];
+        let dy = self.xy[1] - self.start_xy[1];
+        (dx * dx + dy * dy).sqrt() <= LONG_PRESS_MAX_MOVEMENT
+    }
+
+    fn is_long_press(&self) -> bool {
+        self.is_held_in_place() && self.start_time.elapsed() >= LONG_PRESS_DURATION
+    }
+}
+
+impl Touches {
+    /// Update the tracked touches with the given events.
+    ///
+    /// Long presses are recognised either once a touch has been held for `LONG_PRESS_DURATION`
+    /// or, as the GUI may not update while a touch is held still, when such a touch ends.
+    pub fn update<'a, I>(&mut self, events: I)
+    where
+        I: IntoIterator<Item = &'a ui::event::Ui>,
+    {
+        self.long_pressed.clear();
+        for touch in self.active.values_mut() {
+            touch.prev_xy = touch.xy;
+        }
+
+        for event in events {
+            let (widget, t) = match *event {
+                ui::event::Ui::Touch(widget, t) => (widget, t),
+                _ => continue,
+            };
+            match t.phase {
+                ui::input::touch::Phase::Start => {
+                    let touch = Touch {
+                        start_time: Instant::now(),
+                        start_xy: t.xy,
+                        prev_xy: t.xy,
+                        xy: t.xy,
+                        widget,
+                        long_pressed: false,
+                    };
+                    self.active.insert(t.id, touch);
+                },
+                ui::input::touch::Phase::Move => {
+                    if let Some(touch) = self.active.get_mut(&t.id) {
+                        touch.xy = t.xy;
+                    }
+                },
+                ui::input::touch::Phase::End => {
+                    if let Some(mut touch) = self.active.remove(&t.id) {
+                        touch.xy = t.xy;
+                        if !touch.long_pressed && touch.is_long_press() {
+                            self.long_pressed.extend(touch.widget);
+                        }
+                    }
+                },
+                ui::input::touch::Phase::Cancel => {
+                    self.active.remove(&t.id);
+                },
+            }
+        }
+
+        for touch in self.active.values_mut() {
+            if !touch.long_pressed && touch.is_long_press() {
+                touch.long_pressed = true;
+                self.long_pressed.extend(touch.widget);
+            }
+        }
+    }
+
+    /// Whether or not the given widget was long-pressed during the last update.
+    pub fn long_pressed(&self, widget: widget::Id) -> bool {
+        self.long_pressed.contains(&widget)
+    }
+
+    /// The number of touches that began on the given widget.
+    pub fn count_on(&self, widget: widget::Id) -> usize {
+        self.active.values().filter(|t| t.widget == Some(widget)).count()
+    }
+
+    /// The pinch produced since the last update if exactly two touches began on the given widget.
+    pub fn pinch(&self, widget: widget::Id) -> Option<Pinch> {
+        let mut touches = self.active.values().filter(|t| t.widget == Some(widget));
+        let (a, b) = match (touches.next(), touches.next(), touches.next()) {
+            (Some(a), Some(b), None) => (a, b),
+            _ => return None,
+        };
+        let distance = |a: ui::Point, b: ui::Point| {
+            let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+            (dx * dx + dy * dy).sqrt()
+        };
+        let centre = |a: ui::Point, b: ui::Point| [(a[0] + b[0]) * 0.5, (a[1] + b[1]) * 0.5];
+        let prev_distance = distance(a.prev_xy, b.prev_xy);
+        let scale = if prev_distance > 0.0 {
+            distance(a.xy, b.xy) / prev_distance
+        } else {
+            1.0
+        };
+        let prev_centre = centre(a.prev_xy, b.prev_xy);
+        let current_centre = centre(a.xy, b.xy);
+        let pan = [current_centre[0] - prev_centre[0], current_centre[1] - prev_centre[1]];
+        Some(Pinch { scale, pan })
+    }
+}
@@ -4,8 +4,13 @@
 //! hard-coded and rather identified via dynamically generated unique IDs. Otherwise, most
 //! of the logic should remain the same.
 
+use audio::{ducking, reverb};
+use audio::source::Priority;
+use metres::Metres;
 use serde::{Deserialize, Deserializer};
 use slug::slugify;
+use soundscape::activity::Activity;
+use soundscape::schedule::Schedule;
 use utils::Range;
 
 /// All known beyond perception installations (used by default).
@@ -21,6 +26,15 @@ pub const BEYOND_PERCEPTION_NAMES: &'static [&'static str] = &[
     "Turret 2",
 ];
 
+/// The longest duration over which changes to an installation's volume may be faded.
+pub const MAX_VOLUME_FADE_SECS: f64 = 60.0;
+
+/// The highest sensor reading that may be mapped to an installation's gain, e.g. in dB SPL.
+pub const MAX_SENSOR_INPUT: f32 = 140.0;
+
+/// The highest gain that may be applied in response to an installation's sensor.
+pub const MAX_SENSOR_GAIN: f32 = 4.0;
+
 /// A memory efficient unique identifier for an installation.
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Serialize)]
 pub struct Id(pub usize);
@@ -74,6 +88,24 @@ pub struct Installation {
     /// Constraints related to the soundscape.
     #[serde(default)]
     pub soundscape: Soundscape,
+    /// The OSC input address prefix bound to the installation, e.g. "/tracker_a".
+    ///
+    /// Controls received under this prefix only affect sources assigned to the installation. An
+    /// empty prefix binds nothing.
+    #[serde(default)]
+    pub osc_input_prefix: String,
+    /// The installation's reverb bus, returned to the installation's speakers.
+    #[serde(default)]
+    pub reverb: reverb::Params,
+    /// The output level of the installation, applied to the installation's speakers.
+    #[serde(default)]
+    pub volume: Volume,
+    /// Rides the installation's level against an external sensor, e.g. a crowd noise meter.
+    #[serde(default)]
+    pub sensor_gain: SensorGain,
+    /// Ducks the installation's sounds beneath those of a higher priority, e.g. announcements.
+    #[serde(default)]
+    pub ducking: ducking::Params,
 }
 
 impl Default for Installation {
@@ -81,7 +113,21 @@ impl Default for Installation {
         let name = default::name().into();
         let computers = Default::default();
         let soundscape = Default::default();
-        Installation { name, computers, soundscape }
+        let osc_input_prefix = Default::default();
+        let reverb = Default::default();
+        let volume = Default::default();
+        let sensor_gain = Default::default();
+        let ducking = Default::default();
+        Installation {
+            name,
+            computers,
+            soundscape,
+            osc_input_prefix,
+            reverb,
+            volume,
+            sensor_gain,
+            ducking,
+        }
     }
 }
 
@@ -90,12 +136,176 @@ impl Default for Installation {
 pub struct Soundscape {
     #[serde(default = "default::simultaneous_sounds")]
     pub simultaneous_sounds: Range<usize>,
+    /// The number of the simultaneous sounds reserved for each priority class above background.
+    #[serde(default)]
+    pub reserved: Reserved,
+    /// Scales the simultaneous sounds throughout the day.
+    #[serde(default)]
+    pub schedule: Schedule,
+    /// The distance by which the area enclosing the installation's speakers is expanded.
+    ///
+    /// Sounds spawn and move within this area.
+    #[serde(default)]
+    pub area_margin: Metres,
+    /// Maps the visitor activity reported for the installation onto its soundscape.
+    #[serde(default)]
+    pub activity: Activity,
 }
 
+/// The output level of an installation.
+///
+/// Applied on the audio output thread to the speakers assigned to the installation. A speaker
+/// assigned to more than one installation takes the loudest of their levels.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Volume {
+    /// The linear gain applied to the installation's speakers.
+    #[serde(default = "default::volume_gain")]
+    pub gain: f32,
+    /// Whether or not the installation's speakers are silenced.
+    #[serde(default)]
+    pub muted: bool,
+    /// The duration over which changes to the gain or mute are faded.
+    #[serde(default = "default::volume_fade_secs")]
+    pub fade_secs: f64,
+}
+
+/// Maps readings from an external sensor (e.g. an SPL meter or noise sensor) received via OSC to
+/// a gain applied to the installation's speakers on top of its volume.
+///
+/// Readings between the minimum and maximum input are mapped linearly to the gain range and
+/// clamped beyond. Changes are faded over the installation's volume fade duration.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SensorGain {
+    /// Whether or not sensor readings affect the installation's level.
+    #[serde(default)]
+    pub enabled: bool,
+    /// The range of sensor readings mapped to the gain range.
+    #[serde(default = "default::sensor_input")]
+    pub input: Range<f32>,
+    /// The gain applied at the minimum and at the maximum input.
+    #[serde(default = "default::sensor_gain")]
+    pub gain: Range<f32>,
+}
+
+/// The number of an installation's simultaneous sounds reserved for each priority class.
+///
+/// The background class needs no reservation as it may use whatever remains.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Reserved {
+    #[serde(default)]
+    pub feature: usize,
+    #[serde(default)]
+    pub interactive: usize,
+    #[serde(default)]
+    pub announcement: usize,
+}
+
+/// The number of sounds of each priority class active within an installation, indexed by
+/// `Priority::to_index`.
+pub type SoundsPerPriority = [usize; Priority::VARIANT_COUNT];
+
 impl Default for Soundscape {
     fn default() -> Self {
         let simultaneous_sounds = default::SIMULTANEOUS_SOUNDS;
-        Soundscape { simultaneous_sounds }
+        let reserved = Default::default();
+        let schedule = Default::default();
+        let area_margin = Default::default();
+        let activity = Default::default();
+        Soundscape { simultaneous_sounds, reserved, schedule, area_margin, activity }
+    }
+}
+
+impl Volume {
+    /// The gain towards which the installation's speakers fade.
+    pub fn target_gain(&self) -> f32 {
+        match self.muted {
+            true => 0.0,
+            false => self.gain,
+        }
+    }
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Volume {
+            gain: default::volume_gain(),
+            muted: false,
+            fade_secs: default::volume_fade_secs(),
+        }
+    }
+}
+
+impl SensorGain {
+    /// The gain for the given sensor reading, or `1.0` if disabled.
+    pub fn gain_at(&self, reading: f32) -> f32 {
+        if !self.enabled {
+            return 1.0;
+        }
+        let span = self.input.max - self.input.min;
+        let t = match span > 0.0 {
+            true => ((reading - self.input.min) / span).max(0.0).min(1.0),
+            false => if reading >= self.input.min { 1.0 } else { 0.0 },
+        };
+        self.gain.min + (self.gain.max - self.gain.min) * t
+    }
+}
+
+impl Default for SensorGain {
+    fn default() -> Self {
+        SensorGain {
+            enabled: false,
+            input: default::sensor_input(),
+            gain: default::sensor_gain(),
+        }
+    }
+}
+
+impl Reserved {
+    /// The number of sounds reserved for the given priority class.
+    pub fn get(&self, priority: Priority) -> usize {
+        match priority {
+            Priority::Background => 0,
+            Priority::Feature => self.feature,
+            Priority::Interactive => self.interactive,
+            Priority::Announcement => self.announcement,
+        }
+    }
+
+    /// A mutable reference to the number of sounds reserved for the given priority class.
+    ///
+    /// Returns `None` for the background class.
+    pub fn get_mut(&mut self, priority: Priority) -> Option<&mut usize> {
+        match priority {
+            Priority::Background => None,
+            Priority::Feature => Some(&mut self.feature),
+            Priority::Interactive => Some(&mut self.interactive),
+            Priority::Announcement => Some(&mut self.announcement),
+        }
+    }
+
+    /// Whether a sound of the given priority class may be added to an installation that allows
+    /// at most `max` simultaneous sounds and currently plays the given sounds.
+    ///
+    /// The unused reservations of all higher classes are held back from lower classes.
+    pub fn has_headroom(&self, max: usize, active: &SoundsPerPriority, priority: Priority) -> bool {
+        let num_active: usize = active.iter().sum();
+        let held_back: usize = Priority::ALL
+            .iter()
+            .filter(|&&p| p > priority)
+            .map(|&p| self.get(p).saturating_sub(active[p.to_index()]))
+            .sum();
+        num_active + held_back < max
+    }
+}
+
+/// Normalise an OSC input prefix so that it begins with a single '/' and has no trailing '/'.
+///
+/// Returns an empty string if the prefix is empty.
+pub fn normalise_osc_prefix(prefix: &str) -> String {
+    let trimmed = prefix.trim().trim_matches('/');
+    match trimmed.is_empty() {
+        true => String::new(),
+        false => format!("/{}", trimmed),
     }
 }
 
@@ -138,11 +348,30 @@ pub mod default {
     pub fn simultaneous_sounds() -> Range<usize> {
         SIMULTANEOUS_SOUNDS
     }
+
+    pub fn volume_gain() -> f32 {
+        1.0
+    }
+
+    pub fn volume_fade_secs() -> f64 {
+        2.0
+    }
+
+    // A typical range of crowd noise in dB SPL.
+    pub fn sensor_input() -> Range<f32> {
+        Range { min: 55.0, max: 85.0 }
+    }
+
+    // Up to roughly +6dB in the loudest crowds.
+    pub fn sensor_gain() -> Range<f32> {
+        Range { min: 1.0, max: 2.0 }
+    }
 }
 
 /// State related to the computers available to an installation.
 pub mod computer {
     use fxhash::FxHashMap;
+    use slug::slugify;
     use std::net;
 
     /// A unique identifier for a single computer within an installation.
@@ -156,8 +385,43 @@ pub mod computer {
         pub socket: net::SocketAddrV4,
         // The OSC address string.
         pub osc_addr: String,
+        // Whether all OSC sent to the computer is captured to disk.
+        #[serde(default)]
+        pub capture: bool,
+        // Whether OSC to the computer is formatted and logged without actually being sent.
+        #[serde(default)]
+        pub dry_run: bool,
+    }
+
+    /// The name prefixing the files to which OSC sent to the given computer is captured.
+    pub fn capture_name(installation_name: &str, computer: Id) -> String {
+        format!("{}-{}", slugify(installation_name), computer.0)
     }
 
     /// A map from all computer Ids to their addresses.
     pub type Addresses = FxHashMap<Id, Address>;
 }
+
+#[test]
+fn test_reserved_headroom() {
+    let reserved = Reserved { feature: 0, interactive: 2, announcement: 1 };
+    // Background may only use the sounds not reserved for higher classes.
+    assert!(reserved.has_headroom(8, &[4, 0, 0, 0], Priority::Background));
+    assert!(!reserved.has_headroom(8, &[5, 0, 0, 0], Priority::Background));
+    // Interactive sounds may still use their reservation but not that of announcements.
+    assert!(reserved.has_headroom(8, &[5, 0, 1, 0], Priority::Interactive));
+    assert!(!reserved.has_headroom(8, &[5, 0, 2, 0], Priority::Interactive));
+    assert!(reserved.has_headroom(8, &[5, 0, 2, 0], Priority::Announcement));
+    // Once a reservation is in use, it is no longer held back.
+    assert!(!reserved.has_headroom(8, &[5, 0, 2, 1], Priority::Announcement));
+}
+
+#[test]
+fn test_sensor_gain() {
+    let mut sensor = SensorGain::default();
+    assert_eq!(sensor.gain_at(100.0), 1.0);
+    sensor.enabled = true;
+    assert_eq!(sensor.gain_at(0.0), 1.0);
+    assert_eq!(sensor.gain_at(70.0), 1.5);
+    assert_eq!(sensor.gain_at(100.0), 2.0);
+}
@@ -0,0 +1,541 @@
+//! Positional interaction data received from tracking systems.
+//!
+//! Each tracking system reports positions within its own coordinate frame via OSC. An `Input`
+//! describes the OSC address prefix under which a single tracking system sends its positions
+//! along with the `Transform` that maps these positions onto the floorplan. Positions are
+//! transformed and then passed through a `Filter` that smooths tracker jitter and rejects sudden
+//! jumps before they are passed on to the soundscape.
+//!
+//! A single input may track many entities (e.g. visitors) at once, each identified by the
+//! `EntityId` reported alongside its position. Each entity is followed by a `Track` that persists
+//! until no position has been received for the entity within the input's track timeout.
+
+use fxhash::FxHashMap;
+use metres::Metres;
+use nannou::prelude::*;
+use std::time::Instant;
+use time_calc::Ms;
+
+/// A unique identifier for an interaction input.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct Id(pub usize);
+
+/// The identifier reported by a tracking system for a single tracked entity, e.g. a visitor.
+///
+/// Positions received without an identifier are attributed to the default entity `0`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct EntityId(pub i32);
+
+/// The entity used for the visitor simulated by dragging a marker over the floorplan in the GUI.
+pub const SIMULATED_ENTITY: EntityId = EntityId(-1);
+
+/// Uniquely identifies a single tracked entity across all inputs.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TrackId {
+    pub input: Id,
+    pub entity: EntityId,
+}
+
+/// A single source of interaction data, e.g. a tracking system.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Input {
+    /// A human-friendly name for the input.
+    #[serde(default = "default::name")]
+    pub name: String,
+    /// The OSC address prefix under which positions are received, e.g. "/tracker_a".
+    ///
+    /// Positions are expected at "<prefix>/position" with an optional integer entity id followed
+    /// by an `x` and `y` float argument.
+    #[serde(default)]
+    pub osc_prefix: String,
+    /// Maps positions from the input's coordinate frame onto the floorplan.
+    #[serde(default)]
+    pub transform: Transform,
+    /// Describes how transformed positions are smoothed and how outliers are rejected.
+    #[serde(default)]
+    pub smoothing: Smoothing,
+    /// The duration without any received positions after which a tracked entity is considered
+    /// lost.
+    #[serde(default = "default::track_timeout")]
+    pub track_timeout: Ms,
+}
+
+/// Parameters for smoothing the positions of an input and rejecting outliers.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Smoothing {
+    /// The time constant of the exponential moving average applied to positions.
+    ///
+    /// A duration of `0` disables smoothing.
+    #[serde(default = "default::time_constant")]
+    pub time_constant: Ms,
+    /// Positions further than this distance from the smoothed position are rejected.
+    ///
+    /// A distance of `0` disables outlier rejection.
+    #[serde(default = "default::max_jump")]
+    pub max_jump: Metres,
+    /// The number of consecutive rejected positions after which the input is assumed to have
+    /// genuinely moved, in which case the filter jumps straight to the new position.
+    #[serde(default = "default::max_rejections")]
+    pub max_rejections: u32,
+}
+
+/// The runtime state used to smooth the positions of a single input.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Filter {
+    // The smoothed position and the moment at which it was last updated.
+    state: Option<(Point2<Metres>, Instant)>,
+    // The number of consecutive positions that have been rejected.
+    rejections: u32,
+}
+
+/// An affine mapping from an input's coordinate frame onto the floorplan.
+///
+/// Positions are first scaled along each axis, then rotated and then translated. A negative scale
+/// mirrors positions along that axis.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Transform {
+    #[serde(default = "default::scale")]
+    pub scale_x: f64,
+    #[serde(default = "default::scale")]
+    pub scale_y: f64,
+    /// The rotation applied after scaling, counter-clockwise in radians.
+    #[serde(default)]
+    pub radians: f64,
+    /// The position on the floorplan at which the input's origin lies.
+    #[serde(default = "default::translation")]
+    pub translation: Point2<Metres>,
+}
+
+/// A position received from an input.
+#[derive(Copy, Clone, Debug)]
+pub struct Interaction {
+    /// The input from which the position was received.
+    pub input: Id,
+    /// The entity whose position was reported.
+    pub entity: EntityId,
+    /// The position within the input's own coordinate frame.
+    pub raw: Point2<f64>,
+    /// The position on the floorplan after the input's transform has been applied.
+    pub position: Point2<Metres>,
+}
+
+/// The runtime state of a single tracked entity.
+#[derive(Copy, Clone, Debug)]
+pub struct Track {
+    /// The latest accepted interaction for the entity.
+    pub interaction: Interaction,
+    /// The moment at which the entity was first seen.
+    pub first_seen: Instant,
+    /// The moment at which a position was last received for the entity, accepted or not.
+    pub last_seen: Instant,
+    filter: Filter,
+}
+
+/// All entities currently tracked across all inputs.
+#[derive(Debug, Default)]
+pub struct Tracks {
+    map: FxHashMap<TrackId, Track>,
+}
+
+/// A pair of corresponding points used to calibrate an input's `Transform`.
+#[derive(Copy, Clone, Debug)]
+pub struct CalibrationPoint {
+    /// The position reported by the input.
+    pub raw: Point2<f64>,
+    /// The position on the floorplan at which the input was located.
+    pub floorplan: Point2<Metres>,
+}
+
+impl Input {
+    /// Produce the interaction for the given position reported by the input.
+    pub fn interaction(&self, id: Id, entity: EntityId, raw: Point2<f64>) -> Interaction {
+        let position = self.transform.apply(raw);
+        Interaction { input: id, entity, raw, position }
+    }
+}
+
+impl Interaction {
+    /// The track to which the interaction belongs.
+    pub fn track_id(&self) -> TrackId {
+        TrackId { input: self.input, entity: self.entity }
+    }
+}
+
+impl Tracks {
+    /// Update the track of the given entity with a position reported by the input.
+    ///
+    /// A new track is started if the entity is not yet tracked. Returns the smoothed interaction
+    /// or `None` if the position was rejected as an outlier.
+    pub fn update(
+        &mut self,
+        input: &Input,
+        id: Id,
+        entity: EntityId,
+        raw: Point2<f64>,
+        now: Instant,
+    ) -> Option<Interaction> {
+        let mut interaction = input.interaction(id, entity, raw);
+        let track = self.map.entry(interaction.track_id()).or_insert_with(|| Track {
+            interaction,
+            first_seen: now,
+            last_seen: now,
+            filter: Default::default(),
+        });
+        track.last_seen = now;
+        interaction.position = track.filter.apply(&input.smoothing, interaction.position, now)?;
+        track.interaction = interaction;
+        Some(interaction)
+    }
+
+    /// Remove all tracks that have not been seen within their input's track timeout, along with
+    /// all tracks whose input no longer exists.
+    ///
+    /// The `Id` of each removed track is pushed onto `lost`.
+    pub fn remove_lost(
+        &mut self,
+        inputs: &FxHashMap<Id, Input>,
+        now: Instant,
+        lost: &mut Vec<TrackId>,
+    ) {
+        self.map.retain(|track_id, track| {
+            let is_alive = match inputs.get(&track_id.input) {
+                None => false,
+                Some(input) => {
+                    let elapsed = now.duration_since(track.last_seen);
+                    let elapsed_ms = elapsed.as_secs() as f64 * 1_000.0
+                        + elapsed.subsec_nanos() as f64 / 1_000_000.0;
+                    elapsed_ms < input.track_timeout.ms()
+                },
+            };
+            if !is_alive {
+                lost.push(*track_id);
+            }
+            is_alive
+        });
+    }
+
+    /// Remove all tracks belonging to the given input.
+    pub fn remove_input(&mut self, id: &Id) {
+        self.map.retain(|track_id, _| track_id.input != *id);
+    }
+
+    /// Reset the smoothing of all tracks belonging to the given input, e.g. after the input's
+    /// transform has changed.
+    pub fn reset_filters(&mut self, id: &Id) {
+        for (_, track) in self.map.iter_mut().filter(|&(track_id, _)| track_id.input == *id) {
+            track.filter = Default::default();
+        }
+    }
+
+    /// The track of the given input that most recently received a position.
+    pub fn latest(&self, id: &Id) -> Option<&Track> {
+        self.map
+            .iter()
+            .filter(|&(track_id, _)| track_id.input == *id)
+            .map(|(_, track)| track)
+            .max_by_key(|track| track.last_seen)
+    }
+
+    /// The number of entities tracked by the given input.
+    pub fn count(&self, id: &Id) -> usize {
+        self.map.keys().filter(|track_id| track_id.input == *id).count()
+    }
+
+    /// All current tracks.
+    pub fn iter(&self) -> impl Iterator<Item = (&TrackId, &Track)> {
+        self.map.iter()
+    }
+}
+
+impl Filter {
+    /// Filter the given position received at the given moment.
+    ///
+    /// Returns the smoothed position or `None` if the position was rejected as an outlier.
+    pub fn apply(
+        &mut self,
+        smoothing: &Smoothing,
+        position: Point2<Metres>,
+        now: Instant,
+    ) -> Option<Point2<Metres>> {
+        let (smoothed, last) = match self.state {
+            Some(state) => state,
+            None => return Some(self.reset(position, now)),
+        };
+
+        // Reject positions that jump too far from the smoothed position.
+        let max_jump = smoothing.max_jump.0;
+        if max_jump > 0.0 {
+            let distance = (position.x - smoothed.x).0.hypot((position.y - smoothed.y).0);
+            if distance > max_jump {
+                self.rejections += 1;
+                if self.rejections < smoothing.max_rejections {
+                    return None;
+                }
+                return Some(self.reset(position, now));
+            }
+        }
+        self.rejections = 0;
+
+        // Move towards the new position by an amount relative to the time since the last update
+        // so that smoothing is independent of the rate at which the input sends positions.
+        let time_constant_ms = smoothing.time_constant.ms();
+        let amount = if time_constant_ms > 0.0 {
+            let elapsed = now.duration_since(last);
+            let elapsed_ms = elapsed.as_secs() as f64 * 1_000.0
+                + elapsed.subsec_nanos() as f64 / 1_000_000.0;
+            1.0 - (-elapsed_ms / time_constant_ms).exp()
+        } else {
+            1.0
+        };
+        let x = smoothed.x + (position.x - smoothed.x) * amount;
+        let y = smoothed.y + (position.y - smoothed.y) * amount;
+        let smoothed = pt2(x, y);
+        self.state = Some((smoothed, now));
+        Some(smoothed)
+    }
+
+    // Jump straight to the given position.
+    fn reset(&mut self, position: Point2<Metres>, now: Instant) -> Point2<Metres> {
+        self.state = Some((position, now));
+        self.rejections = 0;
+        position
+    }
+}
+
+impl Transform {
+    /// Map the given position from the input's coordinate frame onto the floorplan.
+    pub fn apply(&self, p: Point2<f64>) -> Point2<Metres> {
+        let (sin, cos) = self.radians.sin_cos();
+        let (x, y) = (p.x * self.scale_x, p.y * self.scale_y);
+        let rx = x * cos - y * sin;
+        let ry = x * sin + y * cos;
+        pt2(self.translation.x + Metres(rx), self.translation.y + Metres(ry))
+    }
+
+    /// Map the given floorplan position back into the input's coordinate frame.
+    ///
+    /// Returns `None` if either axis has a scale of zero.
+    pub fn invert(&self, p: Point2<Metres>) -> Option<Point2<f64>> {
+        if self.scale_x.abs() < ::std::f64::EPSILON || self.scale_y.abs() < ::std::f64::EPSILON {
+            return None;
+        }
+        let (sin, cos) = self.radians.sin_cos();
+        let (dx, dy) = ((p.x - self.translation.x).0, (p.y - self.translation.y).0);
+        let x = dx * cos + dy * sin;
+        let y = dy * cos - dx * sin;
+        Some(pt2(x / self.scale_x, y / self.scale_y))
+    }
+
+    /// Solve for the transform that best maps the raw positions of the given points onto their
+    /// floorplan positions.
+    ///
+    /// Two points determine a uniform scale, rotation and translation. Three or more points
+    /// determine a least squares fit that may also scale or mirror each axis independently.
+    ///
+    /// Returns `None` if there are too few distinct points.
+    pub fn calibrate(points: &[CalibrationPoint]) -> Option<Self> {
+        match points.len() {
+            0 | 1 => None,
+            2 => Self::similarity(&points[0], &points[1]),
+            _ => Self::least_squares(points)
+                .or_else(|| Self::similarity(&points[0], &points[points.len() - 1])),
+        }
+    }
+
+    // The uniformly scaled transform mapping `a.raw` onto `a.floorplan` and `b.raw` onto
+    // `b.floorplan`.
+    fn similarity(a: &CalibrationPoint, b: &CalibrationPoint) -> Option<Self> {
+        let (rdx, rdy) = (b.raw.x - a.raw.x, b.raw.y - a.raw.y);
+        let (fdx, fdy) = ((b.floorplan.x - a.floorplan.x).0, (b.floorplan.y - a.floorplan.y).0);
+        let raw_len = rdx.hypot(rdy);
+        if raw_len < ::std::f64::EPSILON {
+            return None;
+        }
+        let scale = fdx.hypot(fdy) / raw_len;
+        let radians = fdy.atan2(fdx) - rdy.atan2(rdx);
+        Some(Self::from_scaled_rotation(scale, scale, radians, a))
+    }
+
+    // The least squares affine fit of the given points, decomposed into scale and rotation.
+    fn least_squares(points: &[CalibrationPoint]) -> Option<Self> {
+        // Accumulate the normal equations for rows of the form `[x, y, 1]`.
+        let mut ata = [[0.0; 3]; 3];
+        let mut atx = [0.0; 3];
+        let mut aty = [0.0; 3];
+        for p in points {
+            let row = [p.raw.x, p.raw.y, 1.0];
+            for i in 0..3 {
+                for j in 0..3 {
+                    ata[i][j] += row[i] * row[j];
+                }
+                atx[i] += row[i] * p.floorplan.x.0;
+                aty[i] += row[i] * p.floorplan.y.0;
+            }
+        }
+        let [m00, m01, tx] = solve_3x3(&ata, &atx)?;
+        let [m10, m11, ty] = solve_3x3(&ata, &aty)?;
+
+        // Decompose the linear part into a rotation of an axis-aligned scale, ignoring shear.
+        let scale_x = m00.hypot(m10);
+        let radians = m10.atan2(m00);
+        let (sin, cos) = radians.sin_cos();
+        let scale_y = -sin * m01 + cos * m11;
+        let translation = pt2(Metres(tx), Metres(ty));
+        Some(Transform { scale_x, scale_y, radians, translation })
+    }
+
+    // The transform with the given scale and rotation whose translation maps `p.raw` onto
+    // `p.floorplan`.
+    fn from_scaled_rotation(
+        scale_x: f64,
+        scale_y: f64,
+        radians: f64,
+        p: &CalibrationPoint,
+    ) -> Self {
+        let translation = pt2(Metres(0.0), Metres(0.0));
+        let mut transform = Transform { scale_x, scale_y, radians, translation };
+        let origin = transform.apply(p.raw);
+        transform.translation = pt2(p.floorplan.x - origin.x, p.floorplan.y - origin.y);
+        transform
+    }
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Input {
+            name: default::name(),
+            osc_prefix: String::new(),
+            transform: Default::default(),
+            smoothing: Default::default(),
+            track_timeout: default::track_timeout(),
+        }
+    }
+}
+
+impl Default for Smoothing {
+    fn default() -> Self {
+        Smoothing {
+            time_constant: default::time_constant(),
+            max_jump: default::max_jump(),
+            max_rejections: default::max_rejections(),
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform {
+            scale_x: default::scale(),
+            scale_y: default::scale(),
+            radians: 0.0,
+            translation: default::translation(),
+        }
+    }
+}
+
+// Solve the 3x3 system `m * x = b` via Cramer's rule, returning `None` if `m` is singular.
+fn solve_3x3(m: &[[f64; 3]; 3], b: &[f64; 3]) -> Option<[f64; 3]> {
+    fn det(m: &[[f64; 3]; 3]) -> f64 {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+    // Compare the determinant relative to the magnitude of `m` as coordinates may be in any unit.
+    let d = det(m);
+    let max = m.iter().flat_map(|row| row.iter()).fold(0.0f64, |max, v| max.max(v.abs()));
+    if d.abs() <= 1e-12 * max * max * max {
+        return None;
+    }
+    let mut x = [0.0; 3];
+    for col in 0..3 {
+        let mut mc = *m;
+        for row in 0..3 {
+            mc[row][col] = b[row];
+        }
+        x[col] = det(&mc) / d;
+    }
+    Some(x)
+}
+
+pub mod default {
+    use metres::Metres;
+    use nannou::prelude::*;
+    use time_calc::Ms;
+
+    pub fn name() -> String {
+        "Interaction Input".into()
+    }
+
+    pub fn scale() -> f64 {
+        1.0
+    }
+
+    pub fn translation() -> Point2<Metres> {
+        pt2(Metres(0.0), Metres(0.0))
+    }
+
+    pub fn time_constant() -> Ms {
+        Ms(150.0)
+    }
+
+    pub fn max_jump() -> Metres {
+        Metres(2.0)
+    }
+
+    pub fn max_rejections() -> u32 {
+        10
+    }
+
+    pub fn track_timeout() -> Ms {
+        Ms(2_000.0)
+    }
+}
+
+#[test]
+fn test_calibrate() {
+    let expected = Transform {
+        scale_x: 0.001,
+        scale_y: -0.002,
+        radians: 0.5,
+        translation: pt2(Metres(3.0), Metres(-2.0)),
+    };
+    let points: Vec<_> = [(0.0, 0.0), (1000.0, 0.0), (0.0, 1000.0), (500.0, 250.0)]
+        .iter()
+        .map(|&(x, y)| {
+            let raw = pt2(x, y);
+            CalibrationPoint { raw, floorplan: expected.apply(raw) }
+        })
+        .collect();
+    let transform = Transform::calibrate(&points).unwrap();
+    assert!((transform.scale_x - expected.scale_x).abs() < 1e-9);
+    assert!((transform.scale_y - expected.scale_y).abs() < 1e-9);
+    assert!((transform.radians - expected.radians).abs() < 1e-9);
+    let p = transform.apply(pt2(123.0, 456.0));
+    let q = expected.apply(pt2(123.0, 456.0));
+    assert!((p.x - q.x).0.abs() < 1e-9 && (p.y - q.y).0.abs() < 1e-9);
+    let raw = expected.invert(q).unwrap();
+    assert!((raw.x - 123.0).abs() < 1e-9 && (raw.y - 456.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_filter_rejects_outliers() {
+    use std::time::Duration;
+    let smoothing = Smoothing {
+        time_constant: Ms(0.0),
+        max_jump: Metres(1.0),
+        max_rejections: 3,
+    };
+    let mut filter = Filter::default();
+    let mut now = Instant::now();
+    let mut next = |filter: &mut Filter, x: f64| {
+        now += Duration::from_millis(10);
+        filter.apply(&smoothing, pt2(Metres(x), Metres(0.0)), now).map(|p| p.x.0)
+    };
+    assert_eq!(next(&mut filter, 0.0), Some(0.0));
+    assert_eq!(next(&mut filter, 0.5), Some(0.5));
+    assert_eq!(next(&mut filter, 5.0), None);
+    assert_eq!(next(&mut filter, 0.6), Some(0.6));
+    assert_eq!(next(&mut filter, 5.0), None);
+    assert_eq!(next(&mut filter, 5.0), None);
+    assert_eq!(next(&mut filter, 5.0), Some(5.0));
+}
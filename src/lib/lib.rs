@@ -9,23 +9,31 @@ extern crate crossbeam;
 #[macro_use]
 extern crate custom_derive;
 extern crate fxhash;
+extern crate gilrs;
 extern crate hound; // wav loading
 extern crate nannou;
 extern crate nannou_audio;
 extern crate nannou_osc;
 #[macro_use]
 extern crate newtype_derive;
+extern crate notify;
 extern crate num_cpus;
 extern crate pitch_calc;
 extern crate rand_xorshift;
+extern crate rayon;
+extern crate resvg;
 extern crate rustfft;
+#[cfg(feature = "link")]
+extern crate rusty_link; // ableton link tempo sync
 extern crate serde; // serialization
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
 extern crate slug;
 extern crate time_calc;
+extern crate tiny_skia;
 extern crate threadpool;
+extern crate usvg;
 extern crate utils as mindtree_utils;
 extern crate walkdir;
 
@@ -38,14 +46,17 @@ use std::sync::atomic::AtomicUsize;
 
 mod audio;
 mod camera;
+mod cli;
 mod config;
 mod gui;
 mod installation;
+mod interaction;
 mod master;
 mod metres;
 mod project;
 mod osc;
 mod soundscape;
+mod time_zone;
 mod utils;
 
 pub fn run() {
@@ -64,7 +75,35 @@ struct Model {
     soundscape: Soundscape,
     config: Config,
     audio_monitor: gui::monitor::Monitor,
+    audio_watcher: Option<gui::audio_watcher::AudioWatcher>,
+    gamepad: Option<gui::gamepad::Gamepad>,
     wav_reader: audio::source::wav::reader::Handle,
+    audio_input_streams: audio::input::Streams,
+    audio_output_stream: audio::output::Stream,
+    audio_output_handles: AudioOutputHandles,
+    aggregate_output_streams: Vec<audio::aggregate::Stream>,
+    assets: PathBuf,
+    overrides: cli::Overrides,
+}
+
+// The handles given to each audio output model, kept so that the streams may be rebuilt.
+struct AudioOutputHandles {
+    frame_count: Arc<AtomicUsize>,
+    audio_monitor_tx: gui::monitor::Sender,
+    osc_out_msg_tx: osc::output::Tx,
+    soundscape_tx: mpsc::Sender<soundscape::Message>,
+    wav_reader: audio::source::wav::reader::Handle,
+}
+
+// The streams built for the current stream configuration.
+struct AudioStreams {
+    input: audio::input::Stream,
+    input_device_name: String,
+    input_channels: usize,
+    output: nannou_audio::Stream<audio::output::Model>,
+    // The channels of the primary output device and all aggregated devices.
+    output_channels: usize,
+    aggregate_outputs: Vec<audio::aggregate::Stream>,
 }
 
 // The path to the server's config file.
@@ -88,13 +127,32 @@ fn model(app: &App) -> Model {
         app.set_loop_mode(LoopMode::Wait);
     }
 
+    // Read any config overrides from the command line and environment.
+    let overrides = match cli::Overrides::from_env_and_args() {
+        Ok(cli::Parsed::Run(overrides)) => overrides,
+        Ok(cli::Parsed::Help) => {
+            println!("{}", cli::USAGE);
+            std::process::exit(0);
+        },
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        },
+    };
+
     // Find the assets directory.
-    let assets = app.assets_path()
-        .expect("could not find assets directory");
+    let assets = match overrides.assets {
+        Some(ref assets) => assets.clone(),
+        None => app.assets_path().expect("could not find assets directory"),
+    };
 
-    // Load the configuration struct.
+    // Load the configuration struct and apply any overrides.
     let config_path = config_path(&assets);
-    let config: Config = utils::load_from_json_or_default(&config_path);
+    let mut config: Config = utils::load_from_json_or_default(&config_path);
+    overrides.apply(&mut config);
+
+    // Everything that depends on the sample rate or buffer size must be created after this.
+    audio::set_stream_config(config.stream);
 
     // Spawn the OSC input thread.
     let osc_receiver = nannou_osc::receiver(config.osc_input_port)
@@ -104,13 +162,37 @@ fn model(app: &App) -> Model {
     let (_osc_in_thread_handle, osc_in_log_rx, control_rx) = osc::input::spawn(osc_receiver);
 
     // Spawn the OSC output thread.
-    let (_osc_out_thread_handle, osc_out_msg_tx, osc_out_log_rx) = osc::output::spawn();
+    let captures_directory = osc::capture::captures_directory(&assets);
+    let (_osc_out_thread_handle, osc_out_msg_tx, osc_out_log_rx) =
+        osc::output::spawn(captures_directory);
 
     // A channel for sending active sound info from the audio thread to the GUI.
     let app_proxy = app.create_proxy();
     let (audio_monitor, audio_monitor_tx, audio_monitor_rx) = gui::monitor::spawn(app_proxy)
         .expect("failed to spawn audio_monitor thread");
 
+    // Watch the audio directory for changes to WAV files.
+    let audio_path = project::audio_directory(&assets);
+    let (audio_watcher, audio_watcher_rx) =
+        match gui::audio_watcher::spawn(&audio_path, app.create_proxy()) {
+            Ok((watcher, rx)) => (Some(watcher), rx),
+            Err(err) => {
+                eprintln!("failed to watch \"{}\" for changes: {}", audio_path.display(), err);
+                let (_tx, rx) = mpsc::channel();
+                (None, rx)
+            },
+        };
+
+    // Poll any connected game controllers for nudging and camera control.
+    let (gamepad, gamepad_rx) = match gui::gamepad::spawn(app.create_proxy()) {
+        Ok((gamepad, rx)) => (Some(gamepad), rx),
+        Err(err) => {
+            eprintln!("failed to spawn gamepad thread: {}", err);
+            let (_tx, rx) = mpsc::channel();
+            (None, rx)
+        },
+    };
+
     // Spawn the thread used for reading wavs.
     let wav_reader = audio::source::wav::reader::spawn();
 
@@ -121,48 +203,34 @@ fn model(app: &App) -> Model {
     // synchronising continuous WAV soures.
     let frame_count = Arc::new(AtomicUsize::new(0));
 
-    // Retrieve the audio host.
-    let audio_host = audio::host();
-
-    // Initialise the audio input model and create the input stream.
-    let input_device = audio::find_input_device(&audio_host, &config.target_input_device_name)
-        .expect("no input devices available on the system");
-    let max_supported_input_channels = input_device.max_supported_input_channels();
-    let audio_input_channels = std::cmp::min(max_supported_input_channels, audio::MAX_CHANNELS);
-    println!("Selected Input Device: {:?}", input_device.name());
-    let audio_input_model = audio::input::Model::new();
-    let audio_input_stream = audio_host
-        .new_input_stream(audio_input_model)
-        .capture(audio::input::capture)
-        .sample_rate(audio::SAMPLE_RATE as u32)
-        .frames_per_buffer(audio::FRAMES_PER_BUFFER)
-        .channels(audio_input_channels)
-        .device(input_device)
-        .build()
-        .expect("failed to build audio input stream");
-
-    // Initialise the audio output model and create the output stream.
-    let output_device = audio::find_output_device(&audio_host, &config.target_output_device_name)
-        .expect("no output devices available on the system");
-    println!("Selected Output Device: {:?}", output_device.name());
-    let max_supported_output_channels = output_device.max_supported_output_channels();
-    let audio_output_channels = std::cmp::min(max_supported_output_channels, audio::MAX_CHANNELS);
-    let audio_output_model = audio::output::Model::new(
-        frame_count.clone(),
+    // The handles shared with every audio output model, including those of rebuilt streams.
+    let audio_output_handles = AudioOutputHandles {
+        frame_count: frame_count.clone(),
         audio_monitor_tx,
-        osc_out_msg_tx.clone(),
-        soundscape_tx.clone(),
-        wav_reader.clone(),
+        osc_out_msg_tx: osc_out_msg_tx.clone(),
+        soundscape_tx: soundscape_tx.clone(),
+        wav_reader: wav_reader.clone(),
+    };
+
+    // Build the input and output streams for the stream configuration.
+    let streams = build_audio_streams(&config, &audio_output_handles)
+        .unwrap_or_else(|err| panic!("{}", err));
+    let AudioStreams {
+        input: audio_input_stream,
+        input_device_name,
+        input_channels: audio_input_channels,
+        output: audio_output_stream,
+        output_channels: audio_output_channels,
+        aggregate_outputs: aggregate_output_streams,
+    } = streams;
+
+    // Streams for other input devices are opened on demand by the realtime sources that use them.
+    let audio_input_streams = audio::input::Streams::new(
+        audio_input_stream,
+        input_device_name,
+        audio_input_channels,
     );
-    let audio_output_stream = audio_host
-        .new_output_stream(audio_output_model)
-        .render(audio::output::render)
-        .sample_rate(audio::SAMPLE_RATE as u32)
-        .frames_per_buffer(audio::FRAMES_PER_BUFFER)
-        .channels(audio_output_channels)
-        .device(output_device)
-        .build()
-        .expect("failed to build audio output stream");
+    let audio_output_stream = audio::output::Stream::new(audio_output_stream);
 
     // To be shared between the `Composer` and `GUI` threads as both are responsible for creating
     // sounds and sending them to the audio thread.
@@ -175,15 +243,16 @@ fn model(app: &App) -> Model {
         soundscape_tx,
         soundscape_rx,
         wav_reader.clone(),
-        audio_input_stream.clone(),
+        audio_input_streams.clone(),
         audio_output_stream.clone(),
         sound_id_gen.clone(),
     );
 
-    // Create a window.
+    // Create a window. When headless, the window is never shown.
     let window = app.new_window()
         .title("Audio Server")
         .size(config.window_width, config.window_height)
+        .visible(!overrides.headless)
         .build()
         .expect("failed to create window");
 
@@ -196,9 +265,11 @@ fn model(app: &App) -> Model {
         control_rx,
         soundscape.clone(),
         wav_reader.clone(),
-        audio_input_stream.clone(),
+        audio_input_streams.clone(),
         audio_output_stream.clone(),
         audio_monitor_rx,
+        audio_watcher_rx,
+        gamepad_rx,
     );
     let gui = gui::Model::new(
         &assets,
@@ -216,66 +287,268 @@ fn model(app: &App) -> Model {
     // Some platforms do this automatically, but this is necessary for platforms that are paused by
     // default (e.g. ASIO). Eventually, CPAL should be made to have consistent behaviour across
     // platforms.
-    if let Err(err) = audio_input_stream.play() {
-        eprintln!("Failed to start playing the audio input stream: {}", err);
-    }
-    if let Err(err) = audio_output_stream.play() {
-        eprintln!("Failed to start playing the audio output stream: {}", err);
-    }
+    play_audio_streams(&audio_input_streams, &audio_output_stream, &aggregate_output_streams);
 
     Model {
         soundscape,
         config,
         gui,
         audio_monitor,
+        audio_watcher,
+        gamepad,
         wav_reader,
+        audio_input_streams,
+        audio_output_stream,
+        audio_output_handles,
+        aggregate_output_streams,
+        assets,
+        overrides,
+    }
+}
+
+// Build the input, output and aggregate output streams for the current stream configuration.
+//
+// The devices are announced via the OSC output thread. The streams are not yet played.
+fn build_audio_streams(
+    config: &Config,
+    handles: &AudioOutputHandles,
+) -> Result<AudioStreams, String> {
+    // Retrieve the audio host.
+    let audio_host = audio::host();
+
+    // Initialise the audio input model and create the input stream.
+    let input_device = audio::find_input_device(&audio_host, &config.target_input_device_name)
+        .ok_or_else(|| "no input devices available on the system".to_string())?;
+    let max_supported_input_channels = input_device.max_supported_input_channels();
+    let input_channels = std::cmp::min(max_supported_input_channels, audio::MAX_CHANNELS);
+    println!("Selected Input Device: {:?}", input_device.name());
+    let input_device_name = input_device.name().unwrap_or_default();
+    let audio_input_model = audio::input::Model::new();
+    let input = audio_host
+        .new_input_stream(audio_input_model)
+        .capture(audio::input::capture)
+        .sample_rate(audio::sample_rate() as u32)
+        .frames_per_buffer(audio::frames_per_buffer())
+        .channels(input_channels)
+        .device(input_device)
+        .build()
+        .map_err(|err| format!("failed to build audio input stream: {}", err))?;
+
+    // Initialise the audio output model and create the output stream.
+    let output_device = audio::find_output_device(&audio_host, &config.target_output_device_name)
+        .ok_or_else(|| "no output devices available on the system".to_string())?;
+    println!("Selected Output Device: {:?}", output_device.name());
+    let max_supported_output_channels = output_device.max_supported_output_channels();
+    let primary_output_channels =
+        std::cmp::min(max_supported_output_channels, audio::MAX_CHANNELS);
+    let mut audio_output_model = audio::output::Model::new(
+        handles.frame_count.clone(),
+        handles.audio_monitor_tx.clone(),
+        handles.osc_out_msg_tx.clone(),
+        handles.soundscape_tx.clone(),
+        handles.wav_reader.clone(),
+    );
+
+    // Open a stream for each additional output device, appending its channels to the primary's.
+    let mut aggregate = audio::aggregate::Aggregate::new(primary_output_channels);
+    let mut aggregate_outputs = vec![];
+    for name in &config.aggregate_output_device_names {
+        let device = match audio::find_named_output_device(&audio_host, name) {
+            Some(device) => device,
+            None => {
+                eprintln!("failed to find aggregate output device \"{}\"", name);
+                continue;
+            },
+        };
+        let remaining_channels = audio::MAX_CHANNELS - aggregate.channels();
+        let channels = std::cmp::min(device.max_supported_output_channels(), remaining_channels);
+        if channels == 0 {
+            eprintln!("no channels remaining for aggregate output device \"{}\"", name);
+            continue;
+        }
+        println!("Aggregated Output Device: {:?}", device.name());
+        let (aggregate_device, aggregate_model) = aggregate.new_device(channels);
+        let stream = audio_host
+            .new_output_stream(aggregate_model)
+            .render(audio::aggregate::render)
+            .sample_rate(audio::sample_rate() as u32)
+            .frames_per_buffer(audio::frames_per_buffer())
+            .channels(channels)
+            .device(device)
+            .build();
+        match stream {
+            Ok(stream) => {
+                aggregate.add_device(aggregate_device);
+                aggregate_outputs.push(stream);
+            },
+            Err(err) => eprintln!("failed to build aggregate output stream: {}", err),
+        }
     }
+    audio_output_model.dither =
+        audio::dither::Dither::new(config.stream.output_word_length, config.stream.dither);
+    let output_channels = if aggregate_outputs.is_empty() {
+        primary_output_channels
+    } else {
+        let channels = aggregate.channels();
+        audio_output_model.aggregate = Some(aggregate);
+        channels
+    };
+
+    // Include the audio devices within each announcement sent by the OSC output thread.
+    let audio_devices = osc::output::AudioDevices {
+        input: input_device_name.clone(),
+        output: output_device.name().unwrap_or_default(),
+        output_channels,
+        sample_rate: audio::sample_rate() as u32,
+    };
+    handles.osc_out_msg_tx.push(osc::output::Message::AudioDevices(audio_devices));
+
+    let output = audio_host
+        .new_output_stream(audio_output_model)
+        .render(audio::output::render)
+        .sample_rate(audio::sample_rate() as u32)
+        .frames_per_buffer(audio::frames_per_buffer())
+        .channels(primary_output_channels)
+        .device(output_device)
+        .build()
+        .map_err(|err| format!("failed to build audio output stream: {}", err))?;
+
+    Ok(AudioStreams {
+        input,
+        input_device_name,
+        input_channels,
+        output,
+        output_channels,
+        aggregate_outputs,
+    })
+}
+
+// Start playing the given streams.
+fn play_audio_streams(
+    input: &audio::input::Streams,
+    output: &audio::output::Stream,
+    aggregate_outputs: &[audio::aggregate::Stream],
+) {
+    if let Err(err) = input.play() {
+        eprintln!("Failed to start playing the audio input stream: {}", err);
+    }
+    if let Err(err) = output.play() {
+        eprintln!("Failed to start playing the audio output stream: {}", err);
+    }
+    for stream in aggregate_outputs {
+        if let Err(err) = stream.play() {
+            eprintln!("Failed to start playing an aggregate output stream: {}", err);
+        }
+    }
+}
+
+// Close the running audio streams and rebuild them with the given configuration in place.
+//
+// The streams are closed before rebuilding as some hosts only allow a device to be opened once. If
+// the new streams fail to build, those of the previous configuration are rebuilt instead. The
+// project is then synced to the new stream models.
+fn rebuild_audio_streams(model: &mut Model, stream: audio::StreamConfig) {
+    model.audio_input_streams.close();
+    model.audio_output_stream.close();
+    model.aggregate_output_streams.clear();
+
+    let previous = model.config.stream;
+    model.config.stream = stream;
+    audio::set_stream_config(stream);
+    let streams = match build_audio_streams(&model.config, &model.audio_output_handles) {
+        Ok(streams) => streams,
+        Err(err) => {
+            eprintln!("failed to rebuild the audio streams: {}", err);
+            model.config.stream = previous;
+            audio::set_stream_config(previous);
+            match build_audio_streams(&model.config, &model.audio_output_handles) {
+                Ok(streams) => streams,
+                Err(err) => {
+                    eprintln!("failed to rebuild the audio streams as they were: {}", err);
+                    return;
+                },
+            }
+        },
+    };
+
+    let AudioStreams {
+        input,
+        input_device_name,
+        input_channels,
+        output,
+        output_channels,
+        aggregate_outputs,
+    } = streams;
+    model.audio_input_streams.replace_default(input, input_device_name, input_channels);
+    model.audio_output_stream.replace(output);
+    model.aggregate_output_streams = aggregate_outputs;
+    play_audio_streams(
+        &model.audio_input_streams,
+        &model.audio_output_stream,
+        &model.aggregate_output_streams,
+    );
+
+    let running = model.config.stream;
+    model.gui.audio_streams_rebuilt(running, input_channels, output_channels);
 }
 
 // Update the application in accordance with the given event.
-fn update(_app: &App, model: &mut Model, _update: Update) {
+fn update(app: &App, model: &mut Model, _update: Update) {
     let Model { ref mut gui, ref config, .. } = *model;
-    gui.update(&config.project_default);
+    gui.update(app, &config.project_default);
+
+    // Rebuild the audio streams if a new configuration was applied.
+    if let Some(stream) = gui.take_stream_config_to_apply() {
+        rebuild_audio_streams(model, stream);
+    }
 }
 
 // Draw the state of the application to the screen.
 fn view(app: &App, model: &Model, frame: Frame) {
+    if model.overrides.headless {
+        return;
+    }
     model.gui.ui.draw_to_frame_if_changed(app, &frame).expect("failed to draw to frame");
 }
 
 // Re-join with spawned threads on application exit.
-fn exit(app: &App, model: Model) {
+fn exit(_app: &App, model: Model) {
     let Model {
-        gui,
+        mut gui,
         mut config,
         soundscape,
         audio_monitor,
+        audio_watcher,
+        gamepad,
         wav_reader,
+        assets,
+        overrides,
         ..
     } = model;
 
     // Update whether or not cpu saving mode should be enabled when re-opening.
     config.cpu_saving_mode = gui.cpu_saving_mode;
+    config.touch_mode = gui.touch_mode;
+    config.master_meter = gui.master_meter_settings();
+    config.sound_widgets = gui.sound_widget_settings();
+    config.stream = gui.stream_config();
 
     // Update the selected project directory slug if necessary.
     if let Some(selected_project_slug) = gui.selected_project_slug() {
         config.selected_project_slug = selected_project_slug;
     }
 
-    // Find the assets directory so we can save state before closing.
-    let assets = app.assets_path().expect("could not find assets directory");
-
-    // Save the top-level json config.
+    // Save the top-level json config, leaving any overridden values as they were in the file.
     let config_path = config_path(&assets);
+    let saved_config: Config = utils::load_from_json_or_default(&config_path);
+    overrides.restore(&mut config, &saved_config);
     if let Err(err) = utils::save_to_json(&config_path, &config) {
         eprintln!("failed to save \"assets/config.json\" during exit: {}", err);
     }
 
     // Save the selected gui project if there is one.
-    if let Some((project, _)) = gui.project {
-        if let Err(err) = project.save(&assets) {
-            eprintln!("failed to save selected project during exit: {}", err);
-        }
+    if let Err(err) = gui.save_project() {
+        eprintln!("failed to save selected project during exit: {}", err);
     }
 
     // Wait for the audio monitoring thread to close
@@ -283,6 +556,16 @@ fn exit(app: &App, model: Model) {
     // This should be instant as `GUI` has exited and the receiving channel should be dropped.
     audio_monitor.join().expect("failed to join audio_monitor thread when exiting");
 
+    // Stop watching the audio directory.
+    if let Some(audio_watcher) = audio_watcher {
+        audio_watcher.join().expect("failed to join audio_watcher thread when exiting");
+    }
+
+    // Stop polling game controllers.
+    if let Some(gamepad) = gamepad {
+        gamepad.join().expect("failed to join gamepad thread when exiting");
+    }
+
     // Send exit signal to the composer thread.
     let soundscape_thread = soundscape.exit().expect("failed to exit soundscape thread");
     soundscape_thread.join().expect("failed to join the soundscape thread when exiting");
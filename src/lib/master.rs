@@ -1,6 +1,9 @@
 use audio;
+use audio::{ambisonics, bass, binaural, blackout, distance, headroom, limiter, reverb};
 use time_calc::Ms;
 use metres::Metres;
+use soundscape;
+use time_zone::TimeZone;
 
 /// Master state of the project.
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -19,6 +22,45 @@ pub struct Master {
     /// It is stored as a square for faster calculations
     #[serde(default = "default_proximity_limit")]
     pub proximity_limit_2: Metres,
+    /// The global reverb bus, returned to all speakers.
+    #[serde(default)]
+    pub reverb: reverb::Params,
+    /// The brickwall limiter applied to the summed output.
+    #[serde(default)]
+    pub limiter: limiter::Params,
+    /// The routing of low frequencies to any speakers marked as subwoofers.
+    #[serde(default)]
+    pub bass: bass::Params,
+    /// Whether sounds are rendered via DBAP or the ambisonics bus.
+    #[serde(default)]
+    pub ambisonics: ambisonics::Params,
+    /// The binaural bus for monitoring the exhibition on headphones.
+    #[serde(default)]
+    pub binaural: binaural::Params,
+    /// The attenuation and air absorption applied to sources using the distance model.
+    #[serde(default)]
+    pub distance: distance::Params,
+    /// Reduces the gain of each sound with the number of sounds playing within its installations.
+    #[serde(default)]
+    pub headroom: headroom::Params,
+    /// The fades and schedule of the master blackout.
+    #[serde(default)]
+    pub blackout: blackout::Params,
+    /// The interval at which the soundscape is stepped forward.
+    #[serde(default = "default_soundscape_tick_rate")]
+    pub soundscape_tick_rate: Ms,
+    /// The soundscape's intensity macro, scaling all groups via their intensity response.
+    #[serde(default = "default_soundscape_intensity")]
+    pub soundscape_intensity: f32,
+    /// The time zone of the venue against which all daily schedules are evaluated.
+    #[serde(default)]
+    pub time_zone: TimeZone,
+    /// The scenes of the soundscape, crossfaded between when cued.
+    #[serde(default)]
+    pub soundscape_scenes: soundscape::scene::Scenes,
+    /// The tempo to whose beats or bars the soundscape's spawns are quantised.
+    #[serde(default)]
+    pub soundscape_tempo: soundscape::tempo::Params,
 }
 
 impl Default for Master {
@@ -27,8 +69,23 @@ impl Default for Master {
         let realtime_source_latency = default_realtime_source_latency();
         let dbap_rolloff_db = default_dbap_rolloff_db();
         let proximity_limit_2 = default_proximity_limit();
+        let reverb = Default::default();
+        let limiter = Default::default();
+        let bass = Default::default();
+        let ambisonics = Default::default();
+        let binaural = Default::default();
+        let distance = Default::default();
+        let headroom = Default::default();
+        let blackout = Default::default();
+        let soundscape_tick_rate = default_soundscape_tick_rate();
+        let soundscape_intensity = default_soundscape_intensity();
+        let time_zone = Default::default();
+        let soundscape_scenes = Default::default();
+        let soundscape_tempo = Default::default();
         Master { volume, realtime_source_latency, 
-            dbap_rolloff_db, proximity_limit_2 }
+            dbap_rolloff_db, proximity_limit_2, reverb, limiter, bass,
+            ambisonics, binaural, distance, headroom, blackout, soundscape_tick_rate,
+            soundscape_intensity, time_zone, soundscape_scenes, soundscape_tempo }
     }
 }
 
@@ -47,3 +104,11 @@ fn default_dbap_rolloff_db() -> f64 {
 fn default_proximity_limit() -> Metres {
     audio::DEFAULT_PROXIMITY_LIMIT_2
 }
+
+fn default_soundscape_tick_rate() -> Ms {
+    soundscape::DEFAULT_TICK_RATE
+}
+
+fn default_soundscape_intensity() -> f32 {
+    soundscape::DEFAULT_INTENSITY
+}
@@ -0,0 +1,141 @@
+//! On-disk capture of all OSC sent to a single installation computer.
+//!
+//! Each line records the UTC time at which a message was sent, the target socket, the OSC address
+//! and arguments, along with any error encountered while sending (or `DRY RUN` if the message
+//! was only logged), e.g.
+//!
+//! ```text
+//! 2018-05-21_14-03-22.118 127.0.0.1:9002 /beyond_perception/waves [0.25, 0.1] OK
+//! ```
+//!
+//! Captures are written to `assets/osc_captures/` within files named after the target and the
+//! time at which the file was opened. Once a file reaches `MAX_FILE_BYTES` a new one is started
+//! so that no single file grows unwieldy.
+
+use nannou_osc as osc;
+use project::history;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The directory within the assets directory to which captures are written.
+const CAPTURES_DIRECTORY_STEM: &'static str = "osc_captures";
+
+/// The extension of capture files.
+const EXTENSION: &'static str = "log";
+
+/// The size beyond which a new capture file is started.
+pub const MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// The capture of all messages sent to a single target.
+pub struct Capture {
+    directory: PathBuf,
+    name: String,
+    // The file currently being written and the number of bytes written to it.
+    file: Option<(BufWriter<File>, u64)>,
+}
+
+impl Capture {
+    /// Capture messages to files prefixed with the given name within the given directory.
+    ///
+    /// The first file is created upon the first message.
+    pub fn new(directory: PathBuf, name: String) -> Self {
+        Capture { directory, name, file: None }
+    }
+
+    /// Record a message sent to the given address along with any error encountered.
+    ///
+    /// `dry_run` indicates that the message was only logged and not actually sent.
+    pub fn write(
+        &mut self,
+        addr: &SocketAddr,
+        msg: &osc::Message,
+        error: Option<&osc::CommunicationError>,
+        dry_run: bool,
+    ) -> io::Result<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let (secs, millis) = (now.as_secs(), now.subsec_millis());
+        let line = format_line(secs, millis, addr, msg, error, dry_run);
+        let len = line.len() as u64;
+        let is_full = match self.file {
+            Some((_, bytes)) => bytes + len > MAX_FILE_BYTES,
+            None => true,
+        };
+        if is_full {
+            self.file = None;
+            fs::create_dir_all(&self.directory)?;
+            let stem = format!("{}_{}", self.name, history::format_timestamp(now.as_secs()));
+            let path = unique_path(&self.directory, &stem);
+            self.file = Some((BufWriter::new(File::create(path)?), 0));
+        }
+        let (ref mut file, ref mut bytes) = *self.file.as_mut().expect("no capture file");
+        file.write_all(line.as_bytes())?;
+        file.flush()?;
+        *bytes += len;
+        Ok(())
+    }
+}
+
+/// The directory to which all captures are written.
+pub fn captures_directory<P>(assets: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    assets.as_ref().join(CAPTURES_DIRECTORY_STEM)
+}
+
+// A path with the given stem that does not yet exist, in case of rotating within one second.
+fn unique_path(directory: &Path, stem: &str) -> PathBuf {
+    let path = directory.join(stem).with_extension(EXTENSION);
+    if !path.exists() {
+        return path;
+    }
+    (1..)
+        .map(|i| directory.join(format!("{}-{}", stem, i)).with_extension(EXTENSION))
+        .find(|path| !path.exists())
+        .expect("no unique capture path")
+}
+
+fn format_line(
+    secs: u64,
+    millis: u32,
+    addr: &SocketAddr,
+    msg: &osc::Message,
+    error: Option<&osc::CommunicationError>,
+    dry_run: bool,
+) -> String {
+    let args = msg
+        .args
+        .as_ref()
+        .map(|args| args.iter().map(format_arg).collect::<Vec<_>>().join(", "))
+        .unwrap_or_default();
+    let result = match (error, dry_run) {
+        (Some(err), _) => format!("ERROR {}", err),
+        (None, true) => "DRY RUN".to_string(),
+        (None, false) => "OK".to_string(),
+    };
+    let time = history::format_timestamp(secs);
+    format!("{}.{:03} {} {} [{}] {}\n", time, millis, addr, msg.addr, args, result)
+}
+
+fn format_arg(arg: &osc::Type) -> String {
+    match *arg {
+        osc::Type::Float(f) => format!("{}", f),
+        osc::Type::Int(i) => format!("{}", i),
+        osc::Type::String(ref s) => format!("{:?}", s),
+        ref arg => format!("{:?}", arg),
+    }
+}
+
+#[test]
+fn test_format_line() {
+    let addr = "127.0.0.1:9002".parse().unwrap();
+    let args = vec![osc::Type::Float(0.25), osc::Type::Int(3), osc::Type::String("a".into())];
+    let msg = osc::Message { addr: "/waves".into(), args: Some(args) };
+    let line = format_line(0, 7, &addr, &msg, None, false);
+    assert_eq!(line, "1970-01-01_00-00-00.007 127.0.0.1:9002 /waves [0.25, 3, \"a\"] OK\n");
+    let line = format_line(0, 7, &addr, &msg, None, true);
+    assert!(line.ends_with("] DRY RUN\n"));
+}
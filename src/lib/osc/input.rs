@@ -1,4 +1,5 @@
-use nannou_osc::{self as osc, Type::Float};
+use nannou_osc::{self as osc, Type::{Float, Int}};
+use osc::namespace::Address;
 use std;
 use std::net::SocketAddr;
 use std::sync::mpsc;
@@ -8,6 +9,15 @@ const SOURCE_VOLUME_ADDR: &'static str = "/source_volume";
 const MASTER_VOLUME_ADDR: &'static str = "/master_volume";
 const PLAY_SOUNDSCAPE: &'static str = "/play_soundscape";
 const PAUSE_SOUNDSCAPE: &'static str = "/pause_soundscape";
+const POSITION_ADDR: &'static str = "/position";
+const BLACKOUT_ADDR: &'static str = "/blackout";
+const RESTORE_ADDR: &'static str = "/restore";
+const VOLUME_ADDR: &'static str = "/volume";
+const MUTE_ADDR: &'static str = "/mute";
+const INTENSITY_ADDR: &'static str = "/intensity";
+const SENSOR_ADDR: &'static str = "/sensor";
+const ACTIVITY_ADDR: &'static str = "/activity";
+const SCENE_ADDR: &'static str = "/scene";
 
 /// A record of a received message.
 #[derive(Debug)]
@@ -25,12 +35,27 @@ pub enum Control {
     MasterVolume(MasterVolume),
     PauseSoundscape,
     PlaySoundscape,
+    InstallationSourceVolume(InstallationSourceVolume),
+    InstallationVolume(InstallationVolume),
+    InstallationSensor(InstallationSensor),
+    InstallationActivity(InstallationActivity),
+    InteractionPosition(InteractionPosition),
+    Blackout(Blackout),
+    Restore(Restore),
+    Intensity(Intensity),
+    Scene(Scene),
 }
 
 /// An OSC input message that was parsed as the master volume for the exhibition.
 #[derive(Clone, Debug)]
 pub struct MasterVolume(pub f32);
 
+/// An OSC input message that was parsed as the soundscape's intensity macro.
+///
+/// Expects the address "/bp/intensity" with a single `Float` argument clamped to `0.0..=1.0`.
+#[derive(Clone, Debug)]
+pub struct Intensity(pub f32);
+
 /// An OSC input message that was parsed as volume for a source.
 ///
 /// Expects the following OSC message:
@@ -48,6 +73,128 @@ pub struct SourceVolume {
     pub volume: f32,
 }
 
+/// An OSC input message that was parsed as volume for sources under an installation's prefix.
+///
+/// Expects the following OSC message:
+///
+/// - Address: "<prefix>/source_volume/<source_name>" or "<prefix>/source_volume"
+/// - Arguments: `Float` where `Float` is the volume.
+///
+/// The volume is only applied to sources assigned to the installations bound to the prefix. If
+/// no source name is given, the volume is applied to all of these sources.
+#[derive(Clone, Debug)]
+pub struct InstallationSourceVolume {
+    /// The address prefix under which the message was received, e.g. "/tracker_a".
+    pub prefix: String,
+    /// The name of the source to which this will be applied, if any.
+    pub name: Option<String>,
+    /// The value that will be assigned to the `audio::Source`'s `volume` field.
+    pub volume: f32,
+}
+
+/// An OSC input message that was parsed as the volume or mute of the installations bound to a
+/// prefix.
+///
+/// Expects one of the following OSC messages:
+///
+/// - Address: "<prefix>/volume", Arguments: `Float` where `Float` is the linear gain.
+/// - Address: "<prefix>/mute", Arguments: `Int` or `Float` where non-zero mutes and zero unmutes.
+///
+/// Changes are faded over each installation's volume fade duration.
+#[derive(Clone, Debug)]
+pub struct InstallationVolume {
+    /// The address prefix under which the message was received, e.g. "/tracker_a".
+    pub prefix: String,
+    /// The new gain of the installations, if any.
+    pub gain: Option<f32>,
+    /// Whether the installations should be muted, if given.
+    pub muted: Option<bool>,
+}
+
+/// An OSC input message that was parsed as a reading from the sensor of the installations bound
+/// to a prefix, e.g. an SPL meter.
+///
+/// Expects the address "<prefix>/sensor" with a single `Float` or `Int` reading.
+#[derive(Clone, Debug)]
+pub struct InstallationSensor {
+    /// The address prefix under which the message was received, e.g. "/atrium".
+    pub prefix: String,
+    /// The sensor reading, mapped to a gain via each installation's `SensorGain`.
+    pub reading: f32,
+}
+
+/// An OSC input message that was parsed as the visitor activity measured within the installations
+/// bound to a prefix, e.g. by a people counter or a motion sensor.
+///
+/// Expects the address "<prefix>/activity" with a single `Float` or `Int` reading.
+#[derive(Clone, Debug)]
+pub struct InstallationActivity {
+    /// The address prefix under which the message was received, e.g. "/atrium".
+    pub prefix: String,
+    /// The activity reading, mapped onto the soundscape via each installation's `Activity`.
+    pub reading: f32,
+}
+
+/// An OSC input message that was parsed as a position reported by an interaction input.
+///
+/// Expects the following OSC message:
+///
+/// - Address: "<prefix>/position"
+/// - Arguments: `Float`, `Float` where the floats are the `x` and `y` coordinates.
+///
+/// Tracking systems that follow many entities at once may precede the coordinates with an `Int`
+/// identifying the entity, i.e. `Int`, `Float`, `Float`.
+///
+/// The position is within the input's own coordinate frame and is transformed onto the floorplan
+/// by the interaction input bound to the prefix.
+#[derive(Clone, Debug)]
+pub struct InteractionPosition {
+    /// The address prefix under which the message was received, e.g. "/tracker_a".
+    pub prefix: String,
+    /// The identifier of the tracked entity, if one was given.
+    pub entity: Option<i32>,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// An OSC input message that was parsed as the beginning of a master blackout.
+///
+/// Expects the following OSC message:
+///
+/// - Address: "/bp/blackout"
+/// - Arguments: optionally up to three `Float`s overriding the project's fade out, hold and fade
+///   in durations in seconds, in that order.
+#[derive(Clone, Debug)]
+pub struct Blackout {
+    pub fade_out_secs: Option<f32>,
+    pub hold_secs: Option<f32>,
+    pub fade_in_secs: Option<f32>,
+}
+
+/// An OSC input message that was parsed as a request to restore from a blackout early.
+///
+/// Expects the following OSC message:
+///
+/// - Address: "/bp/restore"
+/// - Arguments: optionally a `Float` overriding the project's fade in duration in seconds.
+#[derive(Clone, Debug)]
+pub struct Restore {
+    pub fade_in_secs: Option<f32>,
+}
+
+/// An OSC input message that was parsed as a cue to crossfade to a scene.
+///
+/// Expects the following OSC message:
+///
+/// - Address: "/bp/scene/<scene_name>"
+/// - Arguments: optionally a `Float` overriding the project's crossfade duration in seconds.
+#[derive(Clone, Debug)]
+pub struct Scene {
+    /// The name of the scene to which the soundscape crossfades.
+    pub name: String,
+    pub crossfade_secs: Option<f32>,
+}
+
 impl From<MasterVolume> for Control {
     fn from(mv: MasterVolume) -> Self {
         Control::MasterVolume(mv)
@@ -60,6 +207,60 @@ impl From<SourceVolume> for Control {
     }
 }
 
+impl From<InstallationSourceVolume> for Control {
+    fn from(isv: InstallationSourceVolume) -> Self {
+        Control::InstallationSourceVolume(isv)
+    }
+}
+
+impl From<InstallationVolume> for Control {
+    fn from(iv: InstallationVolume) -> Self {
+        Control::InstallationVolume(iv)
+    }
+}
+
+impl From<InstallationSensor> for Control {
+    fn from(is: InstallationSensor) -> Self {
+        Control::InstallationSensor(is)
+    }
+}
+
+impl From<InstallationActivity> for Control {
+    fn from(ia: InstallationActivity) -> Self {
+        Control::InstallationActivity(ia)
+    }
+}
+
+impl From<InteractionPosition> for Control {
+    fn from(ip: InteractionPosition) -> Self {
+        Control::InteractionPosition(ip)
+    }
+}
+
+impl From<Blackout> for Control {
+    fn from(b: Blackout) -> Self {
+        Control::Blackout(b)
+    }
+}
+
+impl From<Restore> for Control {
+    fn from(r: Restore) -> Self {
+        Control::Restore(r)
+    }
+}
+
+impl From<Scene> for Control {
+    fn from(s: Scene) -> Self {
+        Control::Scene(s)
+    }
+}
+
+impl From<Intensity> for Control {
+    fn from(i: Intensity) -> Self {
+        Control::Intensity(i)
+    }
+}
+
 // Finds the "/bp" string and returns the remainder if any.
 fn parse_bp(s: &str) -> Option<&str> {
     if s.starts_with(BEYOND_PERCEPTION_ADDR) {
@@ -83,6 +284,45 @@ fn parse_source_volume(s: &str) -> Option<&str> {
     }
 }
 
+// Finds the "/scene/<scene_name>" string and returns the name of the scene.
+fn parse_scene(s: &str) -> Option<&str> {
+    if s.starts_with(SCENE_ADDR) && s[SCENE_ADDR.len()..].starts_with('/') {
+        let name = &s[SCENE_ADDR.len() + "/".len()..];
+        if !name.is_empty() {
+            return Some(name);
+        }
+    }
+    None
+}
+
+// Splits an address of the form "<prefix>/source_volume[/<source_name>]" into the prefix and the
+// optional source name.
+fn parse_prefixed_source_volume(s: &str) -> Option<(&str, Option<&str>)> {
+    let idx = s.find(SOURCE_VOLUME_ADDR)?;
+    let (prefix, rest) = s.split_at(idx);
+    if prefix.is_empty() {
+        return None;
+    }
+    if rest == SOURCE_VOLUME_ADDR {
+        return Some((prefix, None));
+    }
+    parse_source_volume(rest).map(|name| (prefix, Some(name)))
+}
+
+// Finds the prefix of an address of the form "<prefix>/position".
+fn parse_prefixed_position(s: &str) -> Option<&str> {
+    parse_prefixed(s, POSITION_ADDR)
+}
+
+// Finds the prefix of an address of the form "<prefix><suffix>".
+fn parse_prefixed<'a>(s: &'a str, suffix: &str) -> Option<&'a str> {
+    if s.ends_with(suffix) && s.len() > suffix.len() {
+        Some(&s[..s.len() - suffix.len()])
+    } else {
+        None
+    }
+}
+
 // Finds the "/master_volume" string. Returns `true` if found.
 fn parse_master_volume(s: &str) -> bool {
     s == MASTER_VOLUME_ADDR
@@ -98,8 +338,94 @@ fn parse_pause_soundscape(s: &str) -> bool {
     s == PAUSE_SOUNDSCAPE
 }
 
+// Finds the "/blackout" string. Returns `true` if found.
+fn parse_blackout(s: &str) -> bool {
+    s == BLACKOUT_ADDR
+}
+
+// Finds the "/restore" string. Returns `true` if found.
+fn parse_restore(s: &str) -> bool {
+    s == RESTORE_ADDR
+}
+
+// Finds the "/intensity" string. Returns `true` if found.
+fn parse_intensity(s: &str) -> bool {
+    s == INTENSITY_ADDR
+}
+
 impl Control {
     fn from_osc_msg(msg: &osc::Message) -> Option<Self> {
+        Self::from_bp_osc_msg(msg).or_else(|| Self::from_prefixed_osc_msg(msg))
+    }
+
+    // Parse a control for an installation or interaction input from a message with a non-"/bp"
+    // address prefix.
+    fn from_prefixed_osc_msg(msg: &osc::Message) -> Option<Self> {
+        if parse_bp(&msg.addr).is_some() {
+            return None;
+        }
+        let arg = |i: usize| msg.args.as_ref().and_then(|args| args.get(i));
+        match (parse_prefixed_source_volume(&msg.addr), arg(0)) {
+            (Some((prefix, name)), Some(&Float(volume))) => {
+                let prefix = prefix.into();
+                let name = name.map(Into::into);
+                let source_volume = InstallationSourceVolume { prefix, name, volume };
+                return Some(source_volume.into());
+            },
+            _ => (),
+        }
+        match (parse_prefixed(&msg.addr, VOLUME_ADDR), arg(0)) {
+            (Some(prefix), Some(&Float(gain))) => {
+                let prefix = prefix.into();
+                let gain = Some(gain.max(0.0));
+                let volume = InstallationVolume { prefix, gain, muted: None };
+                return Some(volume.into());
+            },
+            _ => (),
+        }
+        if let Some(prefix) = parse_prefixed(&msg.addr, MUTE_ADDR) {
+            let muted = match arg(0) {
+                Some(&Int(i)) => i != 0,
+                Some(&Float(f)) => f != 0.0,
+                _ => return None,
+            };
+            let prefix = prefix.into();
+            let volume = InstallationVolume { prefix, gain: None, muted: Some(muted) };
+            return Some(volume.into());
+        }
+        if let Some(prefix) = parse_prefixed(&msg.addr, SENSOR_ADDR) {
+            let reading = match arg(0) {
+                Some(&Int(i)) => i as f32,
+                Some(&Float(f)) => f,
+                _ => return None,
+            };
+            let prefix = prefix.into();
+            let sensor = InstallationSensor { prefix, reading };
+            return Some(sensor.into());
+        }
+        if let Some(prefix) = parse_prefixed(&msg.addr, ACTIVITY_ADDR) {
+            let reading = match arg(0) {
+                Some(&Int(i)) => i as f32,
+                Some(&Float(f)) => f,
+                _ => return None,
+            };
+            let prefix = prefix.into();
+            let activity = InstallationActivity { prefix, reading };
+            return Some(activity.into());
+        }
+        let prefix = parse_prefixed_position(&msg.addr)?;
+        let (entity, x, y) = match (arg(0), arg(1), arg(2)) {
+            (Some(&Int(entity)), Some(&Float(x)), Some(&Float(y))) => (Some(entity), x, y),
+            (Some(&Float(x)), Some(&Float(y)), _) => (None, x, y),
+            _ => return None,
+        };
+        let prefix = prefix.into();
+        let position = InteractionPosition { prefix, entity, x, y };
+        Some(position.into())
+    }
+
+    // Parse a global control from a message with the "/bp" address prefix.
+    fn from_bp_osc_msg(msg: &osc::Message) -> Option<Self> {
         parse_bp(&msg.addr)
             .and_then(|s| {
                 match (parse_master_volume(s), msg.args.as_ref().and_then(|args| args.get(0))) {
@@ -119,6 +445,14 @@ impl Control {
                     _ => (),
                 }
 
+                match (parse_intensity(s), msg.args.as_ref().and_then(|args| args.get(0))) {
+                    (true, Some(&Float(intensity))) => {
+                        let intensity = Intensity(intensity.min(1.0).max(0.0));
+                        return Some(intensity.into())
+                    }
+                    _ => (),
+                }
+
                 if parse_play_soundscape(s) {
                     return Some(Control::PlaySoundscape);
                 }
@@ -127,11 +461,164 @@ impl Control {
                     return Some(Control::PauseSoundscape);
                 }
 
+                let secs = |i: usize| match msg.args.as_ref().and_then(|args| args.get(i)) {
+                    Some(&Float(secs)) => Some(secs.max(0.0)),
+                    _ => None,
+                };
+
+                if parse_blackout(s) {
+                    let blackout = Blackout {
+                        fade_out_secs: secs(0),
+                        hold_secs: secs(1),
+                        fade_in_secs: secs(2),
+                    };
+                    return Some(blackout.into());
+                }
+
+                if parse_restore(s) {
+                    let restore = Restore { fade_in_secs: secs(0) };
+                    return Some(restore.into());
+                }
+
+                if let Some(name) = parse_scene(s) {
+                    let scene = Scene { name: name.into(), crossfade_secs: secs(0) };
+                    return Some(scene.into());
+                }
+
                 None
             })
     }
 }
 
+/// Documentation of the global addresses, each beginning with "/bp", to which the server responds.
+pub fn namespace() -> Vec<Address> {
+    let bp = |addr: &str| format!("{}{}", BEYOND_PERCEPTION_ADDR, addr);
+    let source_volume = bp(SOURCE_VOLUME_ADDR);
+    vec![
+        Address::new(
+            bp(MASTER_VOLUME_ADDR),
+            "Float",
+            "Sets the master volume, clamped to 0.0..=1.0.",
+            bp(MASTER_VOLUME_ADDR),
+            vec![Float(0.8)],
+        ),
+        Address::new(
+            format!("{}/<source_name>", source_volume),
+            "Float",
+            "Sets the volume of the first source with the given name.",
+            format!("{}/rain", source_volume),
+            vec![Float(0.5)],
+        ),
+        Address::new(
+            bp(INTENSITY_ADDR),
+            "Float",
+            "Sets the soundscape's intensity macro, clamped to 0.0..=1.0.",
+            bp(INTENSITY_ADDR),
+            vec![Float(0.25)],
+        ),
+        Address::new(
+            bp(PLAY_SOUNDSCAPE),
+            "",
+            "Plays the soundscape.",
+            bp(PLAY_SOUNDSCAPE),
+            vec![],
+        ),
+        Address::new(
+            bp(PAUSE_SOUNDSCAPE),
+            "",
+            "Pauses the soundscape.",
+            bp(PAUSE_SOUNDSCAPE),
+            vec![],
+        ),
+        Address::new(
+            bp(BLACKOUT_ADDR),
+            "[Float fade_out_secs] [Float hold_secs] [Float fade_in_secs]",
+            "Begins a master blackout, overriding the project's durations with any given.",
+            bp(BLACKOUT_ADDR),
+            vec![Float(2.0), Float(30.0), Float(5.0)],
+        ),
+        Address::new(
+            bp(RESTORE_ADDR),
+            "[Float fade_in_secs]",
+            "Restores from the blackout underway, overriding the project's fade in if given.",
+            bp(RESTORE_ADDR),
+            vec![Float(1.0)],
+        ),
+        Address::new(
+            format!("{}/<scene_name>", bp(SCENE_ADDR)),
+            "[Float crossfade_secs]",
+            "Crossfades to the named scene, overriding the project's crossfade if given.",
+            format!("{}/night", bp(SCENE_ADDR)),
+            vec![Float(120.0)],
+        ),
+    ]
+}
+
+/// Documentation of the addresses to which the server responds under the given installation
+/// prefix.
+pub fn installation_namespace(prefix: &str) -> Vec<Address> {
+    let addr = |suffix: &str| format!("{}{}", prefix, suffix);
+    vec![
+        Address::new(
+            addr(SOURCE_VOLUME_ADDR),
+            "Float",
+            "Sets the volume of all sources assigned to the installation.",
+            addr(SOURCE_VOLUME_ADDR),
+            vec![Float(0.5)],
+        ),
+        Address::new(
+            format!("{}/<source_name>", addr(SOURCE_VOLUME_ADDR)),
+            "Float",
+            "Sets the volume of the named source if it is assigned to the installation.",
+            format!("{}/rain", addr(SOURCE_VOLUME_ADDR)),
+            vec![Float(0.5)],
+        ),
+        Address::new(
+            addr(VOLUME_ADDR),
+            "Float",
+            "Fades the installation's output to the given linear gain.",
+            addr(VOLUME_ADDR),
+            vec![Float(0.7)],
+        ),
+        Address::new(
+            addr(MUTE_ADDR),
+            "Int | Float",
+            "Mutes the installation if non-zero or unmutes it if zero.",
+            addr(MUTE_ADDR),
+            vec![Int(1)],
+        ),
+        Address::new(
+            addr(SENSOR_ADDR),
+            "Float | Int",
+            "A reading from the installation's sensor, mapped to a gain by its sensor settings.",
+            addr(SENSOR_ADDR),
+            vec![Float(62.5)],
+        ),
+        Address::new(
+            addr(ACTIVITY_ADDR),
+            "Float | Int",
+            "The visitor activity within the installation, mapped by its activity settings.",
+            addr(ACTIVITY_ADDR),
+            vec![Int(12)],
+        ),
+    ]
+}
+
+/// Documentation of the addresses to which the server responds under the given interaction
+/// input prefix.
+pub fn interaction_namespace(prefix: &str) -> Vec<Address> {
+    let addr = format!("{}{}", prefix, POSITION_ADDR);
+    vec![
+        Address::new(
+            addr.clone(),
+            "[Int entity] Float x, Float y",
+            "The position of a tracked entity within the input's own coordinate frame.",
+            addr,
+            vec![Int(3), Float(1.5), Float(-0.25)],
+        ),
+    ]
+}
+
 /// Spawn the OSC receiver thread.
 pub fn spawn(
     osc_rx: osc::Receiver,
@@ -181,3 +668,15 @@ fn run(
         }
     }
 }
+
+#[test]
+fn test_namespace_examples() {
+    let addresses = namespace()
+        .into_iter()
+        .chain(installation_namespace("/atrium"))
+        .chain(interaction_namespace("/tracker_a"));
+    for address in addresses {
+        let control = Control::from_osc_msg(&address.example);
+        assert!(control.is_some(), "unhandled example for {}", address.addr);
+    }
+}
@@ -1,2 +1,4 @@
+pub mod capture;
 pub mod input;
+pub mod namespace;
 pub mod output;
@@ -0,0 +1,59 @@
+//! Documentation of the OSC namespace, i.e. every address to which the server responds and every
+//! address that it emits.
+//!
+//! Each entry is generated by the module handling its address from the same constants and
+//! argument layouts used when parsing or sending, so that integrators may rely upon it in place of
+//! a hand-written spec.
+
+use nannou_osc as osc;
+use std::fmt::Write;
+
+/// The documentation of a single OSC address.
+#[derive(Clone, Debug)]
+pub struct Address {
+    /// The address, where any part enclosed in `<...>` is provided by the sender.
+    pub addr: String,
+    /// The expected argument types in order, where `[...]` marks optional arguments.
+    pub args: &'static str,
+    /// What the message does or contains.
+    pub description: &'static str,
+    /// An example of the message.
+    pub example: osc::Message,
+}
+
+impl Address {
+    /// Document the given address along with an example message.
+    pub fn new(
+        addr: String,
+        args: &'static str,
+        description: &'static str,
+        example_addr: String,
+        example_args: Vec<osc::Type>,
+    ) -> Self {
+        let example = osc::Message { addr: example_addr, args: Some(example_args) };
+        Address { addr, args, description, example }
+    }
+
+    /// Format the documentation as an indented block of text.
+    pub fn format(&self) -> String {
+        let mut s = String::new();
+        writeln!(s, "{}  {}", self.addr, self.args).ok();
+        writeln!(s, "    {}", self.description).ok();
+        writeln!(s, "    e.g. {}", format_message(&self.example)).ok();
+        s
+    }
+}
+
+/// Format the given message as its address followed by its arguments.
+pub fn format_message(msg: &osc::Message) -> String {
+    let mut s = msg.addr.clone();
+    for arg in msg.args.iter().flat_map(|args| args.iter()) {
+        match *arg {
+            osc::Type::Float(f) => write!(s, " {:.2}", f),
+            osc::Type::Int(i) => write!(s, " {}", i),
+            osc::Type::String(ref string) => write!(s, " \"{}\"", string),
+            ref arg => write!(s, " {:?}", arg),
+        }.ok();
+    }
+    s
+}
@@ -3,8 +3,12 @@ use fxhash::FxHashMap;
 use installation;
 use nannou_osc as osc;
 use nannou_osc::Type::{Float, Int};
+use nannou_osc::Type::String as Str;
+use osc::capture::Capture;
+use osc::namespace::Address;
 use std;
 use std::iter::once;
+use std::path::PathBuf;
 use std::sync::{mpsc, Arc};
 
 pub type MessageQueue = Arc<MsQueue<Message>>;
@@ -14,11 +18,47 @@ type Rx = MessageQueue;
 /// The OSC sender type used by the osc output thread.
 pub type Sender = osc::Sender<osc::Connected>;
 
+/// The address at which the server announces its version and configuration to every installation
+/// computer upon startup and whenever a project is loaded.
+pub const ANNOUNCE_ADDR: &'static str = "/bp/announce";
+
+/// The version of the audio server included within every announcement.
+pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
 /// Messages that can be received by the `osc::output` thread.
 pub enum Message {
     Audio(installation::Id, AudioFrameData),
     Osc(OscTarget),
+    /// Send a single message to the given installation computer.
+    ///
+    /// Used by the GUI's OSC composer for sending arbitrary test messages.
+    Send(installation::Id, installation::computer::Id, osc::Message),
     ClearProjectSpecificData,
+    /// The audio devices opened at startup, included within all following announcements.
+    AudioDevices(AudioDevices),
+    /// Announce the loaded project to every installation computer and log it.
+    ///
+    /// Should follow the targets of the project so that every computer receives it.
+    Announce(Announcement),
+}
+
+/// The audio devices in use by the server.
+#[derive(Clone, Debug, Default)]
+pub struct AudioDevices {
+    pub input: String,
+    pub output: String,
+    /// The total number of output channels, including those of any aggregated devices.
+    pub output_channels: usize,
+    pub sample_rate: u32,
+}
+
+/// A summary of the loaded project so that show-control systems may verify the configuration.
+#[derive(Clone, Debug, Default)]
+pub struct Announcement {
+    pub project: String,
+    pub installations: usize,
+    pub speakers: usize,
+    pub sources: usize,
 }
 
 /// Add or remove an OSC target for a given installation.
@@ -32,6 +72,12 @@ pub enum OscTarget {
     Remove(installation::Id, installation::computer::Id),
     RemoveInstallation(installation::Id),
     UpdateAddr(installation::Id, installation::computer::Id, String),
+    /// Begin capturing all messages sent to the computer to files prefixed with the given name,
+    /// or stop capturing if `None`.
+    Capture(installation::Id, installation::computer::Id, Option<String>),
+    /// Whether messages to the computer should be formatted, logged and captured as usual
+    /// without actually being sent.
+    DryRun(installation::Id, installation::computer::Id, bool),
 }
 
 /// Specifies where the target OSC sender should come from.
@@ -75,10 +121,16 @@ pub struct Log {
     pub addr: std::net::SocketAddr,
     pub msg: osc::Message,
     pub error: Option<osc::CommunicationError>,
+    /// Whether the message was only logged and not actually sent.
+    pub dry_run: bool,
 }
 
 /// Spawn the osc sender thread.
-pub fn spawn() -> (std::thread::JoinHandle<()>, Tx, mpsc::Receiver<Log>) {
+///
+/// Captures of sent messages are written to the given directory.
+pub fn spawn(
+    captures_directory: PathBuf,
+) -> (std::thread::JoinHandle<()>, Tx, mpsc::Receiver<Log>) {
     let msg_queue = Arc::new(MsQueue::new());
     let msg_tx = msg_queue.clone();
     let msg_rx = msg_queue;
@@ -86,16 +138,38 @@ pub fn spawn() -> (std::thread::JoinHandle<()>, Tx, mpsc::Receiver<Log>) {
     let handle = std::thread::Builder::new()
         .name("osc_out".into())
         .spawn(move || {
-            run(msg_rx, log_tx);
+            run(msg_rx, log_tx, captures_directory);
         })
         .unwrap();
     (handle, msg_tx, log_rx)
 }
 
-fn run(msg_rx: Rx, log_tx: mpsc::Sender<Log>) {
+fn run(msg_rx: Rx, log_tx: mpsc::Sender<Log>, captures_directory: PathBuf) {
     struct Target {
         osc_tx: Arc<Sender>,
         osc_addr: String,
+        // Present while capturing all messages sent to the target.
+        capture: Option<Capture>,
+        // Whether messages are logged without being sent.
+        dry_run: bool,
+    }
+
+    // Send the message to the target unless it is a dry run.
+    fn send(target: &Target, msg: osc::Message) -> Option<osc::CommunicationError> {
+        match target.dry_run {
+            true => None,
+            false => target.osc_tx.send(msg).err(),
+        }
+    }
+
+    // Record the sent message if the target is being captured.
+    fn capture(target: &mut Target, log: &Log) {
+        if let Some(ref mut capture) = target.capture {
+            let error = log.error.as_ref();
+            if let Err(err) = capture.write(&log.addr, &log.msg, error, log.dry_run) {
+                eprintln!("failed to capture sent OSC message: {}", err);
+            }
+        }
     }
 
     enum Update {
@@ -138,9 +212,32 @@ fn run(msg_rx: Rx, log_tx: mpsc::Sender<Log>) {
     // A map containing the latest data received in terms of messages.
     let mut last_received = FxHashMap::default();
     let mut last_sent = FxHashMap::default();
+    let mut audio_devices = AudioDevices::default();
     for update in update_rx {
         match update {
             Update::Msg(msg) => match msg {
+                Message::AudioDevices(devices) => {
+                    audio_devices = devices;
+                },
+                // Log the announcement and send it to every computer of every installation.
+                Message::Announce(announcement) => {
+                    println!("{}", banner(&announcement, &audio_devices));
+                    let msg = osc::Message {
+                        addr: ANNOUNCE_ADDR.into(),
+                        args: Some(announce_args(&announcement, &audio_devices)),
+                    };
+                    for (&installation, targets) in osc_txs.iter_mut() {
+                        for (&computer, target) in targets.iter_mut() {
+                            let error = send(target, msg.clone());
+                            let addr = target.osc_tx.remote_addr();
+                            let dry_run = target.dry_run;
+                            let msg = msg.clone();
+                            let log = Log { installation, computer, addr, msg, error, dry_run };
+                            capture(target, &log);
+                            log_tx.send(log).ok();
+                        }
+                    }
+                },
                 // Clear all project specific data.
                 Message::ClearProjectSpecificData => {
                     last_received.clear();
@@ -151,6 +248,29 @@ fn run(msg_rx: Rx, log_tx: mpsc::Sender<Log>) {
                 Message::Audio(installation, data) => {
                     last_received.insert(installation, data);
                 }
+                // Send a composed message to the given installation computer.
+                Message::Send(installation, computer, msg) => {
+                    let target = match osc_txs
+                        .get_mut(&installation)
+                        .and_then(|t| t.get_mut(&computer))
+                    {
+                        Some(target) => target,
+                        None => continue,
+                    };
+                    let error = send(target, msg.clone());
+                    let addr = target.osc_tx.remote_addr();
+                    let dry_run = target.dry_run;
+                    let log = Log {
+                        installation,
+                        computer,
+                        addr,
+                        msg,
+                        error,
+                        dry_run,
+                    };
+                    capture(target, &log);
+                    log_tx.send(log).ok();
+                }
                 // Some OSC target should be added or removed.
                 Message::Osc(osc) => match osc {
                     OscTarget::Add(installation_id, computer, target, osc_addr) => {
@@ -168,10 +288,16 @@ fn run(msg_rx: Rx, log_tx: mpsc::Sender<Log>) {
                                 }
                             },
                         };
-                        osc_txs
+                        // Continue any capture or dry run in progress for the computer.
+                        let targets = osc_txs
                             .entry(installation_id)
-                            .or_insert_with(FxHashMap::default)
-                            .insert(computer, Target { osc_tx, osc_addr });
+                            .or_insert_with(FxHashMap::default);
+                        let (capture, dry_run) = match targets.remove(&computer) {
+                            Some(t) => (t.capture, t.dry_run),
+                            None => (None, false),
+                        };
+                        let target = Target { osc_tx, osc_addr, capture, dry_run };
+                        targets.insert(computer, target);
                     }
                     OscTarget::Remove(installation, computer) => {
                         if let Some(txs) = osc_txs.get_mut(&installation) {
@@ -188,51 +314,37 @@ fn run(msg_rx: Rx, log_tx: mpsc::Sender<Log>) {
                             }
                         }
                     }
+                    OscTarget::Capture(installation, computer, name) => {
+                        if let Some(txs) = osc_txs.get_mut(&installation) {
+                            if let Some(comp) = txs.get_mut(&computer) {
+                                let directory = captures_directory.clone();
+                                comp.capture = name.map(|name| Capture::new(directory, name));
+                            }
+                        }
+                    }
+                    OscTarget::DryRun(installation, computer, dry_run) => {
+                        if let Some(txs) = osc_txs.get_mut(&installation) {
+                            if let Some(comp) = txs.get_mut(&computer) {
+                                comp.dry_run = dry_run;
+                            }
+                        }
+                        // Ensure the latest data is actually sent once the dry run ends.
+                        last_sent.remove(&(installation, computer));
+                    }
                 },
             },
 
             Update::SendOsc => for (installation, data) in last_received.drain() {
-                let AudioFrameData {
-                    avg_peak,
-                    avg_rms,
-                    avg_fft,
-                    speakers,
-                } = data;
-
-                let targets = match osc_txs.get(&installation) {
+                let targets = match osc_txs.get_mut(&installation) {
                     Some(targets) => targets,
                     None => continue,
                 };
 
-                // The buffer used to collect arguments.
-                let mut args = Vec::new();
-
-                // Push the analysis of the averaged channels.
-                args.push(Float(avg_peak));
-                args.push(Float(avg_rms));
-                let lmh = avg_fft.lmh.iter().map(|&f| Float(f));
-                args.extend(lmh);
-                let bins = avg_fft.bins.iter().map(|&f| Float(f));
-                args.extend(bins);
-
-                // Push the Peak and RMS per speaker.
-                let speakers = speakers.into_iter().enumerate().flat_map(|(i, s)| {
-                    once(Int(i as _))
-                        .chain(once(Float(s.peak)))
-                        .chain(once(Float(s.rms)))
-                });
-                args.extend(speakers);
+                let args = audio_frame_args(data);
 
                 // Retrieve the OSC sender for each computer in the installation.
-                for target in targets.iter() {
-                    let (
-                        &computer,
-                        &Target {
-                            ref osc_tx,
-                            ref osc_addr,
-                        },
-                    ) = target;
-                    let addr = &osc_addr[..];
+                for (&computer, target) in targets.iter_mut() {
+                    let addr = &target.osc_addr[..];
 
                     // Send the message!
                     let msg = osc::Message {
@@ -247,7 +359,7 @@ fn run(msg_rx: Rx, log_tx: mpsc::Sender<Log>) {
                     }
 
                     // Send the OSC.
-                    let error = osc_tx.send(msg.clone()).err();
+                    let error = send(target, msg.clone());
 
                     // Update the `last_sent` map if there were no errors.
                     if error.is_none() {
@@ -255,17 +367,130 @@ fn run(msg_rx: Rx, log_tx: mpsc::Sender<Log>) {
                     }
 
                     // Log the message for displaying in the GUI.
-                    let addr = osc_tx.remote_addr();
+                    let addr = target.osc_tx.remote_addr();
+                    let dry_run = target.dry_run;
                     let log = Log {
                         installation,
                         computer,
                         addr,
                         msg,
                         error,
+                        dry_run,
                     };
+                    capture(target, &log);
                     log_tx.send(log).ok();
                 }
             },
         }
     }
 }
+
+// The arguments of the message sent to each computer with every frame of audio analysis.
+fn audio_frame_args(data: AudioFrameData) -> Vec<osc::Type> {
+    let AudioFrameData {
+        avg_peak,
+        avg_rms,
+        avg_fft,
+        speakers,
+    } = data;
+
+    // The buffer used to collect arguments.
+    let mut args = Vec::new();
+
+    // Push the analysis of the averaged channels.
+    args.push(Float(avg_peak));
+    args.push(Float(avg_rms));
+    let lmh = avg_fft.lmh.iter().map(|&f| Float(f));
+    args.extend(lmh);
+    let bins = avg_fft.bins.iter().map(|&f| Float(f));
+    args.extend(bins);
+
+    // Push the Peak and RMS per speaker.
+    let speakers = speakers.into_iter().enumerate().flat_map(|(i, s)| {
+        once(Int(i as _))
+            .chain(once(Float(s.peak)))
+            .chain(once(Float(s.rms)))
+    });
+    args.extend(speakers);
+    args
+}
+
+// A human-readable summary of the announcement for the log.
+fn banner(announcement: &Announcement, devices: &AudioDevices) -> String {
+    format!(
+        "Audio Server v{} - Project \"{}\": {} installations, {} speakers, {} sources - \
+         Input \"{}\", Output \"{}\" ({} channels at {} Hz)",
+        VERSION,
+        announcement.project,
+        announcement.installations,
+        announcement.speakers,
+        announcement.sources,
+        devices.input,
+        devices.output,
+        devices.output_channels,
+        devices.sample_rate,
+    )
+}
+
+// The arguments of the announcement sent to each computer upon startup and project change.
+fn announce_args(announcement: &Announcement, devices: &AudioDevices) -> Vec<osc::Type> {
+    vec![
+        Str(VERSION.into()),
+        Str(announcement.project.clone()),
+        Int(announcement.installations as _),
+        Int(announcement.speakers as _),
+        Int(announcement.sources as _),
+        Str(devices.input.clone()),
+        Str(devices.output.clone()),
+        Int(devices.output_channels as _),
+        Int(devices.sample_rate as _),
+    ]
+}
+
+/// Documentation of the announcement sent to every installation computer.
+pub fn announce_namespace() -> Address {
+    let announcement = Announcement {
+        project: "Beyond Perception".into(),
+        installations: 9,
+        speakers: 48,
+        sources: 120,
+    };
+    let devices = AudioDevices {
+        input: "Built-in Input".into(),
+        output: "MADIface USB".into(),
+        output_channels: 64,
+        sample_rate: 48_000,
+    };
+    Address::new(
+        ANNOUNCE_ADDR.into(),
+        "String version, String project, Int installations, Int speakers, Int sources, \
+         String input device, String output device, Int output channels, Int sample rate",
+        "The server's version and configuration, sent upon startup and whenever a project is \
+         loaded so that show-control systems may verify the right configuration is running.",
+        ANNOUNCE_ADDR.into(),
+        announce_args(&announcement, &devices),
+    )
+}
+
+/// Documentation of the message sent to an installation computer at the given OSC address with
+/// every frame of audio analysis.
+pub fn namespace(osc_addr: &str) -> Address {
+    let speaker = |peak, rms| Speaker { peak, rms };
+    let example = AudioFrameData {
+        avg_peak: 0.5,
+        avg_rms: 0.25,
+        avg_fft: FftData {
+            lmh: [0.5, 0.25, 0.125],
+            bins: [0.5, 0.4, 0.3, 0.25, 0.2, 0.15, 0.1, 0.05],
+        },
+        speakers: vec![speaker(0.5, 0.25), speaker(0.4, 0.2)],
+    };
+    Address::new(
+        osc_addr.into(),
+        "Float peak, Float rms, Float x3 low/mid/high, Float x8 bins, \
+         then Int index, Float peak, Float rms per speaker",
+        "The installation's audio analysis, sent at up to 60 Hz whenever it changes.",
+        osc_addr.into(),
+        audio_frame_args(example),
+    )
+}
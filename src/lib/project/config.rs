@@ -1,4 +1,5 @@
 use metres::Metres;
+use super::history;
 use utils::Seed;
 
 /// Various configuration parameters for a single project.
@@ -29,6 +30,9 @@ pub struct Config {
     /// This value is squared for speed
     #[serde(default = "default::proximity_limit")]
     pub proximity_limit_2: Metres,
+    /// The policy describing which snapshots are kept within the project's history.
+    #[serde(default)]
+    pub history: history::Retention,
 }
 
 impl Default for Config {
@@ -44,6 +48,7 @@ impl Default for Config {
         let max_speaker_radius_metres = default::max_speaker_radius_metres();
         let seed = default::seed();
         let proximity_limit_2 = default::proximity_limit();
+        let history = Default::default();
         Config {
             window_width,
             window_height,
@@ -56,6 +61,7 @@ impl Default for Config {
             max_speaker_radius_metres,
             seed,
             proximity_limit_2,
+            history,
         }
     }
 }
@@ -0,0 +1,158 @@
+//! The references from each source to the installations, soundscape groups and speakers of a
+//! project.
+//!
+//! Removing an installation, group or speaker leaves its ID behind within any sources that refer
+//! to it. These dangling references silently change how the source plays, e.g. a source that is
+//! only heard from a removed speaker is never heard at all. They are collected here so that they
+//! may be reviewed and removed.
+
+use audio;
+use installation;
+use soundscape;
+use std::fmt;
+use super::State;
+
+/// A reference from a source to another part of the project.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Dependency {
+    /// The source plays within the installation.
+    Installation(installation::Id),
+    /// The source belongs to the soundscape group.
+    Group(soundscape::group::Id),
+    /// The source is only heard from the speaker.
+    IncludedSpeaker(audio::speaker::Id),
+    /// The source is never heard from the speaker.
+    ExcludedSpeaker(audio::speaker::Id),
+}
+
+/// A dependency along with the name of the part of the project that it refers to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub dependency: Dependency,
+    /// The name of the installation, group or speaker, or `None` if it no longer exists.
+    pub name: Option<String>,
+}
+
+impl Dependency {
+    /// The name of the referenced installation, group or speaker if it exists.
+    pub fn name(&self, state: &State) -> Option<String> {
+        match *self {
+            Dependency::Installation(ref id) => state.installations.get(id).map(|i| i.name.clone()),
+            Dependency::Group(ref id) => state.soundscape_groups.get(id).map(|g| g.name.clone()),
+            Dependency::IncludedSpeaker(ref id) | Dependency::ExcludedSpeaker(ref id) => {
+                state.speakers.get(id).map(|s| s.name.clone())
+            },
+        }
+    }
+
+    /// Remove the reference from the given soundscape constraints, if it is held there.
+    pub fn remove_from_constraints(&self, constraints: &mut audio::source::Soundscape) {
+        match *self {
+            Dependency::Installation(ref id) => {
+                constraints.installations.remove(id);
+            },
+            Dependency::Group(ref id) => {
+                constraints.groups.remove(id);
+            },
+            _ => (),
+        }
+    }
+
+    /// Remove the reference from the given speaker filter, if it is held there.
+    pub fn remove_from_filter(&self, filter: &mut audio::speaker::Filter) {
+        match *self {
+            Dependency::IncludedSpeaker(ref id) => {
+                filter.include.remove(id);
+            },
+            Dependency::ExcludedSpeaker(ref id) => {
+                filter.exclude.remove(id);
+            },
+            _ => (),
+        }
+    }
+
+    /// Remove the reference from the given source.
+    pub fn remove_from(&self, source: &mut audio::Source) {
+        if let Some(audio::source::Role::Soundscape(ref mut constraints)) = source.role {
+            self.remove_from_constraints(constraints);
+        }
+        self.remove_from_filter(&mut source.speaker_filter);
+    }
+}
+
+impl Entry {
+    /// Whether the referenced part of the project no longer exists.
+    pub fn is_dangling(&self) -> bool {
+        self.name.is_none()
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Dependency::Installation(id) => write!(f, "Installation {}", id.0),
+            Dependency::Group(id) => write!(f, "Group {}", id.0),
+            Dependency::IncludedSpeaker(id) => write!(f, "Only From Speaker {}", id.0),
+            Dependency::ExcludedSpeaker(id) => write!(f, "Never From Speaker {}", id.0),
+        }
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.dependency {
+            Dependency::Installation(_) => "Installation",
+            Dependency::Group(_) => "Group",
+            Dependency::IncludedSpeaker(_) => "Only From Speaker",
+            Dependency::ExcludedSpeaker(_) => "Never From Speaker",
+        };
+        match self.name {
+            Some(ref name) => write!(f, "{} \"{}\"", kind, name),
+            None => write!(f, "{} (REMOVED)", self.dependency),
+        }
+    }
+}
+
+/// All references from the source with the given ID, ordered by kind and then by ID.
+pub fn of_source(state: &State, id: &audio::source::Id) -> Vec<Entry> {
+    let source = match state.sources.get(id) {
+        None => return vec![],
+        Some(source) => source,
+    };
+    let mut dependencies = vec![];
+    if let Some(audio::source::Role::Soundscape(ref constraints)) = source.audio.role {
+        let mut installations: Vec<_> = constraints.installations.iter().cloned().collect();
+        installations.sort_by_key(|id| id.0);
+        dependencies.extend(installations.into_iter().map(Dependency::Installation));
+        let mut groups: Vec<_> = constraints.groups.iter().cloned().collect();
+        groups.sort_by_key(|id| id.0);
+        dependencies.extend(groups.into_iter().map(Dependency::Group));
+    }
+    let mut include: Vec<_> = source.audio.speaker_filter.include.iter().cloned().collect();
+    include.sort_by_key(|id| id.0);
+    dependencies.extend(include.into_iter().map(Dependency::IncludedSpeaker));
+    let mut exclude: Vec<_> = source.audio.speaker_filter.exclude.iter().cloned().collect();
+    exclude.sort_by_key(|id| id.0);
+    dependencies.extend(exclude.into_iter().map(Dependency::ExcludedSpeaker));
+    dependencies
+        .into_iter()
+        .map(|dependency| {
+            let name = dependency.name(state);
+            Entry { dependency, name }
+        })
+        .collect()
+}
+
+/// Every reference to an installation, group or speaker that no longer exists, by source.
+pub fn dangling(state: &State) -> Vec<(audio::source::Id, Dependency)> {
+    let mut ids: Vec<_> = state.sources.keys().cloned().collect();
+    ids.sort_by_key(|id| id.0);
+    ids.into_iter()
+        .flat_map(|id| {
+            of_source(state, &id)
+                .into_iter()
+                .filter(Entry::is_dangling)
+                .map(move |entry| (id, entry.dependency))
+        })
+        .collect()
+}
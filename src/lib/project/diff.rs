@@ -0,0 +1,185 @@
+//! Comparison of a project's in-memory state against its last saved files.
+//!
+//! The in-memory and saved project are compared via their JSON representation. This way every
+//! serialized parameter is covered without requiring a comparison for each type, and the reported
+//! paths match the structure of the project's "config.json" and "state.json" files.
+
+use serde;
+use serde_json::{self, Map, Value};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use utils;
+use super::{project_config_path, project_directory_path, project_state_path, Project};
+
+/// The maximum number of characters used to describe a single value within a `Change`.
+const MAX_VALUE_CHARS: usize = 40;
+
+/// A single difference between the last saved project and its in-memory state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    /// A human-readable path to the parameter, e.g. `speakers["Speaker 3"].audio.point.x`.
+    ///
+    /// Map entries with a `name` field are described by their name rather than their ID.
+    pub path: String,
+    pub kind: Kind,
+}
+
+/// The kind of difference between the saved and in-memory parameter.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kind {
+    /// The parameter does not exist within the saved project.
+    Added(Value),
+    /// The parameter exists within the saved project but no longer exists in memory.
+    Removed(Value),
+    /// The parameter has been changed since the project was saved.
+    Modified { saved: Value, current: Value },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            Kind::Added(ref value) => write!(f, "+ {}: {}", self.path, describe(value)),
+            Kind::Removed(ref value) => write!(f, "- {}: {}", self.path, describe(value)),
+            Kind::Modified { ref saved, ref current } => {
+                write!(f, "~ {}: {} -> {}", self.path, describe(saved), describe(current))
+            },
+        }
+    }
+}
+
+/// Collect all changes made to the given project since it was last saved to the assets directory.
+///
+/// If the project has never been saved, all of its parameters are reported as added.
+pub fn changes_since_save<P>(project: &Project, assets: P) -> io::Result<Vec<Change>>
+where
+    P: AsRef<Path>,
+{
+    let directory = project_directory_path(&assets, &project.name);
+    let saved_config = load_saved_value(&project_config_path(&directory))?;
+    let saved_state = load_saved_value(&project_state_path(&directory))?;
+    let current_config = to_saved_value(&project.config)?;
+    let current_state = to_saved_value(&project.state)?;
+    let mut changes = vec![];
+    diff("config", &saved_config, &current_config, &mut changes);
+    diff("", &saved_state, &current_state, &mut changes);
+    Ok(changes)
+}
+
+/// Append the differences between the `saved` and `current` values at the given path.
+///
+/// Arrays whose elements only differ in order are considered unchanged, as sets are serialized in
+/// an arbitrary order.
+pub fn diff(path: &str, saved: &Value, current: &Value, changes: &mut Vec<Change>) {
+    match (saved, current) {
+        (&Value::Object(ref saved), &Value::Object(ref current)) => {
+            for (key, saved_value) in saved {
+                let current_value = current.get(key);
+                let path = child_path(path, key, current_value.unwrap_or(saved_value));
+                match current_value {
+                    Some(current_value) => diff(&path, saved_value, current_value, changes),
+                    None => {
+                        let kind = Kind::Removed(saved_value.clone());
+                        changes.push(Change { path, kind });
+                    },
+                }
+            }
+            for (key, current_value) in current {
+                if !saved.contains_key(key) {
+                    let path = child_path(path, key, current_value);
+                    let kind = Kind::Added(current_value.clone());
+                    changes.push(Change { path, kind });
+                }
+            }
+        },
+        _ => {
+            let is_unchanged = match (saved, current) {
+                (&Value::Array(ref a), &Value::Array(ref b)) => is_reordering(a, b),
+                _ => saved == current,
+            };
+            if !is_unchanged {
+                let path = path.to_string();
+                let (saved, current) = (saved.clone(), current.clone());
+                let kind = Kind::Modified { saved, current };
+                changes.push(Change { path, kind });
+            }
+        },
+    }
+}
+
+// Load the JSON value of a saved project file, producing an empty object if it does not exist.
+fn load_saved_value(path: &Path) -> io::Result<Value> {
+    if !path.exists() {
+        return Ok(Value::Object(Map::new()));
+    }
+    utils::load_from_json(path).map_err(|err| match err {
+        utils::FileError::Io(err) => err,
+        utils::FileError::Format(err) => io::Error::new(io::ErrorKind::InvalidData, err),
+    })
+}
+
+// Produce the JSON value of `t` as it would be loaded after saving.
+//
+// Serializing to a string first ensures numbers are rounded in the same way as the saved files,
+// e.g. `f32`s are written with their shortest representation.
+fn to_saved_value<T>(t: &T) -> io::Result<Value>
+where
+    T: serde::Serialize,
+{
+    let string = serde_json::to_string(t)?;
+    let value = serde_json::from_str(&string)?;
+    Ok(value)
+}
+
+// The path to the field or map entry with the given key.
+fn child_path(path: &str, key: &str, value: &Value) -> String {
+    let name = value.as_object().and_then(|obj| obj.get("name")).and_then(Value::as_str);
+    match name {
+        Some(name) => format!("{}[{:?}]", path, name),
+        None if key.parse::<u64>().is_ok() => format!("{}[{}]", path, key),
+        None if path.is_empty() => key.to_string(),
+        None => format!("{}.{}", path, key),
+    }
+}
+
+// Whether or not `b` contains the same elements as `a` in any order.
+fn is_reordering(a: &[Value], b: &[Value]) -> bool {
+    let count = |values: &[Value], v: &Value| values.iter().filter(|&x| x == v).count();
+    a.len() == b.len() && a.iter().all(|v| count(a, v) == count(b, v))
+}
+
+// A short description of the given value for display.
+fn describe(value: &Value) -> String {
+    let name = value.as_object().and_then(|obj| obj.get("name")).and_then(Value::as_str);
+    let string = match name {
+        Some(name) => format!("{:?}", name),
+        None => value.to_string(),
+    };
+    if string.chars().count() <= MAX_VALUE_CHARS {
+        return string;
+    }
+    let truncated: String = string.chars().take(MAX_VALUE_CHARS).collect();
+    format!("{}...", truncated)
+}
+
+#[test]
+fn test_diff() {
+    let saved: Value = serde_json::from_str(r#"{
+        "speakers": {
+            "0": { "name": "Front", "audio": { "channel": 0 } },
+            "1": { "name": "Back", "audio": { "channel": 1 } }
+        },
+        "soloed": [1, 2, 3]
+    }"#).unwrap();
+    let current: Value = serde_json::from_str(r#"{
+        "speakers": {
+            "0": { "name": "Front", "audio": { "channel": 2 } }
+        },
+        "soloed": [3, 1, 2],
+        "camera": { "zoom": 1.0 }
+    }"#).unwrap();
+    let mut changes = vec![];
+    diff("", &saved, &current, &mut changes);
+    let paths: Vec<_> = changes.iter().map(|c| &c.path[..]).collect();
+    assert_eq!(paths, vec!["speakers[\"Front\"].audio.channel", "speakers[\"Back\"]", "camera"]);
+}
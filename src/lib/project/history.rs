@@ -0,0 +1,281 @@
+//! Timestamped snapshots of a project written upon each explicit save.
+//!
+//! Each snapshot is a copy of the project's "config.json" and "state.json" files within a
+//! directory named after the UTC time at which it was taken, e.g.
+//! `history/2018-05-21_14-03-22/`. Older snapshots are pruned following the project's
+//! `Retention` policy so that the history does not grow without bound.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use super::{project_config_path, project_state_path, variation};
+
+/// The project sub-directory in which all snapshots are stored.
+const HISTORY_DIRECTORY_STEM: &'static str = "history";
+
+const SECS_PER_DAY: u64 = 60 * 60 * 24;
+const DAYS_PER_WEEK: u64 = 7;
+
+/// Describes which snapshots are kept when the history is pruned.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Retention {
+    /// The number of most recent snapshots that are always kept.
+    #[serde(default = "default::recent")]
+    pub recent: usize,
+    /// The number of days for which the latest snapshot of each day is kept.
+    #[serde(default = "default::daily")]
+    pub daily: usize,
+    /// The number of weeks for which the latest snapshot of each week is kept.
+    #[serde(default = "default::weekly")]
+    pub weekly: usize,
+}
+
+/// A single snapshot within a project's history.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// The directory containing the snapshot's files.
+    pub path: PathBuf,
+    /// The moment at which the snapshot was taken in seconds since the unix epoch.
+    pub secs: u64,
+}
+
+impl Snapshot {
+    /// The name of the snapshot, describing the UTC time at which it was taken.
+    pub fn name(&self) -> String {
+        format_timestamp(self.secs)
+    }
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Retention {
+            recent: default::recent(),
+            daily: default::daily(),
+            weekly: default::weekly(),
+        }
+    }
+}
+
+/// The path of the "history" directory within the given project directory.
+pub fn history_directory_path<P>(project_directory: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    project_directory.as_ref().join(HISTORY_DIRECTORY_STEM)
+}
+
+/// Copy the saved files of the project within the given directory into a new snapshot and prune
+/// the history following the given retention policy.
+///
+/// Returns the new snapshot.
+pub fn snapshot<P>(project_directory: P, retention: &Retention) -> io::Result<Snapshot>
+where
+    P: AsRef<Path>,
+{
+    let project_directory = project_directory.as_ref();
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::from_secs(0))
+        .as_secs();
+    let path = history_directory_path(project_directory).join(format_timestamp(secs));
+    fs::create_dir_all(&path)?;
+    fs::copy(project_config_path(project_directory), project_config_path(&path))?;
+    fs::copy(project_state_path(project_directory), project_state_path(&path))?;
+    let variation_path = variation::variation_path(project_directory);
+    if variation_path.exists() {
+        fs::copy(variation_path, variation::variation_path(&path))?;
+    }
+    prune(project_directory, retention)?;
+    Ok(Snapshot { path, secs })
+}
+
+/// Load all snapshots within the project directory's history, newest first.
+///
+/// Returns an empty list if the project has no history yet.
+pub fn snapshots<P>(project_directory: P) -> io::Result<Vec<Snapshot>>
+where
+    P: AsRef<Path>,
+{
+    let history_directory = history_directory_path(project_directory);
+    if !history_directory.exists() {
+        return Ok(vec![]);
+    }
+    let mut snapshots: Vec<_> = fs::read_dir(history_directory)?
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| project_state_path(p).exists())
+        .filter_map(|path| {
+            let secs = path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(parse_timestamp);
+            secs.map(|secs| Snapshot { path, secs })
+        })
+        .collect();
+    snapshots.sort_by(|a, b| b.secs.cmp(&a.secs));
+    Ok(snapshots)
+}
+
+/// Remove all snapshots that are not kept by the given retention policy.
+pub fn prune<P>(project_directory: P, retention: &Retention) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let snapshots = snapshots(project_directory)?;
+    let secs: Vec<_> = snapshots.iter().map(|s| s.secs).collect();
+    for (snapshot, keep) in snapshots.iter().zip(retained(&secs, retention)) {
+        if !keep {
+            fs::remove_dir_all(&snapshot.path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Overwrite the saved files of the project within the given directory with those of the given
+/// snapshot.
+///
+/// The currently saved files are first written to a new snapshot so that the restore may itself
+/// be undone. The project should be reloaded from its directory afterwards.
+pub fn restore<P>(
+    project_directory: P,
+    restored: &Snapshot,
+    retention: &Retention,
+) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    // Read the restored files first in case the snapshot is pruned below.
+    let project_directory = project_directory.as_ref();
+    let config = fs::read(project_config_path(&restored.path))?;
+    let state = fs::read(project_state_path(&restored.path))?;
+    let restored_variation_path = variation::variation_path(&restored.path);
+    let variation = match restored_variation_path.exists() {
+        true => Some(fs::read(restored_variation_path)?),
+        false => None,
+    };
+    snapshot(project_directory, retention)?;
+    fs::write(project_config_path(project_directory), config)?;
+    fs::write(project_state_path(project_directory), state)?;
+    if let Some(variation) = variation {
+        fs::write(variation::variation_path(project_directory), variation)?;
+    }
+    Ok(())
+}
+
+// Whether or not each of the given snapshot times (sorted newest first) is kept by the policy.
+fn retained(secs: &[u64], retention: &Retention) -> Vec<bool> {
+    let mut keep = vec![false; secs.len()];
+    let mut days_kept = vec![];
+    let mut weeks_kept = vec![];
+    for (i, &s) in secs.iter().enumerate() {
+        let day = s / SECS_PER_DAY;
+        let week = day / DAYS_PER_WEEK;
+        if i < retention.recent {
+            keep[i] = true;
+        }
+        if !days_kept.contains(&day) && days_kept.len() < retention.daily {
+            days_kept.push(day);
+            keep[i] = true;
+        }
+        if !weeks_kept.contains(&week) && weeks_kept.len() < retention.weekly {
+            weeks_kept.push(week);
+            keep[i] = true;
+        }
+    }
+    keep
+}
+
+/// Format the given seconds since the unix epoch as a UTC `YYYY-MM-DD_HH-MM-SS` timestamp.
+pub fn format_timestamp(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / SECS_PER_DAY) as i64);
+    let secs_of_day = secs % SECS_PER_DAY;
+    let (hour, min, sec) = (secs_of_day / 3600, secs_of_day / 60 % 60, secs_of_day % 60);
+    format!("{:04}-{:02}-{:02}_{:02}-{:02}-{:02}", year, month, day, hour, min, sec)
+}
+
+// Parse a timestamp produced by `format_timestamp` back to seconds since the unix epoch.
+fn parse_timestamp(s: &str) -> Option<u64> {
+    let mut parts = s.split(|c| c == '-' || c == '_').map(|p| p.parse::<u64>());
+    let mut next = || parts.next().and_then(Result::ok);
+    let (year, month, day) = (next()?, next()?, next()?);
+    let (hour, min, sec) = (next()?, next()?, next()?);
+    if month < 1 || month > 12 || day < 1 || day > 31 || hour > 23 || min > 59 || sec > 59 {
+        return None;
+    }
+    let days = days_from_civil(year as i64, month, day);
+    if days < 0 {
+        return None;
+    }
+    Some(days as u64 * SECS_PER_DAY + hour * 3600 + min * 60 + sec)
+}
+
+// The number of days since the unix epoch for the given proleptic gregorian date.
+//
+// Follows Howard Hinnant's `days_from_civil` algorithm.
+fn days_from_civil(year: i64, month: u64, day: u64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = (year - era * 400) as u64;
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era as i64 - 719_468
+}
+
+// The proleptic gregorian date for the given number of days since the unix epoch.
+//
+// Follows Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u64, u64) {
+    let days = days + 719_468;
+    let era = (if days >= 0 { days } else { days - 146_096 }) / 146_097;
+    let day_of_era = (days - era * 146_097) as u64;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era as i64 + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+pub mod default {
+    pub fn recent() -> usize {
+        10
+    }
+
+    pub fn daily() -> usize {
+        7
+    }
+
+    pub fn weekly() -> usize {
+        4
+    }
+}
+
+#[test]
+fn test_timestamp_roundtrip() {
+    assert_eq!(format_timestamp(0), "1970-01-01_00-00-00");
+    assert_eq!(format_timestamp(951_782_400), "2000-02-29_00-00-00");
+    for &secs in &[0, 951_782_400, 1_526_911_402, 4_102_444_799] {
+        assert_eq!(parse_timestamp(&format_timestamp(secs)), Some(secs));
+    }
+    assert_eq!(parse_timestamp("not-a-snapshot"), None);
+}
+
+#[test]
+fn test_retained() {
+    let retention = Retention { recent: 2, daily: 2, weekly: 2 };
+    let day = SECS_PER_DAY;
+    // Newest first: three snapshots today, one yesterday, one last week and one long ago.
+    let secs = [
+        100 * day + 30,
+        100 * day + 20,
+        100 * day + 10,
+        99 * day,
+        93 * day,
+        50 * day,
+    ];
+    let keep = retained(&secs, &retention);
+    assert_eq!(keep, vec![true, true, false, true, true, false]);
+}
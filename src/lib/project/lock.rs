@@ -0,0 +1,197 @@
+//! Leases and conflict detection for projects shared between machines.
+//!
+//! When projects live on a shared network drive, two machines may open the same project at once.
+//! The first to open a project writes a `lock.json` lease into its directory and renews it while
+//! the project remains open. Others opening the project find the active lease and warn the user.
+//! A lease that has not been renewed within `LEASE_SECS` is considered abandoned (e.g. following a
+//! crash) and may be taken over.
+//!
+//! Independently of the lease, the modification time of the project's "state.json" is recorded
+//! upon opening and saving so that saves by another machine in the meantime are detected rather
+//! than silently overwritten.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use super::project_state_path;
+use utils;
+
+/// The name of the lease file within a project directory.
+const LOCK_FILE_NAME: &'static str = "lock.json";
+
+/// The duration after which a lease that has not been renewed is considered abandoned.
+pub const LEASE_SECS: u64 = 5 * 60;
+
+/// The interval at which a held lease is renewed and another's lease is re-checked.
+const RENEW_INTERVAL_SECS: u64 = 60;
+
+/// The contents of a project's lease file.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Lease {
+    /// The name of the machine holding the lease.
+    pub host: String,
+    /// The ID of the process holding the lease.
+    pub pid: u32,
+    /// The moment at which the lease was last renewed in seconds since the unix epoch.
+    pub renewed_secs: u64,
+}
+
+/// The lease on and the last known saved state of the currently open project.
+///
+/// The lease is released when the `Lock` is dropped.
+#[derive(Debug)]
+pub struct Lock {
+    directory: PathBuf,
+    // Whether the lease file is held by this process.
+    is_held: bool,
+    // The active lease of another machine or process, if any.
+    other: Option<Lease>,
+    // The modification time of "state.json" as of opening or the last save.
+    state_modified: Option<SystemTime>,
+    // The moment at which the lease was last renewed or checked.
+    last_checked_secs: u64,
+}
+
+impl Lease {
+    // A lease for this process renewed at the given time.
+    fn new(renewed_secs: u64) -> Self {
+        Lease { host: host_name(), pid: process::id(), renewed_secs }
+    }
+
+    /// Whether or not the lease is held by this process.
+    pub fn is_ours(&self) -> bool {
+        self.pid == process::id() && self.host == host_name()
+    }
+
+    /// Whether the lease has not been renewed within `LEASE_SECS` of the given time.
+    pub fn is_expired(&self, now_secs: u64) -> bool {
+        now_secs.saturating_sub(self.renewed_secs) > LEASE_SECS
+    }
+}
+
+impl Lock {
+    /// Open the project within the given directory, taking its lease unless another machine or
+    /// process holds an active one.
+    pub fn acquire<P>(project_directory: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let mut lock = Lock {
+            directory: project_directory.as_ref().to_path_buf(),
+            is_held: false,
+            other: None,
+            state_modified: None,
+            last_checked_secs: 0,
+        };
+        lock.mark_saved();
+        lock.check(now_secs());
+        lock
+    }
+
+    /// The directory of the locked project.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// The active lease held by another machine or process, if any.
+    pub fn other(&self) -> Option<&Lease> {
+        self.other.as_ref()
+    }
+
+    /// Renew the lease if it is held or re-check another's lease, at most once per renewal
+    /// interval.
+    pub fn update(&mut self) {
+        let now = now_secs();
+        if now.saturating_sub(self.last_checked_secs) >= RENEW_INTERVAL_SECS {
+            self.check(now);
+        }
+    }
+
+    /// Whether or not "state.json" was written by someone else since the project was opened or
+    /// last saved by this process.
+    pub fn is_modified_externally(&self) -> bool {
+        let modified = state_modified(&self.directory);
+        modified.is_some() && modified != self.state_modified
+    }
+
+    /// Record the current modification time of "state.json" following a save or reload.
+    pub fn mark_saved(&mut self) {
+        self.state_modified = state_modified(&self.directory);
+    }
+
+    // Take or renew the lease unless another holds an active one.
+    fn check(&mut self, now: u64) {
+        self.last_checked_secs = now;
+        let path = lock_path(&self.directory);
+        let existing: Option<Lease> = utils::load_from_json(&path).ok();
+        self.other = existing.filter(|lease| !lease.is_ours() && !lease.is_expired(now));
+        self.is_held = self.other.is_none();
+        if self.is_held {
+            if let Err(err) = utils::save_to_json(&path, &Lease::new(now)) {
+                eprintln!("failed to write project lease \"{}\": {}", path.display(), err);
+            }
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if !self.is_held {
+            return;
+        }
+        // Only remove the lease if it was not taken over in the meantime.
+        let path = lock_path(&self.directory);
+        let lease: Option<Lease> = utils::load_from_json(&path).ok();
+        if lease.map(|lease| lease.is_ours()).unwrap_or(false) {
+            if let Err(err) = fs::remove_file(&path) {
+                eprintln!("failed to remove project lease \"{}\": {}", path.display(), err);
+            }
+        }
+    }
+}
+
+/// The path of the lease file within the given project directory.
+pub fn lock_path<P>(project_directory: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    project_directory.as_ref().join(LOCK_FILE_NAME)
+}
+
+/// The name of this machine, used to identify the holder of a lease.
+pub fn host_name() -> String {
+    env::var("HOSTNAME")
+        .or_else(|_| env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| {
+            fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|s| s.trim().to_string())
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn state_modified(project_directory: &Path) -> Option<SystemTime> {
+    fs::metadata(project_state_path(project_directory))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::from_secs(0))
+        .as_secs()
+}
+
+#[test]
+fn test_lease_expiry() {
+    let lease = Lease { host: "other".into(), pid: 1, renewed_secs: 1_000 };
+    assert!(!lease.is_ours());
+    assert!(!lease.is_expired(1_000 + LEASE_SECS));
+    assert!(lease.is_expired(1_000 + LEASE_SECS + 1));
+    assert!(Lease::new(0).is_ours());
+}
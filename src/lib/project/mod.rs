@@ -16,7 +16,9 @@ use camera::Camera;
 use fxhash::{FxHashMap, FxHashSet};
 use gui;
 use installation::{self, Installation};
+use interaction;
 use master::Master;
+use metres::Metres;
 use osc;
 use slug::slugify;
 use soundscape;
@@ -30,6 +32,13 @@ use utils;
 use walkdir::WalkDir;
 
 pub mod config;
+pub mod dependencies;
+pub mod diff;
+pub mod history;
+pub mod lock;
+pub mod part;
+pub mod simulation;
+pub mod variation;
 
 pub use self::config::Config;
 
@@ -51,6 +60,12 @@ const CONFIG_EXTENSION: &'static str = "json";
 /// The name of the directory where the WAVs are stored.
 const AUDIO_DIRECTORY_STEM: &'static str = "audio";
 
+/// The project sub-directory in which recordings of the output are stored.
+const RECORDINGS_DIRECTORY_STEM: &'static str = "recordings";
+
+/// The project sub-directory in which loudness logs of the master bus are stored.
+const LOUDNESS_DIRECTORY_STEM: &'static str = "loudness";
+
 /// All state related to a single project including configuration.
 ///
 /// A single project describes a particular configuration of the audio server.
@@ -62,6 +77,10 @@ pub struct Project {
     pub config: Config,
     /// The state of the project.
     pub state: State,
+    /// The slug of the project from which this project inherits if it is a variation.
+    ///
+    /// See the `variation` module for details.
+    pub base: Option<String>,
 }
 
 /// All state related to a single project.
@@ -74,6 +93,7 @@ pub struct Project {
 /// 3. Soundscape groups and constraints.
 /// 4. Seaker layout.
 /// 5. Audio source params and soundscape constraints.
+/// 6. Interaction inputs and their transforms onto the floorplan.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct State {
     /// The human-readable name of the project.
@@ -87,6 +107,9 @@ pub struct State {
     /// All installations in the exhibition along with their soundscape constraints.
     #[serde(default = "default_beyond_perception_installations")]
     pub installations: Installations,
+    /// All inputs from which positional interaction data is received, e.g. tracking systems.
+    #[serde(default)]
+    pub interaction_inputs: InteractionInputs,
     /// All soundscape groups within the exhibition.
     #[serde(default)]
     pub soundscape_groups: SoundscapeGroups,
@@ -99,11 +122,17 @@ pub struct State {
     /// The state of the camera over the floorplan.
     #[serde(default)]
     pub camera: Camera,
+    /// The rotation, mirroring and offset of the floorplan image.
+    #[serde(default)]
+    pub floorplan: gui::floorplan::Transform,
 }
 
 /// A map of all installations within the exhibition to their soundscape constraints.
 pub type Installations = FxHashMap<installation::Id, Installation>;
 
+/// A map of all interaction inputs to their OSC mappings and transforms.
+pub type InteractionInputs = FxHashMap<interaction::Id, interaction::Input>;
+
 /// A map of all soundscape groups within the exhibition for the project.
 pub type SoundscapeGroups = FxHashMap<soundscape::group::Id, SoundscapeGroup>;
 
@@ -134,6 +163,12 @@ pub struct SoundscapeGroup {
     pub name: String,
     /// Soundscape-specific parameters.
     pub soundscape: soundscape::Group,
+    /// The position of the group within the GUI's group lists.
+    #[serde(default)]
+    pub order: usize,
+    /// An index into the GUI's palette of group colours.
+    #[serde(default)]
+    pub color: usize,
 }
 
 /// State of a single speaker within the exhibition associated with a single project.
@@ -158,18 +193,22 @@ impl State {
     fn default_from_name(name: String) -> Self {
         let master = Default::default();
         let installations = default_beyond_perception_installations();
+        let interaction_inputs = Default::default();
         let soundscape_groups = Default::default();
         let speakers = Default::default();
         let sources = Default::default();
         let camera = Default::default();
+        let floorplan = Default::default();
         State {
             name,
             master,
             installations,
+            interaction_inputs,
             soundscape_groups,
             speakers,
             sources,
             camera,
+            floorplan,
         }
     }
 
@@ -209,6 +248,7 @@ impl Project {
         assets: P,
         config: Config,
         mut state: State,
+        base: Option<String>,
     ) -> Self
     where
         P: AsRef<Path>,
@@ -224,13 +264,11 @@ impl Project {
         ////////////////////////////////////////////////////
 
         let assets = assets.as_ref();
-        let audio_path = assets.join(AUDIO_DIRECTORY_STEM);
+        let audio_path = audio_directory(assets);
         state.auto_name_installations_if_all_unnamed();
-        state.sources.remove_invalid_sources(&audio_path);
-        state.sources.load_missing_sources(audio_path);
-        state.sources.remove_invalid_soloed();
+        state.sources.prepare(audio_path);
 
-        Project { config, state }
+        Project { config, state, base }
     }
 
     /// This method clears the state on all threads and re-populates them with the state of the
@@ -264,6 +302,17 @@ impl Project {
         let dbap_rolloff_db = self.master.dbap_rolloff_db;
         let realtime_source_latency = self.master.realtime_source_latency;
         let proximity_limit_2 = self.master.proximity_limit_2;
+        let reverb = self.master.reverb;
+        let limiter = self.master.limiter;
+        let bass = self.master.bass;
+        let ambisonics = self.master.ambisonics;
+        let binaural = self.master.binaural;
+        let distance = self.master.distance;
+        let headroom = self.master.headroom;
+        let soundscape_intensity = self.master.soundscape_intensity;
+        let time_zone = self.master.time_zone;
+        let scene = self.master.soundscape_scenes.current.clone();
+        let tempo = self.master.soundscape_tempo;
         channels
             .audio_output
             .send(move |audio| {
@@ -271,14 +320,28 @@ impl Project {
                 audio.dbap_rolloff_db = dbap_rolloff_db;
                 // Square for efficiency
                 audio.proximity_limit_2 = proximity_limit_2;
+                audio.reverb.set_params(reverb);
+                audio.limiter.set_params(limiter);
+                audio.bass = bass;
+                audio.ambisonics.set_params(ambisonics);
+                audio.binaural.set_params(binaural);
+                audio.distance = distance;
+                audio.headroom = headroom;
             })
             .expect("failed to send loaded master volume and dbap rolloff");
         channels
             .soundscape
             .send(move |soundscape| {
                 soundscape.realtime_source_latency = realtime_source_latency;
+                soundscape.set_intensity(soundscape_intensity);
+                soundscape.time_zone = time_zone;
+                if let Some(scene) = scene {
+                    soundscape.crossfade_to_scene(scene, 0.0);
+                }
+                soundscape.set_tempo(tempo);
             })
-            .expect("failed to send loaded realtime source latency");
+            .expect("failed to send loaded realtime source latency, intensity and time zone");
+        channels.soundscape.set_tick_rate(self.master.soundscape_tick_rate);
 
         // Installations to soundscape, osc output and audio output.
         for (&id, installation) in self.installations.iter() {
@@ -302,14 +365,29 @@ impl Project {
                 let add = osc::output::OscTarget::Add(id, computer, target, osc_addr);
                 let msg = osc::output::Message::Osc(add);
                 channels.osc_out_msg_tx.push(msg);
+                if addr.capture {
+                    let name = installation::computer::capture_name(&installation.name, computer);
+                    let capture = osc::output::OscTarget::Capture(id, computer, Some(name));
+                    channels.osc_out_msg_tx.push(osc::output::Message::Osc(capture));
+                }
+                if addr.dry_run {
+                    let dry_run = osc::output::OscTarget::DryRun(id, computer, true);
+                    channels.osc_out_msg_tx.push(osc::output::Message::Osc(dry_run));
+                }
             }
 
             // Audio output thread.
             let computers = installation.computers.len();
+            let reverb = audio::reverb::Reverb::new(installation.reverb);
+            let volume = installation.volume;
+            let ducking = installation.ducking;
             channels
                 .audio_output
                 .send(move |audio| {
                     audio.insert_installation(id, computers);
+                    audio.insert_installation_reverb(id, reverb);
+                    audio.update_installation_volume(id, volume);
+                    audio.update_installation_ducking(id, ducking);
                 })
                 .expect("failed to send loaded installation to audio output thread");
         }
@@ -346,12 +424,9 @@ impl Project {
         // Sources to the audio input and soundscape threads.
         for (&id, source) in self.sources.iter() {
             if let audio::source::Kind::Realtime(ref realtime) = source.kind {
-                let clone = realtime.clone();
                 channels
                     .audio_input
-                    .send(move |audio| {
-                        audio.sources.insert(id, clone);
-                    })
+                    .insert_source(id, realtime.clone())
                     .expect("failed to send source to audio input thread");
             }
             if let Some(clone) = soundscape::Source::from_audio_source(&source) {
@@ -364,7 +439,14 @@ impl Project {
             }
         }
 
-
+        // Announce the project to every installation computer now that all targets are added.
+        let announcement = osc::output::Announcement {
+            project: self.name.clone(),
+            installations: self.installations.len(),
+            speakers: self.speakers.len(),
+            sources: self.sources.len(),
+        };
+        channels.osc_out_msg_tx.push(osc::output::Message::Announce(announcement));
     }
 
     /// Create a new project with a unique, default name.
@@ -393,7 +475,7 @@ impl Project {
         let config = default_config.clone();
         let state = State::default_from_name(name);
 
-        Self::from_config_and_state(assets, config, state)
+        Self::from_config_and_state(assets, config, state, None)
     }
 
     /// The same as `load`, but loads the project from the given slug rather than the full path.
@@ -416,6 +498,9 @@ impl Project {
     ///
     /// If the project "config.json" does not exist or is invalid, a default config will be used.
     ///
+    /// If the project is a variation, its state is resolved from the latest state of its base. The
+    /// variation's own "state.json" is used if the base cannot be loaded.
+    ///
     /// **Panics** if the project "state.json" does not exist or is invalid. However, the method
     /// will attempt to fall back to reasonable default for each field that cannot be deserialized.
     pub fn load<A, P>(
@@ -432,12 +517,23 @@ impl Project {
         let config: Config = utils::load_from_json(&config_path)
             .unwrap_or_else(|_| default_config.clone());
 
-        // Load the state json.
-        let state_path = project_state_path(project_directory_path);
-        let state: State = utils::load_from_json(&state_path)
-            .expect("failed to load project state");
+        // Load the state json, resolving it from the base if the project is a variation.
+        let variation = variation::load(&project_directory_path);
+        let resolved = variation.as_ref().and_then(|variation| {
+            variation.resolve(&assets_path)
+                .map_err(|err| eprintln!("failed to resolve project variation: {}", err))
+                .ok()
+        });
+        let state: State = match resolved {
+            Some(state) => state,
+            None => {
+                let state_path = project_state_path(&project_directory_path);
+                utils::load_from_json(&state_path).expect("failed to load project state")
+            },
+        };
 
-        Self::from_config_and_state(assets_path, config, state)
+        let base = variation.map(|variation| variation.base);
+        Self::from_config_and_state(assets_path, config, state, base)
     }
 
     /// Save the project in its current state.
@@ -460,13 +556,74 @@ impl Project {
         }
 
         // Save the state json file.
-        let state_path = project_state_path(project_directory);
+        let state_path = project_state_path(&project_directory);
         if let Err(err) = utils::save_to_json(&state_path, &self.state) {
             eprintln!("failed to save project state.json: {}", err);
         }
 
+        // Save only the parameters overridden by a variation so that it follows its base.
+        if let Some(ref base) = self.base {
+            match variation::Variation::from_state(&assets, base, &self.state) {
+                Err(err) => eprintln!("failed to compare project variation with base: {}", err),
+                Ok(variation) => {
+                    let variation_path = variation::variation_path(&project_directory);
+                    if let Err(err) = utils::save_to_json(&variation_path, &variation) {
+                        eprintln!("failed to save project variation.json: {}", err);
+                    }
+                },
+            }
+        }
+
         Ok(())
     }
+
+    /// Save the project and write a timestamped snapshot of the saved files to its history.
+    ///
+    /// This should be used for explicit saves by the user rather than for the saves made when
+    /// switching between projects.
+    pub fn save_with_snapshot<P>(&self, assets: P) -> io::Result<history::Snapshot>
+    where
+        P: AsRef<Path>,
+    {
+        self.save(&assets)?;
+        let project_directory = project_directory_path(&assets, &self.name);
+        history::snapshot(project_directory, &self.config.history)
+    }
+}
+
+impl Source {
+    /// Create a source with default parameters for the given WAV.
+    pub fn from_wav(name: String, wav: audio::source::Wav) -> Self {
+        let kind = audio::source::Kind::Wav(wav);
+        let role = None;
+        let spread = audio::source::default::SPREAD;
+        let channel_radians = audio::source::default::CHANNEL_RADIANS;
+        let height = Metres(0.0);
+        let volume = audio::source::default::VOLUME;
+        let muted = bool::default();
+        let enabled = audio::source::default::ENABLED;
+        let effects = Default::default();
+        let reverb_send = 0.0;
+        let distance_model = false;
+        let dbap_rolloff_db = None;
+        let speaker_filter = Default::default();
+        let audio = audio::Source {
+            kind,
+            role,
+            spread,
+            channel_radians,
+            height,
+            volume,
+            muted,
+            enabled,
+            effects,
+            reverb_send,
+            distance_model,
+            dbap_rolloff_db,
+            speaker_filter,
+        };
+        Source { name, audio }
+    }
 }
 
 impl Sources {
@@ -476,15 +633,63 @@ impl Sources {
         audio::source::Id(next_id)
     }
 
-    /// Check for invalid WAV sources
+    /// Check for, relink and load any missing WAV sources within the given audio directory and
+    /// remove any soloed sources that no longer exist.
+    pub fn prepare<P>(&mut self, audio_path: P)
+    where
+        P: AsRef<Path>,
+    {
+        let audio_path = audio_path.as_ref();
+        self.check_missing_sources(audio_path);
+        self.relink_missing_sources(audio_path);
+        self.report_missing_sources();
+        self.load_missing_sources(audio_path);
+        self.remove_invalid_soloed();
+    }
+
+    /// Check for WAV sources whose files can no longer be found.
+    ///
+    /// See the `check_missing_sources` function for details.
+    pub fn check_missing_sources<P>(&mut self, audio_path: P)
+    where
+        P: AsRef<Path>,
+    {
+        check_missing_sources(audio_path, self);
+    }
+
+    /// Relink missing WAV sources to files of the same name within the given directory.
     ///
-    /// If there are any ".wav" files in `assets/audio` that have not yet been loaded into the
-    /// stored sources, load them as `Wav` kind sources.
-    pub fn remove_invalid_sources<P>(&mut self, audio_path: P)
+    /// See the `relink_missing_sources` function for details.
+    pub fn relink_missing_sources<P>(&mut self, search_path: P)
     where
         P: AsRef<Path>,
     {
-        remove_invalid_sources(audio_path, self);
+        relink_missing_sources(search_path, self);
+    }
+
+    /// The IDs of all WAV sources whose files are missing.
+    pub fn missing_sources(&self) -> Vec<audio::source::Id> {
+        self.map
+            .iter()
+            .filter(|&(_, source)| source.audio.kind.is_missing())
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// Print a report of all WAV sources whose files are missing.
+    pub fn report_missing_sources(&self) {
+        let missing = self.missing_sources();
+        if missing.is_empty() {
+            return;
+        }
+        eprintln!("{} WAV source(s) could not be found and will not be played until relinked:",
+                  missing.len());
+        for id in missing {
+            let source = &self.map[&id];
+            if let audio::source::Kind::Wav(ref wav) = source.audio.kind {
+                eprintln!("  \"{}\": {}", source.name, wav.path.display());
+            }
+        }
     }
 
     /// Remove all sources from the "soloed" set that no longer exist.
@@ -545,11 +750,12 @@ fn test_update_path_from_relative() {
     assert_eq!(update_path_from_relative(path, relative), Some(PathBuf::from(expected)));
 }
 
-/// Check for invalid WAV sources.
+/// Check for WAV sources whose files can no longer be found.
 ///
-/// If the source path's could not be correctly updated, we attempt to re-attach the path from the
-/// `audio` component of the path and onwards.
-pub fn remove_invalid_sources<P>(audio_path: P, sources: &mut Sources)
+/// If a source path is invalid, we attempt to re-attach the path from the `audio` component of
+/// the path and onwards. If the file still cannot be found, the source is marked as missing
+/// rather than removed so that its settings are retained until it is relinked.
+pub fn check_missing_sources<P>(audio_path: P, sources: &mut Sources)
 where
     P: AsRef<Path>,
 {
@@ -558,10 +764,17 @@ where
     // Check the validity of the WAV source paths.
     //
     // If a path is invalid, check to see if it exists within the given `audio_path`. If so,
-    // update the source path. Otherwise, remove it.
-    let mut to_remove = vec![];
-    for (&id, source) in sources.map.iter_mut() {
+    // update the source path. Otherwise, mark it as missing.
+    for source in sources.map.values_mut() {
         if let audio::source::Kind::Wav(ref mut wav) = source.audio.kind {
+            // Check the variation files in the same manner, retaining any that are missing.
+            for variation in &mut wav.variations.files {
+                match update_path_from_relative(&variation.path, audio_path) {
+                    Some(ref new_path) if new_path.exists() => variation.path = new_path.clone(),
+                    _ => variation.missing = true,
+                }
+            }
+
             // Check to see that the WAV path contains the `audio` directory in its path.
             //
             // If so, check the path at the new location relative to the audio path.
@@ -569,37 +782,157 @@ where
             // If we can find it, return the new absolute path.
             let new_path = update_path_from_relative(&wav.path, audio_path);
 
-            // Update the wavs path, or remove the source if we couldn't find it.
+            // Update the wavs path, or mark the source as missing if we couldn't find it.
             if let Some(new_path) = new_path {
                 if new_path.exists() {
                     // Reload the WAV file to make sure we have up-to-date info.
-                    let mut new_wav = match audio::source::Wav::from_path(new_path.clone()) {
+                    let mut new_wav = match wav.reload_from_path(new_path.clone()) {
                         Ok(wav) => wav,
                         Err(err) => {
-                            eprintln!("Failed to load wav from path \"{}\": {}. It will be ignored.",
+                            eprintln!("Failed to load wav from path \"{}\": {}.",
                                       new_path.display(), err);
+                            wav.missing = true;
                             continue;
                         },
                     };
-                    new_wav.should_loop = wav.should_loop;
-                    new_wav.playback = wav.playback;
                     mem::swap(wav, &mut new_wav);
                     continue;
                 }
-                eprintln!("Could not find WAV source at \"{}\" or at \"{}\". It will be ignored.",
-                          wav.path.display(),
-                          new_path.display());
-            } else {
-                eprintln!("Could not find WAV source at \"{}\". It will be ignored.",
-                          wav.path.display());
             }
 
-            to_remove.push(id);
+            wav.missing = true;
+        }
+    }
+}
+
+/// Relink missing WAV sources to files of the same name within the given directory.
+///
+/// Only exact (case-insensitive) file name matches are relinked, as this is performed without
+/// user confirmation. Use `find_relink_path` for fuzzy matching.
+pub fn relink_missing_sources<P>(search_path: P, sources: &mut Sources)
+where
+    P: AsRef<Path>,
+{
+    let search_path = search_path.as_ref();
+    let candidates = wav_paths_in_directory(search_path);
+    for source in sources.map.values_mut() {
+        if let audio::source::Kind::Wav(ref mut wav) = source.audio.kind {
+            if !wav.missing {
+                continue;
+            }
+            let new_path = match best_relink_candidate(&wav.path, &candidates, 1.0) {
+                Some(path) => path,
+                None => continue,
+            };
+            match wav.reload_from_path(new_path.clone()) {
+                Ok(new_wav) => {
+                    eprintln!("Relinked missing WAV \"{}\" to \"{}\"",
+                              wav.path.display(), new_path.display());
+                    *wav = new_wav;
+                },
+                Err(err) => {
+                    eprintln!("Failed to load wav from path \"{}\": {}.",
+                              new_path.display(), err);
+                },
+            }
+        }
+    }
+}
+
+/// The minimum file name similarity for a file to be suggested when relinking a missing WAV.
+pub const MIN_RELINK_SIMILARITY: f32 = 0.6;
+
+/// Search the given directory for the WAV file whose name most closely matches that of the
+/// given missing path.
+///
+/// Returns `None` if no file name reaches the `MIN_RELINK_SIMILARITY`.
+pub fn find_relink_path<P>(search_path: P, missing_path: &Path) -> Option<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    let candidates = wav_paths_in_directory(search_path.as_ref());
+    best_relink_candidate(missing_path, &candidates, MIN_RELINK_SIMILARITY)
+}
+
+// The candidate whose file name is most similar to the missing path's, if any reach `min`.
+fn best_relink_candidate(
+    missing_path: &Path,
+    candidates: &[PathBuf],
+    min: f32,
+) -> Option<PathBuf> {
+    let file_name = |path: &Path| {
+        path.file_name()
+            .and_then(OsStr::to_str)
+            .map(str::to_ascii_lowercase)
+    };
+    let missing_name = file_name(missing_path)?;
+    candidates
+        .iter()
+        .filter_map(|path| file_name(path).map(|name| (path, similarity(&missing_name, &name))))
+        .filter(|&(_, similarity)| similarity >= min)
+        .fold(None, |best: Option<(&PathBuf, f32)>, (path, similarity)| match best {
+            Some((_, best_similarity)) if best_similarity >= similarity => best,
+            _ => Some((path, similarity)),
+        })
+        .map(|(path, _)| path.clone())
+}
+
+/// The similarity of the two given strings in the range `0.0..=1.0`, where `1.0` is identical.
+///
+/// This is the Levenshtein distance normalised by the length of the longer string.
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let max_len = cmp::max(a.len(), b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+    let mut prev: Vec<usize> = (0..b.len() + 1).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for i in 0..a.len() {
+        curr[0] = i + 1;
+        for j in 0..b.len() {
+            let substitution = prev[j] + if a[i] == b[j] { 0 } else { 1 };
+            curr[j + 1] = cmp::min(substitution, cmp::min(prev[j + 1], curr[j]) + 1);
         }
+        mem::swap(&mut prev, &mut curr);
     }
-    for id in to_remove {
-        sources.map.remove(&id);
+    1.0 - prev[b.len()] as f32 / max_len as f32
+}
+
+#[test]
+fn test_similarity() {
+    assert_eq!(similarity("rain.wav", "rain.wav"), 1.0);
+    assert!((similarity("rain.wav", "rain_2.wav") - 0.8).abs() < 1e-6);
+    assert!(similarity("rain.wav", "thunder.wav") < MIN_RELINK_SIMILARITY);
+}
+
+// All non-hidden ".wav" files within the given directory and its sub-directories.
+fn wav_paths_in_directory(dir: &Path) -> Vec<PathBuf> {
+    if !dir.is_dir() {
+        return vec![];
     }
+    WalkDir::new(dir)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let file_name = e.file_name();
+            let file_path = Path::new(&file_name);
+            if utils::is_file_hidden(&file_path) {
+                return None;
+            }
+            let ext = file_path
+                .extension()
+                .and_then(OsStr::to_str)
+                .map(str::to_ascii_lowercase);
+            match ext.as_ref().map(|e| &e[..]) {
+                Some("wav") | Some("wave") => Some(e.path().to_path_buf()),
+                _ => None,
+            }
+        })
+        .collect()
 }
 
 /// Load missing WAV sources.
@@ -616,26 +949,7 @@ where
     //
     // Ignores all hidden files.
     if audio_path.exists() && audio_path.is_dir() {
-        let wav_paths = WalkDir::new(&audio_path)
-            .follow_links(true)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-            .filter_map(|e| {
-                let file_name = e.file_name();
-                let file_path = Path::new(&file_name);
-                if utils::is_file_hidden(&file_path) {
-                    return None;
-                }
-                let ext = file_path
-                    .extension()
-                    .and_then(OsStr::to_str)
-                    .map(str::to_ascii_lowercase);
-                match ext.as_ref().map(|e| &e[..]) {
-                    Some("wav") | Some("wave") => Some(e.path().to_path_buf()),
-                    _ => None,
-                }
-            });
+        let wav_paths = wav_paths_in_directory(audio_path);
 
         // Find the next available ID in case we find new sources.
         let mut next_id = sources.next_id();
@@ -645,8 +959,8 @@ where
             // If we already have this one, continue.
             for s in sources.map.values() {
                 match s.audio.kind {
-                    audio::source::Kind::Wav(ref wav) => if wav.path == path {
-                        if wav.path == path {
+                    audio::source::Kind::Wav(ref wav) => {
+                        if wav.path == path || wav.variations.contains_path(&path) {
                             continue 'paths;
                         }
                     },
@@ -666,21 +980,7 @@ where
                     continue;
                 }
             };
-            let kind = audio::source::Kind::Wav(wav);
-            let role = None;
-            let spread = audio::source::default::SPREAD;
-            let channel_radians = audio::source::default::CHANNEL_RADIANS;
-            let volume = audio::source::default::VOLUME;
-            let muted = bool::default();
-            let audio = audio::Source {
-                kind,
-                role,
-                spread,
-                channel_radians,
-                volume,
-                muted,
-            };
-            let source = Source { name, audio };
+            let source = Source::from_wav(name, wav);
             sources.map.insert(next_id, source);
             next_id = audio::source::Id(next_id.0 + 1);
         }
@@ -693,12 +993,55 @@ pub fn next_installation_id(installations: &Installations) -> installation::Id {
     installation::Id(next_id)
 }
 
+/// Search for and return the next available interaction input ID.
+pub fn next_interaction_input_id(inputs: &InteractionInputs) -> interaction::Id {
+    let next_id = inputs.keys().map(|id| id.0 + 1).fold(0, cmp::max);
+    interaction::Id(next_id)
+}
+
 /// Search for and return the next available group ID.
 pub fn next_soundscape_group_id(groups: &SoundscapeGroups) -> soundscape::group::Id {
     let next_id = groups.keys().map(|id| id.0).fold(0, cmp::max) + 1;
     soundscape::group::Id(next_id)
 }
 
+/// Collect all groups in the order in which they should be displayed.
+///
+/// Groups that share the same `order` (e.g. those loaded from older projects) are sorted by name.
+pub fn ordered_soundscape_groups(
+    groups: &SoundscapeGroups,
+) -> Vec<(soundscape::group::Id, &SoundscapeGroup)> {
+    let mut groups_vec: Vec<_> = groups.iter().map(|(&id, group)| (id, group)).collect();
+    groups_vec.sort_by(|a, b| a.1.order.cmp(&b.1.order).then(a.1.name.cmp(&b.1.name)));
+    groups_vec
+}
+
+/// Move the group with the given `Id` to the given index within the group ordering.
+///
+/// All groups are renumbered so that their `order` reflects their new position.
+pub fn move_soundscape_group(
+    groups: &mut SoundscapeGroups,
+    id: soundscape::group::Id,
+    index: usize,
+) {
+    let mut ids: Vec<_> = ordered_soundscape_groups(groups)
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    let current = match ids.iter().position(|&other| other == id) {
+        Some(current) => current,
+        None => return,
+    };
+    ids.remove(current);
+    let index = cmp::min(index, ids.len());
+    ids.insert(index, id);
+    for (order, id) in ids.into_iter().enumerate() {
+        if let Some(group) = groups.get_mut(&id) {
+            group.order = order;
+        }
+    }
+}
+
 /// Given the map of speakers, produce the next available unique `Id`.
 pub fn next_speaker_id(speakers: &Speakers) -> audio::speaker::Id {
     let next_id = speakers.keys().map(|id| id.0).fold(0, cmp::max) + 1;
@@ -802,13 +1145,34 @@ pub fn default_beyond_perception_installations() -> Installations {
                     let computer = installation::computer::Id(i);
                     let socket = "127.0.0.1:9002".parse().unwrap();
                     let osc_addr = osc_addr.clone();
-                    let addr = installation::computer::Address { socket, osc_addr };
+                    let capture = false;
+                    let dry_run = false;
+                    let addr = installation::computer::Address {
+                        socket,
+                        osc_addr,
+                        capture,
+                        dry_run,
+                    };
                     (computer, addr)
                 })
                 .collect();
             let soundscape = Default::default();
             let name = name.into();
-            let installation = Installation { name, computers, soundscape };
+            let osc_input_prefix = Default::default();
+            let reverb = Default::default();
+            let volume = Default::default();
+            let sensor_gain = Default::default();
+            let ducking = Default::default();
+            let installation = Installation {
+                name,
+                computers,
+                soundscape,
+                osc_input_prefix,
+                reverb,
+                volume,
+                sensor_gain,
+                ducking,
+            };
             (id, installation)
         })
         .collect()
@@ -819,6 +1183,14 @@ pub fn default_project_name() -> String {
     "My Project".into()
 }
 
+/// The path of the "assets/audio/" directory in which all WAV sources are stored.
+pub fn audio_directory<P>(assets: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    assets.as_ref().join(AUDIO_DIRECTORY_STEM)
+}
+
 /// The path of the "assetes/projects/" directory.
 pub fn projects_directory<P>(assets: P) -> PathBuf
 where
@@ -859,6 +1231,22 @@ where
         .with_extension(STATE_EXTENSION)
 }
 
+/// The path of the "recordings" directory within the given project directory.
+pub fn recordings_directory_path<P>(project_directory: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    project_directory.as_ref().join(RECORDINGS_DIRECTORY_STEM)
+}
+
+/// The path of the "loudness" directory within the given project directory.
+pub fn loudness_directory_path<P>(project_directory: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    project_directory.as_ref().join(LOUDNESS_DIRECTORY_STEM)
+}
+
 /// Loads the path of every project directory within the `projects/` directory.
 pub fn load_project_directories<P>(assets: P) -> io::Result<Vec<PathBuf>>
 where
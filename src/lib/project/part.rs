@@ -0,0 +1,230 @@
+//! Importing and exporting parts of a project independently of one another.
+//!
+//! The state of a project is split into two parts:
+//!
+//! - The **layout**: the master parameters, the speakers along with their calibration and the
+//!   interaction inputs. These describe a particular venue.
+//! - The **content**: the installations, soundscape groups and sources. These describe the
+//!   soundscape that tours between venues.
+//!
+//! Exported parts are written as JSON with the same fields as a project's "state.json", so a part
+//! may be imported from either an exported file or directly from the state of another project.
+//! This allows for dropping touring content onto each venue's fixed calibration.
+
+use master::Master;
+use serde_json;
+use slug::slugify;
+use std::fs;
+use std::path::{Path, PathBuf};
+use super::{audio_directory, load_project_directories, project_state_path};
+use super::{InteractionInputs, Installations, Project, SoundscapeGroups, Sources, Speakers};
+use utils::{self, FileError};
+
+/// The assets sub-directory in which all exported parts are stored.
+const EXPORTS_DIRECTORY_STEM: &'static str = "exports";
+
+/// The extension used for exported parts.
+const EXPORT_EXTENSION: &'static str = "json";
+
+/// A part of a project's state that may be imported or exported independently.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Part {
+    /// The master parameters, speakers and interaction inputs.
+    Layout,
+    /// The installations, soundscape groups and sources.
+    Content,
+}
+
+/// A file from which a part may be imported.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportSource {
+    /// The path to the JSON file.
+    pub path: PathBuf,
+    /// Whether the file is the state of another project rather than an exported part.
+    pub is_project: bool,
+}
+
+// The layout of a project, borrowed for serialization.
+#[derive(Serialize)]
+struct LayoutRef<'a> {
+    master: &'a Master,
+    speakers: &'a Speakers,
+    interaction_inputs: &'a InteractionInputs,
+}
+
+// The content of a project, borrowed for serialization.
+#[derive(Serialize)]
+struct ContentRef<'a> {
+    installations: &'a Installations,
+    soundscape_groups: &'a SoundscapeGroups,
+    sources: &'a Sources,
+}
+
+#[derive(Deserialize)]
+struct Layout {
+    master: Master,
+    speakers: Speakers,
+    #[serde(default)]
+    interaction_inputs: InteractionInputs,
+}
+
+#[derive(Deserialize)]
+struct Content {
+    installations: Installations,
+    #[serde(default)]
+    soundscape_groups: SoundscapeGroups,
+    sources: Sources,
+}
+
+impl Part {
+    /// All parts in the order in which they are displayed.
+    pub const ALL: [Part; 2] = [Part::Layout, Part::Content];
+
+    /// The name of the part as used within file names and the GUI.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Part::Layout => "layout",
+            Part::Content => "content",
+        }
+    }
+}
+
+impl ImportSource {
+    /// The name of the project or exported file from which the part would be imported.
+    pub fn name(&self) -> String {
+        let path = match self.is_project {
+            true => self.path.parent().unwrap_or(&self.path),
+            false => &self.path,
+        };
+        path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("")
+            .to_string()
+    }
+}
+
+/// The path of the "assets/exports/" directory in which all exported parts are stored.
+pub fn exports_directory<P>(assets: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    assets.as_ref().join(EXPORTS_DIRECTORY_STEM)
+}
+
+/// The path to which the given part of the project with the given name is exported.
+pub fn export_path<P>(assets: P, project_name: &str, part: Part) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let stem = format!("{}-{}", slugify(project_name), part.name());
+    exports_directory(assets).join(stem).with_extension(EXPORT_EXTENSION)
+}
+
+/// Export the given part of the project to the "assets/exports/" directory.
+///
+/// Returns the path of the exported file.
+pub fn export<P>(
+    assets: P,
+    project: &Project,
+    part: Part,
+) -> Result<PathBuf, FileError<serde_json::Error>>
+where
+    P: AsRef<Path>,
+{
+    fs::create_dir_all(exports_directory(&assets))?;
+    let path = export_path(&assets, &project.name, part);
+    match part {
+        Part::Layout => {
+            let layout = LayoutRef {
+                master: &project.master,
+                speakers: &project.speakers,
+                interaction_inputs: &project.interaction_inputs,
+            };
+            utils::save_to_json(&path, &layout)?;
+        },
+        Part::Content => {
+            let content = ContentRef {
+                installations: &project.installations,
+                soundscape_groups: &project.soundscape_groups,
+                sources: &project.sources,
+            };
+            utils::save_to_json(&path, &content)?;
+        },
+    }
+    Ok(path)
+}
+
+/// All files from which the given part may be imported into the project with the given name.
+///
+/// This includes the state of every other project along with all exports of the part.
+pub fn import_sources<P>(assets: P, project_name: &str, part: Part) -> Vec<ImportSource>
+where
+    P: AsRef<Path>,
+{
+    let project_slug = slugify(project_name);
+    let mut sources: Vec<_> = load_project_directories(&assets)
+        .unwrap_or_else(|_| vec![])
+        .into_iter()
+        .filter(|dir| dir.file_name().and_then(|name| name.to_str()) != Some(&project_slug[..]))
+        .map(|dir| ImportSource { path: project_state_path(dir), is_project: true })
+        .collect();
+    let suffix = format!("-{}.{}", part.name(), EXPORT_EXTENSION);
+    if let Ok(entries) = fs::read_dir(exports_directory(&assets)) {
+        let mut exports: Vec<_> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .map(|name| name.ends_with(&suffix))
+                    .unwrap_or(false)
+            })
+            .map(|path| ImportSource { path, is_project: false })
+            .collect();
+        exports.sort_by(|a, b| a.path.cmp(&b.path));
+        sources.extend(exports);
+    }
+    sources
+}
+
+/// Replace the given part of the project with that of the JSON file at the given path.
+///
+/// The file may either be an exported part or the "state.json" of another project. Any speaker
+/// assignments to installations that no longer exist are removed. The project should be synced
+/// to all threads afterwards via `Project::reset_and_sync_all_threads`.
+pub fn import<A, P>(
+    assets: A,
+    project: &mut Project,
+    part: Part,
+    path: P,
+) -> Result<(), FileError<serde_json::Error>>
+where
+    A: AsRef<Path>,
+    P: AsRef<Path>,
+{
+    match part {
+        Part::Layout => {
+            let Layout { master, speakers, interaction_inputs } =
+                utils::load_from_json(path.as_ref())?;
+            project.master = master;
+            project.speakers = speakers;
+            project.interaction_inputs = interaction_inputs;
+        },
+        Part::Content => {
+            let Content { installations, soundscape_groups, sources } =
+                utils::load_from_json(path.as_ref())?;
+            project.installations = installations;
+            project.soundscape_groups = soundscape_groups;
+            project.sources = sources;
+            project.sources.prepare(audio_directory(assets));
+        },
+    }
+
+    // Speakers may only be assigned to the installations of the project.
+    let Project { ref mut state, .. } = *project;
+    let installations = &state.installations;
+    for speaker in state.speakers.values_mut() {
+        speaker.audio.installations.retain(|id| installations.contains_key(id));
+    }
+    Ok(())
+}
@@ -0,0 +1,233 @@
+//! Exporting the speaker layout and DBAP settings for use within acoustic simulation software.
+//!
+//! Two files are written alongside the exported parts within "assets/exports/":
+//!
+//! - `<project>-simulation.json`: the documented interchange schema below.
+//! - `<project>-simulation.csv`: one row per speaker, suitable for the loudspeaker import of tools
+//!   such as EASE or CATT-Acoustic via a spreadsheet.
+//!
+//! The JSON schema (version 1) looks like:
+//!
+//! ```text
+//! {
+//!   "schema": "audio_server.simulation",
+//!   "version": 1,
+//!   "project": "Beyond Perception",
+//!   "units": { "distance": "metres", "level": "dB", "delay": "ms", "angle": "degrees" },
+//!   "dbap": { "rolloff_db": 4.0, "proximity_limit": 7.0 },
+//!   "master_volume_db": -6.02,
+//!   "speakers": [
+//!     {
+//!       "name": "S1",
+//!       "channel": 1,
+//!       "position": { "x": 1.0, "y": 2.0, "z": 3.0 },
+//!       "orientation": { "azimuth": 0.0, "elevation": 0.0 },
+//!       "level_db": -3.0,
+//!       "delay_ms": 1.5,
+//!       "subwoofer": false,
+//!       "muted": false,
+//!       "installations": ["Waves"]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Channels are one-based to match the GUI. `x` and `y` are the speaker's position on the
+//! floorplan and `z` its height above the floor. The azimuth is the speaker's aim, anti-clockwise
+//! from the positive `x` axis. The elevation is always `0.0` and is intended to be refined within
+//! the simulation software. Speakers are ordered by channel.
+
+use serde_json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use super::Project;
+use super::part::exports_directory;
+use slug::slugify;
+use utils::{self, FileError};
+
+/// The name of the schema written to the JSON export.
+pub const SCHEMA: &'static str = "audio_server.simulation";
+
+/// The version of the JSON schema, incremented upon any incompatible change.
+pub const VERSION: u32 = 1;
+
+/// The stem suffix shared by both exported files.
+const FILE_SUFFIX: &'static str = "simulation";
+
+/// The header of the CSV export.
+const CSV_HEADER: &'static str =
+    "name,channel,x,y,z,azimuth,elevation,level_db,delay_ms,subwoofer,muted,installations";
+
+/// The speaker layout and DBAP settings of a project in the interchange schema.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Simulation {
+    pub schema: &'static str,
+    pub version: u32,
+    pub project: String,
+    pub units: Units,
+    pub dbap: Dbap,
+    pub master_volume_db: f32,
+    pub speakers: Vec<Speaker>,
+}
+
+/// The units used throughout the export.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Units {
+    pub distance: &'static str,
+    pub level: &'static str,
+    pub delay: &'static str,
+    pub angle: &'static str,
+}
+
+/// The DBAP settings of the project.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Dbap {
+    pub rolloff_db: f64,
+    /// The distance beyond which speakers are excluded from a sound's DBAP calculation.
+    pub proximity_limit: f64,
+}
+
+/// A single speaker of the project.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Speaker {
+    pub name: String,
+    pub channel: usize,
+    pub position: Position,
+    pub orientation: Orientation,
+    pub level_db: f32,
+    pub delay_ms: f64,
+    pub subwoofer: bool,
+    pub muted: bool,
+    pub installations: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub struct Orientation {
+    pub azimuth: f64,
+    pub elevation: f64,
+}
+
+impl Simulation {
+    /// Describe the speaker layout and DBAP settings of the given project.
+    pub fn from_project(project: &Project) -> Self {
+        let mut speakers: Vec<Speaker> = project
+            .speakers
+            .values()
+            .map(|speaker| {
+                let mut installations: Vec<String> = speaker
+                    .installations
+                    .iter()
+                    .filter_map(|id| project.installations.get(id))
+                    .map(|installation| installation.name.clone())
+                    .collect();
+                installations.sort();
+                Speaker {
+                    name: speaker.name.clone(),
+                    channel: speaker.channel + 1,
+                    position: Position {
+                        x: speaker.point.x.0,
+                        y: speaker.point.y.0,
+                        z: speaker.height.0,
+                    },
+                    orientation: Orientation {
+                        azimuth: speaker.aim_radians.to_degrees(),
+                        elevation: 0.0,
+                    },
+                    level_db: speaker.trim_db,
+                    delay_ms: speaker.delay.ms(),
+                    subwoofer: speaker.subwoofer,
+                    muted: speaker.muted,
+                    installations,
+                }
+            })
+            .collect();
+        speakers.sort_by(|a, b| a.channel.cmp(&b.channel).then_with(|| a.name.cmp(&b.name)));
+        Simulation {
+            schema: SCHEMA,
+            version: VERSION,
+            project: project.name.clone(),
+            units: Units { distance: "metres", level: "dB", delay: "ms", angle: "degrees" },
+            dbap: Dbap {
+                rolloff_db: project.master.dbap_rolloff_db,
+                proximity_limit: project.master.proximity_limit_2.0.sqrt(),
+            },
+            master_volume_db: 20.0 * project.master.volume.max(1e-6).log10(),
+            speakers,
+        }
+    }
+
+    /// Format the speakers as CSV with a header row.
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", CSV_HEADER);
+        for s in &self.speakers {
+            let row = format!(
+                "{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                csv_field(&s.name),
+                s.channel,
+                s.position.x,
+                s.position.y,
+                s.position.z,
+                s.orientation.azimuth,
+                s.orientation.elevation,
+                s.level_db,
+                s.delay_ms,
+                s.subwoofer,
+                s.muted,
+                csv_field(&s.installations.join(";")),
+            );
+            csv.push_str(&row);
+        }
+        csv
+    }
+}
+
+/// The paths to which the JSON and CSV exports of the project with the given name are written.
+pub fn export_paths<P>(assets: P, project_name: &str) -> (PathBuf, PathBuf)
+where
+    P: AsRef<Path>,
+{
+    let stem = format!("{}-{}", slugify(project_name), FILE_SUFFIX);
+    let path = exports_directory(assets).join(stem);
+    (path.with_extension("json"), path.with_extension("csv"))
+}
+
+/// Export the speaker layout and DBAP settings of the project to the "assets/exports/" directory.
+///
+/// Returns the paths of the JSON and CSV exports.
+pub fn export<P>(
+    assets: P,
+    project: &Project,
+) -> Result<(PathBuf, PathBuf), FileError<serde_json::Error>>
+where
+    P: AsRef<Path>,
+{
+    fs::create_dir_all(exports_directory(&assets))?;
+    let (json_path, csv_path) = export_paths(&assets, &project.name);
+    let simulation = Simulation::from_project(project);
+    utils::save_to_json(&json_path, &simulation)?;
+    utils::safe_file_save(&csv_path, simulation.to_csv().as_bytes())?;
+    Ok((json_path, csv_path))
+}
+
+// Quote the field if it contains a delimiter, quote or newline.
+fn csv_field(s: &str) -> String {
+    if s.contains(|c: char| c == ',' || c == '"' || c == '\n') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[test]
+fn test_csv_field() {
+    assert_eq!(csv_field("Left"), "Left");
+    assert_eq!(csv_field("Left, Upper"), "\"Left, Upper\"");
+    assert_eq!(csv_field("The \"Big\" One"), "\"The \"\"Big\"\" One\"");
+}
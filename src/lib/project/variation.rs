@@ -0,0 +1,229 @@
+//! Lightweight variations of a project, e.g. "Opening Night" or "School Visits".
+//!
+//! A variation inherits the state of a base project and stores only the parameters that it
+//! overrides within its "variation.json", as a JSON merge patch (RFC 7386) over the state of the
+//! base. Upon loading, the patch is applied to the latest saved state of the base so that edits to
+//! the base carry through to all of its variations. A variation may itself be the base of another.
+//!
+//! The fully resolved "state.json" of a variation is saved alongside its overrides so that the
+//! variation may still be listed, diffed, imported from and loaded should its base be removed.
+
+use serde_json::{self, Map, Value};
+use std::path::{Path, PathBuf};
+use utils;
+use super::{load_project_directories, project_state_path, projects_directory, State};
+
+/// The file stem of the overrides of a variation.
+const VARIATION_FILE_STEM: &'static str = "variation";
+
+/// The extension used for serializing and deserializing the overrides of a variation.
+const VARIATION_EXTENSION: &'static str = "json";
+
+/// The greatest depth to which variations may be nested, guarding against cycles.
+pub const MAX_DEPTH: usize = 8;
+
+/// The base of a variation and the parameters that the variation overrides.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Variation {
+    /// The slug of the project from which the variation inherits.
+    pub base: String,
+    /// A JSON merge patch over the state of the base.
+    #[serde(default = "empty_patch")]
+    pub overrides: Value,
+}
+
+impl Variation {
+    /// Describe the given state as a variation of the project with the given slug.
+    pub fn from_state<P>(assets: P, base: &str, state: &State) -> Result<Self, String>
+    where
+        P: AsRef<Path>,
+    {
+        let base_state = resolve_state(assets, base)?;
+        let state = serde_json::to_value(state).map_err(|err| format!("{}", err))?;
+        let overrides = patch(&base_state, &state);
+        Ok(Variation { base: base.to_string(), overrides })
+    }
+
+    /// Resolve the state of the variation by applying its overrides to the state of its base.
+    pub fn resolve<P>(&self, assets: P) -> Result<State, String>
+    where
+        P: AsRef<Path>,
+    {
+        let mut state = resolve_state(assets, &self.base)?;
+        merge(&mut state, &self.overrides);
+        serde_json::from_value(state).map_err(|err| format!("{}", err))
+    }
+}
+
+/// The file path for the "variation.json" file within the given project directory.
+pub fn variation_path<P>(project_directory: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    project_directory
+        .as_ref()
+        .join(VARIATION_FILE_STEM)
+        .with_extension(VARIATION_EXTENSION)
+}
+
+/// Load the variation within the given project directory.
+///
+/// Returns `None` if the project is not a variation or if its "variation.json" is invalid.
+pub fn load<P>(project_directory: P) -> Option<Variation>
+where
+    P: AsRef<Path>,
+{
+    let path = variation_path(project_directory);
+    if !path.exists() {
+        return None;
+    }
+    match utils::load_from_json(&path) {
+        Ok(variation) => Some(variation),
+        Err(err) => {
+            eprintln!("failed to load \"{}\": {}", path.display(), err);
+            None
+        },
+    }
+}
+
+/// Load the state of the project with the given slug as JSON, resolving it from its base if the
+/// project is a variation.
+pub fn resolve_state<P>(assets: P, slug: &str) -> Result<Value, String>
+where
+    P: AsRef<Path>,
+{
+    let mut slug = slug.to_string();
+    let mut patches = vec![];
+    for _ in 0..MAX_DEPTH {
+        let directory = projects_directory(&assets).join(&slug);
+        match load(&directory) {
+            Some(variation) => {
+                slug = variation.base;
+                patches.push(variation.overrides);
+            },
+            None => {
+                let path = project_state_path(&directory);
+                let mut state: Value = utils::load_from_json(&path)
+                    .map_err(|err| format!("failed to load \"{}\": {}", path.display(), err))?;
+                for patch in patches.iter().rev() {
+                    merge(&mut state, patch);
+                }
+                return Ok(state);
+            },
+        }
+    }
+    Err(format!("variations are nested deeper than {} projects", MAX_DEPTH))
+}
+
+/// Point all variations of the project with the slug `old` to the slug `new`, e.g. after the base
+/// project is renamed.
+pub fn rename_base<P>(assets: P, old: &str, new: &str)
+where
+    P: AsRef<Path>,
+{
+    let directories = match load_project_directories(assets) {
+        Ok(directories) => directories,
+        Err(err) => {
+            eprintln!("failed to load project directories: {}", err);
+            return;
+        },
+    };
+    for directory in directories {
+        let mut variation = match load(&directory) {
+            Some(ref variation) if variation.base == old => variation.clone(),
+            _ => continue,
+        };
+        variation.base = new.to_string();
+        if let Err(err) = utils::save_to_json(&variation_path(&directory), &variation) {
+            eprintln!("failed to update base of \"{}\": {}", directory.display(), err);
+        }
+    }
+}
+
+/// Apply the JSON merge patch to the target.
+///
+/// Objects are merged recursively, `null` removes a field and all other values are replaced.
+pub fn merge(target: &mut Value, patch: &Value) {
+    let patch = match *patch {
+        Value::Object(ref patch) => patch,
+        _ => {
+            *target = patch.clone();
+            return;
+        },
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let target = target.as_object_mut().expect("target is not an object");
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// Produce the JSON merge patch that transforms `base` into `current`.
+pub fn patch(base: &Value, current: &Value) -> Value {
+    match (base, current) {
+        (&Value::Object(ref base), &Value::Object(ref current)) => {
+            let mut patch = Map::new();
+            for (key, base_value) in base {
+                match current.get(key) {
+                    None => {
+                        patch.insert(key.clone(), Value::Null);
+                    },
+                    Some(value) => if value != base_value {
+                        patch.insert(key.clone(), self::patch(base_value, value));
+                    },
+                }
+            }
+            for (key, value) in current {
+                if !base.contains_key(key) && !value.is_null() {
+                    patch.insert(key.clone(), value.clone());
+                }
+            }
+            Value::Object(patch)
+        },
+        _ => current.clone(),
+    }
+}
+
+/// The number of parameters overridden by the given patch.
+pub fn count_overrides(patch: &Value) -> usize {
+    match *patch {
+        Value::Object(ref map) => map.values().map(count_overrides).sum(),
+        _ => 1,
+    }
+}
+
+fn empty_patch() -> Value {
+    Value::Object(Map::new())
+}
+
+#[test]
+fn test_patch_and_merge() {
+    let json = |s: &str| -> Value { serde_json::from_str(s).unwrap() };
+    let base = json(r#"{
+        "name": "Base",
+        "master": { "volume": 0.5, "limiter": { "enabled": true, "threshold": -1.0 } },
+        "speakers": { "0": { "name": "A" }, "1": { "name": "B" } }
+    }"#);
+    let current = json(r#"{
+        "name": "School Visits",
+        "master": { "volume": 0.3, "limiter": { "enabled": true, "threshold": -1.0 } },
+        "speakers": { "0": { "name": "A" } }
+    }"#);
+    let overrides = patch(&base, &current);
+    let expected = json(r#"{
+        "name": "School Visits",
+        "master": { "volume": 0.3 },
+        "speakers": { "1": null }
+    }"#);
+    assert_eq!(overrides, expected);
+    assert_eq!(count_overrides(&overrides), 3);
+    let mut resolved = base.clone();
+    merge(&mut resolved, &overrides);
+    assert_eq!(resolved, current);
+}
@@ -0,0 +1,133 @@
+//! Soundscape parameters driven by the visitor activity measured within each installation.
+//!
+//! Installations may report their activity, e.g. the number of visitors from a people counter or
+//! a motion level, via OSC at "<prefix>/activity". Each installation maps its latest reading onto
+//! a response between `0.0` and `1.0` via its input range and curve. The response scales the
+//! installation's target number of simultaneous sounds and may optionally drive the soundscape's
+//! intensity macro, which in turn scales the occurrence rate, simultaneous sounds and gain of
+//! every group via their intensity response.
+
+use utils::Range;
+
+/// The highest activity reading that may be selected via the GUI, e.g. a number of visitors.
+pub const MAX_INPUT: f32 = 200.0;
+
+/// The greatest scale that may be applied to an installation's density.
+pub const MAX_DENSITY: f32 = 4.0;
+
+/// The lowest exponent that may be applied to the response.
+pub const MIN_CURVE: f32 = 0.25;
+
+/// The highest exponent that may be applied to the response.
+pub const MAX_CURVE: f32 = 4.0;
+
+/// Maps the activity measured within an installation onto soundscape parameters.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Activity {
+    /// Whether or not activity readings affect the soundscape.
+    #[serde(default)]
+    pub enabled: bool,
+    /// The range of readings mapped onto the response, clamped beyond.
+    #[serde(default = "default::input")]
+    pub input: Range<f32>,
+    /// The exponent applied to the response, where values above `1.0` respond more slowly to
+    /// low readings.
+    #[serde(default = "default::curve")]
+    pub curve: f32,
+    /// The scale applied to the installation's simultaneous sounds at the minimum and at the
+    /// maximum input.
+    #[serde(default = "default::density")]
+    pub density: Range<f32>,
+    /// Whether or not readings drive the soundscape's intensity macro.
+    #[serde(default)]
+    pub drives_intensity: bool,
+    /// The intensity at the minimum and at the maximum input.
+    #[serde(default = "default::intensity")]
+    pub intensity: Range<f32>,
+}
+
+pub mod default {
+    use utils::Range;
+
+    pub fn input() -> Range<f32> {
+        Range { min: 0.0, max: 20.0 }
+    }
+
+    pub fn curve() -> f32 {
+        1.0
+    }
+
+    pub fn density() -> Range<f32> {
+        Range { min: 0.5, max: 2.0 }
+    }
+
+    pub fn intensity() -> Range<f32> {
+        Range { min: 0.2, max: 0.8 }
+    }
+}
+
+impl Activity {
+    /// The response to the given reading in the range `0.0` to `1.0`.
+    pub fn response(&self, reading: f32) -> f32 {
+        let span = self.input.max - self.input.min;
+        let t = match span > 0.0 {
+            true => ((reading - self.input.min) / span).max(0.0).min(1.0),
+            false => if reading >= self.input.min { 1.0 } else { 0.0 },
+        };
+        t.powf(self.curve.max(::std::f32::EPSILON))
+    }
+
+    /// The scale applied to the installation's simultaneous sounds for the given reading.
+    ///
+    /// Returns `1.0` while disabled or if no reading has been received.
+    pub fn density_at(&self, reading: Option<f32>) -> f32 {
+        match reading {
+            Some(reading) if self.enabled => lerp(self.density, self.response(reading)),
+            _ => 1.0,
+        }
+    }
+
+    /// The intensity macro driven by the given reading.
+    ///
+    /// Returns `None` if disabled or if the activity does not drive the intensity.
+    pub fn intensity_at(&self, reading: f32) -> Option<f32> {
+        if !self.enabled || !self.drives_intensity {
+            return None;
+        }
+        Some(lerp(self.intensity, self.response(reading)).max(0.0).min(1.0))
+    }
+}
+
+impl Default for Activity {
+    fn default() -> Self {
+        Activity {
+            enabled: false,
+            input: default::input(),
+            curve: default::curve(),
+            density: default::density(),
+            drives_intensity: false,
+            intensity: default::intensity(),
+        }
+    }
+}
+
+// Interpolate linearly across the given range.
+fn lerp(range: Range<f32>, t: f32) -> f32 {
+    range.min + (range.max - range.min) * t
+}
+
+#[test]
+fn test_activity() {
+    let mut activity = Activity::default();
+    assert_eq!(activity.density_at(Some(20.0)), 1.0);
+    assert_eq!(activity.intensity_at(20.0), None);
+    activity.enabled = true;
+    assert_eq!(activity.density_at(None), 1.0);
+    assert_eq!(activity.density_at(Some(-5.0)), 0.5);
+    assert_eq!(activity.density_at(Some(10.0)), 1.25);
+    assert_eq!(activity.density_at(Some(100.0)), 2.0);
+    activity.drives_intensity = true;
+    assert_eq!(activity.intensity_at(0.0), Some(0.2));
+    activity.curve = 2.0;
+    assert_eq!(activity.response(10.0), 0.25);
+}
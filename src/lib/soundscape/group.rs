@@ -2,6 +2,12 @@
 //!
 //! Soundscape groups allow for describing rules/constraints for multiple sounds at once.
 
+use audio;
+use metres::Metres;
+use soundscape::heatmap::Heatmap;
+use soundscape::modulation::Modulation;
+use soundscape::scene;
+use soundscape::schedule::Schedule;
 use time_calc::Ms;
 use utils::Range;
 
@@ -18,6 +24,88 @@ pub struct Id(pub usize);
 pub struct Group {
     pub occurrence_rate: Range<Ms>,
     pub simultaneous_sounds: Range<usize>,
+    /// Whether or not sounds from this group are silenced.
+    #[serde(default)]
+    pub muted: bool,
+    /// Whether or not this group is soloed.
+    ///
+    /// While any group is soloed, only sounds from soloed groups are spawned.
+    #[serde(default)]
+    pub soloed: bool,
+    /// How the group responds to the soundscape's intensity macro.
+    #[serde(default)]
+    pub intensity_response: IntensityResponse,
+    /// Scales the occurrence rate and simultaneous sounds throughout the day.
+    #[serde(default)]
+    pub schedule: Schedule,
+    /// The tendency of each of the group's sources to be followed by others within the group.
+    #[serde(default)]
+    pub transitions: Transitions,
+    /// Slowly varying noise scaling the group's occurrence rate, spread and volume.
+    #[serde(default)]
+    pub modulation: Modulation,
+    /// The minimum distance kept between the group's concurrently active sounds.
+    ///
+    /// Sounds are spawned clear of one another where possible and agents steer apart when closer.
+    #[serde(default)]
+    pub min_separation: Metres,
+    /// The level of the group within each of the soundscape's scenes.
+    #[serde(default)]
+    pub scene_levels: scene::Levels,
+    /// Weights the likelihood of the group's sounds spawning and moving about each cell of the
+    /// floorplan.
+    #[serde(default)]
+    pub heatmap: Heatmap,
+}
+
+/// The greatest minimum separation that may be assigned to a group via the GUI.
+pub const MAX_MIN_SEPARATION: Metres = Metres(10.0);
+
+/// The greatest weight that may be assigned to a transition.
+pub const MAX_TRANSITION_WEIGHT: f32 = 10.0;
+
+/// A Markov chain describing which of a group's sources tend to follow one another.
+///
+/// Once a sound is spawned from one of the group's sources, the next source of the group is picked
+/// from the available targets of that source's transitions in proportion to their weights. When
+/// the source has no transitions or none of its targets are available, the soundscape falls back
+/// to its regular selection.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Transitions {
+    #[serde(default)]
+    pub edges: Vec<Transition>,
+}
+
+/// The relative likelihood of a sound from one source being followed by a sound from another.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Transition {
+    pub from: audio::source::Id,
+    pub to: audio::source::Id,
+    pub weight: f32,
+}
+
+/// How a group responds to the soundscape's intensity macro.
+///
+/// Each range describes the scale applied at zero and at full intensity. The scale is
+/// interpolated geometrically so that ranges symmetric about `1.0` (e.g. `0.5` to `2.0`) leave the
+/// group untouched at the default intensity of `0.5` with a linear curve.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct IntensityResponse {
+    /// The scale applied to the rate at which sounds occur.
+    #[serde(default = "default::intensity_rate")]
+    pub rate: Range<f32>,
+    /// The scale applied to the minimum and maximum number of simultaneous sounds.
+    #[serde(default = "default::intensity_simultaneous")]
+    pub simultaneous: Range<f32>,
+    /// The scale applied to the gain of the group's sounds.
+    #[serde(default = "default::intensity_gain")]
+    pub gain: Range<f32>,
+    /// The exponent applied to the intensity before interpolating.
+    ///
+    /// Values below `1.0` respond more strongly at low intensities, values above `1.0` at high
+    /// intensities.
+    #[serde(default = "default::intensity_curve")]
+    pub curve: f32,
 }
 
 pub mod default {
@@ -28,15 +116,169 @@ pub mod default {
         max: Ms(HR_MS as _),
     };
     pub const SIMULTANEOUS_SOUNDS: Range<usize> = Range { min: 1, max: 10 };
+    /// The duration over which active sounds are faded out when their group is muted.
+    pub const MUTE_FADE_OUT: Ms = Ms(2_000.0);
+
+    pub fn intensity_rate() -> Range<f32> {
+        Range { min: 0.5, max: 2.0 }
+    }
+
+    pub fn intensity_simultaneous() -> Range<f32> {
+        Range { min: 0.5, max: 2.0 }
+    }
+
+    // Roughly -3dB to +3dB.
+    pub fn intensity_gain() -> Range<f32> {
+        Range { min: 0.71, max: 1.41 }
+    }
+
+    pub fn intensity_curve() -> f32 {
+        1.0
+    }
+}
+
+impl Group {
+    /// Whether or not sounds may be spawned from this group given the solo state of all groups.
+    pub fn is_audible(&self, any_soloed: bool) -> bool {
+        !self.muted && (!any_soloed || self.soloed)
+    }
+
+    /// The occurrence rate interval of the group at the given intensity and scheduled density.
+    ///
+    /// A higher rate shortens the interval between sounds.
+    pub fn occurrence_rate_at(&self, intensity: f32, density: f32) -> Range<Ms> {
+        let scale = self.intensity_response.scale(self.intensity_response.rate, intensity);
+        let scale = (scale * density).max(::std::f32::EPSILON) as f64;
+        Range {
+            min: Ms(self.occurrence_rate.min.ms() / scale),
+            max: Ms(self.occurrence_rate.max.ms() / scale),
+        }
+    }
+
+    /// The number of simultaneous sounds allowed by the group at the given intensity and scheduled
+    /// density.
+    pub fn simultaneous_sounds_at(&self, intensity: f32, density: f32) -> Range<usize> {
+        let response = &self.intensity_response;
+        let scale = (response.scale(response.simultaneous, intensity) * density).max(0.0);
+        let min = (self.simultaneous_sounds.min as f32 * scale).round() as usize;
+        let max = (self.simultaneous_sounds.max as f32 * scale).round() as usize;
+        Range { min, max: max.max(min) }
+    }
+
+    /// The gain applied to the group's sounds at the given intensity.
+    pub fn gain_at(&self, intensity: f32) -> f32 {
+        self.intensity_response.scale(self.intensity_response.gain, intensity)
+    }
+}
+
+impl Transitions {
+    /// The weight of the transition between the given sources, or `0.0` if there is none.
+    pub fn weight(&self, from: &audio::source::Id, to: &audio::source::Id) -> f32 {
+        self.edges
+            .iter()
+            .find(|t| t.from == *from && t.to == *to)
+            .map(|t| t.weight)
+            .unwrap_or(0.0)
+    }
+
+    /// Set the weight of the transition between the given sources.
+    ///
+    /// A weight of `0.0` or less removes the transition.
+    pub fn set(&mut self, from: audio::source::Id, to: audio::source::Id, weight: f32) {
+        self.edges.retain(|t| t.from != from || t.to != to);
+        if weight > 0.0 {
+            self.edges.push(Transition { from, to, weight });
+        }
+    }
+}
+
+impl IntensityResponse {
+    /// The scale within the given range at the given intensity.
+    pub fn scale(&self, range: Range<f32>, intensity: f32) -> f32 {
+        let t = intensity.max(0.0).min(1.0).powf(self.curve.max(::std::f32::EPSILON));
+        let min = range.min.max(::std::f32::EPSILON);
+        let max = range.max.max(::std::f32::EPSILON);
+        min * (max / min).powf(t)
+    }
+}
+
+impl Default for IntensityResponse {
+    fn default() -> Self {
+        IntensityResponse {
+            rate: default::intensity_rate(),
+            simultaneous: default::intensity_simultaneous(),
+            gain: default::intensity_gain(),
+            curve: default::intensity_curve(),
+        }
+    }
 }
 
 impl Default for Group {
     fn default() -> Self {
         let occurrence_rate = default::OCCURRENCE_RATE;
         let simultaneous_sounds = default::SIMULTANEOUS_SOUNDS;
+        let muted = false;
+        let soloed = false;
+        let intensity_response = Default::default();
+        let schedule = Default::default();
+        let transitions = Default::default();
+        let modulation = Default::default();
+        let min_separation = Default::default();
+        let scene_levels = Default::default();
+        let heatmap = Default::default();
         Group {
             occurrence_rate,
             simultaneous_sounds,
+            muted,
+            soloed,
+            intensity_response,
+            schedule,
+            transitions,
+            modulation,
+            min_separation,
+            scene_levels,
+            heatmap,
         }
     }
 }
+
+#[test]
+fn test_intensity_response() {
+    let response = IntensityResponse::default();
+    let rate = response.rate;
+    assert!((response.scale(rate, 0.5) - 1.0).abs() < 1e-6);
+    assert!((response.scale(rate, 0.0) - 0.5).abs() < 1e-6);
+    assert!((response.scale(rate, 1.0) - 2.0).abs() < 1e-6);
+    let group = Group {
+        occurrence_rate: Range { min: Ms(1_000.0), max: Ms(4_000.0) },
+        simultaneous_sounds: Range { min: 1, max: 4 },
+        muted: false,
+        soloed: false,
+        intensity_response: response,
+        schedule: Default::default(),
+        transitions: Default::default(),
+        modulation: Default::default(),
+        min_separation: Default::default(),
+        scene_levels: Default::default(),
+        heatmap: Default::default(),
+    };
+    assert_eq!(group.occurrence_rate_at(1.0, 1.0).max, Ms(2_000.0));
+    assert_eq!(group.simultaneous_sounds_at(1.0, 1.0), Range { min: 2, max: 8 });
+    assert_eq!(group.simultaneous_sounds_at(0.5, 1.0), group.simultaneous_sounds);
+    // The scheduled density scales the group along with the intensity.
+    assert_eq!(group.occurrence_rate_at(0.5, 2.0).max, Ms(2_000.0));
+    assert_eq!(group.simultaneous_sounds_at(0.5, 0.0), Range { min: 0, max: 0 });
+}
+
+#[test]
+fn test_transitions() {
+    let (a, b) = (audio::source::Id(0), audio::source::Id(1));
+    let mut transitions = Transitions::default();
+    transitions.set(a, b, 2.0);
+    assert_eq!(transitions.weight(&a, &b), 2.0);
+    assert_eq!(transitions.weight(&b, &a), 0.0);
+    transitions.set(a, b, 3.0);
+    assert_eq!(transitions.edges.len(), 1);
+    transitions.set(a, b, 0.0);
+    assert!(transitions.edges.is_empty());
+}
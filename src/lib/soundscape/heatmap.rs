@@ -0,0 +1,183 @@
+//! A spawn probability raster painted over the floorplan for a soundscape group.
+//!
+//! Spawning uniformly over an installation's area ignores sightlines and quiet corners. Each group
+//! may instead paint a weight onto a grid of square cells over the floorplan, where unpainted cells
+//! have a weight of `1.0`. Sounds from the group's sources are spawned and agents pick their
+//! targets in proportion to the weight beneath them relative to the heaviest cell. Sources
+//! belonging to several groups use the product of the groups' probabilities.
+
+use metres::Metres;
+use nannou::prelude::*;
+
+/// The width and height of each cell.
+pub const CELL_SIZE: Metres = Metres(0.5);
+
+/// The weight of cells that have not been painted.
+pub const DEFAULT_WEIGHT: f32 = 1.0;
+
+/// The greatest weight that may be painted onto a cell.
+pub const MAX_WEIGHT: f32 = 4.0;
+
+/// The greatest brush radius that may be selected via the GUI.
+pub const MAX_BRUSH_RADIUS: Metres = Metres(5.0);
+
+/// A raster of weights over the floorplan.
+///
+/// The raster grows to cover each painted cell. Weights are stored row by row from the bottom.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Heatmap {
+    /// The column and row of the raster's bottom left cell within the floorplan's grid.
+    #[serde(default)]
+    pub origin: [i64; 2],
+    /// The number of cells along each row.
+    #[serde(default)]
+    pub columns: usize,
+    /// The weight of each cell.
+    #[serde(default)]
+    pub weights: Vec<f32>,
+}
+
+impl Heatmap {
+    /// Whether or not any cells have been painted.
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    /// The number of rows within the raster.
+    pub fn rows(&self) -> usize {
+        match self.columns {
+            0 => 0,
+            columns => self.weights.len() / columns,
+        }
+    }
+
+    /// The weight of the cell beneath the given point.
+    pub fn weight_at(&self, point: Point2<Metres>) -> f32 {
+        self.index(cell(point))
+            .map(|i| self.weights[i])
+            .unwrap_or(DEFAULT_WEIGHT)
+    }
+
+    /// The likelihood of accepting a point, i.e. its weight relative to the heaviest cell.
+    pub fn probability_at(&self, point: Point2<Metres>) -> f32 {
+        if self.is_empty() {
+            return 1.0;
+        }
+        let max = self.weights.iter().cloned().fold(DEFAULT_WEIGHT, f32::max);
+        self.weight_at(point) / max
+    }
+
+    /// The centre of the cell at the given index within `weights`.
+    pub fn cell_centre(&self, index: usize) -> Point2<Metres> {
+        let column = self.origin[0] + (index % self.columns) as i64;
+        let row = self.origin[1] + (index / self.columns) as i64;
+        cell_centre([column, row])
+    }
+
+    /// Paint the given weight onto each cell whose centre lies within the radius of the point.
+    ///
+    /// The cell beneath the point is always painted.
+    pub fn paint(&mut self, point: Point2<Metres>, radius: Metres, weight: f32) {
+        let weight = weight.max(0.0).min(MAX_WEIGHT);
+        let r = radius.0.max(0.0);
+        let min = cell(Point2 { x: Metres(point.x.0 - r), y: Metres(point.y.0 - r) });
+        let max = cell(Point2 { x: Metres(point.x.0 + r), y: Metres(point.y.0 + r) });
+        self.grow(min, max);
+        let centre = cell(point);
+        for row in min[1]..max[1] + 1 {
+            for column in min[0]..max[0] + 1 {
+                let c = cell_centre([column, row]);
+                let distance = (c.x.0 - point.x.0).hypot(c.y.0 - point.y.0);
+                if distance <= r || [column, row] == centre {
+                    let index = self.index([column, row]).expect("cell outside of raster");
+                    self.weights[index] = weight;
+                }
+            }
+        }
+    }
+
+    /// Remove all painted cells.
+    pub fn clear(&mut self) {
+        *self = Default::default();
+    }
+
+    // The index of the given cell within `weights`, if the raster covers it.
+    fn index(&self, cell: [i64; 2]) -> Option<usize> {
+        let x = cell[0] - self.origin[0];
+        let y = cell[1] - self.origin[1];
+        if x < 0 || y < 0 || x >= self.columns as i64 || y >= self.rows() as i64 {
+            return None;
+        }
+        Some(y as usize * self.columns + x as usize)
+    }
+
+    // Extend the raster to cover the cells between `min` and `max` inclusive.
+    fn grow(&mut self, min: [i64; 2], max: [i64; 2]) {
+        let (origin, end) = match self.is_empty() {
+            true => (min, max),
+            false => {
+                let end = [
+                    self.origin[0] + self.columns as i64 - 1,
+                    self.origin[1] + self.rows() as i64 - 1,
+                ];
+                if min[0] >= self.origin[0] && min[1] >= self.origin[1]
+                    && max[0] <= end[0] && max[1] <= end[1]
+                {
+                    return;
+                }
+                let origin = [min[0].min(self.origin[0]), min[1].min(self.origin[1])];
+                (origin, [max[0].max(end[0]), max[1].max(end[1])])
+            },
+        };
+        let columns = (end[0] - origin[0] + 1) as usize;
+        let rows = (end[1] - origin[1] + 1) as usize;
+        let mut weights = vec![DEFAULT_WEIGHT; columns * rows];
+        for (i, &weight) in self.weights.iter().enumerate() {
+            let column = self.origin[0] + (i % self.columns) as i64 - origin[0];
+            let row = self.origin[1] + (i / self.columns) as i64 - origin[1];
+            weights[row as usize * columns + column as usize] = weight;
+        }
+        self.origin = origin;
+        self.columns = columns;
+        self.weights = weights;
+    }
+}
+
+// The column and row of the cell beneath the given point.
+fn cell(point: Point2<Metres>) -> [i64; 2] {
+    let column = (point.x.0 / CELL_SIZE.0).floor() as i64;
+    let row = (point.y.0 / CELL_SIZE.0).floor() as i64;
+    [column, row]
+}
+
+// The centre of the given cell.
+fn cell_centre(cell: [i64; 2]) -> Point2<Metres> {
+    let x = Metres((cell[0] as f64 + 0.5) * CELL_SIZE.0);
+    let y = Metres((cell[1] as f64 + 0.5) * CELL_SIZE.0);
+    Point2 { x, y }
+}
+
+#[test]
+fn test_heatmap() {
+    let p = |x, y| Point2 { x: Metres(x), y: Metres(y) };
+    let mut heatmap = Heatmap::default();
+    assert_eq!(heatmap.probability_at(p(1.0, 1.0)), 1.0);
+
+    // Painting a quiet corner leaves the rest of the floorplan untouched.
+    heatmap.paint(p(1.0, 1.0), Metres(0.0), 0.0);
+    assert_eq!(heatmap.weights.len(), 1);
+    assert_eq!(heatmap.weight_at(p(1.1, 1.1)), 0.0);
+    assert_eq!(heatmap.weight_at(p(5.0, 5.0)), DEFAULT_WEIGHT);
+
+    // The raster grows to cover new strokes while keeping earlier ones.
+    heatmap.paint(p(-2.0, 3.0), Metres(0.5), 2.0);
+    assert_eq!(heatmap.weight_at(p(1.1, 1.1)), 0.0);
+    assert_eq!(heatmap.weight_at(p(-2.0, 3.0)), 2.0);
+    assert_eq!(heatmap.probability_at(p(-2.0, 3.0)), 1.0);
+    assert_eq!(heatmap.probability_at(p(5.0, 5.0)), 0.5);
+    let centre = heatmap.cell_centre(0);
+    assert_eq!(heatmap.weight_at(centre), DEFAULT_WEIGHT);
+
+    heatmap.clear();
+    assert!(heatmap.is_empty());
+}
@@ -1,29 +1,59 @@
 use audio;
 use fxhash::{FxHashMap, FxHashSet};
+use hound;
 use installation;
+use interaction;
 use metres::Metres;
 use mindtree_utils::noise_walk;
 use nannou;
 use nannou::prelude::*;
 use nannou::rand::{Rng, SeedableRng};
+use num_cpus;
 use rand_xorshift::XorShiftRng;
+use rayon;
+use rayon::prelude::*;
 use std::cmp;
+use std::collections::VecDeque;
 use std::ops;
 use std::sync::atomic::{AtomicBool, AtomicUsize};
 use std::sync::{atomic, mpsc, Arc, Mutex};
 use std::thread;
 use std::time;
 use time_calc::Ms;
+use time_zone::TimeZone;
 use utils::{self, duration_to_secs, Range, Seed};
 
 pub use self::group::Group;
 pub use self::movement::Movement;
-use self::movement::BoundingRect;
 
+pub mod activity;
 pub mod group;
+pub mod heatmap;
+pub mod modulation;
 pub mod movement;
-
-const TICK_RATE_MS: u64 = 16;
+pub mod scene;
+pub mod schedule;
+pub mod simulation;
+pub mod tempo;
+
+/// The default interval at which the soundscape is stepped forward.
+pub const DEFAULT_TICK_RATE: Ms = Ms(16.0);
+/// The shortest interval that may be specified between soundscape ticks.
+pub const MIN_TICK_RATE: Ms = Ms(1.0);
+/// The longest interval that may be specified between soundscape ticks.
+pub const MAX_TICK_RATE: Ms = Ms(100.0);
+/// The default intensity of the soundscape, leaving all groups untouched with their default
+/// response.
+pub const DEFAULT_INTENSITY: f32 = 0.5;
+
+// The number of ticks over which the measured jitter is averaged.
+const JITTER_AVERAGE_TICKS: u64 = 32;
+
+// The number of candidate spawn points tried in search of one clear of the group's other sounds.
+const SPAWN_POINT_ATTEMPTS: usize = 16;
+
+// The longest chain of follow-ups spawned in turn, e.g. by a source that follows itself.
+const MAX_FOLLOW_UP_DEPTH: usize = 16;
 
 type Installations = FxHashMap<installation::Id, installation::Soundscape>;
 type Groups = FxHashMap<group::Id, Group>;
@@ -31,12 +61,20 @@ type Sources = FxHashMap<audio::source::Id, Source>;
 type Speakers = FxHashMap<audio::speaker::Id, Speaker>;
 type GroupsLastUsed = FxHashMap<group::Id, time::Instant>;
 type SourcesLastUsed = FxHashMap<audio::source::Id, time::Instant>;
+type SourcesLastEnded = FxHashMap<audio::source::Id, time::Instant>;
+type RecentSpawns = VecDeque<audio::source::Id>;
+type GroupsLastSource = FxHashMap<group::Id, audio::source::Id>;
+type GroupModulations = FxHashMap<group::Id, modulation::Scales>;
 type InstallationAreas = FxHashMap<installation::Id, movement::Area>;
 type InstallationSpeakers = FxHashMap<installation::Id, Vec<audio::speaker::Id>>;
 type ActiveSounds = FxHashMap<audio::sound::Id, ActiveSound>;
+type Interactions = FxHashMap<interaction::TrackId, TrackedInteraction>;
+type Followers = FxHashMap<(audio::source::Id, interaction::TrackId), Follower>;
+type PendingFollowUps = Vec<PendingFollowUp>;
 type ActiveSoundPositions = FxHashMap<audio::sound::Id, ActiveSoundPosition>;
 type ActiveSoundsPerInstallation = FxHashMap<installation::Id, Vec<audio::sound::Id>>;
 type TargetSoundsPerInstallation = FxHashMap<installation::Id, usize>;
+type ActivityReadings = FxHashMap<installation::Id, f32>;
 type AvailableGroups = Vec<AvailableGroup>;
 type AvailableSources = Vec<AvailableSource>;
 
@@ -80,6 +118,12 @@ pub struct Soundscape {
     thread: Arc<Mutex<Option<thread::JoinHandle<()>>>>,
     /// Whether or not the soundscape is currently playing.
     is_playing: Arc<AtomicBool>,
+    /// The interval between ticks in microseconds, read by the ticker thread before each tick.
+    tick_rate_us: Arc<AtomicUsize>,
+    /// The average duration in microseconds by which ticks have missed their deadline.
+    tick_jitter_us: Arc<AtomicUsize>,
+    /// The state of the tempo clock's Link session, if any.
+    tempo: Arc<tempo::Shared>,
 }
 
 /// Data related to a single speaker that is relevant to the soundscape.
@@ -92,13 +136,21 @@ pub struct Speaker {
 }
 
 /// Properties of an audio source that are relevant to the soundscape thread.
+#[derive(Clone)]
 pub struct Source {
     pub constraints: audio::source::Soundscape,
     pub kind: audio::source::Kind,
     pub spread: Metres,
     pub channel_radians: f32,
+    pub height: Metres,
     pub volume: f32,
     pub muted: bool,
+    pub enabled: bool,
+    pub effects: audio::effect::Effects,
+    pub reverb_send: f32,
+    pub distance_model: bool,
+    pub dbap_rolloff_db: Option<f64>,
+    pub speaker_filter: audio::speaker::Filter,
     /// The time at which the source was last used to create a sound.
     pub last_sound_created: Option<time::Instant>,
 }
@@ -111,6 +163,50 @@ pub struct ActiveSound {
     pub movement: Movement,
     /// The handle associated with this sound.
     handle: audio::sound::Handle,
+    /// The modulation scales most recently sent to the audio output thread.
+    modulation: modulation::Scales,
+    /// The number of follow-ups in the chain ending with this sound, or `0` if not a follow-up.
+    follow_up_depth: usize,
+}
+
+// The latest interaction received for a tracked entity.
+struct TrackedInteraction {
+    interaction: interaction::Interaction,
+    // The moment at which the entity was first seen.
+    first_seen: time::Instant,
+}
+
+// A sound spawned to follow a single tracked visitor.
+struct Follower {
+    // The active sound following the visitor.
+    sound_id: audio::sound::Id,
+    // The moment at which the visitor was lost, if they are not currently tracked.
+    lost_since: Option<time::Instant>,
+}
+
+// The state shared between the pool threads while updating the movement of active sounds.
+struct MovementContext<'a> {
+    since_last_tick: &'a time::Duration,
+    sources: &'a Sources,
+    groups: &'a Groups,
+    installations: &'a Installations,
+    installation_areas: &'a InstallationAreas,
+    target_sounds_per_installation: &'a TargetSoundsPerInstallation,
+    active_sound_positions: &'a ActiveSoundPositions,
+}
+
+// A follow-up sound awaiting its delay after the sound that triggered it ended.
+struct PendingFollowUp {
+    // The source from which the follow-up is spawned.
+    source_id: audio::source::Id,
+    // The last position of the sound that ended.
+    point: Point2<Metres>,
+    // The distance from the point within which the follow-up is spawned.
+    radius: Metres,
+    // The playback duration of the soundscape at which the follow-up is due.
+    due: time::Duration,
+    // The number of follow-ups in the chain ending with this one.
+    depth: usize,
 }
 
 // The current positioning of an active sound.
@@ -127,6 +223,11 @@ pub struct Model {
     frame_count: Arc<AtomicUsize>,
     /// The latency applied to realtime sounds when spawned.
     pub realtime_source_latency: Ms,
+    /// The intensity macro in the range `0.0` to `1.0`, applied to all groups via their
+    /// `IntensityResponse`.
+    intensity: f32,
+    /// The time zone of the venue, against which sources' day gain curves are evaluated.
+    pub time_zone: TimeZone,
     /// The soundscape's deterministic source of randomness.
     seed: Seed,
     /// For generating unique IDs for each new sound.
@@ -137,6 +238,8 @@ pub struct Model {
     playback_duration: time::Duration,
     /// All installations within the exhibition.
     installations: Installations,
+    /// The latest activity reading received for each installation.
+    activity_readings: ActivityReadings,
     /// Constraints for collections of sources.
     groups: Groups,
     /// All sources available to the soundscape for producing audio.
@@ -147,8 +250,34 @@ pub struct Model {
     groups_last_used: GroupsLastUsed,
     /// The moment at which each `Source` was last used to spawn a sound.
     sources_last_used: SourcesLastUsed,
+    /// The moment at which the latest sound of each `Source` ended.
+    sources_last_ended: SourcesLastEnded,
+    /// The sources of the most recently spawned sounds, oldest first.
+    ///
+    /// Holds at most `audio::source::MAX_NO_REPEAT_SPAWNS` sources.
+    recent_spawns: RecentSpawns,
+    /// The source of the sound most recently spawned from each `Group`.
+    ///
+    /// Used to follow each group's `Transitions`.
+    groups_last_source: GroupsLastSource,
+    /// The scales applied by the noise modulation of each `Group` with any modulators enabled.
+    ///
+    /// This is updated upon each `Tick`.
+    group_modulations: GroupModulations,
+    /// The crossfade of each `Group`'s level towards the most recently cued scene.
+    scene: scene::Crossfade,
+    /// The tempo to whose beats or bars spawns are quantised, if enabled.
+    tempo: tempo::Clock,
     /// All sounds currently being played that were spawned by the soundscape thread.
     active_sounds: ActiveSounds,
+    /// The latest position of each entity tracked by the interaction inputs, mapped onto the
+    /// floorplan.
+    interactions: Interactions,
+    /// The sounds following each tracked visitor, keyed by the source from which they were
+    /// spawned and the visitor that they follow.
+    followers: Followers,
+    /// The follow-up sounds due to spawn once the playback duration reaches their delay.
+    pending_follow_ups: PendingFollowUps,
 
     // The following are intermediary buffers re-used between calculations.
     //
@@ -176,11 +305,13 @@ pub struct Model {
     /// A handle to the wav reader thread.
     wav_reader: audio::source::wav::reader::Handle,
     /// A handle for submitting new sounds to the input stream.
-    audio_input_stream: audio::input::Stream,
+    audio_input_stream: audio::input::Streams,
     /// A handle for submitting new sounds to the output stream.
     audio_output_stream: audio::output::Stream,
     // A handle to the ticker thread.
     _tick_thread: thread::JoinHandle<()>,
+    // The pool across which per-installation work is split during each tick.
+    pool: rayon::ThreadPool,
 }
 
 // Data related to the suitability of a group or source for selection of use within the soundscape.
@@ -228,6 +359,8 @@ struct AvailableSource {
     id: audio::source::Id,
     // Params that describe the suitability of the source for use with a sound.
     suitability: Suitability,
+    // The relative likelihood of the source being picked among equally suitable sources.
+    weight: f32,
     // Ranges used to trigger playback.
     playback_duration: Range<Ms>,
     attack_duration: Range<Ms>,
@@ -272,16 +405,30 @@ impl Source {
         let kind = source.kind.clone();
         let spread = source.spread;
         let channel_radians = source.channel_radians;
+        let height = source.height;
         let volume = source.volume;
         let muted = source.muted;
+        let enabled = source.enabled;
+        let effects = source.effects;
+        let reverb_send = source.reverb_send;
+        let distance_model = source.distance_model;
+        let dbap_rolloff_db = source.dbap_rolloff_db;
+        let speaker_filter = source.speaker_filter.clone();
         let last_sound_created = None;
         Some(Source {
             constraints,
             kind,
             spread,
             channel_radians,
+            height,
             volume,
             muted,
+            enabled,
+            effects,
+            reverb_send,
+            distance_model,
+            dbap_rolloff_db,
+            speaker_filter,
             last_sound_created,
         })
     }
@@ -292,15 +439,29 @@ impl Source {
         let role = Some(audio::source::Role::Soundscape(self.constraints.clone()));
         let spread = self.spread;
         let channel_radians = self.channel_radians;
+        let height = self.height;
         let volume = self.volume;
         let muted = self.muted;
+        let enabled = self.enabled;
+        let effects = self.effects;
+        let reverb_send = self.reverb_send;
+        let distance_model = self.distance_model;
+        let dbap_rolloff_db = self.dbap_rolloff_db;
+        let speaker_filter = self.speaker_filter.clone();
         audio::Source {
             kind,
             role,
             spread,
             channel_radians,
+            height,
             volume,
             muted,
+            enabled,
+            effects,
+            reverb_send,
+            distance_model,
+            dbap_rolloff_db,
+            speaker_filter,
         }
     }
 }
@@ -319,6 +480,26 @@ impl Soundscape {
         Ok(())
     }
 
+    /// Set the interval at which the soundscape is stepped forward.
+    ///
+    /// The interval is clamped to the `MIN_TICK_RATE` and `MAX_TICK_RATE`.
+    pub fn set_tick_rate(&self, rate: Ms) {
+        let ms = rate.ms().max(MIN_TICK_RATE.ms()).min(MAX_TICK_RATE.ms());
+        let us = (ms * 1_000.0).round() as usize;
+        self.tick_rate_us.store(us, atomic::Ordering::Relaxed);
+    }
+
+    /// The average duration by which recent ticks have missed their deadline.
+    pub fn tick_jitter(&self) -> Ms {
+        let us = self.tick_jitter_us.load(atomic::Ordering::Relaxed);
+        Ms(us as f64 / 1_000.0)
+    }
+
+    /// The state of the Ableton Link session joined by the tempo clock, if any.
+    pub fn link_status(&self) -> Option<tempo::LinkStatus> {
+        self.tempo.link_status()
+    }
+
     /// Whether or not the soundscape is currently playing.
     pub fn is_playing(&self) -> bool {
         self.is_playing.load(atomic::Ordering::Relaxed)
@@ -385,9 +566,17 @@ impl Model {
             source.installations.remove(id);
         }
 
+        self.activity_readings.remove(id);
         self.installations.remove(id)
     }
 
+    /// Record the latest activity reading of the given installation, e.g. a number of visitors.
+    ///
+    /// The installation's density responds from the next tick.
+    pub fn set_activity_reading(&mut self, installation: installation::Id, reading: f32) {
+        self.activity_readings.insert(installation, reading);
+    }
+
     /// Update the given installation's state.
     ///
     /// Returns `false` if the installation was not there.
@@ -404,6 +593,31 @@ impl Model {
         }
     }
 
+    /// Update the latest interaction received for its tracked entity.
+    pub fn update_interaction(&mut self, interaction: interaction::Interaction) {
+        let track = interaction.track_id();
+        let first_seen = self.interactions
+            .get(&track)
+            .map(|tracked| tracked.first_seen)
+            .unwrap_or_else(time::Instant::now);
+        let tracked = TrackedInteraction { interaction, first_seen };
+        self.interactions.insert(track, tracked);
+    }
+
+    /// Remove the tracked entity with the given `TrackId`, e.g. once it has been lost.
+    pub fn remove_interaction(
+        &mut self,
+        track: &interaction::TrackId,
+    ) -> Option<interaction::Interaction>
+    {
+        self.interactions.remove(track).map(|tracked| tracked.interaction)
+    }
+
+    /// Remove all entities tracked by the given input.
+    pub fn remove_interaction_input(&mut self, input: &interaction::Id) {
+        self.interactions.retain(|track, _| track.input != *input);
+    }
+
     /// Insert a new soundscape group.
     pub fn insert_group(&mut self, id: group::Id, group: Group) -> Option<Group> {
         self.groups.insert(id, group)
@@ -425,11 +639,76 @@ impl Model {
         }
     }
 
+    /// Set the intensity macro, clamped to the range `0.0` to `1.0`.
+    ///
+    /// The gain of all active sounds is updated immediately while the occurrence rates and
+    /// simultaneous sound limits take effect from the next tick.
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity.max(0.0).min(1.0);
+        self.update_intensity_gains();
+    }
+
+    /// Crossfade the level of each group towards its level within the given scene.
+    ///
+    /// The crossfade progresses with each tick while the soundscape is playing.
+    pub fn crossfade_to_scene(&mut self, scene: String, secs: f64) {
+        self.scene.start(scene, secs, &self.groups);
+    }
+
+    /// Set the tempo to whose beats or bars spawns are quantised.
+    pub fn set_tempo(&mut self, params: tempo::Params) {
+        self.tempo.set_params(params);
+    }
+
+    /// Send the gain of each active sound at the current intensity to the audio output thread.
+    ///
+    /// This should be called after updating the `intensity_response` of any group or the groups
+    /// of any source.
+    pub fn update_intensity_gains(&self) {
+        for (&sound_id, sound) in self.active_sounds.iter() {
+            let gain = match self.sources.get(&sound.handle.source_id()) {
+                Some(source) => intensity_gain(source, &self.groups, self.intensity),
+                None => continue,
+            };
+            send_intensity_gain(&self.audio_output_stream, sound_id, gain);
+        }
+    }
+
+    /// Mutable access to all soundscape groups.
+    pub fn groups_mut(&mut self) -> impl Iterator<Item = &mut Group> {
+        self.groups.values_mut()
+    }
+
     /// Remove the given soundscape group.
     pub fn remove_group(&mut self, id: &group::Id) -> Option<Group> {
         self.groups.remove(id)
     }
 
+    /// Fade out all active sounds whose sources have been disabled or silenced by a group mute or
+    /// solo.
+    ///
+    /// This should be called after updating the `muted` or `soloed` state of any group or the
+    /// `enabled` state of any source.
+    pub fn fade_out_inaudible_sounds(&mut self) {
+        let fade_out_frames = group::default::MUTE_FADE_OUT.to_samples(audio::sample_rate());
+        for (&sound_id, sound) in self.active_sounds.iter() {
+            let is_audible = self.sources
+                .get(&sound.handle.source_id())
+                .map(|source| source_is_audible(source, &self.groups))
+                .unwrap_or(true);
+            if is_audible {
+                continue;
+            }
+            self.audio_output_stream
+                .send(move |audio| {
+                    audio.update_sound(&sound_id, move |sound| {
+                        sound.signal.fade_out(fade_out_frames);
+                    });
+                })
+                .expect("failed to send sound fade out to audio output thread");
+        }
+    }
+
     /// Insert a speaker into the inner map.
     pub fn insert_speaker(&mut self, id: audio::speaker::Id, speaker: Speaker) -> Option<Speaker> {
         self.speakers.insert(id, speaker)
@@ -493,8 +772,10 @@ impl Model {
             seed,
             ref playback_duration,
             ref sources,
+            ref groups,
             ref speakers,
             ref installations,
+            ref activity_readings,
             ref mut active_sounds,
 
             // Intermediary buffers.
@@ -502,17 +783,25 @@ impl Model {
             ref mut installation_areas,
             ref mut target_sounds_per_installation,
             ref mut active_sound_positions,
+            time_zone,
             ..
         } = *self;
 
         // Collect the necessary data for generating a `Movement` instance from the constraints.
         update_installation_speakers(speakers, installation_speakers);
-        update_installation_areas(speakers, installation_speakers, installation_areas);
+        update_installation_areas(
+            speakers,
+            installations,
+            installation_speakers,
+            installation_areas,
+        );
         update_target_sounds_per_installation(
             seed,
             playback_duration,
             installations,
             &installation_areas,
+            activity_readings,
+            time_zone.now_secs_of_day(),
             target_sounds_per_installation,
         );
         update_active_sound_positions(active_sounds, active_sound_positions);
@@ -532,6 +821,7 @@ impl Model {
                     let movement = generate_movement(
                         *source_id,
                         sources,
+                        groups,
                         installation,
                         installations,
                         &installation_areas,
@@ -553,8 +843,39 @@ impl Model {
     }
 
     /// Remove an active sound from the hashmap.
+    ///
+    /// The moment is recorded as the end of the sound's source's latest playback and each of the
+    /// source's follow-ups is rolled for spawning near the sound's last position, unless the
+    /// sound ends a chain of `MAX_FOLLOW_UP_DEPTH` follow-ups.
     pub fn remove_active_sound(&mut self, id: &audio::sound::Id) -> Option<ActiveSound> {
-        self.active_sounds.remove(id)
+        let sound = self.active_sounds.remove(id);
+        if let Some(ref sound) = sound {
+            let source_id = sound.source_id();
+            self.sources_last_ended.insert(source_id, time::Instant::now());
+            let depth = sound.follow_up_depth + 1;
+            let source = match depth > MAX_FOLLOW_UP_DEPTH {
+                true => None,
+                false => self.sources.get(&source_id),
+            };
+            if let Some(source) = source {
+                let mut rng = nannou::rand::thread_rng();
+                for follow_up in &source.follow_ups {
+                    if rng.gen::<f32>() >= follow_up.probability {
+                        continue;
+                    }
+                    let delay = audio::source::random_playback_duration(&mut rng, follow_up.delay);
+                    let delay = time::Duration::from_millis(delay.ms().max(0.0) as u64);
+                    self.pending_follow_ups.push(PendingFollowUp {
+                        source_id: follow_up.source,
+                        point: sound.position().point,
+                        radius: follow_up.radius,
+                        due: self.playback_duration + delay,
+                        depth,
+                    });
+                }
+            }
+        }
+        sound
     }
 
     /// Update the state of all active sounds spawned via the source with the given `Id`.
@@ -583,12 +904,21 @@ impl Model {
     /// This is called when we switch between projects within the GUI.
     pub fn clear_project_specific_data(&mut self) {
         self.installations.clear();
+        self.activity_readings.clear();
         self.groups.clear();
         self.sources.clear();
         self.speakers.clear();
         self.groups_last_used.clear();
         self.sources_last_used.clear();
+        self.sources_last_ended.clear();
+        self.recent_spawns.clear();
+        self.groups_last_source.clear();
+        self.group_modulations.clear();
+        self.scene = Default::default();
         self.active_sounds.clear();
+        self.interactions.clear();
+        self.followers.clear();
+        self.pending_follow_ups.clear();
         self.installation_speakers.clear();
         self.installation_areas.clear();
         self.target_sounds_per_installation.clear();
@@ -670,23 +1000,57 @@ pub fn spawn(
     tx: mpsc::Sender<Message>,
     rx: mpsc::Receiver<Message>,
     wav_reader: audio::source::wav::reader::Handle,
-    audio_input_stream: audio::input::Stream,
+    audio_input_stream: audio::input::Streams,
     audio_output_stream: audio::output::Stream,
     sound_id_gen: audio::sound::IdGenerator,
 ) -> Soundscape {
     let is_playing = Arc::new(AtomicBool::new(true));
+    let default_tick_rate_us = (DEFAULT_TICK_RATE.ms() * 1_000.0) as usize;
+    let tick_rate_us = Arc::new(AtomicUsize::new(default_tick_rate_us));
+    let tick_jitter_us = Arc::new(AtomicUsize::new(0));
+    let tempo_shared = Arc::new(tempo::Shared::default());
 
     // Spawn a thread to generate and send ticks.
+    //
+    // Rather than sleeping for a fixed duration, the ticker sleeps until the deadline of the next
+    // tick so that the time spent sending each tick does not accumulate as drift.
     let tick_tx = tx.clone();
     let tick_is_playing = is_playing.clone();
+    let ticker_rate_us = tick_rate_us.clone();
+    let ticker_jitter_us = tick_jitter_us.clone();
     let _tick_thread = thread::Builder::new()
         .name("soundscape_ticker".into())
         .spawn(move || {
             let mut last = time::Instant::now();
+            let mut deadline = last;
+            let mut jitter_us = 0;
             let mut playback_duration = time::Duration::from_secs(0);
             loop {
-                thread::sleep(time::Duration::from_millis(TICK_RATE_MS));
+                let rate_us = ticker_rate_us.load(atomic::Ordering::Relaxed) as u64;
+                let interval = time::Duration::from_micros(rate_us);
+                deadline += interval;
+                let now = time::Instant::now();
+                if deadline > now {
+                    thread::sleep(deadline - now);
+                }
                 let instant = time::Instant::now();
+
+                // Measure how late this tick is relative to its deadline.
+                let lateness = match instant > deadline {
+                    true => instant - deadline,
+                    false => time::Duration::from_secs(0),
+                };
+                let lateness_us = lateness.as_secs() * 1_000_000 + lateness.subsec_micros() as u64;
+                jitter_us = (jitter_us * (JITTER_AVERAGE_TICKS - 1) + lateness_us)
+                    / JITTER_AVERAGE_TICKS;
+                ticker_jitter_us.store(jitter_us as usize, atomic::Ordering::Relaxed);
+
+                // If we have fallen behind by more than a whole tick (e.g. the system was
+                // suspended), start again from now rather than sending a burst of ticks.
+                if lateness > interval {
+                    deadline = instant;
+                }
+
                 let since_last_tick = instant.duration_since(last);
                 last = instant;
                 if !tick_is_playing.load(atomic::Ordering::Relaxed) {
@@ -707,33 +1071,65 @@ pub fn spawn(
 
     // The model maintaining state between messages.
     let realtime_source_latency = audio::DEFAULT_REALTIME_SOURCE_LATENCY;
+    let intensity = DEFAULT_INTENSITY;
+    let time_zone = TimeZone::UTC;
     let playback_duration = time::Duration::from_secs(0);
     let installations = Default::default();
+    let activity_readings = Default::default();
     let groups = Default::default();
     let sources = Default::default();
     let speakers = Default::default();
     let active_sounds = Default::default();
+    let interactions = Default::default();
+    let followers = Default::default();
+    let pending_follow_ups = Default::default();
     let installation_speakers = Default::default();
     let installation_areas = Default::default();
     let groups_last_used = Default::default();
     let sources_last_used = Default::default();
+    let sources_last_ended = Default::default();
+    let recent_spawns = Default::default();
+    let groups_last_source = Default::default();
+    let group_modulations = Default::default();
+    let scene = Default::default();
+    let tempo = tempo::Clock::new(tempo_shared.clone());
     let target_sounds_per_installation = Default::default();
     let active_sound_positions = Default::default();
     let active_sounds_per_installation = Default::default();
     let available_groups = Default::default();
     let available_sources = Default::default();
+
+    // The pool used to split the per-installation work of each tick across threads.
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_cpus::get())
+        .thread_name(|i| format!("soundscape_worker_{}", i))
+        .build()
+        .expect("failed to build the soundscape thread pool");
+
     let model = Model {
         frame_count,
         realtime_source_latency,
+        intensity,
+        time_zone,
         seed,
         playback_duration,
         installations,
+        activity_readings,
         groups,
         sources,
         speakers,
         active_sounds,
+        interactions,
+        followers,
+        pending_follow_ups,
         groups_last_used,
         sources_last_used,
+        sources_last_ended,
+        recent_spawns,
+        groups_last_source,
+        group_modulations,
+        scene,
+        tempo,
         installation_speakers,
         installation_areas,
         target_sounds_per_installation,
@@ -746,6 +1142,7 @@ pub fn spawn(
         audio_output_stream,
         sound_id_gen,
         _tick_thread,
+        pool,
     };
 
     // Spawn the soundscape thread.
@@ -758,6 +1155,9 @@ pub fn spawn(
         tx,
         thread,
         is_playing,
+        tick_rate_us,
+        tick_jitter_us,
+        tempo: tempo_shared,
     }
 }
 
@@ -772,8 +1172,12 @@ fn run(mut model: Model, msg_rx: mpsc::Receiver<Message>) {
             // Break from the loop and finish the thread.
             Message::Exit => break,
 
-            // Step forward the state of the soundscape.
-            Message::Tick(t) => tick(&mut model, t),
+            // Step forward the state of the soundscape, no longer spawning from any WAVs that
+            // could not be opened.
+            Message::Tick(t) => {
+                let missing_wavs = tick(&mut model, t);
+                mark_wavs_missing(&mut model.sources, &missing_wavs);
+            },
 
             // Play all active sounds.
             Message::Play => {
@@ -860,6 +1264,10 @@ fn update_active_sounds_per_installation(
         installation_active_sounds.clear();
     }
     for (&sound_id, sound) in active_sound_positions.iter() {
+        // Sounds following visitors do not count towards an installation's target.
+        if sources.get(&sound.source_id).map(|s| s.follow.enabled).unwrap_or(false) {
+            continue;
+        }
         if let Some(inst) = closest_assigned_installation(sound, sources, installation_areas) {
             active_sounds_per_installation
                 .entry(inst)
@@ -946,6 +1354,7 @@ fn agent_installation_data(
 fn generate_movement(
     source_id: audio::source::Id,
     sources: &Sources,
+    groups: &Groups,
     installation: installation::Id,
     installations: &Installations,
     installation_areas: &InstallationAreas,
@@ -961,7 +1370,8 @@ fn generate_movement(
             let y = area.bounding_rect.bottom + area.bounding_rect.height() * pos.y;
             let point = pt2(x, y);
             let radians = 0.0;
-            let position = audio::sound::Position { point, radians };
+            let height = Metres(0.0);
+            let position = audio::sound::Position { point, radians, height };
             Movement::Fixed(position)
         },
         audio::source::Movement::Generative(ref gen) => match *gen {
@@ -984,6 +1394,7 @@ fn generate_movement(
                     &target_sounds_per_installation,
                     &active_sound_positions,
                 );
+                let source = &sources[&source_id];
                 let agent = movement::Agent::generate(
                     rng,
                     installation,
@@ -992,6 +1403,7 @@ fn generate_movement(
                     max_force,
                     max_rotation,
                     agent.directional,
+                    |p| heatmap_probability(source, groups, p),
                 );
                 let generative = movement::Generative::Agent(agent);
                 let movement = Movement::Generative(generative);
@@ -1023,6 +1435,19 @@ fn generate_movement(
                 movement
             }
         },
+        // Paths without points fall back to the centre of the installation.
+        audio::source::Movement::Path(ref path) => match movement::Path::new(path) {
+            Some(path) => Movement::Path(path),
+            None => {
+                let area = installation_areas
+                    .get(&installation)
+                    .expect("no area for the given installation");
+                let point = area.centroid;
+                let radians = 0.0;
+                let height = Metres(0.0);
+                Movement::Fixed(audio::sound::Position { point, radians, height })
+            },
+        },
     }
 }
 
@@ -1054,26 +1479,24 @@ fn update_installation_speakers(
 
 // Update the map from installations to their areas.
 //
-// An installations `Area` is determined via the assigned speaker locations.
+// An installations `Area` is determined via the convex hull of the assigned speaker locations,
+// expanded by the installation's margin.
 fn update_installation_areas(
     speakers: &Speakers,
+    installations: &Installations,
     installation_speakers: &InstallationSpeakers,
     installation_areas: &mut InstallationAreas,
 ) {
     installation_areas.clear();
     for (&installation, installation_speakers) in installation_speakers {
-        let speaker_points = || installation_speakers.iter().map(|id| speakers[id].point);
-        let bounding_rect = match BoundingRect::from_points(speaker_points()) {
+        let margin = installations
+            .get(&installation)
+            .map(|constraints| constraints.area_margin)
+            .unwrap_or(Metres(0.0));
+        let speaker_points = installation_speakers.iter().map(|id| speakers[id].point);
+        let area = match movement::Area::from_points(speaker_points, margin) {
             None => continue,
-            Some(rect) => rect,
-        };
-        let centroid = match nannou::geom::centroid(speaker_points().map(|p| pt2(p.x.0, p.y.0))) {
-            None => continue,
-            Some(p) => pt2(Metres(p.x), Metres(p.y)),
-        };
-        let area = movement::Area {
-            bounding_rect,
-            centroid,
+            Some(area) => area,
         };
         installation_areas.insert(installation, area);
     }
@@ -1092,12 +1515,14 @@ fn installation_target_sounds(
     installation: &installation::Id,
     constraints: &installation::Soundscape,
     installation_areas: &InstallationAreas,
+    activity_reading: Option<f32>,
+    secs_of_day: u32,
 ) -> usize {
     if !installation_areas.contains_key(installation) {
         return 0;
     }
     let playback_secs = duration_to_secs(playback_duration);
-    // Update the target number of sounds very slowly. Say, once every 5 minutes.
+    // Wander the target number of sounds very slowly, over roughly an hour.
     let hr_secs = 1.0 * 60.0 * 60.0;
     let hz = 1.0 / hr_secs;
     // Offset the phase using the `installation::Id` as a unique seed.
@@ -1111,9 +1536,15 @@ fn installation_target_sounds(
     // Amplify the noise_walk slightly so that it occasionally reaches min and max.
     let amp = (noise_walk(phase) * 1.5).min(1.0).max(-1.0);
     let normalised_amp = amp * 0.5 + 0.5;
+    // Ranges loaded from older project files may be inverted, so be sure to order the bounds.
     let range = &constraints.simultaneous_sounds;
-    let range_diff = range.max - range.min;
-    (range.min as f64 + normalised_amp * range_diff as f64) as usize
+    let (min, max) = (range.min.min(range.max), range.min.max(range.max));
+    // Scale the range by the installation's scheduled density at the current time of day and by
+    // its response to the latest activity reading.
+    let scheduled = constraints.schedule.density_at(secs_of_day);
+    let density = (scheduled * constraints.activity.density_at(activity_reading)).max(0.0) as f64;
+    let (min, max) = (min as f64 * density, max as f64 * density);
+    (min + normalised_amp * (max - min)).round() as usize
 }
 
 // Determine the target number of sounds per installation.
@@ -1125,6 +1556,8 @@ fn update_target_sounds_per_installation(
     playback_duration: &time::Duration,
     installations: &Installations,
     installation_areas: &InstallationAreas,
+    activity_readings: &ActivityReadings,
+    secs_of_day: u32,
     target_sounds_per_installation: &mut TargetSoundsPerInstallation,
 ) {
     target_sounds_per_installation.clear();
@@ -1135,31 +1568,192 @@ fn update_target_sounds_per_installation(
             installation,
             installation_constraints,
             installation_areas,
+            activity_readings.get(installation).cloned(),
+            secs_of_day,
         );
         target_sounds_per_installation.insert(*installation, target_num_sounds);
     }
 }
 
+// Whether or not sounds may be spawned from the given source given the mute and solo state of all
+// groups.
+//
+// A source is silenced if it is disabled or its WAV is missing, if any of its groups are muted, or
+// if some group is soloed and the source is not a member of any soloed group.
+fn source_is_audible(source: &Source, groups: &Groups) -> bool {
+    if !source.enabled || source.kind.is_missing() {
+        return false;
+    }
+    let any_soloed = groups.values().any(|g| g.soloed);
+    let mut source_groups = source.groups.iter().filter_map(|id| groups.get(id));
+    if source_groups.clone().any(|g| g.muted) {
+        return false;
+    }
+    !any_soloed || source_groups.any(|g| g.soloed)
+}
+
+// The gain of sounds spawned from the given source at the given intensity.
+//
+// This is the mean of the gains of the source's groups, or `1.0` if the source has no groups.
+fn intensity_gain(source: &Source, groups: &Groups, intensity: f32) -> f32 {
+    let gains = source.groups.iter().filter_map(|id| groups.get(id)).map(|g| g.gain_at(intensity));
+    let (sum, count) = gains.fold((0.0, 0), |(sum, count), gain| (sum + gain, count + 1));
+    if count == 0 {
+        1.0
+    } else {
+        sum / count as f32
+    }
+}
+
+// Send the intensity gain of the sound with the given ID to the audio output thread.
+fn send_intensity_gain(
+    audio_output_stream: &audio::output::Stream,
+    sound_id: audio::sound::Id,
+    gain: f32,
+) {
+    audio_output_stream
+        .send(move |audio| {
+            audio.update_sound(&sound_id, move |sound| {
+                sound.intensity_gain = gain;
+            });
+        })
+        .expect("failed to send sound intensity gain to audio output thread");
+}
+
+// Determine the modulation scales of each group with any modulators enabled.
+fn update_group_modulations(
+    seed: Seed,
+    playback_duration: &time::Duration,
+    groups: &Groups,
+    group_modulations: &mut GroupModulations,
+) {
+    let playback_secs = duration_to_secs(playback_duration);
+    group_modulations.clear();
+    let extension = groups
+        .iter()
+        .filter(|&(_, group)| group.modulation.is_enabled())
+        .map(|(id, group)| (*id, group.modulation.scales_at(&seed, id, playback_secs)));
+    group_modulations.extend(extension);
+}
+
+// The modulation scales of a source, averaged across its groups.
+fn source_modulation(
+    source: &Source,
+    group_modulations: &GroupModulations,
+) -> modulation::Scales {
+    let mut scales = modulation::Scales { rate: 0.0, spread: 0.0, volume: 0.0 };
+    let mut count = 0;
+    for id in &source.groups {
+        let s = group_modulations.get(id).cloned().unwrap_or_default();
+        scales.rate += s.rate;
+        scales.spread += s.spread;
+        scales.volume += s.volume;
+        count += 1;
+    }
+    if count == 0 {
+        return modulation::Scales::default();
+    }
+    let n = count as f32;
+    modulation::Scales {
+        rate: scales.rate / n,
+        spread: scales.spread / n,
+        volume: scales.volume / n,
+    }
+}
+
+// The level of a source within the current point of the scene crossfade, averaged across its
+// groups.
+fn source_scene_level(source: &Source, groups: &Groups, scene: &scene::Crossfade) -> f32 {
+    let levels = source
+        .groups
+        .iter()
+        .filter_map(|id| groups.get(id).map(|group| scene.level(id, group)));
+    let (sum, count) = levels.fold((0.0, 0), |(sum, count), level| (sum + level, count + 1));
+    match count {
+        0 => 1.0,
+        n => sum / n as f32,
+    }
+}
+
+// Send the modulated spread and gain of the sound with the given ID to the audio output thread.
+fn send_modulation(
+    audio_output_stream: &audio::output::Stream,
+    sound_id: audio::sound::Id,
+    spread: Metres,
+    scales: modulation::Scales,
+) {
+    audio_output_stream
+        .send(move |audio| {
+            audio.update_sound(&sound_id, move |sound| {
+                sound.spread = Metres(spread.0 * scales.spread as f64);
+                sound.modulation_gain = scales.volume;
+            });
+        })
+        .expect("failed to send sound modulation to audio output thread");
+}
+
+// Send the time-of-day gain of the sound with the given ID to the audio output thread.
+fn send_day_gain(
+    audio_output_stream: &audio::output::Stream,
+    sound_id: audio::sound::Id,
+    gain: f32,
+) {
+    audio_output_stream
+        .send(move |audio| {
+            audio.update_sound(&sound_id, move |sound| {
+                sound.day_gain = gain;
+            });
+        })
+        .expect("failed to send sound time of day gain to audio output thread");
+}
+
+// Send the priority of the sound's source to the audio thread for ducking.
+fn send_priority(
+    audio_output_stream: &audio::output::Stream,
+    sound_id: audio::sound::Id,
+    priority: audio::source::Priority,
+) {
+    audio_output_stream
+        .send(move |audio| {
+            audio.update_sound(&sound_id, move |sound| {
+                sound.priority = Some(priority);
+            });
+        })
+        .expect("failed to send sound priority to audio output thread");
+}
+
 // Collect available groups of sources (based on occurrence rate and simultaneous sounds) for the
 // given installation at the given moment in time.
-fn update_available_groups(
+fn update_available_groups<I>(
     tick: &Tick,
     sources: &Sources,
     groups: &Groups,
-    active_sounds: &ActiveSounds,
+    active_sources: I,
     groups_last_used: &GroupsLastUsed,
+    group_modulations: &GroupModulations,
+    scene: &scene::Crossfade,
+    intensity: f32,
+    secs_of_day: u32,
     available_groups: &mut AvailableGroups,
-) {
+)
+where
+    I: Clone + Iterator<Item = audio::source::Id>,
+{
     available_groups.clear();
+    let any_soloed = groups.values().any(|g| g.soloed);
     let extension = groups
         .iter()
         .filter_map(|(group_id, group)| {
+            // Skip groups that have been muted or are not soloed while others are.
+            if !group.is_audible(any_soloed) {
+                return None;
+            }
+
             // The total number of active sounds spawned via this group across all installations.
-            let num_active_sounds = active_sounds
-                .values()
-                .filter(|sound| {
-                    let source_id = sound.source_id();
-                    let source = match sources.get(&source_id) {
+            let num_active_sounds = active_sources
+                .clone()
+                .filter(|source_id| {
+                    let source = match sources.get(source_id) {
                         None => return false,
                         Some(s) => s,
                     };
@@ -1167,15 +1761,22 @@ fn update_available_groups(
                 })
                 .count();
 
+            // Scale the group's constraints by the soundscape's intensity, its schedule, its level
+            // within the current scene and its modulated rate.
+            let density = group.schedule.density_at(secs_of_day) * scene.level(group_id, group);
+            let simultaneous_sounds = group.simultaneous_sounds_at(intensity, density);
+            let rate = group_modulations.get(group_id).map(|s| s.rate).unwrap_or(1.0);
+            let occurrence_rate = group.occurrence_rate_at(intensity, density * rate);
+
             // If there are no available sounds, skip this group.
-            let num_available_sounds = if group.simultaneous_sounds.max > num_active_sounds {
-                group.simultaneous_sounds.max - num_active_sounds
+            let num_available_sounds = if simultaneous_sounds.max > num_active_sounds {
+                simultaneous_sounds.max - num_active_sounds
             } else {
                 return None;
             };
 
-            let num_sounds_needed = if group.simultaneous_sounds.min > num_active_sounds {
-                group.simultaneous_sounds.min - num_active_sounds
+            let num_sounds_needed = if simultaneous_sounds.min > num_active_sounds {
+                simultaneous_sounds.min - num_active_sounds
             } else {
                 0
             };
@@ -1188,13 +1789,12 @@ fn update_available_groups(
                 let duration_since_last_ms =
                     Ms(duration_to_secs(&duration_since_last) * 1_000.0);
                 let duration_since_min_interval =
-                    if duration_since_last_ms > group.occurrence_rate.min {
-                        duration_since_last_ms - group.occurrence_rate.min
+                    if duration_since_last_ms > occurrence_rate.min {
+                        duration_since_last_ms - occurrence_rate.min
                     } else {
                         return None;
                     };
-                let duration_until_sound_needed =
-                    group.occurrence_rate.max - duration_since_last_ms;
+                let duration_until_sound_needed = occurrence_rate.max - duration_since_last_ms;
                 Some(Timing {
                     duration_since_min_interval,
                     duration_until_sound_needed,
@@ -1203,7 +1803,7 @@ fn update_available_groups(
                 None
             };
 
-            let occurrence_rate_interval = group.occurrence_rate;
+            let occurrence_rate_interval = occurrence_rate;
             let suitability = Suitability {
                 occurrence_rate_interval,
                 num_sounds_needed,
@@ -1220,15 +1820,67 @@ fn update_available_groups(
     available_groups.extend(extension);
 }
 
-fn update_available_sources(
+// The likelihood of the given source following the most recent source of each of its groups.
+fn transition_weight(
+    source_id: &audio::source::Id,
+    source: &Source,
+    groups: &Groups,
+    groups_last_source: &GroupsLastSource,
+) -> f32 {
+    source
+        .groups
+        .iter()
+        .filter_map(|group_id| {
+            let last_source = groups_last_source.get(group_id)?;
+            let group = groups.get(group_id)?;
+            Some(group.transitions.weight(last_source, source_id))
+        })
+        .sum()
+}
+
+// The index of the weight selected by `r` within `0.0..1.0`.
+//
+// Each index is picked with a likelihood proportional to its weight. If no weight is greater than
+// zero, all indices are equally likely.
+fn weighted_index<I>(weights: I, r: f32) -> usize
+where
+    I: Clone + ExactSizeIterator<Item = f32>,
+{
+    let len = weights.len();
+    let total: f32 = weights.clone().map(|w| w.max(0.0)).sum();
+    if total <= 0.0 {
+        return cmp::min((r * len as f32) as usize, len.saturating_sub(1));
+    }
+    let mut target = r * total;
+    let mut last = 0;
+    for (i, w) in weights.map(|w| w.max(0.0)).enumerate() {
+        if w <= 0.0 {
+            continue;
+        }
+        if target < w {
+            return i;
+        }
+        target -= w;
+        last = i;
+    }
+    last
+}
+
+fn update_available_sources<I>(
     installation: &installation::Id,
     tick: &Tick,
     sources: &Sources,
-    active_sounds: &ActiveSounds,
+    groups: &Groups,
+    active_sources: I,
     sources_last_used: &SourcesLastUsed,
+    sources_last_ended: &SourcesLastEnded,
+    recent_spawns: &RecentSpawns,
     available_groups: &AvailableGroups,
     available_sources: &mut AvailableSources,
-) {
+)
+where
+    I: Clone + Iterator<Item = audio::source::Id>,
+{
     // Find all available sources for the front group.
     available_sources.clear();
     let extension = sources.iter().filter_map(|(source_id, source)| {
@@ -1242,11 +1894,48 @@ fn update_available_sources(
             return None;
         }
 
+        // Skip sources that have been silenced via a group mute or solo.
+        if !source_is_audible(source, groups) {
+            return None;
+        }
+
+        // Sources that follow visitors are only spawned via `update_followers`.
+        if source.follow.enabled {
+            return None;
+        }
+
+        // Skip sources that were picked too recently to be repeated.
+        if let Some(&last_use) = sources_last_used.get(source_id) {
+            let spawns_since = recent_spawns
+                .iter()
+                .rev()
+                .position(|id| id == source_id)
+                .unwrap_or(recent_spawns.len());
+            let since = Ms(duration_to_secs(&tick.instant.duration_since(last_use)) * 1_000.0);
+            if source.no_repeat.excludes(spawns_since, since) {
+                return None;
+            }
+        }
+
         // How many instances of this sound are already playing.
-        let num_sounds = active_sounds
-            .values()
-            .filter(|s| s.source_id() == *source_id)
-            .count();
+        let num_sounds = active_sources.clone().filter(|id| id == source_id).count();
+
+        // Skip sources that are playing or cooling down after their last sound ended.
+        if source.cooldown > Ms(0.0) {
+            if num_sounds > 0 {
+                return None;
+            }
+            if let Some(&last_ended) = sources_last_ended.get(source_id) {
+                // The sound may have ended after the moment of this tick.
+                let since = match last_ended < tick.instant {
+                    true => tick.instant.duration_since(last_ended),
+                    false => time::Duration::from_secs(0),
+                };
+                if Ms(duration_to_secs(&since) * 1_000.0) < source.cooldown {
+                    return None;
+                }
+            }
+        }
 
         // If there are no available sounds, skip this group.
         let num_available_sounds = if source.simultaneous_sounds.max > num_sounds {
@@ -1295,7 +1984,8 @@ fn update_available_sources(
         Some(AvailableSource {
             id: *source_id,
             suitability,
-            playback_duration: source.playback_duration,
+            weight: source.weight,
+            playback_duration: source.kind.clamp_playback_duration(source.playback_duration),
             attack_duration: source.attack_duration,
             release_duration: source.release_duration,
         })
@@ -1303,6 +1993,658 @@ fn update_available_sources(
     available_sources.extend(extension);
 }
 
+// Select the group and source from which the next sound within the given installation is spawned.
+//
+// Returns the selected group along with the index of the selected source within
+// `available_sources`, or `None` if no group or source is currently available. This is shared by
+// each tick and the fast-forward simulation so that both select sounds in the same manner.
+fn select_source<I>(
+    installation: &installation::Id,
+    tick: &Tick,
+    sources: &Sources,
+    groups: &Groups,
+    active_sources: I,
+    groups_last_used: &GroupsLastUsed,
+    sources_last_used: &SourcesLastUsed,
+    sources_last_ended: &SourcesLastEnded,
+    recent_spawns: &RecentSpawns,
+    groups_last_source: &GroupsLastSource,
+    group_modulations: &GroupModulations,
+    scene: &scene::Crossfade,
+    intensity: f32,
+    secs_of_day: u32,
+    constraints: &installation::Soundscape,
+    num_target_sounds: usize,
+    sounds_per_priority: &installation::SoundsPerPriority,
+    available_groups: &mut AvailableGroups,
+    available_sources: &mut AvailableSources,
+) -> Option<(group::Id, usize)>
+where
+    I: Clone + Iterator<Item = audio::source::Id>,
+{
+    // Collect available groups of sounds (based on occurrence rate and simultaneous sounds).
+    update_available_groups(
+        tick,
+        sources,
+        groups,
+        active_sources.clone(),
+        groups_last_used,
+        group_modulations,
+        scene,
+        intensity,
+        secs_of_day,
+        available_groups,
+    );
+
+    if available_groups.is_empty() {
+        return None;
+    }
+
+    // Find all available sources.
+    update_available_sources(
+        installation,
+        tick,
+        sources,
+        groups,
+        active_sources,
+        sources_last_used,
+        sources_last_ended,
+        recent_spawns,
+        available_groups,
+        available_sources,
+    );
+
+    // Only keep sources whose priority class may add a sound to the installation.
+    let range = &constraints.simultaneous_sounds;
+    let max_sounds = range.min.max(range.max);
+    let num_active_sounds: usize = sounds_per_priority.iter().sum();
+    let reserved = &constraints.reserved;
+    available_sources.retain(|s| {
+        let priority = sources[&s.id].priority;
+        let within_target = num_active_sounds < num_target_sounds;
+        let within_reservation =
+            sounds_per_priority[priority.to_index()] < reserved.get(priority);
+        (within_target || within_reservation)
+            && reserved.has_headroom(max_sounds, sounds_per_priority, priority)
+    });
+
+    if available_sources.is_empty() {
+        return None;
+    }
+
+    // Sort the groups by:
+    //
+    // 1. The number of sounds needed
+    // 2. The duration until a sound is needed to beat the occurrence rate.
+    available_groups.sort_by(|a, b| suitability(&a.suitability, &b.suitability));
+
+    // Sort the groups by:
+    //
+    // 1. The number of sounds needed
+    // 2. The duration until a sound is needed to beat the occurrence rate.
+    available_sources.sort_by(|a, b| suitability(&a.suitability, &b.suitability));
+
+    // Retrieve one of the most suitable groups from which this source will be "picked".
+    let group_index: usize = {
+        let num_equal = utils::count_equal(&*available_groups, |a, b| {
+            suitability(&a.suitability, &b.suitability)
+        });
+        nannou::rand::thread_rng().gen_range(0, num_equal)
+    };
+
+    // Prefer the sources that follow the last source of one of their groups. Otherwise
+    // retrieve one of the most suitable sources, weighted by their `weight`.
+    let source_index: usize = {
+        let transitions = available_sources.iter().map(|s| {
+            transition_weight(&s.id, &sources[&s.id], groups, groups_last_source)
+        });
+        let r = nannou::rand::thread_rng().gen::<f32>();
+        if transitions.clone().any(|w| w > 0.0) {
+            weighted_index(transitions, r)
+        } else {
+            let num_equal = utils::count_equal(&*available_sources, |a, b| {
+                suitability(&a.suitability, &b.suitability)
+            });
+            let weights = available_sources[..num_equal].iter().map(|s| s.weight);
+            weighted_index(weights, r)
+        }
+    };
+
+    Some((available_groups[group_index].id, source_index))
+}
+
+// Track the time at which the given group and source were last used along with the most recent
+// spawns and the most recent source of each of the source's groups.
+fn record_spawn(
+    group_id: group::Id,
+    source_id: audio::source::Id,
+    instant: time::Instant,
+    sources: &Sources,
+    groups_last_used: &mut GroupsLastUsed,
+    sources_last_used: &mut SourcesLastUsed,
+    recent_spawns: &mut RecentSpawns,
+    groups_last_source: &mut GroupsLastSource,
+) {
+    groups_last_used.insert(group_id, instant);
+    sources_last_used.insert(source_id, instant);
+    if recent_spawns.len() == audio::source::MAX_NO_REPEAT_SPAWNS {
+        recent_spawns.pop_front();
+    }
+    recent_spawns.push_back(source_id);
+    for &group_id in &sources[&source_id].groups {
+        groups_last_source.insert(group_id, source_id);
+    }
+}
+
+// Spawn, move and release the sounds following tracked visitors for each source with `follow`
+// enabled.
+//
+// A sound is spawned for each visitor that has been tracked for at least the source's spawn
+// delay, up to the source's maximum number of followers. A sound is released once its visitor has
+// been lost for longer than the source's release delay.
+fn update_followers(
+    tick: &Tick,
+    sources: &Sources,
+    groups: &Groups,
+    installation_areas: &InstallationAreas,
+    interactions: &Interactions,
+    followers: &mut Followers,
+    active_sounds: &mut ActiveSounds,
+    sound_id_gen: &mut audio::sound::IdGenerator,
+    frame_count: u64,
+    wav_reader: &audio::source::wav::reader::Handle,
+    audio_input_stream: &audio::input::Streams,
+    audio_output_stream: &audio::output::Stream,
+    realtime_source_latency: Ms,
+    intensity: f32,
+    secs_of_day: u32,
+    missing_wavs: &mut Vec<audio::source::Id>,
+) {
+    let ms_since = |instant: time::Instant| {
+        Ms(duration_to_secs(&tick.instant.duration_since(instant)) * 1_000.0)
+    };
+
+    // Forget followers whose sounds have ended and release those that should no longer follow.
+    let mut rng = nannou::rand::thread_rng();
+    followers.retain(|&(source_id, track), follower| {
+        if !active_sounds.contains_key(&follower.sound_id) {
+            return false;
+        }
+        let source = match sources.get(&source_id) {
+            Some(source) => source,
+            None => return false,
+        };
+        if interactions.contains_key(&track) {
+            follower.lost_since = None;
+        } else if follower.lost_since.is_none() {
+            follower.lost_since = Some(tick.instant);
+        }
+        let is_lost = follower
+            .lost_since
+            .map(|since| ms_since(since) >= source.follow.release_delay)
+            .unwrap_or(false);
+        if source.follow.enabled && source_is_audible(source, groups) && !is_lost {
+            return true;
+        }
+        let sound_id = follower.sound_id;
+        let release_frames =
+            audio::source::random_playback_duration(&mut rng, source.release_duration)
+                .to_samples(audio::sample_rate());
+        audio_output_stream
+            .send(move |audio| {
+                audio.update_sound(&sound_id, move |sound| {
+                    sound.signal.fade_out(release_frames);
+                });
+            })
+            .expect("failed to send follower fade out to audio output thread");
+        false
+    });
+
+    // Move each follower to the latest position of its visitor.
+    for (&(_, track), follower) in followers.iter() {
+        let tracked = match interactions.get(&track) {
+            Some(tracked) => tracked,
+            None => continue,
+        };
+        if let Some(sound) = active_sounds.get_mut(&follower.sound_id) {
+            let point = tracked.interaction.position;
+            let audio::sound::Position { radians, height, .. } = sound.position();
+            sound.movement = Movement::Fixed(audio::sound::Position { point, radians, height });
+        }
+    }
+
+    // Spawn a follower for each visitor that has been tracked for long enough.
+    for (&source_id, source) in sources.iter() {
+        if !source.follow.enabled || !source_is_audible(source, groups) {
+            continue;
+        }
+        let num_followers = followers.keys().filter(|&&(id, _)| id == source_id).count();
+        if num_followers >= source.follow.max_followers {
+            continue;
+        }
+
+        // Favour the visitors that have been tracked the longest.
+        let mut visitors: Vec<_> = interactions
+            .iter()
+            .filter(|&(track, _)| !followers.contains_key(&(source_id, *track)))
+            .filter(|&(_, tracked)| ms_since(tracked.first_seen) >= source.follow.spawn_delay)
+            .collect();
+        visitors.sort_by_key(|&(_, tracked)| tracked.first_seen);
+
+        let num_to_spawn = source.follow.max_followers - num_followers;
+        for (&track, tracked) in visitors.into_iter().take(num_to_spawn) {
+            let position = audio::sound::Position {
+                point: tracked.interaction.position,
+                radians: 0.0,
+                height: source.height,
+            };
+
+            // The installation whose area is closest to the visitor.
+            let sound_position = ActiveSoundPosition { source_id, position };
+            let installation =
+                match closest_assigned_installation(&sound_position, sources, installation_areas) {
+                    Some(installation) => installation,
+                    None => continue,
+                };
+
+            let attack_duration_frames =
+                audio::source::random_playback_duration(&mut rng, source.attack_duration)
+                    .to_samples(audio::sample_rate());
+            let release_duration_frames =
+                audio::source::random_playback_duration(&mut rng, source.release_duration)
+                    .to_samples(audio::sample_rate());
+            let continuous_preview = false;
+
+            // Followers play for as long as their visitor is tracked.
+            let max_duration_frames = None;
+
+            let mut audio_source = source.to_audio_source();
+            audio_source.effects = audio_source
+                .effects
+                .randomised(&mut rng, &source.effect_randomisation);
+            let sound_id = sound_id_gen.generate_next();
+            let handle = audio::sound::spawn_from_source(
+                sound_id,
+                source_id,
+                &audio_source,
+                position,
+                attack_duration_frames,
+                release_duration_frames,
+                continuous_preview,
+                max_duration_frames,
+                frame_count,
+                wav_reader,
+                audio_input_stream,
+                audio_output_stream,
+                realtime_source_latency,
+            );
+            let handle = match handle {
+                Ok(handle) => handle,
+                Err(err) => {
+                    report_missing_wav(source_id, source, err, missing_wavs);
+                    break;
+                },
+            };
+            let gain = intensity_gain(source, groups, intensity);
+            send_intensity_gain(audio_output_stream, sound_id, gain);
+            send_priority(audio_output_stream, sound_id, source.priority);
+            if !source.day_gain.is_empty() {
+                send_day_gain(audio_output_stream, sound_id, source.day_gain.gain_at(secs_of_day));
+            }
+
+            let active_sound = ActiveSound {
+                initial_installation: installation,
+                movement: Movement::Fixed(position),
+                handle,
+                modulation: Default::default(),
+                follow_up_depth: 0,
+            };
+            active_sounds.insert(sound_id, active_sound);
+            let lost_since = None;
+            followers.insert((source_id, track), Follower { sound_id, lost_since });
+        }
+    }
+}
+
+// Spawn each pending follow-up whose delay has passed.
+//
+// Follow-ups are spawned at a random point within their radius of the sound that triggered them,
+// bypassing the soundscape selection. Follow-ups from sources that are missing, inaudible or that
+// follow visitors are discarded, as are those that would exceed the simultaneous sounds allowed by
+// the source or its groups, or the target number of sounds of the closest installation.
+fn update_follow_ups(
+    tick: &Tick,
+    sources: &Sources,
+    groups: &Groups,
+    scene: &scene::Crossfade,
+    installation_areas: &InstallationAreas,
+    target_sounds_per_installation: &TargetSoundsPerInstallation,
+    pending_follow_ups: &mut PendingFollowUps,
+    active_sounds: &mut ActiveSounds,
+    sound_id_gen: &mut audio::sound::IdGenerator,
+    frame_count: u64,
+    wav_reader: &audio::source::wav::reader::Handle,
+    audio_input_stream: &audio::input::Streams,
+    audio_output_stream: &audio::output::Stream,
+    realtime_source_latency: Ms,
+    intensity: f32,
+    secs_of_day: u32,
+    missing_wavs: &mut Vec<audio::source::Id>,
+) {
+    let mut rng = nannou::rand::thread_rng();
+    let mut i = 0;
+    while i < pending_follow_ups.len() {
+        if pending_follow_ups[i].due > tick.playback_duration {
+            i += 1;
+            continue;
+        }
+        let PendingFollowUp { source_id, point, radius, depth, .. } =
+            pending_follow_ups.swap_remove(i);
+        let source = match sources.get(&source_id) {
+            Some(source) => source,
+            None => continue,
+        };
+        if source.follow.enabled || !source_is_audible(source, groups) {
+            continue;
+        }
+        let limited = source_at_simultaneous_limit(
+            source_id,
+            source,
+            sources,
+            groups,
+            scene,
+            intensity,
+            secs_of_day,
+            active_sounds,
+        );
+        if limited {
+            continue;
+        }
+
+        // A uniformly distributed point within the radius.
+        let angle = rng.gen::<f64>() * 2.0 * PI_F64;
+        let distance = radius.0 * rng.gen::<f64>().sqrt();
+        let point = Point2 {
+            x: Metres(point.x.0 + angle.cos() * distance),
+            y: Metres(point.y.0 + angle.sin() * distance),
+        };
+        let radians = rng.gen::<f32>() * 2.0 * ::std::f32::consts::PI;
+        let position = audio::sound::Position { point, radians, height: source.height };
+
+        // The installation whose area is closest to the follow-up.
+        let sound_position = ActiveSoundPosition { source_id, position };
+        let installation =
+            match closest_assigned_installation(&sound_position, sources, installation_areas) {
+                Some(installation) => installation,
+                None => continue,
+            };
+
+        // Follow-ups are not spawned beyond the installation's target, as with regular sounds.
+        let num_target_sounds = target_sounds_per_installation
+            .get(&installation)
+            .cloned()
+            .unwrap_or(0);
+        let num_installation_sounds = active_sounds
+            .values()
+            .map(|sound| sound.active_sound_position())
+            .filter(|sound| {
+                // Sounds following visitors do not count towards an installation's target.
+                let follows = sources.get(&sound.source_id).map(|s| s.follow.enabled);
+                if follows.unwrap_or(false) {
+                    return false;
+                }
+                let closest = closest_assigned_installation(sound, sources, installation_areas);
+                closest == Some(installation)
+            })
+            .count();
+        if num_installation_sounds >= num_target_sounds {
+            continue;
+        }
+
+        let attack_duration_frames =
+            audio::source::random_playback_duration(&mut rng, source.attack_duration)
+                .to_samples(audio::sample_rate());
+        let release_duration_frames =
+            audio::source::random_playback_duration(&mut rng, source.release_duration)
+                .to_samples(audio::sample_rate());
+        let continuous_preview = false;
+        let playback_duration = source.kind.clamp_playback_duration(source.playback_duration);
+        let duration_frames = audio::source::random_playback_duration(&mut rng, playback_duration)
+            .to_samples(audio::sample_rate());
+
+        let mut audio_source = source.to_audio_source();
+        audio_source.effects = audio_source
+            .effects
+            .randomised(&mut rng, &source.effect_randomisation);
+        let sound_id = sound_id_gen.generate_next();
+        let handle = audio::sound::spawn_from_source(
+            sound_id,
+            source_id,
+            &audio_source,
+            position,
+            attack_duration_frames,
+            release_duration_frames,
+            continuous_preview,
+            Some(duration_frames),
+            frame_count,
+            wav_reader,
+            audio_input_stream,
+            audio_output_stream,
+            realtime_source_latency,
+        );
+        let handle = match handle {
+            Ok(handle) => handle,
+            Err(err) => {
+                report_missing_wav(source_id, source, err, missing_wavs);
+                continue;
+            },
+        };
+        let gain = intensity_gain(source, groups, intensity);
+        send_intensity_gain(audio_output_stream, sound_id, gain);
+        send_priority(audio_output_stream, sound_id, source.priority);
+        if !source.day_gain.is_empty() {
+            send_day_gain(audio_output_stream, sound_id, source.day_gain.gain_at(secs_of_day));
+        }
+
+        let active_sound = ActiveSound {
+            initial_installation: installation,
+            movement: Movement::Fixed(position),
+            handle,
+            modulation: Default::default(),
+            follow_up_depth: depth,
+        };
+        active_sounds.insert(sound_id, active_sound);
+    }
+}
+
+// Whether another sound from the given source would exceed the simultaneous sounds allowed by the
+// source or any of its groups, in the same manner as checked when selecting sources.
+fn source_at_simultaneous_limit(
+    source_id: audio::source::Id,
+    source: &Source,
+    sources: &Sources,
+    groups: &Groups,
+    scene: &scene::Crossfade,
+    intensity: f32,
+    secs_of_day: u32,
+    active_sounds: &ActiveSounds,
+) -> bool {
+    let active_sources = || active_sounds.values().map(|s| s.source_id());
+    let num_sounds = active_sources().filter(|id| *id == source_id).count();
+    if num_sounds >= source.simultaneous_sounds.max {
+        return true;
+    }
+    source.groups.iter().any(|group_id| {
+        let group = match groups.get(group_id) {
+            Some(group) => group,
+            None => return false,
+        };
+        let density = group.schedule.density_at(secs_of_day) * scene.level(group_id, group);
+        let simultaneous_sounds = group.simultaneous_sounds_at(intensity, density);
+        let num_active_sounds = active_sources()
+            .filter(|id| sources.get(id).map(|s| s.groups.contains(group_id)).unwrap_or(false))
+            .count();
+        num_active_sounds >= simultaneous_sounds.max
+    })
+}
+
+// Update the movement of the given sounds spawned for a single installation.
+//
+// Returns the new position of each sound. This is called from the soundscape's pool.
+fn update_installation_movement(
+    installation: installation::Id,
+    sounds: Vec<(audio::sound::Id, &mut ActiveSound)>,
+    context: &MovementContext,
+) -> Vec<(audio::sound::Id, audio::sound::Position)>
+{
+    let mut rng = nannou::rand::thread_rng();
+    let installation_area = context.installation_areas.get(&installation);
+    sounds
+        .into_iter()
+        .map(|(sound_id, sound)| {
+            let source_id = sound.handle.source_id();
+            match sound.movement {
+                Movement::Fixed(_) => (),
+                Movement::Generative(ref mut generative) => match *generative {
+                    movement::Generative::Agent(ref mut agent) => {
+                        let installation_data = agent_installation_data(
+                            source_id,
+                            context.sources,
+                            context.installations,
+                            context.installation_areas,
+                            context.target_sounds_per_installation,
+                            context.active_sound_positions,
+                        );
+                        let neighbours = match context.sources.get(&source_id) {
+                            None => vec![],
+                            Some(source) => {
+                                let others = context
+                                    .active_sound_positions
+                                    .iter()
+                                    .filter(|&(&id, _)| id != sound_id)
+                                    .map(|(_, s)| (s.source_id, s.position.point));
+                                let (sources, groups) = (context.sources, context.groups);
+                                separation_neighbours(source, sources, groups, others)
+                            },
+                        };
+                        let delta_time = context.since_last_tick;
+                        let groups = context.groups;
+                        let weight = |p| match context.sources.get(&source_id) {
+                            Some(source) => heatmap_probability(source, groups, p),
+                            None => 1.0,
+                        };
+                        agent.update(&mut rng, delta_time, &installation_data, &neighbours, weight);
+                    },
+                    movement::Generative::Ngon(ref mut ngon) => {
+                        if let Some(area) = installation_area {
+                            ngon.update(context.since_last_tick, &area.bounding_rect);
+                        }
+                    },
+                },
+                Movement::Path(ref mut path) => path.update(context.since_last_tick),
+            }
+
+            // Movement is planar, so the height is always taken from the sound's source.
+            let mut position = sound.position();
+            if let Some(source) = context.sources.get(&source_id) {
+                position.height = source.height;
+            }
+            (sound_id, position)
+        })
+        .collect()
+}
+
+// The minimum separation between the sounds of the two given sources.
+//
+// This is the greatest `min_separation` of the groups to which both sources belong.
+fn min_separation(a: &Source, b: &Source, groups: &Groups) -> Metres {
+    a.groups
+        .iter()
+        .filter(|id| b.groups.contains(id))
+        .filter_map(|id| groups.get(id))
+        .fold(Metres(0.0), |max, group| max.max(group.min_separation))
+}
+
+// The locations of the `others` that a sound from the given source should keep clear of, each
+// paired with the minimum separation from it.
+fn separation_neighbours<I>(
+    source: &Source,
+    sources: &Sources,
+    groups: &Groups,
+    others: I,
+) -> Vec<(Point2<Metres>, Metres)>
+where
+    I: IntoIterator<Item = (audio::source::Id, Point2<Metres>)>,
+{
+    let any_separation = source
+        .groups
+        .iter()
+        .filter_map(|id| groups.get(id))
+        .any(|group| group.min_separation > Metres(0.0));
+    if !any_separation {
+        return vec![];
+    }
+    others
+        .into_iter()
+        .filter_map(|(other_id, point)| {
+            let other = sources.get(&other_id)?;
+            let separation = min_separation(source, other, groups);
+            if separation > Metres(0.0) {
+                Some((point, separation))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// The likelihood of a sound from the given source occupying the given point.
+//
+// This is the product of the heatmap probabilities of each of the source's groups.
+fn heatmap_probability(source: &Source, groups: &Groups, point: Point2<Metres>) -> f32 {
+    source
+        .groups
+        .iter()
+        .filter_map(|id| groups.get(id))
+        .fold(1.0, |p, group| p * group.heatmap.probability_at(point))
+}
+
+// Choose a point within the area at which to spawn a sound, clear of the given neighbours.
+//
+// Candidates are weighted by `weight`. If no clear point is found, the candidate that intrudes
+// least upon its neighbours is chosen.
+fn spawn_point<R, F>(
+    mut rng: R,
+    area: &movement::Area,
+    neighbours: &[(Point2<Metres>, Metres)],
+    weight: F,
+) -> Point2<Metres>
+where
+    R: Rng,
+    F: Fn(Point2<Metres>) -> f32,
+{
+    // The least distance by which the point clears the separation of each neighbour.
+    let clearance = |p: Point2<Metres>| {
+        neighbours
+            .iter()
+            .map(|&(n, separation)| (p.x.0 - n.x.0).hypot(p.y.0 - n.y.0) - separation.0)
+            .fold(::std::f64::INFINITY, f64::min)
+    };
+    let mut best = area.random_weighted_point(&mut rng, &weight);
+    let mut best_clearance = clearance(best);
+    for _ in 1..SPAWN_POINT_ATTEMPTS {
+        if best_clearance >= 0.0 {
+            break;
+        }
+        let candidate = area.random_weighted_point(&mut rng, &weight);
+        let candidate_clearance = clearance(candidate);
+        if candidate_clearance > best_clearance {
+            best = candidate;
+            best_clearance = candidate_clearance;
+        }
+    }
+    best
+}
 
 // Order the two sets or properties by their suitability for use as the next sound.
 fn suitability(a: &Suitability, b: &Suitability) -> cmp::Ordering {
@@ -1320,19 +2662,32 @@ fn suitability(a: &Suitability, b: &Suitability) -> cmp::Ordering {
 }
 
 // Called each time the soundscape thread receives a tick.
-fn tick(model: &mut Model, tick: Tick) {
+//
+// Returns the sources whose WAVs could not be opened when spawning.
+fn tick(model: &mut Model, tick: Tick) -> Vec<audio::source::Id> {
     let Model {
         ref frame_count,
         realtime_source_latency,
+        intensity,
         seed,
         ref mut playback_duration,
         ref installations,
+        ref activity_readings,
         ref groups,
         ref speakers,
         ref sources,
         ref mut groups_last_used,
         ref mut sources_last_used,
+        ref sources_last_ended,
+        ref mut recent_spawns,
+        ref mut groups_last_source,
+        ref mut group_modulations,
+        ref mut scene,
+        ref mut tempo,
         ref mut active_sounds,
+        ref interactions,
+        ref mut followers,
+        ref mut pending_follow_ups,
         ref mut installation_speakers,
         ref mut installation_areas,
         ref mut target_sounds_per_installation,
@@ -1344,19 +2699,24 @@ fn tick(model: &mut Model, tick: Tick) {
         ref wav_reader,
         ref audio_input_stream,
         ref audio_output_stream,
+        ref pool,
+        time_zone,
         ..
     } = *model;
 
     // Update the playback duration so far.
     *playback_duration = tick.playback_duration;
 
+    // The time of day at the venue at which day gain curves and schedules are evaluated.
+    let secs_of_day = time_zone.now_secs_of_day();
+
     // Update the map from installations to speakers.
     update_installation_speakers(speakers, installation_speakers);
 
     // Create the map from installations to their areas.
     //
     // An installations `Area` is determined via the assigned speaker locations.
-    update_installation_areas(speakers, installation_speakers, installation_areas);
+    update_installation_areas(speakers, installations, installation_speakers, installation_areas);
 
     // Determine the target number of sounds per installation.
     //
@@ -1367,50 +2727,135 @@ fn tick(model: &mut Model, tick: Tick) {
         &tick.playback_duration,
         installations,
         installation_areas,
+        activity_readings,
+        secs_of_day,
         target_sounds_per_installation,
     );
 
+    // Step the noise modulating each group's rate, spread and volume.
+    update_group_modulations(seed, &tick.playback_duration, groups, group_modulations);
+
+    // Progress the crossfade between scenes.
+    scene.update(duration_to_secs(&tick.since_last_tick));
+
+    // While quantised to a tempo, new sounds are only spawned as each beat or bar begins.
+    let on_quantum = tempo.update(tick.instant);
+
+    // The sources whose WAVs could not be opened, e.g. if moved or removed on disk.
+    let mut missing_wavs = vec![];
+
+    // Spawn, move and release the sounds that follow tracked visitors.
+    update_followers(
+        &tick,
+        sources,
+        groups,
+        installation_areas,
+        interactions,
+        followers,
+        active_sounds,
+        sound_id_gen,
+        frame_count.load(atomic::Ordering::Relaxed) as _,
+        wav_reader,
+        audio_input_stream,
+        audio_output_stream,
+        realtime_source_latency,
+        intensity,
+        secs_of_day,
+        &mut missing_wavs,
+    );
+
+    // Spawn the follow-ups whose delay has passed since the sound that triggered them ended.
+    if on_quantum {
+        update_follow_ups(
+            &tick,
+            sources,
+            groups,
+            scene,
+            installation_areas,
+            target_sounds_per_installation,
+            pending_follow_ups,
+            active_sounds,
+            sound_id_gen,
+            frame_count.load(atomic::Ordering::Relaxed) as _,
+            wav_reader,
+            audio_input_stream,
+            audio_output_stream,
+            realtime_source_latency,
+            intensity,
+            secs_of_day,
+            &mut missing_wavs,
+        );
+    }
+
     // Update the movement of each active sound.
-    {
-        let mut rng = nannou::rand::thread_rng();
-        update_active_sound_positions(active_sounds, active_sound_positions);
+    //
+    // Sounds are grouped by the installation for which they were spawned and each group is
+    // updated on the pool. The resulting positions are merged before dispatching any updates to
+    // the audio output thread.
+    update_active_sound_positions(active_sounds, active_sound_positions);
+    let positions: Vec<_> = {
+        let mut sounds_per_installation: FxHashMap<_, Vec<_>> = FxHashMap::default();
         for (&sound_id, sound) in active_sounds.iter_mut() {
-            let initial_installation_area = installation_areas.get(&sound.initial_installation);
-            match sound.movement {
-                Movement::Fixed(_) => (),
-                Movement::Generative(ref mut generative) => match *generative {
-                    movement::Generative::Agent(ref mut agent) => {
-                        let source_id = sound.handle.source_id();
-                        let installation_data = agent_installation_data(
-                            source_id,
-                            sources,
-                            installations,
-                            installation_areas,
-                            &target_sounds_per_installation,
-                            &active_sound_positions,
-                        );
-                        agent.update(&mut rng, &tick.since_last_tick, &installation_data);
-                    },
-                    movement::Generative::Ngon(ref mut ngon) => {
-                        if let Some(area) = initial_installation_area {
-                            ngon.update(&tick.since_last_tick, &area.bounding_rect);
-                        }
-                    },
-                },
-            }
-
-            // Update the position of the sounds on the audio thread.
-            //
-            // The audio thread will then notify the GUI of the new position upon the next rendered
-            // buffer.
-            let position = sound.position();
-            audio_output_stream
-                .send(move |audio| {
-                    audio.update_sound(&sound_id, move |sound| {
-                        sound.position = position;
-                    });
+            sounds_per_installation
+                .entry(sound.initial_installation)
+                .or_insert_with(Vec::new)
+                .push((sound_id, sound));
+        }
+        let context = MovementContext {
+            since_last_tick: &tick.since_last_tick,
+            sources,
+            groups,
+            installations,
+            installation_areas,
+            target_sounds_per_installation,
+            active_sound_positions,
+        };
+        pool.install(|| {
+            sounds_per_installation
+                .into_par_iter()
+                .flat_map(|(installation, sounds)| {
+                    update_installation_movement(installation, sounds, &context)
                 })
-                .expect("failed to send audio output thread updated sound position");
+                .collect()
+        })
+    };
+
+    // Update the position of the sounds on the audio thread.
+    //
+    // The audio thread will then notify the GUI of the new position upon the next rendered buffer.
+    for (sound_id, position) in positions {
+        audio_output_stream
+            .send(move |audio| {
+                audio.update_sound(&sound_id, move |sound| {
+                    sound.position = position;
+                });
+            })
+            .expect("failed to send audio output thread updated sound position");
+    }
+
+    // Update the gain of sounds whose sources continuously follow their day gain curve.
+    for (&sound_id, sound) in active_sounds.iter() {
+        let day_gain = match sources.get(&sound.handle.source_id()) {
+            Some(source) if source.day_gain.continuous && !source.day_gain.is_empty() => {
+                &source.day_gain
+            },
+            _ => continue,
+        };
+        send_day_gain(audio_output_stream, sound_id, day_gain.gain_at(secs_of_day));
+    }
+
+    // Update the spread and gain of sounds whose groups are modulated or crossfading between
+    // scenes.
+    for (&sound_id, sound) in active_sounds.iter_mut() {
+        let source = match sources.get(&sound.source_id()) {
+            Some(source) => source,
+            None => continue,
+        };
+        let mut scales = source_modulation(source, group_modulations);
+        scales.volume *= source_scene_level(source, groups, scene);
+        if scales != sound.modulation {
+            sound.modulation = scales;
+            send_modulation(audio_output_stream, sound_id, source.spread, scales);
         }
     }
 
@@ -1427,15 +2872,34 @@ fn tick(model: &mut Model, tick: Tick) {
         active_sounds_per_installation,
     );
 
+    // Hold any new sounds until the next beat or bar.
+    if !on_quantum {
+        return missing_wavs;
+    }
+
     // Determine how many sounds to add (if any) by finding the difference between the target
     // number and actual number.
+    //
+    // Sounds of a priority class with a reservation may be added beyond the target until their
+    // reservation is filled, so long as the installation's maximum is not exceeded.
     'installations: for (installation, &num_target_sounds) in target_sounds_per_installation.iter() {
-        let num_active_sounds = match active_sounds_per_installation.get(installation) {
-            None => 0,
-            Some(sounds) => sounds.len(),
-        };
-        let sounds_to_add = if num_target_sounds > num_active_sounds {
-            num_target_sounds - num_active_sounds
+        let active_installation_sounds = active_sounds_per_installation
+            .get(installation)
+            .map(|sounds| &sounds[..])
+            .unwrap_or(&[]);
+        let mut sounds_per_priority = installation::SoundsPerPriority::default();
+        for sound_id in active_installation_sounds {
+            let source_id = active_sound_positions[sound_id].source_id;
+            if let Some(source) = sources.get(&source_id) {
+                sounds_per_priority[source.priority.to_index()] += 1;
+            }
+        }
+        let constraints = &installations[installation];
+        let range = &constraints.simultaneous_sounds;
+        let max_sounds = range.min.max(range.max);
+        let num_active_sounds = active_installation_sounds.len();
+        let sounds_to_add = if max_sounds > num_active_sounds {
+            max_sounds - num_active_sounds
         } else {
             // If there are no sounds to add, move on to the next installation.
             continue 'installations;
@@ -1455,64 +2919,32 @@ fn tick(model: &mut Model, tick: Tick) {
         // updated and the vec should be re-sorted.
         for _ in 0..sounds_to_add {
             {
-                // Collect available groups of sounds (based on occurrence rate and simultaneous sounds).
-                update_available_groups(
+                // Select one of the most suitable groups and sources.
+                let active_sources = active_sounds.values().map(|s| s.source_id());
+                let (group_id, source_index) = match select_source(
+                    installation,
                     &tick,
                     sources,
                     groups,
-                    active_sounds,
+                    active_sources,
                     groups_last_used,
-                    available_groups,
-                );
-
-                // If there are no available groups, go to the next installation.
-                if available_groups.is_empty() {
-                    continue 'installations;
-                }
-
-                // Find all available sources.
-                update_available_sources(
-                    installation,
-                    &tick,
-                    sources,
-                    active_sounds,
                     sources_last_used,
+                    sources_last_ended,
+                    recent_spawns,
+                    groups_last_source,
+                    group_modulations,
+                    scene,
+                    intensity,
+                    secs_of_day,
+                    constraints,
+                    num_target_sounds,
+                    &sounds_per_priority,
                     available_groups,
                     available_sources,
-                );
-
-                // If there are no available sources for this group, continue to the next
-                // installtion.
-                if available_sources.is_empty() {
-                    continue 'installations;
-                }
-
-                // Sort the groups by:
-                //
-                // 1. The number of sounds needed
-                // 2. The duration until a sound is needed to beat the occurrence rate.
-                available_groups.sort_by(|a, b| suitability(&a.suitability, &b.suitability));
-
-                // Sort the groups by:
-                //
-                // 1. The number of sounds needed
-                // 2. The duration until a sound is needed to beat the occurrence rate.
-                available_sources.sort_by(|a, b| suitability(&a.suitability, &b.suitability));
-
-                // Retrieve one of the most suitable groups from which this source will be "picked".
-                let group_index: usize = {
-                    let num_equal = utils::count_equal(&*available_groups, |a, b| {
-                        suitability(&a.suitability, &b.suitability)
-                    });
-                    nannou::rand::thread_rng().gen_range(0, num_equal)
-                };
-
-                // Retrieve one of the most suitable sources.
-                let source_index: usize = {
-                    let num_equal = utils::count_equal(&*available_sources, |a, b| {
-                        suitability(&a.suitability, &b.suitability)
-                    });
-                    nannou::rand::thread_rng().gen_range(0, num_equal)
+                ) {
+                    Some(selection) => selection,
+                    // If there are no available groups or sources, go to the next installation.
+                    None => continue 'installations,
                 };
 
                 // Pick one of the most suitable sources.
@@ -1526,62 +2958,38 @@ fn tick(model: &mut Model, tick: Tick) {
                     // 2. Movement properties and constraints of the source and group.
                     let initial_position = {
                         let mut rng = nannou::rand::thread_rng();
-                        let left: bool = rng.gen();
-                        let x_mag: f64 = rng.gen();
-                        let x = match left {
-                            true => {
-                                Metres(x_mag)
-                                    * (installation_area.centroid.x
-                                        - installation_area.bounding_rect.left)
-                                    + installation_area.centroid.x
-                            }
-                            false => {
-                                Metres(x_mag)
-                                    * (installation_area.centroid.x
-                                        - installation_area.bounding_rect.right)
-                                    + installation_area.centroid.x
-                            }
-                        };
-                        let down: bool = rng.gen();
-                        let y_mag: f64 = rng.gen();
-                        let y = match down {
-                            true => {
-                                Metres(y_mag)
-                                    * (installation_area.centroid.y
-                                        - installation_area.bounding_rect.bottom)
-                                    + installation_area.centroid.y
-                            }
-                            false => {
-                                Metres(y_mag)
-                                    * (installation_area.centroid.y
-                                        - installation_area.bounding_rect.top)
-                                    + installation_area.centroid.y
-                            }
-                        };
-                        let point = Point2 { x, y };
+                        let others = active_sounds
+                            .values()
+                            .map(|s| (s.handle.source_id(), s.position().point));
+                        let spawned = &sources[&source.id];
+                        let neighbours = separation_neighbours(spawned, sources, groups, others);
+                        let weight = |p| heatmap_probability(spawned, groups, p);
+                        let point = spawn_point(&mut rng, installation_area, &neighbours, weight);
                         let radians = rng.gen::<f32>() * 2.0 * ::std::f32::consts::PI;
-                        audio::sound::Position { point, radians }
+                        let height = spawned.height;
+                        audio::sound::Position { point, radians, height }
                     };
 
                     // Generate the attack and release durations based on their source ranges.
                     let mut rng = nannou::rand::thread_rng();
                     let attack_duration_frames =
                         audio::source::random_playback_duration(&mut rng, source.attack_duration)
-                            .to_samples(audio::SAMPLE_RATE);
+                            .to_samples(audio::sample_rate());
                     let release_duration_frames =
                         audio::source::random_playback_duration(&mut rng, source.release_duration)
-                            .to_samples(audio::SAMPLE_RATE);
+                            .to_samples(audio::sample_rate());
                     let duration_frames =
                         audio::source::random_playback_duration(&mut rng, source.playback_duration)
-                            .to_samples(audio::SAMPLE_RATE);
+                            .to_samples(audio::sample_rate());
 
                     // This is not a continuous preview (this is only used for GUI sounds).
                     let continuous_preview = false;
 
                     // Choose a movement type based on the source's assigned options.
-                    let movement = generate_movement(
+                    let mut movement = generate_movement(
                         source.id,
                         &sources,
+                        groups,
                         *installation,
                         installations,
                         installation_areas,
@@ -1589,8 +2997,18 @@ fn tick(model: &mut Model, tick: Tick) {
                         &active_sounds,
                     );
 
-                    // Spawn the sound from this source
-                    let audio_source = sources[&source.id].to_audio_source();
+                    // Agents begin at the spawn point, clear of the sounds of the same groups.
+                    if let Movement::Generative(ref mut generative) = movement {
+                        if let movement::Generative::Agent(ref mut agent) = *generative {
+                            agent.set_location(initial_position.point);
+                        }
+                    }
+
+                    // Spawn the sound from this source with its randomised effects.
+                    let mut audio_source = sources[&source.id].to_audio_source();
+                    audio_source.effects = audio_source
+                        .effects
+                        .randomised(&mut rng, &sources[&source.id].effect_randomisation);
                     let source_id = source.id;
                     let sound_id = sound_id_gen.generate_next();
                     let sound = audio::sound::spawn_from_source(
@@ -1608,16 +3026,43 @@ fn tick(model: &mut Model, tick: Tick) {
                         audio_output_stream,
                         realtime_source_latency,
                     );
+                    let sound = match sound {
+                        Ok(sound) => sound,
+                        Err(err) => {
+                            let source = &sources[&source_id];
+                            report_missing_wav(source_id, source, err, &mut missing_wavs);
+                            continue;
+                        },
+                    };
+                    let gain = intensity_gain(&sources[&source_id], groups, intensity);
+                    send_intensity_gain(audio_output_stream, sound_id, gain);
+                    send_priority(audio_output_stream, sound_id, sources[&source_id].priority);
+                    let day_gain = &sources[&source_id].day_gain;
+                    if !day_gain.is_empty() {
+                        let gain = day_gain.gain_at(secs_of_day);
+                        send_day_gain(audio_output_stream, sound_id, gain);
+                    }
 
                     // Track the time at which the group and source were last used.
-                    groups_last_used.insert(available_groups[group_index].id, tick.instant);
-                    sources_last_used.insert(source_id, tick.instant);
+                    record_spawn(
+                        group_id,
+                        source_id,
+                        tick.instant,
+                        sources,
+                        groups_last_used,
+                        sources_last_used,
+                        recent_spawns,
+                        groups_last_source,
+                    );
+                    sounds_per_priority[sources[&source_id].priority.to_index()] += 1;
 
                     // Create the active sound for out use.
                     let active_sound = ActiveSound {
                         initial_installation: *installation,
                         handle: sound,
                         movement,
+                        modulation: Default::default(),
+                        follow_up_depth: 0,
                     };
 
                     // Store the new active sound.
@@ -1626,4 +3071,43 @@ fn tick(model: &mut Model, tick: Tick) {
             }
         }
     }
+
+    missing_wavs
+}
+
+// Report that a sound could not be spawned from the given source as its WAV could not be opened.
+fn report_missing_wav(
+    source_id: audio::source::Id,
+    source: &Source,
+    err: hound::Error,
+    missing_wavs: &mut Vec<audio::source::Id>,
+) {
+    if let audio::source::Kind::Wav(ref wav) = source.kind {
+        eprintln!("failed to open WAV \"{}\": {}", wav.path.display(), err);
+    }
+    missing_wavs.push(source_id);
+}
+
+// Mark the WAVs of the given sources as missing so that no more sounds are spawned from them.
+//
+// They are found again once the GUI reloads them, e.g. when the file is restored.
+fn mark_wavs_missing(sources: &mut Sources, missing_wavs: &[audio::source::Id]) {
+    for id in missing_wavs {
+        if let Some(source) = sources.get_mut(id) {
+            if let audio::source::Kind::Wav(ref mut wav) = source.kind {
+                wav.missing = true;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_weighted_index() {
+    let weights = [1.0, 0.0, 3.0];
+    assert_eq!(weighted_index(weights.iter().cloned(), 0.0), 0);
+    assert_eq!(weighted_index(weights.iter().cloned(), 0.2), 0);
+    assert_eq!(weighted_index(weights.iter().cloned(), 0.3), 2);
+    assert_eq!(weighted_index(weights.iter().cloned(), 0.999), 2);
+    // All indices are equally likely when no weight is greater than zero.
+    assert_eq!(weighted_index([0.0, 0.0].iter().cloned(), 0.75), 1);
 }
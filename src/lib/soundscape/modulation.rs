@@ -0,0 +1,162 @@
+//! Slowly varying noise modulating the occurrence rate, spread and volume of a soundscape group.
+//!
+//! Static parameters can make an installation feel mechanical over a long visit. Each modulator
+//! follows the same smooth `noise_walk` that drives the target number of sounds per installation,
+//! with its phase advancing along with the soundscape's playback duration. The parameter is scaled
+//! between the modulator's bounds over roughly one `period`, typically minutes to hours. Every
+//! group and parameter is offset by a unique phase so that they wander independently.
+
+use mindtree_utils::noise_walk;
+use nannou::rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use soundscape::group;
+use time_calc::Ms;
+use utils::{self, Range, Seed, HR_MS, MIN_MS};
+
+/// The shortest period that may be selected via the GUI.
+pub const MIN_PERIOD: Ms = Ms(MIN_MS);
+
+/// The longest period that may be selected via the GUI.
+pub const MAX_PERIOD: Ms = Ms(HR_MS * 4.0);
+
+/// The lowest scale that may be selected via the GUI.
+pub const MIN_SCALE: f32 = 0.25;
+
+/// The highest scale that may be selected via the GUI.
+pub const MAX_SCALE: f32 = 4.0;
+
+/// The noise modulators of a single group.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Modulation {
+    /// Scales the rate at which the group's sounds occur.
+    #[serde(default)]
+    pub rate: Modulator,
+    /// Scales the spread of the channels of the group's sounds.
+    #[serde(default)]
+    pub spread: Modulator,
+    /// Scales the gain of the group's sounds.
+    #[serde(default)]
+    pub volume: Modulator,
+}
+
+/// A noise generator scaling a single parameter.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Modulator {
+    #[serde(default)]
+    pub enabled: bool,
+    /// The bounds of the scale applied to the parameter.
+    #[serde(default = "default::range")]
+    pub range: Range<f32>,
+    /// The rough duration over which the scale wanders between its bounds.
+    #[serde(default = "default::period")]
+    pub period: Ms,
+}
+
+/// The scales applied by a group's modulators at a single moment.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Scales {
+    pub rate: f32,
+    pub spread: f32,
+    pub volume: f32,
+}
+
+pub mod default {
+    use time_calc::Ms;
+    use utils::{Range, MIN_MS};
+
+    pub fn range() -> Range<f32> {
+        Range { min: 0.5, max: 2.0 }
+    }
+
+    pub fn period() -> Ms {
+        Ms(MIN_MS * 20.0)
+    }
+}
+
+impl Modulation {
+    /// Whether or not any of the modulators are enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.rate.enabled || self.spread.enabled || self.volume.enabled
+    }
+
+    /// The scales of the given group after the given duration of playback.
+    pub fn scales_at(&self, seed: &Seed, group: &group::Id, playback_secs: f64) -> Scales {
+        if !self.is_enabled() {
+            return Scales::default();
+        }
+        let mut seed = utils::add_seeds(seed, &group_seed(group));
+        if seed == [0; 16] {
+            seed[0] = 1;
+        }
+        // Spread the offsets over many periods so that the parameters are uncorrelated.
+        let mut rng = XorShiftRng::from_seed(seed);
+        let mut offset = || rng.gen::<f64>() * 1_000.0;
+        Scales {
+            rate: self.rate.scale_at(offset(), playback_secs),
+            spread: self.spread.scale_at(offset(), playback_secs),
+            volume: self.volume.scale_at(offset(), playback_secs),
+        }
+    }
+}
+
+impl Modulator {
+    /// The scale at the given phase offset after the given duration of playback.
+    ///
+    /// Returns `1.0` while disabled.
+    pub fn scale_at(&self, phase_offset: f64, playback_secs: f64) -> f32 {
+        if !self.enabled {
+            return 1.0;
+        }
+        let period_secs = (self.period.ms() / 1_000.0).max(1.0);
+        let phase = phase_offset + playback_secs / period_secs;
+        // Amplify the noise_walk slightly so that it occasionally reaches min and max.
+        let amp = (noise_walk(phase) * 1.5).min(1.0).max(-1.0);
+        let t = (amp * 0.5 + 0.5) as f32;
+        // Interpolate geometrically so that ranges symmetric about `1.0` centre on it.
+        let min = self.range.min.max(::std::f32::EPSILON);
+        let max = self.range.max.max(::std::f32::EPSILON);
+        min * (max / min).powf(t)
+    }
+}
+
+impl Default for Modulator {
+    fn default() -> Self {
+        Modulator {
+            enabled: false,
+            range: default::range(),
+            period: default::period(),
+        }
+    }
+}
+
+impl Default for Scales {
+    fn default() -> Self {
+        Scales {
+            rate: 1.0,
+            spread: 1.0,
+            volume: 1.0,
+        }
+    }
+}
+
+// A unique seed for the given group.
+fn group_seed(group: &group::Id) -> Seed {
+    let mut seed = [0; 16];
+    for (i, byte) in seed.iter_mut().enumerate() {
+        *byte = (group.0 as u64 >> ((i % 8) * 8)) as u8;
+    }
+    seed
+}
+
+#[test]
+fn test_modulator_scale() {
+    let mut modulator = Modulator::default();
+    assert_eq!(modulator.scale_at(0.0, 1_000.0), 1.0);
+    modulator.enabled = true;
+    for i in 0..100 {
+        let scale = modulator.scale_at(0.0, i as f64 * 60.0);
+        assert!(scale >= 0.5 - 1e-6 && scale <= 2.0 + 1e-6);
+    }
+    let modulation = Modulation::default();
+    assert_eq!(modulation.scales_at(&[0; 16], &group::Id(0), 0.0), Scales::default());
+}
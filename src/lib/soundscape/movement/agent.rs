@@ -10,6 +10,9 @@ use utils::{self, duration_to_secs, pt2, vt2};
 // The minimum distance that the point may be from the target before it may switch to the next.
 const TARGET_DISTANCE_THRESHOLD: Metres = Metres(1.0);
 
+// The weight of the separation force relative to the target seeking force.
+const SEPARATION_WEIGHT: f64 = 2.0;
+
 // The point and vector types in exhibition space.
 type Point = Point2<Metres>;
 type Vector = Vector2<Metres>;
@@ -58,7 +61,9 @@ pub type InstallationDataMap = FxHashMap<installation::Id, InstallationData>;
 
 impl Agent {
     /// Generate a new agent starting in the given installation area.
-    pub fn generate<R>(
+    ///
+    /// Locations are weighted by the given `weight`, e.g. the heatmaps of the source's groups.
+    pub fn generate<R, F>(
         mut rng: R,
         start_installation: installation::Id,
         installations: &InstallationDataMap,
@@ -66,15 +71,17 @@ impl Agent {
         max_force: f64,
         max_rotation: f64,
         directional: bool,
+        weight: F,
     ) -> Self
     where
         R: Rng,
+        F: Fn(Point) -> f32,
     {
         let installation_data = installations
             .get(&start_installation)
             .expect("no `InstallationData` for given for `start_installation`");
-        let location = generate_installation_target(&mut rng, &installation_data.area);
-        let target_location = generate_target(&mut rng, installations);
+        let location = generate_installation_target(&mut rng, &installation_data.area, &weight);
+        let target_location = generate_target(&mut rng, installations, &weight);
         // Generate these based on "weight" or whatever user params are decided upon.
         let start_magnitude = rng.gen::<f64>() * max_speed;
         let desired_velocity = desired_velocity(location, target_location);
@@ -107,7 +114,13 @@ impl Agent {
         } else {
             0.0
         };
-        audio::sound::Position { point, radians }
+        let height = Metres(0.0);
+        audio::sound::Position { point, radians, height }
+    }
+
+    /// Move the agent to the given location, e.g. a spawn point clear of other sounds.
+    pub fn set_location(&mut self, location: Point) {
+        self.location = location;
     }
 
     /// Produce the agent's target seeking force for its current state.
@@ -123,6 +136,20 @@ impl Agent {
         )
     }
 
+    /// Produce a force steering the agent away from the neighbours that are closer than their
+    /// given minimum separation.
+    ///
+    /// The force is in `Metres` per second and should be applied accordingly.
+    pub fn separation_force(&self, neighbours: &[(Point, Metres)]) -> Vector {
+        separation_force(
+            self.location,
+            self.velocity,
+            neighbours,
+            self.max_speed,
+            self.max_force,
+        )
+    }
+
     /// Applies the given force to the agent, updating its internal state appropriately.
     pub fn apply_force(&mut self, force: Vector, delta_time: &time::Duration) {
         use std::f64::consts::PI;
@@ -155,29 +182,38 @@ impl Agent {
     }
 
     /// Update the agent for the given past amount of time.
-    pub fn update<R>(
+    ///
+    /// The agent steers away from each of the given `neighbours` that are closer than their
+    /// minimum separation. New targets are weighted by the given `weight`.
+    pub fn update<R, F>(
         &mut self,
         mut rng: R,
         delta_time: &time::Duration,
         installations: &InstallationDataMap,
+        neighbours: &[(Point, Metres)],
+        weight: F,
     ) where
         R: Rng,
+        F: Fn(Point) -> f32,
     {
         // We can't know where to go if there are no assigned installations.
         if !installations.is_empty() {
             if should_pick_new_target(self.location, self.target_location, &installations) {
-                self.target_location = generate_target(&mut rng, &installations);
+                self.target_location = generate_target(&mut rng, &installations, &weight);
             }
         }
 
-        // Determine the steering force to apply based on how much time has passed.
-        let force = self.seek_force();
+        // Determine the steering force to apply based on how much time has passed, favouring
+        // separation from neighbours over reaching the target.
+        let seek = vt2::to_f64(self.seek_force());
+        let separation = vt2::to_f64(self.separation_force(neighbours));
+        let force = vt2::to_metres(seek + separation * SEPARATION_WEIGHT);
         self.apply_force(force, delta_time);
 
         // If we've reached the target, pick a new one.
         if reached_target(self.location, self.target_location) {
             if !installations.is_empty() {
-                self.target_location = generate_target(rng, installations);
+                self.target_location = generate_target(rng, installations, &weight);
             }
         }
     }
@@ -260,9 +296,10 @@ fn installation_suitability_order(a: &InstallationData, b: &InstallationData) ->
 }
 
 /// Generate a new target within one of the given installations.
-fn generate_target<R>(mut rng: R, installations: &InstallationDataMap) -> Point
+fn generate_target<R, F>(mut rng: R, installations: &InstallationDataMap, weight: F) -> Point
 where
     R: Rng,
+    F: Fn(Point) -> f32,
 {
     // Collect references to installation data into a `Vec` that we can sort by target suitability.
     let mut vec: Vec<_> = installations.values().collect();
@@ -271,19 +308,16 @@ where
     // Randomly select one of the installations from the front of the vec.
     let index = (rng.gen::<f32>().powi(4) * vec.len() as f32) as usize;
     let data = &vec[index];
-    generate_installation_target(rng, &data.area)
+    generate_installation_target(rng, &data.area, weight)
 }
 
 /// Generate a target location within the given installation.
-fn generate_installation_target<R>(mut rng: R, installation_area: &super::Area) -> Point
+fn generate_installation_target<R, F>(mut rng: R, area: &super::Area, weight: F) -> Point
 where
     R: Rng,
+    F: Fn(Point) -> f32,
 {
-    let x_len = installation_area.bounding_rect.right - installation_area.bounding_rect.left;
-    let y_len = installation_area.bounding_rect.top - installation_area.bounding_rect.bottom;
-    let x = installation_area.bounding_rect.left + x_len * rng.gen::<f64>();
-    let y = installation_area.bounding_rect.bottom + y_len * rng.gen::<f64>();
-    Point2 { x, y }
+    area.random_weighted_point(&mut rng, weight)
 }
 
 /// Whether or not the current point has reached the target.
@@ -337,3 +371,45 @@ fn seek_force(
     let steering_limited = limit_magnitude(steering_force, max_force);
     steering_limited
 }
+
+/// Produces a force that steers an agent away from neighbours closer than their separation.
+///
+/// Closer neighbours repel more strongly. Returns a zero vector if no neighbours are too close.
+fn separation_force(
+    current_position: Point,
+    current_velocity: Vector,
+    neighbours: &[(Point, Metres)],
+    max_speed: f64,
+    max_force: f64,
+) -> Vector {
+    let position = pt2::to_f64(current_position);
+    let mut away = vec2(0.0, 0.0);
+    for &(point, separation) in neighbours {
+        let offset = position - pt2::to_f64(point);
+        let distance = offset.magnitude();
+        if distance >= separation.0 {
+            continue;
+        }
+        // Neighbours at the exact same location are escaped in an arbitrary direction.
+        let direction = if distance > 0.0 { offset / distance } else { vec2(1.0, 0.0) };
+        away += direction * (1.0 - distance / separation.0);
+    }
+    if away.magnitude2() == 0.0 {
+        return vt2::to_metres(away);
+    }
+    let desired = vt2::to_metres(away.normalize() * max_speed);
+    let steering_force = steering_force(current_velocity, desired);
+    limit_magnitude(steering_force, max_force)
+}
+
+#[test]
+fn test_separation_force() {
+    let p = |x, y| Point2 { x: Metres(x), y: Metres(y) };
+    let velocity = vt2::to_metres(vec2(0.0, 0.0));
+    let neighbours = [(p(1.0, 0.0), Metres(2.0)), (p(10.0, 0.0), Metres(2.0))];
+    let force = vt2::to_f64(separation_force(p(0.0, 0.0), velocity, &neighbours, 1.0, 1.0));
+    assert!(force.x < 0.0 && force.y == 0.0);
+    let far = [(p(5.0, 0.0), Metres(2.0))];
+    let force = vt2::to_f64(separation_force(p(0.0, 0.0), velocity, &far, 1.0, 1.0));
+    assert_eq!(force, vec2(0.0, 0.0));
+}
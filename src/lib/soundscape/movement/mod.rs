@@ -1,12 +1,22 @@
 use audio;
 use metres::Metres;
 use nannou::prelude::*;
+use nannou::rand::Rng;
+use std::cmp;
 
 pub use self::agent::Agent;
 pub use self::ngon::Ngon;
+pub use self::path::Path;
 
 pub mod agent;
 pub mod ngon;
+pub mod path;
+
+/// The number of points describing the rounded corners of an area expanded by a margin.
+const MARGIN_RESOLUTION: usize = 16;
+
+/// The number of points sampled from the bounding box in search of one within an area.
+const MAX_SAMPLE_ATTEMPTS: usize = 32;
 
 /// Whether the sound has fixed movement or generative movement.
 #[derive(Debug)]
@@ -15,6 +25,8 @@ pub enum Movement {
     Fixed(audio::sound::Position),
     /// The sound's movement is guided by a generative algorithm.
     Generative(Generative),
+    /// The sound travels along a path drawn over the floorplan.
+    Path(Path),
 }
 
 /// Generative movement kinds applied to an active sound.
@@ -35,11 +47,20 @@ pub struct BoundingRect {
     pub bottom: Metres,
 }
 
-/// Includes the bounding box and
-#[derive(Copy, Clone, Debug)]
+/// The region within which an installation's sounds may spawn and move.
+///
+/// Determined via the convex hull of the installation's speakers, expanded by its margin.
+#[derive(Clone, Debug)]
 pub struct Area {
+    /// The bounding box of the hull.
     pub bounding_rect: BoundingRect,
+    /// The centroid of the speakers.
     pub centroid: Point2<Metres>,
+    /// The vertices of the hull in counter-clockwise order.
+    ///
+    /// Contains fewer than three vertices if the speakers lie along a single line and there is no
+    /// margin.
+    pub hull: Vec<Point2<Metres>>,
 }
 
 impl Generative {
@@ -58,10 +79,89 @@ impl Movement {
         match *self {
             Movement::Fixed(position) => position,
             Movement::Generative(ref generative) => generative.position(),
+            Movement::Path(ref path) => path.position(),
         }
     }
 }
 
+impl Area {
+    /// Determine the area enclosing the given speaker points, expanded by the given margin.
+    ///
+    /// Returns `None` if there are no points.
+    pub fn from_points<I>(points: I, margin: Metres) -> Option<Self>
+    where
+        I: IntoIterator<Item = Point2<Metres>>,
+    {
+        let points: Vec<_> = points.into_iter().collect();
+        if points.is_empty() {
+            return None;
+        }
+        let n = points.len() as f64;
+        let centroid = points.iter().fold(pt2(Metres(0.0), Metres(0.0)), |acc, p| {
+            pt2(acc.x + p.x / n, acc.y + p.y / n)
+        });
+        let hull = expanded_hull(&points, margin);
+        let bounding_rect = match BoundingRect::from_points(hull.iter().cloned()) {
+            None => return None,
+            Some(rect) => rect,
+        };
+        Some(Area { bounding_rect, centroid, hull })
+    }
+
+    /// Whether or not the given point lies within the hull.
+    pub fn contains(&self, p: Point2<Metres>) -> bool {
+        hull_contains(&self.hull, p)
+    }
+
+    /// Generate a random point within the area.
+    ///
+    /// Samples the bounding box until a point within the hull is found, falling back to the
+    /// centroid. Points are generated along the hull if it has no width.
+    pub fn random_point<R>(&self, mut rng: R) -> Point2<Metres>
+    where
+        R: Rng,
+    {
+        match self.hull.len() {
+            0 => return self.centroid,
+            1 => return self.hull[0],
+            2 => {
+                let (a, b) = (self.hull[0], self.hull[1]);
+                let lerp = rng.gen::<f64>();
+                return pt2(a.x + (b.x - a.x) * lerp, a.y + (b.y - a.y) * lerp);
+            },
+            _ => (),
+        }
+        let rect = &self.bounding_rect;
+        for _ in 0..MAX_SAMPLE_ATTEMPTS {
+            let x = rect.left + rect.width() * rng.gen::<f64>();
+            let y = rect.bottom + rect.height() * rng.gen::<f64>();
+            let p = pt2(x, y);
+            if self.contains(p) {
+                return p;
+            }
+        }
+        self.centroid
+    }
+
+    /// Generate a random point within the area, accepting each candidate with the probability
+    /// returned by `weight`, e.g. that of a group's heatmap.
+    ///
+    /// Falls back to an unweighted point if no candidate is accepted.
+    pub fn random_weighted_point<R, F>(&self, mut rng: R, weight: F) -> Point2<Metres>
+    where
+        R: Rng,
+        F: Fn(Point2<Metres>) -> f32,
+    {
+        for _ in 0..MAX_SAMPLE_ATTEMPTS {
+            let p = self.random_point(&mut rng);
+            if rng.gen::<f32>() < weight(p) {
+                return p;
+            }
+        }
+        self.random_point(rng)
+    }
+}
+
 impl BoundingRect {
     /// Initialise a bounding box at a single point in space.
     pub fn from_point(p: Point2<Metres>) -> Self {
@@ -112,3 +212,94 @@ impl BoundingRect {
         self.top - self.bottom
     }
 }
+
+/// The convex hull of the given points in counter-clockwise order.
+///
+/// Points lying along the edges of the hull are omitted, so the hull of points along a single line
+/// is its two ends.
+pub fn convex_hull(points: &[Point2<Metres>]) -> Vec<Point2<Metres>> {
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| (a.x, a.y).partial_cmp(&(b.x, b.y)).unwrap_or(cmp::Ordering::Equal));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    // Andrew's monotone chain: the lower half from left to right then the upper from right to left.
+    let mut hull: Vec<Point2<Metres>> = Vec::with_capacity(points.len() + 1);
+    for &p in &points {
+        while hull.len() >= 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    let lower_len = hull.len() + 1;
+    for &p in points.iter().rev().skip(1) {
+        while hull.len() >= lower_len && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+        {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    // The last point is the first.
+    hull.pop();
+    hull
+}
+
+/// The convex hull of the given points expanded outward by the given margin.
+///
+/// The corners of the expanded hull are rounded.
+pub fn expanded_hull(points: &[Point2<Metres>], margin: Metres) -> Vec<Point2<Metres>> {
+    let hull = convex_hull(points);
+    if margin <= Metres(0.0) {
+        return hull;
+    }
+    let around = hull
+        .iter()
+        .flat_map(|&p| {
+            (0..MARGIN_RESOLUTION).map(move |i| {
+                let radians = i as f64 / MARGIN_RESOLUTION as f64 * 2.0 * ::std::f64::consts::PI;
+                pt2(p.x + margin * radians.cos(), p.y + margin * radians.sin())
+            })
+        })
+        .collect::<Vec<_>>();
+    convex_hull(&around)
+}
+
+/// Whether or not the given point lies within the given counter-clockwise convex hull.
+///
+/// Always `false` for hulls with fewer than three vertices.
+pub fn hull_contains(hull: &[Point2<Metres>], p: Point2<Metres>) -> bool {
+    if hull.len() < 3 {
+        return false;
+    }
+    hull.iter()
+        .zip(hull.iter().cycle().skip(1))
+        .all(|(&a, &b)| cross(a, b, p) >= 0.0)
+}
+
+// The cross product of `oa` and `ob`, positive if `o`, `a` and `b` turn counter-clockwise.
+fn cross(o: Point2<Metres>, a: Point2<Metres>, b: Point2<Metres>) -> f64 {
+    (a.x - o.x).0 * (b.y - o.y).0 - (a.y - o.y).0 * (b.x - o.x).0
+}
+
+#[test]
+fn test_area_hull() {
+    let p = |x, y| pt2(Metres(x), Metres(y));
+    let speakers = [p(0.0, 0.0), p(2.0, 0.0), p(1.0, 1.0), p(2.0, 2.0), p(0.0, 2.0), p(1.0, 0.0)];
+    let hull = convex_hull(&speakers);
+    assert_eq!(hull, vec![p(0.0, 0.0), p(2.0, 0.0), p(2.0, 2.0), p(0.0, 2.0)]);
+    let line = [p(0.0, 0.0), p(1.0, 1.0), p(2.0, 2.0)];
+    assert_eq!(convex_hull(&line), vec![p(0.0, 0.0), p(2.0, 2.0)]);
+
+    let area = Area::from_points(speakers.iter().cloned(), Metres(0.0)).unwrap();
+    assert!(area.contains(p(1.0, 1.5)));
+    assert!(!area.contains(p(2.5, 1.0)));
+    let area = Area::from_points(speakers.iter().cloned(), Metres(1.0)).unwrap();
+    assert!(area.contains(p(2.5, 1.0)));
+    assert!(!area.contains(p(3.5, 1.0)));
+    assert!(area.bounding_rect.right > Metres(2.9));
+    let mut rng = ::nannou::rand::thread_rng();
+    for _ in 0..100 {
+        assert!(area.contains(area.random_point(&mut rng)));
+    }
+}
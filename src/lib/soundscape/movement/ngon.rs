@@ -81,7 +81,8 @@ impl Ngon {
             normalised_dimensions,
         );
         let point = vertex_at_index(vertices, middle, half_dim, radians_offset, 0);
-        let sound_position = audio::sound::Position { point, radians };
+        let height = Metres(0.0);
+        let sound_position = audio::sound::Position { point, radians, height };
         let state = State { sound_position, position };
         Ngon {
             vertices,
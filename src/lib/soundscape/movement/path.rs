@@ -0,0 +1,141 @@
+use audio;
+use audio::source::movement::PathPoint;
+use metres::Metres;
+use nannou::prelude::*;
+use std::time;
+use utils::duration_to_secs;
+
+/// Travels along a path drawn over the floorplan.
+///
+/// Sounds begin at the first point of the path. Sounds on paths that are not looped come to rest at
+/// the last point, while sounds on looped paths continue from the first point again.
+#[derive(Debug)]
+pub struct Path {
+    /// The path as straight segments, each beginning at a point with its speed.
+    polyline: Vec<PathPoint>,
+    looped: bool,
+    /// The index of the segment currently being travelled.
+    segment: usize,
+    /// The distance travelled along the current segment in metres.
+    travelled: f64,
+    /// The position along the path described in "metres" space over the exhibition.
+    sound_position: audio::sound::Position,
+}
+
+impl Path {
+    /// Begin travelling along the given path.
+    ///
+    /// Returns `None` if the path has no points.
+    pub fn new(path: &audio::source::movement::Path) -> Option<Self> {
+        let polyline = path.polyline();
+        let first = match polyline.first() {
+            Some(first) => first.point,
+            None => return None,
+        };
+        let sound_position = audio::sound::Position {
+            point: first,
+            radians: 0.0,
+            height: Metres(0.0),
+        };
+        let mut path = Path {
+            polyline,
+            looped: path.looped,
+            segment: 0,
+            travelled: 0.0,
+            sound_position,
+        };
+        path.update_sound_position();
+        Some(path)
+    }
+
+    /// The current position along the path.
+    pub fn position(&self) -> audio::sound::Position {
+        self.sound_position
+    }
+
+    /// Travel along the path for the given past amount of time.
+    pub fn update(&mut self, delta_time: &time::Duration) {
+        let n_segments = self.polyline.len().saturating_sub(1);
+        let mut secs = duration_to_secs(delta_time);
+        // Bound the number of segments crossed in a single update in case every segment of a
+        // looped path has no length.
+        for _ in 0..n_segments + 1 {
+            if secs <= 0.0 || self.segment >= n_segments {
+                break;
+            }
+            let (length, speed) = self.segment_length_and_speed();
+            if speed <= 0.0 {
+                break;
+            }
+            let secs_remaining = (length - self.travelled).max(0.0) / speed;
+            if secs < secs_remaining {
+                self.travelled += secs * speed;
+                break;
+            }
+            secs -= secs_remaining;
+            self.travelled = 0.0;
+            self.segment += 1;
+            if self.segment == n_segments && self.looped {
+                self.segment = 0;
+            }
+        }
+        self.update_sound_position();
+    }
+
+    // The length of the current segment and the speed at which it is travelled.
+    fn segment_length_and_speed(&self) -> (f64, f64) {
+        let start = &self.polyline[self.segment];
+        let end = &self.polyline[self.segment + 1];
+        let (dx, dy) = (end.point.x.0 - start.point.x.0, end.point.y.0 - start.point.y.0);
+        (dx.hypot(dy), start.speed)
+    }
+
+    // Update the point and orientation of the sound from the current segment.
+    fn update_sound_position(&mut self) {
+        if self.segment + 1 >= self.polyline.len() {
+            if let Some(last) = self.polyline.last() {
+                self.sound_position.point = last.point;
+            }
+            return;
+        }
+        let start = self.polyline[self.segment].point;
+        let end = self.polyline[self.segment + 1].point;
+        let (length, _) = self.segment_length_and_speed();
+        let lerp = if length > 0.0 { (self.travelled / length).min(1.0) } else { 0.0 };
+        let (dx, dy) = (end.x.0 - start.x.0, end.y.0 - start.y.0);
+        self.sound_position.point = Point2 {
+            x: Metres(start.x.0 + dx * lerp),
+            y: Metres(start.y.0 + dy * lerp),
+        };
+        if length > 0.0 {
+            self.sound_position.radians = dy.atan2(dx) as f32;
+        }
+    }
+}
+
+#[test]
+fn test_path_travel() {
+    let point = |x, y, speed| PathPoint { point: Point2 { x: Metres(x), y: Metres(y) }, speed };
+    let source_path = audio::source::movement::Path {
+        points: vec![point(0.0, 0.0, 1.0), point(2.0, 0.0, 2.0), point(2.0, 2.0, 1.0)],
+        looped: false,
+        smooth: false,
+    };
+    let mut path = Path::new(&source_path).unwrap();
+    // One second at 1m/s along the first segment.
+    path.update(&time::Duration::from_secs(1));
+    assert_eq!(path.position().point, Point2 { x: Metres(1.0), y: Metres(0.0) });
+    // A further second and a half crosses onto the second segment, travelled at 2m/s.
+    path.update(&time::Duration::from_millis(1_500));
+    assert_eq!(path.position().point, Point2 { x: Metres(2.0), y: Metres(1.0) });
+    // Sounds come to rest at the end of paths that are not looped.
+    path.update(&time::Duration::from_secs(10));
+    assert_eq!(path.position().point, Point2 { x: Metres(2.0), y: Metres(2.0) });
+
+    // Looped paths return to the first point.
+    let looped = audio::source::movement::Path { looped: true, ..source_path };
+    assert_eq!(looped.polyline().len(), 4);
+    let mut path = Path::new(&looped).unwrap();
+    path.update(&time::Duration::from_millis(2_000 + 1_000 + 2_000));
+    assert_eq!(path.segment, 2);
+}
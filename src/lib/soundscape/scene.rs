@@ -0,0 +1,228 @@
+//! Named sets of soundscape parameters, e.g. "day" and "night", crossfaded over a duration.
+//!
+//! Each group may assign a level to each scene. The level scales the group's occurrence rate,
+//! simultaneous sounds and gain while the scene is current, where groups without a level for the
+//! scene play as usual. Hard-switching between scenes would make every active sound change
+//! character at once, so the soundscape instead crossfades the level of each group from its
+//! current value towards that of the newly cued scene. Scenes are cued at scheduled times of day at
+//! the venue (see the project's time zone) or via OSC.
+
+use audio::blackout::{self, TimeOfDay};
+use fxhash::FxHashMap;
+use soundscape::group::{self, Group};
+
+/// The greatest level that may be assigned to a group within a scene.
+pub const MAX_LEVEL: f32 = 2.0;
+
+/// The longest crossfade that may be selected via the GUI.
+pub const MAX_CROSSFADE_SECS: f64 = 60.0 * 10.0;
+
+/// The scenes of the soundscape and the times of day at which they are cued.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Scenes {
+    /// The unique names of all scenes.
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// The scene most recently cued, if any.
+    #[serde(default)]
+    pub current: Option<String>,
+    /// The duration over which the soundscape crossfades towards a newly cued scene.
+    #[serde(default = "default::crossfade_secs")]
+    pub crossfade_secs: f64,
+    /// The scenes cued throughout the day, sorted by time of day.
+    #[serde(default)]
+    pub schedule: Vec<Cue>,
+}
+
+/// A scene cued at a time of day.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Cue {
+    pub time: TimeOfDay,
+    pub scene: String,
+}
+
+/// The levels assigned to a group within each scene.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Levels {
+    #[serde(default)]
+    pub levels: Vec<Level>,
+}
+
+/// The level of a group within a single scene.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Level {
+    pub scene: String,
+    pub level: f32,
+}
+
+/// The crossfade of each group's level towards the current scene on the soundscape thread.
+#[derive(Clone, Debug, Default)]
+pub struct Crossfade {
+    // The scene towards which the groups are fading, if any.
+    scene: Option<String>,
+    // The level of each group at the start of the crossfade.
+    from: FxHashMap<group::Id, f32>,
+    // The duration of the crossfade.
+    secs: f64,
+    // The duration since the crossfade began.
+    elapsed_secs: f64,
+}
+
+pub mod default {
+    pub fn crossfade_secs() -> f64 {
+        60.0
+    }
+}
+
+impl Scenes {
+    /// Whether or not a scene with the given name exists.
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.iter().any(|n| n == name)
+    }
+
+    /// The scene cued after `previous_secs` and up to and including `now_secs`, both in seconds
+    /// since midnight, if any.
+    ///
+    /// If more than one scene was cued, the latest within the schedule is returned.
+    pub fn due(&self, previous_secs: u32, now_secs: u32) -> Option<&str> {
+        self.schedule
+            .iter()
+            .filter(|cue| blackout::is_due(&[cue.time], previous_secs, now_secs))
+            .last()
+            .map(|cue| &cue.scene[..])
+    }
+}
+
+impl Default for Scenes {
+    fn default() -> Self {
+        Scenes {
+            names: Vec::new(),
+            current: None,
+            crossfade_secs: default::crossfade_secs(),
+            schedule: Vec::new(),
+        }
+    }
+}
+
+impl Levels {
+    /// The level within the given scene, or `1.0` if the scene assigns none.
+    pub fn get(&self, scene: &str) -> f32 {
+        self.levels
+            .iter()
+            .find(|l| l.scene == scene)
+            .map(|l| l.level)
+            .unwrap_or(1.0)
+    }
+
+    /// Assign the level within the given scene.
+    pub fn set(&mut self, scene: &str, level: f32) {
+        self.remove(scene);
+        self.levels.push(Level { scene: scene.to_string(), level });
+    }
+
+    /// Remove any level assigned within the given scene.
+    pub fn remove(&mut self, scene: &str) {
+        self.levels.retain(|l| l.scene != scene);
+    }
+}
+
+impl Crossfade {
+    /// The scene towards which the groups are fading, if any.
+    pub fn scene(&self) -> Option<&str> {
+        self.scene.as_ref().map(|s| &s[..])
+    }
+
+    /// Begin crossfading each of the given groups from its current level towards its level
+    /// within the given scene over the given duration.
+    pub fn start<'a, I>(&mut self, scene: String, secs: f64, groups: I)
+    where
+        I: IntoIterator<Item = (&'a group::Id, &'a Group)>,
+    {
+        let from = groups.into_iter().map(|(id, group)| (*id, self.level(id, group))).collect();
+        self.from = from;
+        self.scene = Some(scene);
+        self.secs = secs.max(0.0);
+        self.elapsed_secs = 0.0;
+    }
+
+    /// Progress the crossfade by the given duration.
+    pub fn update(&mut self, delta_secs: f64) {
+        self.elapsed_secs += delta_secs;
+    }
+
+    /// The level of the given group at the current point within the crossfade.
+    pub fn level(&self, id: &group::Id, group: &Group) -> f32 {
+        let to = match self.scene {
+            Some(ref scene) => group.scene_levels.get(scene),
+            None => 1.0,
+        };
+        if self.elapsed_secs >= self.secs {
+            return to;
+        }
+        let from = self.from.get(id).cloned().unwrap_or(1.0);
+        let t = (self.elapsed_secs / self.secs) as f32;
+        from + (to - from) * t
+    }
+}
+
+/// Parse a schedule from comma separated `HH:MM=scene` cues.
+///
+/// The resulting cues are sorted by time of day.
+pub fn parse_schedule(s: &str) -> Result<Vec<Cue>, String> {
+    let mut schedule = s
+        .split(',')
+        .map(|c| c.trim())
+        .filter(|c| !c.is_empty())
+        .map(|c| {
+            let err = || format!("invalid cue `{}`, expected `HH:MM=scene`", c);
+            let mut parts = c.splitn(2, '=');
+            let time: TimeOfDay = parts.next().ok_or_else(err)?.trim().parse()?;
+            let scene = parts.next().map(|s| s.trim()).filter(|s| !s.is_empty()).ok_or_else(err)?;
+            Ok(Cue { time, scene: scene.to_string() })
+        })
+        .collect::<Result<Vec<Cue>, _>>()?;
+    schedule.sort_by_key(|c| c.time);
+    schedule.dedup_by_key(|c| c.time);
+    Ok(schedule)
+}
+
+/// Format a schedule as comma separated `HH:MM=scene` cues.
+pub fn schedule_string(schedule: &[Cue]) -> String {
+    schedule
+        .iter()
+        .map(|c| format!("{}={}", c.time, c.scene))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[test]
+fn test_scenes() {
+    let schedule = parse_schedule("20:00=night, 07:30 = day").unwrap();
+    assert_eq!(schedule_string(&schedule), "07:30=day, 20:00=night");
+    assert!(parse_schedule("20:00").is_err());
+    let scenes = Scenes { schedule, ..Default::default() };
+    let hour = 60 * 60;
+    assert_eq!(scenes.due(20 * hour - 1, 20 * hour), Some("night"));
+    assert_eq!(scenes.due(20 * hour, 20 * hour + 1), None);
+
+    let (a, b) = (group::Id(0), group::Id(1));
+    let mut groups: FxHashMap<group::Id, Group> = FxHashMap::default();
+    groups.insert(a, Group::default());
+    groups.insert(b, Group::default());
+    groups.get_mut(&a).unwrap().scene_levels.set("night", 0.0);
+
+    // Groups fade from their current level towards the scene's and remain there.
+    let mut crossfade = Crossfade::default();
+    assert_eq!(crossfade.level(&a, &groups[&a]), 1.0);
+    crossfade.start("night".into(), 10.0, &groups);
+    crossfade.update(5.0);
+    assert_eq!(crossfade.level(&a, &groups[&a]), 0.5);
+    assert_eq!(crossfade.level(&b, &groups[&b]), 1.0);
+    crossfade.update(5.0);
+    assert_eq!(crossfade.level(&a, &groups[&a]), 0.0);
+
+    // Crossfades cued midway begin from the current level.
+    crossfade.start("day".into(), 10.0, &groups);
+    crossfade.update(5.0);
+    assert_eq!(crossfade.level(&a, &groups[&a]), 0.5);
+}
@@ -0,0 +1,87 @@
+//! A 24-hour density curve for soundscape groups and installations.
+//!
+//! Exhibitions often run for many hours a day and a single static density rarely suits all of them,
+//! e.g. sparse mornings and dense evenings. A schedule describes a scale applied to the occurrence
+//! rate and simultaneous sounds of a group, or to the simultaneous sounds of an installation, at
+//! each time of day at the venue (see the project's time zone). The scale is interpolated linearly
+//! between neighbouring points, wrapping around midnight, and evaluated on every soundscape tick.
+//! A schedule with no points leaves the density untouched.
+
+use audio::blackout::TimeOfDay;
+use audio::source::day_gain;
+
+/// The greatest density that may be assigned to a point.
+pub const MAX_DENSITY: f32 = 4.0;
+
+/// A 24-hour density curve.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Schedule {
+    /// The points of the curve, sorted by time of day.
+    #[serde(default)]
+    pub points: Vec<Point>,
+}
+
+/// The density of the curve at a single time of day.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Point {
+    pub time: TimeOfDay,
+    /// The scale applied to the occurrence rate and the number of simultaneous sounds.
+    pub density: f32,
+}
+
+impl Schedule {
+    /// Whether or not the schedule affects the density at all.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The density at the given number of seconds since midnight.
+    pub fn density_at(&self, secs_of_day: u32) -> f32 {
+        day_gain::interpolate(&self.points, secs_of_day, |p| (p.time, p.density)).unwrap_or(1.0)
+    }
+}
+
+/// Parse a schedule from comma or whitespace separated `HH:MM=density` points.
+///
+/// The resulting points are sorted by time of day.
+pub fn parse_points(s: &str) -> Result<Vec<Point>, String> {
+    let mut points = s
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let err = || format!("invalid point `{}`, expected `HH:MM=density`", p);
+            let mut parts = p.splitn(2, '=');
+            let time: TimeOfDay = parts.next().ok_or_else(err)?.parse()?;
+            let density: f32 = parts.next().and_then(|d| d.parse().ok()).ok_or_else(err)?;
+            if !(density >= 0.0 && density <= MAX_DENSITY) {
+                return Err(format!("density `{}` must be between 0 and {}", density, MAX_DENSITY));
+            }
+            Ok(Point { time, density })
+        })
+        .collect::<Result<Vec<Point>, _>>()?;
+    points.sort_by_key(|p| p.time);
+    points.dedup_by_key(|p| p.time);
+    Ok(points)
+}
+
+/// Format a schedule as comma separated `HH:MM=density` points.
+pub fn points_string(points: &[Point]) -> String {
+    points
+        .iter()
+        .map(|p| format!("{}={}", p.time, p.density))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[test]
+fn test_schedule() {
+    let points = parse_points("20:00=2, 08:00=0.5").unwrap();
+    assert_eq!(points_string(&points), "08:00=0.5, 20:00=2");
+    assert!(parse_points("08:00=5").is_err());
+    let schedule = Schedule { points };
+    let hour = 60 * 60;
+    assert_eq!(schedule.density_at(8 * hour), 0.5);
+    assert_eq!(schedule.density_at(14 * hour), 1.25);
+    assert_eq!(schedule.density_at(2 * hour), 1.25);
+    assert_eq!(Schedule::default().density_at(0), 1.0);
+}
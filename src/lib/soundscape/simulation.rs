@@ -0,0 +1,254 @@
+//! A fast-forward simulation of the soundscape over a day at the venue, without audio.
+//!
+//! Tuning occurrence rates and simultaneous sounds by waiting in real time is hopeless. Instead, a
+//! snapshot of the soundscape's installations, groups and sources is stepped through a day at many
+//! times real-time, selecting sounds exactly as each tick of the soundscape thread does. The
+//! sounds spawned throughout the day, the number of sounds within each installation and the use of
+//! each source are tallied into a `Report`.
+//!
+//! The simulation is coarser than playback. The scheduler is stepped once per `STEP`, each sound
+//! remains within the installation for which it was spawned and neither visitors, followers,
+//! follow-ups nor movement are simulated. The intensity, activity readings and modulation are
+//! those of the soundscape at the moment the snapshot was taken.
+
+use audio;
+use fxhash::FxHashMap;
+use installation;
+use nannou;
+use std::time;
+use utils::{self, duration_to_secs, Seed};
+use super::{scene, select_source, record_spawn, update_group_modulations};
+use super::{update_installation_areas, update_installation_speakers};
+use super::{update_target_sounds_per_installation, ActivityReadings, Groups, InstallationAreas};
+use super::{Installations, Model, Sources, Tick};
+
+/// The interval at which the scheduler is stepped throughout the simulated day.
+pub const STEP: time::Duration = time::Duration::from_secs(1);
+
+/// The number of bins into which the simulated day is divided, i.e. one per 15 minutes.
+pub const BINS: usize = 96;
+
+/// A snapshot of the soundscape from which a day may be simulated.
+pub struct Simulation {
+    seed: Seed,
+    intensity: f32,
+    playback_duration: time::Duration,
+    installations: Installations,
+    activity_readings: ActivityReadings,
+    groups: Groups,
+    sources: Sources,
+    installation_areas: InstallationAreas,
+    scene: scene::Crossfade,
+}
+
+/// The outcome of a simulated day.
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    /// The number of sounds spawned within each bin of the day.
+    pub spawns: Vec<usize>,
+    /// The mean number of sounds playing within each installation during each bin of the day.
+    pub installation_sounds: FxHashMap<installation::Id, Vec<f32>>,
+    /// The number of sounds spawned from each source throughout the day.
+    pub source_spawns: FxHashMap<audio::source::Id, usize>,
+}
+
+// A sound playing within the simulation.
+struct Sound {
+    source_id: audio::source::Id,
+    installation: installation::Id,
+    ends: time::Instant,
+}
+
+impl Simulation {
+    /// Take a snapshot of the given soundscape.
+    pub fn new(model: &Model) -> Self {
+        let mut installation_speakers = Default::default();
+        let mut installation_areas = Default::default();
+        update_installation_speakers(&model.speakers, &mut installation_speakers);
+        update_installation_areas(
+            &model.speakers,
+            &model.installations,
+            &installation_speakers,
+            &mut installation_areas,
+        );
+        Simulation {
+            seed: model.seed,
+            intensity: model.intensity,
+            playback_duration: model.playback_duration,
+            installations: model.installations.clone(),
+            activity_readings: model.activity_readings.clone(),
+            groups: model.groups.clone(),
+            sources: model.sources.clone(),
+            installation_areas,
+            scene: model.scene.clone(),
+        }
+    }
+
+    /// Step the soundscape through a day beginning at midnight at the venue, cueing each of the
+    /// given scenes at its scheduled time.
+    pub fn run(mut self, scenes: &scene::Scenes) -> Report {
+        let mut report = Report { spawns: vec![0; BINS], ..Default::default() };
+        for &installation in self.installations.keys() {
+            report.installation_sounds.insert(installation, vec![0.0; BINS]);
+        }
+
+        let mut sounds: Vec<Sound> = vec![];
+        let mut groups_last_used = Default::default();
+        let mut sources_last_used = Default::default();
+        let mut sources_last_ended = FxHashMap::default();
+        let mut recent_spawns = Default::default();
+        let mut groups_last_source = Default::default();
+        let mut group_modulations = Default::default();
+        let mut target_sounds_per_installation = FxHashMap::default();
+        let mut available_groups = vec![];
+        let mut available_sources = vec![];
+        let mut rng = nannou::rand::thread_rng();
+
+        // Begin within the scene cued most recently before midnight, if any.
+        if let Some(cue) = scenes.schedule.last() {
+            self.scene.start(cue.scene.clone(), 0.0, &self.groups);
+        }
+
+        let step_secs = duration_to_secs(&STEP);
+        let steps = (utils::DAY_MS / 1_000.0 / step_secs) as usize;
+        let steps_per_bin = steps / BINS;
+        let start = time::Instant::now();
+        let mut previous_secs = (utils::DAY_MS / 1_000.0) as u32 - 1;
+        for step in 0..steps {
+            let elapsed = STEP * step as u32;
+            let tick = Tick {
+                instant: start + elapsed,
+                since_last_tick: STEP,
+                playback_duration: self.playback_duration + elapsed,
+            };
+            let secs_of_day = elapsed.as_secs() as u32;
+            let bin = (step / steps_per_bin).min(BINS - 1);
+
+            // Cue each scene at its scheduled time.
+            if let Some(name) = scenes.due(previous_secs, secs_of_day) {
+                self.scene.start(name.to_string(), scenes.crossfade_secs, &self.groups);
+            }
+            previous_secs = secs_of_day;
+            self.scene.update(step_secs);
+
+            // End the sounds whose playback has finished.
+            sounds.retain(|sound| {
+                if sound.ends > tick.instant {
+                    return true;
+                }
+                sources_last_ended.insert(sound.source_id, sound.ends);
+                false
+            });
+
+            update_target_sounds_per_installation(
+                self.seed,
+                &tick.playback_duration,
+                &self.installations,
+                &self.installation_areas,
+                &self.activity_readings,
+                secs_of_day,
+                &mut target_sounds_per_installation,
+            );
+            update_group_modulations(
+                self.seed,
+                &tick.playback_duration,
+                &self.groups,
+                &mut group_modulations,
+            );
+
+            // Spawn sounds within each installation as the soundscape thread would.
+            for (installation, &num_target_sounds) in target_sounds_per_installation.iter() {
+                if !self.installation_areas.contains_key(installation) {
+                    continue;
+                }
+                let constraints = &self.installations[installation];
+                let range = &constraints.simultaneous_sounds;
+                let max_sounds = range.min.max(range.max);
+                let mut sounds_per_priority = installation::SoundsPerPriority::default();
+                let mut num_active_sounds = 0;
+                for sound in sounds.iter().filter(|s| s.installation == *installation) {
+                    num_active_sounds += 1;
+                    if let Some(source) = self.sources.get(&sound.source_id) {
+                        sounds_per_priority[source.priority.to_index()] += 1;
+                    }
+                }
+
+                for _ in num_active_sounds..max_sounds {
+                    let (group_id, source_index) = match select_source(
+                        installation,
+                        &tick,
+                        &self.sources,
+                        &self.groups,
+                        sounds.iter().map(|s| s.source_id),
+                        &groups_last_used,
+                        &sources_last_used,
+                        &sources_last_ended,
+                        &recent_spawns,
+                        &groups_last_source,
+                        &group_modulations,
+                        &self.scene,
+                        self.intensity,
+                        secs_of_day,
+                        constraints,
+                        num_target_sounds,
+                        &sounds_per_priority,
+                        &mut available_groups,
+                        &mut available_sources,
+                    ) {
+                        Some(selection) => selection,
+                        None => break,
+                    };
+
+                    let source = &available_sources[source_index];
+                    let duration =
+                        audio::source::random_playback_duration(&mut rng, source.playback_duration);
+                    let duration_ms = duration.ms().max(0.0).min(utils::DAY_MS);
+                    let ends = tick.instant + time::Duration::from_millis(duration_ms as u64);
+                    let source_id = source.id;
+                    record_spawn(
+                        group_id,
+                        source_id,
+                        tick.instant,
+                        &self.sources,
+                        &mut groups_last_used,
+                        &mut sources_last_used,
+                        &mut recent_spawns,
+                        &mut groups_last_source,
+                    );
+                    sounds_per_priority[self.sources[&source_id].priority.to_index()] += 1;
+                    sounds.push(Sound { source_id, installation: *installation, ends });
+                    report.spawns[bin] += 1;
+                    *report.source_spawns.entry(source_id).or_insert(0) += 1;
+                }
+            }
+
+            // Tally the sounds playing within each installation.
+            for sound in &sounds {
+                if let Some(counts) = report.installation_sounds.get_mut(&sound.installation) {
+                    counts[bin] += 1.0;
+                }
+            }
+        }
+
+        // Average the tallies over each bin.
+        for counts in report.installation_sounds.values_mut() {
+            for count in counts.iter_mut() {
+                *count /= steps_per_bin as f32;
+            }
+        }
+
+        report
+    }
+}
+
+impl Report {
+    /// The total number of sounds spawned throughout the day.
+    pub fn total_spawns(&self) -> usize {
+        self.spawns.iter().sum()
+    }
+
+    /// The greatest number of sounds spawned within a single bin.
+    pub fn max_spawns(&self) -> usize {
+        self.spawns.iter().cloned().max().unwrap_or(0)
+    }
+}
@@ -0,0 +1,273 @@
+//! Quantisation of the soundscape's spawns to the beats or bars of a tempo.
+//!
+//! The generative layer drifts apart from any musician playing alongside it, as sounds are spawned
+//! whenever the scheduler allows. While enabled, each spawn is instead held until the next beat or
+//! bar of a tempo, within a single tick. The tempo is kept by the soundscape's own clock or, when
+//! built with the `link` feature, shared with other applications on the network via Ableton Link so
+//! that the soundscape's beats and bars line up with theirs.
+
+#[cfg(feature = "link")]
+use rusty_link::{AblLink, SessionState};
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::Arc;
+use std::time;
+use utils::duration_to_secs;
+
+/// The lowest tempo that may be selected via the GUI.
+pub const MIN_BPM: f64 = 20.0;
+
+/// The highest tempo that may be selected via the GUI.
+pub const MAX_BPM: f64 = 300.0;
+
+/// The greatest number of beats per bar that may be selected via the GUI.
+pub const MAX_BEATS_PER_BAR: u32 = 16;
+
+/// Whether or not the server was built with Ableton Link support.
+pub const LINK_SUPPORTED: bool = cfg!(feature = "link");
+
+/// The tempo to which the soundscape's spawns are quantised.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Params {
+    /// Whether or not spawns are quantised to the tempo.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Whether or not the tempo and phase are shared with peers via Ableton Link.
+    ///
+    /// Ignored unless the server was built with the `link` feature.
+    #[serde(default)]
+    pub link: bool,
+    /// The tempo in beats per minute. While linked, changes are proposed to the session.
+    #[serde(default = "default::bpm")]
+    pub bpm: f64,
+    /// The number of beats within each bar.
+    #[serde(default = "default::beats_per_bar")]
+    pub beats_per_bar: u32,
+    /// Whether spawns are held until the next beat or the next bar.
+    #[serde(default)]
+    pub quantum: Quantum,
+}
+
+/// The division of the tempo on which sounds may be spawned.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub enum Quantum {
+    Beat,
+    Bar,
+}
+
+/// The state of the Link session, shared with the GUI.
+#[derive(Debug, Default)]
+pub struct Shared {
+    // The number of peers within the session.
+    peers: AtomicUsize,
+    // The session tempo in thousandths of a beat per minute, or `0` while not linked.
+    milli_bpm: AtomicUsize,
+}
+
+/// A snapshot of the Link session.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LinkStatus {
+    /// The number of other applications within the session.
+    pub peers: usize,
+    /// The tempo of the session in beats per minute.
+    pub bpm: f64,
+}
+
+/// The tempo clock on the soundscape thread.
+pub struct Clock {
+    params: Params,
+    // The instant and beat from which the soundscape's own clock counts.
+    anchor: (time::Instant, f64),
+    // The beat reached upon the previous update while enabled.
+    last_beat: Option<f64>,
+    // The Link session, if joined.
+    #[cfg(feature = "link")]
+    link: Option<AblLink>,
+    shared: Arc<Shared>,
+}
+
+pub mod default {
+    pub fn bpm() -> f64 {
+        120.0
+    }
+
+    pub fn beats_per_bar() -> u32 {
+        4
+    }
+}
+
+impl Params {
+    /// The number of beats between each moment at which sounds may be spawned.
+    pub fn quantum_beats(&self) -> f64 {
+        match self.quantum {
+            Quantum::Beat => 1.0,
+            Quantum::Bar => self.beats_per_bar.max(1) as f64,
+        }
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            enabled: false,
+            link: false,
+            bpm: default::bpm(),
+            beats_per_bar: default::beats_per_bar(),
+            quantum: Default::default(),
+        }
+    }
+}
+
+impl Default for Quantum {
+    fn default() -> Self {
+        Quantum::Beat
+    }
+}
+
+impl Shared {
+    /// The state of the Link session, or `None` while not linked.
+    pub fn link_status(&self) -> Option<LinkStatus> {
+        let milli_bpm = self.milli_bpm.load(atomic::Ordering::Relaxed);
+        if milli_bpm == 0 {
+            return None;
+        }
+        let peers = self.peers.load(atomic::Ordering::Relaxed);
+        let bpm = milli_bpm as f64 / 1_000.0;
+        Some(LinkStatus { peers, bpm })
+    }
+
+    fn store(&self, peers: usize, bpm: f64) {
+        self.peers.store(peers, atomic::Ordering::Relaxed);
+        let milli_bpm = (bpm * 1_000.0).round().max(0.0) as usize;
+        self.milli_bpm.store(milli_bpm, atomic::Ordering::Relaxed);
+    }
+}
+
+impl Clock {
+    /// A disabled clock at the default tempo, publishing the state of its Link session to
+    /// `shared`.
+    pub fn new(shared: Arc<Shared>) -> Self {
+        Clock {
+            params: Default::default(),
+            anchor: (time::Instant::now(), 0.0),
+            last_beat: None,
+            #[cfg(feature = "link")]
+            link: None,
+            shared,
+        }
+    }
+
+    /// Apply the given parameters, joining or leaving the Link session as necessary.
+    ///
+    /// The soundscape's own clock continues from its current beat at the new tempo.
+    pub fn set_params(&mut self, params: Params) {
+        let now = time::Instant::now();
+        self.anchor = (now, self.own_beat(now));
+        if !params.enabled {
+            self.last_beat = None;
+        }
+        #[cfg(feature = "link")]
+        {
+            match (params.enabled && params.link, self.link.is_some()) {
+                (true, false) => {
+                    let link = AblLink::new(params.bpm);
+                    link.enable(true);
+                    self.link = Some(link);
+                },
+                (true, true) if params.bpm != self.params.bpm => {
+                    let link = self.link.as_ref().unwrap();
+                    let mut state = SessionState::new();
+                    link.capture_app_session_state(&mut state);
+                    state.set_tempo(params.bpm, link.clock_micros());
+                    link.commit_app_session_state(&state);
+                },
+                (false, true) => {
+                    self.link = None;
+                    self.shared.store(0, 0.0);
+                },
+                _ => (),
+            }
+        }
+        self.params = params;
+    }
+
+    /// Step the clock to the given instant.
+    ///
+    /// Returns whether or not sounds may be spawned, i.e. `true` while disabled or if a beat or
+    /// bar began since the previous update.
+    pub fn update(&mut self, now: time::Instant) -> bool {
+        if !self.params.enabled {
+            return true;
+        }
+        let beat = self.beat(now);
+        let began = match self.last_beat {
+            Some(last) => crossed(last, beat, self.params.quantum_beats()),
+            None => false,
+        };
+        self.last_beat = Some(beat);
+        began
+    }
+
+    // The current beat, taken from the Link session if joined.
+    #[cfg(feature = "link")]
+    fn beat(&self, now: time::Instant) -> f64 {
+        let link = match self.link {
+            Some(ref link) => link,
+            None => return self.own_beat(now),
+        };
+        let mut state = SessionState::new();
+        link.capture_app_session_state(&mut state);
+        let peers = link.num_peers() as usize;
+        self.shared.store(peers, state.tempo());
+        let bar = self.params.beats_per_bar.max(1) as f64;
+        state.beat_at_time(link.clock_micros(), bar)
+    }
+
+    // The current beat of the soundscape's own clock.
+    #[cfg(not(feature = "link"))]
+    fn beat(&self, now: time::Instant) -> f64 {
+        self.own_beat(now)
+    }
+
+    // The beat of the soundscape's own clock at the given instant.
+    fn own_beat(&self, now: time::Instant) -> f64 {
+        let (instant, beat) = self.anchor;
+        let secs = match now > instant {
+            true => duration_to_secs(&(now - instant)),
+            false => 0.0,
+        };
+        beat + secs * self.params.bpm / 60.0
+    }
+}
+
+// Whether or not a multiple of `step` beats lies after `last` and up to and including `beat`.
+//
+// Beats that move backwards, e.g. when a Link peer resets the phase, are treated as a new start.
+fn crossed(last: f64, beat: f64, step: f64) -> bool {
+    (beat / step).floor() != (last / step).floor()
+}
+
+#[test]
+fn test_clock() {
+    let mut clock = Clock::new(Default::default());
+    let start = clock.anchor.0;
+    let at = |ms| start + time::Duration::from_millis(ms);
+    assert!(clock.update(at(100)));
+
+    // At 120 BPM, spawns may only occur at the start of each beat, twice per second.
+    let params = Params { enabled: true, ..Default::default() };
+    clock.set_params(params);
+    let start = clock.anchor.0;
+    let at = |ms| start + time::Duration::from_millis(ms);
+    assert!(!clock.update(at(100)));
+    assert!(!clock.update(at(400)));
+    assert!(clock.update(at(600)));
+    assert!(!clock.update(at(900)));
+
+    // Quantised to bars of 4 beats, the next bar begins 2 seconds later.
+    clock.set_params(Params { quantum: Quantum::Bar, ..params });
+    let start = clock.anchor.0;
+    let at = |ms| start + time::Duration::from_millis(ms);
+    assert!(!clock.update(at(1_000)));
+    assert!(!clock.update(at(1_900)));
+    assert!(clock.update(at(2_100)));
+}
@@ -0,0 +1,109 @@
+//! The time zone of the venue against which all schedules are evaluated.
+//!
+//! Projects tour between venues, so daily schedules such as blackouts and time of day gains are
+//! authored in the venue's local time rather than that of the machine running the server. The zone
+//! is stored as a fixed offset from UTC and so must be updated by hand when the venue enters or
+//! leaves daylight saving time.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The furthest offset behind UTC in use, e.g. Baker Island.
+pub const MIN_OFFSET_MINUTES: i16 = -12 * 60;
+
+/// The furthest offset ahead of UTC in use, e.g. Kiribati.
+pub const MAX_OFFSET_MINUTES: i16 = 14 * 60;
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// A fixed offset from UTC.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub struct TimeZone {
+    /// The number of minutes by which the venue's time is ahead of UTC.
+    #[serde(default)]
+    pub offset_minutes: i16,
+}
+
+impl TimeZone {
+    /// Coordinated Universal Time, used by projects that do not specify a time zone.
+    pub const UTC: Self = TimeZone { offset_minutes: 0 };
+
+    /// The number of seconds since midnight in this time zone at the given unix time.
+    pub fn secs_of_day(&self, unix_secs: u64) -> u32 {
+        let secs = unix_secs as i64 + self.offset_minutes as i64 * 60;
+        (((secs % SECS_PER_DAY) + SECS_PER_DAY) % SECS_PER_DAY) as u32
+    }
+
+    /// The current number of seconds since midnight in this time zone.
+    pub fn now_secs_of_day(&self) -> u32 {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.secs_of_day(secs)
+    }
+}
+
+impl fmt::Display for TimeZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.offset_minutes == 0 {
+            return write!(f, "UTC");
+        }
+        let sign = if self.offset_minutes < 0 { '-' } else { '+' };
+        let minutes = self.offset_minutes.abs();
+        write!(f, "UTC{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+    }
+}
+
+impl FromStr for TimeZone {
+    type Err = String;
+    /// Parses offsets such as `UTC`, `UTC+10`, `+10:00`, `-03:30` or `UTC+5:45`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid time zone `{}`, expected e.g. `UTC+10:00`", s);
+        let trimmed = s.trim();
+        let offset = match trimmed.get(..3) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("UTC") => trimmed[3..].trim(),
+            _ => trimmed,
+        };
+        if offset.is_empty() {
+            return Ok(TimeZone::UTC);
+        }
+        let (sign, offset) = match offset.chars().next() {
+            Some('+') => (1, &offset[1..]),
+            Some('-') => (-1, &offset[1..]),
+            _ => return Err(err()),
+        };
+        let mut parts = offset.splitn(2, ':');
+        let hours: i16 = parts
+            .next()
+            .and_then(|h| h.parse().ok())
+            .filter(|h| *h <= 14)
+            .ok_or_else(err)?;
+        let minutes: i16 = match parts.next() {
+            None => 0,
+            Some(m) => m.parse().ok().filter(|m| *m < 60).ok_or_else(err)?,
+        };
+        let offset_minutes = sign * (hours * 60 + minutes);
+        if offset_minutes < MIN_OFFSET_MINUTES || offset_minutes > MAX_OFFSET_MINUTES {
+            return Err(err());
+        }
+        Ok(TimeZone { offset_minutes })
+    }
+}
+
+#[test]
+fn test_time_zone() {
+    let melbourne: TimeZone = "UTC+10".parse().unwrap();
+    let newfoundland: TimeZone = "-3:30".parse().unwrap();
+    assert_eq!(melbourne.offset_minutes, 600);
+    assert_eq!(newfoundland.to_string(), "UTC-03:30");
+    assert_eq!("utc".parse::<TimeZone>(), Ok(TimeZone::UTC));
+    assert!("UTC+15".parse::<TimeZone>().is_err());
+    assert!("10:00".parse::<TimeZone>().is_err());
+    // 23:00 UTC is 09:00 the following day in Melbourne and 19:30 in Newfoundland.
+    let secs = 23 * 60 * 60;
+    assert_eq!(melbourne.secs_of_day(secs), 9 * 60 * 60);
+    assert_eq!(newfoundland.secs_of_day(secs), 19 * 60 * 60 + 30 * 60);
+    assert_eq!(newfoundland.secs_of_day(0), 20 * 60 * 60 + 30 * 60);
+}